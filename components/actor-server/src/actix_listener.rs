@@ -0,0 +1,101 @@
+//! Actor endpoints served with [actix-web](https://docs.rs/actix-web).
+use std::sync::Arc;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderName, HeaderValue},
+        StatusCode,
+    },
+    middleware::Next,
+    web, HttpRequest, HttpResponse, Scope,
+};
+use keri_core::{
+    actor::keri_actor::{ActorRequest, ActorResponse, KeriActor, ResponseBody},
+    transport::correlation::{CorrelationId, CORRELATION_ID_HEADER},
+};
+
+use crate::router::{error_response, route, AdminToken};
+
+/// Registers route answering all requests with actor and admin token taken
+/// from application data, `web::Data<Arc<dyn KeriActor>>` and
+/// `web::Data<AdminToken>`.
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/{path:.*}", web::to(serve));
+}
+
+/// Returns service with all endpoints of `actor`, that can be mounted in
+/// existing actix application, for example:
+/// `App::new().service(web::scope("/witness").service(actor_scope(actor)))`.
+/// Admin endpoints are disabled, unless token is provided with
+/// `.app_data(web::Data::new(AdminToken(Some(token))))` on returned scope.
+pub fn actor_scope(actor: Arc<dyn KeriActor>) -> Scope {
+    web::scope("")
+        .app_data(web::Data::new(actor))
+        .app_data(web::Data::new(AdminToken(None)))
+        .configure(configure_routes)
+}
+
+async fn serve(
+    req: HttpRequest,
+    body: web::Bytes,
+    actor: web::Data<Arc<dyn KeriActor>>,
+    admin_token: web::Data<AdminToken>,
+) -> HttpResponse {
+    let request = ActorRequest {
+        method: req.method().to_string(),
+        path: format!("/{}", req.match_info().query("path")),
+        headers: req
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: body.to_vec(),
+    };
+    let resp = route(&**actor, &admin_token, &request)
+        .await
+        .unwrap_or_else(error_response);
+    into_http_response(resp)
+}
+
+fn into_http_response(resp: ActorResponse) -> HttpResponse {
+    let mut builder = HttpResponse::build(
+        StatusCode::from_u16(resp.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    );
+    for header in resp.headers {
+        builder.insert_header(header);
+    }
+    match resp.body {
+        ResponseBody::Full(body) => builder.body(body),
+        ResponseBody::Chunked(chunks) => builder.streaming(futures::stream::iter(
+            chunks.map(|chunk| chunk.map(web::Bytes::from)),
+        )),
+    }
+}
+
+/// Middleware that processes request within scope of its correlation ID,
+/// taken from `X-Correlation-Id` header or generated, logs it and returns it
+/// in response header. Outgoing requests made during processing carry the
+/// same ID. When actor scope is mounted in other application, it can be
+/// added with `.wrap(actix_web::middleware::from_fn(correlate))`.
+pub async fn correlate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let id = CorrelationId::from_header(
+        req.headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    );
+    log::info!("[{}] {} {}", id, req.method(), req.path());
+    let mut res = id.instrument(next.call(req)).await?;
+    log::info!("[{}] Responded with {}", id, res.status());
+    if let (Ok(name), Ok(value)) = (
+        HeaderName::try_from(CORRELATION_ID_HEADER),
+        HeaderValue::from_str(id.as_str()),
+    ) {
+        res.headers_mut().insert(name, value);
+    }
+    Ok(res)
+}
@@ -0,0 +1,62 @@
+//! Actor endpoints served with [axum](https://docs.rs/axum), for
+//! applications built on tower/hyper stack.
+use std::sync::Arc;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use keri_core::actor::keri_actor::{ActorRequest, ActorResponse, KeriActor, ResponseBody};
+
+use crate::router::{handle, AdminToken};
+
+#[derive(Clone)]
+struct ActorState {
+    actor: Arc<dyn KeriActor>,
+    admin_token: Arc<AdminToken>,
+}
+
+/// Returns router with all endpoints of `actor`. It can be merged or nested
+/// into existing axum application, and wrapped with tower middlewares.
+pub fn actor_router(actor: Arc<dyn KeriActor>, admin_token: AdminToken) -> Router {
+    Router::new().fallback(serve).with_state(ActorState {
+        actor,
+        admin_token: Arc::new(admin_token),
+    })
+}
+
+async fn serve(
+    State(state): State<ActorState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let req = ActorRequest {
+        method: method.to_string(),
+        path: uri.path().to_string(),
+        headers: headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: body.to_vec(),
+    };
+    into_response(handle(&*state.actor, &state.admin_token, req).await)
+}
+
+fn into_response(resp: ActorResponse) -> Response {
+    let mut builder = axum::http::Response::builder().status(resp.status);
+    for (name, value) in &resp.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let body = match resp.body {
+        ResponseBody::Full(body) => Body::from(body),
+        ResponseBody::Chunked(chunks) => Body::from_stream(futures::stream::iter(chunks)),
+    };
+    builder
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
@@ -0,0 +1,141 @@
+//! Loading actor configuration from file, environment variables and command
+//! line arguments.
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use figment::{
+    providers::{Env, Format, Serialized, Yaml},
+    Figment,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Suffix of environment variable with whole configuration in JSON or YAML
+/// format, for example `WITNESS_CONFIG`.
+pub const ENV_CONFIG_SUFFIX: &str = "CONFIG";
+
+/// Loads configuration. Values from `config_file` are overwritten by
+/// configuration passed in single `{env_prefix}CONFIG` variable, then by
+/// variables prefixed with `env_prefix` and finally by `args`.
+pub fn load_config<T: DeserializeOwned>(
+    config_file: &Path,
+    env_prefix: &str,
+    args: impl Serialize,
+) -> Result<T> {
+    let figment = Figment::new().merge(Yaml::file(config_file));
+    let figment = match std::env::var(format!("{}{}", env_prefix, ENV_CONFIG_SUFFIX)) {
+        Ok(config) => figment.merge(Yaml::string(&config)),
+        Err(_) => figment,
+    };
+    figment
+        .merge(Env::prefixed(env_prefix).ignore(&[ENV_CONFIG_SUFFIX]))
+        .merge(Serialized::defaults(args))
+        .extract()
+        .context("Failed to load config")
+}
+
+/// Returns seed provided directly, by file or by environment variable. At
+/// most one of them can be set.
+pub fn resolve_seed(
+    seed: Option<&str>,
+    seed_file: Option<&Path>,
+    seed_env: Option<&str>,
+) -> Result<Option<String>> {
+    match (seed, seed_file, seed_env) {
+        (seed, None, None) => Ok(seed.map(str::to_string)),
+        (None, Some(path), None) => Ok(Some(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read seed file {:?}", path))?
+                .trim()
+                .to_string(),
+        )),
+        (None, None, Some(var)) => Ok(Some(
+            std::env::var(var)
+                .with_context(|| format!("Missing seed environment variable {}", var))?
+                .trim()
+                .to_string(),
+        )),
+        _ => bail!("Only one of `seed`, `seed_file` and `seed_env` can be set"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use figment::Jail;
+    use serde::{Deserialize, Serialize};
+
+    use super::{load_config, resolve_seed};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        db_path: String,
+        http_port: Option<u16>,
+        seed: Option<String>,
+    }
+
+    #[derive(Serialize)]
+    struct Args {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seed: Option<String>,
+    }
+
+    #[test]
+    fn test_load_config_precedence() {
+        Jail::expect_with(|jail| {
+            jail.create_file("config.yml", "db_path: file\nhttp_port: 3232\nseed: file")?;
+            let load = |seed: Option<&str>| {
+                let args = Args {
+                    seed: seed.map(str::to_string),
+                };
+                load_config::<Config>(Path::new("config.yml"), "TEST_", args)
+                    .map_err(|e| e.to_string())
+            };
+            assert_eq!(
+                load(None)?,
+                Config {
+                    db_path: "file".into(),
+                    http_port: Some(3232),
+                    seed: Some("file".into()),
+                }
+            );
+
+            jail.set_env("TEST_CONFIG", "{db_path: config_var, http_port: 3333}");
+            jail.set_env("TEST_HTTP_PORT", "4444");
+            assert_eq!(
+                load(Some("args"))?,
+                Config {
+                    db_path: "config_var".into(),
+                    http_port: Some(4444),
+                    seed: Some("args".into()),
+                }
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_resolve_seed() {
+        Jail::expect_with(|jail| {
+            jail.create_file("seed", "file_seed\n")?;
+            jail.set_env("TEST_SEED", " env_seed ");
+
+            let resolve = |seed, file: Option<&str>, env| {
+                resolve_seed(seed, file.map(Path::new), env).map_err(|e| e.to_string())
+            };
+            assert_eq!(resolve(None, None, None)?, None);
+            assert_eq!(resolve(Some("seed"), None, None)?, Some("seed".into()));
+            assert_eq!(resolve(None, Some("seed"), None)?, Some("file_seed".into()));
+            assert_eq!(
+                resolve(None, None, Some("TEST_SEED"))?,
+                Some("env_seed".into())
+            );
+
+            assert!(resolve(Some("seed"), Some("seed"), None).is_err());
+            assert!(resolve(None, Some("seed"), Some("TEST_SEED")).is_err());
+            assert!(resolve(None, Some("missing"), None).is_err());
+            assert!(resolve(None, None, Some("TEST_MISSING_SEED")).is_err());
+            Ok(())
+        });
+    }
+}
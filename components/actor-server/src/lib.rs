@@ -0,0 +1,19 @@
+//! Serving KERI actors, like witness and watcher, over HTTP and framed TCP.
+//! Requests are answered by one router over [`KeriActor`], so all listeners
+//! process them the same way. Actors add their own endpoints with
+//! [`KeriActor::handle_request`].
+//!
+//! [`KeriActor`]: keri_core::actor::keri_actor::KeriActor
+//! [`KeriActor::handle_request`]: keri_core::actor::keri_actor::KeriActor::handle_request
+pub mod actix_listener;
+#[cfg(feature = "axum")]
+pub mod axum_listener;
+pub mod config;
+mod router;
+pub mod service;
+#[cfg(feature = "tcp")]
+pub mod tcp_listener;
+
+pub use crate::router::{
+    error_response, handle, parse_id, parse_json, parse_role, route, AdminToken,
+};
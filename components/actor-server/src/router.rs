@@ -0,0 +1,192 @@
+use keri_core::{
+    actor::{
+        error::ActorError,
+        keri_actor::{ActorRequest, ActorResponse, KeriActor},
+    },
+    oobi::{error::OobiError, EndRole, LocationScheme, Oobi, Role},
+    prefix::IdentifierPrefix,
+    transport::correlation::{CorrelationId, CORRELATION_ID_HEADER},
+};
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+
+/// Token expected in `Authorization: Bearer` header of admin requests, which
+/// paths start with `/admin`. Admin endpoints are disabled if it's not set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdminToken(pub Option<String>);
+
+impl AdminToken {
+    /// Checks token of request. Tokens are compared in constant time, so
+    /// response time doesn't reveal how much of token was guessed.
+    pub fn authorize(&self, req: &ActorRequest) -> Result<(), ActorError> {
+        let provided = req
+            .header("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match (&self.0, provided) {
+            (Some(expected), Some(provided))
+                if bool::from(expected.as_bytes().ct_eq(provided.as_bytes())) =>
+            {
+                Ok(())
+            }
+            _ => Err(ActorError::Unauthorized),
+        }
+    }
+}
+
+/// Answers request with `actor`. Endpoints specific to actor (see
+/// [`KeriActor::handle_request`]) are tried first, then the ones common to
+/// all actors:
+/// - `GET /introduce` - location scheme of actor,
+/// - `GET /oobi/{eid}` - signed location schemes of `eid`,
+/// - `GET /oobi/{cid}/{role}/{eid}` - end role of `eid` for `cid`,
+/// - `POST /process` - key events, receipts and other notices,
+/// - `POST /query` - KEL and mailbox queries,
+/// - `POST /register` - signed replies,
+/// - `POST /forward` - exchange messages,
+/// - `POST /resolve` - OOBI to resolve, as JSON.
+///
+/// Requests to admin endpoints are refused unless they carry `admin_token`.
+pub async fn route(
+    actor: &dyn KeriActor,
+    admin_token: &AdminToken,
+    req: &ActorRequest,
+) -> Result<ActorResponse, ActorError> {
+    let segments = req.segments();
+    if segments.first() == Some(&"admin") {
+        admin_token.authorize(req)?;
+    }
+    if let Some(resp) = actor.handle_request(req).await {
+        return resp;
+    }
+
+    let body = req.body.as_slice();
+    match (req.method.as_str(), segments.as_slice()) {
+        ("GET", ["introduce"]) => ActorResponse::json(&actor.oobi()),
+        ("GET", ["oobi", eid]) => Ok(ActorResponse::ok(actor.location_oobis(&parse_id(eid)?)?)),
+        ("GET", ["oobi", cid, role, eid]) => Ok(ActorResponse::ok(actor.role_oobis(
+            &parse_id(cid)?,
+            parse_role(role)?,
+            &parse_id(eid)?,
+        )?)),
+        ("POST", ["process"]) => {
+            actor.process_notices(body)?;
+            Ok(ActorResponse::ok(vec![]))
+        }
+        ("POST", ["query"]) => {
+            let resp = actor
+                .process_queries(body)
+                .await?
+                .iter()
+                .map(|msg| msg.to_string())
+                .collect::<Vec<_>>()
+                .join("");
+            Ok(ActorResponse::ok(resp))
+        }
+        ("POST", ["register"]) => {
+            actor.process_replies(body)?;
+            Ok(ActorResponse::ok(vec![]))
+        }
+        ("POST", ["forward"]) => {
+            actor.process_exchanges(body)?;
+            Ok(ActorResponse::ok(vec![]))
+        }
+        ("POST", ["resolve"]) => {
+            actor.resolve_oobi(parse_oobi(body)?).await?;
+            Ok(ActorResponse::ok(vec![]))
+        }
+        _ => Ok(ActorResponse::new(404, format!("Not found: {}", req.path))),
+    }
+}
+
+/// Processes request within scope of its correlation ID, taken from
+/// `X-Correlation-Id` header or generated, and turns errors into responses
+/// with HTTP status codes. Correlation ID is returned in response header.
+pub async fn handle(
+    actor: &dyn KeriActor,
+    admin_token: &AdminToken,
+    req: ActorRequest,
+) -> ActorResponse {
+    let id = CorrelationId::from_header(req.header(CORRELATION_ID_HEADER));
+    log::info!("[{}] {} {}", id, req.method, req.path);
+    let resp = id
+        .instrument(route(actor, admin_token, &req))
+        .await
+        .unwrap_or_else(error_response);
+    log::info!("[{}] Responded with {}", id, resp.status);
+    resp.with_header(CORRELATION_ID_HEADER, id.as_str())
+}
+
+/// Response with error serialized to JSON and its HTTP status code.
+pub fn error_response(err: ActorError) -> ActorResponse {
+    let status = err.http_status_code().as_u16();
+    let retry_after = err.retry_after();
+    let resp = ActorResponse::new(status, serde_json::to_vec(&err).unwrap_or_default())
+        .with_header("Content-Type", "application/json");
+    match retry_after {
+        Some(retry_after) => resp.with_header("Retry-After", retry_after.as_secs()),
+        None => resp,
+    }
+}
+
+pub fn parse_id(segment: &str) -> Result<IdentifierPrefix, ActorError> {
+    segment
+        .parse()
+        .map_err(|_| ActorError::GeneralError(format!("Invalid identifier {}", segment)))
+}
+
+pub fn parse_role(segment: &str) -> Result<Role, ActorError> {
+    segment
+        .parse()
+        .map_err(|_| ActorError::GeneralError(format!("Invalid role {}", segment)))
+}
+
+pub fn parse_json<T: for<'a> Deserialize<'a>>(body: &[u8]) -> Result<T, ActorError> {
+    serde_json::from_slice(body).map_err(|e| ActorError::GeneralError(e.to_string()))
+}
+
+/// Parses OOBI (end role or location scheme) from JSON request body.
+fn parse_oobi(body: &[u8]) -> Result<Oobi, ActorError> {
+    #[derive(Debug, Deserialize)]
+    #[serde(untagged)]
+    enum RequestData {
+        EndRole(EndRole),
+        LocationScheme(LocationScheme),
+    }
+
+    let oobi = match serde_json::from_slice(body)
+        .map_err(|_| OobiError::Parse(String::from_utf8_lossy(body).to_string()))?
+    {
+        RequestData::EndRole(end_role) => Oobi::EndRole(end_role),
+        RequestData::LocationScheme(loc_scheme) => Oobi::Location(loc_scheme),
+    };
+    Ok(oobi)
+}
+
+#[cfg(test)]
+mod tests {
+    use keri_core::actor::{error::ActorError, keri_actor::ActorRequest};
+
+    use super::AdminToken;
+
+    #[test]
+    fn test_authorize() {
+        let request = |token: &str| {
+            ActorRequest::new("GET", "/admin/identifiers", vec![])
+                .with_header("Authorization", format!("Bearer {}", token))
+        };
+        let token = AdminToken(Some("secret".to_string()));
+        assert!(token.authorize(&request("secret")).is_ok());
+        for wrong in ["", "secreT", "secret2", "secre"] {
+            assert!(matches!(
+                token.authorize(&request(wrong)),
+                Err(ActorError::Unauthorized)
+            ));
+        }
+        assert!(matches!(
+            token.authorize(&ActorRequest::new("GET", "/admin/identifiers", vec![])),
+            Err(ActorError::Unauthorized)
+        ));
+        // Admin endpoints are disabled without token.
+        assert!(AdminToken(None).authorize(&request("")).is_err());
+    }
+}
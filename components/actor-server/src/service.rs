@@ -0,0 +1,129 @@
+//! Options for running actor as a system service: pid file, logging to
+//! journald or rotated file and readiness notification.
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use flexi_logger::{Cleanup, Criterion, FileSpec, Logger, LoggerHandle, Naming};
+pub use sd_notify::NotifyState;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    #[default]
+    Stderr,
+    Journald,
+    File,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogConfig {
+    #[serde(default)]
+    pub log_target: LogTarget,
+
+    /// Log level or filter in `RUST_LOG` format. `RUST_LOG` environment
+    /// variable takes precedence.
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+
+    /// Path of log file. Required if `log_target` is `file`.
+    pub log_file: Option<PathBuf>,
+
+    /// Size in bytes after which log file is rotated.
+    #[serde(default = "default_log_max_size")]
+    pub log_max_size: u64,
+
+    /// Number of rotated log files to keep.
+    #[serde(default = "default_log_max_files")]
+    pub log_max_files: usize,
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_log_max_files() -> usize {
+    5
+}
+
+/// Sets up global logger. Records are filtered with `log_level` (or
+/// `RUST_LOG`) for all targets. Returned handle needs to be kept alive as
+/// long as logs are written.
+pub fn init_logging(config: &LogConfig) -> Result<LoggerHandle> {
+    let logger = Logger::try_with_env_or_str(&config.log_level)?;
+    let logger = match config.log_target {
+        LogTarget::Stderr => logger.log_to_stderr(),
+        LogTarget::File => {
+            let path = config
+                .log_file
+                .as_ref()
+                .context("`log_file` is required when logging to file")?;
+            logger.log_to_file(FileSpec::try_from(path)?).rotate(
+                Criterion::Size(config.log_max_size),
+                Naming::Numbers,
+                Cleanup::KeepLogFiles(config.log_max_files),
+            )
+        }
+        #[cfg(feature = "journald")]
+        LogTarget::Journald => logger.log_to_writer(Box::new(journald::JournalWriter::new()?)),
+        #[cfg(not(feature = "journald"))]
+        LogTarget::Journald => anyhow::bail!("Built without `journald` feature"),
+    };
+    Ok(logger.start()?)
+}
+
+#[cfg(feature = "journald")]
+mod journald {
+    use flexi_logger::{writers::LogWriter, DeferredNow};
+    use log::{Log, Record};
+    use systemd_journal_logger::JournalLog;
+
+    /// Sends records, already filtered by flexi_logger, to journald.
+    pub struct JournalWriter(JournalLog);
+
+    impl JournalWriter {
+        pub fn new() -> std::io::Result<Self> {
+            Ok(Self(JournalLog::new()?))
+        }
+    }
+
+    impl LogWriter for JournalWriter {
+        fn write(&self, _now: &mut DeferredNow, record: &Record) -> std::io::Result<()> {
+            self.0.log(record);
+            Ok(())
+        }
+
+        fn flush(&self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+/// File with process id. It's removed when dropped.
+pub struct PidFile(PathBuf);
+
+impl PidFile {
+    pub fn create(path: PathBuf) -> Result<Self> {
+        fs::write(&path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("Failed to write pid file {:?}", path))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Notifies systemd about service state. Does nothing if service wasn't
+/// started by systemd.
+pub fn notify(state: NotifyState) {
+    if let Err(e) = sd_notify::notify(false, &[state]) {
+        log::warn!("Failed to notify systemd: {}", e);
+    }
+}
@@ -0,0 +1,55 @@
+//! Actor endpoints served over framed TCP (see
+//! [`keri_core::transport::tcp`]), for deployments that avoid HTTP. Requests
+//! use the same paths and bodies as HTTP endpoints.
+use std::{sync::Arc, time::Duration};
+
+use keri_core::{
+    actor::keri_actor::{ActorRequest, KeriActor},
+    transport::tcp::{serve, TcpRequest, TcpResponse},
+};
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+use crate::router::{handle, AdminToken};
+
+/// Time after which idle connection is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Serves all endpoints of `actor` over framed TCP on given address.
+pub async fn listen_tcp(
+    actor: Arc<dyn KeriActor>,
+    admin_token: AdminToken,
+    addr: impl ToSocketAddrs,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    let admin_token = Arc::new(admin_token);
+    serve(listener, IDLE_TIMEOUT, move |req| {
+        let actor = actor.clone();
+        let admin_token = admin_token.clone();
+        async move { handle_request(&*actor, &admin_token, req).await }
+    })
+    .await
+}
+
+/// Answers request like HTTP listeners do, see [`handle`].
+pub async fn handle_request(
+    actor: &dyn KeriActor,
+    admin_token: &AdminToken,
+    req: TcpRequest,
+) -> TcpResponse {
+    let req = ActorRequest {
+        method: req.method,
+        path: req.path,
+        headers: req.headers,
+        body: req.body,
+    };
+    let resp = handle(actor, admin_token, req).await;
+    let (status, headers) = (resp.status, resp.headers.clone());
+    match resp.into_bytes() {
+        Ok(body) => TcpResponse {
+            status,
+            headers,
+            body,
+        },
+        Err(e) => TcpResponse::new(500, e.to_string()),
+    }
+}
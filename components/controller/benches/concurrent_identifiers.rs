@@ -0,0 +1,92 @@
+//! Measures throughput of an agent managing many identifiers. The same
+//! number of interaction events is anchored first by identifiers one after
+//! another, then by identifiers split between threads. Events of different
+//! identifiers don't wait for each other's locks, but writes to single redb
+//! database still do, so both runs are repeated with events split into
+//! shards. Run with `cargo bench -p keri-controller`.
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use keri_controller::{
+    config::ControllerConfig, controller::Controller, identifier::Identifier, BasicPrefix,
+    CryptoBox, KeyManager, SelfSigningPrefix,
+};
+use tempfile::Builder;
+
+const IDENTIFIERS: usize = 100;
+const EVENTS_PER_IDENTIFIER: usize = 10;
+const THREADS: usize = 8;
+
+fn incept(controller: &Controller) -> (Identifier, CryptoBox) {
+    let km = CryptoBox::new().unwrap();
+    let icp = async_std::task::block_on(controller.incept(
+        vec![BasicPrefix::Ed25519(km.public_key())],
+        vec![BasicPrefix::Ed25519(km.next_public_key())],
+        vec![],
+        0,
+    ))
+    .unwrap();
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp.as_bytes()).unwrap());
+    let identifier = controller
+        .finalize_incept(icp.as_bytes(), &signature)
+        .unwrap();
+    (identifier, km)
+}
+
+fn anchor_all(identifiers: &mut [(Identifier, CryptoBox)]) {
+    for (identifier, km) in identifiers {
+        for _ in 0..EVENTS_PER_IDENTIFIER {
+            let ixn = identifier.anchor(&[]).unwrap();
+            let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(ixn.as_bytes()).unwrap());
+            async_std::task::block_on(identifier.finalize_anchor(ixn.as_bytes(), signature))
+                .unwrap();
+        }
+    }
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let events = IDENTIFIERS * EVENTS_PER_IDENTIFIER;
+    println!(
+        "{}: {} events in {:?} ({:.0} events/s)",
+        name,
+        events,
+        elapsed,
+        events as f64 / elapsed.as_secs_f64()
+    );
+}
+
+/// Anchors events sequentially and from threads, with events kept in
+/// `event_shards` databases.
+fn run(event_shards: Option<usize>) {
+    let root = Builder::new().prefix("bench-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        event_shards,
+        ..Default::default()
+    })
+    .unwrap();
+    let mut identifiers: Vec<_> = (0..IDENTIFIERS).map(|_| incept(&controller)).collect();
+    let shards = event_shards.unwrap_or(1);
+
+    let start = Instant::now();
+    anchor_all(&mut identifiers);
+    report(&format!("{} shards, sequential", shards), start.elapsed());
+
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for chunk in identifiers.chunks_mut(IDENTIFIERS.div_ceil(THREADS)) {
+            scope.spawn(|| anchor_all(chunk));
+        }
+    });
+    report(
+        &format!("{} shards, {} threads", shards, THREADS),
+        start.elapsed(),
+    );
+}
+
+fn main() {
+    run(None);
+    run(Some(THREADS));
+}
@@ -0,0 +1,182 @@
+//! Setup shared by examples. Examples use witnesses and watcher started
+//! with `docker-compose.yml` from repository root. Their URLs can be
+//! changed with `KERI_WITNESS_URLS` (comma separated) and `KERI_WATCHER_URL`
+//! environment variables.
+#![allow(dead_code)]
+
+use std::{env, sync::Arc, time::Duration};
+
+use keri_controller::{
+    config::ControllerConfig, controller::Controller, error::ControllerError,
+    identifier::Identifier, mailbox_updating::ActionRequired, BasicPrefix, CryptoBox, EndRole,
+    IdentifierPrefix, KeyManager, LocationScheme, Oobi, SelfSigningPrefix,
+};
+use keri_core::oobi::Role;
+use tempfile::TempDir;
+use url::Url;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const DEFAULT_WITNESS_URLS: &str =
+    "http://localhost:3232/,http://localhost:3233/,http://localhost:3234/";
+const DEFAULT_WATCHER_URL: &str = "http://localhost:3236/";
+
+/// How many times mailbox or watcher is asked before giving up, because
+/// actors process messages independently.
+const ATTEMPTS: usize = 10;
+
+pub fn witness_urls() -> Result<Vec<Url>> {
+    let urls = env::var("KERI_WITNESS_URLS").unwrap_or_else(|_| DEFAULT_WITNESS_URLS.into());
+    Ok(urls
+        .split(',')
+        .map(|url| Url::parse(url.trim()))
+        .collect::<std::result::Result<_, _>>()?)
+}
+
+pub fn watcher_url() -> Result<Url> {
+    let url = env::var("KERI_WATCHER_URL").unwrap_or_else(|_| DEFAULT_WATCHER_URL.into());
+    Ok(Url::parse(&url)?)
+}
+
+pub fn basic_prefixes(locations: &[LocationScheme]) -> Vec<BasicPrefix> {
+    locations
+        .iter()
+        .filter_map(|loc| match &loc.eid {
+            IdentifierPrefix::Basic(bp) => Some(bp.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Number of witness receipts required: majority of witnesses.
+pub fn witness_threshold(witnesses: usize) -> u64 {
+    (witnesses / 2 + 1) as u64
+}
+
+pub fn sign(
+    keys: &CryptoBox,
+    data: &[u8],
+) -> std::result::Result<SelfSigningPrefix, ControllerError> {
+    Ok(SelfSigningPrefix::Ed25519Sha512(keys.sign(data)?))
+}
+
+/// Identifier together with its keys and controller, which database is
+/// removed when party is dropped.
+pub struct Party {
+    pub controller: Arc<Controller>,
+    pub identifier: Identifier,
+    pub keys: CryptoBox,
+    pub witnesses: Vec<LocationScheme>,
+    _db: TempDir,
+}
+
+impl Party {
+    /// Incepts identifier witnessed by all witnesses from
+    /// [`witness_urls`], and waits for their receipts.
+    pub async fn incept(name: &str) -> Result<Self> {
+        let db = tempfile::Builder::new().prefix(name).tempdir()?;
+        let controller = Arc::new(
+            Controller::init(ControllerConfig {
+                db_path: db.path().to_owned(),
+                ..Default::default()
+            })
+            .await?,
+        );
+
+        // Witnesses introduce themselves, so their identifiers don't need
+        // to be known in advance.
+        let mut witnesses = vec![];
+        for url in witness_urls()? {
+            witnesses.push(controller.introduce(&url).await?);
+        }
+
+        let keys = CryptoBox::new()?;
+        let pk = BasicPrefix::Ed25519(keys.public_key());
+        let npk = BasicPrefix::Ed25519(keys.next_public_key());
+        let icp_event = controller
+            .incept(
+                vec![pk],
+                vec![npk],
+                witnesses.clone(),
+                witness_threshold(witnesses.len()),
+            )
+            .await?;
+        let signature = sign(&keys, icp_event.as_bytes())?;
+        let identifier = controller.finalize_incept(icp_event.as_bytes(), &signature)?;
+
+        let mut party = Party {
+            controller,
+            identifier,
+            keys,
+            witnesses,
+            _db: db,
+        };
+        party.identifier.notify_witnesses().await?;
+        let id = party.identifier.id().clone();
+        party.wait_for_sn(&id, 0).await?;
+        println!("{}: incepted {}", name, id);
+        Ok(party)
+    }
+
+    pub fn id(&self) -> &IdentifierPrefix {
+        self.identifier.id()
+    }
+
+    pub fn witness_ids(&self) -> Vec<BasicPrefix> {
+        basic_prefixes(&self.witnesses)
+    }
+
+    /// Queries mailbox of `about`, which is this identifier or group it
+    /// participates in.
+    pub async fn query_mailbox(&mut self, about: &IdentifierPrefix) -> Result<Vec<ActionRequired>> {
+        let witnesses = self.witness_ids();
+        Ok(self
+            .identifier
+            .query_mailbox_with(about, &witnesses, &self.keys)
+            .await?)
+    }
+
+    /// Queries mailbox of `about` until its event `sn` is accepted, e.g.
+    /// when receipts are collected. Returns required actions found in the
+    /// meantime.
+    pub async fn wait_for_sn(
+        &mut self,
+        about: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<Vec<ActionRequired>> {
+        let mut actions = vec![];
+        for _ in 0..ATTEMPTS {
+            actions.extend(self.query_mailbox(about).await?);
+            if matches!(self.identifier.find_state(about), Ok(state) if state.sn >= sn) {
+                return Ok(actions);
+            }
+            async_std::task::sleep(Duration::from_millis(500)).await;
+        }
+        Err(format!("Event {} of {} wasn't accepted", sn, about).into())
+    }
+
+    /// Queries mailbox until any action is required.
+    pub async fn wait_for_actions(&mut self) -> Result<Vec<ActionRequired>> {
+        let id = self.id().clone();
+        for _ in 0..ATTEMPTS {
+            let actions = self.query_mailbox(&id).await?;
+            if !actions.is_empty() {
+                return Ok(actions);
+            }
+            async_std::task::sleep(Duration::from_millis(500)).await;
+        }
+        Err(format!("No actions required from {}", id).into())
+    }
+
+    /// Gets KEL of `other` from its witness, as it would be done with OOBI
+    /// received out of band.
+    pub async fn resolve(&self, other: &Party) -> Result<()> {
+        let oobi = Oobi::EndRole(EndRole {
+            cid: other.id().clone(),
+            role: Role::Witness,
+            eid: other.witnesses[0].eid.clone(),
+        });
+        self.identifier.resolve_oobi(&oobi).await?;
+        Ok(())
+    }
+}
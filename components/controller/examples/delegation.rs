@@ -0,0 +1,78 @@
+//! Delegated identifier: delegate asks delegator for approval of its
+//! inception through witnesses' mailboxes.
+//!
+//! Run with `cargo run -p keri-controller --example delegation` after
+//! starting witnesses with `docker compose up -d`.
+mod common;
+
+use common::{sign, witness_threshold, Party, Result};
+use keri_controller::mailbox_updating::ActionRequired;
+use keri_core::prefix::IndexedSignature;
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let mut delegator = Party::incept("delegator").await?;
+    let mut delegate = Party::incept("delegate").await?;
+    delegator.resolve(&delegate).await?;
+
+    // Delegate proposes delegated inception to delegator.
+    let witnesses = delegate.witness_ids();
+    let threshold = witness_threshold(witnesses.len());
+    let (dip, exn_messages) = delegate.identifier.incept_group(
+        vec![],
+        1,
+        Some(witnesses),
+        Some(threshold),
+        Some(delegator.id().clone()),
+    )?;
+    let signature_dip = sign(&delegate.keys, dip.as_bytes())?;
+    let signature_exn = sign(&delegate.keys, exn_messages[0].as_bytes())?;
+    let delegated_id = delegate
+        .identifier
+        .finalize_group_incept(
+            dip.as_bytes(),
+            signature_dip,
+            vec![(exn_messages[0].as_bytes().to_vec(), signature_exn)],
+        )
+        .await?;
+    println!("delegate: requested delegation of {}", delegated_id);
+
+    // Delegator approves it by anchoring delegated event in own KEL.
+    for action in delegator.wait_for_actions().await? {
+        match action {
+            ActionRequired::DelegationRequest(ixn, exn) => {
+                let signature_ixn = sign(&delegator.keys, &ixn.encode()?)?;
+                let signature_exn = sign(&delegator.keys, &exn.encode()?)?;
+                delegator
+                    .identifier
+                    .finalize_group_incept(&ixn.encode()?, signature_ixn.clone(), vec![])
+                    .await?;
+                delegator.identifier.notify_witnesses().await?;
+                let id = delegator.id().clone();
+                delegator.wait_for_sn(&id, 1).await?;
+
+                // Send approval back to delegate.
+                let data_signature = IndexedSignature::new_both_same(signature_ixn, 0);
+                delegator
+                    .identifier
+                    .finalize_exchange(&exn.encode()?, signature_exn, data_signature)
+                    .await?;
+                println!("delegator: approved delegation");
+            }
+            ActionRequired::MultisigRequest(_, _, _) => {
+                return Err("Unexpected multisig request".into())
+            }
+            ActionRequired::NextMailboxPage(_) => {
+                return Err("Unexpected mailbox page query".into())
+            }
+        }
+    }
+
+    // Delegate gets delegator's updated KEL, then delegating event and
+    // receipts of delegated inception.
+    delegate.resolve(&delegator).await?;
+    delegate.wait_for_sn(&delegated_id, 0).await?;
+    println!("delegate: delegated identifier {} accepted", delegated_id);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Group identifier of two participants, who exchange group inception
+//! through witnesses' mailboxes.
+//!
+//! Run with `cargo run -p keri-controller --example multisig` after
+//! starting witnesses with `docker compose up -d`.
+mod common;
+
+use common::{sign, witness_threshold, Party, Result};
+use keri_controller::mailbox_updating::ActionRequired;
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let mut alice = Party::incept("alice").await?;
+    let mut bob = Party::incept("bob").await?;
+    // Participants need each other's KEL to verify their signatures.
+    alice.resolve(&bob).await?;
+    bob.resolve(&alice).await?;
+
+    // Alice proposes group and sends it to Bob's mailbox.
+    let witnesses = alice.witness_ids();
+    let threshold = witness_threshold(witnesses.len());
+    let (group_inception, exn_messages) = alice.identifier.incept_group(
+        vec![bob.id().clone()],
+        2,
+        Some(witnesses),
+        Some(threshold),
+        None,
+    )?;
+    let signature_icp = sign(&alice.keys, group_inception.as_bytes())?;
+    let signature_exn = sign(&alice.keys, exn_messages[0].as_bytes())?;
+    let group_id = alice
+        .identifier
+        .finalize_group_incept(
+            group_inception.as_bytes(),
+            signature_icp,
+            vec![(exn_messages[0].as_bytes().to_vec(), signature_exn)],
+        )
+        .await?;
+    println!("alice: proposed group {}", group_id);
+
+    // Bob finds proposal in his mailbox and signs it too.
+    for action in bob.wait_for_actions().await? {
+        match action {
+            ActionRequired::MultisigRequest(event, exn, _metadata) => {
+                let signature_icp = sign(&bob.keys, &event.encode()?)?;
+                let signature_exn = sign(&bob.keys, &exn.encode()?)?;
+                bob.identifier
+                    .finalize_group_incept(
+                        &event.encode()?,
+                        signature_icp,
+                        vec![(exn.encode()?, signature_exn)],
+                    )
+                    .await?;
+                println!("bob: signed group inception");
+            }
+            ActionRequired::DelegationRequest(_, _) => {
+                return Err("Unexpected delegation request".into())
+            }
+            ActionRequired::NextMailboxPage(_) => {
+                return Err("Unexpected mailbox page query".into())
+            }
+        }
+    }
+
+    // Both participants collect signatures and receipts of group inception.
+    alice.wait_for_sn(&group_id, 0).await?;
+    bob.wait_for_sn(&group_id, 0).await?;
+    println!("alice, bob: group {} accepted", group_id);
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Single signature identifier: inception, key rotation and signing data
+//! that other identifier verifies.
+//!
+//! Run with `cargo run -p keri-controller --example single_sig` after
+//! starting witnesses with `docker compose up -d`.
+mod common;
+
+use common::{sign, witness_threshold, Party, Result};
+use keri_controller::{BasicPrefix, KeyManager};
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let mut alice = Party::incept("alice").await?;
+
+    // Rotate keys. Witnesses stay the same.
+    let threshold = witness_threshold(alice.witnesses.len());
+    alice.keys.rotate()?;
+    let pk = BasicPrefix::Ed25519(alice.keys.public_key());
+    let npk = BasicPrefix::Ed25519(alice.keys.next_public_key());
+    let rot = alice
+        .identifier
+        .rotate(vec![pk], vec![npk], 1, vec![], vec![], threshold)
+        .await?;
+    let signature = sign(&alice.keys, rot.as_bytes())?;
+    alice
+        .identifier
+        .finalize_rotate(rot.as_bytes(), signature)
+        .await?;
+    alice.identifier.notify_witnesses().await?;
+    let id = alice.id().clone();
+    alice.wait_for_sn(&id, 1).await?;
+    println!("alice: rotated keys");
+
+    // Sign data with current keys.
+    let data = r#"{"msg":"hello"}"#;
+    let signature = sign(&alice.keys, data.as_bytes())?;
+    let signed = alice.identifier.sign_to_cesr(data, &[signature])?;
+
+    // Bob gets Alice's KEL from her witness and verifies signature.
+    let bob = Party::incept("bob").await?;
+    bob.resolve(&alice).await?;
+    bob.controller.verify_from_cesr(&signed)?;
+    println!("bob: verified {}", signed);
+
+    Ok(())
+}
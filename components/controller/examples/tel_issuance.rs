@@ -0,0 +1,69 @@
+//! Credential issuance and revocation in transaction event log (TEL) of
+//! issuer's registry. TEL events are published to issuer's witnesses, which
+//! serve as registry backers.
+//!
+//! Run with `cargo run -p keri-controller --example tel_issuance` after
+//! starting witnesses with `docker compose up -d`.
+mod common;
+
+use common::{sign, Party, Result};
+use keri_controller::{IdentifierPrefix, TelState};
+use keri_core::actor::prelude::{HashFunction, HashFunctionCode};
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let mut issuer = Party::incept("issuer").await?;
+    let id = issuer.id().clone();
+
+    // Incept registry. Its inception is anchored in issuer's KEL.
+    let (registry_id, vcp_ixn) = issuer.identifier.incept_registry()?;
+    let signature = sign(&issuer.keys, &vcp_ixn)?;
+    issuer
+        .identifier
+        .finalize_incept_registry(&vcp_ixn, signature)
+        .await?;
+    issuer.identifier.notify_witnesses().await?;
+    issuer.wait_for_sn(&id, 1).await?;
+    println!("issuer: incepted registry {}", registry_id);
+
+    // Issue credential, represented here by its digest.
+    let credential = r#"{"name":"example credential"}"#;
+    let credential_said =
+        HashFunction::from(HashFunctionCode::Blake3_256).derive(credential.as_bytes());
+    let (vc_id, iss_ixn) = issuer.identifier.issue(credential_said)?;
+    let vc_said = match &vc_id {
+        IdentifierPrefix::SelfAddressing(sai) => sai.said.clone(),
+        _ => return Err("Unexpected credential identifier".into()),
+    };
+    let signature = sign(&issuer.keys, &iss_ixn)?;
+    issuer
+        .identifier
+        .finalize_issue(&iss_ixn, signature)
+        .await?;
+    issuer.identifier.notify_witnesses().await?;
+    issuer.wait_for_sn(&id, 2).await?;
+    issuer.identifier.notify_backers().await?;
+    assert!(matches!(
+        issuer.identifier.find_vc_state(&vc_said)?,
+        Some(TelState::Issued(_))
+    ));
+    println!("issuer: issued {}", vc_id);
+
+    // Revoke it.
+    let rev_ixn = issuer.identifier.revoke(&vc_said)?;
+    let signature = sign(&issuer.keys, &rev_ixn)?;
+    issuer
+        .identifier
+        .finalize_revoke(&rev_ixn, signature)
+        .await?;
+    issuer.identifier.notify_witnesses().await?;
+    issuer.wait_for_sn(&id, 3).await?;
+    issuer.identifier.notify_backers().await?;
+    assert!(matches!(
+        issuer.identifier.find_vc_state(&vc_said)?,
+        Some(TelState::Revoked)
+    ));
+    println!("issuer: revoked {}", vc_id);
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Verifier, who doesn't share witnesses with issuer, gets issuer's KEL and
+//! credential status from its watcher.
+//!
+//! Run with `cargo run -p keri-controller --example watcher_verification`
+//! after starting witnesses and watcher with `docker compose up -d`.
+mod common;
+
+use std::time::Duration;
+
+use common::{sign, watcher_url, Party, Result};
+use keri_controller::{EndRole, IdentifierPrefix, Oobi, TelState};
+use keri_core::{
+    actor::prelude::{HashFunction, HashFunctionCode},
+    oobi::Role,
+};
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    // Issuer issues credential.
+    let mut issuer = Party::incept("issuer").await?;
+    let id = issuer.id().clone();
+    let (registry_id, vcp_ixn) = issuer.identifier.incept_registry()?;
+    let signature = sign(&issuer.keys, &vcp_ixn)?;
+    issuer
+        .identifier
+        .finalize_incept_registry(&vcp_ixn, signature)
+        .await?;
+    issuer.identifier.notify_witnesses().await?;
+    issuer.wait_for_sn(&id, 1).await?;
+
+    let credential_said =
+        HashFunction::from(HashFunctionCode::Blake3_256).derive(br#"{"name":"example"}"#);
+    let (vc_id, iss_ixn) = issuer.identifier.issue(credential_said)?;
+    let vc_said = match &vc_id {
+        IdentifierPrefix::SelfAddressing(sai) => sai.said.clone(),
+        _ => return Err("Unexpected credential identifier".into()),
+    };
+    let signature = sign(&issuer.keys, &iss_ixn)?;
+    issuer
+        .identifier
+        .finalize_issue(&iss_ixn, signature)
+        .await?;
+    issuer.identifier.notify_witnesses().await?;
+    issuer.wait_for_sn(&id, 2).await?;
+    issuer.identifier.notify_backers().await?;
+    println!("issuer: issued {}", vc_id);
+
+    // Verifier sets up watcher.
+    let verifier = Party::incept("verifier").await?;
+    let watcher = verifier.controller.introduce(&watcher_url()?).await?;
+    let add_watcher = verifier.identifier.add_watcher(watcher.eid.clone())?;
+    let signature = sign(&verifier.keys, add_watcher.as_bytes())?;
+    verifier
+        .identifier
+        .finalize_add_watcher(add_watcher.as_bytes(), signature)
+        .await?;
+    println!("verifier: added watcher {}", watcher.eid);
+
+    // Issuer's OOBIs are provided to verifier out of band. Verifier passes
+    // them to watcher, which finds issuer's KEL and TEL.
+    let issuer_witness = issuer.witnesses[0].clone();
+    let oobis = [
+        Oobi::Location(issuer_witness.clone()),
+        Oobi::EndRole(EndRole {
+            cid: issuer.id().clone(),
+            role: Role::Witness,
+            eid: issuer_witness.eid.clone(),
+        }),
+        Oobi::EndRole(EndRole {
+            cid: registry_id.clone(),
+            role: Role::Witness,
+            eid: issuer_witness.eid.clone(),
+        }),
+    ];
+    for oobi in &oobis {
+        verifier
+            .identifier
+            .send_oobi_to_watcher(verifier.id(), oobi)
+            .await?;
+    }
+
+    // Watcher gets KEL in the background, so ask until it's found.
+    let last_event_seal = issuer.identifier.get_last_event_seal()?;
+    let mut kel_found = false;
+    for _ in 0..10 {
+        let queries = verifier
+            .identifier
+            .query_watchers(&last_event_seal)?
+            .into_iter()
+            .map(|qry| -> Result<_> {
+                let signature = sign(&verifier.keys, &qry.encode()?)?;
+                Ok((qry, signature))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (_updates, errors) = verifier.identifier.finalize_query(queries).await;
+        let accepted = verifier
+            .controller
+            .find_state(&id)
+            .is_ok_and(|state| state.sn == last_event_seal.sn);
+        if errors.is_empty() && accepted {
+            kel_found = true;
+            break;
+        }
+        async_std::task::sleep(Duration::from_millis(500)).await;
+    }
+    if !kel_found {
+        return Err("Watcher didn't find issuer's KEL".into());
+    }
+    println!("verifier: got issuer's KEL");
+
+    // The same for TEL.
+    for _ in 0..10 {
+        let qry = verifier
+            .identifier
+            .query_tel(registry_id.clone(), vc_id.clone())?;
+        let signature = sign(&verifier.keys, &qry.encode()?)?;
+        verifier
+            .identifier
+            .finalize_query_tel(qry, signature)
+            .await?;
+        if let Some(TelState::Issued(_)) = verifier.identifier.find_vc_state(&vc_said)? {
+            println!("verifier: credential {} is issued", vc_id);
+            return Ok(());
+        }
+        async_std::task::sleep(Duration::from_millis(500)).await;
+    }
+    Err("Watcher didn't find credential status".into())
+}
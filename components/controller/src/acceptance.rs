@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use keri_core::{event::KeyEvent, event_message::msg::KeriEvent};
+
+use crate::{controller::Controller, error::ControllerError};
+
+/// Decision of [`AcceptanceHook`] about key event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Acceptance {
+    Allow,
+    /// Event isn't processed. Contains reason reported in
+    /// `keri_core::error::Error::EventRejected`.
+    Reject(String),
+}
+
+/// Local policy of embedding application, consulted before controller
+/// processes key event. It's checked in addition to validation, so it can
+/// only narrow set of accepted events, e.g. forbid rotations that weren't
+/// initiated through application's UI. Hook can be called more than once for
+/// the same event, for example when more signatures of multisig event
+/// arrive.
+pub trait AcceptanceHook: Send + Sync {
+    fn on_before_accept(&self, event: &KeriEvent<KeyEvent>) -> Acceptance;
+}
+
+impl<F> AcceptanceHook for F
+where
+    F: Fn(&KeriEvent<KeyEvent>) -> Acceptance + Send + Sync,
+{
+    fn on_before_accept(&self, event: &KeriEvent<KeyEvent>) -> Acceptance {
+        self(event)
+    }
+}
+
+impl Controller {
+    /// Registers hook consulted before any key event is processed by this
+    /// controller, including events of its own identifiers and events
+    /// received from witnesses and watchers. Replaces previously registered
+    /// hook.
+    pub fn on_before_accept(
+        &self,
+        hook: impl AcceptanceHook + 'static,
+    ) -> Result<(), ControllerError> {
+        self.known_events
+            .set_acceptance_hook(Some(Arc::new(hook)))?;
+        Ok(())
+    }
+
+    /// Removes registered acceptance hook.
+    pub fn clear_acceptance_hook(&self) -> Result<(), ControllerError> {
+        self.known_events.set_acceptance_hook(None)?;
+        Ok(())
+    }
+}
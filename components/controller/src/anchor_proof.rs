@@ -0,0 +1,88 @@
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event::{event_data::EventData, sections::seal::Seal, KeyEvent},
+    event_message::{msg::KeriEvent, signature::Nontransferable},
+    prefix::{IdentifierPrefix, IndexedSignature},
+    processor::event_storage::KelEntry,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{identifier::Identifier, known_events::KnownEvents};
+
+/// Proof that data was anchored in identifier's KEL: accepted `ixn` event
+/// containing seal of the data, with signatures and witness receipts needed
+/// to verify it independently.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnchorProof {
+    /// Anchoring interaction event.
+    pub event: KeriEvent<KeyEvent>,
+    /// Position of the seal in event's data.
+    pub seal_index: usize,
+    /// Controller signatures attached to the event.
+    pub signatures: Vec<IndexedSignature>,
+    /// Witness receipts of the event.
+    pub witness_receipts: Vec<Nontransferable>,
+}
+
+impl AnchorProof {
+    fn new(entry: KelEntry, seal_index: usize) -> Self {
+        let witness_receipts = match entry.receipts {
+            Some(receipt) => receipt.signatures,
+            None => entry.event.witness_receipts.unwrap_or_default(),
+        };
+        Self {
+            event: entry.event.event_message,
+            seal_index,
+            signatures: entry.event.signatures,
+            witness_receipts,
+        }
+    }
+
+    /// Returns the seal of anchored data.
+    pub fn seal(&self) -> Option<&Seal> {
+        match &self.event.data.event_data {
+            EventData::Ixn(ixn) => ixn.data.get(self.seal_index),
+            _ => None,
+        }
+    }
+}
+
+/// Checks if `seal` anchors data with digest `said`. Event seals anchor
+/// events of other identifiers or registries by their digest.
+fn seals(seal: &Seal, said: &SelfAddressingIdentifier) -> bool {
+    match seal {
+        Seal::Digest(digest) => &digest.digest() == said,
+        Seal::Event(event) => &event.event_digest() == said,
+        Seal::Location(_) | Seal::Root(_) => false,
+    }
+}
+
+impl KnownEvents {
+    /// Finds accepted `ixn` event of identifier that anchors `said` and
+    /// returns it as proof of anchoring. If data was anchored more than
+    /// once, the first anchoring event is used.
+    pub fn get_anchoring_proof(
+        &self,
+        id: &IdentifierPrefix,
+        said: &SelfAddressingIdentifier,
+    ) -> Option<AnchorProof> {
+        self.kel_iter(id).map_while(Result::ok).find_map(|entry| {
+            let seal_index = match &entry.event.event_message.data.event_data {
+                EventData::Ixn(ixn) => ixn.data.iter().position(|seal| seals(seal, said)),
+                _ => None,
+            }?;
+            Some(AnchorProof::new(entry, seal_index))
+        })
+    }
+}
+
+impl Identifier {
+    /// Returns proof that `said` was anchored in KEL of `id`, if it's known.
+    pub fn get_anchoring_proof(
+        &self,
+        id: &IdentifierPrefix,
+        said: &SelfAddressingIdentifier,
+    ) -> Option<AnchorProof> {
+        self.known_events.get_anchoring_proof(id, said)
+    }
+}
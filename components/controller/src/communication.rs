@@ -1,22 +1,40 @@
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+};
 
-use futures::future::join_all;
+use futures::{
+    future::{join_all, BoxFuture},
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
 use keri_core::{
-    actor::{error::ActorError, parse_event_stream, simple_controller::PossibleResponse},
-    event_message::signed_event_message::{Message, Notice, Op, SignedEventMessage},
-    oobi::{EndRole, LocationScheme, Oobi, Scheme},
-    prefix::{BasicPrefix, IdentifierPrefix},
+    actor::{error::ActorError, parse_stream, simple_controller::PossibleResponse},
+    event_message::{
+        signature::Nontransferable,
+        signed_event_message::{
+            Message, Notice, Op, SignedEventMessage, SignedNontransferableReceipt,
+        },
+    },
+    oobi::{
+        traversal::{OobiLimits, OobiTraversal},
+        EndRole, LocationScheme, Oobi, Role, Scheme,
+    },
+    prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
     query::{
         mailbox::SignedMailboxQuery,
+        provenance::SignedProvenance,
         query_event::{SignedKelQuery, SignedQueryMessage},
+        reply_event::ReplyRoute,
     },
+    state::WitnessConfig,
     transport::{Transport, TransportError},
 };
-use teliox::transport::GeneralTelTransport;
+use teliox::transport::TelTransport;
 
 use crate::{
     error::ControllerError,
-    identifier::mechanics::MechanicsError,
+    identifier::{mechanics::MechanicsError, witness_health::WitnessHealthLog},
     known_events::{KnownEvents, OobiRetrieveError},
 };
 
@@ -47,73 +65,244 @@ impl From<TransportError> for SendingError {
     }
 }
 
+/// Checks if error means that remote actor couldn't be reached at all, for
+/// example because direct access to it is blocked.
+fn is_unreachable(err: &SendingError) -> bool {
+    matches!(
+        err,
+        SendingError::TransportError(
+            TransportError::NetworkError(_)
+                | TransportError::Timeout(_)
+                | TransportError::ConnectionRefused(_)
+        )
+    )
+}
+
+/// Witness and result of sending event to it.
+type Delivery = (
+    BasicPrefix,
+    Result<Vec<SignedNontransferableReceipt>, SendingError>,
+);
+
+/// Schemes tried by default, in order, when identifier advertises more than
+/// one location.
+pub const DEFAULT_SCHEME_PRIORITY: [Scheme; 2] = [Scheme::Https, Scheme::Http];
+
+/// Orders locations by position of their scheme in `priority`. Locations with
+/// `requested` scheme are kept even if it isn't in `priority` and are tried
+/// last in that case. Other locations are dropped.
+fn order_locations(
+    locations: Vec<LocationScheme>,
+    requested: &Scheme,
+    priority: &[Scheme],
+) -> Vec<LocationScheme> {
+    let rank = |scheme: &Scheme| {
+        priority
+            .iter()
+            .position(|s| s == scheme)
+            .or_else(|| (scheme == requested).then_some(priority.len()))
+    };
+    let mut ranked: Vec<_> = locations
+        .into_iter()
+        .filter_map(|loc| rank(&loc.scheme).map(|rank| (rank, loc)))
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, loc)| loc).collect()
+}
+
+/// Runs request with each location in turn, until one of them can be
+/// reached. Returns `missing` if there are no locations.
+async fn with_location_fallback<T, F, Fut>(
+    locations: Vec<LocationScheme>,
+    missing: SendingError,
+    request: F,
+) -> Result<T, SendingError>
+where
+    F: Fn(LocationScheme) -> Fut,
+    Fut: Future<Output = Result<T, TransportError>>,
+{
+    let mut result = Err(missing);
+    for loc in locations {
+        result = request(loc).await.map_err(SendingError::from);
+        match &result {
+            Err(err) if is_unreachable(err) => continue,
+            _ => break,
+        }
+    }
+    result
+}
+
 pub struct Communication {
     pub events: Arc<KnownEvents>,
     pub transport: Box<dyn Transport + Send + Sync>,
-    pub tel_transport: Box<dyn GeneralTelTransport + Send + Sync>,
+    pub tel_transport: Box<dyn TelTransport + Send + Sync>,
+    /// Order in which schemes of identifier's locations are tried. See
+    /// [`Communication::set_scheme_priority`].
+    pub scheme_priority: RwLock<Vec<Scheme>>,
+    /// Limits of following OOBIs revealed while resolving OOBI.
+    pub oobi_limits: OobiLimits,
+    /// Results of probing witnesses, see
+    /// [`Identifier::probe_witnesses`](crate::identifier::Identifier::probe_witnesses).
+    pub(crate) witness_health: WitnessHealthLog,
 }
 
 impl Communication {
     pub fn new(
         known_events: Arc<KnownEvents>,
         transport: Box<dyn Transport<ActorError> + Send + Sync>,
-        tel_transport: Box<dyn GeneralTelTransport + Send + Sync>,
+        tel_transport: Box<dyn TelTransport + Send + Sync>,
     ) -> Self {
         Communication {
             events: known_events,
             transport,
             tel_transport,
+            scheme_priority: RwLock::new(DEFAULT_SCHEME_PRIORITY.to_vec()),
+            oobi_limits: OobiLimits::default(),
+            witness_health: WitnessHealthLog::default(),
+        }
+    }
+
+    /// Sets order in which schemes are tried when identifier advertises
+    /// more than one location, e.g. `[Https, Http, Tcp]`. If location can't
+    /// be reached, the next one is used. Schemes missing from the list are
+    /// used only when explicitly requested.
+    pub fn set_scheme_priority(&self, priority: Vec<Scheme>) {
+        if let Ok(mut current) = self.scheme_priority.write() {
+            *current = priority;
         }
     }
 
+    /// Returns locations of `id` in order in which they should be tried.
+    fn find_locations(
+        &self,
+        id: &IdentifierPrefix,
+        scheme: &Scheme,
+    ) -> Result<Vec<LocationScheme>, SendingError> {
+        let locations = self.events.get_loc_schemas(id)?;
+        let priority = self
+            .scheme_priority
+            .read()
+            .map(|priority| priority.clone())
+            .unwrap_or_else(|_| DEFAULT_SCHEME_PRIORITY.to_vec());
+        Ok(order_locations(locations, scheme, &priority))
+    }
+
+    /// Runs request with locations of `id`, starting from preferred scheme
+    /// and moving to the next one if location can't be reached.
+    async fn with_locations<T, F, Fut>(
+        &self,
+        id: &IdentifierPrefix,
+        scheme: Scheme,
+        request: F,
+    ) -> Result<T, SendingError>
+    where
+        F: Fn(LocationScheme) -> Fut,
+        Fut: Future<Output = Result<T, TransportError>>,
+    {
+        let locations = self.find_locations(id, &scheme)?;
+        let missing = OobiRetrieveError::MissingOobi(id.clone(), Some(scheme)).into();
+        with_location_fallback(locations, missing, request).await
+    }
+
     /// Make http request to get identifier's endpoints information.
     pub async fn resolve_loc_schema(&self, lc: &LocationScheme) -> Result<(), MechanicsError> {
+        self.fetch_loc_scheme(lc).await?;
+        Ok(())
+    }
+
+    /// Asks actor at `url` for its location scheme and resolves it, so
+    /// actor can be used e.g. as witness or watcher without knowing its
+    /// identifier in advance.
+    pub async fn introduce(&self, url: &url::Url) -> Result<LocationScheme, MechanicsError> {
+        let lc = self.transport.request_introduction(url.clone()).await?;
+        self.resolve_loc_schema(&lc).await?;
+        Ok(lc)
+    }
+
+    /// Checks if any known location of `id` responds to location scheme
+    /// request. Response isn't saved.
+    pub async fn is_reachable(&self, id: &IdentifierPrefix) -> bool {
+        self.with_locations(id, Scheme::Http, |loc| {
+            self.transport.request_loc_scheme(loc)
+        })
+        .await
+        .is_ok()
+    }
+
+    async fn fetch_loc_scheme(&self, lc: &LocationScheme) -> Result<Vec<Message>, MechanicsError> {
         let oobis = self.transport.request_loc_scheme(lc.clone()).await?;
-        for oobi in oobis {
-            self.events.save(&Message::Op(oobi))?;
+        let msgs: Vec<_> = oobis.into_iter().map(Message::Op).collect();
+        for msg in &msgs {
+            self.events.save(msg)?;
         }
-        Ok(())
+        Ok(msgs)
     }
 
     /// Make http request to get identifier's endpoints information.
     pub async fn resolve_end_role(&self, er: &EndRole) -> Result<(), MechanicsError> {
+        let mut traversal = OobiTraversal::new(Oobi::EndRole(er.clone()), self.oobi_limits.clone());
+        self.fetch_end_role(er, &mut traversal).await?;
+        Ok(())
+    }
+
+    /// Requests end role from endpoint provider locations admitted by
+    /// `traversal` and saves the response.
+    async fn fetch_end_role(
+        &self,
+        er: &EndRole,
+        traversal: &mut OobiTraversal,
+    ) -> Result<Vec<Message>, MechanicsError> {
         let EndRole { cid, role, eid } = er.clone();
-        // TODO what if more than one
-        let loc = self
-            .events
-            .get_loc_schemas(&eid)
-            .map_err(SendingError::OobiError)?
-            .first()
-            .ok_or(SendingError::OobiError(OobiRetrieveError::MissingOobi(
-                cid.clone(),
-                None,
-            )))?
-            .clone();
-        let response = self.transport.request_end_role(loc, cid, role, eid).await?;
-
-        let msgs = parse_event_stream(response.as_ref()).map_err(|e| {
-            MechanicsError::OtherError(format!(
-                "Can't parse response while oobi resolving: {}",
-                e.to_string()
-            ))
-        })?;
-        for msg in msgs {
+        let locations = self
+            .find_locations(&eid, &Scheme::Http)?
+            .into_iter()
+            .filter(|loc| traversal.admit(&loc.url).is_ok())
+            .collect();
+        let missing = OobiRetrieveError::MissingOobi(eid.clone(), Some(Scheme::Http)).into();
+        let response = with_location_fallback(locations, missing, |loc| {
+            self.transport
+                .request_end_role(loc, cid.clone(), role.clone(), eid.clone())
+        })
+        .await?;
+        traversal.check_response(&response)?;
+
+        let msgs = parse_stream::<Message>(response.as_ref(), self.events.strict_canonicalization)?;
+        for msg in &msgs {
             // TODO This ignore signatures. Add verification.
             if let Message::Op(Op::Reply(signed_oobi)) = msg {
-                self.events.save_oobi(&signed_oobi)?;
+                self.events.save_oobi(signed_oobi)?;
             } else {
-                self.events.save(&msg)?;
+                self.events.save(msg)?;
             }
         }
-        Ok(())
+        Ok(msgs)
     }
 
-    /// Make http request to get identifier's endpoints information.
+    /// Make http request to get identifier's endpoints information. OOBIs
+    /// of other endpoint providers revealed in responses are resolved too,
+    /// within [`Communication::oobi_limits`]. Only failure of `oobi` itself
+    /// is returned.
     pub async fn resolve_oobi(&self, oobi: &Oobi) -> Result<(), MechanicsError> {
-        match oobi {
-            Oobi::Location(loc) => self.resolve_loc_schema(loc).await,
-            Oobi::EndRole(er) => self.resolve_end_role(er).await,
+        let mut traversal = OobiTraversal::new(oobi.clone(), self.oobi_limits.clone());
+        while let Some((oobi, depth)) = traversal.next_oobi() {
+            let resolved = match &oobi {
+                Oobi::Location(loc) if traversal.admit(&loc.url).is_ok() => self
+                    .fetch_loc_scheme(loc)
+                    .await
+                    .map(|msgs| (loc.eid.clone(), msgs)),
+                Oobi::Location(_) => continue,
+                Oobi::EndRole(er) => self
+                    .fetch_end_role(er, &mut traversal)
+                    .await
+                    .map(|msgs| (er.eid.clone(), msgs)),
+            };
+            match resolved {
+                Ok((source, msgs)) => traversal.discovered(depth, &source, &msgs),
+                Err(err) if depth == 0 => return Err(err),
+                Err(_) => (),
+            }
         }
+        Ok(())
     }
 
     pub async fn send_message_to(
@@ -122,9 +311,40 @@ impl Communication {
         scheme: Scheme,
         msg: Message,
     ) -> Result<(), SendingError> {
-        let loc = self.events.find_location(&id, scheme)?;
-        self.transport.send_message(loc, msg).await?;
-        Ok(())
+        let msg = &msg;
+        self.with_locations(&id, scheme, move |loc| {
+            self.transport.send_message(loc, msg.clone())
+        })
+        .await
+    }
+
+    /// Sends event to `id` and returns receipts it issued in response.
+    pub async fn send_event_to(
+        &self,
+        id: &IdentifierPrefix,
+        scheme: Scheme,
+        event: SignedEventMessage,
+    ) -> Result<Vec<SignedNontransferableReceipt>, SendingError> {
+        let event = &event;
+        self.with_locations(id, scheme, move |loc| {
+            self.transport.send_event(loc, event.clone())
+        })
+        .await
+    }
+
+    /// Requests watcher's provenance of KEL of `id`, or of TEL of `vc_id`
+    /// from registry `id`. Signature isn't checked.
+    pub async fn request_provenance(
+        &self,
+        watcher: &IdentifierPrefix,
+        id: &IdentifierPrefix,
+        vc_id: Option<&IdentifierPrefix>,
+    ) -> Result<SignedProvenance, SendingError> {
+        self.with_locations(watcher, Scheme::Http, |loc| {
+            self.transport
+                .request_provenance(loc, id.clone(), vc_id.cloned())
+        })
+        .await
     }
 
     pub async fn send_query_to(
@@ -133,11 +353,12 @@ impl Communication {
         scheme: Scheme,
         query: SignedKelQuery,
     ) -> Result<PossibleResponse, SendingError> {
-        let loc = self.events.find_location(id, scheme)?;
-        Ok(self
-            .transport
-            .send_query(loc, SignedQueryMessage::KelQuery(query))
-            .await?)
+        let query = SignedQueryMessage::KelQuery(query);
+        let query = &query;
+        self.with_locations(id, scheme, move |loc| {
+            self.transport.send_query(loc, query.clone())
+        })
+        .await
     }
 
     pub async fn send_management_query_to(
@@ -146,11 +367,12 @@ impl Communication {
         scheme: Scheme,
         query: SignedMailboxQuery,
     ) -> Result<PossibleResponse, SendingError> {
-        let loc = self.events.find_location(id, scheme)?;
-        Ok(self
-            .transport
-            .send_query(loc, SignedQueryMessage::MailboxQuery(query))
-            .await?)
+        let query = SignedQueryMessage::MailboxQuery(query);
+        let query = &query;
+        self.with_locations(id, scheme, move |loc| {
+            self.transport.send_query(loc, query.clone())
+        })
+        .await
     }
 
     async fn send_oobi_to(
@@ -159,9 +381,10 @@ impl Communication {
         scheme: Scheme,
         oobi: Oobi,
     ) -> Result<(), SendingError> {
-        let loc = self.events.find_location(id, scheme)?;
-        self.transport.resolve_oobi(loc, oobi).await?;
-        Ok(())
+        self.with_locations(id, scheme, |loc| {
+            self.transport.resolve_oobi(loc, oobi.clone())
+        })
+        .await
     }
 
     /// Publish key event to witnesses
@@ -209,6 +432,108 @@ impl Communication {
         Ok(())
     }
 
+    /// Publish key event to witnesses concurrently and return as soon as
+    /// receipts returned by witnesses satisfy witness threshold (toad).
+    /// Sending to the remaining witnesses continues in background, and
+    /// afterwards all collected receipts are sent to witnesses. Returns
+    /// witnesses that receipted event before return. If threshold can't be
+    /// reached, returns after all witnesses responded.
+    pub async fn publish_until_receipted(
+        self: &Arc<Self>,
+        witness_config: WitnessConfig,
+        message: &SignedEventMessage,
+    ) -> Result<Vec<BasicPrefix>, MechanicsError> {
+        let mut pending: FuturesUnordered<BoxFuture<'static, Delivery>> = witness_config
+            .witnesses
+            .iter()
+            .map(|witness| {
+                let (communication, witness, event) =
+                    (Arc::clone(self), witness.clone(), message.clone());
+                async move {
+                    let id = IdentifierPrefix::Basic(witness.clone());
+                    let result = communication.send_event_to(&id, Scheme::Http, event).await;
+                    (witness, result)
+                }
+                .boxed()
+            })
+            .collect();
+
+        let mut couplets = vec![];
+        while !witness_config.enough_receipts(couplets.clone(), vec![])? {
+            match pending.next().await {
+                Some(delivery) => couplets.extend(self.process_delivery(delivery)?),
+                None => break,
+            }
+        }
+        let receipted = couplets
+            .iter()
+            .map(|(witness, _)| witness.clone())
+            .collect();
+
+        let (communication, witnesses, event) = (
+            Arc::clone(self),
+            witness_config.witnesses.clone(),
+            message.clone(),
+        );
+        async_std::task::spawn(async move {
+            while let Some(delivery) = pending.next().await {
+                // Unreachable witness will get receipts in next publish.
+                let _ = communication.process_delivery(delivery);
+            }
+            let _ = communication.publish_receipts(witnesses, &event).await;
+        });
+
+        Ok(receipted)
+    }
+
+    /// Processes receipts returned by witness. Returns signatures of the
+    /// witness. Witness that couldn't be reached gives no signatures.
+    fn process_delivery(
+        &self,
+        (witness, result): Delivery,
+    ) -> Result<Vec<(BasicPrefix, SelfSigningPrefix)>, MechanicsError> {
+        let mut couplets = vec![];
+        for rct in result.unwrap_or_default() {
+            couplets.extend(
+                rct.signatures
+                    .iter()
+                    .flat_map(|sigs| match sigs {
+                        Nontransferable::Couplet(couplets) => couplets.clone(),
+                        Nontransferable::Indexed(_) => vec![],
+                    })
+                    .filter(|(bp, _sig)| bp == &witness),
+            );
+            self.events
+                .process(&Message::Notice(Notice::NontransferableRct(rct)))?;
+        }
+        Ok(couplets)
+    }
+
+    /// Sends all receipts of event collected so far to witnesses.
+    async fn publish_receipts(
+        &self,
+        witness_prefixes: Vec<BasicPrefix>,
+        message: &SignedEventMessage,
+    ) -> Result<(), MechanicsError> {
+        let (prefix, sn, digest) = (
+            message.event_message.data.get_prefix(),
+            message.event_message.data.get_sn(),
+            message.event_message.digest()?,
+        );
+        if let Some(rct) = self.events.find_receipt(&prefix, sn, &digest)? {
+            let receipt = Message::Notice(Notice::NontransferableRct(rct));
+            join_all(witness_prefixes.into_iter().map(|witness_id| {
+                self.send_message_to(
+                    IdentifierPrefix::Basic(witness_id),
+                    Scheme::Http,
+                    receipt.clone(),
+                )
+            }))
+            .await;
+        }
+        Ok(())
+    }
+
     /// Sends identifier's endpoint information to identifiers's watchers.
     // TODO use stream instead of json
     pub async fn send_oobi_to_watcher(
@@ -216,11 +541,106 @@ impl Communication {
         id: &IdentifierPrefix,
         oobi: &Oobi,
     ) -> Result<(), ControllerError> {
-        for watcher in self.events.get_watchers(id)?.iter() {
-            self.send_oobi_to(watcher, Scheme::Http, oobi.clone())
+        let watchers = self
+            .events
+            .oobi_manager
+            .get_current_end_roles(id, Role::Watcher)?
+            .into_iter()
+            .filter_map(|rpy| match rpy.reply.get_route() {
+                ReplyRoute::EndRoleAdd(end_role) => Some(end_role.eid),
+                _ => None,
+            });
+        for watcher in watchers {
+            self.send_oobi_to(&watcher, Scheme::Http, oobi.clone())
                 .await?;
         }
 
         Ok(())
     }
 }
+
+#[async_std::test]
+async fn test_location_fallback() {
+    use std::sync::Mutex;
+
+    let witness: IdentifierPrefix = "BKVb58uITf48YoMPz8SBOTVwLgTO9BY4oEXRPoYIOErX"
+        .parse()
+        .unwrap();
+    let loc = |scheme: Scheme, url: &str| {
+        LocationScheme::new(witness.clone(), scheme, url::Url::parse(url).unwrap())
+    };
+    let locations = vec![
+        loc(Scheme::Tcp, "tcp://witness.example:5633/"),
+        loc(Scheme::Http, "http://witness.example:3232/"),
+        loc(Scheme::Https, "https://witness.example/"),
+        loc(Scheme::Http, "http://backup.example:3232/"),
+    ];
+
+    // Https is preferred and tcp is dropped, as it isn't in default priority.
+    let ordered = order_locations(locations.clone(), &Scheme::Http, &DEFAULT_SCHEME_PRIORITY);
+    assert_eq!(
+        ordered,
+        vec![
+            locations[2].clone(),
+            locations[1].clone(),
+            locations[3].clone()
+        ]
+    );
+    // Requested scheme is kept even if it isn't in priority.
+    let ordered = order_locations(locations.clone(), &Scheme::Tcp, &[Scheme::Http]);
+    assert_eq!(
+        ordered,
+        vec![
+            locations[1].clone(),
+            locations[3].clone(),
+            locations[0].clone()
+        ]
+    );
+
+    // Unreachable locations are skipped.
+    let called = Mutex::new(vec![]);
+    let result = with_location_fallback(
+        ordered.clone(),
+        SendingError::OobiError(OobiRetrieveError::MissingOobi(witness.clone(), None)),
+        |loc| {
+            called.lock().unwrap().push(loc.url.clone());
+            let reachable = loc.scheme == Scheme::Tcp;
+            async move {
+                if reachable {
+                    Ok(())
+                } else {
+                    Err(TransportError::NetworkError("unreachable".into()))
+                }
+            }
+        },
+    )
+    .await;
+    assert!(result.is_ok());
+    assert_eq!(called.lock().unwrap().len(), 3);
+
+    // Errors returned by reachable actor aren't retried.
+    let called = Mutex::new(vec![]);
+    let result: Result<(), _> = with_location_fallback(
+        ordered,
+        SendingError::OobiError(OobiRetrieveError::MissingOobi(witness.clone(), None)),
+        |loc| {
+            called.lock().unwrap().push(loc);
+            async { Err(TransportError::EmptyResponse) }
+        },
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(SendingError::TransportError(TransportError::EmptyResponse))
+    ));
+    assert_eq!(called.lock().unwrap().len(), 1);
+
+    // No locations.
+    let result: Result<(), _> = with_location_fallback(
+        vec![],
+        SendingError::OobiError(OobiRetrieveError::MissingOobi(witness.clone(), None)),
+        |_| async { Ok(()) },
+    )
+    .await;
+    assert!(matches!(result, Err(SendingError::OobiError(_))));
+}
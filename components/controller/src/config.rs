@@ -1,18 +1,43 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use keri_core::{
-    oobi::LocationScheme,
+    oobi::{traversal::OobiLimits, LocationScheme, Scheme},
     processor::escrow::EscrowConfig,
+    query::reply_event::DEFAULT_MAX_CLOCK_DRIFT,
     transport::{default::DefaultTransport, Transport},
 };
-use teliox::transport::{GeneralTelTransport, TelTransport};
+use serde::{Deserialize, Serialize};
+use teliox::transport::{DefaultTelTransport, TelTransport};
+
+use crate::communication::DEFAULT_SCHEME_PRIORITY;
 
 pub struct ControllerConfig {
     pub db_path: PathBuf,
     pub initial_oobis: Vec<LocationScheme>,
     pub escrow_config: EscrowConfig,
+    /// How far in the future timestamps of accepted replies can be.
+    pub max_clock_drift: Duration,
     pub transport: Box<dyn Transport + Send + Sync>,
-    pub tel_transport: Box<dyn GeneralTelTransport + Send + Sync>,
+    pub tel_transport: Box<dyn TelTransport + Send + Sync>,
+    /// Named environments, e.g. `dev` or `prod`, that can be selected with
+    /// [`Controller::with_profile`](crate::controller::Controller::with_profile).
+    pub profiles: HashMap<String, Profile>,
+    /// Order in which schemes of witness and watcher locations are tried.
+    pub scheme_priority: Vec<Scheme>,
+    /// Limits of following OOBIs revealed while resolving OOBI.
+    pub oobi_limits: OobiLimits,
+    /// Whether received events, which bytes differ from their canonical
+    /// serialization, are rejected. Disabled by default.
+    pub strict_canonicalization: bool,
+    /// Number of redb databases events are split into by identifier, so
+    /// writes of different identifiers don't wait for the same database
+    /// lock. Used only when database is created, existing one keeps its
+    /// layout. Events are kept in single database if not set.
+    pub event_shards: Option<usize>,
 }
 
 impl Default for ControllerConfig {
@@ -21,8 +46,59 @@ impl Default for ControllerConfig {
             db_path: PathBuf::from("db"),
             initial_oobis: vec![],
             escrow_config: EscrowConfig::default(),
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
             transport: Box::new(DefaultTransport::new()),
-            tel_transport: Box::new(TelTransport),
+            tel_transport: Box::new(DefaultTelTransport::new()),
+            profiles: HashMap::new(),
+            scheme_priority: DEFAULT_SCHEME_PRIORITY.to_vec(),
+            oobi_limits: OobiLimits::default(),
+            strict_canonicalization: false,
+            event_shards: None,
         }
     }
 }
+
+impl ControllerConfig {
+    /// Adds profiles from JSON file, which maps profile names to witness and
+    /// watcher locations, e.g.
+    /// `{"prod": {"witnesses": [{"eid": "...", "scheme": "http", "url": "..."}], "watchers": []}}`.
+    /// Profiles with the same name are replaced.
+    pub fn with_profiles_file(mut self, path: impl AsRef<Path>) -> Result<Self, ProfileError> {
+        let file = std::fs::read_to_string(path)?;
+        let profiles: HashMap<String, Profile> = serde_json::from_str(&file)?;
+        self.profiles.extend(profiles);
+        Ok(self)
+    }
+
+    /// Uses [`DefaultTransport`] that rejects query responses without valid
+    /// integrity envelope signed by queried witness, see
+    /// [`DefaultTransport::with_envelope_verification`]. Replaces previously
+    /// set `transport`.
+    pub fn with_envelope_verification(self) -> Self {
+        Self {
+            transport: Box::new(DefaultTransport::new().with_envelope_verification()),
+            ..self
+        }
+    }
+}
+
+/// Witnesses and watchers preconfigured for environment. Their OOBIs are
+/// resolved when profile is selected, so they can be used for inception and
+/// added as watchers without providing locations again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub witnesses: Vec<LocationScheme>,
+    #[serde(default)]
+    pub watchers: Vec<LocationScheme>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProfileError {
+    #[error("Can't read profiles file: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid profiles file: {0}")]
+    FormatError(#[from] serde_json::Error),
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
+}
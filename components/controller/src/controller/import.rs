@@ -0,0 +1,157 @@
+use std::io::Read;
+
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event_message::{
+        cesr_adapter::check_canonical,
+        signed_event_message::{Message, Notice},
+    },
+    prefix::IdentifierPrefix,
+};
+
+use crate::{error::ControllerError, known_events::KnownEvents};
+
+use super::Controller;
+
+/// Size of chunks read from imported stream.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Counts of elements processed so far by [`Controller::import_stream`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportProgress {
+    /// Number of bytes read from stream.
+    pub bytes_read: u64,
+    /// Number of processed messages, including receipts and replies.
+    pub processed: usize,
+    /// Key events saved in KEL.
+    pub accepted: usize,
+    /// Key events waiting in escrow, e.g. for missing prior events,
+    /// signatures or receipts.
+    pub escrowed: usize,
+    /// Key events that were already in KEL. They are not processed again.
+    pub duplicates: usize,
+}
+
+/// Event of imported stream, that was escrowed during import.
+type Escrowed = (IdentifierPrefix, u64, SelfAddressingIdentifier);
+
+impl Controller {
+    /// Imports KELs, receipts and replies from CESR stream, e.g. fetched out
+    /// of band. Stream is parsed incrementally and messages are processed in
+    /// order of arrival. Events received before their dependencies are
+    /// escrowed and accepted when dependencies are processed. After whole
+    /// stream is read, escrows are reprocessed once more. `progress` is
+    /// called after each processed message and once more with final counts.
+    pub fn import_stream(
+        &self,
+        mut reader: impl Read,
+        progress: impl Fn(ImportProgress),
+    ) -> Result<ImportProgress, ControllerError> {
+        let mut import = Import {
+            known_events: &self.known_events,
+            progress: ImportProgress::default(),
+            escrowed: vec![],
+        };
+        let mut buf = Vec::new();
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let mut eof = false;
+        loop {
+            let mut consumed = 0;
+            while consumed < buf.len() {
+                let (parsed, rest_len) = match cesrox::parse(&buf[consumed..]) {
+                    // Attachments of parsed message may continue in next chunk,
+                    // so it's taken only if next message is started or stream
+                    // has ended.
+                    Ok((rest, parsed))
+                        if (eof && rest.is_empty())
+                            || rest.first().map_or(false, |b| *b != b'-') =>
+                    {
+                        (parsed, rest.len())
+                    }
+                    _ if !eof => break,
+                    _ => return Err(ControllerError::CesrFormatError),
+                };
+                consumed = buf.len() - rest_len;
+                if self.known_events.strict_canonicalization {
+                    check_canonical(&parsed.payload)?;
+                }
+                import.process(Message::try_from(parsed)?)?;
+                progress(import.progress.clone());
+            }
+            buf.drain(..consumed);
+            if eof {
+                break;
+            }
+            let read = reader.read(&mut chunk)?;
+            eof = read == 0;
+            buf.extend_from_slice(&chunk[..read]);
+            import.progress.bytes_read += read as u64;
+        }
+
+        self.reprocess_escrows()?;
+        import.recount_escrowed();
+        progress(import.progress.clone());
+        Ok(import.progress)
+    }
+}
+
+struct Import<'a> {
+    known_events: &'a KnownEvents,
+    progress: ImportProgress,
+    escrowed: Vec<Escrowed>,
+}
+
+impl<'a> Import<'a> {
+    fn process(&mut self, message: Message) -> Result<(), ControllerError> {
+        if let Message::Notice(Notice::Event(event)) = &message {
+            let event = &event.event_message;
+            let id = event.data.get_prefix();
+            let sn = event.data.get_sn();
+            let digest = event.digest()?;
+            if self.is_accepted(&id, sn, &digest)? {
+                self.progress.processed += 1;
+                self.progress.duplicates += 1;
+                return Ok(());
+            }
+            self.known_events.process(&message)?;
+            if self.is_accepted(&id, sn, &digest)? {
+                self.progress.accepted += 1;
+            } else {
+                self.escrowed.push((id, sn, digest));
+                self.progress.escrowed += 1;
+            }
+        } else {
+            self.known_events.process(&message)?;
+        }
+        self.progress.processed += 1;
+        Ok(())
+    }
+
+    /// Checks which of escrowed events were accepted since they were
+    /// processed.
+    fn recount_escrowed(&mut self) {
+        let escrowed = std::mem::take(&mut self.escrowed);
+        self.escrowed = escrowed
+            .into_iter()
+            .filter(|(id, sn, digest)| !self.is_accepted(id, *sn, digest).unwrap_or(false))
+            .collect();
+        self.progress.accepted += self.progress.escrowed - self.escrowed.len();
+        self.progress.escrowed = self.escrowed.len();
+    }
+
+    fn is_accepted(
+        &self,
+        id: &IdentifierPrefix,
+        sn: u64,
+        digest: &SelfAddressingIdentifier,
+    ) -> Result<bool, ControllerError> {
+        Ok(self
+            .known_events
+            .storage
+            .get_event_at_sn(id, sn)
+            .map(|stored| stored.signed_event_message.event_message.digest())
+            .transpose()?
+            .as_ref()
+            == Some(digest))
+    }
+}
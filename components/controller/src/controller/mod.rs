@@ -1,61 +1,135 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use keri_core::{
-    event_message::signature::Signature,
+    actor::parse_stream,
+    event::sections::threshold::SignatureThreshold,
+    event_message::{
+        signature::Signature,
+        signed_event_message::{Message, Notice},
+    },
     oobi::LocationScheme,
     prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
-    processor::validator::VerificationError,
+    processor::{kel_audit::AuditReport, validator::VerificationError},
     state::IdentifierState,
 };
 
 use crate::{
     communication::Communication,
-    config::ControllerConfig,
+    config::{ControllerConfig, Profile, ProfileError},
     error::ControllerError,
     identifier::{
+        bundle::IdentityBundle,
         mechanics::{query_mailbox::QueryCache, MechanicsError},
         Identifier,
     },
     known_events::KnownEvents,
 };
+pub mod import;
 pub mod verifying;
 
 pub struct Controller {
     pub known_events: Arc<KnownEvents>,
     pub communication: Arc<Communication>,
     pub query_cache: Arc<QueryCache>,
+    profiles: HashMap<String, Profile>,
+    profile: Option<Profile>,
 }
 
 impl Controller {
+    /// Opens controller and resolves initial OOBIs. It blocks current thread
+    /// until OOBIs are resolved, so in async code [`Controller::init`]
+    /// should be used instead, because blocking inside async runtime can
+    /// deadlock it.
     pub fn new(config: ControllerConfig) -> Result<Self, ControllerError> {
+        let initial_oobis = config.initial_oobis.clone();
+        let controller = Self::open(config)?;
+        if !initial_oobis.is_empty() {
+            async_std::task::block_on(controller.setup_witnesses(&initial_oobis))?;
+        }
+        Ok(controller)
+    }
+
+    /// Opens controller and resolves initial OOBIs using transport from
+    /// config, without blocking.
+    pub async fn init(config: ControllerConfig) -> Result<Self, ControllerError> {
+        let initial_oobis = config.initial_oobis.clone();
+        let controller = Self::open(config)?;
+        controller.setup_witnesses(&initial_oobis).await?;
+        Ok(controller)
+    }
+
+    /// Opens databases of controller. Initial OOBIs aren't resolved.
+    fn open(config: ControllerConfig) -> Result<Self, ControllerError> {
         let ControllerConfig {
             db_path,
-            initial_oobis,
             escrow_config,
+            max_clock_drift,
             transport,
             tel_transport,
+            profiles,
+            scheme_priority,
+            oobi_limits,
+            strict_canonicalization,
+            event_shards,
+            initial_oobis: _,
         } = config;
         std::fs::create_dir_all(&db_path).unwrap();
         let mut query_db_path = db_path.clone();
         query_db_path.push("query_cache");
 
-        let events = Arc::new(KnownEvents::new(db_path, escrow_config)?);
+        let events = Arc::new(KnownEvents::new(
+            db_path,
+            escrow_config,
+            max_clock_drift,
+            strict_canonicalization,
+            event_shards,
+        )?);
         let query_cache = Arc::new(QueryCache::new(&query_db_path)?);
         let comm = Arc::new(Communication {
             events: events.clone(),
             transport,
             tel_transport,
+            scheme_priority: RwLock::new(scheme_priority),
+            oobi_limits,
+            witness_health: Default::default(),
         });
 
-        let controller = Self {
+        Ok(Self {
             known_events: events.clone(),
             communication: comm,
             query_cache,
-        };
-        if !initial_oobis.is_empty() {
-            async_std::task::block_on(controller.setup_witnesses(&initial_oobis)).unwrap();
-        }
-        Ok(controller)
+            profiles,
+            profile: None,
+        })
+    }
+
+    /// Selects one of profiles from [`ControllerConfig::profiles`] and
+    /// resolves its witness and watcher locations. It blocks current thread,
+    /// in async code [`Controller::select_profile`] should be used instead.
+    pub fn with_profile(self, name: &str) -> Result<Self, ControllerError> {
+        async_std::task::block_on(self.select_profile(name))
+    }
+
+    /// Selects one of profiles from [`ControllerConfig::profiles`] and
+    /// resolves its witness and watcher locations, without blocking.
+    pub async fn select_profile(mut self, name: &str) -> Result<Self, ControllerError> {
+        let profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ProfileError::UnknownProfile(name.to_string()))?;
+        self.setup_witnesses(&profile.witnesses).await?;
+        self.setup_witnesses(&profile.watchers).await?;
+        self.profile = Some(profile);
+        Ok(self)
+    }
+
+    /// Returns profile selected with [`Controller::with_profile`].
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
     }
 
     pub async fn incept(
@@ -70,12 +144,59 @@ impl Controller {
             .incept(public_keys, next_pub_keys, witnesses, witness_threshold)
     }
 
+    /// Generates inception event with witness threshold that can be
+    /// weighted, e.g. when witnesses aren't equally trusted.
+    pub async fn incept_with_witness_threshold(
+        &self,
+        public_keys: Vec<BasicPrefix>,
+        next_pub_keys: Vec<BasicPrefix>,
+        witnesses: Vec<LocationScheme>,
+        witness_threshold: &SignatureThreshold,
+    ) -> Result<String, MechanicsError> {
+        self.setup_witnesses(&witnesses).await?;
+        self.known_events.incept_with_witness_threshold(
+            public_keys,
+            next_pub_keys,
+            witnesses,
+            witness_threshold,
+        )
+    }
+
+    /// Generates inception event of identifier controlled by several keys,
+    /// e.g. kept on different devices, and signed when `signature_threshold`
+    /// of them agree. Signatures can be collected with
+    /// [`SignatureCollection::inception`] and event finalized with
+    /// [`Controller::finalize_multi_key_incept`].
+    ///
+    /// [`SignatureCollection::inception`]: crate::identifier::multi_key::SignatureCollection::inception
+    pub async fn incept_multi_key(
+        &self,
+        public_keys: Vec<BasicPrefix>,
+        signature_threshold: &SignatureThreshold,
+        next_pub_keys: Vec<BasicPrefix>,
+        next_threshold: &SignatureThreshold,
+        witnesses: Vec<LocationScheme>,
+        witness_threshold: &SignatureThreshold,
+    ) -> Result<String, MechanicsError> {
+        self.setup_witnesses(&witnesses).await?;
+        self.known_events.incept_with_thresholds(
+            public_keys,
+            signature_threshold,
+            next_pub_keys,
+            next_threshold,
+            witnesses,
+            witness_threshold,
+        )
+    }
+
     pub fn finalize_incept(
         &self,
         event: &[u8],
         sig: &SelfSigningPrefix,
     ) -> Result<Identifier, ControllerError> {
         let initialized_id = self.known_events.finalize_inception(event, sig).unwrap();
+        self.query_cache
+            .save_own_identifier(&initialized_id, None)?;
         Ok(Identifier::new(
             initialized_id,
             None,
@@ -85,6 +206,103 @@ impl Controller {
         ))
     }
 
+    /// Returns identifiers incepted or imported with this controller,
+    /// including those from previous runs, in order of inception.
+    pub fn list_own_identifiers(&self) -> Result<Vec<IdentifierPrefix>, ControllerError> {
+        Ok(self
+            .query_cache
+            .own_identifiers()?
+            .into_iter()
+            .map(|(id, _registry_id)| id)
+            .collect())
+    }
+
+    /// Restores handle of identifier incepted or imported with this
+    /// controller, e.g. after restart. Its registry is loaded from database
+    /// and mailbox indexes are used as before. Settings of previous handle,
+    /// like delegation policy, need to be set again.
+    pub fn load_identifier(&self, id: &IdentifierPrefix) -> Result<Identifier, ControllerError> {
+        let (_id, registry_id) = self
+            .query_cache
+            .own_identifiers()?
+            .into_iter()
+            .find(|(own_id, _registry_id)| own_id == id)
+            .ok_or(ControllerError::UnknownIdentifierError)?;
+        Ok(Identifier::new(
+            id.clone(),
+            registry_id,
+            self.known_events.clone(),
+            self.communication.clone(),
+            self.query_cache.clone(),
+        ))
+    }
+
+    /// Loads identifier data exported with `Identifier::export_bundle`. All
+    /// events and replies from bundle are verified before being saved.
+    /// Bundle KEL must contain only events of bundle identifier. Private
+    /// keys need to be provided separately.
+    pub fn import_bundle(&self, bundle: &IdentityBundle) -> Result<Identifier, ControllerError> {
+        let kel = parse_stream::<Message>(
+            bundle.kel.as_bytes(),
+            self.known_events.strict_canonicalization,
+        )?;
+        let kel_ids: Vec<_> = kel
+            .iter()
+            .filter_map(|msg| match msg {
+                Message::Notice(Notice::Event(event)) => {
+                    Some(event.event_message.data.get_prefix())
+                }
+                _ => None,
+            })
+            .collect();
+        if kel_ids.is_empty() || kel_ids.iter().any(|id| id != &bundle.id) {
+            return Err(ControllerError::BundleIdentifierMismatch(bundle.id.clone()));
+        }
+        for msg in &kel {
+            self.known_events.process(msg)?;
+        }
+        self.known_events.process_stream(bundle.oobis.as_bytes())?;
+        if let Some(tel) = &bundle.tel {
+            self.known_events
+                .tel
+                .parse_and_process_tel_stream(tel.as_bytes())?;
+        };
+        if let Some(vc_tels) = &bundle.vc_tels {
+            self.known_events
+                .tel
+                .parse_and_process_tel_stream(vc_tels.as_bytes())?;
+        };
+        for cursor in &bundle.mailbox_cursors {
+            self.query_cache.set_last_asked_index(
+                &bundle.id,
+                &cursor.recipient,
+                &cursor.reminder(),
+            )?;
+        }
+        // Group cursors exported by older versions don't say which group
+        // they belong to, so group mailboxes are read again from the start.
+        for cursor in &bundle.group_mailbox_cursors {
+            if let Some(group) = &cursor.group {
+                self.query_cache.set_last_asked_group_index(
+                    &bundle.id,
+                    group,
+                    &cursor.recipient,
+                    &cursor.reminder(),
+                )?;
+            }
+        }
+        self.query_cache
+            .save_own_identifier(&bundle.id, bundle.registry_id.as_ref())?;
+
+        Ok(Identifier::new(
+            bundle.id.clone(),
+            bundle.registry_id.clone(),
+            self.known_events.clone(),
+            self.communication.clone(),
+            self.query_cache.clone(),
+        ))
+    }
+
     async fn setup_witnesses(&self, oobis: &[LocationScheme]) -> Result<(), MechanicsError> {
         for lc in oobis {
             self.communication.resolve_loc_schema(lc).await?;
@@ -92,6 +310,12 @@ impl Controller {
         Ok(())
     }
 
+    /// Resolves location scheme of actor (e.g. witness or watcher)
+    /// listening at `url`. See [`Communication::introduce`].
+    pub async fn introduce(&self, url: &url::Url) -> Result<LocationScheme, ControllerError> {
+        Ok(self.communication.introduce(url).await?)
+    }
+
     pub fn get_kel_with_receipts(
         &self,
         id: &IdentifierPrefix,
@@ -106,4 +330,25 @@ impl Controller {
     pub fn find_state(&self, id: &IdentifierPrefix) -> Result<IdentifierState, MechanicsError> {
         self.known_events.get_state(id)
     }
+
+    /// Verifies again stored KEL of identifier, e.g. after restoring
+    /// database from backup.
+    pub fn verify_kel_integrity(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<AuditReport, MechanicsError> {
+        self.known_events.verify_kel_integrity(id)
+    }
+
+    /// Processes again all escrowed events, e.g. after missing KEL events
+    /// were saved out of band. Doesn't wait for escrows to be triggered by
+    /// newly received events.
+    pub fn reprocess_escrows(&self) -> Result<(), MechanicsError> {
+        self.known_events.reprocess_escrows()
+    }
+
+    /// Processes again escrowed events of given identifier.
+    pub fn reprocess_escrow_for(&self, id: &IdentifierPrefix) -> Result<(), MechanicsError> {
+        self.known_events.reprocess_escrow_for(id)
+    }
 }
@@ -28,14 +28,9 @@ impl KnownEvents {
         // Split into OOBIs and other data
         let (oobis, to_verify): (Vec<Oobi>, Vec<_>) = data.into_iter().partition_map(|d| {
             let oobi: Result<Oobi, _> = match &d.payload {
-                Payload::JSON(json) => serde_json::from_slice(json)
-                    .map_err(|_e| ControllerError::OtherError("Wrong JSON".to_string())),
-                Payload::CBOR(_) => Err(ControllerError::OtherError(
-                    "CBOR format not implemented yet".to_string(),
-                )),
-                Payload::MGPK(_) => Err(ControllerError::OtherError(
-                    "MGPK format not implemented yet".to_string(),
-                )),
+                Payload::JSON(json) => serde_json::from_slice(json).map_err(ControllerError::from),
+                Payload::CBOR(_) => Err(ControllerError::UnsupportedPayload("CBOR")),
+                Payload::MGPK(_) => Err(ControllerError::UnsupportedPayload("MGPK")),
             };
             match oobi {
                 Ok(oobi) => itertools::Either::Left(oobi),
@@ -3,14 +3,18 @@ use keri_core::{
     database::{redb::RedbError, sled::DbError},
     event_message::cesr_adapter::ParseError,
     oobi::Scheme,
-    prefix::IdentifierPrefix,
+    prefix::{BasicPrefix, IdentifierPrefix},
     processor::validator::VerificationError,
+    transport::TransportError,
 };
 use thiserror::Error;
 
 use crate::{
     communication::SendingError,
-    identifier::{mechanics::MechanicsError, query::WatcherResponseError},
+    config::ProfileError,
+    identifier::{
+        mechanics::MechanicsError, query::WatcherResponseError, signing_request::Operation,
+    },
 };
 
 #[derive(Error, Debug)]
@@ -51,8 +55,8 @@ pub enum ControllerError {
     #[error("Cesr error")]
     CesrFormatError,
 
-    #[error("Wrong signature")]
-    FaultySignature,
+    #[error("Signature {signature_index} doesn't match any of current keys")]
+    SignatureMismatch { signature_index: usize },
 
     #[error("Verification failed for following elements: {0:?}")]
     VerificationError(Vec<(VerificationError, String)>),
@@ -60,12 +64,89 @@ pub enum ControllerError {
     #[error(transparent)]
     TelError(#[from] teliox::error::Error),
 
-    #[error("Error: {0}")]
-    OtherError(String),
+    #[error("Transport error: {0}")]
+    Transport(#[source] TransportError),
+
+    #[error("TEL transport error: {0}")]
+    TelTransport(#[source] TransportError),
+
+    #[error("Event {sn} of {id} is escrowed")]
+    Escrowed { id: IdentifierPrefix, sn: u64 },
+
+    #[error("Event {sn} of {id} is missing receipts of witnesses {missing_witnesses:?}")]
+    NotWitnessed {
+        id: IdentifierPrefix,
+        sn: u64,
+        missing_witnesses: Vec<BasicPrefix>,
+    },
+
+    #[error("Registry of {0} isn't incepted")]
+    RegistryNotIncepted(IdentifierPrefix),
+
+    #[error("Signing request payload doesn't match its digest")]
+    DigestMismatch,
+
+    #[error("Invalid identifier: {0}")]
+    InvalidIdentifier(String),
+
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Invalid UTF-8: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+
+    #[error("{0} payload isn't supported")]
+    UnsupportedPayload(&'static str),
 
     #[error(transparent)]
-    Mechanic(#[from] MechanicsError),
+    Mechanic(MechanicsError),
 
     #[error("Watcher response error: {0}")]
     WatcherResponseError(#[from] WatcherResponseError),
+
+    #[error("Unsupported identity bundle version: {0}")]
+    UnsupportedBundleVersion(u16),
+
+    #[error("Identity bundle KEL doesn't belong to {0}")]
+    BundleIdentifierMismatch(IdentifierPrefix),
+
+    #[error("Key manager is locked")]
+    WalletLocked,
+
+    #[error("Signing threshold isn't reached, keys {0:?} didn't sign yet")]
+    ThresholdNotReached(Vec<BasicPrefix>),
+
+    #[error("Signing request of {0:?} operation can't be finalized here")]
+    UnexpectedSigningRequest(Operation),
+
+    #[error(transparent)]
+    ProfileError(#[from] ProfileError),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Errors that callers may want to handle, like transport failures or
+/// escrowed events, are lifted to their own variants.
+impl From<MechanicsError> for ControllerError {
+    fn from(err: MechanicsError) -> Self {
+        match err {
+            MechanicsError::Transport(e) => Self::Transport(e),
+            MechanicsError::TelTransport(e) => Self::TelTransport(e),
+            MechanicsError::Escrowed { id, sn } => Self::Escrowed { id, sn },
+            MechanicsError::NotWitnessed {
+                id,
+                sn,
+                missing_witnesses,
+            } => Self::NotWitnessed {
+                id,
+                sn,
+                missing_witnesses,
+            },
+            MechanicsError::SignatureMismatch { signature_index } => {
+                Self::SignatureMismatch { signature_index }
+            }
+            e => Self::Mechanic(e),
+        }
+    }
 }
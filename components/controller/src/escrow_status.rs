@@ -0,0 +1,216 @@
+use chrono::{DateTime, Local};
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event::event_data::EventData,
+    event_message::{signature::Nontransferable, signed_event_message::SignedEventMessage},
+    prefix::{BasicPrefix, IdentifierPrefix},
+};
+
+use crate::{
+    controller::Controller, identifier::mechanics::MechanicsError, known_events::KnownEvents,
+};
+
+/// Event waiting in escrow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscrowedEvent {
+    pub sn: u64,
+    pub digest: Option<SelfAddressingIdentifier>,
+    pub escrowed_at: DateTime<Local>,
+}
+
+impl EscrowedEvent {
+    fn new(event: &SignedEventMessage, escrowed_at: DateTime<Local>) -> Self {
+        Self {
+            sn: event.event_message.data.sn,
+            digest: event.event_message.digest().ok(),
+            escrowed_at,
+        }
+    }
+}
+
+/// Event waiting for witness receipts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartiallyWitnessedEvent {
+    pub event: EscrowedEvent,
+    /// Witnesses that already receipted the event.
+    pub receipted_by: Vec<BasicPrefix>,
+    /// Witnesses which receipts are still missing.
+    pub missing_witnesses: Vec<BasicPrefix>,
+}
+
+/// Delegated event waiting for delegator's approval.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelegationPendingEvent {
+    pub event: EscrowedEvent,
+    pub delegator: Option<IdentifierPrefix>,
+}
+
+/// Events of identifier that are kept in escrows, because they couldn't be
+/// accepted into KEL yet.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EscrowStatus {
+    /// Events waiting for events preceding them.
+    pub out_of_order: Vec<EscrowedEvent>,
+    /// Events waiting for more signatures.
+    pub partially_signed: Vec<EscrowedEvent>,
+    pub partially_witnessed: Vec<PartiallyWitnessedEvent>,
+    pub delegation_pending: Vec<DelegationPendingEvent>,
+}
+
+impl EscrowStatus {
+    pub fn is_empty(&self) -> bool {
+        self.out_of_order.is_empty()
+            && self.partially_signed.is_empty()
+            && self.partially_witnessed.is_empty()
+            && self.delegation_pending.is_empty()
+    }
+}
+
+impl KnownEvents {
+    /// Returns events of identifier that are waiting in escrows together with
+    /// time they were escrowed at.
+    pub fn escrow_status(&self, id: &IdentifierPrefix) -> EscrowStatus {
+        let out_of_order = self
+            .out_of_order_escrow
+            .escrowed_out_of_order
+            .get_timestamped(id)
+            .into_iter()
+            .flatten()
+            .map(|(event, escrowed_at)| EscrowedEvent::new(&event, escrowed_at))
+            .collect();
+        let partially_signed = self
+            .partially_signed_escrow
+            .escrowed_partially_signed
+            .get_timestamped(id)
+            .into_iter()
+            .flatten()
+            .map(|(event, escrowed_at)| EscrowedEvent::new(&event, escrowed_at))
+            .collect();
+        let partially_witnessed = self
+            .partially_witnessed_escrow
+            .get_partially_witnessed_of(id)
+            .into_iter()
+            .map(|(event, escrowed_at)| self.partially_witnessed_event(&event, escrowed_at))
+            .collect();
+        // Delegated events are escrowed under delegator's identifier.
+        let delegation_pending = self
+            .delegation_escrow
+            .delegation_escrow
+            .get_all_timestamped()
+            .into_iter()
+            .flatten()
+            .filter(|(event, _)| &event.event_message.data.get_prefix() == id)
+            .map(|(event, escrowed_at)| {
+                let delegator = match &event.event_message.data.event_data {
+                    EventData::Dip(dip) => Some(dip.delegator.clone()),
+                    _ => self.get_state(id).ok().and_then(|state| state.delegator),
+                };
+                DelegationPendingEvent {
+                    event: EscrowedEvent::new(&event, escrowed_at),
+                    delegator,
+                }
+            })
+            .collect();
+
+        EscrowStatus {
+            out_of_order,
+            partially_signed,
+            partially_witnessed,
+            delegation_pending,
+        }
+    }
+
+    /// Returns error explaining why event `sn` of identifier isn't accepted:
+    /// it's missing witness receipts or is kept in other escrow.
+    pub(crate) fn not_accepted_error(&self, id: &IdentifierPrefix, sn: u64) -> MechanicsError {
+        match self
+            .escrow_status(id)
+            .partially_witnessed
+            .into_iter()
+            .find(|escrowed| escrowed.event.sn == sn)
+        {
+            Some(escrowed) => MechanicsError::NotWitnessed {
+                id: id.clone(),
+                sn,
+                missing_witnesses: escrowed.missing_witnesses,
+            },
+            None => MechanicsError::Escrowed { id: id.clone(), sn },
+        }
+    }
+
+    fn partially_witnessed_event(
+        &self,
+        event: &SignedEventMessage,
+        escrowed_at: DateTime<Local>,
+    ) -> PartiallyWitnessedEvent {
+        let id = event.event_message.data.get_prefix();
+        let current_witnesses = || {
+            self.get_state(&id)
+                .map(|state| state.witness_config.witnesses)
+                .unwrap_or_default()
+        };
+        let expected = match &event.event_message.data.event_data {
+            EventData::Icp(icp) => icp.witness_config.initial_witnesses.clone(),
+            EventData::Dip(dip) => dip.inception_data.witness_config.initial_witnesses.clone(),
+            EventData::Rot(rot) | EventData::Drt(rot) => current_witnesses()
+                .into_iter()
+                .filter(|wit| !rot.witness_config.prune.contains(wit))
+                .chain(rot.witness_config.graft.iter().cloned())
+                .collect(),
+            EventData::Ixn(_) => current_witnesses(),
+        };
+
+        let sn = event.event_message.data.sn;
+        let digest = event.event_message.digest().ok();
+        let escrowed_receipts = self
+            .partially_witnessed_escrow
+            .get_escrowed_receipts_of(&id)
+            .into_iter()
+            .filter(|rct| {
+                rct.body.sn == sn && Some(&rct.body.receipted_event_digest) == digest.as_ref()
+            })
+            .flat_map(|rct| rct.signatures);
+        let mut receipted_by: Vec<BasicPrefix> = vec![];
+        for sig in event
+            .witness_receipts
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .chain(escrowed_receipts)
+        {
+            let witnesses: Vec<_> = match sig {
+                Nontransferable::Couplet(couplets) => {
+                    couplets.into_iter().map(|(wit, _sig)| wit).collect()
+                }
+                Nontransferable::Indexed(indexed) => indexed
+                    .iter()
+                    .filter_map(|sig| expected.get(sig.index.current() as usize).cloned())
+                    .collect(),
+            };
+            for witness in witnesses {
+                if !receipted_by.contains(&witness) {
+                    receipted_by.push(witness);
+                }
+            }
+        }
+        let missing_witnesses = expected
+            .into_iter()
+            .filter(|wit| !receipted_by.contains(wit))
+            .collect();
+
+        PartiallyWitnessedEvent {
+            event: EscrowedEvent::new(event, escrowed_at),
+            receipted_by,
+            missing_witnesses,
+        }
+    }
+}
+
+impl Controller {
+    /// Returns events of identifier that are waiting in escrows. Useful to
+    /// find out why event wasn't accepted, e.g. which witness receipts are
+    /// still missing.
+    pub fn escrow_status(&self, id: &IdentifierPrefix) -> EscrowStatus {
+        self.known_events.escrow_status(id)
+    }
+}
@@ -0,0 +1,261 @@
+use keri_core::{
+    event::{event_data::EventData, sections::seal::Seal},
+    event_message::signed_event_message::{Message, Op},
+    oobi::Role,
+    prefix::IdentifierPrefix,
+    query::reply_event::ReplyRoute,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ControllerError, mailbox_updating::MailboxReminder};
+
+use super::Identifier;
+
+/// Version of identity bundle format produced by this library.
+pub const IDENTITY_BUNDLE_VERSION: u16 = 1;
+
+/// Position in witness mailbox up to which messages were already processed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MailboxCursor {
+    /// Identifier of mailbox provider (usually witness).
+    pub recipient: IdentifierPrefix,
+    /// Group which mailbox is read. Missing for own mailbox.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<IdentifierPrefix>,
+    pub receipt: usize,
+    pub multisig: usize,
+    pub delegate: usize,
+}
+
+impl MailboxCursor {
+    fn new(
+        recipient: IdentifierPrefix,
+        group: Option<IdentifierPrefix>,
+        reminder: MailboxReminder,
+    ) -> Self {
+        Self {
+            recipient,
+            group,
+            receipt: reminder.receipt,
+            multisig: reminder.multisig,
+            delegate: reminder.delegate,
+        }
+    }
+
+    pub fn reminder(&self) -> MailboxReminder {
+        MailboxReminder {
+            receipt: self.receipt,
+            multisig: self.multisig,
+            delegate: self.delegate,
+        }
+    }
+}
+
+/// Portable container with everything that is needed to continue managing
+/// identifier on another device, except private keys. Keys need to be
+/// transferred out of band.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentityBundle {
+    /// Bundle format version.
+    pub version: u16,
+    pub id: IdentifierPrefix,
+    pub registry_id: Option<IdentifierPrefix>,
+    /// CESR stream of identifier's KEL with attached receipts.
+    pub kel: String,
+    /// CESR stream of management TEL of identifier's registry.
+    pub tel: Option<String>,
+    /// CESR stream of TELs of credentials issued in identifier's registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vc_tels: Option<String>,
+    /// CESR stream of signed location scheme replies of witnesses, watchers
+    /// and mailboxes, followed by identifier's end role replies.
+    pub oobis: String,
+    /// Cursors of own mailbox.
+    pub mailbox_cursors: Vec<MailboxCursor>,
+    /// Cursors of group mailbox.
+    pub group_mailbox_cursors: Vec<MailboxCursor>,
+}
+
+impl IdentityBundle {
+    pub fn to_json(&self) -> Result<String, ControllerError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(bundle: &str) -> Result<Self, ControllerError> {
+        let bundle: IdentityBundle = serde_json::from_str(bundle)?;
+        if bundle.version != IDENTITY_BUNDLE_VERSION {
+            return Err(ControllerError::UnsupportedBundleVersion(bundle.version));
+        };
+        Ok(bundle)
+    }
+}
+
+impl Identifier {
+    /// Exports identifier's KEL with receipts, management TEL and TELs of
+    /// issued credentials, OOBIs of its witnesses, watchers and mailboxes and
+    /// mailbox cursors as a portable bundle. Private keys are not included.
+    pub fn export_bundle(&self) -> Result<IdentityBundle, ControllerError> {
+        let kel = self
+            .known_events
+            .storage
+            .get_kel_messages_with_receipts_all(&self.id)?
+            .ok_or(ControllerError::UnknownIdentifierError)?
+            .into_iter()
+            .map(|notice| Message::Notice(notice).to_cesr())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+
+        let tel = match &self.registry_id {
+            Some(registry_id) => self
+                .known_events
+                .tel
+                .get_management_tel(registry_id)?
+                .map(|events| {
+                    events
+                        .map(|event| event.serialize())
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .transpose()?
+                .map(|events| String::from_utf8(events.concat()))
+                .transpose()
+                .map_err(|_e| ControllerError::CesrFormatError)?,
+            None => None,
+        };
+        let vc_tels = match &self.registry_id {
+            Some(registry_id) => Some(
+                String::from_utf8(self.export_vc_tels(registry_id)?)
+                    .map_err(|_e| ControllerError::CesrFormatError)?,
+            ),
+            None => None,
+        };
+
+        let oobi_manager = &self.known_events.oobi_manager;
+        let mut end_roles = vec![];
+        for role in [Role::Witness, Role::Watcher, Role::Messagebox, Role::Agent] {
+            end_roles.append(
+                &mut oobi_manager
+                    .get_end_role(&self.id, role)?
+                    .unwrap_or_default(),
+            );
+        }
+        let providers =
+            self.witnesses()
+                .map(IdentifierPrefix::Basic)
+                .chain(end_roles.iter().filter_map(|rpy| {
+                    if let ReplyRoute::EndRoleAdd(er) = rpy.reply.get_route() {
+                        Some(er.eid)
+                    } else {
+                        None
+                    }
+                }));
+        let mut replies = vec![];
+        for eid in providers {
+            replies.append(
+                &mut oobi_manager
+                    .get_signed_loc_scheme(&eid)?
+                    .unwrap_or_default(),
+            );
+        }
+        let oobis = replies
+            .into_iter()
+            .chain(end_roles)
+            .map(|rpy| Message::Op(Op::Reply(rpy)).to_cesr())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+
+        let mailbox_cursors = self
+            .query_cache
+            .all_asked_indexes(&self.id)?
+            .into_iter()
+            .map(|(recipient, reminder)| MailboxCursor::new(recipient, None, reminder))
+            .collect();
+        let group_mailbox_cursors = self
+            .query_cache
+            .all_asked_group_indexes(&self.id)?
+            .into_iter()
+            .map(|(group, recipient, reminder)| {
+                MailboxCursor::new(recipient, Some(group), reminder)
+            })
+            .collect();
+
+        Ok(IdentityBundle {
+            version: IDENTITY_BUNDLE_VERSION,
+            id: self.id.clone(),
+            registry_id: self.registry_id.clone(),
+            kel: String::from_utf8(kel).map_err(|_e| ControllerError::CesrFormatError)?,
+            tel,
+            vc_tels,
+            oobis: String::from_utf8(oobis).map_err(|_e| ControllerError::CesrFormatError)?,
+            mailbox_cursors,
+            group_mailbox_cursors,
+        })
+    }
+
+    /// Returns TEL events of credentials from `registry_id`, which issuance
+    /// or revocation was anchored in identifier's KEL.
+    fn export_vc_tels(&self, registry_id: &IdentifierPrefix) -> Result<Vec<u8>, ControllerError> {
+        let mut vc_ids: Vec<IdentifierPrefix> = vec![];
+        for entry in self.known_events.kel_iter(&self.id) {
+            if let EventData::Ixn(ixn) = entry?.event.event_message.data.event_data {
+                for seal in ixn.data {
+                    match seal {
+                        Seal::Event(seal)
+                            if &seal.prefix != registry_id && !vc_ids.contains(&seal.prefix) =>
+                        {
+                            vc_ids.push(seal.prefix)
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        let tel_storage = &self.known_events.tel.processor.tel_reference;
+        let mut events = vec![];
+        for vc_id in vc_ids {
+            // Event seals can anchor also other data, e.g. delegated events.
+            for event in tel_storage.get_events(&vc_id)? {
+                if &event.event.get_registry_id()? == registry_id {
+                    events.push(event.serialize()?);
+                }
+            }
+        }
+        Ok(events.concat())
+    }
+}
+
+#[test]
+fn test_bundle_version() {
+    let bundle = IdentityBundle {
+        version: IDENTITY_BUNDLE_VERSION,
+        id: "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+            .parse()
+            .unwrap(),
+        registry_id: None,
+        kel: "".to_string(),
+        tel: None,
+        vc_tels: None,
+        oobis: "".to_string(),
+        mailbox_cursors: vec![MailboxCursor {
+            recipient: "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+                .parse()
+                .unwrap(),
+            group: None,
+            receipt: 2,
+            multisig: 0,
+            delegate: 1,
+        }],
+        group_mailbox_cursors: vec![],
+    };
+    let json = bundle.to_json().unwrap();
+    assert_eq!(IdentityBundle::from_json(&json).unwrap(), bundle);
+
+    let newer = IdentityBundle {
+        version: IDENTITY_BUNDLE_VERSION + 1,
+        ..bundle
+    };
+    assert!(matches!(
+        IdentityBundle::from_json(&newer.to_json().unwrap()),
+        Err(ControllerError::UnsupportedBundleVersion(_))
+    ));
+}
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event::KeyEvent,
+    event_message::msg::KeriEvent,
+    mailbox::exchange::ExchangeMessage,
+    prefix::{IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
+    signer::KeyManager,
+};
+
+use crate::{error::ControllerError, mailbox_updating::ActionRequired};
+
+use super::{mechanics::MechanicsError, Identifier};
+
+/// Decision made by [`DelegationPolicy`] about delegation request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Delegating event is signed and published without user interaction,
+    /// if identifier has delegation signer. Otherwise it's returned to user
+    /// like deferred request.
+    Approve,
+    /// Request is ignored.
+    Reject,
+    /// Request is returned to user as [`ActionRequired::DelegationRequest`].
+    Defer,
+}
+
+/// Policy consulted by delegator when delegated event (`dip` or `drt`)
+/// arrives in its mailbox.
+pub trait DelegationPolicy: Send + Sync {
+    fn approve(&self, delegated_event: &KeriEvent<KeyEvent>) -> Decision;
+}
+
+impl<F> DelegationPolicy for F
+where
+    F: Fn(&KeriEvent<KeyEvent>) -> Decision + Send + Sync,
+{
+    fn approve(&self, delegated_event: &KeriEvent<KeyEvent>) -> Decision {
+        self(delegated_event)
+    }
+}
+
+/// Approves events of listed delegates and defers all others.
+pub struct AllowedDelegates(pub Vec<IdentifierPrefix>);
+
+impl DelegationPolicy for AllowedDelegates {
+    fn approve(&self, delegated_event: &KeriEvent<KeyEvent>) -> Decision {
+        if self.0.contains(&delegated_event.data.get_prefix()) {
+            Decision::Approve
+        } else {
+            Decision::Defer
+        }
+    }
+}
+
+/// Delegating event that was signed automatically. Exchange is sent to
+/// delegate when the event is accepted in delegator's KEL, so witness
+/// receipts can be attached to it.
+pub(crate) struct PendingDelegation {
+    delegating_event: KeriEvent<KeyEvent>,
+    exchange: ExchangeMessage,
+    event_signature: IndexedSignature,
+    exchange_signature: SelfSigningPrefix,
+}
+
+impl Identifier {
+    /// Sets policy consulted when delegation request arrives in mailbox.
+    /// Without policy all requests are returned as
+    /// [`ActionRequired::DelegationRequest`].
+    pub fn set_delegation_policy(&mut self, policy: impl DelegationPolicy + 'static) {
+        self.delegation_policy = Some(Arc::new(policy));
+    }
+
+    /// Sets key manager used to sign delegating events of requests approved
+    /// by delegation policy. Requests are returned to user while key manager
+    /// is locked.
+    pub fn set_delegation_signer(&mut self, signer: Arc<dyn KeyManager + Send + Sync>) {
+        self.delegation_signer = Some(signer);
+    }
+
+    /// Consults delegation policy about `delegated_event`. Digests of
+    /// delegating events of approved requests are remembered, so they can be
+    /// signed after mailbox is processed.
+    pub(crate) fn delegation_decision(
+        &self,
+        delegated_event: &KeriEvent<KeyEvent>,
+        delegating_event: &KeriEvent<KeyEvent>,
+    ) -> Result<Decision, MechanicsError> {
+        let decision = self
+            .delegation_policy
+            .as_ref()
+            .map_or(Decision::Defer, |policy| policy.approve(delegated_event));
+        if decision == Decision::Approve {
+            self.approved_delegations
+                .lock()
+                .map_err(|_e| MechanicsError::LockingError)?
+                .push(delegating_event.digest()?);
+        }
+        Ok(decision)
+    }
+
+    /// Signs and publishes delegating events of approved requests. Returns
+    /// actions that still need to be handled by user.
+    pub(crate) async fn apply_delegation_approvals(
+        &mut self,
+        actions: Vec<ActionRequired>,
+    ) -> Result<Vec<ActionRequired>, ControllerError> {
+        let approved: Vec<SelfAddressingIdentifier> = std::mem::take(
+            &mut *self
+                .approved_delegations
+                .lock()
+                .map_err(|_e| MechanicsError::LockingError)?,
+        );
+        let signer = match &self.delegation_signer {
+            Some(signer) if !approved.is_empty() && !signer.is_locked() => signer.clone(),
+            _ => return Ok(actions),
+        };
+
+        let mut remaining = vec![];
+        let mut signed = 0;
+        for action in actions {
+            match action {
+                ActionRequired::DelegationRequest(delegating_event, exchange)
+                    if approved.contains(&delegating_event.digest()?) =>
+                {
+                    let sign = |data: Vec<u8>| {
+                        signer
+                            .sign(&data)
+                            .map(SelfSigningPrefix::Ed25519Sha512)
+                            .map_err(|e| match e {
+                                keri_core::error::Error::WalletLocked => {
+                                    ControllerError::WalletLocked
+                                }
+                                e => e.into(),
+                            })
+                    };
+                    let signature = sign(delegating_event.encode()?)?;
+                    let exchange_signature = sign(exchange.encode()?)?;
+                    self.finalize_key_event(&delegating_event, &signature)?;
+                    let own_index = self.get_index(&delegating_event.data)?;
+                    self.pending_delegations.push(PendingDelegation {
+                        event_signature: IndexedSignature::new_both_same(
+                            signature,
+                            own_index as u16,
+                        ),
+                        delegating_event,
+                        exchange,
+                        exchange_signature,
+                    });
+                    signed += 1;
+                }
+                action => remaining.push(action),
+            }
+        }
+        if signed > 0 {
+            self.notify_witnesses().await?;
+        }
+        Ok(remaining)
+    }
+
+    /// Sends exchanges of automatically signed delegating events, that were
+    /// accepted in KEL, to delegates.
+    pub(crate) async fn send_pending_delegations(&mut self) -> Result<(), ControllerError> {
+        let mut waiting = vec![];
+        let mut result = Ok(());
+        for pending in std::mem::take(&mut self.pending_delegations) {
+            let accepted = self
+                .known_events
+                .storage
+                .get_event_at_sn(&self.id, pending.delegating_event.data.get_sn())
+                .and_then(|ev| ev.signed_event_message.event_message.digest().ok());
+            if result.is_err()
+                || accepted.is_none()
+                || accepted != pending.delegating_event.digest().ok()
+            {
+                waiting.push(pending);
+                continue;
+            }
+            // Keep exchange to retry, if it can't be sent now.
+            if let Err(e) = self.send_pending_delegation(&pending).await {
+                result = Err(e);
+                waiting.push(pending);
+            }
+        }
+        self.pending_delegations = waiting;
+        result
+    }
+
+    async fn send_pending_delegation(
+        &self,
+        pending: &PendingDelegation,
+    ) -> Result<(), ControllerError> {
+        self.finalize_exchange(
+            &pending.exchange.encode()?,
+            pending.exchange_signature.clone(),
+            pending.event_signature.clone(),
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use keri_core::{
+    event_message::signature::{Signature, SignerData},
+    oobi::Role,
+    prefix::IdentifierPrefix,
+    query::reply_event::ReplyRoute,
+};
+
+use crate::{error::ControllerError, identifier::Identifier};
+
+/// Verification status of end role reply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndRoleStatus {
+    /// Reply is signed with identifier's current keys.
+    Fresh,
+    /// Identifier rotated keys since reply was signed, so it should be
+    /// authorized again.
+    Stale,
+}
+
+/// Endpoint provider designated by identifier in end role reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndRoleEntry {
+    pub eid: IdentifierPrefix,
+    pub role: Role,
+    pub status: EndRoleStatus,
+}
+
+impl Identifier {
+    /// Returns watchers currently designated by identifier.
+    pub fn watchers(&self) -> Result<Vec<EndRoleEntry>, ControllerError> {
+        self.end_roles(Role::Watcher)
+    }
+
+    /// Returns mailboxes currently designated by identifier.
+    pub fn mailboxes(&self) -> Result<Vec<EndRoleEntry>, ControllerError> {
+        self.end_roles(Role::Messagebox)
+    }
+
+    /// Returns agents currently designated by identifier.
+    pub fn agents(&self) -> Result<Vec<EndRoleEntry>, ControllerError> {
+        self.end_roles(Role::Agent)
+    }
+
+    /// Returns endpoint providers of given role, that weren't removed by
+    /// later `end_role_cut` reply.
+    pub fn end_roles(&self, role: Role) -> Result<Vec<EndRoleEntry>, ControllerError> {
+        let last_est_sn = self
+            .known_events
+            .storage
+            .get_last_establishment_event_seal(&self.id)
+            .map(|seal| seal.sn);
+        Ok(self
+            .known_events
+            .oobi_manager
+            .get_current_end_roles(&self.id, role.clone())?
+            .into_iter()
+            .filter_map(|rpy| {
+                let ReplyRoute::EndRoleAdd(end_role) = rpy.reply.get_route() else {
+                    return None;
+                };
+                let status = match &rpy.signature {
+                    Signature::Transferable(SignerData::EventSeal(seal), _)
+                        if Some(seal.sn) != last_est_sn =>
+                    {
+                        EndRoleStatus::Stale
+                    }
+                    _ => EndRoleStatus::Fresh,
+                };
+                Some(EndRoleEntry {
+                    eid: end_role.eid,
+                    role: role.clone(),
+                    status,
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,64 @@
+use keri_core::{
+    actor::event_generator,
+    event_message::cesr_adapter::{parse_event_type, EventType},
+    oobi::Role,
+    prefix::{IdentifierPrefix, SelfSigningPrefix},
+    query::reply_event::ReplyRoute,
+};
+
+use crate::identifier::{end_role::EndRoleStatus, Identifier};
+
+use super::MechanicsError;
+
+impl Identifier {
+    /// Generates reply event with `end_role_add` route, that designates
+    /// `agent_id` as identifier's agent. Agent receives identifier's KEL.
+    /// Messages aren't routed through agent yet, as there is no forwarding
+    /// envelope agent could process.
+    pub fn add_agent(&self, agent_id: IdentifierPrefix) -> Result<String, MechanicsError> {
+        String::from_utf8(
+            event_generator::generate_end_role(&self.id, &agent_id, Role::Agent, true).encode()?,
+        )
+        .map_err(|_e| MechanicsError::EventFormatError)
+    }
+
+    /// Generates reply event with `end_role_cut` route.
+    pub fn remove_agent(&self, agent_id: IdentifierPrefix) -> Result<String, MechanicsError> {
+        String::from_utf8(
+            event_generator::generate_end_role(&self.id, &agent_id, Role::Agent, false).encode()?,
+        )
+        .map_err(|_e| MechanicsError::EventFormatError)
+    }
+
+    /// Processes signed `end_role_add` or `end_role_cut` reply and sends it
+    /// to the agent.
+    pub async fn finalize_agent_role(
+        &self,
+        event: &[u8],
+        sig: SelfSigningPrefix,
+    ) -> Result<(), MechanicsError> {
+        let parsed_event =
+            parse_event_type(event).map_err(|_e| MechanicsError::EventFormatError)?;
+        match parsed_event {
+            EventType::Rpy(rpy) => match rpy.get_route() {
+                ReplyRoute::EndRoleAdd(role) | ReplyRoute::EndRoleCut(role)
+                    if role.role == Role::Agent =>
+                {
+                    self.finalize_add_role(&self.id, rpy, vec![sig]).await
+                }
+                _ => Err(MechanicsError::WrongEventTypeError),
+            },
+            _ => Err(MechanicsError::WrongEventTypeError),
+        }
+    }
+
+    /// Returns agent designated by identifier. Agents authorized before last
+    /// key rotation aren't returned.
+    pub fn routing_agent(&self) -> Option<IdentifierPrefix> {
+        self.agents()
+            .ok()?
+            .into_iter()
+            .find(|entry| entry.status == EndRoleStatus::Fresh)
+            .map(|entry| entry.eid)
+    }
+}
@@ -2,6 +2,7 @@ use keri_core::{
     actor::prelude::SelfAddressingIdentifier,
     database::EventDatabase,
     error::Error,
+    event::receipt::Receipt,
     event_message::{
         signature::Nontransferable,
         signed_event_message::{Message, Notice, SignedNontransferableReceipt},
@@ -22,6 +23,8 @@ pub enum BroadcastingError {
 
 impl Identifier {
     /// Send new receipts obtained via [`Self::finalize_query`] to specified witnesses.
+    /// Signatures of all known receipts of the same event are sent to each
+    /// witness in single receipt message.
     /// Returns number of new receipts sent per witness or first error.
     pub async fn broadcast_receipts(
         &mut self,
@@ -35,56 +38,71 @@ impl Identifier {
             .into_iter()
             .flatten()
             .collect::<Vec<_>>();
-        let mut n = 0;
 
+        // Group couplets by receipted event.
+        let mut aggregated: Vec<(Receipt, Vec<(BasicPrefix, SelfSigningPrefix)>)> = vec![];
         for rct in receipts {
-            let rct_digest = rct.body.receipted_event_digest.clone();
             let couplets = self
                 .couplets(&rct)
                 .map_err(|_e| BroadcastingError::MissingEvent {
-                    digest: rct_digest.clone(),
+                    digest: rct.body.receipted_event_digest.clone(),
                 })?;
-
-            for dest_wit_id in dest_wit_ids {
-                for (id, sig) in &couplets {
-                    // Don't send receipt to witness who created it.
-                    // TODO: this only works if the target witness ID is a BasicPrefix.
-                    if let IdentifierPrefix::Basic(dest_wit_id) = dest_wit_id {
-                        if id.eq(dest_wit_id) {
-                            continue;
+            match aggregated
+                .iter_mut()
+                .find(|(body, _)| body.receipted_event_digest == rct.body.receipted_event_digest)
+            {
+                Some((_, known)) => {
+                    for couplet in couplets {
+                        if !known.contains(&couplet) {
+                            known.push(couplet);
                         }
                     }
+                }
+                None => aggregated.push((rct.body, couplets)),
+            }
+        }
 
-                    // Don't send the same receipt twice.
-                    if {
-                        self.broadcasted_rcts.contains(&(
+        let mut n = 0;
+        for (body, couplets) in aggregated {
+            let rct_digest = body.receipted_event_digest.clone();
+            for dest_wit_id in dest_wit_ids {
+                let to_send = couplets
+                    .iter()
+                    .filter(|(id, _sig)| {
+                        // Don't send receipt to witness who created it.
+                        // TODO: this only works if the target witness ID is a BasicPrefix.
+                        let own = matches!(dest_wit_id, IdentifierPrefix::Basic(dest_wit_id) if id == dest_wit_id);
+                        // Don't send the same receipt twice.
+                        let sent = self.broadcasted_rcts.contains(&(
                             rct_digest.clone(),
                             id.clone(),
                             dest_wit_id.clone(),
-                        ))
-                    } {
-                        continue;
-                    }
-                    let rct_to_send = SignedNontransferableReceipt {
-                        body: rct.body.clone(),
-                        signatures: vec![Nontransferable::Couplet(vec![(id.clone(), sig.clone())])],
-                    };
-                    self.communication
-                        .send_message_to(
-                            dest_wit_id.clone(),
-                            Scheme::Http,
-                            Message::Notice(Notice::NontransferableRct(rct_to_send.clone())),
-                        )
-                        .await?;
+                        ));
+                        !own && !sent
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if to_send.is_empty() {
+                    continue;
+                }
 
-                    // Remember event digest and witness ID to avoid sending the same receipt twice.
-                    self.broadcasted_rcts.insert((
-                        rct_digest.clone(),
-                        id.clone(),
+                let rct_to_send = SignedNontransferableReceipt {
+                    body: body.clone(),
+                    signatures: vec![Nontransferable::Couplet(to_send.clone())],
+                };
+                self.communication
+                    .send_message_to(
                         dest_wit_id.clone(),
-                    ));
+                        Scheme::Http,
+                        Message::Notice(Notice::NontransferableRct(rct_to_send)),
+                    )
+                    .await?;
 
-                    n += 1;
+                // Remember event digest and witness ID to avoid sending the same receipt twice.
+                n += to_send.len();
+                for (id, _sig) in to_send {
+                    self.broadcasted_rcts
+                        .insert((rct_digest.clone(), id, dest_wit_id.clone()));
                 }
             }
         }
@@ -1,10 +1,7 @@
 use keri_core::actor::prelude::HashFunctionCode;
 use keri_core::{
     actor::prelude::SerializationFormats,
-    event::{
-        sections::seal::{EventSeal, Seal},
-        KeyEvent,
-    },
+    event::{sections::seal::EventSeal, KeyEvent},
     event_message::msg::KeriEvent,
     mailbox::exchange::{Exchange, ExchangeMessage, ForwardTopic, FwdArgs},
 };
@@ -21,18 +18,19 @@ impl Identifier {
         delegated_event: &KeriEvent<KeyEvent>,
     ) -> Result<(KeriEvent<KeyEvent>, ExchangeMessage), MechanicsError> {
         let delegate = delegated_event.data.get_prefix();
-        let delegated_seal = {
-            let event_digest = delegated_event.digest()?;
-            let sn = delegated_event.data.get_sn();
-            Seal::Event(EventSeal::new(delegate.clone(), sn, event_digest))
-        };
+        let delegated_seal = EventSeal::new(
+            delegate.clone(),
+            delegated_event.data.get_sn(),
+            delegated_event.digest()?,
+        );
         let delegating_event = self
             .known_events
-            .anchor_with_seal(&self.id, &[delegated_seal])?;
+            .anchor_with_seal(&self.id, &[delegated_seal.into()])?;
         let exn_message = Exchange::Fwd {
             args: FwdArgs {
                 recipient_id: delegate,
                 topic: ForwardTopic::Delegate,
+                metadata: None,
             },
             to_forward: delegating_event.clone(),
         }
@@ -0,0 +1,71 @@
+use futures::future::join_all;
+use keri_core::{
+    actor::prelude::SerializationFormats,
+    event::{receipt::Receipt, KeyEvent},
+    event_message::{
+        msg::KeriEvent,
+        signed_event_message::{Message, Notice, SignedTransferableReceipt},
+    },
+    oobi::Scheme,
+    prefix::{IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
+};
+
+use crate::identifier::Identifier;
+
+use super::MechanicsError;
+
+impl Identifier {
+    /// Creates transferable receipt of other identifier's event. `sig` is
+    /// signature of encoded `event` made with one of identifier's current
+    /// keys. Receipt is sealed to identifier's last establishment event,
+    /// saved locally and sent to witnesses of receipted identifier, so its
+    /// KEL needs to be known.
+    pub async fn endorse(
+        &self,
+        event: &KeriEvent<KeyEvent>,
+        sig: SelfSigningPrefix,
+    ) -> Result<SignedTransferableReceipt, MechanicsError> {
+        let encoded = event.encode()?;
+        let key_index = self
+            .known_events
+            .get_state(&self.id)?
+            .current
+            .public_keys
+            .iter()
+            .position(|bp| bp.verify(&encoded, &sig).unwrap_or(false))
+            .ok_or(MechanicsError::SignatureMismatch { signature_index: 0 })?;
+        let seal = self
+            .known_events
+            .storage
+            .get_last_establishment_event_seal(&self.id)
+            .ok_or(MechanicsError::UnknownIdentifierError(self.id.clone()))?;
+
+        let receipt = SignedTransferableReceipt::new(
+            Receipt::new(
+                SerializationFormats::JSON,
+                event.digest()?,
+                event.data.get_prefix(),
+                event.data.get_sn(),
+            ),
+            seal,
+            vec![IndexedSignature::new_both_same(sig, key_index as u16)],
+        );
+        let message = Message::Notice(Notice::TransferableRct(receipt.clone()));
+        self.known_events.process(&message)?;
+
+        let witnesses = self.known_events.find_witnesses_at_event(event)?;
+        let results = join_all(witnesses.into_iter().map(|witness| {
+            self.communication.send_message_to(
+                IdentifierPrefix::Basic(witness),
+                Scheme::Http,
+                message.clone(),
+            )
+        }))
+        .await;
+        for result in results {
+            result?;
+        }
+
+        Ok(receipt)
+    }
+}
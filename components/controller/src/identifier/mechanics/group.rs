@@ -6,8 +6,8 @@ use keri_core::{
         signature::{Signature, SignerData},
         signed_event_message::{Message, Op},
     },
-    mailbox::exchange::{Exchange, ForwardTopic, SignedExchange},
-    prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
+    mailbox::exchange::{Exchange, ExchangeMetadata, ForwardTopic, SignedExchange},
+    prefix::{BasicPrefix, CesrPrimitive, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
 };
 
 use crate::identifier::Identifier;
@@ -29,6 +29,30 @@ impl Identifier {
         initial_witness: Option<Vec<BasicPrefix>>,
         witness_threshold: Option<u64>,
         delegator: Option<IdentifierPrefix>,
+    ) -> Result<(String, Vec<String>), MechanicsError> {
+        self.incept_group_with_metadata(
+            participants,
+            signature_threshold,
+            initial_witness,
+            witness_threshold,
+            delegator,
+            None,
+        )
+    }
+
+    /// Init group identifier, attaching `metadata` to exchange messages sent
+    /// to group participants. Participants get it along with
+    /// [`ActionRequired::MultisigRequest`].
+    ///
+    /// [`ActionRequired::MultisigRequest`]: crate::mailbox_updating::ActionRequired::MultisigRequest
+    pub fn incept_group_with_metadata(
+        &self,
+        participants: Vec<IdentifierPrefix>,
+        signature_threshold: u64,
+        initial_witness: Option<Vec<BasicPrefix>>,
+        witness_threshold: Option<u64>,
+        delegator: Option<IdentifierPrefix>,
+        metadata: Option<ExchangeMetadata>,
     ) -> Result<(String, Vec<String>), MechanicsError> {
         let key_config = self
             .known_events
@@ -64,7 +88,13 @@ impl Identifier {
         let mut exchanges = participants
             .iter()
             .map(|id| -> Result<_, _> {
-                let exn = event_generator::exchange(id, &icp, ForwardTopic::Multisig).encode()?;
+                let exn = event_generator::exchange_with_metadata(
+                    id,
+                    &icp,
+                    ForwardTopic::Multisig,
+                    metadata.clone(),
+                )
+                .encode()?;
                 String::from_utf8(exn).map_err(|_e| MechanicsError::EventFormatError)
             })
             .collect::<Result<Vec<String>, MechanicsError>>()?;
@@ -89,6 +119,110 @@ impl Identifier {
         group_event: &[u8],
         sig: SelfSigningPrefix,
         exchanges: Vec<(Vec<u8>, SelfSigningPrefix)>,
+    ) -> Result<IdentifierPrefix, MechanicsError> {
+        self.finalize_group_event(group_event, sig, exchanges).await
+    }
+
+    /// Rotates keys of group identifier. Group participants need to rotate
+    /// their own keys first, so their current keys match next keys committed
+    /// in group's last establishment event. New group keys and next keys
+    /// digests are taken from the latest known states of this identifier and
+    /// `participants`.
+    ///
+    /// Returns serialized group rot and list of exchange messages to sign.
+    /// Other participants get rotation from mailbox as
+    /// [`ActionRequired::MultisigRequest`] and sign it with
+    /// [`Identifier::finalize_group_rotate`].
+    ///
+    /// [`ActionRequired::MultisigRequest`]: crate::mailbox_updating::ActionRequired::MultisigRequest
+    pub fn rotate_group(
+        &self,
+        group_id: &IdentifierPrefix,
+        participants: Vec<IdentifierPrefix>,
+        signature_threshold: u64,
+        witness_to_add: Vec<BasicPrefix>,
+        witness_to_remove: Vec<BasicPrefix>,
+        witness_threshold: u64,
+    ) -> Result<(String, Vec<String>), MechanicsError> {
+        let group_state = self.known_events.get_state(group_id)?;
+        let committed = group_state.current.next_keys_data.next_keys_hashes();
+
+        let mut members = std::iter::once(&self.id)
+            .chain(participants.iter())
+            .map(|id| -> Result<_, MechanicsError> {
+                let current = self.known_events.get_state(id)?.current;
+                Ok((
+                    current.public_keys,
+                    current.next_keys_data.next_keys_hashes(),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Keep order of keys committed in last establishment event, so
+        // participant's current and prior next key indexes are the same.
+        members.sort_by_key(|(pks, _npks)| {
+            pks.first()
+                .and_then(|pk| {
+                    committed
+                        .iter()
+                        .position(|digest| digest.verify_binding(pk.to_str().as_bytes()))
+                })
+                .unwrap_or(usize::MAX)
+        });
+        let (pks, npks) = members.into_iter().fold(
+            (vec![], vec![]),
+            |(mut pks, mut npks), (mut member_pks, mut member_npks)| {
+                pks.append(&mut member_pks);
+                npks.append(&mut member_npks);
+                (pks, npks)
+            },
+        );
+
+        let threshold = SignatureThreshold::Simple(signature_threshold);
+        let rot = event_generator::rotate_with_next_hashes(
+            group_state,
+            pks,
+            &threshold,
+            npks,
+            &threshold,
+            witness_to_add,
+            witness_to_remove,
+            witness_threshold,
+        )?;
+
+        let serialized_rot = String::from_utf8(rot.encode()?)
+            .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))?;
+
+        let exchanges = participants
+            .iter()
+            .map(|id| -> Result<_, _> {
+                let exn = event_generator::exchange(id, &rot, ForwardTopic::Multisig).encode()?;
+                String::from_utf8(exn).map_err(|_e| MechanicsError::EventFormatError)
+            })
+            .collect::<Result<Vec<String>, MechanicsError>>()?;
+
+        Ok((serialized_rot, exchanges))
+    }
+
+    /// Joins group rotation with signature and verifies them. Exchanges
+    /// generated by [`Identifier::rotate_group`] are sent to witness to be
+    /// forwarded to group participants. Participants who got rotation from
+    /// mailbox don't need to send any exchanges.
+    pub async fn finalize_group_rotate(
+        &mut self,
+        group_event: &[u8],
+        sig: SelfSigningPrefix,
+        exchanges: Vec<(Vec<u8>, SelfSigningPrefix)>,
+    ) -> Result<(), MechanicsError> {
+        self.finalize_group_event(group_event, sig, exchanges)
+            .await?;
+        Ok(())
+    }
+
+    async fn finalize_group_event(
+        &mut self,
+        group_event: &[u8],
+        sig: SelfSigningPrefix,
+        exchanges: Vec<(Vec<u8>, SelfSigningPrefix)>,
     ) -> Result<IdentifierPrefix, MechanicsError> {
         // Join icp event with signature
         let key_event =
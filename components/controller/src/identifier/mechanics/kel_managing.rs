@@ -1,6 +1,13 @@
 use keri_core::{
     actor::{event_generator, prelude::SelfAddressingIdentifier},
-    event::{event_data::EventData, sections::seal::Seal, KeyEvent},
+    event::{
+        event_data::{rotation::RotationEvent, EventData},
+        sections::{
+            seal::{EventSeal, Seal},
+            threshold::SignatureThreshold,
+        },
+        KeyEvent,
+    },
     event_message::{
         cesr_adapter::{parse_event_type, EventType},
         msg::KeriEvent,
@@ -16,6 +23,18 @@ use crate::identifier::Identifier;
 
 use super::MechanicsError;
 
+/// Interaction event anchoring seals, together with its location in
+/// identifier's KEL, so anchored data can be referenced once the event is
+/// finalized.
+#[derive(Debug, Clone)]
+pub struct AnchoringEvent {
+    /// Serialized `ixn` event, to be signed and passed to
+    /// [`Identifier::finalize_anchor`].
+    pub event: String,
+    /// Prefix, sn and digest of the `ixn` event.
+    pub location: EventSeal,
+}
+
 impl Identifier {
     /// Generate and return rotation event for Identifier
     pub async fn rotate(
@@ -27,24 +46,37 @@ impl Identifier {
         witness_to_remove: Vec<BasicPrefix>,
         witness_threshold: u64,
     ) -> Result<String, MechanicsError> {
-        for wit_oobi in &witness_to_add {
-            self.communication.resolve_loc_schema(wit_oobi).await?;
-        }
+        let witnesses_to_add = self.resolve_witnesses_to_add(&witness_to_add).await?;
+        let state = self.known_events.get_state(&self.id)?;
 
-        let witnesses_to_add = witness_to_add
-            .iter()
-            .map(|wit| {
-                if let IdentifierPrefix::Basic(bp) = &wit.eid {
-                    Ok(bp.clone())
-                } else {
-                    Err(MechanicsError::WrongWitnessPrefixError)
-                }
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        event_generator::rotate(
+            state,
+            current_keys,
+            new_next_keys,
+            new_next_threshold,
+            witnesses_to_add,
+            witness_to_remove,
+            witness_threshold,
+        )
+        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+    }
 
+    /// Generate and return rotation event with witness threshold that can be
+    /// weighted, e.g. when witnesses aren't equally trusted. Threshold is
+    /// checked against witness list resulting from the rotation.
+    pub async fn rotate_with_witness_threshold(
+        &self,
+        current_keys: Vec<BasicPrefix>,
+        new_next_keys: Vec<BasicPrefix>,
+        new_next_threshold: u64,
+        witness_to_add: Vec<LocationScheme>,
+        witness_to_remove: Vec<BasicPrefix>,
+        witness_threshold: &SignatureThreshold,
+    ) -> Result<String, MechanicsError> {
+        let witnesses_to_add = self.resolve_witnesses_to_add(&witness_to_add).await?;
         let state = self.known_events.get_state(&self.id)?;
 
-        event_generator::rotate(
+        event_generator::rotate_with_witness_threshold(
             state,
             current_keys,
             new_next_keys,
@@ -56,6 +88,59 @@ impl Identifier {
         .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
     }
 
+    /// Generate and return rotation event that uses only `current_keys`
+    /// subset of next keys committed in previous establishment event, with
+    /// `current_threshold` as new signing threshold. Revealed keys need to
+    /// satisfy previous next threshold. Thresholds can be weighted. Event
+    /// should be signed with one of revealed keys and provided to
+    /// [`Identifier::finalize_rotate`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn rotate_partial(
+        &self,
+        current_keys: Vec<BasicPrefix>,
+        current_threshold: SignatureThreshold,
+        new_next_keys: Vec<BasicPrefix>,
+        new_next_threshold: SignatureThreshold,
+        witness_to_add: Vec<LocationScheme>,
+        witness_to_remove: Vec<BasicPrefix>,
+        witness_threshold: u64,
+    ) -> Result<String, MechanicsError> {
+        let witnesses_to_add = self.resolve_witnesses_to_add(&witness_to_add).await?;
+        let state = self.known_events.get_state(&self.id)?;
+
+        event_generator::partial_rotate(
+            state,
+            current_keys,
+            current_threshold,
+            new_next_keys,
+            new_next_threshold,
+            witnesses_to_add,
+            witness_to_remove,
+            witness_threshold,
+        )
+        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+    }
+
+    async fn resolve_witnesses_to_add(
+        &self,
+        witness_to_add: &[LocationScheme],
+    ) -> Result<Vec<BasicPrefix>, MechanicsError> {
+        for wit_oobi in witness_to_add {
+            self.communication.resolve_loc_schema(wit_oobi).await?;
+        }
+
+        witness_to_add
+            .iter()
+            .map(|wit| {
+                if let IdentifierPrefix::Basic(bp) = &wit.eid {
+                    Ok(bp.clone())
+                } else {
+                    Err(MechanicsError::WrongWitnessPrefixError)
+                }
+            })
+            .collect()
+    }
+
     /// Generate and return interaction event for Identifier
     pub fn anchor(&self, payload: &[SelfAddressingIdentifier]) -> Result<String, MechanicsError> {
         let state = self.known_events.get_state(&self.id)?;
@@ -63,6 +148,21 @@ impl Identifier {
             .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
     }
 
+    /// Generate interaction event anchoring given seals, see [`Seal::digest`],
+    /// [`Seal::event`] and [`Seal::registry`]. Digests of data can be
+    /// passed directly.
+    pub fn anchor_data<S>(&self, seals: &[S]) -> Result<AnchoringEvent, MechanicsError>
+    where
+        S: Clone + Into<Seal>,
+    {
+        let seals: Vec<Seal> = seals.iter().cloned().map(Into::into).collect();
+        let ixn = self.anchor_with_seal(&seals)?;
+        let location = EventSeal::new(ixn.data.prefix.clone(), ixn.data.sn, ixn.digest()?);
+        let event = String::from_utf8(ixn.encode()?)
+            .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))?;
+        Ok(AnchoringEvent { event, location })
+    }
+
     pub fn anchor_with_seal(
         &self,
         seal_list: &[Seal],
@@ -80,25 +180,8 @@ impl Identifier {
         let parsed_event =
             parse_event_type(event).map_err(|_e| MechanicsError::EventFormatError)?;
         if let EventType::KeyEvent(ke) = parsed_event {
-            // Provide kel for new witnesses
-            // TODO  should add to notify_witness instead of sending directly?
-            match &ke.data.event_data {
-                EventData::Rot(rot) | EventData::Drt(rot) => {
-                    let own_kel = self.known_events.find_kel_with_receipts(&self.id).unwrap();
-                    for witness in &rot.witness_config.graft {
-                        let witness_id = IdentifierPrefix::Basic(witness.clone());
-                        for msg in &own_kel {
-                            self.communication
-                                .send_message_to(
-                                    witness_id.clone(),
-                                    Scheme::Http,
-                                    Message::Notice(msg.clone()),
-                                )
-                                .await?;
-                        }
-                    }
-                }
-                _ => (),
+            if let EventData::Rot(rot) | EventData::Drt(rot) = &ke.data.event_data {
+                self.provide_kel_to_new_witnesses(rot).await?;
             };
             self.finalize_key_event(&ke, &sig)?;
             Ok(())
@@ -107,6 +190,29 @@ impl Identifier {
         }
     }
 
+    /// Sends own KEL to witnesses added in rotation, so they can receipt
+    /// it.
+    pub(crate) async fn provide_kel_to_new_witnesses(
+        &self,
+        rot: &RotationEvent,
+    ) -> Result<(), MechanicsError> {
+        // TODO  should add to notify_witness instead of sending directly?
+        let own_kel = self.known_events.find_kel_with_receipts(&self.id).unwrap();
+        for witness in &rot.witness_config.graft {
+            let witness_id = IdentifierPrefix::Basic(witness.clone());
+            for msg in &own_kel {
+                self.communication
+                    .send_message_to(
+                        witness_id.clone(),
+                        Scheme::Http,
+                        Message::Notice(msg.clone()),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn finalize_anchor(
         &mut self,
         event: &[u8],
@@ -131,10 +237,27 @@ impl Identifier {
         event: &KeriEvent<KeyEvent>,
         sig: &SelfSigningPrefix,
     ) -> Result<(), MechanicsError> {
-        let own_index = self.get_index(&event.data).unwrap();
-        let signature = IndexedSignature::new_both_same(sig.clone(), own_index as u16);
+        let signature = match &event.data.event_data {
+            EventData::Rot(rot) | EventData::Drt(rot) => {
+                self.rotation_signature(event, rot, sig)?
+            }
+            _ => {
+                let own_index = self.get_index(&event.data).unwrap();
+                IndexedSignature::new_both_same(sig.clone(), own_index as u16)
+            }
+        };
+
+        self.save_key_event(event, vec![signature])
+    }
 
-        let signed_message = event.sign(vec![signature], None, None);
+    /// Saves own key event signed with `signatures`, and marks it to be
+    /// sent to witnesses.
+    pub(crate) fn save_key_event(
+        &mut self,
+        event: &KeriEvent<KeyEvent>,
+        signatures: Vec<IndexedSignature>,
+    ) -> Result<(), MechanicsError> {
+        let signed_message = event.sign(signatures, None, None);
         self.known_events
             .save(&Message::Notice(Notice::Event(signed_message.clone())))?;
 
@@ -146,6 +269,40 @@ impl Identifier {
         Ok(())
     }
 
+    /// Attaches indexes to rotation event signature. In partial rotation,
+    /// signing key can have different position in new keys list than in
+    /// next keys list committed in previous event.
+    fn rotation_signature(
+        &self,
+        event: &KeriEvent<KeyEvent>,
+        rot: &RotationEvent,
+        sig: &SelfSigningPrefix,
+    ) -> Result<IndexedSignature, MechanicsError> {
+        let data = event.encode()?;
+        let (current_index, key) = rot
+            .key_config
+            .public_keys
+            .iter()
+            .enumerate()
+            .find(|(_, pk)| pk.verify(&data, sig).unwrap_or(false))
+            .ok_or(MechanicsError::SignatureMismatch { signature_index: 0 })?;
+        let prev_next_index = self
+            .known_events
+            .next_keys_hashes(&self.id)?
+            .iter()
+            .position(|digest| digest.verify_binding(key.to_str().as_bytes()))
+            .ok_or(MechanicsError::SignatureMismatch { signature_index: 0 })?;
+        Ok(if current_index == prev_next_index {
+            IndexedSignature::new_both_same(sig.clone(), current_index as u16)
+        } else {
+            IndexedSignature::new_both_diffrent(
+                sig.clone(),
+                current_index as u16,
+                prev_next_index as u16,
+            )
+        })
+    }
+
     /// Helper function for getting the position of identifier's public key in
     /// group's current keys list.
     pub(crate) fn get_index(&self, key_event: &KeyEvent) -> Result<usize, MechanicsError> {
@@ -160,11 +317,20 @@ impl Identifier {
                     .position(|pk| pk.eq(&own_pk))
             }
             EventData::Rot(rot) => {
+                // Participant could have rotated own keys before signing
+                // group rotation, so its current key is in the event.
+                let own_pk = self.known_events.current_public_keys(&self.id)?[0].clone();
                 let own_npk = &self.known_events.next_keys_hashes(&self.id)?[0];
                 rot.key_config
                     .public_keys
                     .iter()
-                    .position(|pk| own_npk.verify_binding(pk.to_str().as_bytes()))
+                    .position(|pk| pk.eq(&own_pk))
+                    .or_else(|| {
+                        rot.key_config
+                            .public_keys
+                            .iter()
+                            .position(|pk| own_npk.verify_binding(pk.to_str().as_bytes()))
+                    })
             }
             EventData::Dip(dip) => {
                 // TODO what if group participant is a group and has more than one
@@ -177,11 +343,20 @@ impl Identifier {
                     .position(|pk| pk.eq(&own_pk))
             }
             EventData::Drt(drt) => {
+                // Participant could have rotated own keys before signing
+                // group rotation, so its current key is in the event.
+                let own_pk = self.known_events.current_public_keys(&self.id)?[0].clone();
                 let own_npk = &self.known_events.next_keys_hashes(&self.id)?[0];
                 drt.key_config
                     .public_keys
                     .iter()
-                    .position(|pk| own_npk.verify_binding(pk.to_str().as_bytes()))
+                    .position(|pk| pk.eq(&own_pk))
+                    .or_else(|| {
+                        drt.key_config
+                            .public_keys
+                            .iter()
+                            .position(|pk| own_npk.verify_binding(pk.to_str().as_bytes()))
+                    })
             }
             EventData::Ixn(_ixn) => {
                 let own_pk = self.known_events.current_public_keys(&self.id)?[0].clone();
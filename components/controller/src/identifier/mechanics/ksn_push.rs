@@ -0,0 +1,167 @@
+use keri_core::{
+    actor::prelude::{HashFunctionCode, SerializationFormats},
+    event_message::{
+        cesr_adapter::{parse_event_type, EventType},
+        signed_event_message::{Message, Op},
+    },
+    oobi::Scheme,
+    prefix::{IdentifierPrefix, SelfSigningPrefix},
+    query::{
+        key_state_notice::KeyStateNotice,
+        reply_event::{ReplyEvent, ReplyRoute, SignedReply},
+    },
+    signer::KeyManager,
+};
+
+use crate::{error::ControllerError, identifier::Identifier};
+
+use super::MechanicsError;
+
+/// Result of pushing key state notice to single peer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KsnDeliveryStatus {
+    Delivered,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KsnDelivery {
+    pub peer: IdentifierPrefix,
+    pub status: KsnDeliveryStatus,
+}
+
+impl Identifier {
+    /// Sets peers that will be notified about identifier's key state by
+    /// [`Identifier::finalize_push_ksn`]. Location of every peer needs to be
+    /// known, e.g. from resolved OOBI. Peers are saved in database, so they
+    /// are restored with [`Controller::load_identifier`].
+    ///
+    /// If query signer is set (see [`Identifier::set_query_signer`]), key
+    /// state is pushed automatically when rotation is accepted after
+    /// collecting witness receipts from mailbox.
+    ///
+    /// [`Controller::load_identifier`]: crate::controller::Controller::load_identifier
+    pub fn set_ksn_peers(&mut self, peers: Vec<IdentifierPrefix>) -> Result<(), ControllerError> {
+        self.query_cache.save_ksn_peers(&self.id, &peers)?;
+        self.ksn_peers = peers;
+        Ok(())
+    }
+
+    pub fn ksn_peers(&self) -> &[IdentifierPrefix] {
+        &self.ksn_peers
+    }
+
+    /// Returns delivery statuses of the last automatic key state push.
+    pub fn ksn_deliveries(&self) -> &[KsnDelivery] {
+        &self.ksn_deliveries
+    }
+
+    /// Generates reply event with key state notice of identifier's current
+    /// state. Should be called after last rotation was fully receipted.
+    pub fn key_state_notice(&self) -> Result<String, MechanicsError> {
+        let state = self.known_events.get_state(&self.id)?;
+        if state.sn != self.cached_state.sn {
+            return Err(self
+                .known_events
+                .not_accepted_error(&self.id, self.cached_state.sn));
+        }
+        let timestamp = self.known_events.storage.clock.now().into();
+        let rpy = ReplyEvent::new_reply(
+            ReplyRoute::Ksn(
+                self.id.clone(),
+                KeyStateNotice::new_ksn(state, SerializationFormats::JSON, timestamp),
+            ),
+            HashFunctionCode::Blake3_256,
+            SerializationFormats::JSON,
+        );
+        String::from_utf8(rpy.encode()?).map_err(|_e| MechanicsError::EventFormatError)
+    }
+
+    /// Sends identifier's KEL and signed key state notice to configured
+    /// peers. Returns delivery status of every peer, failed delivery doesn't
+    /// stop sending to the rest of peers.
+    pub async fn finalize_push_ksn(
+        &self,
+        event: &[u8],
+        sig: SelfSigningPrefix,
+    ) -> Result<Vec<KsnDelivery>, ControllerError> {
+        let rpy = match parse_event_type(event).map_err(|_e| MechanicsError::EventFormatError)? {
+            EventType::Rpy(rpy) => match rpy.get_route() {
+                ReplyRoute::Ksn(signer, ksn)
+                    if signer == self.id && ksn.state.prefix == self.id =>
+                {
+                    rpy
+                }
+                _ => return Err(MechanicsError::WrongEventTypeError.into()),
+            },
+            _ => return Err(MechanicsError::WrongEventTypeError.into()),
+        };
+        let seal = self
+            .known_events
+            .storage
+            .get_last_establishment_event_seal(&self.id)
+            .ok_or(MechanicsError::UnknownIdentifierError(self.id.clone()))?;
+        let signature = self.sign_indexed(event, sig, None)?;
+        let signed_rpy = Message::Op(Op::Reply(SignedReply::new_trans(
+            rpy,
+            seal,
+            vec![signature],
+        )));
+        let kel = self
+            .known_events
+            .find_kel_with_receipts(&self.id)
+            .ok_or(MechanicsError::UnknownIdentifierError(self.id.clone()))?;
+
+        let mut deliveries = vec![];
+        for peer in &self.ksn_peers {
+            let mut status = KsnDeliveryStatus::Delivered;
+            for msg in kel
+                .iter()
+                .cloned()
+                .map(Message::Notice)
+                .chain(std::iter::once(signed_rpy.clone()))
+            {
+                if let Err(e) = self
+                    .communication
+                    .send_message_to(peer.clone(), Scheme::Http, msg)
+                    .await
+                {
+                    status = KsnDeliveryStatus::Failed(e.to_string());
+                    break;
+                }
+            }
+            deliveries.push(KsnDelivery {
+                peer: peer.clone(),
+                status,
+            });
+        }
+        Ok(deliveries)
+    }
+
+    /// Pushes key state to configured peers, if last establishment event
+    /// changed since `previous` and it is a rotation. Notice is signed with
+    /// `signer`. Nothing is pushed while some of identifier's events wait
+    /// for receipts, because notice would be outdated.
+    pub(crate) async fn push_ksn_after_rotation<K: KeyManager + ?Sized>(
+        &mut self,
+        previous: Option<u64>,
+        signer: &K,
+    ) -> Result<(), ControllerError> {
+        let last_establishment = match self
+            .known_events
+            .storage
+            .get_last_establishment_event_seal(&self.id)
+        {
+            Some(seal) => seal.sn,
+            None => return Ok(()),
+        };
+        let rotated = last_establishment > 0 && Some(last_establishment) != previous;
+        let receipted = self.known_events.get_state(&self.id)?.sn == self.cached_state.sn;
+        if rotated && receipted && !self.ksn_peers.is_empty() {
+            let ksn = self.key_state_notice()?;
+            let signature = signer.key_type().signature(signer.sign(ksn.as_bytes())?);
+            self.ksn_deliveries = self.finalize_push_ksn(ksn.as_bytes(), signature).await?;
+        }
+        Ok(())
+    }
+}
@@ -9,11 +9,18 @@ use keri_core::{
     event_message::signed_event_message::{
         Message, Notice, SignedEventMessage, SignedNontransferableReceipt,
     },
-    mailbox::{exchange::ForwardTopic, MailboxResponse},
+    mailbox::{
+        exchange::{ExchangeMetadata, ForwardTopic},
+        MailboxResponse,
+    },
     prefix::IdentifierPrefix,
 };
 
-use crate::{error::ControllerError, identifier::Identifier, mailbox_updating::ActionRequired};
+use crate::{
+    error::ControllerError,
+    identifier::{delegation_policy::Decision, subscription::IdentifierEvent, Identifier},
+    mailbox_updating::ActionRequired,
+};
 
 use super::{MechanicsError, ResponseProcessingError};
 
@@ -28,13 +35,14 @@ impl Identifier {
         let req = if from_who == about_who {
             // process own mailbox
             let req = self.process_own_mailbox(res)?;
-            self.query_cache.update_last_asked_index(recipient, res)?;
+            self.query_cache
+                .update_last_asked_index(&self.id, recipient, res)?;
             req
         } else {
             // process group mailbox
             let group_req = self.process_group_mailbox(res, about_who).await?;
             self.query_cache
-                .update_last_asked_group_index(recipient, res)?;
+                .update_last_asked_group_index(&self.id, about_who, recipient, res)?;
             group_req
         };
         Ok(req)
@@ -45,6 +53,10 @@ impl Identifier {
             .process(&Message::Notice(Notice::NontransferableRct(
                 receipt.clone(),
             )))?;
+        self.known_events.subscriptions.publish(
+            &receipt.body.prefix,
+            IdentifierEvent::ReceiptReceived(receipt.clone()),
+        );
         Ok(())
     }
 
@@ -57,10 +69,21 @@ impl Identifier {
                 .map_err(ResponseProcessingError::Receipts)?;
         }
 
+        let now = self.known_events.storage.clock.now().into();
         Iterator::chain(
             mb.multisig
                 .iter()
-                .map(|event| self.process_own_multisig(event)),
+                .map(|event| -> Result<_, MechanicsError> {
+                    let metadata = mb
+                        .get_multisig_metadata(&event.event_message.digest()?)
+                        .cloned();
+                    // Expired requests are skipped, so they aren't signed.
+                    if metadata.as_ref().map_or(false, |md| md.is_expired(now)) {
+                        return Ok(None);
+                    }
+                    self.process_own_multisig(event, metadata).map(Some)
+                })
+                .filter_map(Result::transpose),
             mb.delegate
                 .iter()
                 .map(|del_event| self.process_own_delegate(del_event))
@@ -95,6 +118,7 @@ impl Identifier {
     fn process_own_multisig(
         &self,
         event: &SignedEventMessage,
+        metadata: Option<ExchangeMetadata>,
     ) -> Result<ActionRequired, MechanicsError> {
         self.known_events
             .process(&Message::Notice(Notice::Event(event.clone())))
@@ -102,8 +126,13 @@ impl Identifier {
         let event = event.event_message.clone();
         let receipient = event.data.get_prefix();
         // Construct exn message (will be stored in group identidfier mailbox)
-        let exn = event_generator::exchange(&receipient, &event, ForwardTopic::Multisig);
-        Ok(ActionRequired::MultisigRequest(event, exn))
+        let exn = event_generator::exchange_with_metadata(
+            &receipient,
+            &event,
+            ForwardTopic::Multisig,
+            metadata.clone(),
+        );
+        Ok(ActionRequired::MultisigRequest(event, exn, metadata))
     }
 
     /// If leader and event is fully signed publish event to witness.
@@ -180,10 +209,14 @@ impl Identifier {
                     .process(&Message::Notice(Notice::Event(event_to_confirm.clone())))
                     .map_err(ResponseProcessingError::Delegate)?;
                 let (delegating_event, exn) = self.delegate(&event_to_confirm.event_message)?;
-                Ok(Some(ActionRequired::DelegationRequest(
-                    delegating_event,
-                    exn,
-                )))
+                match self
+                    .delegation_decision(&event_to_confirm.event_message, &delegating_event)?
+                {
+                    Decision::Reject => Ok(None),
+                    Decision::Approve | Decision::Defer => Ok(Some(
+                        ActionRequired::DelegationRequest(delegating_event, exn),
+                    )),
+                }
             }
         }
     }
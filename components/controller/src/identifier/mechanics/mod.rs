@@ -1,15 +1,24 @@
-use keri_core::{oobi::error::OobiError, prefix::IdentifierPrefix, transport::TransportError};
+use keri_core::{
+    event_message::cesr_adapter::ParseError,
+    oobi::error::OobiError,
+    prefix::{BasicPrefix, IdentifierPrefix},
+    transport::TransportError,
+};
 
 use crate::communication::SendingError;
 
 use self::{broadcast::BroadcastingError, query_mailbox::ResponseProcessingError};
 
+pub mod agent_configuration;
 pub mod broadcast;
 pub mod delegate;
+pub mod endorse;
 pub mod group;
 pub mod kel_managing;
+pub mod ksn_push;
 mod mailbox;
 pub mod notify_witness;
+pub mod oobi_reply;
 pub mod query_mailbox;
 pub mod tel_managing;
 pub mod watcher_configuration;
@@ -22,6 +31,9 @@ pub enum MechanicsError {
     #[error("Transport error: {0}")]
     Transport(#[from] TransportError),
 
+    #[error("TEL transport error: {0}")]
+    TelTransport(#[source] TransportError),
+
     #[error("Can't lock")]
     LockingError,
 
@@ -40,8 +52,21 @@ pub enum MechanicsError {
     #[error("Not group participant")]
     NotGroupParticipantError,
 
-    #[error("Error: {0}")]
-    OtherError(String),
+    #[error("Event {sn} of {id} is escrowed")]
+    Escrowed { id: IdentifierPrefix, sn: u64 },
+
+    #[error("Event {sn} of {id} is missing receipts of witnesses {missing_witnesses:?}")]
+    NotWitnessed {
+        id: IdentifierPrefix,
+        sn: u64,
+        missing_witnesses: Vec<BasicPrefix>,
+    },
+
+    #[error("Can't parse response: {0}")]
+    ResponseParseError(#[from] ParseError),
+
+    #[error(transparent)]
+    TelError(#[from] teliox::error::Error),
 
     #[error("Wrong event type")]
     WrongEventTypeError,
@@ -49,6 +74,9 @@ pub enum MechanicsError {
     #[error("Wrong event format")]
     EventFormatError,
 
+    #[error("Signature {signature_index} doesn't match any of identifier's current keys")]
+    SignatureMismatch { signature_index: usize },
+
     #[error("Inception event error: {0}")]
     InceptionError(String),
 
@@ -60,4 +88,10 @@ pub enum MechanicsError {
 
     #[error("Broadcasting error: {0}")]
     BroadcastingError(#[from] BroadcastingError),
+
+    #[error("Provenance from watcher {0} is invalid or not about requested data")]
+    InvalidProvenance(IdentifierPrefix),
+
+    #[error("Weighted witness threshold can't be adjusted to witness changes")]
+    WeightedWitnessThreshold,
 }
@@ -1,37 +1,76 @@
 use futures::future::join_all;
+use keri_core::event_message::signed_event_message::SignedEventMessage;
 
 use crate::identifier::Identifier;
 
 use super::MechanicsError;
 
+/// How [`Identifier::notify_witnesses`] waits for witnesses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PublishingMode {
+    /// Event and receipts are sent to all witnesses before return.
+    #[default]
+    All,
+    /// Event is sent to all witnesses concurrently, and method returns once
+    /// receipts of witnesses satisfying witness threshold are collected.
+    /// Remaining witnesses are notified in background.
+    Quorum,
+}
+
 impl Identifier {
+    /// Sets how [`Identifier::notify_witnesses`] waits for witnesses.
+    pub fn set_publishing_mode(&mut self, mode: PublishingMode) {
+        self.publishing_mode = mode;
+    }
+
     pub async fn notify_witnesses(&mut self) -> Result<usize, MechanicsError> {
-        let mut n = 0;
-        let to_notify = self.to_notify.iter().filter_map(|ev| {
-            // Elect the leader
-            // Leader is identifier with minimal index among all participants who
-            // sign event. He will send message to witness.
-            let id_idx = self.get_index(&ev.event_message.data).unwrap_or_default();
-            let min_sig_idx =
-                ev.signatures
-                    .iter()
-                    .map(|at| at.index.current())
-                    .min()
-                    .expect("event should have at least one signature") as usize;
-            if min_sig_idx == id_idx {
-                let witnesses = self
-                    .known_events
-                    .find_witnesses_at_event(&ev.event_message)
-                    .expect("Can't find witnesses");
-                n += 1;
-                Some(self.communication.publish(witnesses, &ev))
-            } else {
-                None
+        let to_notify = self
+            .to_notify
+            .iter()
+            .filter(|ev| self.is_publishing_leader(ev))
+            .collect::<Vec<_>>();
+        let n = to_notify.len();
+        match self.publishing_mode {
+            PublishingMode::All => {
+                join_all(to_notify.into_iter().map(|ev| {
+                    let witnesses = self
+                        .known_events
+                        .find_witnesses_at_event(&ev.event_message)
+                        .expect("Can't find witnesses");
+                    self.communication.publish(witnesses, ev)
+                }))
+                .await;
             }
-        });
-        join_all(to_notify).await;
+            PublishingMode::Quorum => {
+                let this = &*self;
+                join_all(to_notify.into_iter().map(|ev| async move {
+                    let witness_config = this
+                        .known_events
+                        .find_witness_config_at_event(&ev.event_message)?;
+                    this.communication
+                        .publish_until_receipted(witness_config, ev)
+                        .await
+                }))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            }
+        }
         self.to_notify.clear();
 
         Ok(n)
     }
+
+    /// Elect the leader. Leader is identifier with minimal index among all
+    /// participants who sign event. He will send message to witness.
+    fn is_publishing_leader(&self, ev: &SignedEventMessage) -> bool {
+        let id_idx = self.get_index(&ev.event_message.data).unwrap_or_default();
+        let min_sig_idx = ev
+            .signatures
+            .iter()
+            .map(|at| at.index.current())
+            .min()
+            .expect("event should have at least one signature") as usize;
+        min_sig_idx == id_idx
+    }
 }
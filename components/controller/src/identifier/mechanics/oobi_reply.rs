@@ -0,0 +1,91 @@
+use keri_core::actor::prelude::{HashFunctionCode, SerializationFormats};
+use keri_core::{
+    event_message::{
+        cesr_adapter::{parse_event_type, EventType},
+        signed_event_message::{Message, Op},
+    },
+    oobi::{error::OobiError, LocationScheme, Scheme},
+    prefix::{IdentifierPrefix, SelfSigningPrefix},
+    query::reply_event::{ReplyEvent, ReplyRoute, SignedReply},
+};
+use url::Url;
+
+use crate::{error::ControllerError, identifier::Identifier};
+
+use super::MechanicsError;
+
+impl Identifier {
+    /// Generates reply event with `loc_scheme` route, that advertises
+    /// endpoint hosted by identifier's controller itself (for example in
+    /// direct mode). Reply needs to be signed and provided to
+    /// [`Identifier::finalize_loc_scheme_reply`].
+    pub fn generate_loc_scheme_reply(
+        &self,
+        url: Url,
+        scheme: Scheme,
+    ) -> Result<String, MechanicsError> {
+        let loc_scheme = LocationScheme::new(self.id.clone(), scheme, url);
+        let reply = ReplyEvent::new_reply(
+            ReplyRoute::LocScheme(loc_scheme),
+            HashFunctionCode::Blake3_256,
+            SerializationFormats::JSON,
+        );
+        String::from_utf8(reply.encode()?).map_err(|_e| MechanicsError::EventFormatError)
+    }
+
+    /// Signs location scheme reply with identifier's last establishment
+    /// event and saves it, so it can be served by
+    /// [`Identifier::oobi_response`]. Signature is indexed with position of
+    /// the key that made it, see [`Identifier::sign_indexed`].
+    pub fn finalize_loc_scheme_reply(
+        &self,
+        event: &[u8],
+        sig: SelfSigningPrefix,
+    ) -> Result<SignedReply, ControllerError> {
+        let rpy = match parse_event_type(event).map_err(|_e| MechanicsError::EventFormatError)? {
+            EventType::Rpy(rpy) => rpy,
+            _ => return Err(MechanicsError::WrongEventTypeError.into()),
+        };
+        match rpy.get_route() {
+            ReplyRoute::LocScheme(loc) if loc.eid == self.id => (),
+            _ => return Err(MechanicsError::WrongEventTypeError.into()),
+        };
+        let signed_reply = match &self.id {
+            IdentifierPrefix::Basic(bp) => SignedReply::new_nontrans(rpy, bp.clone(), sig),
+            _ => SignedReply::new_trans(
+                rpy,
+                self.known_events
+                    .storage
+                    .get_last_establishment_event_seal(&self.id)
+                    .ok_or(MechanicsError::UnknownIdentifierError(self.id.clone()))?,
+                vec![self.sign_indexed(event, sig, None)?],
+            ),
+        };
+        self.known_events
+            .process(&Message::Op(Op::Reply(signed_reply.clone())))?;
+        Ok(signed_reply)
+    }
+
+    /// Returns response to `GET /oobi/{eid}` request, i.e. signed location
+    /// scheme replies of identifier in CESR stream. Doesn't depend on any
+    /// HTTP framework, so it can be mounted in application that hosts
+    /// controller's endpoint. Transferable identifier's KEL needs to be
+    /// known by resolver to verify replies, so it should be provided
+    /// separately, for example by witnesses.
+    pub fn oobi_response(&self, eid: &IdentifierPrefix) -> Result<Vec<u8>, MechanicsError> {
+        if eid != &self.id {
+            return Err(MechanicsError::UnknownIdentifierError(eid.clone()));
+        }
+        let replies = self
+            .known_events
+            .oobi_manager
+            .get_signed_loc_scheme(&self.id)
+            .map_err(OobiError::from)?
+            .unwrap_or_default();
+        Ok(replies
+            .into_iter()
+            .map(|rpy| Message::Op(Op::Reply(rpy)).to_cesr())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat())
+    }
+}
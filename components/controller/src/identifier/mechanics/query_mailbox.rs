@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 use keri_core::actor::prelude::HashFunctionCode;
 use keri_core::{
@@ -7,16 +7,17 @@ use keri_core::{
     oobi::Scheme,
     prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
     query::{
-        mailbox::{MailboxQuery, MailboxRoute, QueryArgsMbx},
+        mailbox::{MailboxQuery, MailboxRoute, QueryArgsMbx, QueryTopics},
         query_event::SignedQuery,
     },
+    signer::KeyManager,
 };
 use rusqlite::{params, Connection};
 
 use crate::{
     communication::SendingError,
     error::ControllerError,
-    identifier::Identifier,
+    identifier::{subscription::IdentifierEvent, Identifier},
     mailbox_updating::{ActionRequired, MailboxReminder},
 };
 
@@ -48,10 +49,11 @@ impl Identifier {
 
                 let reminder = if identifier == &self.id {
                     // request own mailbox
-                    self.query_cache.last_asked_index(&recipient)
+                    self.query_cache.last_asked_index(&self.id, &recipient)
                 } else {
                     // request group mailbox
-                    self.query_cache.last_asked_group_index(&recipient)
+                    self.query_cache
+                        .last_asked_group_index(&self.id, identifier, &recipient)
                 }?;
 
                 Ok(MailboxQuery::new_query(
@@ -64,6 +66,8 @@ impl Identifier {
                             // who will get the query
                             src: recipient,
                             topics: reminder.to_query_topics(),
+                            resume: false,
+                            limit: self.mailbox_page_size,
                         },
                         reply_route: "".to_string(),
                     },
@@ -74,40 +78,135 @@ impl Identifier {
             .collect()
     }
 
+    /// Queries mailbox of `identifier` at `witnesses`, signing queries with
+    /// `key_manager`. Shortcut for [`Identifier::query_mailbox`] followed by
+    /// [`Identifier::finalize_query_mailbox`], when keys are at hand. Next
+    /// pages of mailbox are queried and key state notices pushed with
+    /// `key_manager` too.
+    pub async fn query_mailbox_with<K: KeyManager>(
+        &mut self,
+        identifier: &IdentifierPrefix,
+        witnesses: &[BasicPrefix],
+        key_manager: &K,
+    ) -> Result<Vec<ActionRequired>, ControllerError> {
+        let queries = self
+            .query_mailbox(identifier, witnesses)?
+            .into_iter()
+            .map(|qry| -> Result<_, ControllerError> {
+                let signature = key_manager
+                    .key_type()
+                    .signature(key_manager.sign(&qry.encode()?)?);
+                Ok((qry, signature))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.finalize_query_mailbox_pages(queries, Some(key_manager))
+            .await
+    }
+
+    /// Sets maximal number of messages witness returns in single mailbox
+    /// response. Rest of messages is returned in next pages.
+    pub fn set_mailbox_page_size(&mut self, page_size: usize) {
+        self.mailbox_page_size = Some(page_size);
+    }
+
+    /// Sets key manager used by [`Identifier::finalize_query_mailbox`] to
+    /// sign queries for next pages of mailbox. Without it only the first
+    /// page is retrieved, and query for the next one is returned as
+    /// [`ActionRequired::NextMailboxPage`].
+    pub fn set_query_signer(&mut self, signer: Arc<dyn KeyManager + Send + Sync>) {
+        self.query_signer = Some(signer);
+    }
+
     /// Joins query events with their signatures, sends it to witness and
     /// process its response. If user action is needed to finalize process,
-    /// returns proper notification.
+    /// returns proper notification. If response is truncated and query
+    /// signer is set, next pages are queried until mailbox is exhausted.
+    /// Otherwise query for the next page is returned as
+    /// [`ActionRequired::NextMailboxPage`], to be signed and finalized like
+    /// the first one. If query signer is set and receipts complete a
+    /// rotation, key state is pushed to peers set with
+    /// [`Identifier::set_ksn_peers`].
     pub async fn finalize_query_mailbox(
         &mut self,
         queries: Vec<(MailboxQuery, SelfSigningPrefix)>,
     ) -> Result<Vec<ActionRequired>, ControllerError> {
+        let signer = self.query_signer.clone();
+        self.finalize_query_mailbox_pages(queries, signer.as_deref())
+            .await
+    }
+
+    async fn finalize_query_mailbox_pages<K: KeyManager + ?Sized>(
+        &mut self,
+        queries: Vec<(MailboxQuery, SelfSigningPrefix)>,
+        signer: Option<&K>,
+    ) -> Result<Vec<ActionRequired>, ControllerError> {
+        let last_establishment = self
+            .known_events
+            .storage
+            .get_last_establishment_event_seal(&self.id)
+            .map(|seal| seal.sn);
         let mut actions = Vec::new();
-        for (qry, sig) in queries {
-            let args = qry.get_args();
-            let (recipient, about_who, from_who) =
-                (args.src.clone(), Some(&args.i), Some(&args.pre));
-            match self.handle_management_query(&qry, sig).await? {
-                PossibleResponse::Mbx(mbx) => {
-                    // only process if we actually asked about mailbox
-                    if let (Some(from_who), Some(about_who)) =
-                        (from_who.as_ref(), about_who.as_ref())
-                    {
-                        actions.append(
-                            &mut self
+        for query in queries {
+            let mut page = Some(query);
+            while let Some((qry, sig)) = page.take() {
+                let args = qry.get_args();
+                let (recipient, about_who, from_who) =
+                    (args.src.clone(), Some(&args.i), Some(&args.pre));
+                match self.handle_management_query(&qry, sig).await? {
+                    PossibleResponse::Mbx(mbx) => {
+                        // only process if we actually asked about mailbox
+                        if let (Some(from_who), Some(about_who)) =
+                            (from_who.as_ref(), about_who.as_ref())
+                        {
+                            let new_actions = self
                                 .mailbox_response(&recipient, from_who, about_who, &mbx)
-                                .await?,
-                        );
-                        let witnesses = self
-                            .witnesses()
-                            .map(IdentifierPrefix::Basic)
-                            .collect::<Vec<_>>();
-                        self.broadcast_receipts(&witnesses)
-                            .await
-                            .map_err(MechanicsError::BroadcastingError)?;
+                                .await?;
+                            // Delegations approved by policy don't need user action.
+                            let new_actions = self.apply_delegation_approvals(new_actions).await?;
+                            for action in &new_actions {
+                                self.known_events.subscriptions.publish(
+                                    &self.id,
+                                    IdentifierEvent::ActionRequired(action.clone()),
+                                );
+                            }
+                            actions.extend(new_actions);
+                            let witnesses = self
+                                .witnesses()
+                                .map(IdentifierPrefix::Basic)
+                                .collect::<Vec<_>>();
+                            self.broadcast_receipts(&witnesses)
+                                .await
+                                .map_err(MechanicsError::BroadcastingError)?;
+                            self.send_pending_delegations().await?;
+                        }
+                        match (mbx.next, signer) {
+                            // Stop if witness doesn't move forward, to not loop forever.
+                            (Some(next), _) if next == args.topics => {}
+                            (Some(next), Some(signer)) => {
+                                let qry = next_page_query(&args, next);
+                                let signature =
+                                    signer.key_type().signature(signer.sign(&qry.encode()?)?);
+                                page = Some((qry, signature));
+                            }
+                            (Some(next), None) => {
+                                let action =
+                                    ActionRequired::NextMailboxPage(next_page_query(&args, next));
+                                self.known_events.subscriptions.publish(
+                                    &self.id,
+                                    IdentifierEvent::ActionRequired(action.clone()),
+                                );
+                                actions.push(action);
+                            }
+                            (None, _) => {}
+                        }
                     }
-                }
-                _ => panic!("Unexpected response"),
-            };
+                    _ => panic!("Unexpected response"),
+                };
+            }
+        }
+        if let Some(signer) = signer {
+            self.push_ksn_after_rotation(last_establishment, signer)
+                .await?;
         }
 
         Ok(actions)
@@ -139,65 +238,88 @@ impl Identifier {
     }
 }
 
-/// A structure that stores the state of already retrieved mailbox events.
+/// Generates query for mailbox page starting at `next` topic indexes,
+/// returned in truncated response to query of `args`.
+fn next_page_query(args: &QueryArgsMbx, next: QueryTopics) -> MailboxQuery {
+    MailboxQuery::new_query(
+        MailboxRoute::Mbx {
+            args: QueryArgsMbx {
+                topics: next,
+                // Indexes of next page are already resolved by witness.
+                resume: false,
+                ..args.clone()
+            },
+            reply_route: "".to_string(),
+        },
+        SerializationFormats::JSON,
+        HashFunctionCode::Blake3_256,
+    )
+}
+
+/// A structure that stores the state of already retrieved mailbox events
+/// and list of identifiers managed by controller.
 pub struct QueryCache {
     connection: Connection,
-    own_table: String,
-    groups_table: String,
 }
 
 impl QueryCache {
     pub fn new(db_file: &Path) -> Result<Self, rusqlite::Error> {
         let conn = Connection::open(db_file)?;
-        let own_table_name = "own_index".to_string();
-        let group_table_name = "group_index".to_string();
 
-        // Create the table if it doesn't exist
+        // Indexes are kept separately for every identifier that asks
+        // (`owner`) and every mailbox it asks about (`about`), which is the
+        // owner itself or group it participates in. Indexes of older
+        // versions, that were shared by all identifiers, are not used.
         conn.execute(
-            &format!(
-                "CREATE TABLE IF NOT EXISTS {} (
-                identifier TEXT PRIMARY KEY,
+            "CREATE TABLE IF NOT EXISTS mailbox_index (
+                owner TEXT NOT NULL,
+                about TEXT NOT NULL,
+                recipient TEXT NOT NULL,
                 receipt INTEGER NOT NULL,
                 multisig INTEGER NOT NULL,
-                delegate INTEGER NOT NULL
+                delegate INTEGER NOT NULL,
+                PRIMARY KEY (owner, about, recipient)
             )",
-                own_table_name
-            ),
             [],
         )?;
 
-        // Create the table if it doesn't exist
         conn.execute(
-            &format!(
-                "CREATE TABLE IF NOT EXISTS {} (
+            "CREATE TABLE IF NOT EXISTS own_identifiers (
                 identifier TEXT PRIMARY KEY,
-                receipt INTEGER NOT NULL,
-                multisig INTEGER NOT NULL,
-                delegate INTEGER NOT NULL
+                registry_id TEXT
             )",
-                group_table_name
-            ),
             [],
         )?;
 
-        Ok(Self {
-            connection: conn,
-            own_table: own_table_name,
-            groups_table: group_table_name,
-        })
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ksn_peers (
+                identifier TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                peer TEXT NOT NULL,
+                PRIMARY KEY (identifier, position)
+            )",
+            [],
+        )?;
+
+        Ok(Self { connection: conn })
     }
 
     fn load_mailbox_remainder(
         &self,
-        table_name: &str,
-        id: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        about: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
     ) -> Result<MailboxReminder, ControllerError> {
-        let mut stmt = self.connection.prepare(&format!(
-            "SELECT receipt, multisig, delegate FROM {} WHERE identifier = ?1",
-            table_name
-        ))?;
+        let mut stmt = self.connection.prepare(
+            "SELECT receipt, multisig, delegate FROM mailbox_index
+            WHERE owner = ?1 AND about = ?2 AND recipient = ?3",
+        )?;
 
-        let mut rows = stmt.query(params![id.to_string()])?;
+        let mut rows = stmt.query(params![
+            owner.to_string(),
+            about.to_string(),
+            recipient.to_string()
+        ])?;
 
         // Fetch the first row (assuming there is only one match)
         if let Some(row) = rows.next()? {
@@ -215,48 +337,66 @@ impl QueryCache {
         }
     }
 
+    /// Returns indexes of already retrieved messages from `owner`'s own
+    /// mailbox provided by `recipient`.
     pub fn last_asked_index(
         &self,
-        id: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
     ) -> Result<MailboxReminder, ControllerError> {
-        self.load_mailbox_remainder(&self.own_table, id)
+        self.load_mailbox_remainder(owner, owner, recipient)
     }
 
+    /// Returns indexes of messages from mailbox of `group` provided by
+    /// `recipient`, already retrieved by `owner`.
     pub fn last_asked_group_index(
         &self,
-        id: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        group: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
     ) -> Result<MailboxReminder, ControllerError> {
-        self.load_mailbox_remainder(&self.groups_table, id)
+        self.load_mailbox_remainder(owner, group, recipient)
     }
 
-    pub fn update_mailbox_remainder(
+    fn update_mailbox_remainder(
         &self,
-        table_name: &str,
-        key: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        about: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
         res: &MailboxResponse,
     ) -> Result<(), rusqlite::Error> {
+        if let Some(next) = &res.next {
+            // Truncated response points exactly to where next page starts.
+            return self.set_mailbox_remainder(
+                owner,
+                about,
+                recipient,
+                &MailboxReminder {
+                    receipt: next.receipt,
+                    multisig: next.multisig,
+                    delegate: next.delegate,
+                },
+            );
+        }
         self.connection.execute(
-            &format!(
-                "INSERT OR IGNORE INTO {} (identifier, receipt, multisig, delegate)
-        VALUES (?, 0, 0, 0);",
-                table_name
-            ),
-            params![key.to_string()],
+            "INSERT OR IGNORE INTO mailbox_index
+            (owner, about, recipient, receipt, multisig, delegate)
+            VALUES (?1, ?2, ?3, 0, 0, 0);",
+            params![owner.to_string(), about.to_string(), recipient.to_string()],
         )?;
         self.connection.execute(
-            &format!(
-                "UPDATE {} 
-         SET receipt = receipt + ?1, 
-             multisig = multisig + ?2, 
-             delegate = delegate + ?3 
-         WHERE identifier = ?4",
-                table_name,
-            ),
+            "UPDATE mailbox_index
+            SET receipt = receipt + ?4,
+                multisig = multisig + ?5,
+                delegate = delegate + ?6
+            WHERE owner = ?1 AND about = ?2 AND recipient = ?3",
             params![
+                owner.to_string(),
+                about.to_string(),
+                recipient.to_string(),
                 res.receipt.len(),
                 res.multisig.len(),
                 res.delegate.len(),
-                key.to_string()
             ],
         )?;
         Ok(())
@@ -264,18 +404,202 @@ impl QueryCache {
 
     pub fn update_last_asked_index(
         &self,
-        key: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
         res: &MailboxResponse,
     ) -> Result<(), rusqlite::Error> {
-        self.update_mailbox_remainder(&self.own_table, key, res)
+        self.update_mailbox_remainder(owner, owner, recipient, res)
     }
 
     pub fn update_last_asked_group_index(
         &self,
-        id: &IdentifierPrefix,
+        owner: &IdentifierPrefix,
+        group: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
         res: &MailboxResponse,
     ) -> Result<(), rusqlite::Error> {
-        self.update_mailbox_remainder(&self.groups_table, id, res)
+        self.update_mailbox_remainder(owner, group, recipient, res)
+    }
+
+    /// Returns mailboxes asked by `owner` (as `(about, recipient)` pairs)
+    /// with indexes of already retrieved messages.
+    fn load_all_mailbox_remainders(
+        &self,
+        owner: &IdentifierPrefix,
+    ) -> Result<Vec<(IdentifierPrefix, IdentifierPrefix, MailboxReminder)>, ControllerError> {
+        let mut stmt = self.connection.prepare(
+            "SELECT about, recipient, receipt, multisig, delegate FROM mailbox_index
+            WHERE owner = ?1",
+        )?;
+        let mut rows = stmt.query(params![owner.to_string()])?;
+
+        let parse = |id: String| {
+            id.parse::<IdentifierPrefix>()
+                .map_err(|_e| ControllerError::InvalidIdentifier(id))
+        };
+        let mut out = vec![];
+        while let Some(row) = rows.next()? {
+            out.push((
+                parse(row.get(0)?)?,
+                parse(row.get(1)?)?,
+                MailboxReminder {
+                    receipt: row.get(2)?,
+                    multisig: row.get(3)?,
+                    delegate: row.get(4)?,
+                },
+            ));
+        }
+        Ok(out)
+    }
+
+    fn set_mailbox_remainder(
+        &self,
+        owner: &IdentifierPrefix,
+        about: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
+        reminder: &MailboxReminder,
+    ) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO mailbox_index
+            (owner, about, recipient, receipt, multisig, delegate)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+            params![
+                owner.to_string(),
+                about.to_string(),
+                recipient.to_string(),
+                reminder.receipt,
+                reminder.multisig,
+                reminder.delegate
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns indexes of already retrieved messages from `owner`'s own
+    /// mailbox for every recipient that was asked.
+    pub fn all_asked_indexes(
+        &self,
+        owner: &IdentifierPrefix,
+    ) -> Result<Vec<(IdentifierPrefix, MailboxReminder)>, ControllerError> {
+        Ok(self
+            .load_all_mailbox_remainders(owner)?
+            .into_iter()
+            .filter(|(about, _recipient, _reminder)| about == owner)
+            .map(|(_about, recipient, reminder)| (recipient, reminder))
+            .collect())
+    }
+
+    /// Returns indexes of group mailbox messages already retrieved by
+    /// `owner`, as `(group, recipient, indexes)`, for every recipient that
+    /// was asked.
+    pub fn all_asked_group_indexes(
+        &self,
+        owner: &IdentifierPrefix,
+    ) -> Result<Vec<(IdentifierPrefix, IdentifierPrefix, MailboxReminder)>, ControllerError> {
+        Ok(self
+            .load_all_mailbox_remainders(owner)?
+            .into_iter()
+            .filter(|(about, _recipient, _reminder)| about != owner)
+            .collect())
+    }
+
+    pub fn set_last_asked_index(
+        &self,
+        owner: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
+        reminder: &MailboxReminder,
+    ) -> Result<(), rusqlite::Error> {
+        self.set_mailbox_remainder(owner, owner, recipient, reminder)
+    }
+
+    pub fn set_last_asked_group_index(
+        &self,
+        owner: &IdentifierPrefix,
+        group: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
+        reminder: &MailboxReminder,
+    ) -> Result<(), rusqlite::Error> {
+        self.set_mailbox_remainder(owner, group, recipient, reminder)
+    }
+
+    /// Saves identifier managed by controller, with its registry, if there
+    /// is any. Registry of already saved identifier is replaced, but not
+    /// removed if `registry_id` is missing.
+    pub fn save_own_identifier(
+        &self,
+        id: &IdentifierPrefix,
+        registry_id: Option<&IdentifierPrefix>,
+    ) -> Result<(), rusqlite::Error> {
+        self.connection.execute(
+            "INSERT INTO own_identifiers (identifier, registry_id) VALUES (?1, ?2)
+            ON CONFLICT (identifier)
+            DO UPDATE SET registry_id = COALESCE(excluded.registry_id, registry_id);",
+            params![id.to_string(), registry_id.map(|ri| ri.to_string())],
+        )?;
+        Ok(())
+    }
+
+    /// Returns identifiers managed by controller with their registries, in
+    /// order of saving.
+    pub fn own_identifiers(
+        &self,
+    ) -> Result<Vec<(IdentifierPrefix, Option<IdentifierPrefix>)>, ControllerError> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT identifier, registry_id FROM own_identifiers ORDER BY rowid")?;
+        let mut rows = stmt.query([])?;
+
+        let parse = |id: String| {
+            id.parse::<IdentifierPrefix>()
+                .map_err(|_e| ControllerError::InvalidIdentifier(id))
+        };
+        let mut out = vec![];
+        while let Some(row) = rows.next()? {
+            let registry_id: Option<String> = row.get(1)?;
+            out.push((parse(row.get(0)?)?, registry_id.map(parse).transpose()?));
+        }
+        Ok(out)
+    }
+
+    /// Replaces peers notified about key state of `id`.
+    pub fn save_ksn_peers(
+        &self,
+        id: &IdentifierPrefix,
+        peers: &[IdentifierPrefix],
+    ) -> Result<(), rusqlite::Error> {
+        let tx = self.connection.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM ksn_peers WHERE identifier = ?1",
+            params![id.to_string()],
+        )?;
+        for (position, peer) in peers.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO ksn_peers (identifier, position, peer) VALUES (?1, ?2, ?3)",
+                params![id.to_string(), position, peer.to_string()],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Returns peers notified about key state of `id`, in order of saving.
+    pub fn ksn_peers(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<IdentifierPrefix>, ControllerError> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT peer FROM ksn_peers WHERE identifier = ?1 ORDER BY position")?;
+        let mut rows = stmt.query(params![id.to_string()])?;
+
+        let mut out = vec![];
+        while let Some(row) = rows.next()? {
+            let peer: String = row.get(0)?;
+            out.push(
+                peer.parse::<IdentifierPrefix>()
+                    .map_err(|_e| ControllerError::InvalidIdentifier(peer))?,
+            );
+        }
+        Ok(out)
     }
 }
 
@@ -288,14 +612,111 @@ fn test_query_cache() {
     let id: IdentifierPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
         .parse()
         .unwrap();
-    let ind = mc.last_asked_index(&id).unwrap();
+    let owner: IdentifierPrefix = "EGhf8TN8UUIPCK5aHaU3qTGjCBTvWUL2ahhtT3xFflBs"
+        .parse()
+        .unwrap();
+    let other_owner: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    let ind = mc.last_asked_index(&owner, &id).unwrap();
     assert_eq!(ind.receipt, 0);
     assert_eq!(ind.multisig, 0);
     assert_eq!(ind.delegate, 0);
 
-    mc.update_last_asked_index(&id, &mr).unwrap();
-    let ind = mc.last_asked_index(&id).unwrap();
+    mc.update_last_asked_index(&owner, &id, &mr).unwrap();
+    let ind = mc.last_asked_index(&owner, &id).unwrap();
     assert_eq!(ind.receipt, 1);
     assert_eq!(ind.multisig, 0);
     assert_eq!(ind.delegate, 0);
+
+    // Indexes of other identifiers and groups are separate.
+    assert_eq!(mc.last_asked_index(&other_owner, &id).unwrap().receipt, 0);
+    mc.update_last_asked_group_index(&other_owner, &owner, &id, &mr)
+        .unwrap();
+    assert_eq!(mc.last_asked_index(&owner, &id).unwrap().receipt, 1);
+    assert_eq!(mc.all_asked_indexes(&other_owner).unwrap(), vec![]);
+    assert_eq!(
+        mc.all_asked_group_indexes(&other_owner).unwrap(),
+        vec![(
+            owner.clone(),
+            id.clone(),
+            MailboxReminder {
+                receipt: 1,
+                multisig: 0,
+                delegate: 0
+            }
+        )]
+    );
+
+    // Truncated response sets indexes to its next page.
+    let page = MailboxResponse {
+        next: Some(QueryTopics {
+            receipt: 5,
+            multisig: 2,
+            ..Default::default()
+        }),
+        ..mr
+    };
+    mc.update_last_asked_index(&owner, &id, &page).unwrap();
+    assert_eq!(
+        mc.last_asked_index(&owner, &id).unwrap(),
+        MailboxReminder {
+            receipt: 5,
+            multisig: 2,
+            delegate: 0
+        }
+    );
+}
+
+#[test]
+fn test_own_identifiers() {
+    let tmp = tempfile::NamedTempFile::new().unwrap();
+    let mc = QueryCache::new(Path::new(tmp.path())).unwrap();
+    let id: IdentifierPrefix = "EGhf8TN8UUIPCK5aHaU3qTGjCBTvWUL2ahhtT3xFflBs"
+        .parse()
+        .unwrap();
+    let other_id: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    mc.save_own_identifier(&id, None).unwrap();
+    mc.save_own_identifier(&other_id, None).unwrap();
+    // Registry is set later and kept when identifier is saved again.
+    mc.save_own_identifier(&id, Some(&other_id)).unwrap();
+    mc.save_own_identifier(&id, None).unwrap();
+
+    let mc = QueryCache::new(Path::new(tmp.path())).unwrap();
+    assert_eq!(
+        mc.own_identifiers().unwrap(),
+        vec![(id, Some(other_id.clone())), (other_id, None)]
+    );
+}
+
+#[test]
+fn test_ksn_peers() {
+    let tmp = tempfile::NamedTempFile::new().unwrap();
+    let mc = QueryCache::new(Path::new(tmp.path())).unwrap();
+    let id: IdentifierPrefix = "EGhf8TN8UUIPCK5aHaU3qTGjCBTvWUL2ahhtT3xFflBs"
+        .parse()
+        .unwrap();
+    let peer: IdentifierPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let other_peer: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    assert_eq!(mc.ksn_peers(&id).unwrap(), vec![]);
+    mc.save_ksn_peers(&id, &[other_peer.clone(), peer.clone()])
+        .unwrap();
+
+    // Peers are kept after restart, in the same order.
+    let mc = QueryCache::new(Path::new(tmp.path())).unwrap();
+    assert_eq!(
+        mc.ksn_peers(&id).unwrap(),
+        vec![other_peer.clone(), peer.clone()]
+    );
+    assert_eq!(mc.ksn_peers(&peer).unwrap(), vec![]);
+
+    // Saving again replaces previous peers.
+    mc.save_ksn_peers(&id, &[peer.clone()]).unwrap();
+    assert_eq!(mc.ksn_peers(&id).unwrap(), vec![peer]);
 }
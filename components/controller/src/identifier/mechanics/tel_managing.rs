@@ -1,5 +1,5 @@
 use keri_core::{
-    event::sections::seal::{EventSeal, Seal},
+    event::sections::seal::EventSeal,
     prefix::{IdentifierPrefix, SelfSigningPrefix},
 };
 use teliox::{
@@ -26,12 +26,8 @@ impl Identifier {
             vec![],
         )?;
         let id = vcp.get_prefix();
-        let seal = Seal::Event(EventSeal::new(
-            vcp.get_prefix(),
-            vcp.get_sn(),
-            vcp.get_digest()?,
-        ));
-        let ixn = self.anchor_with_seal(&[seal]).unwrap();
+        let seal = EventSeal::new(vcp.get_prefix(), vcp.get_sn(), vcp.get_digest()?);
+        let ixn = self.anchor_with_seal(&[seal.into()])?;
         let source_seal = EventSourceSeal {
             sn: ixn.data.sn,
             digest: ixn.digest()?,
@@ -45,6 +41,7 @@ impl Identifier {
 
         tel.processor.process(verifiable_event)?;
         self.registry_id = Some(id.clone());
+        self.query_cache.save_own_identifier(&self.id, Some(&id))?;
 
         Ok((id, encoded))
     }
@@ -69,9 +66,9 @@ impl Identifier {
             for event in &to_notify {
                 self.communication
                     .tel_transport
-                    .send_tel_event(event.clone(), location.clone())
+                    .send_tel_event(location.clone(), event.clone())
                     .await
-                    .map_err(|e| MechanicsError::OtherError(e.to_string()))?;
+                    .map_err(MechanicsError::TelTransport)?;
             }
         }
         Ok(())
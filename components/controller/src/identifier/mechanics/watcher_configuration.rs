@@ -29,7 +29,7 @@ impl Identifier {
         .map_err(|_e| MechanicsError::EventFormatError)
     }
 
-    async fn finalize_add_role(
+    pub(super) async fn finalize_add_role(
         &self,
         signer_prefix: &IdentifierPrefix,
         event: ReplyEvent,
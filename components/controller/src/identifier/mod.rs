@@ -9,19 +9,36 @@ use keri_core::{
     event_message::signed_event_message::{Notice, SignedEventMessage},
     oobi::Oobi,
     prefix::{BasicPrefix, IdentifierPrefix},
-    state::IdentifierState,
+    processor::event_storage::KelEntry,
+    signer::KeyManager,
+    state::{IdentifierState, StateTransition},
 };
 use teliox::state::{vc_state::TelState, ManagerTelState};
 
 use crate::{communication::Communication, error::ControllerError, known_events::KnownEvents};
 
-use self::mechanics::{query_mailbox::QueryCache, MechanicsError};
+use self::{
+    delegation_policy::{DelegationPolicy, PendingDelegation},
+    mechanics::{
+        ksn_push::KsnDelivery, notify_witness::PublishingMode, query_mailbox::QueryCache,
+        MechanicsError,
+    },
+};
 
+pub mod bundle;
+pub mod delegation_policy;
+pub mod end_role;
 pub mod mechanics;
+pub mod multi_key;
 pub mod nontransferable;
+pub mod provenance;
 pub mod query;
+pub mod rotation_readiness;
 pub mod signing;
+pub mod signing_request;
+pub mod subscription;
 pub mod tel;
+pub mod witness_health;
 
 pub struct Identifier {
     id: IdentifierPrefix,
@@ -36,6 +53,21 @@ pub struct Identifier {
     cached_state: IdentifierState,
     pub(crate) broadcasted_rcts: HashSet<(SelfAddressingIdentifier, BasicPrefix, IdentifierPrefix)>,
     cached_identifiers: Mutex<HashMap<IdentifierPrefix, IdentifierState>>,
+    /// Peers notified about identifier's key state changes.
+    ksn_peers: Vec<IdentifierPrefix>,
+    /// Delivery statuses of the last automatic key state push.
+    ksn_deliveries: Vec<KsnDelivery>,
+    delegation_policy: Option<Arc<dyn DelegationPolicy>>,
+    delegation_signer: Option<Arc<dyn KeyManager + Send + Sync>>,
+    /// Digests of delegating events approved by delegation policy, that
+    /// wait to be signed.
+    approved_delegations: Mutex<Vec<SelfAddressingIdentifier>>,
+    pending_delegations: Vec<PendingDelegation>,
+    /// Maximal number of messages in single mailbox response.
+    mailbox_page_size: Option<usize>,
+    /// Key manager used to sign queries for next pages of mailbox.
+    query_signer: Option<Arc<dyn KeyManager + Send + Sync>>,
+    publishing_mode: PublishingMode,
 }
 
 impl Identifier {
@@ -71,6 +103,7 @@ impl Identifier {
                 IdentifierState::default()
             }
         };
+        let ksn_peers = db.ksn_peers(&id).unwrap_or_default();
         Self {
             id,
             known_events,
@@ -81,6 +114,15 @@ impl Identifier {
             registry_id,
             broadcasted_rcts: HashSet::new(),
             cached_identifiers: Mutex::new(HashMap::new()),
+            ksn_peers,
+            ksn_deliveries: vec![],
+            delegation_policy: None,
+            delegation_signer: None,
+            approved_delegations: Mutex::new(vec![]),
+            pending_delegations: vec![],
+            mailbox_page_size: None,
+            query_signer: None,
+            publishing_mode: PublishingMode::default(),
         }
     }
 
@@ -109,6 +151,15 @@ impl Identifier {
         self.known_events.get_state(id)
     }
 
+    /// Returns key state transitions of identifier, made by its
+    /// establishment events, with time when they were first seen.
+    pub fn state_history(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<StateTransition>, MechanicsError> {
+        Ok(self.known_events.state_history(id)?)
+    }
+
     pub fn find_management_tel_state(
         &self,
         id: &IdentifierPrefix,
@@ -135,10 +186,6 @@ impl Identifier {
             .into_iter()
     }
 
-    pub fn watchers(&self) -> Result<Vec<IdentifierPrefix>, ControllerError> {
-        self.known_events.get_watchers(&self.id)
-    }
-
     /// Returns own identifier accepted Key Event Log
     pub fn get_own_kel(&self) -> Option<Vec<Notice>> {
         self.known_events.find_kel_with_receipts(&self.id)
@@ -149,6 +196,23 @@ impl Identifier {
         self.known_events.find_kel_with_receipts(id)
     }
 
+    /// Returns identifier accepted Key Event Log with receipts, as parsed
+    /// events instead of notices.
+    pub fn get_kel_typed(&self, id: &IdentifierPrefix) -> Result<Vec<KelEntry>, MechanicsError> {
+        Ok(self.known_events.find_kel_typed(id)?)
+    }
+
+    /// Streams identifier accepted Key Event Log with receipts from
+    /// database, without loading it whole into memory.
+    pub fn kel_iter(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> impl Iterator<Item = Result<KelEntry, MechanicsError>> {
+        self.known_events
+            .kel_iter(id)
+            .map(|entry| entry.map_err(MechanicsError::from))
+    }
+
     pub fn get_last_establishment_event_seal(&self) -> Result<EventSeal, ControllerError> {
         self.known_events
             .storage
@@ -0,0 +1,247 @@
+use keri_core::{
+    event::{
+        event_data::EventData,
+        sections::key_config::{KeyConfig, NextKeysData},
+        KeyEvent,
+    },
+    event_message::{
+        cesr_adapter::{parse_event_type, EventType},
+        msg::KeriEvent,
+    },
+    prefix::{BasicPrefix, CesrPrimitive, IndexedSignature, SelfSigningPrefix},
+    signer::KeyManager,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{controller::Controller, error::ControllerError};
+
+use super::{mechanics::MechanicsError, signing_request::Operation, Identifier};
+
+/// Signatures of key event collected from several keys of single controller,
+/// e.g. kept on different devices, until signing threshold of identifier is
+/// reached. Unlike group multisig, signatures aren't exchanged through
+/// witnesses: collection can be serialized, passed between devices directly
+/// and finalized on any of them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignatureCollection {
+    pub operation: Operation,
+    /// Event to be signed.
+    pub payload: String,
+    /// Keys allowed to sign event, with threshold of their signatures.
+    pub key_config: KeyConfig,
+    /// Next keys committed in previous establishment event. Keys revealed
+    /// in rotation need to satisfy their threshold too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prior_next: Option<NextKeysData>,
+    pub signatures: Vec<IndexedSignature>,
+}
+
+impl SignatureCollection {
+    fn new(
+        operation: Operation,
+        payload: String,
+        key_config: KeyConfig,
+        prior_next: Option<NextKeysData>,
+    ) -> Self {
+        Self {
+            operation,
+            payload,
+            key_config,
+            prior_next,
+            signatures: vec![],
+        }
+    }
+
+    /// Starts collecting signatures of inception event generated with
+    /// [`Controller::incept_multi_key`].
+    pub fn inception(event: String) -> Result<Self, ControllerError> {
+        match parse_key_event(&event)?.data.event_data {
+            EventData::Icp(icp) => Ok(Self::new(Operation::Inception, event, icp.key_config, None)),
+            _ => Err(MechanicsError::WrongEventTypeError.into()),
+        }
+    }
+
+    /// Adds signature of payload made with one of the keys. Signature of key
+    /// that already signed replaces the previous one.
+    pub fn add_signature(&mut self, signature: SelfSigningPrefix) -> Result<(), ControllerError> {
+        let current_index = self
+            .key_config
+            .public_keys
+            .iter()
+            .position(|bp| {
+                bp.verify(self.payload.as_bytes(), &signature)
+                    .unwrap_or(false)
+            })
+            .ok_or(ControllerError::SignatureMismatch {
+                signature_index: self.signatures.len(),
+            })?;
+        let prior_next_index = self.prior_next.as_ref().map(|prior_next| {
+            let key = &self.key_config.public_keys[current_index];
+            prior_next
+                .next_keys_hashes()
+                .iter()
+                .position(|digest| digest.verify_binding(key.to_str().as_bytes()))
+        });
+        let indexed = match prior_next_index {
+            None => IndexedSignature::new_both_same(signature, current_index as u16),
+            Some(Some(prior_next_index)) if prior_next_index == current_index => {
+                IndexedSignature::new_both_same(signature, current_index as u16)
+            }
+            Some(Some(prior_next_index)) => IndexedSignature::new_both_diffrent(
+                signature,
+                current_index as u16,
+                prior_next_index as u16,
+            ),
+            // Key wasn't committed in previous event, so it only counts
+            // toward current threshold.
+            Some(None) => IndexedSignature::new_current_only(signature, current_index as u16),
+        };
+        self.signatures
+            .retain(|sig| sig.index.current() != indexed.index.current());
+        self.signatures.push(indexed);
+        Ok(())
+    }
+
+    /// Signs payload with `key_manager`, e.g. key of the device collection
+    /// is currently on, and adds the signature.
+    pub fn sign_with<K: KeyManager + ?Sized>(
+        &mut self,
+        key_manager: &K,
+    ) -> Result<(), ControllerError> {
+        let signature = key_manager
+            .sign(self.payload.as_bytes())
+            .map_err(|e| match e {
+                keri_core::error::Error::WalletLocked => ControllerError::WalletLocked,
+                e => e.into(),
+            })?;
+        self.add_signature(key_manager.key_type().signature(signature))
+    }
+
+    /// Returns keys that didn't sign payload yet.
+    pub fn missing_keys(&self) -> Vec<BasicPrefix> {
+        self.key_config
+            .public_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                !self
+                    .signatures
+                    .iter()
+                    .any(|sig| sig.index.current() as usize == *i)
+            })
+            .map(|(_, key)| key.clone())
+            .collect()
+    }
+
+    /// Checks if collected signatures satisfy signing threshold and, in
+    /// rotation, threshold of previous next keys.
+    pub fn is_complete(&self) -> bool {
+        let current = self
+            .key_config
+            .verify(self.payload.as_bytes(), &self.signatures)
+            .unwrap_or(false);
+        let prior_next = self.prior_next.as_ref().map_or(true, |prior_next| {
+            prior_next
+                .check_threshold(
+                    &self.key_config.public_keys,
+                    self.signatures.iter().map(|sig| &sig.index),
+                )
+                .is_ok()
+        });
+        current && prior_next
+    }
+
+    /// Returns event if collected signatures are enough to finalize it.
+    fn completed_event(&self) -> Result<KeriEvent<KeyEvent>, ControllerError> {
+        if !self.is_complete() {
+            return Err(ControllerError::ThresholdNotReached(self.missing_keys()));
+        }
+        Ok(parse_key_event(&self.payload)?)
+    }
+}
+
+fn parse_key_event(event: &str) -> Result<KeriEvent<KeyEvent>, MechanicsError> {
+    match parse_event_type(event.as_bytes()).map_err(|_e| MechanicsError::EventFormatError)? {
+        EventType::KeyEvent(ke) => Ok(ke),
+        _ => Err(MechanicsError::WrongEventTypeError),
+    }
+}
+
+impl Controller {
+    /// Finalizes inception of identifier controlled by several keys, once
+    /// enough of them signed it.
+    pub fn finalize_multi_key_incept(
+        &self,
+        collection: &SignatureCollection,
+    ) -> Result<Identifier, ControllerError> {
+        if collection.operation != Operation::Inception {
+            return Err(ControllerError::UnexpectedSigningRequest(
+                collection.operation,
+            ));
+        }
+        let icp = collection.completed_event()?;
+        self.known_events
+            .finalize_key_event_with_signatures(&icp, collection.signatures.clone())?;
+        let id = icp.data.get_prefix();
+        self.query_cache.save_own_identifier(&id, None)?;
+        Ok(Identifier::new(
+            id,
+            None,
+            self.known_events.clone(),
+            self.communication.clone(),
+            self.query_cache.clone(),
+        ))
+    }
+}
+
+impl Identifier {
+    /// Starts collecting signatures of rotation or interaction event
+    /// generated for this identifier. Rotation thresholds can be set with
+    /// [`Identifier::rotate_partial`].
+    pub fn collect_signatures(
+        &self,
+        event: String,
+    ) -> Result<SignatureCollection, ControllerError> {
+        let ke = parse_key_event(&event)?;
+        match ke.data.event_data {
+            EventData::Rot(rot) | EventData::Drt(rot) => {
+                let state = self.known_events.get_state(&self.id)?;
+                Ok(SignatureCollection::new(
+                    Operation::Rotation,
+                    event,
+                    rot.key_config,
+                    Some(state.current.next_keys_data),
+                ))
+            }
+            EventData::Ixn(_) => {
+                let state = self.known_events.get_state(&self.id)?;
+                Ok(SignatureCollection::new(
+                    Operation::Interaction,
+                    event,
+                    state.current,
+                    None,
+                ))
+            }
+            EventData::Icp(_) | EventData::Dip(_) => {
+                Err(MechanicsError::WrongEventTypeError.into())
+            }
+        }
+    }
+
+    /// Finalizes rotation or interaction event once enough keys signed it.
+    /// As with single key, witnesses need to be notified afterwards.
+    pub async fn finalize_multi_key_event(
+        &mut self,
+        collection: &SignatureCollection,
+    ) -> Result<(), ControllerError> {
+        let event = match collection.operation {
+            Operation::Rotation | Operation::Interaction => collection.completed_event()?,
+            operation => return Err(ControllerError::UnexpectedSigningRequest(operation)),
+        };
+        if let EventData::Rot(rot) | EventData::Drt(rot) = &event.data.event_data {
+            self.provide_kel_to_new_witnesses(rot).await?;
+        }
+        self.save_key_event(&event, collection.signatures.clone())?;
+        Ok(())
+    }
+}
@@ -56,6 +56,7 @@ impl NontransferableIdentifier {
                     i: identifier,
                     src: Some(IdentifierPrefix::Basic(witness)),
                     limit: Some(limit),
+                    after: None,
                 },
             },
             SerializationFormats::JSON,
@@ -72,6 +73,7 @@ impl NontransferableIdentifier {
                     i: id.clone(),
                     src: Some(IdentifierPrefix::Basic(witness)),
                     limit: None,
+                    after: None,
                 },
             },
             SerializationFormats::JSON,
@@ -131,9 +133,9 @@ impl NontransferableIdentifier {
         let tel_res = self
             .communication
             .tel_transport
-            .send_query(signed_qry, witness_location)
+            .send_query(witness_location, signed_qry)
             .await
-            .map_err(|e| MechanicsError::OtherError(e.to_string()))?;
+            .map_err(MechanicsError::TelTransport)?;
         Ok(tel_res)
     }
 }
@@ -0,0 +1,57 @@
+use keri_core::{
+    prefix::IdentifierPrefix,
+    query::provenance::{ProvenanceBundle, SignedProvenance},
+};
+
+use super::{mechanics::MechanicsError, Identifier};
+
+impl Identifier {
+    /// Asks `watcher` which witnesses supplied events of KEL of `id` and
+    /// when. Returned provenance is signed by the watcher, so it can be kept
+    /// for audit.
+    pub async fn kel_provenance(
+        &self,
+        watcher: &IdentifierPrefix,
+        id: &IdentifierPrefix,
+    ) -> Result<SignedProvenance, MechanicsError> {
+        self.fetch_provenance(watcher, id, None).await
+    }
+
+    /// Asks `watcher` which backer supplied TEL of `vc_id` from registry
+    /// `ri` and when. Returned provenance is signed by the watcher.
+    pub async fn tel_provenance(
+        &self,
+        watcher: &IdentifierPrefix,
+        ri: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Result<SignedProvenance, MechanicsError> {
+        self.fetch_provenance(watcher, ri, Some(vc_id)).await
+    }
+
+    async fn fetch_provenance(
+        &self,
+        watcher: &IdentifierPrefix,
+        id: &IdentifierPrefix,
+        vc_id: Option<&IdentifierPrefix>,
+    ) -> Result<SignedProvenance, MechanicsError> {
+        let provenance = self
+            .communication
+            .request_provenance(watcher, id, vc_id)
+            .await?;
+        let about_requested = match &provenance.bundle {
+            ProvenanceBundle::Kel { id: about, .. } => about == id && vc_id.is_none(),
+            ProvenanceBundle::Tel {
+                ri, vc_id: about, ..
+            } => ri == id && Some(about) == vc_id,
+        };
+        let signed_by_watcher = match watcher {
+            IdentifierPrefix::Basic(watcher) => provenance.verify(watcher)?,
+            _ => false,
+        };
+        if about_requested && signed_by_watcher {
+            Ok(provenance)
+        } else {
+            Err(MechanicsError::InvalidProvenance(watcher.clone()))
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use std::collections::HashSet;
 
+use chrono::{DateTime, FixedOffset};
+
 use crate::communication::SendingError;
 use crate::error::ControllerError;
 use futures::future::join_all;
 use keri_core::actor::error::ActorError;
 use keri_core::actor::prelude::HashFunctionCode;
 use keri_core::error::Error;
+use keri_core::event_message::signed_event_message::{Message, Op};
 use keri_core::oobi::Scheme;
 use keri_core::prefix::IndexedSignature;
 use keri_core::query::query_event::SignedKelQuery;
@@ -13,7 +16,11 @@ use keri_core::{
     actor::{prelude::SerializationFormats, simple_controller::PossibleResponse},
     event::sections::seal::EventSeal,
     prefix::{IdentifierPrefix, SelfSigningPrefix},
-    query::query_event::{LogsQueryArgs, QueryEvent, QueryRoute},
+    query::{
+        key_state_notice::KeyStateNotice,
+        query_event::{LogsBetweenQueryArgs, LogsQueryArgs, QueryEvent, QueryRoute},
+        reply_event::{ReplyRoute, SignedReply},
+    },
 };
 
 use super::Identifier;
@@ -43,10 +50,11 @@ impl Identifier {
         &self,
         about_who: &EventSeal,
     ) -> Result<Vec<QueryEvent>, ControllerError> {
-        self.known_events
-            .get_watchers(&self.id)?
+        self.watchers()?
             .into_iter()
-            .map(|watcher| self.query_log_range(&about_who.prefix, 0, about_who.sn + 1, watcher))
+            .map(|watcher| {
+                self.query_log_range(&about_who.prefix, 0, about_who.sn + 1, watcher.eid)
+            })
             .collect()
     }
 
@@ -76,7 +84,12 @@ impl Identifier {
                 }
             }
             Ok(PossibleResponse::Mbx(_mbx)) => Err(WatcherResponseError::UnexpectedResponse),
-            Ok(PossibleResponse::Ksn(_)) => Err(WatcherResponseError::UnexpectedResponse),
+            Ok(PossibleResponse::Ksn(rpy)) => {
+                // Accepted notice doesn't change KEL. Notice ahead of KEL is
+                // escrowed until missing events are queried.
+                self.process_ksn_reply(rpy)?;
+                Ok(HashSet::new())
+            }
             Err(SendingError::ActorInternalError(ActorError::NotFound(id))) => {
                 Err(WatcherResponseError::KELNotFound(id))
             }
@@ -85,7 +98,8 @@ impl Identifier {
     }
 
     /// Joins query events with their signatures, sends it to recipient and
-    /// process its response. Returns a tuple containing two elements:
+    /// process its response. Key state notices are verified and saved, see
+    /// [`Identifier::finalize_ksn_query`]. Returns a tuple containing two elements:
     ///     1. A notification if any identifier's KEL (Key Event Log) was updated.
     ///     2. A list of errors that occurred either during sending or on the recipient side.
     pub async fn finalize_query(
@@ -150,16 +164,7 @@ impl Identifier {
         qry: QueryEvent,
         sig: SelfSigningPrefix,
     ) -> Result<PossibleResponse, SendingError> {
-        let recipient = match qry.get_route() {
-            QueryRoute::Logs {
-                reply_route: _,
-                args,
-            } => args.src.clone(),
-            QueryRoute::Ksn {
-                reply_route: _,
-                args,
-            } => args.src.clone(),
-        };
+        let recipient = qry.get_route().get_source().cloned();
 
         let query = match &self.id {
             IdentifierPrefix::Basic(bp) => {
@@ -190,6 +195,7 @@ impl Identifier {
                     i: id.clone(),
                     src: Some(watcher),
                     limit: Some(limit),
+                    after: None,
                 },
             },
             SerializationFormats::JSON,
@@ -210,10 +216,93 @@ impl Identifier {
                     i: id.clone(),
                     src: Some(watcher),
                     limit: None,
+                    after: None,
+                },
+            },
+            SerializationFormats::JSON,
+            HashFunctionCode::Blake3_256,
+        ))
+    }
+
+    /// Generates query for events of `id` first seen by `watcher` between
+    /// `from` (inclusive) and `to` (exclusive), e.g. for audit of what
+    /// identifier did in given month.
+    pub fn query_log_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+        watcher: IdentifierPrefix,
+    ) -> Result<QueryEvent, ControllerError> {
+        Ok(QueryEvent::new_query(
+            QueryRoute::LogsBetween {
+                reply_route: "".to_string(),
+                args: LogsBetweenQueryArgs {
+                    i: id.clone(),
+                    src: Some(watcher),
+                    from,
+                    to,
                 },
             },
             SerializationFormats::JSON,
             HashFunctionCode::Blake3_256,
         ))
     }
+
+    /// Generates query for key state notice of `id`, that should be sent to
+    /// `via_watcher`. Witness of `id` can be asked the same way. Query needs to be signed and provided to
+    /// [`Identifier::finalize_ksn_query`].
+    pub fn query_ksn(
+        &self,
+        id: &IdentifierPrefix,
+        via_watcher: IdentifierPrefix,
+    ) -> Result<QueryEvent, ControllerError> {
+        Ok(QueryEvent::new_query(
+            QueryRoute::Ksn {
+                reply_route: "".to_string(),
+                args: LogsQueryArgs {
+                    s: None,
+                    i: id.clone(),
+                    src: Some(via_watcher),
+                    limit: None,
+                    after: None,
+                },
+            },
+            SerializationFormats::JSON,
+            HashFunctionCode::Blake3_256,
+        ))
+    }
+
+    /// Sends signed key state query to watcher and processes its reply.
+    /// Watcher may answer from state it verified with witnesses recently,
+    /// `verified_at` of returned notice tells when it happened. If notice is
+    /// ahead of known KEL, it's escrowed and KEL should be queried with
+    /// [`Identifier::query_full_log`].
+    pub async fn finalize_ksn_query(
+        &self,
+        qry: QueryEvent,
+        sig: SelfSigningPrefix,
+    ) -> Result<KeyStateNotice, WatcherResponseError> {
+        match self.handle_query(qry, sig).await {
+            Ok(PossibleResponse::Ksn(rpy)) => self.process_ksn_reply(rpy),
+            Ok(_) => Err(WatcherResponseError::UnexpectedResponse),
+            Err(SendingError::ActorInternalError(ActorError::NoIdentState { prefix })) => {
+                Err(WatcherResponseError::KELNotFound(prefix))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Verifies signed key state notice reply and saves it, if it's newer
+    /// than previously accepted reply of the same signer (BADA rules).
+    fn process_ksn_reply(&self, rpy: SignedReply) -> Result<KeyStateNotice, WatcherResponseError> {
+        let ksn = match rpy.reply.get_route() {
+            ReplyRoute::Ksn(_, ksn) => ksn,
+            _ => return Err(WatcherResponseError::UnexpectedResponse),
+        };
+        self.known_events
+            .process(&Message::Op(Op::Reply(rpy)))
+            .map_err(|e| WatcherResponseError::ResponseProcessingError(vec![e]))?;
+        Ok(ksn)
+    }
 }
@@ -0,0 +1,108 @@
+use futures::future::join_all;
+use keri_core::{
+    prefix::{BasicPrefix, CesrPrimitive, IdentifierPrefix},
+    signer::KeyManager,
+};
+
+use super::{mechanics::MechanicsError, Identifier};
+
+/// State of key that will be revealed by rotation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextKeyStatus {
+    /// Key manager holds key committed in last establishment event.
+    Available,
+    /// Key manager is locked, so it can't sign rotation.
+    Locked,
+    /// Next key of key manager doesn't match any committed key digest.
+    /// Rotation signed with it would be rejected.
+    Mismatch,
+}
+
+/// Checks worth doing before rotation, which can't be undone. See
+/// [`Identifier::rotation_readiness`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationReadiness {
+    pub next_key: NextKeyStatus,
+    /// Witnesses whose locations don't respond. Rotation won't be receipted
+    /// by them.
+    pub unreachable_witnesses: Vec<BasicPrefix>,
+    /// Sequence numbers of identifier's events still waiting for witness
+    /// receipts.
+    pub partially_witnessed: Vec<u64>,
+    /// Number of committed next keys that belong to other signers, e.g.
+    /// other group participants.
+    pub cosigners: usize,
+    /// Whether next threshold needs signatures of other signers, so they
+    /// need to rotate too.
+    pub cosigners_required: bool,
+}
+
+impl RotationReadiness {
+    /// Returns true if nothing prevents rotation from being accepted and
+    /// witnessed. Rotation can still need signatures of co-signers.
+    pub fn is_ready(&self) -> bool {
+        self.next_key == NextKeyStatus::Available
+            && self.unreachable_witnesses.is_empty()
+            && self.partially_witnessed.is_empty()
+    }
+}
+
+impl Identifier {
+    /// Returns report of things that should be checked before rotating keys
+    /// of `id`, which is this identifier or group it participates in.
+    /// Witnesses are asked for their location scheme to check if they can
+    /// be reached, so it can take a while.
+    pub async fn rotation_readiness<K: KeyManager>(
+        &self,
+        id: &IdentifierPrefix,
+        key_manager: &K,
+    ) -> Result<RotationReadiness, MechanicsError> {
+        let state = self.known_events.get_state(id)?;
+        let next_keys = &state.current.next_keys_data;
+        let committed = next_keys.next_keys_hashes();
+
+        let next_key = key_manager
+            .key_type()
+            .basic_prefix(key_manager.next_public_key());
+        let own_index = committed
+            .iter()
+            .position(|digest| digest.verify_binding(next_key.to_str().as_bytes()));
+        let next_key = match own_index {
+            _ if key_manager.is_locked() => NextKeyStatus::Locked,
+            Some(_) => NextKeyStatus::Available,
+            None => NextKeyStatus::Mismatch,
+        };
+        let own_indexes: Vec<usize> = own_index.into_iter().collect();
+        let cosigners_required = next_keys.threshold.enough_signatures(&own_indexes).is_err();
+
+        let witnesses = state.witness_config.witnesses;
+        let reachable = join_all(witnesses.iter().map(|witness| {
+            self.communication
+                .is_reachable(&IdentifierPrefix::Basic(witness.clone()))
+        }))
+        .await;
+        let unreachable_witnesses = witnesses
+            .into_iter()
+            .zip(reachable)
+            .filter(|(_witness, reachable)| !reachable)
+            .map(|(witness, _)| witness)
+            .collect();
+
+        let partially_witnessed = self
+            .known_events
+            .partially_witnessed_escrow
+            .get_partially_witnessed_events()
+            .into_iter()
+            .filter(|event| &event.event_message.data.prefix == id)
+            .map(|event| event.event_message.data.sn)
+            .collect();
+
+        Ok(RotationReadiness {
+            next_key,
+            unreachable_witnesses,
+            partially_witnessed,
+            cosigners: committed.len() - own_indexes.len(),
+            cosigners_required,
+        })
+    }
+}
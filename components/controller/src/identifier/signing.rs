@@ -2,7 +2,7 @@ use cesrox::ParsedData;
 use keri_core::{
     event::sections::seal::EventSeal,
     event_message::signature::{Signature, SignerData},
-    prefix::{IndexedSignature, SelfSigningPrefix},
+    prefix::{IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
 };
 
 use crate::error::ControllerError;
@@ -10,6 +10,29 @@ use crate::error::ControllerError;
 use super::Identifier;
 
 impl Identifier {
+    /// Returns `signature` of `data` indexed with position of the key that
+    /// made it in current key set of identifier, or of `group` if provided.
+    /// Returns error if signature doesn't match any of the keys.
+    pub fn sign_indexed(
+        &self,
+        data: &[u8],
+        signature: SelfSigningPrefix,
+        group: Option<&IdentifierPrefix>,
+    ) -> Result<IndexedSignature, ControllerError> {
+        let id = group.unwrap_or(&self.id);
+        let key_index = self
+            .known_events
+            .get_state(id)?
+            .current
+            .public_keys
+            .iter()
+            .position(|bp| bp.verify(data, &signature).unwrap_or(false))
+            .ok_or(ControllerError::SignatureMismatch { signature_index: 0 })?;
+        Ok(IndexedSignature::new_both_same(signature, key_index as u16))
+    }
+
+    /// Returns signature made with key of provided index. See
+    /// [`Identifier::sign_indexed`] to find the index automatically.
     pub fn sign_with_index(
         &self,
         signature: SelfSigningPrefix,
@@ -51,24 +74,19 @@ impl Identifier {
             .unwrap()
             .current
             .public_keys;
-        let indexed_signatures: Option<Vec<_>> = signatures
+        let indexed_signatures = signatures
             .iter()
-            .map(|sig| {
-                (
-                    sig,
-                    (state
-                        .iter()
-                        .position(|bp| bp.verify(data, sig).ok().is_some())),
-                )
-            })
-            .map(|(sig, index)| {
-                index.map(|i| IndexedSignature::new_both_same(sig.clone(), i as u16))
+            .enumerate()
+            .map(|(signature_index, sig)| {
+                state
+                    .iter()
+                    .position(|bp| bp.verify(data, sig).unwrap_or(false))
+                    .map(|i| IndexedSignature::new_both_same(sig.clone(), i as u16))
+                    .ok_or(ControllerError::SignatureMismatch { signature_index })
             })
-            .collect();
-        let signature = Signature::Transferable(
-            SignerData::EventSeal(event_seal),
-            indexed_signatures.expect("Provided signatures do not match any of the keys corresponding to the provided event seal"),
-        );
+            .collect::<Result<Vec<_>, _>>()?;
+        let signature =
+            Signature::Transferable(SignerData::EventSeal(event_seal), indexed_signatures);
         Ok(signature)
     }
 
@@ -82,7 +100,9 @@ impl Identifier {
         let signature = self.sign_data(data.as_bytes(), signatures)?;
         ParsedData {
             payload: cesrox::payload::Payload::JSON(data.into()),
-            attachments: vec![signature.into()],
+            attachments: vec![signature
+                .try_into()
+                .map_err(|_e| ControllerError::CesrFormatError)?],
         }
         .to_cesr()
         .map(|data| String::from_utf8(data).unwrap())
@@ -99,7 +119,9 @@ impl Identifier {
         let signature = self.sign_with_index(signature, key_index)?;
         ParsedData {
             payload: cesrox::payload::Payload::JSON(data.into()),
-            attachments: vec![signature.into()],
+            attachments: vec![signature
+                .try_into()
+                .map_err(|_e| ControllerError::CesrFormatError)?],
         }
         .to_cesr()
         .map(|data| String::from_utf8(data).unwrap())
@@ -0,0 +1,268 @@
+use keri_core::{
+    actor::prelude::{HashFunction, HashFunctionCode, SelfAddressingIdentifier},
+    event::event_data::EventData,
+    event_message::cesr_adapter::{parse_event_type, EventType},
+    oobi::LocationScheme,
+    prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
+    query::{mailbox::MailboxQuery, query_event::QueryEvent},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{controller::Controller, error::ControllerError, mailbox_updating::ActionRequired};
+
+use super::{
+    mechanics::MechanicsError,
+    query::{QueryResponse, WatcherResponseError},
+    Identifier,
+};
+
+/// Kind of operation that signing request was generated for. Determines how
+/// completed request is finalized.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Inception,
+    Rotation,
+    Interaction,
+    Exchange,
+    Query,
+    MailboxQuery,
+}
+
+/// Data that need to be signed to finish controller operation. It can be
+/// serialized and transported to a signer that has no access to the
+/// controller (for example air-gapped device) and completed there with
+/// [`SigningRequest::complete_with_signature`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SigningRequest {
+    pub operation: Operation,
+    /// Message to be signed.
+    pub payload: String,
+    /// Blake3 digest of payload. Lets signer check that payload wasn't
+    /// altered on the way.
+    pub digest: SelfAddressingIdentifier,
+    /// Keys that are allowed to sign payload.
+    pub keys: Vec<BasicPrefix>,
+}
+
+/// Signing request joined with signature of its payload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompletedSigningRequest {
+    pub request: SigningRequest,
+    pub signature: SelfSigningPrefix,
+    /// Position of the signing key in request's `keys`.
+    pub key_index: u16,
+}
+
+/// Result of finalizing completed signing request.
+#[derive(Debug)]
+pub enum SigningOutcome {
+    /// Rotation or interaction event was accepted.
+    EventAccepted,
+    /// Query was sent to watchers.
+    Query(QueryResponse, Vec<WatcherResponseError>),
+    /// Mailbox was queried. Contains actions required from user.
+    Mailbox(Vec<ActionRequired>),
+}
+
+impl SigningRequest {
+    pub fn new(operation: Operation, payload: String, keys: Vec<BasicPrefix>) -> Self {
+        let digest = HashFunction::from(HashFunctionCode::Blake3_256).derive(payload.as_bytes());
+        Self {
+            operation,
+            payload,
+            digest,
+            keys,
+        }
+    }
+
+    /// Checks if `digest` matches `payload`.
+    pub fn verify_digest(&self) -> bool {
+        self.digest.verify_binding(self.payload.as_bytes())
+    }
+
+    /// Joins request with `signature` of its payload. Returns error if
+    /// payload doesn't match digest or signature wasn't made by any of
+    /// request's keys.
+    pub fn complete_with_signature(
+        self,
+        signature: SelfSigningPrefix,
+    ) -> Result<CompletedSigningRequest, ControllerError> {
+        if !self.verify_digest() {
+            return Err(ControllerError::DigestMismatch);
+        }
+        let key_index = self
+            .keys
+            .iter()
+            .position(|bp| {
+                bp.verify(self.payload.as_bytes(), &signature)
+                    .unwrap_or(false)
+            })
+            .ok_or(ControllerError::SignatureMismatch { signature_index: 0 })?;
+        Ok(CompletedSigningRequest {
+            request: self,
+            signature,
+            key_index: key_index as u16,
+        })
+    }
+}
+
+impl CompletedSigningRequest {
+    pub fn indexed_signature(&self) -> IndexedSignature {
+        IndexedSignature::new_both_same(self.signature.clone(), self.key_index)
+    }
+
+    fn expect(&self, operation: Operation) -> Result<(), ControllerError> {
+        if self.request.operation == operation {
+            Ok(())
+        } else {
+            Err(ControllerError::UnexpectedSigningRequest(
+                self.request.operation,
+            ))
+        }
+    }
+}
+
+/// Returns keys set by establishment event.
+fn establishment_keys(event: &str) -> Result<Vec<BasicPrefix>, ControllerError> {
+    match parse_event_type(event.as_bytes()).map_err(|_e| MechanicsError::EventFormatError)? {
+        EventType::KeyEvent(ke) => match ke.data.event_data {
+            EventData::Icp(icp) => Ok(icp.key_config.public_keys),
+            EventData::Dip(dip) => Ok(dip.inception_data.key_config.public_keys),
+            EventData::Rot(rot) | EventData::Drt(rot) => Ok(rot.key_config.public_keys),
+            EventData::Ixn(_) => Err(MechanicsError::WrongEventTypeError.into()),
+        },
+        _ => Err(MechanicsError::WrongEventTypeError.into()),
+    }
+}
+
+impl Controller {
+    /// Generates inception event and wraps it in signing request.
+    pub async fn inception_request(
+        &self,
+        public_keys: Vec<BasicPrefix>,
+        next_pub_keys: Vec<BasicPrefix>,
+        witnesses: Vec<LocationScheme>,
+        witness_threshold: u64,
+    ) -> Result<SigningRequest, ControllerError> {
+        let icp = self
+            .incept(
+                public_keys.clone(),
+                next_pub_keys,
+                witnesses,
+                witness_threshold,
+            )
+            .await?;
+        Ok(SigningRequest::new(Operation::Inception, icp, public_keys))
+    }
+
+    pub fn finalize_inception_request(
+        &self,
+        completed: CompletedSigningRequest,
+    ) -> Result<Identifier, ControllerError> {
+        completed.expect(Operation::Inception)?;
+        self.finalize_incept(completed.request.payload.as_bytes(), &completed.signature)
+    }
+}
+
+impl Identifier {
+    /// Wraps `payload` generated by other `Identifier` function in signing
+    /// request. Signing keys of inception and rotation are taken from the
+    /// event itself, of interaction from the state of its identifier and in
+    /// other cases current keys of this identifier are used.
+    pub fn signing_request(
+        &self,
+        operation: Operation,
+        payload: String,
+    ) -> Result<SigningRequest, ControllerError> {
+        let keys = match operation {
+            Operation::Inception | Operation::Rotation => establishment_keys(&payload)?,
+            Operation::Interaction => {
+                match parse_event_type(payload.as_bytes())
+                    .map_err(|_e| MechanicsError::EventFormatError)?
+                {
+                    EventType::KeyEvent(ke) => {
+                        self.find_state(&ke.data.get_prefix())?.current.public_keys
+                    }
+                    _ => return Err(MechanicsError::WrongEventTypeError.into()),
+                }
+            }
+            Operation::Exchange | Operation::Query | Operation::MailboxQuery => {
+                self.current_public_keys()?
+            }
+        };
+        Ok(SigningRequest::new(operation, payload, keys))
+    }
+
+    /// Generates signing requests for mailbox queries. See
+    /// [`Identifier::query_mailbox`].
+    pub fn query_mailbox_requests(
+        &self,
+        identifier: &IdentifierPrefix,
+        witnesses: &[BasicPrefix],
+    ) -> Result<Vec<SigningRequest>, ControllerError> {
+        self.query_mailbox(identifier, witnesses)?
+            .into_iter()
+            .map(|qry| {
+                let payload = String::from_utf8(qry.encode()?)?;
+                self.signing_request(Operation::MailboxQuery, payload)
+            })
+            .collect()
+    }
+
+    /// Finalizes rotation, interaction, query and mailbox query requests.
+    /// Exchanges need signature of forwarded event as well, see
+    /// [`Identifier::finalize_exchange_request`].
+    pub async fn finalize_signing_request(
+        &mut self,
+        completed: CompletedSigningRequest,
+    ) -> Result<SigningOutcome, ControllerError> {
+        let CompletedSigningRequest {
+            request, signature, ..
+        } = completed;
+        match request.operation {
+            Operation::Rotation => {
+                self.finalize_rotate(request.payload.as_bytes(), signature)
+                    .await?;
+                Ok(SigningOutcome::EventAccepted)
+            }
+            Operation::Interaction => {
+                self.finalize_anchor(request.payload.as_bytes(), signature)
+                    .await?;
+                Ok(SigningOutcome::EventAccepted)
+            }
+            Operation::Query => {
+                let qry: QueryEvent = serde_json::from_str(&request.payload)
+                    .map_err(|_e| MechanicsError::EventFormatError)?;
+                let (response, errors) = self.finalize_query(vec![(qry, signature)]).await;
+                Ok(SigningOutcome::Query(response, errors))
+            }
+            Operation::MailboxQuery => {
+                let qry: MailboxQuery = serde_json::from_str(&request.payload)
+                    .map_err(|_e| MechanicsError::EventFormatError)?;
+                let actions = self.finalize_query_mailbox(vec![(qry, signature)]).await?;
+                Ok(SigningOutcome::Mailbox(actions))
+            }
+            operation @ (Operation::Inception | Operation::Exchange) => {
+                Err(ControllerError::UnexpectedSigningRequest(operation))
+            }
+        }
+    }
+
+    /// Finalizes exchange request together with request of the event it
+    /// forwards (for example group inception).
+    pub async fn finalize_exchange_request(
+        &self,
+        exchange: CompletedSigningRequest,
+        forwarded: &CompletedSigningRequest,
+    ) -> Result<(), ControllerError> {
+        exchange.expect(Operation::Exchange)?;
+        self.finalize_exchange(
+            exchange.request.payload.as_bytes(),
+            exchange.signature,
+            forwarded.indexed_signature(),
+        )
+        .await?;
+        Ok(())
+    }
+}
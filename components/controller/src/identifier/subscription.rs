@@ -0,0 +1,180 @@
+use std::sync::RwLock;
+
+use async_std::channel::{unbounded, Receiver, Sender};
+use futures::Stream;
+use keri_core::{
+    error::Error,
+    event_message::signed_event_message::{SignedEventMessage, SignedNontransferableReceipt},
+    prefix::IdentifierPrefix,
+    processor::notification::{Notification, NotificationBus, Notifier},
+};
+use teliox::processor::notification::{TelNotification, TelNotificationBus, TelNotifier};
+
+use crate::{controller::Controller, mailbox_updating::ActionRequired};
+
+use super::Identifier;
+
+/// Kinds of events that can be subscribed with [`Identifier::subscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Events accepted in identifier's KEL.
+    Kel,
+    /// Witness receipts of identifier's events.
+    Receipts,
+    /// Mailbox messages that require action from identifier's controller.
+    Mailbox,
+}
+
+#[derive(Debug, Clone)]
+pub enum IdentifierEvent {
+    KeyEventAccepted(SignedEventMessage),
+    ReceiptReceived(SignedNontransferableReceipt),
+    ActionRequired(ActionRequired),
+}
+
+impl IdentifierEvent {
+    pub fn topic(&self) -> Topic {
+        match self {
+            IdentifierEvent::KeyEventAccepted(_) => Topic::Kel,
+            IdentifierEvent::ReceiptReceived(_) => Topic::Receipts,
+            IdentifierEvent::ActionRequired(_) => Topic::Mailbox,
+        }
+    }
+}
+
+/// Changes of credential registries, published when TEL events are
+/// accepted, including ones received while verifying credentials.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelEvent {
+    RegistryIncepted {
+        registry_id: IdentifierPrefix,
+    },
+    CredentialIssued {
+        registry_id: IdentifierPrefix,
+        vc_id: IdentifierPrefix,
+    },
+    CredentialRevoked {
+        registry_id: IdentifierPrefix,
+        vc_id: IdentifierPrefix,
+    },
+}
+
+impl TelEvent {
+    fn from_notification(notification: &TelNotification) -> Option<Self> {
+        match notification {
+            TelNotification::RegistryIncepted(event) => Some(TelEvent::RegistryIncepted {
+                registry_id: event.event.get_prefix(),
+            }),
+            TelNotification::CredentialIssued(event) => Some(TelEvent::CredentialIssued {
+                registry_id: event.event.get_registry_id().ok()?,
+                vc_id: event.event.get_prefix(),
+            }),
+            TelNotification::CredentialRevoked(event) => Some(TelEvent::CredentialRevoked {
+                registry_id: event.event.get_registry_id().ok()?,
+                vc_id: event.event.get_prefix(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+struct Subscriber {
+    id: IdentifierPrefix,
+    topic: Topic,
+    sender: Sender<IdentifierEvent>,
+}
+
+/// Delivers events to subscribers as they are processed. Subscribers whose
+/// streams were dropped are removed on next published event.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    subscribers: RwLock<Vec<Subscriber>>,
+    tel_subscribers: RwLock<Vec<Sender<TelEvent>>>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&self, id: IdentifierPrefix, topic: Topic) -> Receiver<IdentifierEvent> {
+        let (sender, receiver) = unbounded();
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            subscribers.push(Subscriber { id, topic, sender });
+        }
+        receiver
+    }
+
+    pub fn publish(&self, id: &IdentifierPrefix, event: IdentifierEvent) {
+        let topic = event.topic();
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            subscribers.retain(|subscriber| !subscriber.sender.is_closed());
+            for subscriber in subscribers
+                .iter()
+                .filter(|subscriber| &subscriber.id == id && subscriber.topic == topic)
+            {
+                let _ = subscriber.sender.try_send(event.clone());
+            }
+        }
+    }
+
+    pub fn subscribe_tel(&self) -> Receiver<TelEvent> {
+        let (sender, receiver) = unbounded();
+        if let Ok(mut subscribers) = self.tel_subscribers.write() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    pub fn publish_tel(&self, event: TelEvent) {
+        if let Ok(mut subscribers) = self.tel_subscribers.write() {
+            subscribers.retain(|sender| !sender.is_closed());
+            for sender in subscribers.iter() {
+                let _ = sender.try_send(event.clone());
+            }
+        }
+    }
+}
+
+impl Notifier for Subscriptions {
+    fn notify(&self, notification: &Notification, _bus: &NotificationBus) -> Result<(), Error> {
+        if let Notification::KeyEventAdded(event) = notification {
+            self.publish(
+                &event.event_message.data.get_prefix(),
+                IdentifierEvent::KeyEventAccepted(event.clone()),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TelNotifier for Subscriptions {
+    fn notify(
+        &self,
+        notification: &TelNotification,
+        _bus: &TelNotificationBus,
+    ) -> Result<(), teliox::error::Error> {
+        if let Some(event) = TelEvent::from_notification(notification) {
+            self.publish_tel(event);
+        }
+        Ok(())
+    }
+}
+
+impl Identifier {
+    /// Returns stream of identifier's events of given topic, produced as they
+    /// are processed, for example while querying mailbox. Events are
+    /// buffered until they are taken from the stream, so it should be
+    /// consumed or dropped.
+    pub fn subscribe(&self, topic: Topic) -> impl Stream<Item = IdentifierEvent> + Unpin {
+        self.known_events
+            .subscriptions
+            .subscribe(self.id.clone(), topic)
+    }
+}
+
+impl Controller {
+    /// Returns stream of registry and credential changes, produced as TEL
+    /// events are processed. Wallets can use it to react to revocations
+    /// discovered while verifying credentials. Events are buffered until
+    /// they are taken from the stream, so it should be consumed or dropped.
+    pub fn subscribe_tel(&self) -> impl Stream<Item = TelEvent> + Unpin {
+        self.known_events.subscriptions.subscribe_tel()
+    }
+}
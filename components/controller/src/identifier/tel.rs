@@ -47,7 +47,7 @@ impl Identifier {
 
                 Ok((vc_hash, encoded_ixn))
             }
-            None => Err(ControllerError::OtherError("Tel not incepted".into())),
+            None => Err(ControllerError::RegistryNotIncepted(self.id.clone())),
         }
     }
 
@@ -84,7 +84,7 @@ impl Identifier {
 
                 Ok(encoded_ixn)
             }
-            None => Err(ControllerError::OtherError("Tel not incepted".into())),
+            None => Err(ControllerError::RegistryNotIncepted(self.id.clone())),
         }
     }
 
@@ -138,18 +138,17 @@ impl Identifier {
                 SignedTelQuery::new_trans(qry.clone(), self.id.clone(), signatures)
             }
         };
-        let watcher = self.watchers().unwrap()[0].clone();
+        let watcher = self.watchers().unwrap()[0].eid.clone();
         let location = self.known_events.get_loc_schemas(&watcher).unwrap()[0].clone();
         let tel_res = self
             .communication
             .tel_transport
-            .send_query(query, location)
+            .send_query(location, query)
             .await
-            .map_err(|e| MechanicsError::OtherError(e.to_string()))?;
+            .map_err(MechanicsError::TelTransport)?;
         self.known_events
             .tel
-            .parse_and_process_tel_stream(tel_res.as_bytes())
-            .map_err(|e| MechanicsError::OtherError(e.to_string()))?;
+            .parse_and_process_tel_stream(tel_res.as_bytes())?;
 
         Ok(())
     }
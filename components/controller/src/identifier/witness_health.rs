@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use keri_core::{
+    event::sections::threshold::SignatureThreshold,
+    oobi::LocationScheme,
+    prefix::{BasicPrefix, IdentifierPrefix},
+};
+
+use crate::{communication::Communication, known_events::KnownEvents};
+
+use super::{mechanics::MechanicsError, Identifier};
+
+/// Number of the latest probes taken into account in witness availability.
+const HEALTH_WINDOW: usize = 10;
+
+/// Results of probing witness, see [`Identifier::probe_witnesses`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessHealth {
+    pub witness: BasicPrefix,
+    pub probes: u64,
+    pub failures: u64,
+    /// Failures since the last successful probe.
+    pub consecutive_failures: u64,
+    /// Response time of the last successful probe.
+    pub last_latency: Option<Duration>,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Results of the latest probes, the newest last.
+    recent: VecDeque<bool>,
+}
+
+impl WitnessHealth {
+    fn new(witness: BasicPrefix) -> Self {
+        Self {
+            witness,
+            probes: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            last_latency: None,
+            last_success: None,
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, latency: Option<Duration>) {
+        self.probes += 1;
+        match latency {
+            Some(latency) => {
+                self.consecutive_failures = 0;
+                self.last_latency = Some(latency);
+                self.last_success = Some(Utc::now());
+            }
+            None => {
+                self.failures += 1;
+                self.consecutive_failures += 1;
+            }
+        }
+        if self.recent.len() == HEALTH_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency.is_some());
+    }
+
+    /// Returns share of successful probes among the latest ones. Witness
+    /// that wasn't probed yet is considered available.
+    pub fn availability(&self) -> f64 {
+        if self.recent.is_empty() {
+            return 1.0;
+        }
+        let successes = self.recent.iter().filter(|success| **success).count();
+        successes as f64 / self.recent.len() as f64
+    }
+}
+
+/// Probe results of witnesses, shared by identifiers of controller.
+#[derive(Default)]
+pub(crate) struct WitnessHealthLog(RwLock<HashMap<BasicPrefix, WitnessHealth>>);
+
+impl WitnessHealthLog {
+    /// Records result of probing `witness`. `latency` is missing if witness
+    /// didn't respond.
+    pub fn record(&self, witness: &BasicPrefix, latency: Option<Duration>) {
+        if let Ok(mut log) = self.0.write() {
+            log.entry(witness.clone())
+                .or_insert_with(|| WitnessHealth::new(witness.clone()))
+                .record(latency);
+        }
+    }
+
+    pub fn get(&self, witness: &BasicPrefix) -> WitnessHealth {
+        self.0
+            .read()
+            .ok()
+            .and_then(|log| log.get(witness).cloned())
+            .unwrap_or_else(|| WitnessHealth::new(witness.clone()))
+    }
+}
+
+/// Probes witnesses of identifier, independently of [`Identifier`], so it
+/// can be run as separate task. See [`Identifier::witness_health_checker`].
+#[derive(Clone)]
+pub struct WitnessHealthChecker {
+    id: IdentifierPrefix,
+    known_events: Arc<KnownEvents>,
+    communication: Arc<Communication>,
+}
+
+impl WitnessHealthChecker {
+    /// Asks current witnesses of identifier for their location scheme and
+    /// records response times and failures. Returns updated health of
+    /// witnesses.
+    pub async fn probe(&self) -> Result<Vec<WitnessHealth>, MechanicsError> {
+        let witnesses = self
+            .known_events
+            .get_state(&self.id)?
+            .witness_config
+            .witnesses;
+        join_all(witnesses.iter().map(|witness| async move {
+            let start = Instant::now();
+            let reachable = self
+                .communication
+                .is_reachable(&IdentifierPrefix::Basic(witness.clone()))
+                .await;
+            self.communication
+                .witness_health
+                .record(witness, reachable.then(|| start.elapsed()));
+        }))
+        .await;
+        Ok(witnesses
+            .iter()
+            .map(|witness| self.communication.witness_health.get(witness))
+            .collect())
+    }
+
+    /// Probes witnesses every `interval`. Returns only if identifier's
+    /// state can't be read.
+    pub async fn run(&self, interval: Duration) -> Result<(), MechanicsError> {
+        loop {
+            self.probe().await?;
+            async_std::task::sleep(interval).await;
+        }
+    }
+}
+
+/// When witnesses should be replaced, see
+/// [`Identifier::witness_rotation_advice`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessPolicy {
+    /// Witnesses with lower availability are considered dead.
+    pub min_availability: f64,
+    /// Witnesses added in place of dead ones, in order of preference.
+    pub backups: Vec<LocationScheme>,
+}
+
+/// Witness changes proposed by [`Identifier::witness_rotation_advice`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessRotationAdvice {
+    pub witness_to_remove: Vec<BasicPrefix>,
+    pub witness_to_add: Vec<LocationScheme>,
+    /// Current witness threshold, lowered if there are fewer witnesses after
+    /// rotation.
+    pub witness_threshold: u64,
+}
+
+impl Identifier {
+    /// Returns checker that probes witnesses of this identifier. Probe
+    /// results are shared by all identifiers of controller.
+    pub fn witness_health_checker(&self) -> WitnessHealthChecker {
+        WitnessHealthChecker {
+            id: self.id.clone(),
+            known_events: self.known_events.clone(),
+            communication: self.communication.clone(),
+        }
+    }
+
+    /// Probes current witnesses once, see [`WitnessHealthChecker::probe`].
+    pub async fn probe_witnesses(&self) -> Result<Vec<WitnessHealth>, MechanicsError> {
+        self.witness_health_checker().probe().await
+    }
+
+    /// Returns recorded health of current witnesses.
+    pub fn witness_health(&self) -> Result<Vec<WitnessHealth>, MechanicsError> {
+        let witnesses = self
+            .known_events
+            .get_state(&self.id)?
+            .witness_config
+            .witnesses;
+        Ok(witnesses
+            .iter()
+            .map(|witness| self.communication.witness_health.get(witness))
+            .collect())
+    }
+
+    /// Proposes witness changes if availability of any current witness fell
+    /// below `policy` threshold. Dead witnesses are removed and the same
+    /// number of backups, that aren't witnesses already and aren't known to
+    /// be dead, is added. Returns `None` if all witnesses are available.
+    /// Weighted witness threshold isn't supported, new one should be chosen
+    /// and passed to [`Identifier::rotate_with_witness_threshold`].
+    pub fn witness_rotation_advice(
+        &self,
+        policy: &WitnessPolicy,
+    ) -> Result<Option<WitnessRotationAdvice>, MechanicsError> {
+        let witness_config = self.known_events.get_state(&self.id)?.witness_config;
+        let is_dead = |witness: &BasicPrefix| {
+            self.communication
+                .witness_health
+                .get(witness)
+                .availability()
+                < policy.min_availability
+        };
+        let witness_to_remove: Vec<_> = witness_config
+            .witnesses
+            .iter()
+            .filter(|witness| is_dead(witness))
+            .cloned()
+            .collect();
+        if witness_to_remove.is_empty() {
+            return Ok(None);
+        }
+
+        let witness_to_add: Vec<_> = policy
+            .backups
+            .iter()
+            .filter(|backup| match &backup.eid {
+                IdentifierPrefix::Basic(bp) => {
+                    !witness_config.witnesses.contains(bp) && !is_dead(bp)
+                }
+                _ => false,
+            })
+            .take(witness_to_remove.len())
+            .cloned()
+            .collect();
+
+        let witness_count =
+            witness_config.witnesses.len() - witness_to_remove.len() + witness_to_add.len();
+        let current_threshold = match witness_config.tally {
+            SignatureThreshold::Simple(threshold) => threshold,
+            // Weights are assigned to witness positions, so there is no
+            // obvious weight for added witnesses.
+            SignatureThreshold::Weighted(_) => {
+                return Err(MechanicsError::WeightedWitnessThreshold)
+            }
+        };
+        Ok(Some(WitnessRotationAdvice {
+            witness_to_remove,
+            witness_to_add,
+            witness_threshold: current_threshold.min(witness_count as u64),
+        }))
+    }
+
+    /// Generates rotation event that applies
+    /// [`Identifier::witness_rotation_advice`], if there is any. Keys are
+    /// used as in [`Identifier::rotate`]. Event should be signed and
+    /// provided to [`Identifier::finalize_rotate`].
+    pub async fn propose_witness_rotation(
+        &self,
+        current_keys: Vec<BasicPrefix>,
+        new_next_keys: Vec<BasicPrefix>,
+        new_next_threshold: u64,
+        policy: &WitnessPolicy,
+    ) -> Result<Option<String>, MechanicsError> {
+        match self.witness_rotation_advice(policy)? {
+            Some(advice) => Ok(Some(
+                self.rotate(
+                    current_keys,
+                    new_next_keys,
+                    new_next_threshold,
+                    advice.witness_to_add,
+                    advice.witness_to_remove,
+                    advice.witness_threshold,
+                )
+                .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+#[test]
+fn test_witness_health() {
+    let witness: BasicPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let log = WitnessHealthLog::default();
+    assert_eq!(log.get(&witness).availability(), 1.0);
+
+    log.record(&witness, Some(Duration::from_millis(20)));
+    log.record(&witness, None);
+    let health = log.get(&witness);
+    assert_eq!(health.probes, 2);
+    assert_eq!(health.consecutive_failures, 1);
+    assert_eq!(health.last_latency, Some(Duration::from_millis(20)));
+    assert_eq!(health.availability(), 0.5);
+
+    // Only the latest probes count.
+    for _ in 0..HEALTH_WINDOW {
+        log.record(&witness, None);
+    }
+    let health = log.get(&witness);
+    assert_eq!(health.failures, 11);
+    assert_eq!(health.availability(), 0.0);
+    log.record(&witness, Some(Duration::from_millis(10)));
+    assert_eq!(log.get(&witness).consecutive_failures, 0);
+}
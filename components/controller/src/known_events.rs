@@ -1,8 +1,10 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use keri_core::actor::parse_event_stream;
-use keri_core::database::redb::RedbDatabase;
+use keri_core::actor::parse_stream;
+use keri_core::database::redb::sharded::{RedbStorage, ShardedRedbDatabase};
 use keri_core::database::sled::{DbError, SledEventDatabase};
 use keri_core::error::Error;
 use keri_core::event_message::signed_event_message::SignedNontransferableReceipt;
@@ -10,14 +12,19 @@ use keri_core::oobi::LocationScheme;
 use keri_core::prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix};
 
 use keri_core::processor::escrow::EscrowConfig;
+use keri_core::processor::kel_audit::AuditReport;
 use keri_core::processor::notification::JustNotification;
 
 use keri_core::processor::Processor;
-use keri_core::state::IdentifierState;
+use keri_core::state::{IdentifierState, StateTransition, WitnessConfig};
 use keri_core::{
     actor::{self, event_generator, prelude::SelfAddressingIdentifier},
     database::escrow::EscrowDb,
-    event::{event_data::EventData, sections::seal::Seal, KeyEvent},
+    event::{
+        event_data::EventData,
+        sections::{seal::Seal, threshold::SignatureThreshold},
+        KeyEvent,
+    },
     event_message::{
         cesr_adapter::{parse_event_type, EventType},
         msg::KeriEvent,
@@ -26,18 +33,24 @@ use keri_core::{
     oobi::{OobiManager, Role, Scheme},
     processor::{
         basic_processor::BasicProcessor,
-        escrow::{default_escrow_bus, PartiallyWitnessedEscrow},
-        event_storage::EventStorage,
+        escrow::{
+            default_escrow_bus, DelegationEscrow, OutOfOrderEscrow, PartiallySignedEscrow,
+            PartiallyWitnessedEscrow,
+        },
+        event_storage::{EventStorage, KelEntry},
     },
     query::reply_event::{ReplyEvent, ReplyRoute, SignedReply},
 };
 use teliox::database::EventDatabase;
 use teliox::processor::escrow::default_escrow_bus as tel_escrow_bus;
+use teliox::processor::notification::TelNotificationKind;
 use teliox::processor::storage::TelEventStorage;
 use teliox::tel::Tel;
 
+use crate::acceptance::{Acceptance, AcceptanceHook};
 use crate::error::ControllerError;
-use crate::identifier::mechanics::MechanicsError;
+use crate::identifier::{mechanics::MechanicsError, subscription::Subscriptions};
+use crate::locks::IdentifierLocks;
 
 #[derive(Debug, thiserror::Error)]
 pub enum OobiRetrieveError {
@@ -48,25 +61,50 @@ pub enum OobiRetrieveError {
 }
 
 pub struct KnownEvents {
-    processor: BasicProcessor<RedbDatabase>,
-    pub storage: Arc<EventStorage<RedbDatabase>>,
+    processor: BasicProcessor<RedbStorage>,
+    pub storage: Arc<EventStorage<RedbStorage>>,
     pub oobi_manager: OobiManager,
-    pub partially_witnessed_escrow: Arc<PartiallyWitnessedEscrow<RedbDatabase>>,
-    pub tel: Arc<Tel>,
+    pub partially_witnessed_escrow: Arc<PartiallyWitnessedEscrow<RedbStorage>>,
+    pub(crate) out_of_order_escrow: Arc<OutOfOrderEscrow<RedbStorage>>,
+    pub(crate) partially_signed_escrow: Arc<PartiallySignedEscrow<RedbStorage>>,
+    pub(crate) delegation_escrow: Arc<DelegationEscrow<RedbStorage>>,
+    pub tel: Arc<Tel<RedbStorage>>,
+    pub(crate) subscriptions: Arc<Subscriptions>,
+    acceptance_hook: RwLock<Option<Arc<dyn AcceptanceHook>>>,
+    /// Serializes processing of events of the same identifier, see
+    /// [`KnownEvents::locked_ids`].
+    locks: IdentifierLocks,
+    /// Reject received events, which bytes differ from their canonical
+    /// serialization.
+    pub(crate) strict_canonicalization: bool,
 }
 
 impl KnownEvents {
-    pub fn new(db_path: PathBuf, escrow_config: EscrowConfig) -> Result<Self, ControllerError> {
+    pub fn new(
+        db_path: PathBuf,
+        escrow_config: EscrowConfig,
+        max_clock_drift: Duration,
+        strict_canonicalization: bool,
+        event_shards: Option<usize>,
+    ) -> Result<Self, ControllerError> {
+        let clock = escrow_config.clock.clone();
         let event_database = {
-            let mut path = db_path.clone();
-            path.push("events_database");
-            Arc::new(RedbDatabase::new(&path)?)
+            let path = db_path.join("events_database");
+            let shards_dir = db_path.join("events_shards");
+            // Number of shards is chosen when database is created.
+            let storage = match event_shards {
+                Some(shards) if shards > 1 && !path.exists() && !shards_dir.exists() => {
+                    RedbStorage::Sharded(ShardedRedbDatabase::new(&shards_dir, shards)?)
+                }
+                _ => RedbStorage::open(&path, &shards_dir)?,
+            };
+            Arc::new(storage.with_clock(clock.clone()))
         };
 
         let db = {
             let mut path = db_path.clone();
             path.push("events");
-            Arc::new(SledEventDatabase::new(&path)?)
+            Arc::new(SledEventDatabase::new(&path)?.with_clock(clock.clone()))
         };
 
         let escrow_db = {
@@ -79,19 +117,23 @@ impl KnownEvents {
             let mut path = db_path.clone();
             path.push("oobis");
             OobiManager::new(&path)
+                .with_max_clock_drift(max_clock_drift)
+                .with_clock(clock.clone())
         };
 
         let (
             mut notification_bus,
             (
-                _out_of_order_escrow,
-                _partially_signed_escrow,
+                out_of_order_escrow,
+                partially_signed_escrow,
                 partially_witnessed_escrow,
-                _delegation_escrow,
+                delegation_escrow,
             ),
         ) = default_escrow_bus(event_database.clone(), db.clone(), escrow_db, escrow_config);
 
-        let kel_storage = Arc::new(EventStorage::new(event_database.clone(), db.clone()));
+        let kel_storage = Arc::new(
+            EventStorage::new(event_database.clone(), db.clone()).with_clock(clock.clone()),
+        );
 
         // Initiate tel and it's escrows
         let tel_events_db = {
@@ -113,6 +155,15 @@ impl KnownEvents {
             kel_storage.clone(),
             tel_escrow_db.clone(),
         )?;
+        let subscriptions = Arc::new(Subscriptions::default());
+        tel_bus.register_observer(
+            subscriptions.clone(),
+            vec![
+                TelNotificationKind::RegistryIncepted,
+                TelNotificationKind::CredentialIssued,
+                TelNotificationKind::CredentialRevoked,
+            ],
+        )?;
 
         let tel = Arc::new(Tel::new(
             tel_storage.clone(),
@@ -124,19 +175,30 @@ impl KnownEvents {
             missing_issuer.clone(),
             vec![JustNotification::KeyEventAdded],
         );
+        notification_bus
+            .register_observer(subscriptions.clone(), vec![JustNotification::KeyEventAdded]);
 
         let controller = Self {
             processor: BasicProcessor::new(
                 event_database.clone(),
                 db.clone(),
                 Some(notification_bus),
-            ),
+            )
+            .with_max_clock_drift(max_clock_drift)
+            .with_clock(clock),
             storage: kel_storage,
             oobi_manager,
             partially_witnessed_escrow,
+            out_of_order_escrow,
+            partially_signed_escrow,
+            delegation_escrow,
             // transport,
             tel,
             // tel_transport: tel_transport,
+            subscriptions,
+            acceptance_hook: RwLock::new(None),
+            locks: IdentifierLocks::default(),
+            strict_canonicalization,
         };
 
         Ok(controller)
@@ -147,6 +209,103 @@ impl KnownEvents {
         Ok(())
     }
 
+    /// Processes again escrowed events of identifier, and receipts of them.
+    /// Events that still can't be accepted stay in escrow until timeout.
+    pub fn reprocess_escrow_for(&self, id: &IdentifierPrefix) -> Result<(), MechanicsError> {
+        let out_of_order = self
+            .out_of_order_escrow
+            .escrowed_out_of_order
+            .get(id)
+            .into_iter()
+            .flatten();
+        let partially_witnessed = self
+            .partially_witnessed_escrow
+            .get_partially_witnessed_events()
+            .into_iter()
+            .filter(|event| &event.event_message.data.get_prefix() == id);
+        // Delegated events are escrowed under delegator's identifier.
+        let delegated = self
+            .delegation_escrow
+            .delegation_escrow
+            .get_all()
+            .into_iter()
+            .flatten()
+            .filter(|event| &event.event_message.data.get_prefix() == id);
+        let notices = out_of_order
+            .chain(partially_witnessed)
+            .chain(delegated)
+            .map(Notice::Event)
+            .chain(
+                self.partially_witnessed_escrow
+                    .get_escrowed_receipts_of(id)
+                    .into_iter()
+                    .map(Notice::NontransferableRct),
+            )
+            .collect::<Vec<_>>();
+        let ids = notices
+            .iter()
+            .flat_map(|notice| self.locked_ids(notice))
+            .chain(std::iter::once(id.clone()))
+            .collect::<Vec<_>>();
+        self.locks.with_locks(&ids, || {
+            notices
+                .iter()
+                .try_for_each(|notice| self.processor.process_notice(notice))
+        })?;
+        Ok(())
+    }
+
+    /// Identifiers, which locks are held while `notice` is processed.
+    /// Escrowed events of delegated identifier are accepted while event of
+    /// its delegator is processed, holding only delegator's lock. So events
+    /// of delegated identifier are processed holding delegator's lock too.
+    /// Other escrows release events of the processed identifier, or
+    /// receipts, which don't change key state of identifier they're about.
+    fn locked_ids(&self, notice: &Notice) -> Vec<IdentifierPrefix> {
+        let id = notice.get_prefix();
+        let delegator = match notice {
+            Notice::Event(event) => match &event.event_message.data.event_data {
+                EventData::Dip(dip) => Some(dip.delegator.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+        .or_else(|| {
+            self.storage
+                .get_state(&id)
+                .and_then(|state| state.delegator)
+        });
+        std::iter::once(id).chain(delegator).collect()
+    }
+
+    /// Processes again all escrowed events. Useful when missing events were
+    /// provided out of band, so escrows weren't notified about them.
+    pub fn reprocess_escrows(&self) -> Result<(), MechanicsError> {
+        let ids: HashSet<_> = self
+            .out_of_order_escrow
+            .escrowed_out_of_order
+            .get_all()
+            .into_iter()
+            .flatten()
+            .chain(
+                self.partially_witnessed_escrow
+                    .get_partially_witnessed_events(),
+            )
+            .chain(
+                self.delegation_escrow
+                    .delegation_escrow
+                    .get_all()
+                    .into_iter()
+                    .flatten(),
+            )
+            .map(|event| event.event_message.data.get_prefix())
+            .collect();
+        for id in ids {
+            self.reprocess_escrow_for(&id)?;
+        }
+        Ok(())
+    }
+
     pub fn save_oobi(&self, oobi: &SignedReply) -> Result<(), MechanicsError> {
         Ok(self.oobi_manager.process_oobi(oobi)?)
     }
@@ -176,31 +335,19 @@ impl KnownEvents {
             .next_keys_hashes())
     }
 
-    pub fn get_watchers(
-        &self,
-        id: &IdentifierPrefix,
-    ) -> Result<Vec<IdentifierPrefix>, ControllerError> {
-        Ok(self
-            .oobi_manager
-            .get_end_role(id, Role::Watcher)?
-            .unwrap_or_default()
-            .into_iter()
-            .filter_map(|r| {
-                if let ReplyRoute::EndRoleAdd(adds) = r.reply.get_route() {
-                    Some(adds.eid)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>())
-    }
-
     // Returns messages if they can be returned immediately, i.e. for query message
     pub fn process(&self, msg: &Message) -> Result<Option<Vec<Message>>, Error> {
         let response = match msg.clone() {
             Message::Op(op) => match op {
                 Op::Reply(rpy) => {
-                    actor::process_reply(rpy, &self.oobi_manager, &self.processor, &self.storage)?;
+                    self.locks.with_lock(&rpy.reply.get_prefix(), || {
+                        actor::process_reply(
+                            rpy,
+                            &self.oobi_manager,
+                            &self.processor,
+                            &self.storage,
+                        )
+                    })?;
                     None
                 }
                 Op::Query(_) => {
@@ -210,7 +357,12 @@ impl KnownEvents {
                 Op::Exchange(_) => todo!(),
             },
             Message::Notice(notice) => {
-                self.processor.process_notice(&notice)?;
+                if let Notice::Event(event) = &notice {
+                    self.check_acceptance(&event.event_message)?;
+                }
+                self.locks.with_locks(&self.locked_ids(&notice), || {
+                    self.processor.process_notice(&notice)
+                })?;
                 None
             }
         };
@@ -218,9 +370,32 @@ impl KnownEvents {
         Ok(response)
     }
 
+    pub(crate) fn set_acceptance_hook(
+        &self,
+        hook: Option<Arc<dyn AcceptanceHook>>,
+    ) -> Result<(), MechanicsError> {
+        *self
+            .acceptance_hook
+            .write()
+            .map_err(|_e| MechanicsError::LockingError)? = hook;
+        Ok(())
+    }
+
+    /// Asks registered acceptance hook if event can be processed.
+    fn check_acceptance(&self, event: &KeriEvent<KeyEvent>) -> Result<(), Error> {
+        let hook = self
+            .acceptance_hook
+            .read()
+            .map_err(|_e| Error::MutexPoisoned)?;
+        match hook.as_ref().map(|hook| hook.on_before_accept(event)) {
+            Some(Acceptance::Reject(reason)) => Err(Error::EventRejected(reason)),
+            Some(Acceptance::Allow) | None => Ok(()),
+        }
+    }
+
     /// Parse and process events stream
     pub fn process_stream(&self, stream: &[u8]) -> Result<(), ControllerError> {
-        let messages = parse_event_stream(stream)?;
+        let messages = parse_stream::<Message>(stream, self.strict_canonicalization)?;
         for message in messages {
             self.process(&message)?;
         }
@@ -286,6 +461,18 @@ impl KnownEvents {
         self.storage.get_kel_messages_with_receipts_all(id).unwrap()
     }
 
+    pub fn find_kel_typed(&self, id: &IdentifierPrefix) -> Result<Vec<KelEntry>, Error> {
+        self.storage.get_kel_typed(id)
+    }
+
+    pub fn kel_iter(&self, id: &IdentifierPrefix) -> impl Iterator<Item = Result<KelEntry, Error>> {
+        self.storage.get_kel_stream(id).with_typed_receipts()
+    }
+
+    pub fn state_history(&self, id: &IdentifierPrefix) -> Result<Vec<StateTransition>, Error> {
+        Ok(self.storage.events_db.get_state_history(id)?)
+    }
+
     pub fn find_kel(&self, id: &IdentifierPrefix) -> Option<String> {
         self.storage
             .get_kel(id)
@@ -300,7 +487,59 @@ impl KnownEvents {
         witnesses: Vec<LocationScheme>,
         witness_threshold: u64,
     ) -> Result<String, MechanicsError> {
-        let witnesses = witnesses
+        event_generator::incept(
+            public_keys,
+            next_pub_keys,
+            Self::witness_prefixes(&witnesses)?,
+            witness_threshold,
+            None,
+        )
+        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+    }
+
+    /// Generates inception event with witness threshold that can be
+    /// weighted.
+    pub fn incept_with_witness_threshold(
+        &self,
+        public_keys: Vec<BasicPrefix>,
+        next_pub_keys: Vec<BasicPrefix>,
+        witnesses: Vec<LocationScheme>,
+        witness_threshold: &SignatureThreshold,
+    ) -> Result<String, MechanicsError> {
+        event_generator::incept_with_witness_threshold(
+            public_keys,
+            next_pub_keys,
+            Self::witness_prefixes(&witnesses)?,
+            witness_threshold,
+            None,
+        )
+        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+    }
+
+    /// Generates inception event of identifier controlled by several keys,
+    /// with signing thresholds that can be weighted.
+    pub fn incept_with_thresholds(
+        &self,
+        public_keys: Vec<BasicPrefix>,
+        signature_threshold: &SignatureThreshold,
+        next_pub_keys: Vec<BasicPrefix>,
+        next_threshold: &SignatureThreshold,
+        witnesses: Vec<LocationScheme>,
+        witness_threshold: &SignatureThreshold,
+    ) -> Result<String, MechanicsError> {
+        event_generator::incept_with_thresholds(
+            public_keys,
+            signature_threshold,
+            next_pub_keys,
+            next_threshold,
+            Self::witness_prefixes(&witnesses)?,
+            witness_threshold,
+        )
+        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+    }
+
+    fn witness_prefixes(witnesses: &[LocationScheme]) -> Result<Vec<BasicPrefix>, MechanicsError> {
+        witnesses
             .iter()
             .map(|wit| {
                 if let IdentifierPrefix::Basic(bp) = &wit.eid {
@@ -309,15 +548,7 @@ impl KnownEvents {
                     Err(MechanicsError::WrongWitnessPrefixError)
                 }
             })
-            .collect::<Result<Vec<_>, _>>()?;
-        event_generator::incept(
-            public_keys,
-            next_pub_keys,
-            witnesses,
-            witness_threshold,
-            None,
-        )
-        .map_err(|e| MechanicsError::EventGenerationError(e.to_string()))
+            .collect()
     }
 
     /// Verifies event signature and adds it to kel.
@@ -437,8 +668,16 @@ impl KnownEvents {
         own_index: usize,
     ) -> Result<(), MechanicsError> {
         let signature = IndexedSignature::new_both_same(sig.clone(), own_index as u16);
+        self.finalize_key_event_with_signatures(event, vec![signature])
+    }
 
-        let signed_message = event.sign(vec![signature], None, None);
+    /// Processes own key event signed with several keys.
+    pub fn finalize_key_event_with_signatures(
+        &self,
+        event: &KeriEvent<KeyEvent>,
+        signatures: Vec<IndexedSignature>,
+    ) -> Result<(), MechanicsError> {
+        let signed_message = event.sign(signatures, None, None);
         // self.processor.process_own_event(signed_message)?;
         self.process(&Message::Notice(Notice::Event(signed_message)))?;
 
@@ -478,6 +717,14 @@ impl KnownEvents {
         Ok(state.witness_config.witnesses)
     }
 
+    /// Returns witnesses and their threshold set for `event_message`.
+    pub fn find_witness_config_at_event(
+        &self,
+        event_message: &KeriEvent<KeyEvent>,
+    ) -> Result<WitnessConfig, MechanicsError> {
+        Ok(self.get_state_at_event(event_message)?.witness_config)
+    }
+
     pub fn finalize_add_role(
         &self,
         signer_prefix: &IdentifierPrefix,
@@ -540,4 +787,13 @@ impl KnownEvents {
             .get_state(id)
             .ok_or(MechanicsError::UnknownIdentifierError(id.clone()))
     }
+
+    /// Replays stored KEL of identifier and reports first event that fails
+    /// verification. See [`EventStorage::verify_kel_integrity`].
+    pub fn verify_kel_integrity(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<AuditReport, MechanicsError> {
+        Ok(self.storage.verify_kel_integrity(id)?)
+    }
 }
@@ -1,10 +1,17 @@
+//! Internal crate of KERIOX. Its API isn't stable and can change in any
+//! release. Applications should use the `keriox` crate, which re-exports
+//! semver-stable part of it.
+pub mod acceptance;
+pub mod anchor_proof;
 pub mod config;
 pub mod error;
 // pub mod identifier_controller;
 pub mod communication;
 pub mod controller;
+pub mod escrow_status;
 pub mod identifier;
 pub mod known_events;
+mod locks;
 pub mod mailbox_updating;
 pub mod oobi;
 
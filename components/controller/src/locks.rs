@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use keri_core::{error::Error, prefix::IdentifierPrefix};
+
+/// Locks of single identifiers. Processing of event checks identifier's
+/// state before saving the event, so events of the same identifier need to
+/// be processed one at a time. Events of different identifiers don't share
+/// any lock and are processed concurrently.
+///
+/// Other shared state was audited for the same purpose:
+/// - redb allows one write transaction per database at a time, so writes of
+///   all identifiers kept in single `events_database` file wait for each
+///   other. Events can be split into shards by identifier with
+///   [`ControllerConfig::event_shards`](crate::config::ControllerConfig::event_shards).
+/// - sled trees of escrows and OOBIs are lock-free and don't serialize
+///   writes.
+#[derive(Default)]
+pub(crate) struct IdentifierLocks(Mutex<HashMap<IdentifierPrefix, Arc<Mutex<()>>>>);
+
+impl IdentifierLocks {
+    /// Runs `f` holding lock of `id`. Map of locks is locked only while
+    /// lock of `id` is looked up, not while `f` runs.
+    pub fn with_lock<T>(
+        &self,
+        id: &IdentifierPrefix,
+        f: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.with_locks(std::slice::from_ref(id), f)
+    }
+
+    /// Runs `f` holding locks of all `ids`. Locks are always taken in the
+    /// same order, so threads locking overlapping sets of identifiers can't
+    /// deadlock. Locks no other thread waits for are removed from the map
+    /// afterwards, so it doesn't grow with every identifier ever processed.
+    pub fn with_locks<T>(
+        &self,
+        ids: &[IdentifierPrefix],
+        f: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut ids = ids.to_vec();
+        ids.sort_by_cached_key(|id| id.to_str());
+        ids.dedup();
+        let locks = {
+            let mut map = self.0.lock().map_err(|_e| Error::MutexPoisoned)?;
+            ids.iter()
+                .map(|id| map.entry(id.clone()).or_default().clone())
+                .collect::<Vec<_>>()
+        };
+        let result = {
+            let _guards = locks
+                .iter()
+                .map(|lock| lock.lock().map_err(|_e| Error::MutexPoisoned))
+                .collect::<Result<Vec<_>, _>>()?;
+            f()
+        };
+        drop(locks);
+        self.prune(&ids)?;
+        result
+    }
+
+    /// Removes locks of `ids`, which are referenced only by the map.
+    fn prune(&self, ids: &[IdentifierPrefix]) -> Result<(), Error> {
+        let mut map = self.0.lock().map_err(|_e| Error::MutexPoisoned)?;
+        for id in ids {
+            if map.get(id).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+                map.remove(id);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+#[test]
+fn test_identifier_locks() {
+    use std::{sync::mpsc::channel, thread, time::Duration};
+
+    let locks = Arc::new(IdentifierLocks::default());
+    let first: IdentifierPrefix = "DAVcM7pvoz37lF1HBxFnaZQeGHKC9wVhlytEzKBfzXhV"
+        .parse()
+        .unwrap();
+    let second: IdentifierPrefix = "DApxTJjlbWOgHIMXR_qrryjCIlLFPqnaSRo2M1FFmp4I"
+        .parse()
+        .unwrap();
+
+    // Lock of other identifier can be taken while first one is held.
+    let (sender, receiver) = channel();
+    let other = locks
+        .with_lock(&first, || {
+            let locks = locks.clone();
+            let second = second.clone();
+            let handle = thread::spawn(move || {
+                locks.with_lock(&second, || {
+                    sender.send(()).unwrap();
+                    Ok(())
+                })
+            });
+            assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+            Ok(handle)
+        })
+        .unwrap();
+
+    // Lock of the same identifier waits until it's released.
+    let (sender, receiver) = channel();
+    let same = locks
+        .with_lock(&first, || {
+            let locks = locks.clone();
+            let first = first.clone();
+            let handle = thread::spawn(move || {
+                locks.with_lock(&first, || {
+                    sender.send(()).unwrap();
+                    Ok(())
+                })
+            });
+            assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+            Ok(handle)
+        })
+        .unwrap();
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+
+    // Sets of locks sharing identifiers, taken in different order, don't
+    // deadlock.
+    let handles = [other, same]
+        .into_iter()
+        .chain((0..8).map(|i| {
+            let locks = locks.clone();
+            let ids = match i % 2 {
+                0 => vec![first.clone(), second.clone()],
+                _ => vec![second.clone(), first.clone()],
+            };
+            thread::spawn(move || (0..100).try_for_each(|_| locks.with_locks(&ids, || Ok(()))))
+        }))
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap().unwrap();
+    }
+
+    // Released locks are removed from the map.
+    assert_eq!(locks.len(), 0);
+}
@@ -1,10 +1,12 @@
 // use super::{error::ControllerError, identifier_controller::IdentifierController};
 use keri_core::{
-    event::KeyEvent, event_message::msg::KeriEvent, mailbox::exchange::ExchangeMessage,
-    query::mailbox::QueryTopics,
+    event::KeyEvent,
+    event_message::msg::KeriEvent,
+    mailbox::exchange::{ExchangeMessage, ExchangeMetadata},
+    query::mailbox::{MailboxQuery, QueryTopics},
 };
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 /// Struct for tracking what was the last indexes of processed mailbox messages.
 /// Events in mailbox aren't removed after getting them, so it prevents
 /// processing the same event multiple times.
@@ -27,10 +29,21 @@ impl MailboxReminder {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ActionRequired {
-    MultisigRequest(KeriEvent<KeyEvent>, ExchangeMessage),
+    // Contains multisig event, exchange message that will be send to group
+    // identifier's mailbox after signing and metadata attached by request
+    // initiator.
+    MultisigRequest(
+        KeriEvent<KeyEvent>,
+        ExchangeMessage,
+        Option<ExchangeMetadata>,
+    ),
     // Contains delegating event and exchange message that will be send to
     // delegate after delegating event confirmation.
     DelegationRequest(KeriEvent<KeyEvent>, ExchangeMessage),
+    // Contains query for next page of truncated mailbox response, returned
+    // when query signer isn't set. It should be signed and passed to
+    // `Identifier::finalize_query_mailbox`.
+    NextMailboxPage(MailboxQuery),
 }
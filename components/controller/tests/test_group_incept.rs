@@ -70,5 +70,103 @@ async fn test_group_incept() -> Result<(), ControllerError> {
     let kel = controller.get_kel_with_receipts(&group_id);
     assert!(kel.is_some());
 
+    // Signature index is resolved from current keys of the group.
+    let data = b"some data";
+    let signature = SelfSigningPrefix::Ed25519Sha512(km2.sign(data)?);
+    let indexed = identifier2.sign_indexed(data, signature.clone(), Some(&group_id))?;
+    assert_eq!(indexed.index.current(), 1);
+    let indexed = identifier2.sign_indexed(data, signature.clone(), None)?;
+    assert_eq!(indexed.index.current(), 0);
+    // Signature made with key of other member doesn't match own keys.
+    let signature = SelfSigningPrefix::Ed25519Sha512(km1.sign(data)?);
+    assert!(matches!(
+        identifier2.sign_indexed(data, signature, None),
+        Err(ControllerError::SignatureMismatch { signature_index: 0 })
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_group_rotate() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+
+    let controller = Arc::new(Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?);
+    let mut km1 = CryptoBox::new()?;
+    let mut km2 = CryptoBox::new()?;
+
+    let mut identifiers = vec![];
+    for km in [&km1, &km2] {
+        let pk = BasicPrefix::Ed25519(km.public_key());
+        let npk = BasicPrefix::Ed25519(km.next_public_key());
+        let icp_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+        let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp_event.as_bytes())?);
+        identifiers.push(controller.finalize_incept(icp_event.as_bytes(), &signature)?);
+    }
+    let mut identifier2 = identifiers.pop().unwrap();
+    let mut identifier1 = identifiers.pop().unwrap();
+
+    let (group_inception, _exn_messages) =
+        identifier1.incept_group(vec![identifier2.id().clone()], 2, None, None, None)?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km1.sign(group_inception.as_bytes())?);
+    let group_id = identifier1
+        .finalize_group_incept(group_inception.as_bytes(), signature, vec![])
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km2.sign(group_inception.as_bytes())?);
+    identifier2
+        .finalize_group_incept(group_inception.as_bytes(), signature, vec![])
+        .await?;
+    assert_eq!(identifier1.find_state(&group_id)?.sn, 0);
+
+    // Participants rotate their own keys first.
+    for (identifier, km) in [(&mut identifier1, &mut km1), (&mut identifier2, &mut km2)] {
+        km.rotate()?;
+        let pk = BasicPrefix::Ed25519(km.public_key());
+        let npk = BasicPrefix::Ed25519(km.next_public_key());
+        let rot = identifier
+            .rotate(vec![pk], vec![npk], 1, vec![], vec![], 0)
+            .await?;
+        let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rot.as_bytes())?);
+        identifier
+            .finalize_rotate(rot.as_bytes(), signature)
+            .await?;
+    }
+
+    // Rotation initiated by second participant keeps order of keys committed
+    // in group inception.
+    let (group_rotation, exn_messages) = identifier2.rotate_group(
+        &group_id,
+        vec![identifier1.id().clone()],
+        2,
+        vec![],
+        vec![],
+        0,
+    )?;
+    assert_eq!(exn_messages.len(), 1);
+    let signature = SelfSigningPrefix::Ed25519Sha512(km2.sign(group_rotation.as_bytes())?);
+    identifier2
+        .finalize_group_rotate(group_rotation.as_bytes(), signature, vec![])
+        .await?;
+    // Rotation needs signatures of both participants.
+    assert_eq!(identifier2.find_state(&group_id)?.sn, 0);
+
+    let signature = SelfSigningPrefix::Ed25519Sha512(km1.sign(group_rotation.as_bytes())?);
+    identifier1
+        .finalize_group_rotate(group_rotation.as_bytes(), signature, vec![])
+        .await?;
+
+    let group_state = identifier1.find_state(&group_id)?;
+    assert_eq!(group_state.sn, 1);
+    assert_eq!(
+        group_state.current.public_keys,
+        vec![
+            BasicPrefix::Ed25519(km1.public_key()),
+            BasicPrefix::Ed25519(km2.public_key())
+        ]
+    );
+
     Ok(())
 }
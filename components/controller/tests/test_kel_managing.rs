@@ -1,13 +1,31 @@
 use cesrox::primitives::codes::self_addressing::SelfAddressing;
 use keri_core::{
     actor::prelude::HashFunction,
+    event::{
+        event_data::EventData,
+        sections::{seal::Seal, threshold::SignatureThreshold},
+        KeyEvent,
+    },
+    event_message::msg::KeriEvent,
     oobi::LocationScheme,
-    prefix::{BasicPrefix, SelfSigningPrefix},
-    signer::{CryptoBox, KeyManager},
+    prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
+    signer::{CryptoBox, KeyManager, KeyType, Signer},
 };
 use tempfile::Builder;
 
-use keri_controller::{config::ControllerConfig, controller::Controller, error::ControllerError};
+use keri_controller::{
+    acceptance::Acceptance,
+    anchor_proof::AnchorProof,
+    config::ControllerConfig,
+    controller::Controller,
+    error::ControllerError,
+    identifier::{
+        bundle::IdentityBundle,
+        mechanics::MechanicsError,
+        multi_key::SignatureCollection,
+        signing_request::{Operation, SigningOutcome, SigningRequest},
+    },
+};
 
 #[async_std::test]
 async fn test_kel_managing() -> Result<(), ControllerError> {
@@ -59,17 +77,288 @@ async fn test_kel_managing() -> Result<(), ControllerError> {
 
     let data_to_anchor = b"Hello world";
     let said = HashFunction::from(SelfAddressing::Blake3_256).derive(data_to_anchor);
-    let interaction_event = identifier.anchor(&[said])?;
+    let interaction_event = identifier.anchor(&[said.clone()])?;
 
     let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(interaction_event.as_bytes())?);
     identifier
         .finalize_anchor(interaction_event.as_bytes(), signature)
         .await?;
     let keys = identifier.current_public_keys()?;
-    assert_eq!(keys, vec![second_pk]);
+    assert_eq!(keys, vec![second_pk.clone()]);
     let state = identifier.find_state(identifier.id());
     assert_eq!(state.unwrap().sn, 2);
 
+    let proof = identifier
+        .get_anchoring_proof(identifier.id(), &said)
+        .unwrap();
+    assert_eq!(proof.event.data.sn, 2);
+    assert_eq!(proof.seal_index, 0);
+    assert_eq!(proof.seal(), Some(&Seal::digest(said.clone())));
+    assert_eq!(proof.signatures.len(), 1);
+    assert!(second_pk
+        .verify(&proof.event.encode()?, &proof.signatures[0].signature)
+        .unwrap());
+    let proof_json = serde_json::to_string(&proof).unwrap();
+    assert_eq!(
+        serde_json::from_str::<AnchorProof>(&proof_json).unwrap(),
+        proof
+    );
+
+    // Anchor typed seals and reference the interaction event afterwards.
+    let credential_said = HashFunction::from(SelfAddressing::Blake3_256).derive(b"credential");
+    let registry_id: IdentifierPrefix = "EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"
+        .parse()
+        .unwrap();
+    let anchoring = identifier.anchor_data(&[
+        Seal::digest(credential_said.clone()),
+        Seal::registry(registry_id, 0, credential_said.clone()),
+    ])?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(anchoring.event.as_bytes())?);
+    identifier
+        .finalize_anchor(anchoring.event.as_bytes(), signature)
+        .await?;
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(anchoring.location.prefix, *identifier.id());
+    assert_eq!(anchoring.location.sn, state.sn);
+    assert_eq!(anchoring.location.event_digest(), state.last_event_digest);
+
+    // Digests can be anchored directly.
+    let anchoring = identifier.anchor_data(&[credential_said])?;
+    assert_eq!(anchoring.location.sn, 4);
+
+    // Interaction events don't change key state, so only inception and
+    // rotation are in history.
+    let history = identifier.state_history(identifier.id())?;
+    assert_eq!(
+        history
+            .iter()
+            .map(|transition| transition.sn)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_ne!(history[0].keys_digest, history[1].keys_digest);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_signing_requests() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+
+    // Simulates transport of request to offline signer.
+    let transport = |request: SigningRequest| -> SigningRequest {
+        let transported: SigningRequest =
+            serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        assert!(transported.verify_digest());
+        transported
+    };
+
+    let first_pk = BasicPrefix::Ed25519(km.public_key());
+    let first_next_pk = BasicPrefix::Ed25519(km.next_public_key());
+    let request = controller
+        .inception_request(vec![first_pk.clone()], vec![first_next_pk], vec![], 0)
+        .await?;
+    assert_eq!(request.keys, vec![first_pk.clone()]);
+    let request = transport(request);
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(request.payload.as_bytes())?);
+    let completed = request.complete_with_signature(signature)?;
+    let mut identifier = controller.finalize_inception_request(completed)?;
+    assert_eq!(identifier.current_public_keys()?, vec![first_pk.clone()]);
+
+    // Rotation request is signed with new keys.
+    km.rotate()?;
+    let second_pk = BasicPrefix::Ed25519(km.public_key());
+    let second_next_pk = BasicPrefix::Ed25519(km.next_public_key());
+    let rotation_event = identifier
+        .rotate(
+            vec![second_pk.clone()],
+            vec![second_next_pk],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let request = identifier.signing_request(Operation::Rotation, rotation_event)?;
+    assert_eq!(request.keys, vec![second_pk.clone()]);
+    let request = transport(request);
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(request.payload.as_bytes())?);
+    let completed = request.complete_with_signature(signature)?;
+    assert!(matches!(
+        identifier.finalize_signing_request(completed).await?,
+        SigningOutcome::EventAccepted
+    ));
+    assert_eq!(identifier.current_public_keys()?, vec![second_pk.clone()]);
+
+    let said = HashFunction::from(SelfAddressing::Blake3_256).derive(b"Hello world");
+    let interaction_event = identifier.anchor(&[said])?;
+    let request = identifier.signing_request(Operation::Interaction, interaction_event)?;
+
+    // Signature of other key is rejected.
+    let other_km = CryptoBox::new()?;
+    let wrong_signature =
+        SelfSigningPrefix::Ed25519Sha512(other_km.sign(request.payload.as_bytes())?);
+    assert!(matches!(
+        request.clone().complete_with_signature(wrong_signature),
+        Err(ControllerError::SignatureMismatch { signature_index: 0 })
+    ));
+
+    // Tampered payload is rejected.
+    let mut tampered = request.clone();
+    tampered.payload.push(' ');
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(tampered.payload.as_bytes())?);
+    assert!(tampered.complete_with_signature(signature).is_err());
+
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(request.payload.as_bytes())?);
+    let completed = request.complete_with_signature(signature)?;
+    identifier.finalize_signing_request(completed).await?;
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_partial_rotation() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let km = CryptoBox::new()?;
+    // Key committed in inception event, that won't be revealed in rotation.
+    let unrevealed_pk = BasicPrefix::Ed25519(km.next_public_key());
+    let revealed_signer = Signer::new();
+    let revealed_pk = BasicPrefix::Ed25519(revealed_signer.public_key());
+
+    let inception_event = controller
+        .incept(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![unrevealed_pk.clone(), revealed_pk.clone()],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    // Key that wasn't committed can't be revealed.
+    let uncommitted_pk = BasicPrefix::Ed25519(Signer::new().public_key());
+    let result = identifier
+        .rotate_partial(
+            vec![uncommitted_pk],
+            SignatureThreshold::Simple(1),
+            vec![unrevealed_pk.clone()],
+            SignatureThreshold::Simple(1),
+            vec![],
+            vec![],
+            0,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(MechanicsError::EventGenerationError(_))
+    ));
+
+    // Reveal only the second committed key and commit to unrevealed one
+    // again, with weighted next threshold.
+    let new_next_pk = BasicPrefix::Ed25519(Signer::new().public_key());
+    let next_threshold = SignatureThreshold::single_weighted(vec![(1, 2), (1, 2)]);
+    let rotation_event = identifier
+        .rotate_partial(
+            vec![revealed_pk.clone()],
+            SignatureThreshold::Simple(1),
+            vec![unrevealed_pk, new_next_pk],
+            next_threshold.clone(),
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    // Signature of key that isn't revealed in rotation is rejected.
+    let wrong_signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    assert!(matches!(
+        identifier
+            .finalize_rotate(rotation_event.as_bytes(), wrong_signature)
+            .await,
+        Err(MechanicsError::SignatureMismatch { .. })
+    ));
+
+    let signature =
+        SelfSigningPrefix::Ed25519Sha512(revealed_signer.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+
+    assert_eq!(identifier.current_public_keys()?, vec![revealed_pk]);
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(state.sn, 1);
+    assert_eq!(state.current.next_keys_data.threshold, next_threshold);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_rotate_with_witness_threshold() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let icp = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp.as_bytes())?);
+    let mut identifier = controller.finalize_incept(icp.as_bytes(), &signature)?;
+
+    km.rotate()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    // Threshold can't be reached without witnesses.
+    let weighted = SignatureThreshold::single_weighted(vec![(1, 2)]);
+    assert!(matches!(
+        identifier
+            .rotate_with_witness_threshold(
+                vec![pk.clone()],
+                vec![npk.clone()],
+                1,
+                vec![],
+                vec![],
+                &weighted
+            )
+            .await,
+        Err(MechanicsError::EventGenerationError(_))
+    ));
+
+    let rot = identifier
+        .rotate_with_witness_threshold(
+            vec![pk.clone()],
+            vec![npk],
+            1,
+            vec![],
+            vec![],
+            &SignatureThreshold::Simple(0),
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rot.as_bytes())?);
+    identifier
+        .finalize_rotate(rot.as_bytes(), signature)
+        .await?;
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(state.sn, 1);
+    assert_eq!(state.current.public_keys, vec![pk]);
+    assert_eq!(state.witness_config.tally, SignatureThreshold::Simple(0));
+
     Ok(())
 }
 
@@ -176,3 +465,964 @@ async fn test_kel_managing_with_witness() -> Result<(), ControllerError> {
 
     Ok(())
 }
+
+#[async_std::test]
+async fn test_identity_bundle() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let inception_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    km.rotate()?;
+    let rotation_event = identifier
+        .rotate(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+
+    let (_registry_id, ixn) = identifier.incept_registry()?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(&ixn)?);
+    identifier.finalize_incept_registry(&ixn, signature).await?;
+    let credential_said = HashFunction::from(SelfAddressing::Blake3_256).derive(b"credential");
+    let (_vc_id, ixn) = identifier.issue(credential_said.clone())?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(&ixn)?);
+    identifier.finalize_issue(&ixn, signature).await?;
+
+    let bundle = identifier.export_bundle()?.to_json()?;
+
+    // Import bundle on the other device.
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let other_controller = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    let imported = other_controller.import_bundle(&IdentityBundle::from_json(&bundle)?)?;
+
+    assert_eq!(imported.id(), identifier.id());
+    assert_eq!(
+        imported.find_state(imported.id())?,
+        identifier.find_state(identifier.id())?
+    );
+    assert_eq!(
+        imported.current_public_keys()?,
+        vec![BasicPrefix::Ed25519(km.public_key())]
+    );
+    assert_eq!(
+        other_controller.list_own_identifiers()?,
+        vec![identifier.id().clone()]
+    );
+    // Issued credentials are restored too.
+    assert!(matches!(
+        imported.find_vc_state(&credential_said)?,
+        Some(teliox::state::vc_state::TelState::Issued(_))
+    ));
+
+    // Bundle can't claim KEL of other identifier.
+    let third_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let third_controller = Controller::new(ControllerConfig {
+        db_path: third_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    let other_id: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    let forged = IdentityBundle {
+        id: other_id.clone(),
+        ..IdentityBundle::from_json(&bundle)?
+    };
+    assert!(matches!(
+        third_controller.import_bundle(&forged),
+        Err(ControllerError::BundleIdentifierMismatch(id)) if id == other_id
+    ));
+    assert!(third_controller.list_own_identifiers()?.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_load_identifiers() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let config = || ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    };
+    let controller = Controller::new(config())?;
+
+    let mut ids = vec![];
+    for _ in 0..2 {
+        let km = CryptoBox::new()?;
+        let pk = BasicPrefix::Ed25519(km.public_key());
+        let npk = BasicPrefix::Ed25519(km.next_public_key());
+        let inception_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+        let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+        let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+        if ids.is_empty() {
+            let (_registry_id, ixn) = identifier.incept_registry()?;
+            let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(&ixn)?);
+            identifier.finalize_incept_registry(&ixn, signature).await?;
+        }
+        ids.push((identifier.id().clone(), identifier.registry_id().cloned()));
+    }
+    assert!(ids[0].1.is_some());
+    drop(controller);
+
+    // Identifiers and their registries are restored after restart.
+    let controller = Controller::new(config())?;
+    assert_eq!(
+        controller.list_own_identifiers()?,
+        ids.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>()
+    );
+    for (id, registry_id) in &ids {
+        let identifier = controller.load_identifier(id)?;
+        assert_eq!(identifier.registry_id(), registry_id.as_ref());
+        assert_eq!(
+            identifier.find_state(id)?.sn,
+            if registry_id.is_some() { 1 } else { 0 }
+        );
+    }
+
+    // Identifiers that aren't managed by controller can't be loaded.
+    let unknown: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    assert!(matches!(
+        controller.load_identifier(&unknown),
+        Err(ControllerError::UnknownIdentifierError)
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_reprocess_escrows() -> Result<(), ControllerError> {
+    use keri_core::{
+        database::EventDatabase,
+        event_message::signed_event_message::{Message, Notice},
+    };
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let inception_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    km.rotate()?;
+    let rotation_event = identifier
+        .rotate(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+    let kel = identifier.get_own_kel().unwrap();
+
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let other_controller = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    // Rotation is out of order without inception event.
+    other_controller
+        .known_events
+        .process(&Message::Notice(kel[1].clone()))?;
+    assert!(other_controller.find_state(identifier.id()).is_err());
+
+    // Save inception event directly in database, so escrows aren't notified.
+    let icp = match &kel[0] {
+        Notice::Event(icp) => icp.clone(),
+        _ => unreachable!(),
+    };
+    other_controller
+        .known_events
+        .storage
+        .events_db
+        .add_kel_finalized_event(icp, identifier.id())
+        .unwrap();
+    assert_eq!(other_controller.find_state(identifier.id())?.sn, 0);
+
+    other_controller.reprocess_escrow_for(identifier.id())?;
+    assert_eq!(
+        other_controller.find_state(identifier.id())?,
+        identifier.find_state(identifier.id())?
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_import_stream() -> Result<(), ControllerError> {
+    use keri_controller::controller::import::ImportProgress;
+    use keri_core::event_message::signed_event_message::Message;
+    use std::{cell::RefCell, io::Read};
+
+    /// Reader returning stream in small chunks, that split messages.
+    struct ChunkedReader<'a>(&'a [u8]);
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let inception_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    km.rotate()?;
+    let rotation_event = identifier
+        .rotate(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+
+    // Rotation goes before inception, so it needs to be escrowed.
+    let stream = identifier
+        .get_own_kel()
+        .unwrap()
+        .into_iter()
+        .rev()
+        .map(|notice| Message::Notice(notice).to_cesr())
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let other_controller = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    let reported = RefCell::new(vec![]);
+    let progress = other_controller.import_stream(ChunkedReader(&stream), |progress| {
+        reported.borrow_mut().push(progress)
+    })?;
+    let reported = reported.into_inner();
+    assert_eq!(reported.len(), 3);
+    assert_eq!(reported[0].escrowed, 1);
+    assert_eq!(reported.last(), Some(&progress));
+    assert_eq!(
+        progress,
+        ImportProgress {
+            bytes_read: stream.len() as u64,
+            processed: 2,
+            accepted: 2,
+            escrowed: 0,
+            duplicates: 0,
+        }
+    );
+    assert_eq!(
+        other_controller.find_state(identifier.id())?,
+        identifier.find_state(identifier.id())?
+    );
+
+    // Importing the same stream again only counts duplicates.
+    let progress = other_controller.import_stream(stream.as_slice(), |_| ())?;
+    assert_eq!(progress.duplicates, 2);
+    assert_eq!(progress.accepted, 0);
+
+    // Truncated stream can't be parsed.
+    assert!(matches!(
+        other_controller.import_stream(&stream[..stream.len() - 10], |_| ()),
+        Err(ControllerError::CesrFormatError)
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_escrow_status() -> Result<(), ControllerError> {
+    use keri_core::event_message::signed_event_message::Message;
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    // Inception event designating witness, that didn't receipt it yet.
+    let witness_id: BasicPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let witness_oobi: LocationScheme = serde_json::from_str(&format!(
+        r#"{{"eid":{:?},"scheme":"http","url":"http://witness.example/"}}"#,
+        witness_id
+    ))
+    .unwrap();
+    let km = CryptoBox::new()?;
+    let inception_event = controller.known_events.incept(
+        vec![BasicPrefix::Ed25519(km.public_key())],
+        vec![BasicPrefix::Ed25519(km.next_public_key())],
+        vec![witness_oobi],
+        1,
+    )?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let witnessed_id = controller
+        .known_events
+        .finalize_inception(inception_event.as_bytes(), &signature)?;
+    assert!(controller.find_state(&witnessed_id).is_err());
+
+    let status = controller.escrow_status(&witnessed_id);
+    assert_eq!(status.partially_witnessed.len(), 1);
+    let escrowed = &status.partially_witnessed[0];
+    assert_eq!(escrowed.event.sn, 0);
+    assert!(escrowed.receipted_by.is_empty());
+    assert_eq!(escrowed.missing_witnesses, vec![witness_id]);
+    assert!(status.out_of_order.is_empty());
+
+    // Rotation event without inception is out of order.
+    let mut km = CryptoBox::new()?;
+    let inception_event = controller
+        .incept(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+    km.rotate()?;
+    let rotation_event = identifier
+        .rotate(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+    assert!(controller.escrow_status(identifier.id()).is_empty());
+    let kel = identifier.get_own_kel().unwrap();
+
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let other_controller = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    other_controller
+        .known_events
+        .process(&Message::Notice(kel[1].clone()))?;
+    let status = other_controller.escrow_status(identifier.id());
+    assert_eq!(status.out_of_order.len(), 1);
+    assert_eq!(status.out_of_order[0].sn, 1);
+    assert!(status.partially_witnessed.is_empty());
+
+    // Escrow is cleared when missing event arrives.
+    other_controller
+        .known_events
+        .process(&Message::Notice(kel[0].clone()))?;
+    assert!(other_controller.escrow_status(identifier.id()).is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_loc_scheme_reply() -> Result<(), ControllerError> {
+    use keri_core::{
+        actor::parse_op_stream,
+        event_message::{
+            signature::Signature,
+            signed_event_message::{Message, Op},
+        },
+        oobi::Scheme,
+        query::reply_event::ReplyRoute,
+    };
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+    let km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let inception_event = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    let url = url::Url::parse("http://localhost:3232").unwrap();
+    let rpy = identifier.generate_loc_scheme_reply(url.clone(), Scheme::Http)?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rpy.as_bytes())?);
+    identifier.finalize_loc_scheme_reply(rpy.as_bytes(), signature)?;
+
+    let response = identifier.oobi_response(identifier.id())?;
+    let replies = parse_op_stream(&response).unwrap();
+    assert_eq!(replies.len(), 1);
+    let Op::Reply(reply) = &replies[0] else {
+        unreachable!()
+    };
+    assert!(matches!(reply.reply.get_route(), ReplyRoute::LocScheme(loc) if loc.url == url));
+
+    // Other controller accepts reply after getting identifier's KEL.
+    let verifier_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let verifier = Controller::new(ControllerConfig {
+        db_path: verifier_root.path().to_owned(),
+        ..Default::default()
+    })?;
+    for notice in controller.get_kel_with_receipts(identifier.id()).unwrap() {
+        verifier.known_events.save(&Message::Notice(notice))?;
+    }
+    verifier.known_events.process_stream(&response)?;
+    let locations = verifier
+        .known_events
+        .get_loc_schemas(identifier.id())
+        .unwrap();
+    assert_eq!(
+        locations,
+        vec![LocationScheme::new(
+            identifier.id().clone(),
+            Scheme::Http,
+            url.clone()
+        )]
+    );
+
+    // Signature of multi-key identifier is indexed with position of the key
+    // that made it.
+    let devices = [CryptoBox::new()?, CryptoBox::new()?];
+    let icp = controller
+        .incept_multi_key(
+            devices
+                .iter()
+                .map(|km| BasicPrefix::Ed25519(km.public_key()))
+                .collect(),
+            &SignatureThreshold::Simple(1),
+            devices
+                .iter()
+                .map(|km| BasicPrefix::Ed25519(km.next_public_key()))
+                .collect(),
+            &SignatureThreshold::Simple(1),
+            vec![],
+            &SignatureThreshold::Simple(0),
+        )
+        .await?;
+    let mut collection = SignatureCollection::inception(icp)?;
+    collection.sign_with(&devices[1])?;
+    let multi_key = controller.finalize_multi_key_incept(&collection)?;
+    let rpy = multi_key.generate_loc_scheme_reply(url, Scheme::Http)?;
+    assert!(matches!(
+        multi_key.finalize_loc_scheme_reply(
+            rpy.as_bytes(),
+            SelfSigningPrefix::Ed25519Sha512(km.sign(rpy.as_bytes())?)
+        ),
+        Err(ControllerError::SignatureMismatch { .. })
+    ));
+    let signature = SelfSigningPrefix::Ed25519Sha512(devices[1].sign(rpy.as_bytes())?);
+    let reply = multi_key.finalize_loc_scheme_reply(rpy.as_bytes(), signature)?;
+    match reply.signature {
+        Signature::Transferable(_, signatures) => {
+            assert_eq!(signatures.len(), 1);
+            assert_eq!(signatures[0].index.current(), 1);
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_subscribe_kel() -> Result<(), ControllerError> {
+    use futures::StreamExt;
+    use keri_controller::identifier::subscription::{IdentifierEvent, Topic};
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let km = CryptoBox::new()?;
+    let inception_event = controller
+        .incept(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    let mut kel_events = identifier.subscribe(Topic::Kel);
+    let receipts = identifier.subscribe(Topic::Receipts);
+
+    let said = HashFunction::from(SelfAddressing::Blake3_256).derive(b"some data");
+    let interaction_event = identifier.anchor(&[said])?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(interaction_event.as_bytes())?);
+    identifier
+        .finalize_anchor(interaction_event.as_bytes(), signature)
+        .await?;
+
+    match kel_events.next().await {
+        Some(IdentifierEvent::KeyEventAccepted(event)) => {
+            assert_eq!(&event.event_message.data.get_prefix(), identifier.id());
+            assert_eq!(event.event_message.data.get_sn(), 1);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    };
+    // Dropped subscription doesn't block publishing.
+    drop(receipts);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_rotation_readiness() -> Result<(), ControllerError> {
+    use keri_controller::identifier::rotation_readiness::NextKeyStatus;
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let inception_event = controller
+        .incept(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    let readiness = identifier.rotation_readiness(identifier.id(), &km).await?;
+    assert!(readiness.is_ready());
+    assert_eq!(readiness.next_key, NextKeyStatus::Available);
+    assert_eq!(readiness.cosigners, 0);
+    assert!(!readiness.cosigners_required);
+
+    // Keys rotated only in key manager don't match committed next key.
+    km.rotate()?;
+    let readiness = identifier.rotation_readiness(identifier.id(), &km).await?;
+    assert!(!readiness.is_ready());
+    assert_eq!(readiness.next_key, NextKeyStatus::Mismatch);
+    assert!(readiness.cosigners_required);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_acceptance_hook() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+    // Forbid rotations.
+    controller.on_before_accept(|event: &KeriEvent<KeyEvent>| {
+        match event.data.get_event_data() {
+            EventData::Rot(_) => Acceptance::Reject("rotation not allowed".to_string()),
+            _ => Acceptance::Allow,
+        }
+    })?;
+
+    let mut km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let inception_event = controller
+        .incept(vec![pk.clone()], vec![npk], vec![], 0)
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(inception_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(inception_event.as_bytes(), &signature)?;
+
+    km.rotate()?;
+    let rotation_event = identifier
+        .rotate(
+            vec![BasicPrefix::Ed25519(km.public_key())],
+            vec![BasicPrefix::Ed25519(km.next_public_key())],
+            1,
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(rotation_event.as_bytes())?);
+    let result = identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature.clone())
+        .await;
+    assert!(matches!(
+        result,
+        Err(MechanicsError::EventProcessingError(
+            keri_core::error::Error::EventRejected(_)
+        ))
+    ));
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 0);
+    assert_eq!(identifier.current_public_keys()?, vec![pk]);
+
+    // Rotation is accepted after hook is removed.
+    controller.clear_acceptance_hook()?;
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_profiles() -> Result<(), ControllerError> {
+    use std::{collections::HashMap, io::Write, sync::Arc};
+
+    use keri_controller::config::ProfileError;
+    use keri_core::{
+        prefix::IdentifierPrefix,
+        transport::test::{TestActorMap, TestTransport},
+    };
+    use url::{Host, Url};
+    use witness::{WitnessEscrowConfig, WitnessListener};
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let witness = {
+        let witness_root = Builder::new().prefix("test-wit1-db").tempdir().unwrap();
+        Arc::new(
+            WitnessListener::setup(
+                Url::parse("http://witness1:3232/").unwrap(),
+                witness_root.path(),
+                Some("AK8F6AAiYDpXlWdj2O5F5-6wNCCNJh2A4XOlqwR_HwwH".to_string()),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let witness_id = IdentifierPrefix::Basic(witness.get_prefix());
+    let mut actors: TestActorMap = HashMap::new();
+    actors.insert((Host::Domain("witness1".to_string()), 3232), witness);
+
+    let mut profiles_file = tempfile::NamedTempFile::new().unwrap();
+    write!(
+        profiles_file,
+        r#"{{"dev": {{"witnesses": [{{"eid": "{}", "scheme": "http", "url": "http://witness1:3232/"}}]}}, "prod": {{}}}}"#,
+        witness_id
+    )
+    .unwrap();
+
+    let config = ControllerConfig {
+        db_path: root.path().to_owned(),
+        transport: Box::new(TestTransport::new(actors)),
+        ..Default::default()
+    }
+    .with_profiles_file(profiles_file.path())?;
+    assert_eq!(config.profiles.len(), 2);
+    let controller = Controller::init(config)
+        .await?
+        .select_profile("dev")
+        .await?;
+
+    // Witness location was resolved when profile was selected.
+    let witnesses = controller.profile().unwrap().witnesses.clone();
+    assert_eq!(witnesses.len(), 1);
+    assert!(!controller
+        .known_events
+        .get_loc_schemas(&witness_id)
+        .unwrap()
+        .is_empty());
+
+    let km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let icp_event = controller.incept(vec![pk], vec![npk], witnesses, 1).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(icp_event.as_bytes(), &signature)?;
+    assert_eq!(identifier.notify_witnesses().await?, 1);
+
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let result = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        ..Default::default()
+    })?
+    .with_profile("staging");
+    assert!(matches!(
+        result,
+        Err(ControllerError::ProfileError(ProfileError::UnknownProfile(
+            _
+        )))
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_multi_key_identifier() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    // Identifier is controlled by keys of three devices, any two of them
+    // can sign.
+    let mut devices = vec![CryptoBox::new()?, CryptoBox::new()?, CryptoBox::new()?];
+    let keys = |devices: &[CryptoBox]| -> (Vec<BasicPrefix>, Vec<BasicPrefix>) {
+        devices
+            .iter()
+            .map(|km| {
+                (
+                    BasicPrefix::Ed25519(km.public_key()),
+                    BasicPrefix::Ed25519(km.next_public_key()),
+                )
+            })
+            .unzip()
+    };
+    let (pks, npks) = keys(&devices);
+    let icp = controller
+        .incept_multi_key(
+            pks.clone(),
+            &SignatureThreshold::Simple(2),
+            npks,
+            &SignatureThreshold::Simple(2),
+            vec![],
+            &SignatureThreshold::Simple(0),
+        )
+        .await?;
+
+    let mut collection = SignatureCollection::inception(icp)?;
+    collection.sign_with(&devices[0])?;
+    assert!(!collection.is_complete());
+    assert_eq!(collection.missing_keys(), pks[1..].to_vec());
+    assert!(matches!(
+        controller.finalize_multi_key_incept(&collection),
+        Err(ControllerError::ThresholdNotReached(missing)) if missing == pks[1..]
+    ));
+
+    // Collection is passed to the second device.
+    let mut collection: SignatureCollection =
+        serde_json::from_str(&serde_json::to_string(&collection)?)?;
+    collection.sign_with(&devices[1])?;
+    assert!(collection.is_complete());
+    let mut identifier = controller.finalize_multi_key_incept(&collection)?;
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(state.current.public_keys, pks);
+    assert_eq!(state.current.threshold, SignatureThreshold::Simple(2));
+
+    // Signature of unrelated key is rejected.
+    let ixn = identifier.anchor(&[])?;
+    let mut collection = identifier.collect_signatures(ixn)?;
+    assert!(matches!(
+        collection.sign_with(&CryptoBox::new()?),
+        Err(ControllerError::SignatureMismatch { .. })
+    ));
+    collection.sign_with(&devices[1])?;
+    collection.sign_with(&devices[2])?;
+    identifier.finalize_multi_key_event(&collection).await?;
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 1);
+
+    for km in devices.iter_mut() {
+        km.rotate()?;
+    }
+    let (pks, npks) = keys(&devices);
+    let rot = identifier
+        .rotate_partial(
+            pks.clone(),
+            SignatureThreshold::Simple(2),
+            npks,
+            SignatureThreshold::Simple(2),
+            vec![],
+            vec![],
+            0,
+        )
+        .await?;
+    let mut collection = identifier.collect_signatures(rot)?;
+    collection.sign_with(&devices[0])?;
+    collection.sign_with(&devices[2])?;
+    identifier.finalize_multi_key_event(&collection).await?;
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(state.sn, 2);
+    assert_eq!(state.current.public_keys, pks);
+    assert_eq!(state.current.threshold, SignatureThreshold::Simple(2));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_key_types() -> Result<(), ControllerError> {
+    for key_type in [KeyType::ECDSAsecp256k1, KeyType::Ed448] {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let controller = Controller::new(ControllerConfig {
+            db_path: root.path().to_owned(),
+            ..Default::default()
+        })?;
+
+        let mut km = CryptoBox::with_key_type(key_type)?;
+        let pk = key_type.basic_prefix(km.public_key());
+        let npk = key_type.basic_prefix(km.next_public_key());
+        let icp = controller
+            .incept(vec![pk.clone()], vec![npk], vec![], 0)
+            .await?;
+        let signature = key_type.signature(km.sign(icp.as_bytes())?);
+        let mut identifier = controller.finalize_incept(icp.as_bytes(), &signature)?;
+        assert_eq!(identifier.current_public_keys()?, vec![pk]);
+
+        km.rotate()?;
+        let pk = key_type.basic_prefix(km.public_key());
+        let npk = key_type.basic_prefix(km.next_public_key());
+        let rot = identifier
+            .rotate(vec![pk.clone()], vec![npk], 1, vec![], vec![], 0)
+            .await?;
+        let signature = key_type.signature(km.sign(rot.as_bytes())?);
+        identifier
+            .finalize_rotate(rot.as_bytes(), signature)
+            .await?;
+        assert_eq!(identifier.current_public_keys()?, vec![pk]);
+
+        let ixn = identifier.anchor(&[])?;
+        let signature = key_type.signature(km.sign(ixn.as_bytes())?);
+        identifier
+            .finalize_anchor(ixn.as_bytes(), signature)
+            .await?;
+        assert_eq!(identifier.find_state(identifier.id())?.sn, 2);
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_structured_errors() -> Result<(), ControllerError> {
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        ..Default::default()
+    })?;
+
+    let km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let icp = controller.incept(vec![pk], vec![npk], vec![], 0).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp.as_bytes())?);
+    let identifier = controller.finalize_incept(icp.as_bytes(), &signature)?;
+
+    // Index of signature that doesn't match identifier's keys is reported.
+    let data = "{\"data\":\"some data\"}";
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(data.as_bytes())?);
+    let other_km = CryptoBox::new()?;
+    let wrong_signature = SelfSigningPrefix::Ed25519Sha512(other_km.sign(data.as_bytes())?);
+    assert!(matches!(
+        identifier.sign_to_cesr(data, &[signature, wrong_signature]),
+        Err(ControllerError::SignatureMismatch { signature_index: 1 })
+    ));
+
+    let digest = HashFunction::from(SelfAddressing::Blake3_256).derive(b"credential");
+    assert!(matches!(
+        identifier.issue(digest),
+        Err(ControllerError::RegistryNotIncepted(id)) if &id == identifier.id()
+    ));
+
+    assert!(matches!(
+        IdentityBundle::from_json("not a bundle"),
+        Err(ControllerError::JsonError(_))
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_quorum_publishing() -> Result<(), ControllerError> {
+    use std::{collections::HashMap, sync::Arc};
+
+    use keri_controller::identifier::mechanics::notify_witness::PublishingMode;
+    use keri_core::{
+        oobi::Scheme,
+        transport::test::{TestActorMap, TestTransport},
+    };
+    use url::{Host, Url};
+    use witness::{WitnessEscrowConfig, WitnessListener};
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let mut actors: TestActorMap = HashMap::new();
+    let mut locations = vec![];
+    for port in [3232, 3233, 3234] {
+        let url = Url::parse(&format!("http://witness:{}/", port)).unwrap();
+        let witness_root = Builder::new().prefix("test-wit-db").tempdir().unwrap();
+        let witness = Arc::new(
+            WitnessListener::setup(
+                url.clone(),
+                witness_root.path(),
+                None,
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        );
+        locations.push(LocationScheme {
+            eid: IdentifierPrefix::Basic(witness.get_prefix()),
+            scheme: Scheme::Http,
+            url,
+        });
+        actors.insert((Host::Domain("witness".to_string()), port), witness);
+    }
+
+    let controller = Controller::new(ControllerConfig {
+        db_path: root.path().to_owned(),
+        transport: Box::new(TestTransport::new(actors)),
+        ..Default::default()
+    })?;
+    let km = CryptoBox::new()?;
+    let pk = BasicPrefix::Ed25519(km.public_key());
+    let npk = BasicPrefix::Ed25519(km.next_public_key());
+    let icp_event = controller.incept(vec![pk], vec![npk], locations, 2).await?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(km.sign(icp_event.as_bytes())?);
+    let mut identifier = controller.finalize_incept(icp_event.as_bytes(), &signature)?;
+
+    // Receipts returned by witnesses are processed right away, so event is
+    // accepted without querying mailbox.
+    identifier.set_publishing_mode(PublishingMode::Quorum);
+    assert_eq!(identifier.notify_witnesses().await?, 1);
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 0);
+
+    Ok(())
+}
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
 use keri_controller::{
-    config::ControllerConfig, controller::Controller, error::ControllerError, BasicPrefix,
-    CryptoBox, KeyManager, SelfSigningPrefix,
+    config::ControllerConfig, controller::Controller, error::ControllerError,
+    identifier::subscription::TelEvent, BasicPrefix, CryptoBox, KeyManager, SelfSigningPrefix,
 };
 use keri_core::actor::prelude::{HashFunction, HashFunctionCode};
 
 #[async_std::test]
 async fn test_tel() -> Result<(), ControllerError> {
+    use futures::StreamExt;
     use tempfile::Builder;
 
     // Incept keri identifier
@@ -35,9 +36,10 @@ async fn test_tel() -> Result<(), ControllerError> {
         .unwrap();
 
     let issuer_prefix = identifier1.id().clone();
+    let mut tel_events = controller1.subscribe_tel();
 
     // Incept management TEL
-    let (_registry_id, ixn) = identifier1.incept_registry().unwrap();
+    let (registry_id, ixn) = identifier1.incept_registry().unwrap();
     let signature = SelfSigningPrefix::Ed25519Sha512(km1.sign(&ixn).unwrap());
 
     identifier1
@@ -97,5 +99,25 @@ async fn test_tel() -> Result<(), ControllerError> {
         Some(teliox::state::vc_state::TelState::Revoked)
     ));
 
+    // Registry and credential changes were published.
+    let vc_id = keri_controller::IdentifierPrefix::self_addressing(vc_hash);
+    assert_eq!(
+        tel_events.next().await,
+        Some(TelEvent::RegistryIncepted {
+            registry_id: registry_id.clone()
+        })
+    );
+    assert_eq!(
+        tel_events.next().await,
+        Some(TelEvent::CredentialIssued {
+            registry_id: registry_id.clone(),
+            vc_id: vc_id.clone()
+        })
+    );
+    assert_eq!(
+        tel_events.next().await,
+        Some(TelEvent::CredentialRevoked { registry_id, vc_id })
+    );
+
     Ok(())
 }
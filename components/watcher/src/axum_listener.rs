@@ -0,0 +1,41 @@
+//! Watcher endpoints served with [axum](https://docs.rs/axum), for
+//! applications built on tower/hyper stack. Requests are answered by the
+//! same router as in actix based [`WatcherListener::listen_http`], so both
+//! listeners can be used interchangeably.
+use std::sync::Arc;
+
+use actor_server::{axum_listener::actor_router, AdminToken};
+use axum::Router;
+
+use crate::{
+    watcher::Watcher,
+    watcher_listener::{update_checking, update_tel_checking},
+    WatcherListener,
+};
+
+/// Returns router with all watcher endpoints. It can be merged or nested
+/// into existing axum application, and wrapped with tower middlewares.
+/// Admin endpoints are disabled. Background tasks updating KELs and TELs
+/// need to be spawned separately, see [`WatcherListener::listen_axum`].
+pub fn watcher_router(data: Arc<Watcher>) -> Router {
+    watcher_router_with_admin_token(data, AdminToken(None))
+}
+
+/// Returns router with all watcher endpoints, like [`watcher_router`], but
+/// with admin endpoints enabled for requests carrying `admin_token`.
+pub fn watcher_router_with_admin_token(data: Arc<Watcher>, admin_token: AdminToken) -> Router {
+    actor_router(data, admin_token)
+}
+
+impl WatcherListener {
+    /// Serves watcher endpoints with axum on given address. Must be called
+    /// within tokio runtime.
+    pub async fn listen_axum(self, addr: impl tokio::net::ToSocketAddrs) -> std::io::Result<()> {
+        tokio::spawn(update_tel_checking(self.watcher.clone()));
+        tokio::spawn(update_checking(self.watcher.clone()));
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let router = watcher_router_with_admin_token(self.watcher, AdminToken(self.admin_token));
+        axum::serve(listener, router).await
+    }
+}
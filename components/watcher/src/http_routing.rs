@@ -1,38 +1,18 @@
-use crate::watcher_listener::http_handlers;
-use actix_web::web;
+use std::sync::Arc;
 
-pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.route(
-        "/introduce",
-        actix_web::web::get().to(http_handlers::introduce),
-    )
-    .route(
-        "/oobi/{id}",
-        actix_web::web::get().to(http_handlers::resolve_location),
-    )
-    .route(
-        "/oobi/{cid}/{role}/{eid}",
-        actix_web::web::get().to(http_handlers::resolve_role),
-    )
-    .route(
-        "/process",
-        actix_web::web::post().to(http_handlers::process_notice),
-    )
-    .route(
-        "/query",
-        actix_web::web::post().to(http_handlers::process_query),
-    )
-    .route(
-        "/register",
-        actix_web::web::post().to(http_handlers::process_reply),
-    )
-    .route(
-        "/resolve",
-        actix_web::web::post().to(http_handlers::resolve_oobi),
-    )
-    .route(
-        "/query/tel",
-        actix_web::web::post().to(http_handlers::process_tel_query),
-    )
-    .route("info", actix_web::web::get().to(http_handlers::info));
+use crate::watcher::Watcher;
+use actix_web::Scope;
+use actor_server::actix_listener::actor_scope;
+
+/// Returns service with all watcher endpoints, that can be mounted in
+/// existing actix application, for example:
+/// `App::new().service(web::scope("/watcher").service(watcher_scope(data)))`.
+/// Admin endpoints are disabled, unless token is provided with
+/// `.app_data(web::Data::new(AdminToken(Some(token))))` on returned scope.
+/// Watcher updates identifiers' KELs in background tasks, so
+/// [`update_checking`](crate::watcher_listener::update_checking) and
+/// [`update_tel_checking`](crate::watcher_listener::update_tel_checking)
+/// need to be spawned by application as well.
+pub fn watcher_scope(data: Arc<Watcher>) -> Scope {
+    actor_scope(data)
 }
@@ -1,9 +1,21 @@
+pub use actor_server::{
+    actix_listener::{configure_routes, correlate},
+    AdminToken,
+};
+
 pub use crate::{
+    http_routing::watcher_scope,
+    metrics::WatcherMetrics,
     watcher::{config::WatcherConfig, Watcher},
     watcher_listener::WatcherListener,
 };
 
+#[cfg(feature = "axum")]
+pub mod axum_listener;
 mod http_routing;
+mod metrics;
+#[cfg(feature = "tcp")]
+pub mod tcp_listener;
 #[cfg(test)]
 mod test;
 mod watcher;
@@ -1,22 +1,30 @@
-use std::{net::Ipv4Addr, path::PathBuf, time::Duration};
+use std::{net::Ipv4Addr, path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::Context;
-use clap::Parser;
-use figment::{
-    providers::{Env, Format, Serialized, Yaml},
-    Figment,
+use actor_server::{
+    config,
+    service::{self, LogConfig, LogTarget, NotifyState, PidFile},
 };
+use anyhow::{bail, Context};
+use clap::Parser;
+use futures::future::{self, BoxFuture};
 use keri_core::{
+    actor::stream_log::StreamLog,
+    mailbox::DEFAULT_MAILBOX_MAX_SIZE,
     oobi::{LocationScheme, Scheme},
     prefix::{CesrPrimitive, IdentifierPrefix},
     processor::escrow::EscrowConfig,
+    query::reply_event::DEFAULT_MAX_CLOCK_DRIFT,
+    signer::KeystoreConfig,
     transport::default::DefaultTransport,
 };
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DurationSeconds};
-use teliox::transport::TelTransport;
+use teliox::transport::DefaultTelTransport;
 use url::Url;
-use watcher::{WatcherConfig, WatcherListener};
+use watcher::{
+    watcher_listener::{update_checking, update_tel_checking},
+    WatcherConfig, WatcherListener,
+};
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -26,17 +34,70 @@ pub struct Config {
     public_url: Url,
 
     /// HTTP listen port.
-    http_port: u16,
+    http_port: Option<u16>,
+
+    /// Framed TCP listen port. At least one of `http_port` and `tcp_port`
+    /// needs to be set.
+    tcp_port: Option<u16>,
 
     /// Witness private key
     seed: Option<String>,
 
+    /// Path of file containing seed. Alternative to `seed`.
+    seed_file: Option<PathBuf>,
+
+    /// Name of environment variable containing seed. Alternative to `seed`.
+    seed_env: Option<String>,
+
+    /// Keystore containing watcher key, alternative to `seed`. Key is
+    /// generated if keystore doesn't contain it yet.
+    keystore: Option<KeystoreConfig>,
+
     initial_oobis: Vec<LocationScheme>,
 
     #[serde(default, deserialize_with = "deserialize_escrow_config")]
     escrow_config: EscrowConfig,
 
     tel_storage_path: PathBuf,
+
+    /// Token required by admin endpoints. Admin endpoints are disabled if
+    /// not set.
+    admin_token: Option<String>,
+
+    /// File where process id is written. It's removed on shutdown.
+    pid_file: Option<PathBuf>,
+
+    /// File of debug log of processed streams. `streams.log` in `db_path`
+    /// by default.
+    stream_log_file: Option<PathBuf>,
+
+    /// Whether processed streams are logged from start. Log can also be
+    /// toggled at runtime with admin endpoint.
+    #[serde(default)]
+    stream_log_enabled: bool,
+
+    /// Whether messages, which bytes differ from their canonical
+    /// serialization, are rejected.
+    #[serde(default)]
+    strict_canonicalization: bool,
+
+    /// Maximal difference, in seconds, between timestamp of query and
+    /// watcher clock. If set, queries out of it and queries processed before
+    /// are rejected.
+    query_max_skew: Option<u64>,
+
+    /// SQLite file shared by watcher replicas running behind load balancer.
+    shared_backend: Option<PathBuf>,
+
+    /// Maximal size of mailbox response in bytes.
+    mailbox_max_size: Option<usize>,
+
+    /// How far in the future, in seconds, timestamps of accepted replies and
+    /// key state notices can be.
+    max_clock_drift: Option<u64>,
+
+    #[serde(flatten)]
+    log: LogConfig,
 }
 
 #[serde_as]
@@ -87,6 +148,7 @@ where
             .delegation_timeout
             .or(config.default_timeout)
             .unwrap_or(EscrowConfig::default().delegation_timeout),
+        ..EscrowConfig::default()
     })
 }
 
@@ -111,9 +173,25 @@ struct Args {
     #[serde(skip_serializing_if = "Option::is_none")]
     http_port: Option<u16>,
 
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tcp_port: Option<u16>,
+
     #[arg(short = 's', long)]
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<String>,
+
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid_file: Option<PathBuf>,
+
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_target: Option<LogTarget>,
+
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_file: Option<PathBuf>,
 }
 
 const ENV_PREFIX: &str = "WATCHER_";
@@ -123,22 +201,62 @@ async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     println!("Using config file: {:?}", args.config_file);
 
-    let cfg = Figment::new()
-        .merge(Yaml::file(args.config_file.clone()))
-        .merge(Env::prefixed(ENV_PREFIX))
-        .merge(Serialized::defaults(args))
-        .extract::<Config>()
-        .context("Failed to load config")?;
+    let config_file = args.config_file.clone();
+    let cfg: Config = config::load_config(&config_file, ENV_PREFIX, args)?;
+
+    if cfg.http_port.is_none() && cfg.tcp_port.is_none() {
+        bail!("At least one of `http_port` and `tcp_port` needs to be set");
+    }
 
+    let _logger = service::init_logging(&cfg.log)?;
+    let _pid_file = cfg.pid_file.clone().map(PidFile::create).transpose()?;
+
+    let stream_log_file = cfg
+        .stream_log_file
+        .clone()
+        .unwrap_or_else(|| cfg.db_path.join("streams.log"));
+    let stream_log = StreamLog::new(&stream_log_file)
+        .with_max_size(cfg.log.log_max_size)
+        .with_max_files(cfg.log.log_max_files);
+    stream_log.set_enabled(cfg.stream_log_enabled);
+
+    let priv_key = config::resolve_seed(
+        cfg.seed.as_deref(),
+        cfg.seed_file.as_deref(),
+        cfg.seed_env.as_deref(),
+    )?;
+    let signer = match &cfg.keystore {
+        Some(_) if priv_key.is_some() => bail!("Only one of seed and `keystore` can be set"),
+        Some(keystore) => Some(Arc::new(
+            keystore
+                .signer("watcher")
+                .context("Failed to open keystore")?,
+        )),
+        None => None,
+    };
     let watcher_listener = WatcherListener::new(WatcherConfig {
         public_address: cfg.public_url.clone(),
         db_path: cfg.db_path.clone(),
-        priv_key: cfg.seed,
+        priv_key,
+        signer,
         transport: Box::new(DefaultTransport::new()),
-        tel_transport: Box::new(TelTransport),
+        tel_transport: Box::new(DefaultTelTransport::new()),
         escrow_config: cfg.escrow_config,
         tel_storage_path: cfg.tel_storage_path,
+        stream_log: Some(stream_log),
+        strict_canonicalization: cfg.strict_canonicalization,
+        query_max_skew: cfg.query_max_skew.map(Duration::from_secs),
+        shared_backend: cfg.shared_backend,
+        mailbox_max_size: cfg.mailbox_max_size.unwrap_or(DEFAULT_MAILBOX_MAX_SIZE),
+        max_clock_drift: cfg
+            .max_clock_drift
+            .map_or(DEFAULT_MAX_CLOCK_DRIFT, Duration::from_secs),
+        ..Default::default()
     })?;
+    let watcher_listener = match cfg.admin_token {
+        Some(token) => watcher_listener.with_admin_token(token),
+        None => watcher_listener,
+    };
 
     // Resolve oobi to know how to find witness
     watcher_listener
@@ -147,23 +265,52 @@ async fn main() -> anyhow::Result<()> {
     let watcher_id = watcher_listener.get_prefix();
     let watcher_loc_scheme = LocationScheme {
         eid: IdentifierPrefix::Basic(watcher_id.clone()),
-        scheme: Scheme::Http,
+        scheme: cfg.public_url.scheme().parse().unwrap_or(Scheme::Http),
         url: cfg.public_url.clone(),
     };
 
     println!(
-        "Watcher {} is listening on port {}",
+        "Watcher {} is listening on HTTP port {:?} and TCP port {:?}",
         watcher_id.to_str(),
         cfg.http_port,
+        cfg.tcp_port,
     );
     println!(
         "Watcher's oobi: {}",
         serde_json::to_string(&watcher_loc_scheme).unwrap()
     );
 
-    watcher_listener
-        .listen_http((Ipv4Addr::UNSPECIFIED, cfg.http_port))
-        .await?;
+    let tcp_handle: BoxFuture<'static, std::io::Result<()>> = match cfg.tcp_port {
+        #[cfg(feature = "tcp")]
+        Some(port) => Box::pin(watcher_listener.listen_tcp((Ipv4Addr::UNSPECIFIED, port))),
+        #[cfg(not(feature = "tcp"))]
+        Some(_) => bail!("Watcher was built without `tcp` feature"),
+        None => Box::pin(future::ok(())),
+    };
+    let http_handle = match cfg.http_port {
+        Some(port) => Some(watcher_listener.listen_http((Ipv4Addr::UNSPECIFIED, port))),
+        None => {
+            // HTTP listener runs background tasks, so they need to be
+            // spawned here.
+            actix_web::rt::spawn(update_tel_checking(watcher_listener.watcher.clone()));
+            actix_web::rt::spawn(update_checking(watcher_listener.watcher.clone()));
+            None
+        }
+    };
+    // HTTP socket is already bound and TCP one is bound as soon as
+    // listeners are polled, so watcher can accept requests.
+    service::notify(NotifyState::Ready);
+    future::try_join(
+        async {
+            match http_handle {
+                Some(handle) => handle.await,
+                None => Ok(()),
+            }
+        },
+        tcp_handle,
+    )
+    .await?;
+    service::notify(NotifyState::Stopping);
 
     Ok(())
 }
@@ -0,0 +1,75 @@
+use keri_core::actor::simple_controller::PossibleResponse;
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+
+/// Content type of metrics in Prometheus text format.
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Prometheus metrics of watcher, served at `/metrics`.
+pub struct WatcherMetrics {
+    registry: Registry,
+    pub notices_processed: IntCounter,
+    /// Queries served, labeled with response type.
+    pub queries_served: IntCounterVec,
+    /// Failed requests to witnesses, labeled with request type.
+    pub transport_errors: IntCounterVec,
+    /// Number of escrowed events, labeled with escrow name. Updated when
+    /// metrics are gathered.
+    pub escrow_size: IntGaugeVec,
+}
+
+impl WatcherMetrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("watcher".to_string()), None)?;
+        let notices_processed = IntCounter::new(
+            "notices_processed_total",
+            "Number of successfully processed notices",
+        )?;
+        let queries_served = IntCounterVec::new(
+            Opts::new("queries_served_total", "Number of served queries"),
+            &["response"],
+        )?;
+        let transport_errors = IntCounterVec::new(
+            Opts::new(
+                "transport_errors_total",
+                "Number of failed requests to witnesses",
+            ),
+            &["request"],
+        )?;
+        let escrow_size = IntGaugeVec::new(
+            Opts::new("escrow_size", "Number of escrowed events"),
+            &["escrow"],
+        )?;
+        registry.register(Box::new(notices_processed.clone()))?;
+        registry.register(Box::new(queries_served.clone()))?;
+        registry.register(Box::new(transport_errors.clone()))?;
+        registry.register(Box::new(escrow_size.clone()))?;
+        Ok(Self {
+            registry,
+            notices_processed,
+            queries_served,
+            transport_errors,
+            escrow_size,
+        })
+    }
+
+    pub fn count_query(&self, response: &PossibleResponse) {
+        let label = match response {
+            PossibleResponse::Kel(_) => "kel",
+            PossibleResponse::Mbx(_) => "mbx",
+            PossibleResponse::Ksn(_) => "ksn",
+        };
+        self.queries_served.with_label_values(&[label]).inc();
+    }
+
+    /// Counts KEL query answered with lazily loaded KEL.
+    pub fn count_kel_query(&self) {
+        self.queries_served.with_label_values(&["kel"]).inc();
+    }
+
+    /// Returns all metrics in Prometheus text format.
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).to_string())
+    }
+}
@@ -0,0 +1,27 @@
+//! Watcher endpoints served over framed TCP (see
+//! [`keri_core::transport::tcp`]), for deployments that avoid HTTP. Requests
+//! use the same paths and bodies as HTTP endpoints and are answered by the
+//! same router as in [`WatcherListener::listen_http`].
+use std::future::Future;
+
+use actor_server::{tcp_listener, AdminToken};
+
+use crate::WatcherListener;
+
+impl WatcherListener {
+    /// Serves watcher endpoints over framed TCP on given address. Background
+    /// tasks updating KELs and TELs are not spawned, they are run by
+    /// [`WatcherListener::listen_http`] or need to be spawned separately with
+    /// [`update_checking`](crate::watcher_listener::update_checking) and
+    /// [`update_tel_checking`](crate::watcher_listener::update_tel_checking).
+    pub fn listen_tcp(
+        &self,
+        addr: impl tokio::net::ToSocketAddrs + Send + 'static,
+    ) -> impl Future<Output = std::io::Result<()>> + Send + 'static {
+        tcp_listener::listen_tcp(
+            self.watcher.clone(),
+            AdminToken(self.admin_token.clone()),
+            addr,
+        )
+    }
+}
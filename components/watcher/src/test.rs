@@ -11,14 +11,14 @@ use keri_core::{
         SignedQueryError,
     },
     database::{escrow::EscrowDb, redb::RedbDatabase, sled::SledEventDatabase},
-    event_message::signed_event_message::{Notice, Op},
+    event_message::signed_event_message::{Message, Notice, Op},
     prefix::IdentifierPrefix,
     processor::escrow::EscrowConfig,
     query::{
         query_event::{SignedKelQuery, SignedQueryMessage},
-        reply_event::SignedReply,
+        reply_event::{ReplyRoute, SignedReply},
     },
-    transport::test::{TestActorMap, TestTransport},
+    transport::test::{TestActor, TestActorMap, TestTransport},
 };
 use tempfile::Builder;
 use url::{Host, Url};
@@ -311,12 +311,990 @@ pub fn watcher_forward_ksn() -> Result<(), ActorError> {
     ));
 
     // Send query again
+    let result = futures::executor::block_on(watcher.watcher_data.process_op(query.clone()));
+
+    let verified_at = watcher
+        .watcher_data
+        .verified_at(about_controller.prefix())
+        .unwrap();
+    let ksn_verified_at = |result: Result<Option<PossibleResponse>, ActorError>| match result {
+        Ok(Some(PossibleResponse::Ksn(rpy))) => match rpy.reply.get_route() {
+            ReplyRoute::Ksn(_, ksn) => {
+                // Notice is issued now, even if state was verified before.
+                assert!(ksn.timestamp >= verified_at);
+                ksn.verified_at
+            }
+            _ => unreachable!(),
+        },
+        _ => panic!("Unexpected response"),
+    };
+    assert_eq!(ksn_verified_at(result), Some(verified_at));
+
+    // State was verified recently, so watcher answers from its cache.
     let result = futures::executor::block_on(watcher.watcher_data.process_op(query));
+    assert_eq!(ksn_verified_at(result), Some(verified_at));
+    assert_eq!(
+        watcher
+            .watcher_data
+            .verified_at(about_controller.prefix())
+            .unwrap(),
+        verified_at
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_witness_responses_cross_verification() {
+    use crate::watcher::cross_verification::{
+        CrossVerificationError, Quorum, WitnessQueryPolicy, WitnessResponse, WitnessResponseOutcome,
+    };
+    use keri_core::{actor::prelude::HashFunction, prefix::BasicPrefix};
+
+    let digest_a = HashFunction::from(keri_core::actor::prelude::HashFunctionCode::Blake3_256)
+        .derive("event a".as_bytes());
+    let digest_b = HashFunction::from(keri_core::actor::prelude::HashFunctionCode::Blake3_256)
+        .derive("event b".as_bytes());
+    let witness: BasicPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let state =
+        |sn, digest: &keri_core::actor::prelude::SelfAddressingIdentifier| WitnessResponse {
+            witness: witness.clone(),
+            outcome: WitnessResponseOutcome::State {
+                sn,
+                digest: digest.clone(),
+            },
+        };
+    let timeout = WitnessResponse {
+        witness: witness.clone(),
+        outcome: WitnessResponseOutcome::Timeout,
+    };
+
+    let policy = WitnessQueryPolicy {
+        min_responses: 2,
+        quorum: Quorum::Majority,
+        ..Default::default()
+    };
+    assert_eq!(
+        policy.agreed_sn(
+            &[
+                state(1, &digest_a),
+                state(1, &digest_a),
+                state(0, &digest_b)
+            ],
+            3
+        ),
+        Ok(1)
+    );
+    assert_eq!(
+        policy.agreed_sn(&[state(1, &digest_a), timeout.clone()], 3),
+        Err(CrossVerificationError::NotEnoughResponses {
+            required: 2,
+            got: 1
+        })
+    );
+    assert_eq!(
+        policy.agreed_sn(&[state(1, &digest_a), state(1, &digest_b)], 2),
+        Err(CrossVerificationError::Conflict(1))
+    );
+    assert_eq!(
+        policy.agreed_sn(&[state(1, &digest_a), state(0, &digest_b)], 2),
+        Err(CrossVerificationError::QuorumNotReached {
+            sn: 1,
+            agreeing: 1,
+            responses: 2
+        })
+    );
+
+    let policy = WitnessQueryPolicy {
+        quorum: Quorum::Threshold(1),
+        ..policy
+    };
+    assert_eq!(
+        policy.agreed_sn(&[state(1, &digest_a), state(0, &digest_b)], 2),
+        Ok(1)
+    );
+}
+
+/// Witness that answers key state queries with forged notices. They claim
+/// higher sn and are signed with key other than witness' one.
+struct LyingWitness {
+    witness: Arc<WitnessListener>,
+    impostor: keri_core::signer::Signer,
+}
+
+#[async_trait::async_trait]
+impl TestActor for LyingWitness {
+    async fn send_message(&self, msg: Message) -> Result<(), ActorError> {
+        self.witness.send_message(msg).await
+    }
+
+    async fn send_query(&self, query: SignedQueryMessage) -> Result<PossibleResponse, ActorError> {
+        use keri_core::actor::prelude::{HashFunctionCode, SerializationFormats};
+        use keri_core::query::reply_event::ReplyEvent;
+
+        match self.witness.send_query(query).await? {
+            PossibleResponse::Ksn(rpy) => {
+                let ReplyRoute::Ksn(signer, mut ksn) = rpy.reply.get_route() else {
+                    unreachable!()
+                };
+                ksn.state.sn += 5;
+                let forged = ReplyEvent::new_reply(
+                    ReplyRoute::Ksn(signer, ksn),
+                    HashFunctionCode::Blake3_256,
+                    SerializationFormats::JSON,
+                );
+                let signature =
+                    SelfSigningPrefix::Ed25519Sha512(self.impostor.sign(forged.encode()?)?);
+                Ok(PossibleResponse::Ksn(SignedReply::new_nontrans(
+                    forged,
+                    self.witness.get_prefix(),
+                    signature,
+                )))
+            }
+            resp => Ok(resp),
+        }
+    }
+
+    async fn request_loc_scheme(&self, eid: IdentifierPrefix) -> Result<Vec<Op>, ActorError> {
+        self.witness.request_loc_scheme(eid).await
+    }
+
+    async fn request_end_role(
+        &self,
+        cid: IdentifierPrefix,
+        role: keri_core::oobi::Role,
+        eid: IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        self.witness.request_end_role(cid, role, eid).await
+    }
+
+    async fn request_oobis(&self, cid: IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+        self.witness.request_oobis(cid).await
+    }
+
+    async fn request_provenance(
+        &self,
+        id: IdentifierPrefix,
+        vc_id: Option<IdentifierPrefix>,
+    ) -> Result<keri_core::query::provenance::SignedProvenance, ActorError> {
+        self.witness.request_provenance(id, vc_id).await
+    }
+
+    async fn resolve_oobi(&self, msg: keri_core::oobi::Oobi) -> Result<(), ActorError> {
+        self.witness.resolve_oobi(msg).await
+    }
+}
+
+#[async_std::test]
+async fn test_lying_witness() -> Result<(), ActorError> {
+    use crate::watcher::cross_verification::WitnessResponseOutcome;
+    use keri_core::signer::{CryptoBox, Signer};
+
+    let setup_witness = |host: &str, seed: &str| {
+        let root = Builder::new().prefix("test-wit").tempdir().unwrap();
+        Arc::new(
+            WitnessListener::setup(
+                Url::parse(&format!("http://{}", host)).unwrap(),
+                root.path(),
+                Some(seed.into()),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let honest_witness = setup_witness("witness1", "ArwXoACJgOleVZ2PY7kXn7rA0II0mHYDhc6WrBH8fDAc");
+    let lying_witness = setup_witness("witness2", "AK8F6AAiYDpXlWdj2O5F5-6wNCCNJh2A4XOlqwR_HwwH");
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let icp = controller
+        .incept(
+            Some(vec![
+                honest_witness.get_prefix(),
+                lying_witness.get_prefix(),
+            ]),
+            Some(0),
+            None,
+        )
+        .unwrap();
+    for witness in [&honest_witness, &lying_witness] {
+        witness
+            .witness_data
+            .process_notice(Notice::Event(icp.clone()))
+            .unwrap();
+    }
+
+    let mut actors: TestActorMap = HashMap::new();
+    actors.insert(
+        (Host::Domain("witness1".to_string()), 80),
+        honest_witness.clone(),
+    );
+    actors.insert(
+        (Host::Domain("witness2".to_string()), 80),
+        Arc::new(LyingWitness {
+            witness: lying_witness.clone(),
+            impostor: Signer::new(),
+        }),
+    );
+
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let watcher = Watcher::new(WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        transport: Box::new(TestTransport::new(actors)),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?;
+    watcher.watcher_data.process_notice(Notice::Event(icp))?;
+
+    // Send witnesses' OOBIs to watcher
+    for witness in [&honest_witness, &lying_witness] {
+        let witness = &witness.witness_data;
+        let loc_scheme = witness
+            .oobi_manager
+            .get_loc_scheme(&IdentifierPrefix::Basic(witness.prefix.clone()))
+            .unwrap()
+            .unwrap()[0]
+            .clone();
+        let signature =
+            SelfSigningPrefix::Ed25519Sha512(witness.signer.sign(loc_scheme.encode()?)?);
+        watcher
+            .watcher_data
+            .process_reply(SignedReply::new_nontrans(
+                loc_scheme,
+                witness.prefix.clone(),
+                signature,
+            ))?;
+    }
 
+    let id = controller.prefix().clone();
+    watcher.watcher_data.update_local_kel(&id).await?;
+
+    // Forged notice isn't taken into account, so honest witness' state is
+    // accepted.
+    let responses = watcher.watcher_data.witness_responses(&id);
+    let outcome = |witness: &Arc<WitnessListener>| {
+        responses
+            .iter()
+            .find(|resp| resp.witness == witness.get_prefix())
+            .map(|resp| resp.outcome.clone())
+            .unwrap()
+    };
     assert!(matches!(
-        result,
-        Ok(Some(PossibleResponse::Ksn(SignedReply { .. })))
+        outcome(&honest_witness),
+        WitnessResponseOutcome::State { sn: 0, .. }
+    ));
+    assert!(matches!(
+        outcome(&lying_witness),
+        WitnessResponseOutcome::Error(_)
+    ));
+    assert!(watcher.watcher_data.verified_at(&id).is_some());
+    assert_eq!(
+        watcher.watcher_data.get_state_for_prefix(&id).unwrap().sn,
+        0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_compare_witness_kels() {
+    use crate::watcher::cross_verification::{compare_kels, CrossVerificationError};
+    use keri_core::{
+        actor::prelude::{HashFunction, HashFunctionCode},
+        event::sections::seal::{DigestSeal, Seal},
+        prefix::BasicPrefix,
+        signer::CryptoBox,
+    };
+
+    // Two controllers sharing the same keys, so they create the same
+    // inception event and then conflicting interaction events.
+    let key_manager = Arc::new(Mutex::new(CryptoBox::new().unwrap()));
+    let setup_controller = || {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            key_manager.clone(),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut controller = setup_controller();
+    let mut duplicitous_controller = setup_controller();
+    let icp = controller.incept(None, None, None).unwrap();
+    duplicitous_controller.incept(None, None, None).unwrap();
+    let ixn = controller.anchor(&[]).unwrap();
+    let digest = HashFunction::from(HashFunctionCode::Blake3_256).derive(b"other data");
+    let conflicting_ixn = duplicitous_controller
+        .anchor(&[Seal::Digest(DigestSeal::new(digest))])
+        .unwrap();
+
+    let witness1: BasicPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let witness2: BasicPrefix = "BHndk6cXPCnghFqKt_0SikY1P9z_nIUrHq_SeHgLQCui"
+        .parse()
+        .unwrap();
+    let ixn_digest = ixn.event_message.digest().unwrap();
+
+    // Witness can lag behind.
+    assert_eq!(
+        compare_kels(
+            &[
+                (witness1.clone(), vec![icp.clone(), ixn.clone()]),
+                (witness2.clone(), vec![icp.clone()]),
+            ],
+            Some(&(1, ixn_digest.clone())),
+        ),
+        Ok(())
+    );
+    // Witnesses return different events at the same sn.
+    assert_eq!(
+        compare_kels(
+            &[
+                (witness1.clone(), vec![icp.clone(), ixn.clone()]),
+                (witness2.clone(), vec![icp.clone(), conflicting_ixn.clone()]),
+            ],
+            None,
+        ),
+        Err(CrossVerificationError::Conflict(1))
+    );
+    // Witness' KEL doesn't match agreed key state.
+    assert_eq!(
+        compare_kels(
+            &[(witness2, vec![icp, conflicting_ixn])],
+            Some(&(1, ixn_digest)),
+        ),
+        Err(CrossVerificationError::Conflict(1))
+    );
+}
+
+#[async_std::test]
+async fn test_watcher_mailbox() -> Result<(), ActorError> {
+    use keri_core::{
+        event::sections::threshold::SignatureThreshold,
+        event_message::signed_event_message::Message, signer::CryptoBox,
+    };
+
+    let setup_controller = || {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut initiator = setup_controller();
+    let mut participant = setup_controller();
+    let initiator_icp = initiator.incept(None, None, None).unwrap();
+    let participant_icp = participant.incept(None, None, None).unwrap();
+    initiator
+        .process(&[Message::Notice(Notice::Event(participant_icp.clone()))])
+        .unwrap();
+
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        // Every message exceeds the limit, so each response contains single
+        // message.
+        mailbox_max_size: 1,
+        ..Default::default()
+    })?;
+    watcher
+        .watcher_data
+        .process_notice(Notice::Event(initiator_icp))
+        .unwrap();
+    watcher
+        .watcher_data
+        .process_notice(Notice::Event(participant_icp))
+        .unwrap();
+
+    let (_group_icp, exchanges) = initiator
+        .group_incept(
+            vec![participant.prefix().clone()],
+            &SignatureThreshold::Simple(2),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+    let exn = exchanges[0].clone();
+    let (_second_group_icp, second_exchanges) = initiator
+        .group_incept(
+            vec![participant.prefix().clone()],
+            &SignatureThreshold::Simple(2),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+    // Participant didn't designate watcher yet.
+    let err = watcher
+        .watcher_data
+        .process_op(Op::Exchange(exn.clone()))
+        .await;
+    assert!(matches!(err, Err(ActorError::MissingRole { .. })));
+
+    let end_role = participant.add_watcher(&IdentifierPrefix::Basic(watcher.prefix()))?;
+    watcher.watcher_data.process_op(end_role).await?;
+    watcher.watcher_data.process_op(Op::Exchange(exn)).await?;
+    watcher
+        .watcher_data
+        .process_op(Op::Exchange(second_exchanges[0].clone()))
+        .await?;
+
+    // Participant gets group inception from watcher's mailbox. Second one
+    // doesn't fit in the response.
+    let mbx_query = participant.query_mailbox(&watcher.prefix());
+    let response = watcher
+        .watcher_data
+        .process_op(Op::Query(mbx_query))
+        .await?;
+    match response {
+        Some(PossibleResponse::Mbx(mbx)) => {
+            assert_eq!(mbx.multisig.len(), 1);
+            assert!(mbx.delegate.is_empty());
+            assert_eq!(mbx.next.map(|next| next.multisig), Some(1));
+        }
+        _ => panic!("unexpected response"),
+    };
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_duplicity_report() -> Result<(), ActorError> {
+    use keri_core::{
+        actor::prelude::{HashFunction, HashFunctionCode},
+        event::sections::seal::{DigestSeal, Seal},
+        signer::CryptoBox,
+    };
+
+    // Two controllers sharing the same keys, so they create the same
+    // inception event and then conflicting interaction events.
+    let key_manager = Arc::new(Mutex::new(CryptoBox::new().unwrap()));
+    let setup_controller = || {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            key_manager.clone(),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut controller = setup_controller();
+    let mut duplicitous_controller = setup_controller();
+    let icp = controller.incept(None, None, None).unwrap();
+    duplicitous_controller.incept(None, None, None).unwrap();
+    assert_eq!(controller.prefix(), duplicitous_controller.prefix());
+
+    let ixn = controller.anchor(&[]).unwrap();
+    let digest = HashFunction::from(HashFunctionCode::Blake3_256).derive(b"other data");
+    let conflicting_ixn = duplicitous_controller
+        .anchor(&[Seal::Digest(DigestSeal::new(digest))])
+        .unwrap();
+
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?;
+    let id = controller.prefix().clone();
+    for event in [icp, ixn.clone(), ixn.clone()] {
+        watcher.watcher_data.process_notice(Notice::Event(event))?;
+    }
+
+    // Receiving the same event again isn't duplicity.
+    assert!(watcher.get_duplicity_report(&id)?.is_empty());
+
+    watcher
+        .watcher_data
+        .process_notice(Notice::Event(conflicting_ixn.clone()))?;
+    let report = watcher.get_duplicity_report(&id)?;
+    assert_eq!(report.conflicts.len(), 1);
+    let conflict = &report.conflicts[0];
+    assert_eq!(conflict.sn, 1);
+    assert_eq!(conflict.accepted, ixn);
+    assert_eq!(conflict.conflicting, conflicting_ixn);
+    assert_eq!(conflict.reported_by, None);
+
+    // Watcher's KEL isn't changed.
+    assert_eq!(
+        watcher.watcher_data.get_state_for_prefix(&id).unwrap().sn,
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance() -> Result<(), ActorError> {
+    use keri_core::{query::provenance::ProvenanceBundle, signer::CryptoBox};
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let icp = controller.incept(None, None, None).unwrap();
+    let id = controller.prefix().clone();
+
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?;
+    watcher.watcher_data.process_notice(Notice::Event(icp))?;
+
+    // Event sent directly to watcher wasn't supplied by any witness.
+    let provenance = watcher.kel_provenance(&id)?;
+    assert!(provenance.verify(&watcher.prefix())?);
+    assert_eq!(
+        provenance.bundle,
+        ProvenanceBundle::Kel {
+            id: id.clone(),
+            events: vec![]
+        }
+    );
+
+    let provenance = watcher.tel_provenance(&id, &id)?;
+    assert!(provenance.verify(&watcher.prefix())?);
+    assert!(matches!(
+        provenance.bundle,
+        ProvenanceBundle::Tel {
+            provenance: None,
+            ..
+        }
     ));
 
     Ok(())
 }
+
+#[async_std::test]
+async fn test_verify_presentation() -> Result<(), ActorError> {
+    use cesrox::{payload::Payload, ParsedData};
+    use keri_core::{
+        actor::prelude::{HashFunctionCode, SerializationFormats},
+        event::sections::seal::EventSeal,
+        event_message::signature::{Signature, SignerData},
+        oobi::{LocationScheme, Oobi, Role},
+        prefix::IndexedSignature,
+        query::verdict::CredentialStatus,
+        sai::{compute_said, OrderedValue},
+        signer::{CryptoBox, KeyManager},
+        transport::{Transport, TransportError},
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts requests watcher makes.
+    struct CountingTransport(Arc<AtomicUsize>);
+
+    impl CountingTransport {
+        fn count<T>(&self) -> Result<T, TransportError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(TransportError::NetworkError("unreachable".to_string()))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for CountingTransport {
+        async fn send_message(
+            &self,
+            _loc: LocationScheme,
+            _msg: Message,
+        ) -> Result<(), TransportError> {
+            self.count()
+        }
+        async fn send_query(
+            &self,
+            _loc: LocationScheme,
+            _qry: SignedQueryMessage,
+        ) -> Result<PossibleResponse, TransportError> {
+            self.count()
+        }
+        async fn request_loc_scheme(
+            &self,
+            _loc: LocationScheme,
+        ) -> Result<Vec<Op>, TransportError> {
+            self.count()
+        }
+        async fn request_end_role(
+            &self,
+            _loc: LocationScheme,
+            _cid: IdentifierPrefix,
+            _role: Role,
+            _eid: IdentifierPrefix,
+        ) -> Result<Vec<u8>, TransportError> {
+            self.count()
+        }
+        async fn resolve_oobi(
+            &self,
+            _loc: LocationScheme,
+            _oobi: Oobi,
+        ) -> Result<(), TransportError> {
+            self.count()
+        }
+    }
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let icp = controller.incept(None, None, None).unwrap();
+    let id = controller.prefix().clone();
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        transport: Box::new(CountingTransport(requests.clone())),
+        ..Default::default()
+    })?;
+    watcher
+        .watcher_data
+        .process_notice(Notice::Event(icp.clone()))?;
+
+    let present = |credential: &str, signed: &str| {
+        let signature = SelfSigningPrefix::Ed25519Sha512(
+            controller
+                .key_manager
+                .lock()
+                .unwrap()
+                .sign(signed.as_bytes())
+                .unwrap(),
+        );
+        let signature = Signature::Transferable(
+            SignerData::EventSeal(EventSeal::new(
+                id.clone(),
+                0,
+                icp.event_message.digest().unwrap(),
+            )),
+            vec![IndexedSignature::new_both_same(signature, 0)],
+        );
+        ParsedData {
+            payload: Payload::JSON(credential.as_bytes().to_vec()),
+            attachments: vec![signature.try_into().unwrap()],
+        }
+        .to_cesr()
+        .unwrap()
+    };
+    let with_said = |content: String| {
+        let said = compute_said(
+            &serde_json::from_str::<OrderedValue>(&content).unwrap(),
+            HashFunctionCode::Blake3_256,
+            SerializationFormats::JSON,
+        )
+        .unwrap();
+        content.replace(r#""d":"""#, &format!(r#""d":"{}""#, said))
+    };
+    let credential = with_said(format!(
+        r#"{{"d":"","i":"{}","ri":"EL2KqdbeSkemPII22qQ9dNglhBYa2YaQL7ePjN-3aTGg","a":{{"name":"John"}}}}"#,
+        id
+    ));
+
+    // Signature is valid, but registry's TEL is unknown to watcher.
+    let verdicts = watcher
+        .verify_presentation(&present(&credential, &credential))
+        .await?;
+    assert_eq!(verdicts.len(), 1);
+    assert!(verdicts[0].verify(&watcher.prefix())?);
+    assert!(verdicts[0].verdict.signatures_valid);
+    assert_eq!(verdicts[0].verdict.status, CredentialStatus::Unknown);
+    assert!(!verdicts[0].verdict.valid);
+    assert_eq!(verdicts[0].verdict.issuer, id);
+
+    // Credential changed after signing.
+    let tampered = credential.replace("John", "Jane");
+    let verdicts = watcher
+        .verify_presentation(&present(&tampered, &credential))
+        .await?;
+    assert!(!verdicts[0].verdict.signatures_valid);
+    assert!(!verdicts[0].verdict.valid);
+
+    // Credential signed after change, but its SAID doesn't match content.
+    let verdicts = watcher
+        .verify_presentation(&present(&tampered, &tampered))
+        .await?;
+    assert!(verdicts[0].verdict.signatures_valid);
+    assert_eq!(verdicts[0].verdict.status, CredentialStatus::Unknown);
+    assert!(verdicts[0]
+        .verdict
+        .errors
+        .contains(&"Credential SAID doesn't match its content".to_string()));
+    assert!(!verdicts[0].verdict.valid);
+
+    // OOBI pointing to location unknown to watcher isn't resolved.
+    requests.store(0, Ordering::SeqCst);
+    let oobi = serde_json::to_vec(&Oobi::Location(LocationScheme::new(
+        id.clone(),
+        keri_core::oobi::Scheme::Http,
+        Url::parse("http://internal.example/").unwrap(),
+    )))
+    .unwrap();
+    let mut presentation = cesrox::ParsedData {
+        payload: cesrox::payload::Payload::JSON(oobi),
+        attachments: vec![],
+    }
+    .to_cesr()
+    .unwrap();
+    presentation.extend(present(&credential, &credential));
+    let verdicts = watcher.verify_presentation(&presentation).await?;
+    assert_eq!(verdicts.len(), 1);
+    assert!(verdicts[0].verdict.signatures_valid);
+    assert_eq!(requests.load(Ordering::SeqCst), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_shared_backend() -> Result<(), ActorError> {
+    use keri_core::signer::CryptoBox;
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let icp = controller.incept(None, None, None).unwrap();
+    let id = controller.prefix().clone();
+
+    let shared_root = Builder::new().prefix("shared-backend").tempdir().unwrap();
+    let shared_path = shared_root.path().join("journal.sqlite");
+    let replica = |name: &str| -> Result<_, ActorError> {
+        let root = Builder::new().prefix(name).tempdir().unwrap();
+        let watcher = Watcher::new(crate::WatcherConfig {
+            public_address: Url::parse("http://some/dummy/url").unwrap(),
+            db_path: root.path().to_owned(),
+            tel_storage_path: root.path().join("tel_storage"),
+            shared_backend: Some(shared_path.clone()),
+            ..Default::default()
+        })?;
+        Ok((root, watcher))
+    };
+    let (_first_root, first) = replica("first-replica")?;
+    let (_second_root, second) = replica("second-replica")?;
+
+    // Event accepted by one replica is replayed by the other one.
+    first.watcher_data.process_notice(Notice::Event(icp))?;
+    assert!(second.watcher_data.get_state_for_prefix(&id).is_none());
+    second.watcher_data.sync_shared_backend()?;
+    assert_eq!(second.watcher_data.get_state_for_prefix(&id).unwrap().sn, 0);
+
+    // Replica skips messages it published itself.
+    first.watcher_data.sync_shared_backend()?;
+    assert_eq!(
+        first.watcher_data.get_state_for_prefix(&id),
+        second.watcher_data.get_state_for_prefix(&id)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_metrics() -> Result<(), ActorError> {
+    use keri_core::signer::CryptoBox;
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let db_controller = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        let events_db_path = Builder::new().tempfile().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+        let escrow_db = Arc::new(EscrowDb::new(escrow_root.path()).unwrap());
+        let oobi_root = Builder::new().prefix("oobi-test-db").tempdir().unwrap();
+        SimpleController::new(
+            db_controller,
+            events_db,
+            escrow_db,
+            Arc::new(Mutex::new(CryptoBox::new().unwrap())),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let icp = controller.incept(None, None, None).unwrap();
+
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?;
+    watcher.parse_and_process_notices(&icp.encode().unwrap())?;
+
+    let metrics = watcher.gather_metrics()?;
+    assert!(metrics.contains("watcher_notices_processed_total 1"));
+    assert!(metrics.contains("watcher_escrow_size{escrow=\"out_of_order\"} 0"));
+
+    Ok(())
+}
+
+#[test]
+fn test_backup() -> Result<(), ActorError> {
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?;
+
+    let backup_dir = Builder::new().prefix("backup").tempdir().unwrap();
+    watcher.backup(backup_dir.path())?;
+
+    assert!(backup_dir.path().join("events_database").exists());
+    assert!(backup_dir.path().join("oobi").exists());
+    assert!(backup_dir.path().join("tel_storage/registry").is_file());
+    assert!(backup_dir.path().join("tel_storage/to_forward").is_file());
+
+    Ok(())
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn test_axum_admin_token() -> Result<(), ActorError> {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use crate::{axum_listener, AdminToken};
+
+    let watcher_tel_dir = Builder::new().prefix("cont-test-tel-db").tempdir().unwrap();
+    let root = Builder::new().prefix("cont-test-db").tempdir().unwrap();
+    let watcher = Arc::new(Watcher::new(crate::WatcherConfig {
+        public_address: Url::parse("http://some/dummy/url").unwrap(),
+        db_path: root.path().to_owned(),
+        tel_storage_path: watcher_tel_dir.path().join("tel_storage"),
+        ..Default::default()
+    })?);
+    let admin_request = |token: Option<&str>| {
+        let req = Request::post("/admin/stream-log");
+        let req = match token {
+            Some(token) => req.header("Authorization", format!("Bearer {}", token)),
+            None => req,
+        };
+        req.body(Body::from(r#"{"enabled": false}"#)).unwrap()
+    };
+
+    // Admin endpoints are disabled without token.
+    let router = axum_listener::watcher_router(watcher.clone());
+    let resp = router.oneshot(admin_request(None)).await.unwrap();
+    assert_eq!(resp.status(), 401);
+
+    let router = axum_listener::watcher_router_with_admin_token(
+        watcher,
+        AdminToken(Some("secret".to_string())),
+    );
+    let resp = router
+        .clone()
+        .oneshot(admin_request(Some("wrong")))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = router.oneshot(admin_request(Some("secret"))).await.unwrap();
+    assert!(resp.status().is_success());
+
+    Ok(())
+}
@@ -1,19 +1,59 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use keri_core::{
+    actor::stream_log::StreamLog,
+    mailbox::DEFAULT_MAILBOX_MAX_SIZE,
+    oobi::traversal::OobiLimits,
     processor::escrow::EscrowConfig,
+    query::reply_event::DEFAULT_MAX_CLOCK_DRIFT,
+    signer::Signer,
     transport::{default::DefaultTransport, Transport},
 };
-use teliox::transport::{GeneralTelTransport, TelTransport};
+use teliox::transport::{DefaultTelTransport, TelTransport};
+
+use super::cross_verification::WitnessQueryPolicy;
 
 pub struct WatcherConfig {
     pub public_address: url::Url,
     pub db_path: PathBuf,
     pub priv_key: Option<String>,
+    /// Signer used instead of key derived from `priv_key`, e.g. one backed
+    /// by [`Keystore`](keri_core::signer::Keystore).
+    pub signer: Option<Arc<Signer>>,
     pub transport: Box<dyn Transport + Send + Sync>,
-    pub tel_transport: Box<dyn GeneralTelTransport + Send + Sync>,
+    pub tel_transport: Box<dyn TelTransport + Send + Sync>,
     pub tel_storage_path: PathBuf,
     pub escrow_config: EscrowConfig,
+    /// Rules of comparing KEL state reported by identifier's witnesses.
+    pub witness_query_policy: WitnessQueryPolicy,
+    /// How long state verified with witnesses is used to answer KSN queries
+    /// without asking witnesses again.
+    pub ksn_cache_ttl: Duration,
+    /// How long TEL collected from registry's witness is forwarded without
+    /// asking the witness again.
+    pub tel_cache_ttl: Duration,
+    /// Limits of following OOBIs revealed while resolving OOBI.
+    pub oobi_limits: OobiLimits,
+    /// Debug log of processed streams. If not set, disabled log writing to
+    /// `streams.log` in `db_path` is used.
+    pub stream_log: Option<StreamLog>,
+    /// Reject messages, which bytes differ from their canonical
+    /// serialization. See
+    /// [`check_canonical`](keri_core::event_message::cesr_adapter::check_canonical).
+    pub strict_canonicalization: bool,
+    /// Maximal difference between timestamp of query and watcher clock. If
+    /// set, queries out of it and queries processed before are rejected.
+    pub query_max_skew: Option<Duration>,
+    /// SQLite file shared by watcher replicas running behind load balancer.
+    /// If set, messages accepted by any replica are replayed by the others
+    /// before answering requests, so their databases don't diverge.
+    pub shared_backend: Option<PathBuf>,
+    /// Maximal size of mailbox response in bytes. Rest of messages can be
+    /// retrieved with next query.
+    pub mailbox_max_size: usize,
+    /// How far in the future timestamps of accepted replies and key state
+    /// notices can be.
+    pub max_clock_drift: Duration,
 }
 
 impl Default for WatcherConfig {
@@ -22,10 +62,21 @@ impl Default for WatcherConfig {
             public_address: url::Url::parse("http://localhost:3236").unwrap(),
             db_path: PathBuf::from("db"),
             priv_key: None,
+            signer: None,
             transport: Box::new(DefaultTransport::new()),
-            tel_transport: Box::new(TelTransport),
+            tel_transport: Box::new(DefaultTelTransport::new()),
             tel_storage_path: PathBuf::from("tel_storage"),
             escrow_config: EscrowConfig::default(),
+            witness_query_policy: WitnessQueryPolicy::default(),
+            ksn_cache_ttl: Duration::from_secs(30),
+            tel_cache_ttl: Duration::from_secs(60),
+            oobi_limits: OobiLimits::default(),
+            stream_log: None,
+            strict_canonicalization: false,
+            query_max_skew: None,
+            shared_backend: None,
+            mailbox_max_size: DEFAULT_MAILBOX_MAX_SIZE,
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
         }
     }
 }
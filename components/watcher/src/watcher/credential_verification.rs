@@ -0,0 +1,238 @@
+use cesrox::{group::Group, parse_many, payload::Payload};
+use keri_core::{
+    actor::{
+        error::ActorError,
+        prelude::{SelfAddressingIdentifier, SerializationFormats},
+    },
+    event_message::signature::get_signatures,
+    oobi::{traversal::OobiLimits, Oobi},
+    prefix::{IdentifierPrefix, SelfSigningPrefix},
+    processor::validator::EventValidator,
+    query::{
+        reply_event::ReplyRoute,
+        verdict::{CredentialStatus, SignedVerdict, Verdict},
+    },
+    sai::{verify_said, OrderedValue},
+};
+use serde::Deserialize;
+use teliox::{
+    event::{verifiable_event::VerifiableEvent, Event},
+    processor::validator::TelEventValidator,
+    state::vc_state::TelState,
+};
+
+use super::Watcher;
+
+/// Fields of ACDC credential that are needed to verify it.
+#[derive(Deserialize)]
+struct CredentialFields {
+    #[serde(rename = "d")]
+    said: SelfAddressingIdentifier,
+    #[serde(rename = "i")]
+    issuer: IdentifierPrefix,
+    #[serde(rename = "ri")]
+    registry: Option<IdentifierPrefix>,
+}
+
+fn tel_error(e: teliox::error::Error) -> ActorError {
+    ActorError::GeneralError(e.to_string())
+}
+
+impl Watcher {
+    /// Verifies credential presentation: CESR stream of OOBIs, needed to
+    /// find issuer's KEL and registry's TEL, and ACDC credentials with
+    /// issuer's signatures attached. OOBIs are resolved first, see
+    /// [`Watcher::resolve_presented_oobi`]. Then
+    /// signatures of every credential are checked against issuer's KEL and
+    /// its status against registry's TEL, which events need to be anchored
+    /// in issuer's KEL. Returns verdict for every credential, signed by
+    /// watcher.
+    pub async fn verify_presentation(
+        &self,
+        stream: &[u8],
+    ) -> Result<Vec<SignedVerdict>, ActorError> {
+        self.record_stream("presentations", stream);
+        let (_rest, parsed) = parse_many(stream).map_err(|_e| {
+            ActorError::GeneralError("Can't parse presentation stream".to_string())
+        })?;
+
+        let mut credentials = vec![];
+        for data in parsed {
+            let Payload::JSON(json) = data.payload else {
+                return Err(ActorError::GeneralError(
+                    "Only JSON credentials are supported".to_string(),
+                ));
+            };
+            match serde_json::from_slice::<Oobi>(&json) {
+                Ok(oobi) => {
+                    // Issuer's KEL or TEL may be already known to watcher.
+                    if let Err(e) = self.resolve_presented_oobi(oobi).await {
+                        log::warn!("Failed to resolve presented OOBI: {}", e);
+                    }
+                }
+                Err(_) => credentials.push((json, data.attachments)),
+            }
+        }
+
+        let mut verdicts = vec![];
+        for (json, attachments) in credentials {
+            let verdict = self.verify_credential(&json, attachments).await?;
+            verdicts.push(self.sign_verdict(verdict)?);
+        }
+        Ok(verdicts)
+    }
+
+    /// Resolves OOBI presented with credentials. Presentations aren't
+    /// authenticated, so only locations already known to watcher are
+    /// contacted and OOBIs revealed by responses aren't followed.
+    async fn resolve_presented_oobi(&self, oobi: Oobi) -> Result<(), ActorError> {
+        let eid = match &oobi {
+            Oobi::Location(loc) => &loc.eid,
+            Oobi::EndRole(er) => &er.eid,
+        };
+        let known_locations: Vec<_> = self
+            .watcher_data
+            .get_loc_scheme_for_id(eid)?
+            .into_iter()
+            .filter_map(|rpy| match rpy.reply.data.data {
+                ReplyRoute::LocScheme(loc) => Some(loc),
+                _ => None,
+            })
+            .collect();
+        let known = match &oobi {
+            Oobi::Location(loc) => known_locations.contains(loc),
+            Oobi::EndRole(_) => !known_locations.is_empty(),
+        };
+        if !known {
+            return Err(ActorError::NoLocation { id: eid.clone() });
+        }
+        let limits = OobiLimits {
+            max_depth: 0,
+            ..self.watcher_data.oobi_limits.clone()
+        };
+        self.resolve_oobi_with_limits(oobi, limits).await
+    }
+
+    async fn verify_credential(
+        &self,
+        json: &[u8],
+        attachments: Vec<Group>,
+    ) -> Result<Verdict, ActorError> {
+        let credential: CredentialFields = serde_json::from_slice(json)
+            .map_err(|e| ActorError::GeneralError(format!("Invalid credential: {}", e)))?;
+        let mut errors = vec![];
+
+        let signatures = attachments
+            .into_iter()
+            .map(get_signatures)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?
+            .concat();
+        let storage = &self.watcher_data.event_storage;
+        let validator = EventValidator::new(storage.escrow_db.clone(), storage.events_db.clone());
+        if signatures.is_empty() {
+            errors.push("Credential isn't signed".to_string());
+        }
+        for signature in &signatures {
+            if signature.get_signer().as_ref() != Some(&credential.issuer) {
+                errors.push("Credential isn't signed by its issuer".to_string());
+            } else if let Err(e) = validator.verify(json, signature) {
+                errors.push(format!("Invalid signature: {}", e));
+            }
+        }
+        let signatures_valid = errors.is_empty();
+
+        // TEL is looked up by SAID, so it needs to match credential content.
+        let content: OrderedValue = serde_json::from_slice(json)
+            .map_err(|e| ActorError::GeneralError(format!("Invalid credential: {}", e)))?;
+        let said_valid = verify_said(
+            &content,
+            &["d"],
+            &credential.said,
+            SerializationFormats::JSON,
+        )?;
+        if !said_valid {
+            errors.push("Credential SAID doesn't match its content".to_string());
+        }
+
+        let status = match &credential.registry {
+            Some(_) if !said_valid => CredentialStatus::Unknown,
+            Some(ri) => {
+                let vc_id = IdentifierPrefix::self_addressing(credential.said.clone());
+                self.credential_status(ri, &credential.issuer, &vc_id)
+                    .await
+                    .unwrap_or_else(|e| {
+                        errors.push(format!("Can't verify TEL: {}", e));
+                        CredentialStatus::Unknown
+                    })
+            }
+            None => {
+                errors.push("Credential has no registry".to_string());
+                CredentialStatus::Unknown
+            }
+        };
+
+        Ok(Verdict {
+            credential: credential.said,
+            issuer: credential.issuer,
+            registry: credential.registry,
+            signatures_valid,
+            status,
+            valid: signatures_valid && said_valid && status == CredentialStatus::Issued,
+            errors,
+            verified_at: self.watcher_data.now(),
+        })
+    }
+
+    /// Computes status of `vc_id` from its TEL, fetched from registry's
+    /// witness if cached one is too old. All TEL events need to be anchored
+    /// in issuer's KEL.
+    async fn credential_status(
+        &self,
+        ri: &IdentifierPrefix,
+        issuer: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Result<CredentialStatus, ActorError> {
+        if !self.watcher_data.is_tel_fresh(ri, vc_id) {
+            if let Err(e) = self.update_tel(ri, vc_id).await {
+                log::warn!("Failed to update TEL of {}: {}", vc_id, e);
+            }
+        }
+        let tel = self
+            .watcher_data
+            .tel_to_forward
+            .get(ri, vc_id)
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?
+            .ok_or_else(|| ActorError::GeneralError(format!("Unknown TEL of {}", vc_id)))?;
+
+        let mut state = TelState::NotIssued;
+        for event in VerifiableEvent::parse(tel.as_bytes()).map_err(tel_error)? {
+            TelEventValidator::check_kel_event(
+                self.watcher_data.event_storage.clone(),
+                &event.seal,
+                issuer,
+                event.event.get_digest().map_err(tel_error)?,
+            )
+            .map_err(tel_error)?;
+            if let Event::Vc(vc_event) = &event.event {
+                if &vc_event.data.data.prefix == vc_id {
+                    state = state.apply(vc_event).map_err(tel_error)?;
+                }
+            }
+        }
+        Ok(match state {
+            TelState::NotIssued => CredentialStatus::NotIssued,
+            TelState::Issued(_) => CredentialStatus::Issued,
+            TelState::Revoked => CredentialStatus::Revoked,
+        })
+    }
+
+    fn sign_verdict(&self, verdict: Verdict) -> Result<SignedVerdict, ActorError> {
+        let signature = SelfSigningPrefix::Ed25519Sha512(
+            self.watcher_data
+                .signer
+                .sign(SignedVerdict::signed_data(&verdict)?)?,
+        );
+        Ok(SignedVerdict::new(verdict, self.prefix(), signature))
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, time::Duration};
+
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event_message::{
+        signature::{Nontransferable, Signature},
+        signed_event_message::SignedEventMessage,
+    },
+    prefix::{BasicPrefix, IdentifierPrefix},
+    query::reply_event::{ReplyRoute, SignedReply},
+};
+use thiserror::Error;
+
+/// Describes how many of witnesses responses need to agree before watcher
+/// accepts identifier's state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quorum {
+    /// All responding witnesses need to report the same state.
+    All,
+    /// More than half of responding witnesses need to report the same state.
+    Majority,
+    /// At least given number of witnesses need to report the same state.
+    Threshold(usize),
+}
+
+/// Policy of querying identifier's witnesses while updating local KEL.
+#[derive(Debug, Clone)]
+pub struct WitnessQueryPolicy {
+    /// Minimal number of witnesses that need to respond. If identifier has
+    /// fewer witnesses, all of them need to respond.
+    pub min_responses: usize,
+    pub quorum: Quorum,
+    /// How long to wait for single witness response.
+    pub timeout: Duration,
+}
+
+impl Default for WitnessQueryPolicy {
+    fn default() -> Self {
+        Self {
+            min_responses: 1,
+            quorum: Quorum::Majority,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitnessResponseOutcome {
+    /// Witness returned key state notice with given sn and last event digest.
+    State {
+        sn: u64,
+        digest: SelfAddressingIdentifier,
+    },
+    Error(String),
+    Timeout,
+}
+
+/// Response of single witness recorded while updating identifier's KEL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessResponse {
+    pub witness: BasicPrefix,
+    pub outcome: WitnessResponseOutcome,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CrossVerificationError {
+    #[error("Not enough witness responses. Required: {required}, got: {got}")]
+    NotEnoughResponses { required: usize, got: usize },
+    #[error("Witnesses reported different events at sn {0}")]
+    Conflict(u64),
+    #[error("Quorum not reached for sn {sn}. Agreeing witnesses: {agreeing} of {responses}")]
+    QuorumNotReached {
+        sn: u64,
+        agreeing: usize,
+        responses: usize,
+    },
+    #[error("Invalid response of witness {witness}: {reason}")]
+    InvalidResponse {
+        witness: IdentifierPrefix,
+        reason: String,
+    },
+}
+
+/// Checks that key state notice was signed by `witness`, which was asked
+/// about it. Returns sn and last event digest of the notice.
+pub fn verify_witness_ksn(
+    rpy: &SignedReply,
+    witness: &BasicPrefix,
+) -> Result<(u64, SelfAddressingIdentifier), CrossVerificationError> {
+    let invalid = |reason: &str| CrossVerificationError::InvalidResponse {
+        witness: IdentifierPrefix::Basic(witness.clone()),
+        reason: reason.to_string(),
+    };
+    let ksn = match rpy.reply.get_route() {
+        ReplyRoute::Ksn(signer, ksn) if signer == IdentifierPrefix::Basic(witness.clone()) => ksn,
+        ReplyRoute::Ksn(_, _) => return Err(invalid("notice issued by other witness")),
+        _ => return Err(invalid("not a key state notice")),
+    };
+    let couplets = match &rpy.signature {
+        Signature::NonTransferable(Nontransferable::Couplet(couplets)) if !couplets.is_empty() => {
+            couplets
+        }
+        _ => return Err(invalid("missing witness signature")),
+    };
+    let data = rpy.reply.encode().map_err(|e| invalid(&e.to_string()))?;
+    for (signer, signature) in couplets {
+        if signer != witness {
+            return Err(invalid("signed by other identifier"));
+        }
+        if !signer
+            .verify(&data, signature)
+            .map_err(|e| invalid(&e.to_string()))?
+        {
+            return Err(invalid("wrong signature"));
+        }
+    }
+    rpy.reply
+        .check_digest()
+        .map_err(|e| invalid(&e.to_string()))?;
+    Ok((ksn.state.sn, ksn.state.last_event_digest))
+}
+
+/// Compares KELs returned by witnesses. Witnesses can return KELs of
+/// different length, but they can't disagree about event at the same sn.
+/// If state was already agreed on with key state notices, event at agreed sn
+/// needs to match it too.
+pub fn compare_kels(
+    kels: &[(BasicPrefix, Vec<SignedEventMessage>)],
+    agreed: Option<&(u64, SelfAddressingIdentifier)>,
+) -> Result<(), CrossVerificationError> {
+    let mut digests: HashMap<u64, SelfAddressingIdentifier> = agreed.cloned().into_iter().collect();
+    for (witness, kel) in kels {
+        for event in kel {
+            let sn = event.event_message.data.get_sn();
+            let digest = event.event_message.digest().map_err(|e| {
+                CrossVerificationError::InvalidResponse {
+                    witness: IdentifierPrefix::Basic(witness.clone()),
+                    reason: e.to_string(),
+                }
+            })?;
+            match digests.get(&sn) {
+                Some(known) if known != &digest => {
+                    return Err(CrossVerificationError::Conflict(sn))
+                }
+                Some(_) => (),
+                None => {
+                    digests.insert(sn, digest);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl WitnessQueryPolicy {
+    /// Compares states returned by witnesses. Returns sn of state that is
+    /// agreed according to policy. `witnesses_count` is number of witnesses
+    /// that were asked.
+    pub fn agreed_sn(
+        &self,
+        responses: &[WitnessResponse],
+        witnesses_count: usize,
+    ) -> Result<u64, CrossVerificationError> {
+        let states: Vec<_> = responses
+            .iter()
+            .filter_map(|resp| match &resp.outcome {
+                WitnessResponseOutcome::State { sn, digest } => Some((*sn, digest)),
+                _ => None,
+            })
+            .collect();
+
+        let required = self.min_responses.min(witnesses_count);
+        if states.len() < required || states.is_empty() {
+            return Err(CrossVerificationError::NotEnoughResponses {
+                required,
+                got: states.len(),
+            });
+        }
+
+        // Witnesses can lag behind, but they can't disagree about event at
+        // the same sn.
+        for (i, (sn, digest)) in states.iter().enumerate() {
+            if states[i + 1..]
+                .iter()
+                .any(|(other_sn, other_digest)| other_sn == sn && other_digest != digest)
+            {
+                return Err(CrossVerificationError::Conflict(*sn));
+            }
+        }
+
+        let sn = states.iter().map(|(sn, _)| *sn).max().unwrap_or_default();
+        let agreeing = states.iter().filter(|(s, _)| *s == sn).count();
+        let reached = match self.quorum {
+            Quorum::All => agreeing == states.len(),
+            Quorum::Majority => agreeing * 2 > states.len(),
+            Quorum::Threshold(threshold) => agreeing >= threshold,
+        };
+        if reached {
+            Ok(sn)
+        } else {
+            Err(CrossVerificationError::QuorumNotReached {
+                sn,
+                agreeing,
+                responses: states.len(),
+            })
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use keri_core::{
+    actor::prelude::SelfAddressingIdentifier,
+    event_message::signed_event_message::{Message, Notice, SignedEventMessage},
+    prefix::{BasicPrefix, IdentifierPrefix},
+};
+use serde::{Serialize, Serializer};
+
+/// Signed event that conflicts with event accepted in watcher's KEL at the
+/// same sn.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConflictingEvent {
+    pub sn: u64,
+    #[serde(serialize_with = "serialize_as_cesr")]
+    pub accepted: SignedEventMessage,
+    #[serde(serialize_with = "serialize_as_cesr")]
+    pub conflicting: SignedEventMessage,
+    /// Witness that provided conflicting event, if it was received from one.
+    pub reported_by: Option<BasicPrefix>,
+}
+
+/// Duplicity of identifier observed by watcher.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicityReport {
+    pub id: IdentifierPrefix,
+    pub conflicts: Vec<ConflictingEvent>,
+}
+
+impl DuplicityReport {
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+fn serialize_as_cesr<S: Serializer>(
+    event: &SignedEventMessage,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let cesr = Message::Notice(Notice::Event(event.clone()))
+        .to_cesr()
+        .map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&String::from_utf8_lossy(&cesr))
+}
+
+/// Remembers witnesses that provided conflicting events. Events themselves
+/// are kept in duplicitous events database by event processor.
+#[derive(Default)]
+pub(super) struct DuplicitySources(RwLock<HashMap<String, BasicPrefix>>);
+
+impl DuplicitySources {
+    pub fn record(&self, digest: &SelfAddressingIdentifier, witness: BasicPrefix) {
+        if let Ok(mut sources) = self.0.write() {
+            sources.entry(digest.to_string()).or_insert(witness);
+        }
+    }
+
+    pub fn get(&self, digest: &SelfAddressingIdentifier) -> Option<BasicPrefix> {
+        self.0
+            .read()
+            .ok()
+            .and_then(|sources| sources.get(&digest.to_string()).cloned())
+    }
+}
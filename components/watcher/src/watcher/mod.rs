@@ -1,20 +1,35 @@
 pub mod config;
+mod credential_verification;
+pub mod cross_verification;
+pub mod duplicity;
+mod provenance;
+mod shared_backend;
 mod tel_providing;
 mod watcher_data;
 
-use std::{fs::create_dir_all, sync::Arc};
+use std::{fs::create_dir_all, path::Path, sync::Arc};
 
 use async_std::channel::{unbounded, Receiver};
 use keri_core::{
     actor::{
-        error::ActorError, parse_event_stream, parse_notice_stream, parse_query_stream,
-        parse_reply_stream, simple_controller::PossibleResponse,
+        error::ActorError,
+        keri_actor::{ActorRequest, ActorResponse, KeriActor},
+        parse_event_stream, parse_stream,
+        simple_controller::PossibleResponse,
     },
     error::Error,
-    event_message::signed_event_message::Message,
-    oobi::{error::OobiError, EndRole, LocationScheme},
+    event_message::signed_event_message::{Message, Notice},
+    oobi::{
+        error::OobiError,
+        traversal::{OobiLimits, OobiTraversal},
+        EndRole, LocationScheme, Oobi, Role,
+    },
     prefix::{BasicPrefix, IdentifierPrefix},
-    query::reply_event::{ReplyRoute, SignedReply},
+    query::{
+        provenance::SignedProvenance,
+        query_event::SignedQueryMessage,
+        reply_event::{ReplyRoute, SignedReply},
+    },
 };
 use tel_providing::RegistryMapping;
 use teliox::event::parse_tel_query_stream;
@@ -25,13 +40,18 @@ use teliox::{
 };
 use watcher_data::WatcherData;
 
-use crate::WatcherConfig;
+use self::{cross_verification::WitnessResponse, duplicity::DuplicityReport};
+use crate::{watcher_listener::http_handlers, WatcherConfig};
 
 enum WitnessResp {
     Kel(Vec<Message>),
     Tel(Vec<VerifiableEvent>),
 }
 
+/// Serialized responses sent in chunks, see
+/// [`Watcher::parse_and_process_queries_chunked`].
+pub type ResponseChunks = Box<dyn Iterator<Item = Result<Vec<u8>, Error>> + Send>;
+
 pub struct Watcher {
     pub(crate) watcher_data: Arc<WatcherData>,
     recv: Receiver<IdentifierPrefix>,
@@ -61,10 +81,70 @@ impl Watcher {
         self.watcher_data.prefix.clone()
     }
 
+    /// Returns responses of witnesses collected during last update of
+    /// identifier's KEL.
+    pub fn witness_responses(&self, id: &IdentifierPrefix) -> Vec<WitnessResponse> {
+        self.watcher_data.witness_responses(id)
+    }
+
+    /// Returns events conflicting with identifier's KEL, that were observed
+    /// by watcher.
+    pub fn get_duplicity_report(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<DuplicityReport, ActorError> {
+        self.watcher_data.get_duplicity_report(id)
+    }
+
+    /// Returns witnesses that supplied events of identifier's KEL and time
+    /// of their retrieval, signed by watcher.
+    pub fn kel_provenance(&self, id: &IdentifierPrefix) -> Result<SignedProvenance, ActorError> {
+        self.watcher_data.get_kel_provenance(id)
+    }
+
+    /// Returns backer that supplied TEL of `vc_id` from registry `ri` and
+    /// time of its retrieval, signed by watcher.
+    pub fn tel_provenance(
+        &self,
+        ri: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Result<SignedProvenance, ActorError> {
+        self.watcher_data.get_tel_provenance(ri, vc_id)
+    }
+
     pub fn signed_location(&self, eid: &IdentifierPrefix) -> Result<Vec<SignedReply>, ActorError> {
         self.watcher_data.get_loc_scheme_for_id(eid)
     }
 
+    /// Copies watcher databases into `target` directory without stopping
+    /// watcher. Backup has the same layout as watcher's `db_path`, so it can
+    /// be used as `db_path` directly. TEL storage is copied into
+    /// `tel_storage` subdirectory, to be used as `tel_storage_path`. Escrows
+    /// are not copied.
+    pub fn backup(&self, target: &Path) -> Result<(), ActorError> {
+        let storage = &self.watcher_data.event_storage;
+        create_dir_all(target).map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        storage
+            .events_db
+            .backup(&target.join("events_database"))
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        storage.escrow_db.backup(target)?;
+        self.watcher_data
+            .oobi_manager
+            .backup(&target.join("oobi"))?;
+
+        let tel_target = target.join("tel_storage");
+        create_dir_all(&tel_target).map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        self.registry_id_mapping
+            .backup(&tel_target.join("registry"))
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        self.watcher_data
+            .tel_to_forward
+            .backup(&tel_target.join("to_forward"))
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn process_update_requests(&self) {
         while let Ok(received) = self.recv.recv().await {
             let _ = self.watcher_data.update_local_kel(&received).await;
@@ -73,21 +153,49 @@ impl Watcher {
 
     pub async fn process_update_tel_requests(&self) -> Result<(), ActorError> {
         while let Ok((ri, vc_id)) = self.tel_recv.recv().await {
-            let who_to_ask = self
-                .registry_id_mapping
-                .get(&ri)
-                .map_err(|e| ActorError::GeneralError(e.to_string()))
-                .unwrap()
-                .ok_or(ActorError::GeneralError(format!(
-                    "Can't find TEL of id: {}",
-                    ri
-                )))?;
+            // Failed update is retried with next query.
+            let _ = self.update_tel(&ri, &vc_id).await;
+        }
+        Ok(())
+    }
+
+    /// Returns identifier of witness that stores TEL of registry `ri`.
+    fn registry_witness(&self, ri: &IdentifierPrefix) -> Result<IdentifierPrefix, ActorError> {
+        self.registry_id_mapping
+            .get(ri)
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?
+            .ok_or(ActorError::GeneralError(format!(
+                "Can't find TEL of id: {}",
+                ri
+            )))
+    }
+
+    /// Asks witness registered for `ri` about TEL of `vc_id`.
+    async fn update_tel(
+        &self,
+        ri: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Result<(), ActorError> {
+        let who_to_ask = self.registry_witness(ri)?;
+        self.watcher_data.tel_update(ri, vc_id, who_to_ask).await
+    }
 
+    /// Asks registry's witness again about TELs of all VCs of registry `ri`
+    /// known to watcher, regardless of cache TTL. Returns number of updated
+    /// TELs.
+    pub async fn refresh_tel(&self, ri: &IdentifierPrefix) -> Result<usize, ActorError> {
+        let who_to_ask = self.registry_witness(ri)?;
+        let vc_ids = self
+            .watcher_data
+            .tel_to_forward
+            .vc_ids(ri)
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        for vc_id in &vc_ids {
             self.watcher_data
-                .tel_update(&ri, &vc_id, who_to_ask.clone())
+                .tel_update(ri, vc_id, who_to_ask.clone())
                 .await?;
         }
-        Ok(())
+        Ok(vc_ids.len())
     }
 
     pub fn oobi(&self) -> LocationScheme {
@@ -97,7 +205,44 @@ impl Watcher {
             self.watcher_data.address.clone(),
         )
     }
+    /// Resolves OOBI and OOBIs of other endpoint providers revealed in
+    /// responses, within configured limits. Only failure of `oobi` itself is
+    /// returned.
+    pub async fn resolve_oobi(&self, oobi: Oobi) -> Result<(), ActorError> {
+        self.resolve_oobi_with_limits(oobi, self.watcher_data.oobi_limits.clone())
+            .await
+    }
+
+    async fn resolve_oobi_with_limits(
+        &self,
+        oobi: Oobi,
+        limits: OobiLimits,
+    ) -> Result<(), ActorError> {
+        let mut traversal = OobiTraversal::new(oobi, limits);
+        while let Some((oobi, depth)) = traversal.next_oobi() {
+            let result = match &oobi {
+                Oobi::Location(loc) => self.fetch_loc_scheme(loc, depth, &mut traversal).await,
+                Oobi::EndRole(er) => self.fetch_end_role(er, depth, &mut traversal).await,
+            };
+            if depth == 0 {
+                result?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn resolve_end_role(&self, er: EndRole) -> Result<(), ActorError> {
+        let oobi = Oobi::EndRole(er.clone());
+        let mut traversal = OobiTraversal::new(oobi, self.watcher_data.oobi_limits.clone());
+        self.fetch_end_role(&er, 0, &mut traversal).await
+    }
+
+    async fn fetch_end_role(
+        &self,
+        er: &EndRole,
+        depth: usize,
+        traversal: &mut OobiTraversal,
+    ) -> Result<(), ActorError> {
         // find endpoint data of endpoint provider identifier
         let loc_scheme = self
             .watcher_data
@@ -110,13 +255,17 @@ impl Watcher {
             .clone();
 
         if let ReplyRoute::LocScheme(loc) = loc_scheme {
+            traversal.admit(&loc.url)?;
             let oobis = self
                 .watcher_data
                 .transport
-                .request_end_role(loc, er.cid.clone(), er.role, er.eid.clone())
-                .await?;
+                .request_end_role(loc, er.cid.clone(), er.role.clone(), er.eid.clone())
+                .await
+                .inspect_err(|_e| self.count_transport_error("end_role"))?;
+            traversal.check_response(&oobis)?;
             match Self::parse_witness_response(&oobis)? {
                 WitnessResp::Kel(kel_event) => {
+                    traversal.discovered(depth, &er.eid, &kel_event);
                     for m in kel_event {
                         match m {
                             Message::Op(op) => {
@@ -163,15 +312,43 @@ impl Watcher {
     }
 
     pub async fn resolve_loc_scheme(&self, loc: &LocationScheme) -> Result<(), ActorError> {
+        let oobi = Oobi::Location(loc.clone());
+        let mut traversal = OobiTraversal::new(oobi, self.watcher_data.oobi_limits.clone());
+        self.fetch_loc_scheme(loc, 0, &mut traversal).await
+    }
+
+    async fn fetch_loc_scheme(
+        &self,
+        loc: &LocationScheme,
+        depth: usize,
+        traversal: &mut OobiTraversal,
+    ) -> Result<(), ActorError> {
+        traversal.admit(&loc.url)?;
         let oobis = self
             .watcher_data
             .transport
             .request_loc_scheme(loc.clone())
-            .await?;
+            .await
+            .inspect_err(|_e| self.count_transport_error("loc_scheme"))?;
+        let revealed: Vec<_> = oobis.iter().cloned().map(Message::Op).collect();
+        traversal.discovered(depth, &loc.eid, &revealed);
         self.watcher_data.process_ops(oobis).await?;
         Ok(())
     }
 
+    fn count_transport_error(&self, request: &str) {
+        self.watcher_data
+            .metrics
+            .transport_errors
+            .with_label_values(&[request])
+            .inc();
+    }
+
+    /// Returns watcher metrics in Prometheus text format.
+    pub fn gather_metrics(&self) -> Result<String, ActorError> {
+        self.watcher_data.gather_metrics()
+    }
+
     fn parse_witness_response(input: &[u8]) -> Result<WitnessResp, ActorError> {
         match parse_event_stream(input) {
             Ok(msgs) => Ok(WitnessResp::Kel(msgs)),
@@ -184,8 +361,21 @@ impl Watcher {
         }
     }
 
+    /// Writes processed stream to debug log, if it's enabled.
+    fn record_stream(&self, kind: &str, input_stream: &[u8]) {
+        if let Err(e) = self.watcher_data.stream_log.record(kind, input_stream) {
+            log::warn!("Failed to record {} stream: {}", kind, e);
+        }
+    }
+
+    /// Enables or disables debug log of processed streams.
+    pub fn set_stream_log_enabled(&self, enabled: bool) {
+        self.watcher_data.stream_log.set_enabled(enabled)
+    }
+
     pub fn parse_and_process_notices(&self, input_stream: &[u8]) -> Result<(), Error> {
-        parse_notice_stream(input_stream)?
+        self.record_stream("notices", input_stream);
+        parse_stream::<Notice>(input_stream, self.watcher_data.strict_canonicalization)?
             .into_iter()
             .try_for_each(|notice| self.watcher_data.process_notice(notice))
     }
@@ -194,34 +384,91 @@ impl Watcher {
         &self,
         input_stream: &[u8],
     ) -> Result<Vec<PossibleResponse>, ActorError> {
+        self.record_stream("queries", input_stream);
         let mut responses = Vec::new();
-        for query in parse_query_stream(input_stream)? {
+        for query in parse_stream::<SignedQueryMessage>(
+            input_stream,
+            self.watcher_data.strict_canonicalization,
+        )? {
             match query {
-                keri_core::query::query_event::SignedQueryMessage::KelQuery(kqry) => {
+                SignedQueryMessage::KelQuery(kqry) => {
                     let result = self.watcher_data.process_query(kqry).await?;
                     if let Some(response) = result {
+                        self.watcher_data.metrics.count_query(&response);
                         responses.push(response);
                     }
                 }
-                keri_core::query::query_event::SignedQueryMessage::MailboxQuery(_mqry) => {
-                    unimplemented!()
+                SignedQueryMessage::MailboxQuery(mqry) => {
+                    let result = self.watcher_data.process_mailbox_query(mqry)?;
+                    if let Some(response) = result {
+                        self.watcher_data.metrics.count_query(&response);
+                        responses.push(response);
+                    }
                 }
             }
         }
         Ok(responses)
     }
 
+    /// Like [`Watcher::parse_and_process_queries`], but returns serialized
+    /// responses in chunks. Whole KELs requested by queries are loaded from
+    /// database while response is sent, so they are never kept in memory at
+    /// once.
+    pub async fn parse_and_process_queries_chunked(
+        &self,
+        input_stream: &[u8],
+    ) -> Result<ResponseChunks, ActorError> {
+        self.record_stream("queries", input_stream);
+        let mut chunks: Vec<ResponseChunks> = Vec::new();
+        for query in parse_stream::<SignedQueryMessage>(
+            input_stream,
+            self.watcher_data.strict_canonicalization,
+        )? {
+            let result = match query {
+                SignedQueryMessage::KelQuery(kqry) => {
+                    if let Some(kel) = self.watcher_data.process_kel_query_lazily(&kqry).await? {
+                        self.watcher_data.metrics.count_kel_query();
+                        chunks.push(kel);
+                        continue;
+                    }
+                    self.watcher_data.process_query(kqry).await?
+                }
+                SignedQueryMessage::MailboxQuery(mqry) => {
+                    self.watcher_data.process_mailbox_query(mqry)?
+                }
+            };
+            if let Some(response) = result {
+                self.watcher_data.metrics.count_query(&response);
+                let serialized = response.to_string().into_bytes();
+                chunks.push(Box::new(std::iter::once(Ok(serialized))));
+            }
+        }
+        Ok(Box::new(chunks.into_iter().flatten()))
+    }
+
     pub fn parse_and_process_replies(&self, input_stream: &[u8]) -> Result<(), ActorError> {
-        for reply in parse_reply_stream(input_stream)? {
+        self.record_stream("replies", input_stream);
+        for reply in
+            parse_stream::<SignedReply>(input_stream, self.watcher_data.strict_canonicalization)?
+        {
             self.watcher_data.process_reply(reply)?;
         }
         Ok(())
     }
 
+    pub fn parse_and_process_exchanges(&self, input_stream: &[u8]) -> Result<(), ActorError> {
+        self.record_stream("exchanges", input_stream);
+        for exchange in parse_stream(input_stream, self.watcher_data.strict_canonicalization)? {
+            self.watcher_data.process_exchange(exchange)?;
+        }
+        Ok(())
+    }
+
     pub async fn parse_and_process_tel_queries(
         &self,
         input_stream: &[u8],
     ) -> Result<Vec<TelReplyType>, ActorError> {
+        self.record_stream("tel_queries", input_stream);
         let tel_queries = parse_tel_query_stream(input_stream)
             .map_err(|_e| ActorError::GeneralError("Can't parse TEL query stream".to_string()))?
             .into_iter();
@@ -242,14 +489,22 @@ impl Watcher {
                     }
                 },
             };
-            // Query witness about new tel events
-            self.watcher_data
-                .tel_tx
-                .send((ri.clone(), vc_id.clone()))
-                .await
-                .map_err(|_e| {
-                    ActorError::GeneralError("Internal watcher error: channel problem".to_string())
-                })?;
+            // Query witness about new tel events if cached ones are too old.
+            // If witness can't be reached now, cached TEL is returned and
+            // update is retried in background.
+            if !self.watcher_data.is_tel_fresh(&ri, &vc_id)
+                && self.update_tel(&ri, &vc_id).await.is_err()
+            {
+                self.watcher_data
+                    .tel_tx
+                    .send((ri.clone(), vc_id.clone()))
+                    .await
+                    .map_err(|_e| {
+                        ActorError::GeneralError(
+                            "Internal watcher error: channel problem".to_string(),
+                        )
+                    })?;
+            }
 
             // Check if you have tel to forward
             if let Some(tel) = self
@@ -264,3 +519,54 @@ impl Watcher {
         Ok(out)
     }
 }
+
+#[async_trait::async_trait]
+impl KeriActor for Watcher {
+    fn prefix(&self) -> BasicPrefix {
+        Watcher::prefix(self)
+    }
+
+    fn oobi(&self) -> LocationScheme {
+        Watcher::oobi(self)
+    }
+
+    fn process_notices(&self, stream: &[u8]) -> Result<(), ActorError> {
+        Ok(self.parse_and_process_notices(stream)?)
+    }
+
+    fn process_replies(&self, stream: &[u8]) -> Result<(), ActorError> {
+        self.parse_and_process_replies(stream)
+    }
+
+    fn process_exchanges(&self, stream: &[u8]) -> Result<(), ActorError> {
+        self.parse_and_process_exchanges(stream)
+    }
+
+    async fn process_queries(&self, stream: &[u8]) -> Result<Vec<PossibleResponse>, ActorError> {
+        self.parse_and_process_queries(stream).await
+    }
+
+    fn location_oobis(&self, eid: &IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+        http_handlers::location_oobis(self, eid)
+    }
+
+    fn role_oobis(
+        &self,
+        cid: &IdentifierPrefix,
+        role: Role,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        http_handlers::role_oobis(self, cid, role, eid)
+    }
+
+    async fn resolve_oobi(&self, oobi: Oobi) -> Result<(), ActorError> {
+        Watcher::resolve_oobi(self, oobi).await
+    }
+
+    async fn handle_request(
+        &self,
+        req: &ActorRequest,
+    ) -> Option<Result<ActorResponse, ActorError>> {
+        http_handlers::handle_request(self, req).await
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
+
+use keri_core::{prefix::IdentifierPrefix, query::provenance::EventProvenance};
+
+/// Remembers witnesses, that supplied events accepted in identifiers' KELs.
+#[derive(Default)]
+pub(super) struct EventSources(RwLock<HashMap<IdentifierPrefix, BTreeMap<u64, EventProvenance>>>);
+
+impl EventSources {
+    /// Keeps first source of event. Source of event replaced at the same sn
+    /// (e.g. by recovery) is overwritten.
+    pub fn record(&self, id: &IdentifierPrefix, event: EventProvenance) {
+        if let Ok(mut sources) = self.0.write() {
+            let events = sources.entry(id.clone()).or_default();
+            match events.get(&event.sn) {
+                Some(recorded) if recorded.digest == event.digest => (),
+                _ => {
+                    events.insert(event.sn, event);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, id: &IdentifierPrefix) -> Vec<EventProvenance> {
+        self.0
+            .read()
+            .ok()
+            .and_then(|sources| {
+                sources
+                    .get(id)
+                    .map(|events| events.values().cloned().collect())
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,145 @@
+//! Journal of messages shared by watcher replicas running behind a load
+//! balancer. Every replica appends events, receipts, replies and exchanges
+//! it accepted to SQLite file available to all of them, and replays
+//! messages appended by other replicas before answering requests. Local
+//! databases of replicas therefore converge, and queries are answered the
+//! same way regardless of which replica served them.
+//!
+//! Journal relies on SQLite file locking, so it needs to be stored on file
+//! system that supports it, e.g. local disk of host running the replicas.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use keri_core::{actor::parse_event_stream, event_message::signed_event_message::Message};
+use rand::{distributions::Alphanumeric, Rng};
+use rusqlite::{params, Connection};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SharedBackendError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Can't encode message: {0}")]
+    Encoding(#[from] keri_core::error::Error),
+    #[error("Shared backend lock poisoned")]
+    Poisoned,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS journal (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        origin TEXT NOT NULL,
+        message BLOB NOT NULL
+    );
+";
+
+/// How long replica waits for other replica's write to finish.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct SharedBackend {
+    connection: Mutex<Connection>,
+    /// Random id of this replica run, used to skip its own messages.
+    origin: String,
+    /// Sequence number of last replayed journal entry. It's kept in
+    /// replica's database directory, so journal isn't replayed from the
+    /// beginning after restart.
+    position: Mutex<i64>,
+    position_path: PathBuf,
+}
+
+impl SharedBackend {
+    /// Opens journal at `path`, creating it if needed. Position of replica
+    /// in journal is stored in `position_path`.
+    pub fn new(path: &Path, position_path: PathBuf) -> Result<Self, SharedBackendError> {
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        let position = match fs::read_to_string(&position_path) {
+            Ok(position) => position.trim().parse().unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+        let origin = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        Ok(Self {
+            connection: Mutex::new(connection),
+            origin,
+            position: Mutex::new(position),
+            position_path,
+        })
+    }
+
+    /// Appends message accepted by this replica to journal.
+    pub fn publish(&self, message: &Message) -> Result<(), SharedBackendError> {
+        let message = message.to_cesr()?;
+        self.connection
+            .lock()
+            .map_err(|_| SharedBackendError::Poisoned)?
+            .execute(
+                "INSERT INTO journal (origin, message) VALUES (?1, ?2)",
+                params![self.origin, message],
+            )?;
+        Ok(())
+    }
+
+    /// Passes messages appended by other replicas since last call to
+    /// `apply`, in order they were appended. Returns number of replayed
+    /// messages.
+    pub fn sync(&self, mut apply: impl FnMut(Message)) -> Result<usize, SharedBackendError> {
+        // Position is locked until messages are applied, so concurrent
+        // requests don't replay the same messages twice.
+        let mut position = self
+            .position
+            .lock()
+            .map_err(|_| SharedBackendError::Poisoned)?;
+        let entries = {
+            let connection = self
+                .connection
+                .lock()
+                .map_err(|_| SharedBackendError::Poisoned)?;
+            let mut stmt = connection
+                .prepare("SELECT seq, origin, message FROM journal WHERE seq > ?1 ORDER BY seq")?;
+            let entries = stmt
+                .query_map(params![*position], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            entries
+        };
+        let Some((last, _, _)) = entries.last() else {
+            return Ok(0);
+        };
+        let last = *last;
+
+        let mut replayed = 0;
+        for (seq, origin, message) in entries {
+            if origin == self.origin {
+                continue;
+            }
+            match parse_event_stream(&message) {
+                Ok(messages) => {
+                    for message in messages {
+                        apply(message);
+                        replayed += 1;
+                    }
+                }
+                Err(e) => log::warn!("Skipping unparsable journal entry {}: {}", seq, e),
+            }
+        }
+        *position = last;
+        fs::write(&self.position_path, last.to_string())?;
+        Ok(replayed)
+    }
+}
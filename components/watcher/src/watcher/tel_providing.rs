@@ -59,6 +59,16 @@ impl Store {
         Ok(out.map(|el| el.to_string()))
     }
 
+    /// Returns keys of all saved values.
+    fn keys(&self) -> Result<Vec<String>, StoreError> {
+        let br = BufReader::new(File::open(&self.0)?);
+        Ok(br
+            .lines()
+            .filter_map(|line| line.ok())
+            .filter_map(|line| line.split_once(':').map(|(key, _)| key.to_string()))
+            .collect())
+    }
+
     pub fn save<K: StoreKey>(&self, key: K, value: String) -> Result<(), StoreError> {
         let lines = {
             let br = BufReader::new(File::open(&self.0)?);
@@ -87,8 +97,17 @@ impl Store {
             }
         };
 
-        let mut f = fs::File::create(&self.0).expect("no file found");
+        // Write to temporary file and replace store with it, so concurrent
+        // readers and backups never see partially written contents.
+        let tmp_path = self.0.with_extension("tmp");
+        let mut f = fs::File::create(&tmp_path)?;
         f.write_all(new_contents.as_bytes())?;
+        fs::rename(&tmp_path, &self.0)?;
+        Ok(())
+    }
+
+    fn backup(&self, target: &Path) -> Result<(), StoreError> {
+        fs::copy(&self.0, target)?;
         Ok(())
     }
 }
@@ -156,6 +175,26 @@ impl TelToForward {
         let vc_key = VCKey { ri, vc_id };
         self.tel.get(&vc_key)
     }
+
+    /// Returns identifiers of VCs of registry `ri`, which TEL was saved.
+    pub fn vc_ids(&self, ri: &IdentifierPrefix) -> Result<Vec<IdentifierPrefix>, StoreError> {
+        let ri = ri.to_string();
+        self.tel
+            .keys()?
+            .into_iter()
+            .filter_map(|key| {
+                key.split_once(',')
+                    .filter(|(key_ri, _)| *key_ri == ri)
+                    .map(|(_, vc_id)| vc_id.to_string())
+            })
+            .map(|vc_id| vc_id.parse().map_err(|_e| StoreError::ValueParsing(vc_id)))
+            .collect()
+    }
+
+    /// Copies collected TEL events into `target` file.
+    pub fn backup(&self, target: &Path) -> Result<(), StoreError> {
+        self.tel.backup(target)
+    }
 }
 
 impl StoreKey for IdentifierPrefix {
@@ -189,6 +228,11 @@ impl RegistryMapping {
             .map(|id| id.parse().map_err(|_e| StoreError::ValueParsing(id)))
             .transpose()
     }
+
+    /// Copies mapping into `target` file.
+    pub fn backup(&self, target: &Path) -> Result<(), StoreError> {
+        self.mapping.backup(target)
+    }
 }
 
 #[test]
@@ -217,4 +261,39 @@ fn test_tel_to_forward() {
         .save(&registry_id, &vc_id, not_full_tel.to_string())
         .unwrap();
     assert_eq!(saved.as_ref(), Some(full_tel.to_string()).as_ref());
+
+    assert_eq!(tel_to_forward.vc_ids(&registry_id).unwrap(), vec![vc_id]);
+    assert!(tel_to_forward
+        .vc_ids(
+            &"EL2KqdbeSkemPII22qQ9dNglhBYa2YaQL7ePjN-3aTGg"
+                .parse()
+                .unwrap()
+        )
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_registry_mapping_backup() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let mapping = RegistryMapping::new(&tmp_dir.path().join("registry")).unwrap();
+    let registry_id: IdentifierPrefix = "EEJeOc0HPZScDMKD-L9RsJ9K5-j73IZkMA2tui5gYEpH"
+        .parse()
+        .unwrap();
+    let witness_id: IdentifierPrefix = "BJq7UABlttINuWJh1Xl2lkqZG4NTdUdqnbFJDa6ZyxCC"
+        .parse()
+        .unwrap();
+    mapping
+        .save(registry_id.clone(), witness_id.clone())
+        .unwrap();
+
+    let backup_path = tmp_dir.path().join("registry_backup");
+    mapping.backup(&backup_path).unwrap();
+    // Saving after backup doesn't change it.
+    mapping
+        .save(registry_id.clone(), registry_id.clone())
+        .unwrap();
+
+    let restored = RegistryMapping::new(&backup_path).unwrap();
+    assert_eq!(restored.get(&registry_id).unwrap(), Some(witness_id));
 }
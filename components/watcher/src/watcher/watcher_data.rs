@@ -1,12 +1,19 @@
-use std::{fs::File, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use async_std::channel::Sender;
+use chrono::{DateTime, FixedOffset};
 use futures::future::join_all;
 use itertools::Itertools;
-use keri_core::oobi::LocationScheme;
+use keri_core::actor::stream_log::StreamLog;
+use keri_core::oobi::{traversal::OobiLimits, LocationScheme};
 use keri_core::prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix};
 use keri_core::processor::escrow::default_escrow_bus;
 use keri_core::query::{
+    provenance::{EventProvenance, Provenance, ProvenanceBundle, SignedProvenance},
     reply_event::{ReplyEvent, ReplyRoute, SignedReply},
     ReplyType,
 };
@@ -14,14 +21,15 @@ use keri_core::state::IdentifierState;
 use keri_core::{
     actor::{
         error::ActorError,
-        prelude::{HashFunctionCode, SerializationFormats},
-        process_notice, process_reply,
+        prelude::{HashFunctionCode, SelfAddressingIdentifier, SerializationFormats},
+        process_guarded_query, process_notice, process_reply, process_signed_exn,
+        process_signed_query,
         simple_controller::PossibleResponse,
         QueryError, SignedQueryError,
     },
     oobi::{Role, Scheme},
 };
-use keri_core::{database::escrow::EscrowDb, error::Error};
+use keri_core::{clock::Clock, database::escrow::EscrowDb, error::Error};
 use keri_core::{
     database::{
         redb::RedbDatabase,
@@ -29,26 +37,45 @@ use keri_core::{
     },
     event_message::{
         msg::KeriEvent,
-        signed_event_message::{Message, Notice, Op},
+        signed_event_message::{Message, Notice, Op, SignedEventMessage},
         timestamped::Timestamped,
     },
 };
 use keri_core::{
+    mailbox::exchange::{Exchange, SignedExchange},
     oobi::OobiManager,
     processor::{basic_processor::BasicProcessor, event_storage::EventStorage},
     signer::Signer,
     transport::Transport,
 };
 use keri_core::{
-    processor::{escrow::ReplyEscrow, notification::JustNotification},
-    query::query_event::{
-        LogsQueryArgs, QueryEvent, QueryRoute, SignedKelQuery, SignedQueryMessage,
+    processor::{
+        escrow::{DelegationEscrow, OutOfOrderEscrow, PartiallySignedEscrow, ReplyEscrow},
+        notification::JustNotification,
+    },
+    query::{
+        mailbox::SignedMailboxQuery,
+        query_event::{LogsQueryArgs, QueryEvent, QueryRoute, SignedKelQuery, SignedQueryMessage},
+        replay::QueryReplayGuard,
     },
 };
 use teliox::query::{SignedTelQuery, TelQueryArgs, TelQueryRoute};
-use teliox::transport::GeneralTelTransport;
+use teliox::transport::TelTransport;
 
-use super::{config::WatcherConfig, tel_providing::TelToForward};
+use crate::metrics::WatcherMetrics;
+
+use super::{
+    config::WatcherConfig,
+    cross_verification::{
+        compare_kels, verify_witness_ksn, WitnessQueryPolicy, WitnessResponse,
+        WitnessResponseOutcome,
+    },
+    duplicity::{ConflictingEvent, DuplicityReport, DuplicitySources},
+    provenance::EventSources,
+    shared_backend::SharedBackend,
+    tel_providing::TelToForward,
+    ResponseChunks,
+};
 
 pub struct WatcherData {
     pub address: url::Url,
@@ -58,12 +85,45 @@ pub struct WatcherData {
     pub oobi_manager: OobiManager,
     pub signer: Arc<Signer>,
     pub transport: Box<dyn Transport + Send + Sync>,
-    pub tel_transport: Box<dyn GeneralTelTransport + Send + Sync>,
+    pub tel_transport: Box<dyn TelTransport + Send + Sync>,
     /// Watcher will update KEL of the identifiers that have been sent to this channel.
     tx: Sender<IdentifierPrefix>,
     /// Watcher will update TEL of the identifiers (registry_id, vc_id) that have been sent to this channel.
     pub tel_tx: Sender<(IdentifierPrefix, IdentifierPrefix)>,
     pub(super) tel_to_forward: Arc<TelToForward>,
+    witness_query_policy: WitnessQueryPolicy,
+    /// Witness responses collected during last KEL update of identifier.
+    witness_responses: RwLock<HashMap<IdentifierPrefix, Vec<WitnessResponse>>>,
+    ksn_cache_ttl: Duration,
+    /// Time of last successful state verification with witnesses.
+    verified_at: RwLock<HashMap<IdentifierPrefix, DateTime<FixedOffset>>>,
+    tel_cache_ttl: Duration,
+    /// Source and time of last successful TEL update of (registry_id, vc_id).
+    tel_provenance: RwLock<HashMap<(IdentifierPrefix, IdentifierPrefix), Provenance>>,
+    /// Limits of following OOBIs revealed while resolving OOBI.
+    pub oobi_limits: OobiLimits,
+    /// Debug log of processed streams.
+    pub stream_log: StreamLog,
+    /// Reject messages, which bytes differ from their canonical
+    /// serialization.
+    pub strict_canonicalization: bool,
+    /// Rejects replayed queries and queries with timestamp out of allowed
+    /// skew. Queries aren't checked if it's not set.
+    query_replay_guard: Option<QueryReplayGuard>,
+    /// Witnesses that provided events conflicting with accepted ones.
+    duplicity_sources: DuplicitySources,
+    /// Witnesses that provided events accepted in KELs.
+    event_sources: EventSources,
+    pub metrics: Arc<WatcherMetrics>,
+    out_of_order_escrow: Arc<OutOfOrderEscrow<RedbDatabase>>,
+    partially_signed_escrow: Arc<PartiallySignedEscrow<RedbDatabase>>,
+    delegation_escrow: Arc<DelegationEscrow<RedbDatabase>>,
+    /// Journal shared with other replicas of watcher, if any.
+    shared_backend: Option<SharedBackend>,
+    /// Maximal size of mailbox response in bytes.
+    mailbox_max_size: usize,
+    /// Source of current time, shared with escrows.
+    clock: Arc<dyn Clock>,
 }
 
 impl WatcherData {
@@ -76,26 +136,46 @@ impl WatcherData {
             public_address,
             db_path,
             priv_key,
+            signer,
             transport,
             tel_transport,
             escrow_config,
             tel_storage_path,
+            witness_query_policy,
+            ksn_cache_ttl,
+            tel_cache_ttl,
+            oobi_limits,
+            stream_log,
+            strict_canonicalization,
+            query_max_skew,
+            shared_backend,
+            mailbox_max_size,
+            max_clock_drift,
         } = config;
+        let clock = escrow_config.clock.clone();
+        let stream_log = stream_log.unwrap_or_else(|| StreamLog::new(&db_path.join("streams.log")));
         let mut tel_to_forward_path = tel_storage_path.clone();
         tel_to_forward_path.push("to_forward");
 
-        let signer = Arc::new(
-            priv_key
-                .map(|key| Signer::new_with_seed(&key.parse()?))
-                .unwrap_or_else(|| Ok(Signer::new()))?,
-        );
+        let signer = match signer {
+            Some(signer) => signer,
+            None => Arc::new(
+                priv_key
+                    .map(|key| Signer::new_with_seed(&key.parse()?))
+                    .unwrap_or_else(|| Ok(Signer::new()))?,
+            ),
+        };
+
+        let shared_backend = shared_backend
+            .map(|path| SharedBackend::new(&path, db_path.join("shared_backend_position")))
+            .transpose()
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
 
-        let db = Arc::new(SledEventDatabase::new(db_path.clone())?);
+        let db = Arc::new(SledEventDatabase::new(db_path.clone())?.with_clock(clock.clone()));
         let events_db = {
             let mut path = db_path.clone();
             path.push("events_database");
-            let _file = File::create(&path).unwrap();
-            Arc::new(RedbDatabase::new(&path).unwrap())
+            Arc::new(RedbDatabase::new(&path).unwrap().with_clock(clock.clone()))
         };
 
         let escrow_db = {
@@ -108,10 +188,14 @@ impl WatcherData {
             let mut path = db_path.clone();
             path.push("oobi");
             OobiManager::new(&path)
+                .with_max_clock_drift(max_clock_drift)
+                .with_clock(clock.clone())
         };
 
-        let (mut notification_bus, _) =
-            default_escrow_bus(events_db.clone(), db.clone(), escrow_db, escrow_config);
+        let (
+            mut notification_bus,
+            (out_of_order_escrow, partially_signed_escrow, _, delegation_escrow),
+        ) = default_escrow_bus(events_db.clone(), db.clone(), escrow_db, escrow_config);
         notification_bus.register_observer(
             Arc::new(ReplyEscrow::new(db.clone(), events_db.clone())),
             vec![
@@ -121,9 +205,11 @@ impl WatcherData {
         );
 
         let prefix = BasicPrefix::Ed25519NT(signer.public_key()); // watcher uses non transferable key
-        let processor = BasicProcessor::new(events_db.clone(), db.clone(), Some(notification_bus));
+        let processor = BasicProcessor::new(events_db.clone(), db.clone(), Some(notification_bus))
+            .with_max_clock_drift(max_clock_drift)
+            .with_clock(clock.clone());
 
-        let storage = Arc::new(EventStorage::new(events_db, db));
+        let storage = Arc::new(EventStorage::new(events_db, db).with_clock(clock.clone()));
 
         // construct witness loc scheme oobi
         let loc_scheme = LocationScheme::new(
@@ -160,6 +246,28 @@ impl WatcherData {
             ),
             tel_tx,
             tel_transport,
+            witness_query_policy,
+            witness_responses: RwLock::new(HashMap::new()),
+            ksn_cache_ttl,
+            verified_at: RwLock::new(HashMap::new()),
+            tel_cache_ttl,
+            tel_provenance: RwLock::new(HashMap::new()),
+            oobi_limits,
+            stream_log,
+            strict_canonicalization,
+            query_replay_guard: query_max_skew
+                .map(|max_skew| QueryReplayGuard::new(max_skew).with_clock(clock.clone())),
+            duplicity_sources: DuplicitySources::default(),
+            event_sources: EventSources::default(),
+            metrics: Arc::new(
+                WatcherMetrics::new().map_err(|e| ActorError::GeneralError(e.to_string()))?,
+            ),
+            out_of_order_escrow,
+            partially_signed_escrow,
+            delegation_escrow,
+            shared_backend,
+            mailbox_max_size,
+            clock,
         });
         Ok(watcher.clone())
     }
@@ -169,6 +277,7 @@ impl WatcherData {
         &self,
         eid: &IdentifierPrefix,
     ) -> Result<Vec<SignedReply>, ActorError> {
+        self.sync_shared_backend()?;
         Ok(match self.oobi_manager.get_loc_scheme(eid)? {
             Some(oobis_to_sign) => oobis_to_sign
                 .iter()
@@ -190,6 +299,7 @@ impl WatcherData {
         cid: &IdentifierPrefix,
         role: Role,
     ) -> Result<Vec<SignedReply>, ActorError> {
+        self.sync_shared_backend()?;
         self.oobi_manager
             .get_end_role(&cid, role)
             .map(|el| el.unwrap_or_default())
@@ -223,18 +333,93 @@ impl WatcherData {
     }
 
     pub fn process_notice(&self, notice: Notice) -> Result<(), Error> {
-        process_notice(notice, &self.processor)
+        self.apply_notice(notice.clone())?;
+        self.publish(Message::Notice(notice));
+        Ok(())
+    }
+
+    fn apply_notice(&self, notice: Notice) -> Result<(), Error> {
+        process_notice(notice, &self.processor)?;
+        self.metrics.notices_processed.inc();
+        Ok(())
+    }
+
+    /// Appends accepted message to journal shared with other replicas.
+    fn publish(&self, message: Message) {
+        if let Some(shared_backend) = &self.shared_backend {
+            if let Err(e) = shared_backend.publish(&message) {
+                log::warn!("Failed to publish message to shared backend: {}", e);
+            }
+        }
+    }
+
+    /// Replays messages accepted by other replicas of watcher since last
+    /// synchronization. Does nothing if shared backend isn't configured.
+    pub fn sync_shared_backend(&self) -> Result<(), ActorError> {
+        let Some(shared_backend) = &self.shared_backend else {
+            return Ok(());
+        };
+        shared_backend
+            .sync(|message| {
+                let result = match message {
+                    Message::Notice(notice) => self.apply_notice(notice).map_err(ActorError::from),
+                    Message::Op(Op::Reply(rpy)) => self.apply_reply(rpy).map_err(ActorError::from),
+                    Message::Op(Op::Exchange(exn)) => self.apply_exchange(exn),
+                    Message::Op(Op::Query(_)) => Ok(()),
+                };
+                // Messages already known to this replica are rejected as
+                // duplicates.
+                if let Err(e) = result {
+                    log::debug!("Replayed message not applied: {}", e);
+                }
+            })
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Returns watcher metrics in Prometheus text format. Escrow sizes are
+    /// updated first.
+    pub fn gather_metrics(&self) -> Result<String, ActorError> {
+        for (escrow, size) in [
+            (
+                "out_of_order",
+                self.out_of_order_escrow.escrowed_out_of_order.count(),
+            ),
+            (
+                "partially_signed",
+                self.partially_signed_escrow
+                    .escrowed_partially_signed
+                    .count(),
+            ),
+            (
+                "delegation",
+                self.delegation_escrow.delegation_escrow.count(),
+            ),
+        ] {
+            self.metrics
+                .escrow_size
+                .with_label_values(&[escrow])
+                .set(size as i64);
+        }
+        self.metrics
+            .encode()
+            .map_err(|e| ActorError::GeneralError(e.to_string()))
     }
 
     pub async fn process_op(&self, op: Op) -> Result<Option<PossibleResponse>, ActorError> {
         match op {
             Op::Query(SignedQueryMessage::KelQuery(qry)) => Ok(self.process_query(qry).await?),
-            Op::Query(SignedQueryMessage::MailboxQuery(_qry)) => todo!(),
+            Op::Query(SignedQueryMessage::MailboxQuery(qry)) => {
+                Ok(self.process_mailbox_query(qry)?)
+            }
             Op::Reply(rpy) => {
                 self.process_reply(rpy)?;
                 Ok(None)
             }
-            Op::Exchange(_exn) => Ok(None),
+            Op::Exchange(exn) => {
+                self.process_exchange(exn)?;
+                Ok(None)
+            }
         }
     }
 
@@ -242,6 +427,65 @@ impl WatcherData {
         &self,
         qry: SignedKelQuery,
     ) -> Result<Option<PossibleResponse>, ActorError> {
+        self.check_query(&qry).await?;
+
+        let response =
+            match keri_core::actor::process_query(qry.query.get_route(), &self.event_storage) {
+                Ok(reply) => reply,
+                Err(QueryError::UnknownId { id }) => {
+                    return Err(ActorError::NoIdentState { prefix: id })
+                }
+                Err(e) => {
+                    return Err(ActorError::GeneralError(e.to_string()));
+                }
+            };
+
+        match response {
+            ReplyType::Ksn(mut ksn) => {
+                // Tell when state was last confirmed by witnesses. Notice
+                // timestamp stays time of its issuance.
+                ksn.verified_at = self.verified_at(&ksn.state.prefix);
+                let rpy = ReplyEvent::new_reply(
+                    ReplyRoute::Ksn(IdentifierPrefix::Basic(self.prefix.clone()), ksn),
+                    HashFunctionCode::Blake3_256,
+                    SerializationFormats::JSON,
+                );
+
+                let signature = SelfSigningPrefix::Ed25519Sha512(self.signer.sign(&rpy.encode()?)?);
+                let reply = SignedReply::new_nontrans(rpy, self.prefix.clone(), signature);
+                Ok(Some(PossibleResponse::Ksn(reply)))
+            }
+            ReplyType::Kel(msgs) => Ok(Some(PossibleResponse::Kel(msgs))),
+            ReplyType::Mbx(mbx) => Ok(Some(PossibleResponse::Mbx(mbx))),
+        }
+    }
+
+    /// Processes query for whole KEL as [`WatcherData::process_query`] does,
+    /// but returns KEL serialized in chunks, which are loaded from database
+    /// lazily. Returns `None` if query isn't for whole KEL.
+    pub async fn process_kel_query_lazily(
+        &self,
+        qry: &SignedKelQuery,
+    ) -> Result<Option<ResponseChunks>, ActorError> {
+        let QueryRoute::Logs { args, .. } = qry.query.get_route() else {
+            return Ok(None);
+        };
+        if args.after.is_some() || args.s.is_some() {
+            return Ok(None);
+        }
+        self.check_query(qry).await?;
+        let kel = self
+            .event_storage
+            .get_kel_stream(&args.i)
+            .with_receipts()
+            .map(|notice| notice.and_then(|notice| Message::Notice(notice).to_cesr()));
+        Ok(Some(Box::new(kel)))
+    }
+
+    /// Verifies query and its sender and checks whether watcher needs to
+    /// update KEL from witnesses before answering.
+    async fn check_query(&self, qry: &SignedKelQuery) -> Result<(), ActorError> {
+        self.sync_shared_backend()?;
         let cid = qry
             .signature
             .get_signer()
@@ -254,8 +498,7 @@ impl WatcherData {
         }
 
         // Check signature
-        let signature = qry.signature;
-        let ver_result = signature.verify(
+        let ver_result = qry.signature.verify(
             &qry.query.encode().map_err(|_e| Error::VersionError)?,
             &self.event_storage,
         )?;
@@ -263,6 +506,9 @@ impl WatcherData {
         if !ver_result {
             return Err(SignedQueryError::InvalidSignature.into());
         };
+        if let Some(guard) = &self.query_replay_guard {
+            guard.check(&SignedQueryMessage::KelQuery(qry.clone()))?;
+        }
 
         // Check if we need to update state from witnesses
         match &qry.query.get_route() {
@@ -272,6 +518,13 @@ impl WatcherData {
             } => {
                 let local_state = self.get_state_for_prefix(&args.i);
                 match (local_state, args.s, args.limit) {
+                    (Some(_state), _, _) if args.after.is_some() => {
+                        // Answer with events known now and check for updates.
+                        let id_to_update = qry.query.get_prefix();
+                        self.tx.send(id_to_update.clone()).await.map_err(|_e| {
+                            ActorError::GeneralError("Internal watcher error".to_string())
+                        })?;
+                    }
                     (Some(state), Some(sn), Some(limit)) if sn + limit - 1 <= state.sn => {
                         // KEL is already in database
                     }
@@ -302,45 +555,92 @@ impl WatcherData {
                 let local_state = self.get_state_for_prefix(&args.i);
                 match (local_state, args.s) {
                     (Some(state), Some(sn)) if sn <= state.sn => {}
+                    (Some(_), None) if self.is_state_fresh(&args.i) => {
+                        // Answer from state recently verified with witnesses
+                    }
                     _ => {
                         // query watcher and return info, that it's not ready
                         let _ = self.update_local_kel(&qry.query.get_prefix()).await;
                     }
                 };
             }
+            QueryRoute::LogsBetween {
+                reply_route: _,
+                args,
+            } => {
+                // Answer with events known now and check for updates.
+                let id_to_update = qry.query.get_prefix();
+                self.tx
+                    .send(id_to_update.clone())
+                    .await
+                    .map_err(|_e| ActorError::GeneralError("Internal watcher error".to_string()))?;
+                if self.get_state_for_prefix(&args.i).is_none() {
+                    return Err(ActorError::NotFound(id_to_update));
+                }
+            }
         }
+        Ok(())
+    }
 
-        let response =
-            match keri_core::actor::process_query(qry.query.get_route(), &self.event_storage) {
-                Ok(reply) => reply,
-                Err(QueryError::UnknownId { id }) => {
-                    return Err(ActorError::NoIdentState { prefix: id })
-                }
-                Err(e) => {
-                    return Err(ActorError::GeneralError(e.to_string()));
-                }
-            };
+    /// Saves forwarded event in recipient's mailbox, as witness does.
+    /// Recipient needs to designate watcher with end role, and sender's KEL
+    /// needs to be known to watcher to verify exchange signatures.
+    pub fn process_exchange(&self, exn: SignedExchange) -> Result<(), ActorError> {
+        self.sync_shared_backend()?;
+        self.apply_exchange(exn.clone())?;
+        self.publish(Message::Op(Op::Exchange(exn)));
+        Ok(())
+    }
 
-        match response {
-            ReplyType::Ksn(ksn) => {
-                let rpy = ReplyEvent::new_reply(
-                    ReplyRoute::Ksn(IdentifierPrefix::Basic(self.prefix.clone()), ksn),
-                    HashFunctionCode::Blake3_256,
-                    SerializationFormats::JSON,
-                );
+    fn apply_exchange(&self, exn: SignedExchange) -> Result<(), ActorError> {
+        let recipient = match &exn.exchange_message.data.data {
+            Exchange::Fwd { args, .. } => &args.recipient_id,
+        };
+        if !self.check_role(recipient)? {
+            return Err(ActorError::MissingRole {
+                id: recipient.clone(),
+                role: Role::Watcher,
+            });
+        }
+        process_signed_exn(exn, &self.event_storage)?;
+        Ok(())
+    }
 
-                let signature = SelfSigningPrefix::Ed25519Sha512(self.signer.sign(&rpy.encode()?)?);
-                let reply = SignedReply::new_nontrans(rpy, self.prefix.clone(), signature);
-                Ok(Some(PossibleResponse::Ksn(reply)))
-            }
-            ReplyType::Kel(msgs) => Ok(Some(PossibleResponse::Kel(msgs))),
-            ReplyType::Mbx(mbx) => Ok(Some(PossibleResponse::Mbx(mbx))),
+    /// Returns messages from mailbox of identifier that designated watcher
+    /// with end role.
+    pub fn process_mailbox_query(
+        &self,
+        qry: SignedMailboxQuery,
+    ) -> Result<Option<PossibleResponse>, ActorError> {
+        self.sync_shared_backend()?;
+        let cid = qry
+            .signature
+            .get_signer()
+            .ok_or(ActorError::MissingSignerId)?;
+        if !self.check_role(&cid)? {
+            return Err(ActorError::MissingRole {
+                id: cid.clone(),
+                role: Role::Watcher,
+            });
+        }
+        let from = self.event_storage.mailbox_start(&qry.query.get_args())?;
+
+        let qry = SignedQueryMessage::MailboxQuery(qry);
+        let response = match &self.query_replay_guard {
+            Some(guard) => process_guarded_query(qry, &self.event_storage, guard)?,
+            None => process_signed_query(qry, &self.event_storage)?,
+        };
+        match response {
+            ReplyType::Mbx(mbx) => Ok(Some(PossibleResponse::Mbx(
+                mbx.limit_size(self.mailbox_max_size, &from)?,
+            ))),
+            _ => Ok(None),
         }
     }
 
     pub async fn update_local_kel(&self, id: &IdentifierPrefix) -> Result<(), ActorError> {
         // Update latest state for prefix
-        let _ = self.query_state(id).await;
+        let agreed = self.query_state(id).await.ok();
 
         let escrowed_replies = self
             .event_storage
@@ -353,12 +653,18 @@ impl WatcherData {
         if !escrowed_replies.is_empty() {
             // If there is an escrowed reply it means we don't have the most recent data.
             // In this case forward the query to witness.
-            self.forward_query(id).await?;
+            self.forward_query(id, agreed.as_ref()).await?;
         };
         Ok(())
     }
 
     pub fn process_reply(&self, reply: SignedReply) -> Result<(), Error> {
+        self.apply_reply(reply.clone())?;
+        self.publish(Message::Op(Op::Reply(reply)));
+        Ok(())
+    }
+
+    fn apply_reply(&self, reply: SignedReply) -> Result<(), Error> {
         process_reply(
             reply,
             &self.oobi_manager,
@@ -369,10 +675,18 @@ impl WatcherData {
     }
 
     /// Forward query to registered witnesses and save its response to mailbox.
-    async fn forward_query(&self, id: &IdentifierPrefix) -> Result<(), ActorError> {
+    /// Only events following locally known ones are requested. KELs returned
+    /// by witnesses are compared with each other and with `agreed` state
+    /// before processing.
+    async fn forward_query(
+        &self,
+        id: &IdentifierPrefix,
+        agreed: Option<&(u64, SelfAddressingIdentifier)>,
+    ) -> Result<(), ActorError> {
         let witnesses = self.get_witnesses_for_prefix(&id)?;
+        let mut kels = vec![];
         for witness in witnesses {
-            let witness_id = IdentifierPrefix::Basic(witness);
+            let witness_id = IdentifierPrefix::Basic(witness.clone());
             let route = QueryRoute::Logs {
                 reply_route: "".to_string(),
                 args: LogsQueryArgs {
@@ -380,6 +694,7 @@ impl WatcherData {
                     s: None,
                     src: Some(witness_id.clone()),
                     limit: None,
+                    after: self.get_state_for_prefix(id).map(|state| state.sn),
                 },
             };
 
@@ -404,37 +719,270 @@ impl WatcherData {
                 PossibleResponse::Ksn(rpy) => {
                     self.process_reply(rpy)?;
                 }
-                PossibleResponse::Kel(msgs) => {
-                    for msg in msgs {
-                        if let Message::Notice(notice) = msg {
-                            self.process_notice(notice.clone())?;
-                            if let Notice::Event(evt) = notice {
-                                self.event_storage.add_mailbox_reply(evt)?;
-                            }
-                        }
-                    }
-                }
+                PossibleResponse::Kel(msgs) => kels.push((witness, msgs)),
                 PossibleResponse::Mbx(_mbx) => {
                     panic!("Unexpected response type MBX");
                 }
             }
         }
 
+        let events = kels
+            .iter()
+            .map(|(witness, msgs)| {
+                let events = msgs
+                    .iter()
+                    .filter_map(|msg| match msg {
+                        Message::Notice(Notice::Event(evt)) => Some(evt.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                (witness.clone(), events)
+            })
+            .collect::<Vec<_>>();
+        if let Err(e) = compare_kels(&events, agreed) {
+            for (witness, events) in &events {
+                for evt in events {
+                    self.record_conflict_source(evt, witness);
+                }
+            }
+            return Err(ActorError::GeneralError(e.to_string()));
+        }
+
+        for (witness, msgs) in kels {
+            for msg in msgs {
+                if let Message::Notice(notice) = msg {
+                    if let Notice::Event(evt) = &notice {
+                        self.record_conflict_source(evt, &witness);
+                    }
+                    self.process_notice(notice.clone())?;
+                    if let Notice::Event(evt) = notice {
+                        self.record_event_source(&evt, &witness);
+                        self.event_storage.add_mailbox_reply(evt)?;
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 
-    /// Query witness about KSN for given prefix and save its response to db.
-    /// Returns ID of witness that responded.
-    async fn query_state(&self, prefix: &IdentifierPrefix) -> Result<(), ActorError> {
+    /// Query witnesses about KSN for given prefix and compare their
+    /// responses. Only notices signed by queried witnesses are taken into
+    /// account. Responses are saved to db only if they agree according to
+    /// witness query policy. Returns agreed sn and last event digest.
+    async fn query_state(
+        &self,
+        prefix: &IdentifierPrefix,
+    ) -> Result<(u64, SelfAddressingIdentifier), ActorError> {
         let wits_id = self.get_witnesses_for_prefix(&prefix)?;
-        let _r: Vec<Result<_, _>> = join_all(wits_id.into_iter().map(|id| {
-            let id = IdentifierPrefix::Basic(id);
-
-            self.ksn_update(&prefix, id)
+        let witnesses_count = wits_id.len();
+        let results = join_all(wits_id.into_iter().map(|wit| async move {
+            let resp = async_std::future::timeout(
+                self.witness_query_policy.timeout,
+                self.ksn_request(prefix, IdentifierPrefix::Basic(wit.clone())),
+            )
+            .await;
+            (wit, resp)
         }))
         .await;
 
-        Ok(())
+        let mut replies = vec![];
+        let mut responses = vec![];
+        for (witness, result) in results {
+            let outcome = match result {
+                Ok(Ok(rpy)) => match verify_witness_ksn(&rpy, &witness) {
+                    Ok((sn, digest)) => {
+                        replies.push((sn, rpy));
+                        WitnessResponseOutcome::State { sn, digest }
+                    }
+                    Err(e) => WitnessResponseOutcome::Error(e.to_string()),
+                },
+                Ok(Err(e)) => WitnessResponseOutcome::Error(e.to_string()),
+                Err(_timeout) => WitnessResponseOutcome::Timeout,
+            };
+            responses.push(WitnessResponse { witness, outcome });
+        }
+
+        let agreed = self
+            .witness_query_policy
+            .agreed_sn(&responses, witnesses_count)
+            .map(|sn| {
+                // Witnesses can't disagree about event at the same sn, so
+                // any of agreeing responses can be taken.
+                let digest = responses.iter().find_map(|resp| match &resp.outcome {
+                    WitnessResponseOutcome::State { sn: s, digest } if *s == sn => {
+                        Some(digest.clone())
+                    }
+                    _ => None,
+                });
+                (sn, digest)
+            });
+        self.witness_responses
+            .write()
+            .map_err(|_e| ActorError::GeneralError("Internal watcher error".to_string()))?
+            .insert(prefix.clone(), responses);
+        let (sn, digest) = agreed.map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        let digest =
+            digest.ok_or_else(|| ActorError::GeneralError("Missing agreed state".to_string()))?;
+
+        for (_, rpy) in replies.into_iter().filter(|(reply_sn, _)| *reply_sn == sn) {
+            self.process_reply(rpy)?;
+        }
+        self.verified_at
+            .write()
+            .map_err(|_e| ActorError::GeneralError("Internal watcher error".to_string()))?
+            .insert(prefix.clone(), self.now());
+
+        Ok((sn, digest))
+    }
+
+    /// Returns time of last successful verification of identifier's state
+    /// with its witnesses.
+    pub fn verified_at(&self, id: &IdentifierPrefix) -> Option<DateTime<FixedOffset>> {
+        self.verified_at
+            .read()
+            .ok()
+            .and_then(|verified| verified.get(id).cloned())
+    }
+
+    /// Current time of watcher clock.
+    pub(crate) fn now(&self) -> DateTime<FixedOffset> {
+        self.clock.now().into()
+    }
+
+    fn is_state_fresh(&self, id: &IdentifierPrefix) -> bool {
+        self.verified_at(id)
+            .and_then(|verified_at| self.now().signed_duration_since(verified_at).to_std().ok())
+            .map_or(false, |age| age < self.ksn_cache_ttl)
+    }
+
+    /// Remembers witness that provided event, if it conflicts with event
+    /// accepted at the same sn.
+    fn record_conflict_source(&self, event: &SignedEventMessage, witness: &BasicPrefix) {
+        let data = &event.event_message.data;
+        let accepted = self
+            .event_storage
+            .get_event_at_sn(&data.get_prefix(), data.get_sn());
+        if let (Some(accepted), Ok(digest)) = (accepted, event.event_message.digest()) {
+            if accepted.signed_event_message.event_message.digest().ok() != Some(digest.clone()) {
+                self.duplicity_sources.record(&digest, witness.clone());
+            }
+        }
+    }
+
+    /// Remembers witness that provided event, if event was accepted.
+    fn record_event_source(&self, event: &SignedEventMessage, witness: &BasicPrefix) {
+        let data = &event.event_message.data;
+        let accepted = self
+            .event_storage
+            .get_event_at_sn(&data.get_prefix(), data.get_sn());
+        if let (Some(accepted), Ok(digest)) = (accepted, event.event_message.digest()) {
+            if accepted.signed_event_message.event_message.digest().ok() == Some(digest.clone()) {
+                self.event_sources.record(
+                    &data.get_prefix(),
+                    EventProvenance {
+                        sn: data.get_sn(),
+                        digest,
+                        provenance: Provenance {
+                            source: IdentifierPrefix::Basic(witness.clone()),
+                            retrieved_at: self.now(),
+                        },
+                    },
+                );
+            }
+        }
+    }
+
+    /// Returns witnesses that supplied events of identifier's KEL, signed
+    /// by watcher.
+    pub fn get_kel_provenance(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<SignedProvenance, ActorError> {
+        self.sign_provenance(ProvenanceBundle::Kel {
+            id: id.clone(),
+            events: self.event_sources.get(id),
+        })
+    }
+
+    /// Returns backer that supplied TEL of `vc_id`, signed by watcher.
+    pub fn get_tel_provenance(
+        &self,
+        ri: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Result<SignedProvenance, ActorError> {
+        let provenance = self
+            .tel_provenance
+            .read()
+            .ok()
+            .and_then(|provenance| provenance.get(&(ri.clone(), vc_id.clone())).cloned());
+        self.sign_provenance(ProvenanceBundle::Tel {
+            ri: ri.clone(),
+            vc_id: vc_id.clone(),
+            provenance,
+        })
+    }
+
+    fn sign_provenance(&self, bundle: ProvenanceBundle) -> Result<SignedProvenance, ActorError> {
+        let signature = SelfSigningPrefix::Ed25519Sha512(
+            self.signer.sign(SignedProvenance::signed_data(&bundle)?)?,
+        );
+        Ok(SignedProvenance::new(
+            bundle,
+            self.prefix.clone(),
+            signature,
+        ))
+    }
+
+    /// Returns events received by watcher, that conflict with events accepted
+    /// in identifier's KEL.
+    pub fn get_duplicity_report(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<DuplicityReport, ActorError> {
+        let mut conflicts: Vec<ConflictingEvent> = vec![];
+        let duplicitous = self
+            .event_storage
+            .escrow_db
+            .get_duplicious_events(id)
+            .into_iter()
+            .flatten();
+        for timestamped in duplicitous {
+            let conflicting = timestamped.signed_event_message;
+            let sn = conflicting.event_message.data.get_sn();
+            let digest = conflicting.event_message.digest()?;
+            let Some(accepted) = self.event_storage.get_event_at_sn(id, sn) else {
+                continue;
+            };
+            let accepted = accepted.signed_event_message;
+            // The same event can be received more than once, it's not
+            // duplicity.
+            if accepted.event_message.digest()? == digest
+                || conflicts
+                    .iter()
+                    .any(|c| c.conflicting.event_message.digest().ok() == Some(digest.clone()))
+            {
+                continue;
+            }
+            conflicts.push(ConflictingEvent {
+                sn,
+                reported_by: self.duplicity_sources.get(&digest),
+                accepted,
+                conflicting,
+            });
+        }
+        Ok(DuplicityReport {
+            id: id.clone(),
+            conflicts,
+        })
+    }
+
+    pub fn witness_responses(&self, id: &IdentifierPrefix) -> Vec<WitnessResponse> {
+        self.witness_responses
+            .read()
+            .ok()
+            .and_then(|responses| responses.get(id).cloned())
+            .unwrap_or_default()
     }
 
     pub(crate) async fn tel_update(
@@ -479,25 +1027,63 @@ impl WatcherData {
         let query = SignedTelQuery::new_nontrans(qry, self.prefix.clone(), signature);
         let resp = self
             .tel_transport
-            .send_query(query, loc)
+            .send_query(loc, query)
             .await
-            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+            .map_err(|e| {
+                self.metrics
+                    .transport_errors
+                    .with_label_values(&["tel_query"])
+                    .inc();
+                ActorError::GeneralError(e.to_string())
+            })?;
         self.tel_to_forward
             .save(about_ri, about_vc_id, resp)
             .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        self.tel_provenance
+            .write()
+            .map_err(|_e| ActorError::GeneralError("Internal watcher error".to_string()))?
+            .insert(
+                (about_ri.clone(), about_vc_id.clone()),
+                Provenance {
+                    source: wit_id,
+                    retrieved_at: self.now(),
+                },
+            );
         Ok(())
     }
 
-    async fn ksn_update(
+    /// Returns time of last successful TEL update of `vc_id` from
+    /// registry's witness.
+    pub fn tel_fetched_at(
+        &self,
+        ri: &IdentifierPrefix,
+        vc_id: &IdentifierPrefix,
+    ) -> Option<DateTime<FixedOffset>> {
+        self.tel_provenance.read().ok().and_then(|provenance| {
+            provenance
+                .get(&(ri.clone(), vc_id.clone()))
+                .map(|provenance| provenance.retrieved_at)
+        })
+    }
+
+    pub(super) fn is_tel_fresh(&self, ri: &IdentifierPrefix, vc_id: &IdentifierPrefix) -> bool {
+        self.tel_fetched_at(ri, vc_id)
+            .and_then(|fetched_at| self.now().signed_duration_since(fetched_at).to_std().ok())
+            .map_or(false, |age| age < self.tel_cache_ttl)
+    }
+
+    /// Ask witness about KSN for given prefix.
+    async fn ksn_request(
         &self,
         about_id: &IdentifierPrefix,
         wit_id: IdentifierPrefix,
-    ) -> Result<(), ActorError> {
+    ) -> Result<SignedReply, ActorError> {
         let query_args = LogsQueryArgs {
             i: about_id.clone(),
             s: None,
             src: Some(wit_id.clone()),
             limit: None,
+            after: None,
         };
 
         let qry = QueryEvent::new_query(
@@ -519,13 +1105,10 @@ impl WatcherData {
         let query = SignedKelQuery::new_nontrans(qry, self.prefix.clone(), signature);
         let resp = self.send_query_to(wit_id, Scheme::Http, query).await?;
 
-        let resp = match resp {
-            PossibleResponse::Ksn(ksn) => ksn,
-            e => return Err(ActorError::UnexpectedResponse(e.to_string())),
-        };
-
-        self.process_reply(resp)?;
-        Ok(())
+        match resp {
+            PossibleResponse::Ksn(ksn) => Ok(ksn),
+            e => Err(ActorError::UnexpectedResponse(e.to_string())),
+        }
     }
 
     /// Get witnesses for prefix
@@ -603,7 +1186,13 @@ impl WatcherData {
                 loc,
                 keri_core::query::query_event::SignedQueryMessage::KelQuery(query),
             )
-            .await?;
+            .await
+            .inspect_err(|_e| {
+                self.metrics
+                    .transport_errors
+                    .with_label_values(&["query"])
+                    .inc()
+            })?;
 
         Ok(response)
     }
@@ -1,34 +1,45 @@
-use crate::http_routing::configure_routes;
+use crate::http_routing::watcher_scope;
 use std::{net::ToSocketAddrs, sync::Arc};
 
 use actix_web::{dev::Server, web, App, HttpServer};
+use actor_server::{actix_listener::correlate, AdminToken};
 use keri_core::{actor::error::ActorError, oobi::LocationScheme, prefix::BasicPrefix};
 
 use crate::{watcher::Watcher, WatcherConfig};
 
-use self::http_handlers::ApiError;
-
 pub struct WatcherListener {
     pub watcher: Arc<Watcher>,
+    /// Token required by admin endpoints. Admin endpoints are disabled if
+    /// not set.
+    pub admin_token: Option<String>,
 }
 
 impl WatcherListener {
     pub fn new(config: WatcherConfig) -> Result<Self, ActorError> {
         Ok(Self {
             watcher: Arc::new(Watcher::new(config)?),
+            admin_token: None,
         })
     }
 
+    pub fn with_admin_token(self, token: String) -> Self {
+        Self {
+            admin_token: Some(token),
+            ..self
+        }
+    }
+
     pub fn listen_http(self, addr: impl ToSocketAddrs) -> Server {
         let data = self.watcher.clone();
         actix_web::rt::spawn(update_tel_checking(data.clone()));
         actix_web::rt::spawn(update_checking(data));
 
-        let state = web::Data::new(self.watcher);
+        let watcher = self.watcher;
+        let admin_token = web::Data::new(AdminToken(self.admin_token));
         HttpServer::new(move || {
             App::new()
-                .app_data(state.clone())
-                .configure(configure_routes)
+                .wrap(actix_web::middleware::from_fn(correlate))
+                .service(watcher_scope(watcher.clone()).app_data(admin_token.clone()))
         })
         .disable_signals()
         .bind(addr)
@@ -39,7 +50,7 @@ impl WatcherListener {
     pub async fn resolve_initial_oobis(
         &self,
         initial_oobis: &[LocationScheme],
-    ) -> Result<(), ApiError> {
+    ) -> Result<(), ActorError> {
         for lc in initial_oobis.iter() {
             self.watcher.resolve_loc_scheme(lc).await?;
         }
@@ -62,115 +73,31 @@ pub async fn update_tel_checking(data: Arc<Watcher>) {
 
 pub mod http_handlers {
 
-    use std::sync::Arc;
+    use std::path::PathBuf;
 
-    use actix_web::{
-        http::{header::ContentType, StatusCode},
-        web, HttpResponse, Responder, ResponseError,
-    };
+    use actor_server::{parse_id, parse_json};
     use itertools::Itertools;
     use keri_core::{
-        actor::{error::ActorError, prelude::Message},
+        actor::{
+            error::ActorError,
+            keri_actor::{ActorRequest, ActorResponse},
+            prelude::Message,
+        },
         event_message::signed_event_message::Op,
-        oobi::{error::OobiError, EndRole, LocationScheme, Role},
+        oobi::Role,
         prefix::IdentifierPrefix,
     };
-    use serde::Deserialize;
-
-    use crate::watcher::Watcher;
-
-    pub async fn introduce(data: web::Data<Arc<Watcher>>) -> Result<HttpResponse, ApiError> {
-        Ok(HttpResponse::Ok().json(data.oobi()))
-    }
-
-    pub async fn process_notice(
-        body: web::Bytes,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nGot events to process: \n{}",
-            String::from_utf8_lossy(&body)
-        );
-        data.parse_and_process_notices(&body)
-            .map_err(ActorError::from)?;
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(()))
-    }
-
-    pub async fn process_query(
-        body: web::Bytes,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nGot queries to process: \n{}",
-            String::from_utf8_lossy(&body)
-        );
-        let resp = data
-            .parse_and_process_queries(&body)
-            .await?
-            .iter()
-            .map(|msg| msg.to_string())
-            .join("");
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(resp))
-    }
-
-    pub async fn process_reply(
-        body: web::Bytes,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nGot replies to process: \n{}",
-            String::from_utf8_lossy(&body)
-        );
-
-        data.parse_and_process_replies(&body)?;
+    use serde::{Deserialize, Serialize};
 
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(()))
-    }
-
-    pub async fn resolve_oobi(
-        body: web::Bytes,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nGot oobi to resolve: \n{}",
-            String::from_utf8_lossy(&body)
-        );
-
-        #[derive(Debug, Deserialize)]
-        #[serde(untagged)]
-        enum RequestData {
-            EndRole(EndRole),
-            LocationScheme(LocationScheme),
-        }
-
-        match serde_json::from_slice(&body).map_err(|_| {
-            ApiError(OobiError::Parse(String::from_utf8_lossy(&body).to_string()).into())
-        })? {
-            RequestData::EndRole(end_role) => {
-                data.resolve_end_role(end_role).await?;
-            }
-            RequestData::LocationScheme(loc_scheme) => {
-                data.resolve_loc_scheme(&loc_scheme).await?;
-            }
-        }
-
-        Ok(HttpResponse::Ok().finish())
-    }
+    use crate::{metrics::METRICS_CONTENT_TYPE, watcher::Watcher};
 
-    pub async fn resolve_location(
-        eid: web::Path<IdentifierPrefix>,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        let loc_scheme = data.signed_location(&eid)?;
-        let oobis = loc_scheme
+    /// Body of `/oobi/{eid}` response.
+    pub(crate) fn location_oobis(
+        data: &Watcher,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        let loc_scheme = data.signed_location(eid)?;
+        loc_scheme
             .into_iter()
             .map(|sr| {
                 let sed = Message::Op(Op::Reply(sr));
@@ -178,22 +105,19 @@ pub mod http_handlers {
             })
             .flatten_ok()
             .collect::<Result<Vec<u8>, _>>()
-            .map_err(|e| ApiError(ActorError::GeneralError(e.to_string())))?;
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(String::from_utf8(oobis).unwrap()))
+            .map_err(|e| ActorError::GeneralError(e.to_string()))
     }
 
-    pub async fn resolve_role(
-        path: web::Path<(IdentifierPrefix, Role, IdentifierPrefix)>,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        let (cid, role, eid) = path.into_inner();
-
-        let end_role = data.watcher_data.get_end_role_for_id(&cid, role)?;
-        let loc_scheme = data.watcher_data.get_loc_scheme_for_id(&eid)?;
-        let oobis = end_role
+    /// Body of `/oobi/{cid}/{role}/{eid}` response.
+    pub(crate) fn role_oobis(
+        data: &Watcher,
+        cid: &IdentifierPrefix,
+        role: Role,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        let end_role = data.watcher_data.get_end_role_for_id(cid, role)?;
+        let loc_scheme = data.watcher_data.get_loc_scheme_for_id(eid)?;
+        end_role
             .into_iter()
             .chain(loc_scheme.into_iter())
             .map(|sr| {
@@ -202,109 +126,172 @@ pub mod http_handlers {
             })
             .flatten_ok()
             .collect::<Result<Vec<u8>, _>>()
-            .map_err(|e| ApiError(ActorError::GeneralError(e.to_string())))?;
+            .map_err(|e| ActorError::GeneralError(e.to_string()))
+    }
 
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(String::from_utf8(oobis).unwrap()))
+    /// Answers requests to endpoints specific to watcher, see
+    /// [`KeriActor::handle_request`](keri_core::actor::keri_actor::KeriActor::handle_request).
+    /// Query responses are sent in chunks, so long KELs aren't loaded into
+    /// memory at once.
+    pub(crate) async fn handle_request(
+        data: &Watcher,
+        req: &ActorRequest,
+    ) -> Option<Result<ActorResponse, ActorError>> {
+        route(data, req).await.transpose()
     }
 
-    pub async fn process_tel_query(
-        post_data: String,
-        data: web::Data<Arc<Watcher>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!("\nGot tel query to process: \n{}", post_data);
-        let resp = data
-            .parse_and_process_tel_queries(post_data.as_bytes())
-            .await?
-            .iter()
-            .map(|msg| msg.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        println!("\nWatcher responds with: {}", resp);
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(resp))
+    async fn route(
+        data: &Watcher,
+        req: &ActorRequest,
+    ) -> Result<Option<ActorResponse>, ActorError> {
+        let body = req.body.as_slice();
+        let resp = match (req.method.as_str(), req.segments().as_slice()) {
+            ("POST", ["query"]) => {
+                println!(
+                    "\nGot queries to process: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                ActorResponse::chunked(data.parse_and_process_queries_chunked(body).await?)
+            }
+            ("POST", ["query", "tel"]) => {
+                println!(
+                    "\nGot tel query to process: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                let resp = data
+                    .parse_and_process_tel_queries(body)
+                    .await?
+                    .iter()
+                    .map(|msg| msg.to_string())
+                    .join("");
+                println!("\nWatcher responds with: {}", resp);
+                ActorResponse::ok(resp)
+            }
+            ("POST", ["tel", "refresh"]) => {
+                let RefreshTelRequest { ri } = parse_json(body)?;
+                let refreshed = data.refresh_tel(&ri).await?;
+                ActorResponse::json(&RefreshTelResponse { ri, refreshed })?
+            }
+            ("GET", ["duplicity", id]) => {
+                ActorResponse::json(&data.get_duplicity_report(&parse_id(id)?)?)?
+            }
+            ("GET", ["provenance", id]) => {
+                ActorResponse::json(&data.kel_provenance(&parse_id(id)?)?)?
+            }
+            ("GET", ["provenance", ri, vc_id]) => {
+                ActorResponse::json(&data.tel_provenance(&parse_id(ri)?, &parse_id(vc_id)?)?)?
+            }
+            ("POST", ["verify"]) => {
+                println!(
+                    "\nGot presentation to verify: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                ActorResponse::json(&data.verify_presentation(body).await?)?
+            }
+            ("GET", ["info"]) => {
+                ActorResponse::json(&serde_json::json!({ "version": env!("CARGO_PKG_VERSION") }))?
+            }
+            // Returns watcher metrics in Prometheus text format.
+            ("GET", ["metrics"]) => ActorResponse::ok(data.gather_metrics()?)
+                .with_header("Content-Type", METRICS_CONTENT_TYPE),
+            ("POST", ["admin", "backup"]) => {
+                let BackupRequest { path } = parse_json(body)?;
+                data.backup(&path)?;
+                ActorResponse::json(&serde_json::json!({ "path": path }))?
+            }
+            // Enables or disables debug log of processed streams.
+            ("POST", ["admin", "stream-log"]) => {
+                let StreamLogRequest { enabled } = parse_json(body)?;
+                data.set_stream_log_enabled(enabled);
+                ActorResponse::json(&serde_json::json!({
+                    "enabled": enabled,
+                    "path": data.watcher_data.stream_log.path(),
+                }))?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(resp))
     }
 
-    #[derive(Debug, derive_more::Display, derive_more::From, derive_more::Error)]
-    pub struct ApiError(pub ActorError);
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct RefreshTelRequest {
+        /// Registry identifier which TELs should be updated.
+        pub ri: IdentifierPrefix,
+    }
 
-    impl ResponseError for ApiError {
-        fn status_code(&self) -> StatusCode {
-            self.0.http_status_code()
-        }
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct RefreshTelResponse {
+        pub ri: IdentifierPrefix,
+        /// Number of updated TELs.
+        pub refreshed: usize,
+    }
 
-        fn error_response(&self) -> HttpResponse {
-            HttpResponse::build(self.status_code()).json(&self.0)
-        }
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BackupRequest {
+        /// Directory on watcher host where backup will be written.
+        pub path: PathBuf,
     }
 
-    pub async fn info() -> impl Responder {
-        let version = option_env!("CARGO_PKG_VERSION");
-        if let Some(version) = version {
-            HttpResponse::Ok().json(serde_json::json!({ "version": version }))
-        } else {
-            HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "error": "Failed to retrieve version information" }))
-        }
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct StreamLogRequest {
+        pub enabled: bool,
     }
 }
 
 mod test {
-    use actix_web::{body::MessageBody, web::Bytes};
+    use actor_server::{route, AdminToken};
     use keri_core::{
         actor::{
-            error::ActorError, parse_event_stream, parse_op_stream,
-            simple_controller::PossibleResponse,
+            error::ActorError,
+            keri_actor::ActorRequest,
+            parse_event_stream, parse_op_stream,
+            simple_controller::{parse_response, PossibleResponse},
         },
         event_message::signed_event_message::{Message, Op},
         oobi::{Oobi, Role},
         prefix::IdentifierPrefix,
-        query::query_event::{QueryRoute, SignedQueryMessage},
+        query::{
+            provenance::SignedProvenance,
+            query_event::{QueryRoute, SignedQueryMessage},
+        },
     };
 
+    impl super::WatcherListener {
+        /// Answers request the same way as listeners do and returns body of
+        /// response.
+        async fn request(
+            &self,
+            method: &str,
+            path: String,
+            body: Vec<u8>,
+        ) -> Result<Vec<u8>, ActorError> {
+            let req = ActorRequest::new(method, path, body);
+            route(&*self.watcher, &AdminToken::default(), &req)
+                .await?
+                .into_bytes()
+                .map_err(ActorError::KeriError)
+        }
+    }
+
     #[async_trait::async_trait]
     impl keri_core::transport::test::TestActor for super::WatcherListener {
         async fn send_message(&self, msg: Message) -> Result<(), ActorError> {
-            let payload = String::from_utf8(msg.to_cesr().unwrap()).unwrap();
-            let data = actix_web::web::Data::new(self.watcher.clone());
-            match msg {
-                Message::Notice(_) => {
-                    super::http_handlers::process_notice(Bytes::from(payload), data)
-                        .await
-                        .map_err(|err| err.0)?;
-                }
-                Message::Op(op) => match op {
-                    Op::Query(_) => {
-                        super::http_handlers::process_query(Bytes::from(payload), data)
-                            .await
-                            .map_err(|err| err.0)?;
-                    }
-                    Op::Reply(_) => {
-                        super::http_handlers::process_reply(Bytes::from(payload), data)
-                            .await
-                            .map_err(|err| err.0)?;
-                    }
-                    Op::Exchange(_) => {
-                        panic!("watcher doesn't support exchange")
-                    }
-                },
-            }
-
+            let path = match &msg {
+                Message::Notice(_) => "process",
+                Message::Op(Op::Query(_)) => "query",
+                Message::Op(Op::Reply(_)) => "register",
+                Message::Op(Op::Exchange(_)) => "forward",
+            };
+            self.request("POST", path.to_string(), msg.to_cesr().unwrap())
+                .await?;
             Ok(())
         }
         async fn send_query(
             &self,
             query: SignedQueryMessage,
         ) -> Result<PossibleResponse, ActorError> {
-            let payload =
-                String::from_utf8(Message::from(query.clone()).to_cesr().unwrap()).unwrap();
-            let data = actix_web::web::Data::new(self.watcher.clone());
-            let resp = super::http_handlers::process_query(Bytes::from(payload), data)
-                .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
+            let payload = Message::from(query.clone()).to_cesr().unwrap();
+            let resp = self.request("POST", "query".to_string(), payload).await?;
             if let SignedQueryMessage::KelQuery(qry) = query {
                 match qry.query.get_route() {
                     QueryRoute::Ksn { .. } => {
@@ -315,22 +302,19 @@ mod test {
                         };
                         Ok(PossibleResponse::Ksn(reply))
                     }
-                    QueryRoute::Logs { .. } => {
+                    QueryRoute::Logs { .. } | QueryRoute::LogsBetween { .. } => {
                         let log = parse_event_stream(&resp).unwrap();
                         Ok(PossibleResponse::Kel(log))
                     }
                 }
             } else {
-                panic!("unexpected query type")
+                let resp = String::from_utf8(resp).unwrap();
+                Ok(parse_response(&resp).unwrap())
             }
         }
         async fn request_loc_scheme(&self, eid: IdentifierPrefix) -> Result<Vec<Op>, ActorError> {
-            let data = actix_web::web::Data::new(self.watcher.clone());
-            let resp = super::http_handlers::resolve_location(eid.into(), data)
-                .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
-            let resp = parse_op_stream(resp.as_ref()).unwrap();
+            let resp = self.request("GET", format!("oobi/{}", eid), vec![]).await?;
+            let resp = parse_op_stream(&resp).unwrap();
             Ok(resp)
         }
         async fn request_end_role(
@@ -339,24 +323,61 @@ mod test {
             role: Role,
             eid: IdentifierPrefix,
         ) -> Result<Vec<u8>, ActorError> {
-            let data = actix_web::web::Data::new(self.watcher.clone());
-            let resp = super::http_handlers::resolve_role((cid, role, eid).into(), data)
+            self.request("GET", format!("oobi/{}/{}/{}", cid, role, eid), vec![])
                 .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
-            Ok(resp.to_vec())
+        }
+        async fn request_oobis(&self, _cid: IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+            Err(ActorError::GeneralError(
+                "watcher doesn't serve identifier's OOBI set".to_string(),
+            ))
+        }
+        async fn request_provenance(
+            &self,
+            id: IdentifierPrefix,
+            vc_id: Option<IdentifierPrefix>,
+        ) -> Result<SignedProvenance, ActorError> {
+            match vc_id {
+                Some(vc_id) => self.watcher.tel_provenance(&id, &vc_id),
+                None => self.watcher.kel_provenance(&id),
+            }
         }
         async fn resolve_oobi(&self, msg: Oobi) -> Result<(), ActorError> {
-            let data = actix_web::web::Data::new(self.watcher.clone());
-            let resp = super::http_handlers::resolve_oobi(
-                Bytes::from(serde_json::to_string(&msg).unwrap()),
-                data,
-            )
-            .await
-            .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
-            parse_event_stream(resp.as_ref()).unwrap();
+            let resp = self
+                .request(
+                    "POST",
+                    "resolve".to_string(),
+                    serde_json::to_vec(&msg).unwrap(),
+                )
+                .await?;
+            parse_event_stream(&resp).unwrap();
             Ok(())
         }
     }
+
+    #[async_trait::async_trait]
+    impl teliox::transport::test::TelTestActor for super::WatcherListener {
+        async fn send_query(
+            &self,
+            qry: teliox::query::SignedTelQuery,
+        ) -> Result<String, ActorError> {
+            let input_stream = qry
+                .to_cesr()
+                .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+            Ok(self
+                .watcher
+                .parse_and_process_tel_queries(&input_stream)
+                .await?
+                .into_iter()
+                .map(|msg| msg.to_string())
+                .collect())
+        }
+        async fn send_tel_event(
+            &self,
+            _event: teliox::event::verifiable_event::VerifiableEvent,
+        ) -> Result<(), ActorError> {
+            Err(ActorError::GeneralError(
+                "watcher doesn't accept TEL events".to_string(),
+            ))
+        }
+    }
 }
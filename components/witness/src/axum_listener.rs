@@ -0,0 +1,35 @@
+//! Witness endpoints served with [axum](https://docs.rs/axum), for
+//! applications built on tower/hyper stack. Requests are answered by the
+//! same router as in actix based [`WitnessListener::listen_http`], so both
+//! listeners can be used interchangeably.
+use std::sync::Arc;
+
+use actor_server::{axum_listener::actor_router, AdminToken};
+use axum::Router;
+
+use crate::{witness::Witness, WitnessListener};
+
+/// Returns router with all witness endpoints. It can be merged or nested
+/// into existing axum application, and wrapped with tower middlewares.
+/// Admin endpoints are disabled.
+pub fn witness_router(data: Arc<Witness>) -> Router {
+    witness_router_with_admin_token(data, AdminToken(None))
+}
+
+/// Returns router with all witness endpoints, like [`witness_router`], but
+/// with admin endpoints enabled for requests carrying `admin_token`.
+pub fn witness_router_with_admin_token(data: Arc<Witness>, admin_token: AdminToken) -> Router {
+    actor_router(data, admin_token)
+}
+
+impl WitnessListener {
+    /// Serves witness endpoints with axum on given address.
+    pub async fn listen_axum(&self, addr: impl tokio::net::ToSocketAddrs) -> std::io::Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let router = witness_router_with_admin_token(
+            self.witness_data.clone(),
+            AdminToken(self.admin_token.clone()),
+        );
+        axum::serve(listener, router).await
+    }
+}
@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+use actix_web::Scope;
+use actor_server::actix_listener::actor_scope;
+
+use crate::witness::Witness;
+
+/// Returns service with all witness endpoints, that can be mounted in
+/// existing actix application, for example:
+/// `App::new().service(web::scope("/witness").service(witness_scope(data)))`.
+/// Admin endpoints are disabled, unless token is provided with
+/// `.app_data(web::Data::new(AdminToken(Some(token))))` on returned scope.
+pub fn witness_scope(data: Arc<Witness>) -> Scope {
+    actor_scope(data)
+}
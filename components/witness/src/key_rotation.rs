@@ -0,0 +1,138 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use keri_core::{
+    prefix::{BasicPrefix, SelfSigningPrefix},
+    signer::Signer,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::witness::WitnessError;
+
+/// Designation of witness prefix that replaces `previous` one after witness
+/// rotated its key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub previous: BasicPrefix,
+    pub successor: BasicPrefix,
+}
+
+impl KeyRotation {
+    /// Bytes signed by both keys: JSON serialization of the rotation.
+    pub fn encode(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+/// Key rotation signed by previous and successor key, so it proves control
+/// over both of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedKeyRotation {
+    pub rotation: KeyRotation,
+    pub previous_signature: SelfSigningPrefix,
+    pub successor_signature: SelfSigningPrefix,
+}
+
+impl SignedKeyRotation {
+    pub fn new(previous: &Signer, successor: &Signer) -> Result<Self, WitnessError> {
+        let rotation = KeyRotation {
+            previous: BasicPrefix::Ed25519NT(previous.public_key()),
+            successor: BasicPrefix::Ed25519NT(successor.public_key()),
+        };
+        let data = rotation
+            .encode()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let sign = |signer: &Signer| {
+            signer
+                .sign(&data)
+                .map(SelfSigningPrefix::Ed25519Sha512)
+                .map_err(|_e| WitnessError::SigningError)
+        };
+        Ok(Self {
+            previous_signature: sign(previous)?,
+            successor_signature: sign(successor)?,
+            rotation,
+        })
+    }
+
+    /// Checks signatures of both keys.
+    pub fn verify(&self) -> bool {
+        let data = match self.rotation.encode() {
+            Ok(data) => data,
+            Err(_) => return false,
+        };
+        [
+            (&self.rotation.previous, &self.previous_signature),
+            (&self.rotation.successor, &self.successor_signature),
+        ]
+        .into_iter()
+        .all(|(prefix, signature)| prefix.verify(&data, signature).unwrap_or(false))
+    }
+}
+
+/// Append-only log of witness key rotations, stored as JSON lines. Records
+/// stay after restart, so clients knowing only previous prefix can find its
+/// successor.
+pub struct KeyRotationLog {
+    path: PathBuf,
+    records: RwLock<Vec<SignedKeyRotation>>,
+}
+
+impl KeyRotationLog {
+    pub fn open(path: &Path) -> Result<Self, WitnessError> {
+        let records = match File::open(path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .map(|line| {
+                    serde_json::from_str(&line?)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            records: RwLock::new(records),
+        })
+    }
+
+    pub fn append(&self, record: SignedKeyRotation) -> Result<(), WitnessError> {
+        let mut records = self
+            .records
+            .write()
+            .map_err(|_e| keri_core::error::Error::MutexPoisoned)?;
+        let mut line = serde_json::to_vec(&record)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_all()?;
+        records.push(record);
+        Ok(())
+    }
+
+    /// Returns rotation of `previous` prefix, if it was rotated.
+    pub fn successor(&self, previous: &BasicPrefix) -> Option<SignedKeyRotation> {
+        self.records.read().ok().and_then(|records| {
+            records
+                .iter()
+                .find(|record| &record.rotation.previous == previous)
+                .cloned()
+        })
+    }
+
+    pub fn records(&self) -> Vec<SignedKeyRotation> {
+        self.records
+            .read()
+            .map(|records| records.clone())
+            .unwrap_or_default()
+    }
+}
@@ -1,11 +1,36 @@
+#[cfg(feature = "axum")]
+pub mod axum_listener;
+mod http_routing;
+mod key_rotation;
+mod mailbox_quota;
+mod metrics;
+mod outbox;
+mod peer_receipts;
+mod pruning;
+mod takedown;
+#[cfg(feature = "tcp")]
+pub mod tcp_listener;
 #[cfg(test)]
 mod tests;
 mod witness;
 mod witness_listener;
 mod witness_processor;
 
+pub use actor_server::{
+    actix_listener::{configure_routes, correlate},
+    AdminToken,
+};
+
 pub use crate::{
-    witness::Witness,
-    witness_listener::WitnessListener,
+    http_routing::witness_scope,
+    key_rotation::{KeyRotation, KeyRotationLog, SignedKeyRotation},
+    mailbox_quota::MailboxQuota,
+    metrics::WitnessMetrics,
+    outbox::{DeadLetter, OutboundDelivery, Outbox, OutboxConfig, OutboxReport},
+    peer_receipts::{PeerReceiptsConfig, PendingReceipts},
+    pruning::{ArchiveRecord, PruningArchive, PruningConfig, PruningPolicy, PruningReport},
+    takedown::{SignedTakedown, Takedown, TakedownLog},
+    witness::{NoticeOutcome, Witness, DEFAULT_PEER_SCHEME_PRIORITY},
+    witness_listener::{outbox_processing, peer_receipts_processing, WitnessListener},
     witness_processor::{WitnessEscrowConfig, WitnessProcessor},
 };
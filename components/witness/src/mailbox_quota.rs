@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use keri_core::mailbox::MailboxUsage;
+
+/// Limits of messages forwarded to single recipient's mailbox, so mailbox
+/// of recipient that never queries can't be flooded. Limits that aren't set
+/// aren't checked.
+#[derive(Debug, Clone, Default)]
+pub struct MailboxQuota {
+    /// Maximal number of stored multisig and delegate messages.
+    pub max_messages: Option<usize>,
+    /// Maximal size in bytes of stored multisig and delegate events.
+    pub max_bytes: Option<usize>,
+    /// Forwarded messages stored longer are removed from mailbox.
+    pub expiry: Option<Duration>,
+}
+
+impl MailboxQuota {
+    /// Checks if mailbox of `usage` can store another message of `size`
+    /// bytes.
+    pub fn allows(&self, usage: MailboxUsage, size: usize) -> bool {
+        let usage = usage.with(size);
+        !(self.max_messages.is_some_and(|max| usage.messages > max)
+            || self.max_bytes.is_some_and(|max| usage.bytes > max))
+    }
+}
+
+#[test]
+fn test_mailbox_quota_allows() {
+    let usage = MailboxUsage {
+        messages: 2,
+        bytes: 300,
+    };
+    assert!(MailboxQuota::default().allows(usage, 1000));
+
+    let quota = MailboxQuota {
+        max_messages: Some(3),
+        max_bytes: Some(400),
+        expiry: None,
+    };
+    assert!(quota.allows(usage, 100));
+    assert!(!quota.allows(usage, 101));
+    assert!(!quota.allows(usage.with(0), 0));
+}
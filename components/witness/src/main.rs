@@ -1,19 +1,26 @@
-use std::{net::Ipv4Addr, path::PathBuf, time::Duration};
+use std::{net::Ipv4Addr, path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use figment::{
-    providers::{Env, Format, Serialized, Yaml},
-    Figment,
+use actor_server::{
+    config,
+    service::{self, LogConfig, LogTarget, NotifyState, PidFile},
 };
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use futures::future::{self, BoxFuture};
 use keri_core::{
+    actor::stream_log::StreamLog,
     oobi::{LocationScheme, Scheme},
     prefix::{CesrPrimitive, IdentifierPrefix},
+    signer::KeystoreConfig,
+    transport::default::{DefaultTransport, TransportConfig},
 };
 use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, DurationSeconds};
+use serde_with::{serde_as, DurationMilliSeconds, DurationSeconds};
 use url::Url;
-use witness::{WitnessEscrowConfig, WitnessListener};
+use witness::{
+    outbox_processing, peer_receipts_processing, MailboxQuota, PeerReceiptsConfig, PruningConfig,
+    PruningPolicy, Witness, WitnessEscrowConfig, WitnessListener,
+};
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -23,14 +30,121 @@ pub struct Config {
     public_url: Url,
 
     /// HTTP Listen port
-    http_port: u16,
+    http_port: Option<u16>,
+
+    /// Framed TCP listen port. At least one of `http_port` and `tcp_port`
+    /// needs to be set.
+    tcp_port: Option<u16>,
 
     /// Witness keypair seed
     seed: Option<String>,
 
+    /// Path of file containing seed. Alternative to `seed`.
+    seed_file: Option<PathBuf>,
+
+    /// Name of environment variable containing seed. Alternative to `seed`.
+    seed_env: Option<String>,
+
+    /// Keystore containing witness key, alternative to `seed`. Key is
+    /// generated if keystore doesn't contain it yet.
+    keystore: Option<KeystoreConfig>,
+
+    /// Seed of new witness key. If set, witness rotates from key given by
+    /// `seed` to this one on start. After rotation it should be used as
+    /// `seed`.
+    rotate_to_seed: Option<String>,
+
     /// Time after which an escrowed event is considered stale.
     #[serde(default, deserialize_with = "deserialize_escrow_config")]
     escrow_timeout: WitnessEscrowConfig,
+
+    /// Token required by admin endpoints. Admin endpoints are disabled if
+    /// not set.
+    admin_token: Option<String>,
+
+    /// Maximal size of mailbox response in bytes.
+    mailbox_max_size: Option<usize>,
+
+    /// Maximal number of messages forwarded to single recipient's mailbox.
+    mailbox_max_messages: Option<usize>,
+
+    /// Maximal size in bytes of messages forwarded to single recipient's
+    /// mailbox.
+    mailbox_max_bytes: Option<usize>,
+
+    /// Time, in seconds, after which forwarded messages are removed from
+    /// mailbox.
+    mailbox_expiry: Option<u64>,
+
+    /// Maximal difference, in seconds, between timestamp of query and
+    /// witness clock. If set, queries out of it and queries processed before
+    /// are rejected.
+    query_max_skew: Option<u64>,
+
+    /// How far in the future, in seconds, timestamps of accepted replies and
+    /// key state notices can be.
+    max_clock_drift: Option<u64>,
+
+    /// Identifier allowed to sign takedowns. Takedowns are rejected if not
+    /// set.
+    takedown_operator: Option<IdentifierPrefix>,
+
+    /// File where process id is written. It's removed on shutdown.
+    pid_file: Option<PathBuf>,
+
+    /// File of debug log of processed streams. `streams.log` in `db_path`
+    /// by default.
+    stream_log_file: Option<PathBuf>,
+
+    /// Whether processed streams are logged from start. Log can also be
+    /// toggled at runtime with admin endpoint.
+    #[serde(default)]
+    stream_log_enabled: bool,
+
+    /// Whether events, which bytes differ from their canonical
+    /// serialization, are rejected.
+    #[serde(default)]
+    strict_canonicalization: bool,
+
+    /// Identifiers that opted into pruning of old receipts and events.
+    #[serde(default)]
+    pruning: Vec<PruningEntry>,
+
+    /// Timeouts and retries of requests sent to other witnesses.
+    #[serde(default, deserialize_with = "deserialize_transport_config")]
+    transport: TransportConfig,
+
+    /// Time, in seconds, between requests for receipts missing from other
+    /// witnesses.
+    peer_receipts_interval: Option<u64>,
+
+    /// Number of times missing receipts of event are requested before
+    /// witness gives up.
+    peer_receipts_max_attempts: Option<u32>,
+
+    /// Number of redb databases events are split into, by identifier.
+    /// Events are stored in single database if not set. Existing database
+    /// has to be moved with `--reshard` after it's changed.
+    event_shards: Option<usize>,
+
+    #[serde(flatten)]
+    log: LogConfig,
+}
+
+/// Pruning settings of identifier, that opted into pruning.
+#[derive(Deserialize)]
+struct PruningEntry {
+    id: IdentifierPrefix,
+
+    /// Minimal age, in seconds, of establishment event up to which receipts
+    /// are pruned. Receipts are pruned up to the latest establishment event
+    /// if not set.
+    min_age: Option<u64>,
+
+    /// Whether KEL events preceding that establishment event are pruned
+    /// too.
+    #[serde(default)]
+    events: bool,
 }
 
 #[serde_as]
@@ -47,6 +161,12 @@ struct PartialEscrowConfig {
 
     #[serde_as(as = "Option<DurationSeconds>")]
     delegation_timeout: Option<Duration>,
+
+    #[serde_as(as = "Option<DurationSeconds>")]
+    trans_receipt_timeout: Option<Duration>,
+
+    #[serde_as(as = "Option<DurationSeconds>")]
+    tel_missing_anchor_timeout: Option<Duration>,
 }
 
 fn deserialize_escrow_config<'de, D>(deserializer: D) -> Result<WitnessEscrowConfig, D::Error>
@@ -67,6 +187,52 @@ where
             .delegation_timeout
             .or(config.default_timeout)
             .unwrap_or(WitnessEscrowConfig::default().delegation_timeout),
+        trans_receipt_timeout: config
+            .trans_receipt_timeout
+            .or(config.default_timeout)
+            .unwrap_or(WitnessEscrowConfig::default().trans_receipt_timeout),
+        tel_missing_anchor_timeout: config
+            .tel_missing_anchor_timeout
+            .or(config.default_timeout)
+            .unwrap_or(WitnessEscrowConfig::default().tel_missing_anchor_timeout),
+        ..Default::default()
+    })
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct PartialTransportConfig {
+    #[serde_as(as = "Option<DurationSeconds>")]
+    connect_timeout: Option<Duration>,
+
+    #[serde_as(as = "Option<DurationSeconds>")]
+    request_timeout: Option<Duration>,
+
+    retries: Option<u32>,
+
+    #[serde_as(as = "Option<DurationMilliSeconds>")]
+    backoff: Option<Duration>,
+
+    #[serde_as(as = "Option<DurationSeconds>")]
+    max_backoff: Option<Duration>,
+
+    #[serde_as(as = "Option<DurationSeconds>")]
+    max_retry_after: Option<Duration>,
+}
+
+fn deserialize_transport_config<'de, D>(deserializer: D) -> Result<TransportConfig, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let config = PartialTransportConfig::deserialize(deserializer)?;
+    let default = TransportConfig::default();
+    Ok(TransportConfig {
+        connect_timeout: config.connect_timeout.unwrap_or(default.connect_timeout),
+        request_timeout: config.request_timeout.unwrap_or(default.request_timeout),
+        retries: config.retries.unwrap_or(default.retries),
+        backoff: config.backoff.unwrap_or(default.backoff),
+        max_backoff: config.max_backoff.unwrap_or(default.max_backoff),
+        max_retry_after: config.max_retry_after.unwrap_or(default.max_retry_after),
     })
 }
 
@@ -88,9 +254,31 @@ struct Args {
     #[serde(skip_serializing_if = "Option::is_none")]
     http_port: Option<u16>,
 
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tcp_port: Option<u16>,
+
     #[arg(short = 's', long)]
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<String>,
+
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid_file: Option<PathBuf>,
+
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_target: Option<LogTarget>,
+
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_file: Option<PathBuf>,
+
+    /// Move events database into number of shards set by `event_shards`
+    /// and exit. Witness can't be running.
+    #[arg(long)]
+    #[serde(skip)]
+    reshard: bool,
 }
 
 const ENV_PREFIX: &str = "WITNESS_";
@@ -98,42 +286,180 @@ const ENV_PREFIX: &str = "WITNESS_";
 #[actix_web::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    let reshard = args.reshard;
 
     println!("Using config file {:?}", args.config_file);
 
-    let cfg = Figment::new()
-        .merge(Yaml::file(args.config_file.clone()))
-        .merge(Env::prefixed(ENV_PREFIX))
-        .merge(Serialized::defaults(args))
-        .extract::<Config>()
-        .context("Failed to load config")?;
-
-    let witness_listener = WitnessListener::setup(
-        cfg.public_url.clone(),
-        cfg.db_path.as_path(),
-        cfg.seed,
-        cfg.escrow_timeout,
+    let config_file = args.config_file.clone();
+    let cfg: Config = config::load_config(&config_file, ENV_PREFIX, args)?;
+
+    if cfg.http_port.is_none() && cfg.tcp_port.is_none() {
+        bail!("At least one of `http_port` and `tcp_port` needs to be set");
+    }
+
+    let _logger = service::init_logging(&cfg.log)?;
+
+    let event_shards = cfg.event_shards.unwrap_or(1);
+    if reshard {
+        Witness::reshard(&cfg.db_path, event_shards).context("Failed to reshard events")?;
+        println!("Events database moved into {} shards", event_shards);
+        return Ok(());
+    }
+    match Witness::event_shards(&cfg.db_path)? {
+        None if event_shards > 1 => Witness::reshard(&cfg.db_path, event_shards)?,
+        Some(stored) if stored != event_shards => bail!(
+            "Events database has {} shards, but `event_shards` is {}. Run witness with `--reshard` to move events",
+            stored,
+            event_shards
+        ),
+        _ => (),
+    }
+
+    let _pid_file = cfg.pid_file.clone().map(PidFile::create).transpose()?;
+
+    let seed = config::resolve_seed(
+        cfg.seed.as_deref(),
+        cfg.seed_file.as_deref(),
+        cfg.seed_env.as_deref(),
     )?;
+    let witness_listener = match (&cfg.keystore, seed) {
+        (Some(keystore), None) => WitnessListener::setup_with_signer(
+            cfg.public_url.clone(),
+            cfg.db_path.as_path(),
+            Arc::new(
+                keystore
+                    .signer("witness")
+                    .context("Failed to open keystore")?,
+            ),
+            cfg.escrow_timeout,
+        )?,
+        (None, seed) => WitnessListener::setup(
+            cfg.public_url.clone(),
+            cfg.db_path.as_path(),
+            seed,
+            cfg.escrow_timeout,
+        )?,
+        (Some(_), Some(_)) => bail!("Only one of seed and `keystore` can be set"),
+    };
+    let witness_listener = match &cfg.rotate_to_seed {
+        Some(seed) => witness_listener.with_rotated_key(seed)?,
+        None => witness_listener,
+    };
+    let witness_listener = match cfg.admin_token {
+        Some(token) => witness_listener.with_admin_token(token),
+        None => witness_listener,
+    };
+    let witness_listener = match cfg.mailbox_max_size {
+        Some(max_size) => witness_listener.with_mailbox_max_size(max_size),
+        None => witness_listener,
+    };
+    let witness_listener = witness_listener.with_mailbox_quota(MailboxQuota {
+        max_messages: cfg.mailbox_max_messages,
+        max_bytes: cfg.mailbox_max_bytes,
+        expiry: cfg.mailbox_expiry.map(Duration::from_secs),
+    });
+    let witness_listener = match cfg.query_max_skew {
+        Some(max_skew) => witness_listener.with_query_max_skew(Duration::from_secs(max_skew)),
+        None => witness_listener,
+    };
+    let witness_listener = match cfg.max_clock_drift {
+        Some(max_drift) => witness_listener.with_max_clock_drift(Duration::from_secs(max_drift)),
+        None => witness_listener,
+    };
+    let witness_listener = match cfg.takedown_operator {
+        Some(operator) => witness_listener.with_takedown_operator(operator),
+        None => witness_listener,
+    };
+    let witness_listener = if cfg.strict_canonicalization {
+        witness_listener.with_strict_canonicalization()
+    } else {
+        witness_listener
+    };
+    let witness_listener = witness_listener.with_pruning(PruningConfig {
+        policies: cfg
+            .pruning
+            .iter()
+            .map(|entry| PruningPolicy {
+                id: entry.id.clone(),
+                min_age: entry.min_age.map(Duration::from_secs),
+                events: entry.events,
+            })
+            .collect(),
+    });
+
+    let witness_listener = witness_listener
+        .with_transport(Box::new(DefaultTransport::new_with_config(
+            cfg.transport.clone(),
+        )))
+        .with_peer_receipts_config(PeerReceiptsConfig {
+            max_attempts: cfg
+                .peer_receipts_max_attempts
+                .unwrap_or(PeerReceiptsConfig::default().max_attempts),
+            poll_interval: cfg
+                .peer_receipts_interval
+                .map(Duration::from_secs)
+                .unwrap_or(PeerReceiptsConfig::default().poll_interval),
+        });
+
+    let stream_log_file = cfg
+        .stream_log_file
+        .clone()
+        .unwrap_or_else(|| cfg.db_path.join("streams.log"));
+    let stream_log = StreamLog::new(&stream_log_file)
+        .with_max_size(cfg.log.log_max_size)
+        .with_max_files(cfg.log.log_max_files);
+    stream_log.set_enabled(cfg.stream_log_enabled);
+    let witness_listener = witness_listener.with_stream_log(stream_log);
 
     let witness_id = IdentifierPrefix::Basic(witness_listener.get_prefix());
     let witness_loc_scheme = LocationScheme {
         eid: witness_id.clone(),
-        scheme: Scheme::Http,
+        scheme: cfg.public_url.scheme().parse().unwrap_or(Scheme::Http),
         url: cfg.public_url.clone(),
     };
 
     println!(
-        "\nWitness {} is listening on port {}",
+        "\nWitness {} is listening on HTTP port {:?} and TCP port {:?}",
         witness_listener.get_prefix().to_str(),
         cfg.http_port,
+        cfg.tcp_port,
     );
     println!(
         "Witness's oobi: {}",
         serde_json::to_string(&witness_loc_scheme).unwrap()
     );
 
-    let http_handle = witness_listener.listen_http((Ipv4Addr::UNSPECIFIED, cfg.http_port));
-    http_handle.await?;
+    // HTTP listener spawns outbox and peer receipts processing itself.
+    if cfg.http_port.is_none() {
+        actix_web::rt::spawn(outbox_processing(witness_listener.witness_data.clone()));
+        actix_web::rt::spawn(peer_receipts_processing(
+            witness_listener.witness_data.clone(),
+        ));
+    }
+    let http_handle = cfg
+        .http_port
+        .map(|port| witness_listener.listen_http((Ipv4Addr::UNSPECIFIED, port)));
+    let tcp_handle: BoxFuture<'static, std::io::Result<()>> = match cfg.tcp_port {
+        #[cfg(feature = "tcp")]
+        Some(port) => Box::pin(witness_listener.listen_tcp((Ipv4Addr::UNSPECIFIED, port))),
+        #[cfg(not(feature = "tcp"))]
+        Some(_) => bail!("Witness was built without `tcp` feature"),
+        None => Box::pin(future::ok(())),
+    };
+    // HTTP socket is already bound and TCP one is bound as soon as
+    // listeners are polled, so witness can accept requests.
+    service::notify(NotifyState::Ready);
+    future::try_join(
+        async {
+            match http_handle {
+                Some(handle) => handle.await,
+                None => Ok(()),
+            }
+        },
+        tcp_handle,
+    )
+    .await?;
+    service::notify(NotifyState::Stopping);
 
     Ok(())
 }
@@ -0,0 +1,71 @@
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
+
+/// Content type of metrics in Prometheus text format.
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Prometheus metrics of witness, served at `/metrics`.
+pub struct WitnessMetrics {
+    registry: Registry,
+    pub notices_processed: IntCounter,
+    pub receipts_issued: IntCounter,
+    /// Queries served, labeled with response type.
+    pub queries_served: IntCounterVec,
+    pub transport_errors: IntCounter,
+    /// Number of escrowed messages, labeled with escrow name. Updated
+    /// when metrics are gathered.
+    pub escrow_size: IntGaugeVec,
+    /// Number of outbound deliveries, labeled with state: `pending`,
+    /// `stuck` (failed at least once) or `dead_letter`. Updated when
+    /// metrics are gathered.
+    pub outbox_size: IntGaugeVec,
+}
+
+impl WitnessMetrics {
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("witness".to_string()), None)?;
+        let notices_processed = IntCounter::new(
+            "notices_processed_total",
+            "Number of successfully processed notices",
+        )?;
+        let receipts_issued =
+            IntCounter::new("receipts_issued_total", "Number of issued event receipts")?;
+        let queries_served = IntCounterVec::new(
+            Opts::new("queries_served_total", "Number of served queries"),
+            &["response"],
+        )?;
+        let transport_errors = IntCounter::new(
+            "transport_errors_total",
+            "Number of failed requests to other actors",
+        )?;
+        let escrow_size = IntGaugeVec::new(
+            Opts::new("escrow_size", "Number of escrowed messages"),
+            &["escrow"],
+        )?;
+        registry.register(Box::new(notices_processed.clone()))?;
+        registry.register(Box::new(receipts_issued.clone()))?;
+        registry.register(Box::new(queries_served.clone()))?;
+        registry.register(Box::new(transport_errors.clone()))?;
+        let outbox_size = IntGaugeVec::new(
+            Opts::new("outbox_size", "Number of outbound deliveries"),
+            &["state"],
+        )?;
+        registry.register(Box::new(escrow_size.clone()))?;
+        registry.register(Box::new(outbox_size.clone()))?;
+        Ok(Self {
+            registry,
+            notices_processed,
+            receipts_issued,
+            queries_served,
+            transport_errors,
+            escrow_size,
+            outbox_size,
+        })
+    }
+
+    /// Returns all metrics in Prometheus text format.
+    pub fn encode(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).to_string())
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use keri_core::prefix::IdentifierPrefix;
+use serde::{Deserialize, Serialize};
+
+use crate::witness::WitnessError;
+
+/// Retry policy of outbound deliveries, see
+/// [`Witness::process_outbox`](crate::Witness::process_outbox).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxConfig {
+    /// Delivery is moved to dead letters after that many failed attempts.
+    pub max_attempts: u32,
+    /// Delay after the first failed attempt, doubled after every next one.
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How often background task tries to deliver due messages.
+    pub poll_interval: Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60 * 60),
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Delay before next attempt, after `attempts` failed ones.
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Message waiting to be delivered to peer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboundDelivery {
+    pub id: u64,
+    pub peer: IdentifierPrefix,
+    /// Messages in CESR.
+    pub message: String,
+    /// Number of failed attempts.
+    pub attempts: u32,
+    /// Time of next attempt, in seconds since Unix epoch.
+    pub next_attempt_at: u64,
+    /// Error of the last failed attempt.
+    pub last_error: Option<String>,
+}
+
+/// Delivery abandoned after too many failed attempts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub delivery: OutboundDelivery,
+    /// Time of giving up, in seconds since Unix epoch.
+    pub failed_at: u64,
+}
+
+/// Summary of one [`Witness::process_outbox`](crate::Witness::process_outbox)
+/// run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutboxReport {
+    pub delivered: usize,
+    /// Failed deliveries scheduled for retry.
+    pub retried: usize,
+    /// Failed deliveries moved to dead letters.
+    pub dead_lettered: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Queue {
+    next_id: u64,
+    pending: Vec<OutboundDelivery>,
+}
+
+/// Persistent queue of messages sent by witness to peers. Pending deliveries
+/// are kept in `queue.json`, rewritten on every change, so they survive
+/// restarts. Abandoned deliveries are appended to `dead_letters` file as
+/// JSON lines.
+pub struct Outbox {
+    queue_path: PathBuf,
+    dead_letters_path: PathBuf,
+    pub config: OutboxConfig,
+    queue: Mutex<Queue>,
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+fn invalid_data(e: serde_json::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+impl Outbox {
+    /// Opens outbox stored in `dir`, creating it if needed.
+    pub fn open(dir: &Path, config: OutboxConfig) -> Result<Self, WitnessError> {
+        fs::create_dir_all(dir)?;
+        let queue_path = dir.join("queue.json");
+        let queue = match fs::read(&queue_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(invalid_data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Queue::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            queue_path,
+            dead_letters_path: dir.join("dead_letters"),
+            config,
+            queue: Mutex::new(queue),
+        })
+    }
+
+    /// Adds delivery of CESR `message` to `peer`, to be attempted as soon as
+    /// possible. Returns identifier of delivery.
+    pub fn push(&self, peer: IdentifierPrefix, message: String) -> Result<u64, WitnessError> {
+        let mut queue = self.lock()?;
+        let id = queue.next_id;
+        queue.next_id += 1;
+        queue.pending.push(OutboundDelivery {
+            id,
+            peer,
+            message,
+            attempts: 0,
+            next_attempt_at: now(),
+            last_error: None,
+        });
+        self.save(&queue)?;
+        Ok(id)
+    }
+
+    /// Returns deliveries, which next attempt is due at `now`.
+    pub fn due(&self, now: u64) -> Result<Vec<OutboundDelivery>, WitnessError> {
+        Ok(self
+            .lock()?
+            .pending
+            .iter()
+            .filter(|delivery| delivery.next_attempt_at <= now)
+            .cloned()
+            .collect())
+    }
+
+    /// Removes successfully delivered message from queue.
+    pub fn delivered(&self, id: u64) -> Result<(), WitnessError> {
+        let mut queue = self.lock()?;
+        queue.pending.retain(|delivery| delivery.id != id);
+        self.save(&queue)
+    }
+
+    /// Records failed attempt of delivery at `now` and schedules the next
+    /// one. If delivery ran out of attempts, it's moved to dead letters and
+    /// returned.
+    pub fn failed(
+        &self,
+        id: u64,
+        error: String,
+        now: u64,
+    ) -> Result<Option<DeadLetter>, WitnessError> {
+        let mut queue = self.lock()?;
+        let Some(position) = queue.pending.iter().position(|delivery| delivery.id == id) else {
+            return Ok(None);
+        };
+        let delivery = &mut queue.pending[position];
+        delivery.attempts += 1;
+        delivery.last_error = Some(error);
+        let dead_letter = if delivery.attempts >= self.config.max_attempts {
+            let dead_letter = DeadLetter {
+                delivery: queue.pending.remove(position),
+                failed_at: now,
+            };
+            self.append_dead_letter(&dead_letter)?;
+            Some(dead_letter)
+        } else {
+            delivery.next_attempt_at = now + self.config.backoff(delivery.attempts).as_secs();
+            None
+        };
+        self.save(&queue)?;
+        Ok(dead_letter)
+    }
+
+    /// Returns deliveries waiting in queue. Deliveries with failed attempts
+    /// are stuck, e.g. because peer is unreachable.
+    pub fn pending(&self) -> Result<Vec<OutboundDelivery>, WitnessError> {
+        Ok(self.lock()?.pending.clone())
+    }
+
+    pub fn dead_letters(&self) -> Result<Vec<DeadLetter>, WitnessError> {
+        match File::open(&self.dead_letters_path) {
+            Ok(file) => Ok(BufReader::new(file)
+                .lines()
+                .map(|line| serde_json::from_str(&line?).map_err(invalid_data))
+                .collect::<Result<Vec<_>, _>>()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Queue>, WitnessError> {
+        Ok(self
+            .queue
+            .lock()
+            .map_err(|_e| keri_core::error::Error::MutexPoisoned)?)
+    }
+
+    /// Writes queue to temporary file first, so it isn't left half written.
+    fn save(&self, queue: &Queue) -> Result<(), WitnessError> {
+        let tmp_path = self.queue_path.with_extension("json.tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(queue).map_err(invalid_data)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.queue_path)?;
+        Ok(())
+    }
+
+    fn append_dead_letter(&self, dead_letter: &DeadLetter) -> Result<(), WitnessError> {
+        let mut line = serde_json::to_vec(dead_letter).map_err(invalid_data)?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.dead_letters_path)?;
+        file.write_all(&line)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_outbox() -> Result<(), WitnessError> {
+    let dir = tempfile::Builder::new().prefix("test-outbox").tempdir()?;
+    let config = OutboxConfig {
+        max_attempts: 2,
+        ..Default::default()
+    };
+    let peer: IdentifierPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let outbox = Outbox::open(dir.path(), config.clone())?;
+    let first = outbox.push(peer.clone(), "first".to_string())?;
+    let second = outbox.push(peer.clone(), "second".to_string())?;
+    let now = now();
+    assert_eq!(outbox.due(now)?.len(), 2);
+
+    outbox.delivered(first)?;
+    assert!(outbox
+        .failed(second, "unreachable".to_string(), now)?
+        .is_none());
+    // Failed delivery waits for backoff.
+    assert!(outbox.due(now)?.is_empty());
+    assert_eq!(outbox.due(now + 1)?.len(), 1);
+
+    // Queue survives restart.
+    let outbox = Outbox::open(dir.path(), config)?;
+    let pending = outbox.pending()?;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].attempts, 1);
+    assert_eq!(pending[0].last_error.as_deref(), Some("unreachable"));
+
+    let dead_letter = outbox.failed(second, "unreachable".to_string(), now + 1)?;
+    assert_eq!(
+        dead_letter.map(|dead| dead.delivery.message),
+        Some("second".to_string())
+    );
+    assert!(outbox.pending()?.is_empty());
+    assert_eq!(outbox.dead_letters()?.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use keri_core::{
+    error::Error,
+    prefix::IdentifierPrefix,
+    processor::notification::{Notification, NotificationBus, Notifier},
+};
+
+/// Policy of asking peer witnesses for missing receipts, see
+/// [`Witness::request_pending_receipts`](crate::Witness::request_pending_receipts).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerReceiptsConfig {
+    /// Event is forgotten after that many rounds without collecting all
+    /// receipts.
+    pub max_attempts: u32,
+    /// How often background task asks peers for missing receipts.
+    pub poll_interval: Duration,
+}
+
+impl Default for PeerReceiptsConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            poll_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Events accepted by witness, which receipts of other witnesses can be
+/// still missing. Events are added when they're added to KEL.
+#[derive(Default)]
+pub struct PendingReceipts {
+    /// Number of finished rounds of requests, by identifier and sequence
+    /// number of event.
+    pending: Mutex<HashMap<(IdentifierPrefix, u64), u32>>,
+}
+
+impl PendingReceipts {
+    pub fn pending(&self) -> Vec<(IdentifierPrefix, u64)> {
+        self.pending.lock().unwrap().keys().cloned().collect()
+    }
+
+    pub(crate) fn remove(&self, id: &IdentifierPrefix, sn: u64) {
+        self.pending.lock().unwrap().remove(&(id.clone(), sn));
+    }
+
+    /// Counts failed round of requests. Returns number of failed rounds so
+    /// far.
+    pub(crate) fn failed(&self, id: &IdentifierPrefix, sn: u64) -> u32 {
+        let mut pending = self.pending.lock().unwrap();
+        let attempts = pending.entry((id.clone(), sn)).or_default();
+        *attempts += 1;
+        *attempts
+    }
+}
+
+impl Notifier for PendingReceipts {
+    fn notify(&self, notification: &Notification, _bus: &NotificationBus) -> Result<(), Error> {
+        if let Notification::KeyEventAdded(event) = notification {
+            let key = (
+                event.event_message.data.get_prefix(),
+                event.event_message.data.get_sn(),
+            );
+            self.pending.lock().unwrap().entry(key).or_default();
+        }
+        Ok(())
+    }
+}
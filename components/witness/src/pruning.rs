@@ -0,0 +1,157 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use keri_core::prefix::IdentifierPrefix;
+use serde::{Deserialize, Serialize};
+
+use crate::witness::WitnessError;
+
+/// Identifiers whose old receipts and events can be removed from witness
+/// database, see [`Witness::prune`](crate::Witness::prune).
+#[derive(Debug, Clone, Default)]
+pub struct PruningConfig {
+    /// Pruning settings of identifiers that opted into pruning. Receipts
+    /// and events of other identifiers are never removed.
+    pub policies: Vec<PruningPolicy>,
+}
+
+impl PruningConfig {
+    pub fn policy(&self, id: &IdentifierPrefix) -> Option<&PruningPolicy> {
+        self.policies.iter().find(|policy| &policy.id == id)
+    }
+}
+
+/// Pruning settings of single identifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruningPolicy {
+    pub id: IdentifierPrefix,
+    /// Receipts are pruned up to the latest establishment event first seen
+    /// at least `min_age` ago. If not set, they are pruned up to the latest
+    /// establishment event.
+    pub min_age: Option<Duration>,
+    /// Prune also KEL events preceding that establishment event. Witness
+    /// serves then KEL starting from it, so it should be enabled only for
+    /// identifiers, whose controllers don't need full KEL from witness.
+    pub events: bool,
+}
+
+impl PruningPolicy {
+    /// Policy pruning only receipts, up to the latest establishment event.
+    pub fn new(id: IdentifierPrefix) -> Self {
+        Self {
+            id,
+            min_age: None,
+            events: false,
+        }
+    }
+}
+
+/// Receipts and events removed from witness database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub id: IdentifierPrefix,
+    /// Receipts (and events, if pruned) of events with lower sn were
+    /// removed.
+    pub before_sn: u64,
+    /// Time of pruning, in seconds since Unix epoch.
+    pub pruned_at: u64,
+    /// Removed events and receipts in CESR.
+    pub receipts: String,
+}
+
+impl ArchiveRecord {
+    pub fn new(id: IdentifierPrefix, before_sn: u64, receipts: String) -> Self {
+        let pruned_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        Self {
+            id,
+            before_sn,
+            pruned_at,
+            receipts,
+        }
+    }
+}
+
+/// Append-only archive of pruned receipts and events, stored as JSON lines. It can be
+/// imported back with [`Witness::import_archive`](crate::Witness::import_archive).
+pub struct PruningArchive {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl PruningArchive {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends record to archive. Returns once record is synced to disk, so
+    /// pruned data can be removed from database.
+    pub fn append(&self, record: &ArchiveRecord) -> Result<(), WitnessError> {
+        let _lock = self
+            .lock
+            .lock()
+            .map_err(|_e| keri_core::error::Error::MutexPoisoned)?;
+        let mut line = serde_json::to_vec(record)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        line.push(b'\n');
+        let created = !self.path.exists();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_all()?;
+        // Entry of new file needs to be synced too.
+        #[cfg(unix)]
+        if created {
+            if let Some(dir) = self.path.parent().filter(|dir| dir.is_dir()) {
+                File::open(dir)?.sync_all()?;
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = created;
+        Ok(())
+    }
+
+    /// Reads records of archive at `path`. Missing archive has no records.
+    pub fn read(path: &Path) -> Result<Vec<ArchiveRecord>, WitnessError> {
+        match File::open(path) {
+            Ok(file) => Ok(BufReader::new(file)
+                .lines()
+                .map(|line| {
+                    serde_json::from_str(&line?)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                })
+                .collect::<Result<Vec<_>, _>>()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Summary of receipts and events pruned for one identifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PruningReport {
+    pub id: IdentifierPrefix,
+    /// Receipts (and events, if pruned) of events with lower sn were
+    /// removed.
+    pub before_sn: u64,
+    /// Number of removed receipt messages.
+    pub receipts: usize,
+    /// Number of removed KEL events.
+    pub events: usize,
+}
@@ -0,0 +1,96 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use keri_core::{event_message::signature::Signature, prefix::IdentifierPrefix};
+use serde::{Deserialize, Serialize};
+
+use crate::witness::WitnessError;
+
+/// Instruction to stop serving identifiers and delete their mailbox content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Takedown {
+    pub ids: Vec<IdentifierPrefix>,
+    /// Reason of the takedown, kept in the record for audit.
+    pub reason: String,
+}
+
+impl Takedown {
+    /// Bytes signed by operator: JSON serialization of the takedown.
+    pub fn encode(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+/// Takedown signed by witness operator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedTakedown {
+    pub takedown: Takedown,
+    pub signature: Signature,
+}
+
+/// Append-only log of applied takedowns, stored as JSON lines. Signed
+/// records are kept, so every takedown can be verified later.
+pub struct TakedownLog {
+    path: PathBuf,
+    records: RwLock<Vec<SignedTakedown>>,
+}
+
+impl TakedownLog {
+    pub fn open(path: &Path) -> Result<Self, WitnessError> {
+        let records = match File::open(path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .map(|line| {
+                    serde_json::from_str(&line?)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            records: RwLock::new(records),
+        })
+    }
+
+    pub fn append(&self, record: SignedTakedown) -> Result<(), WitnessError> {
+        let mut records = self
+            .records
+            .write()
+            .map_err(|_e| keri_core::error::Error::MutexPoisoned)?;
+        let mut line = serde_json::to_vec(&record)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_all()?;
+        records.push(record);
+        Ok(())
+    }
+
+    pub fn is_taken_down(&self, id: &IdentifierPrefix) -> bool {
+        self.records
+            .read()
+            .map(|records| {
+                records
+                    .iter()
+                    .any(|record| record.takedown.ids.contains(id))
+            })
+            .unwrap_or(true)
+    }
+
+    pub fn records(&self) -> Vec<SignedTakedown> {
+        self.records
+            .read()
+            .map(|records| records.clone())
+            .unwrap_or_default()
+    }
+}
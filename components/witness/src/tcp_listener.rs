@@ -0,0 +1,23 @@
+//! Witness endpoints served over framed TCP (see
+//! [`keri_core::transport::tcp`]), for deployments that avoid HTTP. Requests
+//! use the same paths and bodies as HTTP endpoints and are answered by the
+//! same router as in [`WitnessListener::listen_http`].
+use std::future::Future;
+
+use actor_server::{tcp_listener, AdminToken};
+
+use crate::WitnessListener;
+
+impl WitnessListener {
+    /// Serves witness endpoints over framed TCP on given address.
+    pub fn listen_tcp(
+        &self,
+        addr: impl tokio::net::ToSocketAddrs + Send + 'static,
+    ) -> impl Future<Output = std::io::Result<()>> + Send + 'static {
+        tcp_listener::listen_tcp(
+            self.witness_data.clone(),
+            AdminToken(self.admin_token.clone()),
+            addr,
+        )
+    }
+}
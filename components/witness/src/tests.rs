@@ -13,15 +13,22 @@ use keri_core::{
         seal::{EventSeal, Seal},
         threshold::SignatureThreshold,
     },
-    event_message::signed_event_message::{Message, Notice, Op, SignedEventMessage},
+    event_message::{
+        signature::Nontransferable,
+        signed_event_message::{Message, Notice, Op, SignedEventMessage},
+    },
     keys::PublicKey,
     mailbox::{exchange::ForwardTopic, MailboxResponse},
+    oobi::Role,
     prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
     processor::{
         basic_processor::BasicProcessor, escrow::EscrowConfig, event_storage::EventStorage,
         Processor,
     },
-    query::query_event::{LogsQueryArgs, SignedQueryMessage},
+    query::{
+        query_event::{LogsQueryArgs, SignedQueryMessage},
+        reply_event::ReplyRoute,
+    },
     signer::{CryptoBox, Signer},
 };
 use tempfile::Builder;
@@ -125,6 +132,16 @@ fn test_not_fully_witnessed() -> Result<(), Error> {
 
     assert_eq!(receipts.len(), 2);
 
+    // Each witness has only its own receipt.
+    assert_eq!(
+        first_witness.missing_receipts(&controller.prefix(), 0)?,
+        vec![second_witness.prefix.clone()]
+    );
+    assert_eq!(
+        second_witness.missing_receipts(&controller.prefix(), 0)?,
+        vec![first_witness.prefix.clone()]
+    );
+
     // Witness updates state of identifier even if it hasn't all receipts
     assert_eq!(
         first_witness
@@ -178,6 +195,9 @@ fn test_not_fully_witnessed() -> Result<(), Error> {
         .into_iter()
         .map(|rct| second_witness.process_notice(rct))
         .collect::<Result<Vec<_>, _>>()?;
+    assert!(first_witness
+        .missing_receipts(&controller.prefix(), 0)?
+        .is_empty());
 
     assert_eq!(
         first_witness
@@ -330,6 +350,7 @@ fn test_qry_rpy() -> Result<(), ActorError> {
         s: None,
         src: Some(alice.prefix().clone()),
         limit: None,
+        after: None,
     };
 
     let qry = QueryEvent::new_query(
@@ -360,17 +381,25 @@ fn test_qry_rpy() -> Result<(), ActorError> {
 
     let response = witness.process_query(query)?;
 
-    // assert_eq!(response.len(), 1);
-    if let Some(PossibleResponse::Kel(response)) = response {
-        match &response[0] {
-            Message::Op(Op::Reply(rpy)) => {
-                if let ReplyRoute::Ksn(_id, ksn) = rpy.reply.get_route() {
-                    assert_eq!(&ksn.state, &alice.get_state().unwrap())
-                }
-            }
-            _ => unreachable!(),
+    let rpy = match response {
+        Some(PossibleResponse::Ksn(rpy)) => rpy,
+        _ => unreachable!(),
+    };
+    match rpy.reply.get_route() {
+        ReplyRoute::Ksn(id, ksn) => {
+            assert_eq!(id, IdentifierPrefix::Basic(witness.prefix.clone()));
+            assert_eq!(&ksn.state, &alice.get_state().unwrap())
         }
+        _ => unreachable!(),
     }
+    // Reply is signed by witness.
+    assert_eq!(
+        rpy.signature.get_signer(),
+        Some(IdentifierPrefix::Basic(witness.prefix.clone()))
+    );
+    assert!(rpy
+        .signature
+        .verify(&rpy.reply.encode()?, &*witness.event_storage)?);
 
     // Bob asks about alices kel
     // construct qry message to ask of alice kel
@@ -379,6 +408,7 @@ fn test_qry_rpy() -> Result<(), ActorError> {
         s: None,
         src: None,
         limit: None,
+        after: None,
     };
     let qry = QueryEvent::new_query(
         QueryRoute::Logs {
@@ -420,6 +450,70 @@ fn test_qry_rpy() -> Result<(), ActorError> {
     Ok(())
 }
 
+#[test]
+fn test_logs_since_sn() -> Result<(), Error> {
+    use keri_core::{
+        actor::process_query,
+        query::{query_event::QueryRoute, ReplyType},
+    };
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+    let rot = controller.rotate(None, None, None)?;
+    witness.process_notice(Notice::Event(rot.clone()))?;
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn.clone()))?;
+
+    let logs_after = |after: u64, limit: Option<u64>| {
+        let route = QueryRoute::Logs {
+            reply_route: "".to_string(),
+            args: LogsQueryArgs {
+                i: id.clone(),
+                s: None,
+                src: None,
+                limit,
+                after: Some(after),
+            },
+        };
+        match process_query(&route, &witness.event_storage).unwrap() {
+            ReplyType::Kel(msgs) => msgs,
+            _ => unreachable!(),
+        }
+    };
+    let sns = |msgs: Vec<Message>| {
+        msgs.into_iter()
+            .filter_map(|msg| match msg {
+                Message::Notice(Notice::Event(event)) => Some(event.event_message.data.sn),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Events after inception, with witness receipts.
+    let delta = logs_after(0, None);
+    assert!(delta
+        .iter()
+        .any(|msg| matches!(msg, Message::Notice(Notice::NontransferableRct(_)))));
+    assert_eq!(sns(delta), vec![1, 2]);
+    assert_eq!(sns(logs_after(0, Some(1))), vec![1]);
+    // Nothing new.
+    assert!(logs_after(2, None).is_empty());
+
+    Ok(())
+}
+
 #[test]
 pub fn test_key_state_notice() -> Result<(), Error> {
     use keri_core::{
@@ -560,6 +654,75 @@ pub fn test_key_state_notice() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+pub fn test_ksn_timestamp_order() -> Result<(), Error> {
+    use keri_core::{
+        processor::validator::EventValidator,
+        query::{
+            reply_event::{ReplyEvent, SignedReply},
+            QueryError,
+        },
+    };
+    use std::time::Duration;
+
+    let signer_arc = Arc::new(Signer::new());
+    let witness_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let witness_root_oobi = Builder::new().prefix("test-db").tempdir().unwrap();
+    let witness = Witness::new(
+        Url::parse("http://example.com").unwrap(),
+        signer_arc.clone(),
+        witness_root.path(),
+        witness_root_oobi.path(),
+        WitnessEscrowConfig::default(),
+    )
+    .unwrap();
+    let witness_id = IdentifierPrefix::Basic(witness.prefix.clone());
+    let controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let db = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+    let redb_root = Builder::new().tempfile().unwrap();
+    let redb = Arc::new(RedbDatabase::new(redb_root.path()).unwrap());
+    let processor = BasicProcessor::new(redb.clone(), db.clone(), None);
+    let icp = witness.event_storage.get_event_at_sn(&id, 0).unwrap();
+    processor.process_notice(&Notice::Event(icp.signed_event_message))?;
+
+    // Notice issued before reply that carries it.
+    let mut ksn = witness
+        .event_storage
+        .get_ksn_for_prefix(&id, SerializationFormats::JSON)?;
+    ksn.timestamp = ksn.timestamp - Duration::from_secs(10);
+    let rpy = ReplyEvent::new_reply(
+        ReplyRoute::Ksn(witness_id.clone(), ksn.clone()),
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    );
+    let signature = SelfSigningPrefix::Ed25519Sha512(signer_arc.sign(rpy.encode()?)?);
+    processor.process_op_reply(&SignedReply::new_nontrans(
+        rpy,
+        witness.prefix.clone(),
+        signature,
+    ))?;
+
+    // Notices are compared by their timestamps, not by timestamps of
+    // replies, so the same notice isn't stale.
+    let validator = EventValidator::new(db, redb);
+    validator.check_timestamp_with_last_ksn(ksn.timestamp, &id, &witness_id)?;
+    let older = ksn.timestamp - Duration::from_secs(1);
+    assert!(matches!(
+        validator.check_timestamp_with_last_ksn(older, &id, &witness_id),
+        Err(Error::QueryError(QueryError::StaleKsn))
+    ));
+    // No notice from that signer yet.
+    assert!(matches!(
+        validator.check_timestamp_with_last_ksn(ksn.timestamp, &id, &id),
+        Err(Error::EventOutOfOrderError)
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn test_mbx() {
     use std::sync::Mutex;
@@ -846,6 +1009,8 @@ pub fn test_multisig() -> Result<(), ActorError> {
         receipt,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(receipt.len(), 1);
@@ -893,6 +1058,8 @@ pub fn test_multisig() -> Result<(), ActorError> {
         receipt,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(receipt.len(), 1);
@@ -1019,6 +1186,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
         receipt: _,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(multisig.len(), 1);
@@ -1039,6 +1208,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
         receipt: _,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(multisig.len(), 1);
@@ -1073,6 +1244,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
         receipt,
         multisig: _,
         delegate,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(receipt.len(), 1);
@@ -1099,6 +1272,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
         receipt,
         multisig: _,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(receipt.len(), 2);
@@ -1141,6 +1316,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
             receipt: _,
             multisig: _,
             delegate,
+            multisig_metadata: _,
+            next: _,
         })) = response
         {
             assert_eq!(delegate.len(), 1);
@@ -1181,6 +1358,8 @@ pub fn test_delegated_multisig() -> Result<(), ActorError> {
             receipt,
             multisig: _,
             delegate: _,
+            multisig_metadata: _,
+            next: _,
         })) = response
         {
             assert_eq!(receipt.len(), 1);
@@ -1257,6 +1436,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
         receipt: _,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(multisig.len(), 1);
@@ -1275,6 +1456,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
         receipt: _,
         multisig,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         assert_eq!(multisig.len(), 1);
@@ -1301,6 +1484,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
             receipt,
             multisig: _,
             delegate: _,
+            multisig_metadata: _,
+            next: _,
         })) = response
         {
             assert_eq!(receipt.len(), 1);
@@ -1358,6 +1543,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
             receipt: _,
             multisig: _,
             delegate,
+            multisig_metadata: _,
+            next: _,
         })) = response
         {
             assert_eq!(delegate.len(), 1);
@@ -1388,6 +1575,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
                 receipt: _,
                 multisig,
                 delegate: _,
+                multisig_metadata: _,
+                next: _,
             })) = response
             {
                 assert_eq!(multisig.len(), 3);
@@ -1426,6 +1615,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
             receipt,
             multisig: _,
             delegate: _,
+            multisig_metadata: _,
+            next: _,
         })) = response
         {
             assert_eq!(receipt.len(), 2);
@@ -1474,6 +1665,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
         receipt: _,
         multisig: _,
         delegate,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         let msg = Message::Notice(Notice::Event(delegate[0].clone()));
@@ -1502,6 +1695,8 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
         receipt,
         multisig: _,
         delegate: _,
+        multisig_metadata: _,
+        next: _,
     })) = response
     {
         child.process_receipt(receipt[0].clone())?;
@@ -1513,3 +1708,1751 @@ pub fn test_delegating_multisig() -> Result<(), ActorError> {
 
     Ok(())
 }
+
+#[test]
+fn test_census() -> Result<(), Error> {
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    assert!(witness.census()?.is_empty());
+
+    let controller = setup_controller(&witness)?;
+    let census = witness.census()?;
+    assert_eq!(census.len(), 1);
+    let entry = &census[0];
+    assert_eq!(&entry.id, controller.prefix());
+    assert_eq!(entry.sn, 0);
+    assert_eq!(entry.witnesses, 1);
+    assert_eq!(entry.receipts, 1);
+    assert!(entry.storage_size > 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_backup() -> Result<(), Error> {
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let kel = witness
+        .event_storage
+        .get_kel_messages_with_receipts_all(controller.prefix())?;
+
+    let backup_root = Builder::new().prefix("test-backup").tempdir().unwrap();
+    witness.backup(backup_root.path()).unwrap();
+
+    // Witness started from backup directory has the same KEL.
+    let restored = Witness::new(
+        Url::parse("http://example.com").unwrap(),
+        Arc::new(Signer::new()),
+        backup_root.path(),
+        &backup_root.path().join("oobi"),
+        WitnessEscrowConfig::default(),
+    )
+    .unwrap();
+    assert!(restored
+        .event_storage
+        .events_db
+        .check_consistency()
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        restored
+            .event_storage
+            .get_kel_messages_with_receipts_all(controller.prefix())?,
+        kel
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_outbox_dead_letters() -> Result<(), crate::witness::WitnessError> {
+    use std::time::Duration;
+
+    use crate::outbox::OutboxConfig;
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+    let mut witness = Witness::new(
+        Url::parse("http://example.com").unwrap(),
+        Arc::new(Signer::new()),
+        root.path(),
+        oobi_root.path(),
+        WitnessEscrowConfig::default(),
+    )?;
+    witness.outbox.config = OutboxConfig {
+        max_attempts: 2,
+        base_backoff: Duration::ZERO,
+        ..Default::default()
+    };
+    let controller = setup_controller(&witness)?;
+    // Only witness of the identifier has no peers to push receipts to.
+    assert_eq!(witness.push_receipts(controller.prefix(), 0)?, 0);
+
+    // Location of peer is unknown, so delivery fails.
+    let peer: IdentifierPrefix = "BDg3H7Sr-eES0XWXiO8nvMxW6mD_1LxLeE1nuiZxhGp4"
+        .parse()
+        .unwrap();
+    let kel = witness
+        .event_storage
+        .get_kel_messages_with_receipts_all(controller.prefix())?
+        .unwrap_or_default()
+        .into_iter()
+        .map(Message::Notice)
+        .collect();
+    witness.enqueue_delivery(peer, kel)?;
+
+    let report = witness.process_outbox().await?;
+    assert_eq!(report.retried, 1);
+    let pending = witness.outbox.pending()?;
+    assert_eq!(pending.len(), 1);
+    assert!(pending[0].last_error.is_some());
+
+    let report = witness.process_outbox().await?;
+    assert_eq!(report.dead_lettered, 1);
+    assert!(witness.outbox.pending()?.is_empty());
+    assert_eq!(witness.outbox.dead_letters()?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_receipts() -> Result<(), ActorError> {
+    use std::collections::HashMap;
+
+    use keri_core::transport::test::{TestActorMap, TestTransport};
+    use url::Host;
+
+    use crate::WitnessListener;
+
+    let root2 = Builder::new().prefix("test-witness2").tempdir().unwrap();
+    let listener2 = Arc::new(
+        WitnessListener::setup(
+            Url::parse("http://witness2:3232").unwrap(),
+            root2.path(),
+            None,
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap(),
+    );
+    let mut actors: TestActorMap = HashMap::new();
+    actors.insert(
+        (Host::Domain("witness2".to_string()), 3232),
+        listener2.clone(),
+    );
+    let root1 = Builder::new().prefix("test-witness1").tempdir().unwrap();
+    let listener1 = WitnessListener::setup(
+        Url::parse("http://witness1:3232").unwrap(),
+        root1.path(),
+        None,
+        WitnessEscrowConfig::default(),
+    )
+    .unwrap()
+    .with_transport(Box::new(TestTransport::new(actors)));
+    let (witness1, witness2) = (&listener1.witness_data, &listener2.witness_data);
+
+    // Witness 1 knows location of witness 2.
+    let witness2_id = IdentifierPrefix::Basic(witness2.prefix.clone());
+    for rpy in witness2.get_loc_scheme_for_id(&witness2_id)?.unwrap() {
+        witness1.oobi_manager.process_oobi(&rpy).unwrap();
+    }
+    // It also knows HTTPS location, which is preferred, but can't be
+    // reached. HTTP location is used then.
+    let https_location = {
+        use keri_core::{
+            oobi::{LocationScheme, Scheme},
+            query::reply_event::{ReplyEvent, SignedReply},
+        };
+        let reply = ReplyEvent::new_reply(
+            ReplyRoute::LocScheme(LocationScheme::new(
+                witness2_id.clone(),
+                Scheme::Https,
+                Url::parse("https://witness2-unreachable").unwrap(),
+            )),
+            HashFunctionCode::Blake3_256,
+            SerializationFormats::JSON,
+        );
+        let signature = witness2.signer.sign(reply.encode()?).unwrap();
+        SignedReply::new_nontrans(
+            reply,
+            witness2.prefix.clone(),
+            SelfSigningPrefix::Ed25519Sha512(signature),
+        )
+    };
+    witness1.oobi_manager.process_oobi(&https_location).unwrap();
+
+    let mut controller = {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-db-oobi").tempdir().unwrap();
+        let events_root = Builder::new().tempfile().unwrap();
+        SimpleController::new(
+            Arc::new(SledEventDatabase::new(root.path()).unwrap()),
+            Arc::new(RedbDatabase::new(events_root.path()).unwrap()),
+            Arc::new(EscrowDb::new(root.path())?),
+            Arc::new(Mutex::new(CryptoBox::new()?)),
+            oobi_root.path(),
+            EscrowConfig::default(),
+        )?
+    };
+    let icp = controller.incept(
+        Some(vec![witness1.prefix.clone(), witness2.prefix.clone()]),
+        Some(2),
+        None,
+    )?;
+    for witness in [witness1, witness2] {
+        witness.process_notice(Notice::Event(icp.clone()))?;
+    }
+    let id = icp.event_message.data.get_prefix();
+    assert_eq!(
+        witness1.missing_receipts(&id, 0)?,
+        vec![witness2.prefix.clone()]
+    );
+    assert_eq!(witness1.pending_receipts.pending(), vec![(id.clone(), 0)]);
+
+    // Witness 1 asks witness 2 for its receipt without waiting for
+    // controller.
+    assert_eq!(witness1.request_pending_receipts().await?, 0);
+    assert!(witness1.missing_receipts(&id, 0)?.is_empty());
+    assert!(witness1.pending_receipts.pending().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keri_actor() -> Result<(), ActorError> {
+    use keri_core::{
+        actor::{keri_actor::KeriActor, parse_op_stream},
+        oobi::Oobi,
+    };
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+    let witness = Witness::setup(
+        Url::parse("http://example.com").unwrap(),
+        root.path(),
+        oobi_root.path(),
+        None,
+        WitnessEscrowConfig::default(),
+    )
+    .unwrap();
+    let controller = setup_controller(&witness)?;
+
+    // Witness is used only through actor interface.
+    let actor: Arc<dyn KeriActor> = Arc::new(witness);
+    let eid = IdentifierPrefix::Basic(actor.prefix());
+    assert_eq!(actor.oobi().eid, eid);
+
+    let location = parse_op_stream(&actor.location_oobis(&eid)?)?;
+    assert!(matches!(
+        &location[..],
+        [Op::Reply(rpy)] if matches!(
+            rpy.reply.get_route(),
+            ReplyRoute::LocScheme(ref loc) if loc.eid == eid
+        )
+    ));
+    let oobis = actor.role_oobis(controller.prefix(), Role::Witness, &eid)?;
+    assert!(!oobis.is_empty());
+
+    assert!(actor
+        .resolve_oobi(Oobi::Location(actor.oobi()))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_mailbox_pagination() -> Result<(), ActorError> {
+    use keri_core::query::mailbox::{QueryArgsMbx, QueryTopics};
+
+    let mut witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn))?;
+
+    // Every receipt exceeds the limit, so each response contains single
+    // message.
+    witness.mailbox_max_size = 1;
+    let response = witness.process_query(controller.query_mailbox(&witness.prefix))?;
+    let next = match response {
+        Some(PossibleResponse::Mbx(MailboxResponse { receipt, next, .. })) => {
+            assert_eq!(receipt.len(), 1);
+            next.unwrap()
+        }
+        _ => unreachable!(),
+    };
+    assert_eq!(next.receipt, 1);
+
+    // Query continued from returned topic indexes gets the rest.
+    let rest = witness
+        .event_storage
+        .get_mailbox_messages(&QueryArgsMbx {
+            i: controller.prefix().clone(),
+            pre: controller.prefix().clone(),
+            src: IdentifierPrefix::Basic(witness.prefix.clone()),
+            topics: next.clone(),
+            resume: false,
+            limit: None,
+        })?
+        .limit_size(witness.mailbox_max_size, &next)?;
+    assert_eq!(rest.receipt.len(), 1);
+    assert_eq!(rest.next, None);
+
+    // Query can limit number of returned messages too.
+    let page_query = |topics: QueryTopics| QueryArgsMbx {
+        i: controller.prefix().clone(),
+        pre: controller.prefix().clone(),
+        src: IdentifierPrefix::Basic(witness.prefix.clone()),
+        topics,
+        resume: false,
+        limit: Some(1),
+    };
+    let first = witness
+        .event_storage
+        .get_mailbox_messages(&page_query(QueryTopics::default()))?;
+    assert_eq!(first.receipt.len(), 1);
+    let next = first.next.unwrap();
+    assert_eq!(next.receipt, 1);
+    let last = witness
+        .event_storage
+        .get_mailbox_messages(&page_query(next))?;
+    assert_eq!(last.receipt.len(), 1);
+    assert_eq!(last.next, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_mailbox_cursor() -> Result<(), ActorError> {
+    use keri_core::query::mailbox::QueryTopics;
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn))?;
+
+    let topics = |receipt| QueryTopics {
+        credential: 0,
+        receipt,
+        replay: 0,
+        multisig: 0,
+        delegate: 0,
+        reply: 0,
+    };
+    let receipts_count = |qry| match witness.process_query(qry) {
+        Ok(Some(PossibleResponse::Mbx(MailboxResponse { receipt, .. }))) => receipt.len(),
+        _ => unreachable!(),
+    };
+    let acknowledge = |receipt| {
+        let ack = controller.query_mailbox_from(&witness.prefix, topics(receipt), false);
+        witness.parse_and_process_mailbox_acks(&Message::Op(Op::Query(ack)).to_cesr().unwrap())
+    };
+    assert_eq!(
+        receipts_count(controller.query_mailbox_from(&witness.prefix, topics(0), true)),
+        2
+    );
+
+    // Controller consumed first receipt, so it's removed from mailbox.
+    acknowledge(1)?;
+    assert_eq!(
+        receipts_count(controller.query_mailbox_from(&witness.prefix, topics(0), true)),
+        1
+    );
+    assert_eq!(receipts_count(controller.query_mailbox(&witness.prefix)), 1);
+    assert_eq!(
+        witness
+            .event_storage
+            .escrow_db
+            .get_mailbox_receipts(controller.prefix())
+            .unwrap()
+            .count(),
+        1
+    );
+    // Index of remaining receipt doesn't change.
+    assert_eq!(
+        receipts_count(controller.query_mailbox_from(&witness.prefix, topics(1), false)),
+        1
+    );
+
+    // Cursor doesn't move back.
+    acknowledge(0)?;
+    assert_eq!(
+        receipts_count(controller.query_mailbox_from(&witness.prefix, topics(0), true)),
+        1
+    );
+
+    // Only mailbox queries can be acknowledged.
+    let ksn_query = controller.query_ksn(controller.prefix())?;
+    assert!(witness
+        .parse_and_process_mailbox_acks(&Message::Op(ksn_query).to_cesr().unwrap())
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_multisig_forward_deduplication() -> Result<(), ActorError> {
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut cont1 = setup_controller(&witness)?;
+    let mut cont2 = setup_controller(&witness)?;
+    let cont3 = setup_controller(&witness)?;
+    for participant in [cont2.prefix(), cont3.prefix()] {
+        let kel = witness
+            .event_storage
+            .get_kel_messages_with_receipts_all(participant)?
+            .unwrap()
+            .into_iter()
+            .map(Message::Notice)
+            .collect::<Vec<_>>();
+        cont1.process(&kel)?;
+    }
+
+    let (group_icp, exchanges) = cont1.group_incept(
+        vec![cont2.prefix().clone(), cont3.prefix().clone()],
+        &SignatureThreshold::Simple(3),
+        Some(vec![witness.prefix.clone()]),
+        Some(1),
+        None,
+    )?;
+    let group_id = group_icp.event_message.data.get_prefix();
+    witness.process_notice(Notice::Event(group_icp.clone()))?;
+    let multisig_count = |id: &IdentifierPrefix| {
+        witness
+            .get_mailbox_messages(id)
+            .map(|mbx| mbx.multisig.len())
+    };
+
+    // Retransmitted exchanges don't duplicate messages.
+    for _ in 0..2 {
+        for exn in &exchanges {
+            witness.process_exchange(exn.clone())?;
+        }
+    }
+    assert_eq!(multisig_count(cont2.prefix())?, 1);
+    assert_eq!(multisig_count(cont3.prefix())?, 1);
+
+    // Both participants forward the same event to group mailbox.
+    for participant in [&cont2, &cont3] {
+        let exn =
+            participant.create_forward_message(&group_id, &group_icp, ForwardTopic::Multisig)?;
+        witness.process_exchange(exn)?;
+    }
+    assert_eq!(multisig_count(&group_id)?, 1);
+
+    // Event with new signature is delivered.
+    let signed_icp = cont2.process_multisig(group_icp)?.unwrap();
+    for _ in 0..2 {
+        let exn = cont2.create_forward_message(&group_id, &signed_icp, ForwardTopic::Multisig)?;
+        witness.process_exchange(exn)?;
+    }
+    assert_eq!(multisig_count(&group_id)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_oobis_for_cid() -> Result<(), ActorError> {
+    use keri_core::{
+        oobi::{LocationScheme, Scheme},
+        query::reply_event::{ReplyEvent, ReplyRoute, SignedReply},
+    };
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+
+    // Witness knows watcher's location.
+    let watcher_signer = Signer::new();
+    let watcher_prefix = BasicPrefix::Ed25519NT(watcher_signer.public_key());
+    let watcher_id = IdentifierPrefix::Basic(watcher_prefix.clone());
+    let loc_scheme = ReplyEvent::new_reply(
+        ReplyRoute::LocScheme(LocationScheme::new(
+            watcher_id.clone(),
+            Scheme::Http,
+            Url::parse("http://watcher.example.com").unwrap(),
+        )),
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    );
+    let signature = watcher_signer.sign(loc_scheme.encode()?)?;
+    witness.process_reply(SignedReply::new_nontrans(
+        loc_scheme,
+        watcher_prefix,
+        SelfSigningPrefix::Ed25519Sha512(signature),
+    ))?;
+
+    // Controller designates watcher.
+    let Op::Reply(end_role) = controller.add_watcher(&watcher_id)? else {
+        unreachable!()
+    };
+    witness.process_reply(end_role)?;
+
+    let routes = witness
+        .get_oobis_for_cid(controller.prefix())?
+        .into_iter()
+        .map(|rpy| rpy.reply.get_route())
+        .collect::<Vec<_>>();
+    assert_eq!(routes.len(), 2);
+    assert!(matches!(&routes[0], ReplyRoute::EndRoleAdd(end_role) if end_role.eid == watcher_id));
+    assert!(matches!(&routes[1], ReplyRoute::LocScheme(loc) if loc.eid == watcher_id));
+
+    Ok(())
+}
+
+#[test]
+fn test_transferable_receipts() -> Result<(), Error> {
+    use keri_core::actor::parse_notice_stream;
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+
+    // Events and sigs are from keripy `test_direct_mode` test.
+    let icp_raw = br#"{"v":"KERI10JSON00012b_","t":"icp","d":"EJe_sKQb1otKrz6COIL8VFvBv3DEFvtKaVFGn1vm0IlL","i":"EJe_sKQb1otKrz6COIL8VFvBv3DEFvtKaVFGn1vm0IlL","s":"0","kt":"1","k":["DC8kCMHKrYZewclvG9vj1R1nSspiRwPi-ByqRwFuyq4i"],"nt":"1","n":["EBPlMwLJ5rSKWCaZq4bczEHLQvYX3P7cILmBzy0Pp4O4"],"bt":"0","b":[],"c":[],"a":[]}-AABAAAWQ0yBzzzVsOJPDkKzbDPzfYXEF5xmQgJSEKXcDO3XMVSL2DmDRYZV73huYX5BAsfzIhBXggKKAcGcEfT38R8L"#;
+    let vrc_raw = br#"{"v":"KERI10JSON000091_","t":"rct","d":"EJe_sKQb1otKrz6COIL8VFvBv3DEFvtKaVFGn1vm0IlL","i":"EJe_sKQb1otKrz6COIL8VFvBv3DEFvtKaVFGn1vm0IlL","s":"0"}-FABEAzjKx3hSVJArKpIOVt2KfTRjq8st22hL25Ho9vnNodz0AAAAAAAAAAAAAAAAAAAAAAAEAzjKx3hSVJArKpIOVt2KfTRjq8st22hL25Ho9vnNodz-AABAAD-iI61odpZQjzm0fN9ZATjHx-KjQ9W3-CIlvhowwUaPC5KnQAIGYFuWJyRgAQalYVSEWoyMK2id_ONTFUE-NcF"#;
+    let val_icp_raw = br#"{"v":"KERI10JSON00012b_","t":"icp","d":"EAzjKx3hSVJArKpIOVt2KfTRjq8st22hL25Ho9vnNodz","i":"EAzjKx3hSVJArKpIOVt2KfTRjq8st22hL25Ho9vnNodz","s":"0","kt":"1","k":["BF5b1hKlY38RoAhR7G8CExP4qjHFvbHx25Drp5Jj2j4p"],"nt":"1","n":["ECoxJfQH0GUrlDKoC3U-neGY1CJib7VyZGh6QhdJtWoT"],"bt":"0","b":[],"c":[],"a":[]}-AABAACOKLyxKvQyy_TvkfQffGnk-p0cc1H11dpxV8gbxvYGm5kfvqPerlorqD21hGRAqvyFQJ967Y8lFl_dxTaal2cA"#;
+    let controller_id: IdentifierPrefix = "EJe_sKQb1otKrz6COIL8VFvBv3DEFvtKaVFGn1vm0IlL".parse()?;
+
+    let has_trans_receipt = |witness: &Witness| -> Result<bool, Error> {
+        Ok(witness
+            .event_storage
+            .get_kel_messages_with_receipts_all(&controller_id)?
+            .unwrap()
+            .iter()
+            .any(|notice| matches!(notice, Notice::TransferableRct(_))))
+    };
+
+    for notice in parse_notice_stream(icp_raw).unwrap() {
+        witness.process_notice(notice)?;
+    }
+    // Validator's KEL is unknown, so receipt is escrowed.
+    for notice in parse_notice_stream(vrc_raw).unwrap() {
+        witness.process_notice(notice)?;
+    }
+    assert!(!has_trans_receipt(&witness)?);
+
+    // Receipt is accepted after validator's inception event and served with
+    // controller's KEL.
+    for notice in parse_notice_stream(val_icp_raw).unwrap() {
+        witness.process_notice(notice)?;
+    }
+    assert!(has_trans_receipt(&witness)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_witness_key_rotation() -> Result<(), Error> {
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+    let open_witness = |signer: &Arc<Signer>| {
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            signer.clone(),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let (previous_signer, signer) = (Arc::new(Signer::new()), Arc::new(Signer::new()));
+
+    let mut witness = open_witness(&previous_signer);
+    let previous_prefix = witness.prefix.clone();
+    let mut controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+
+    witness.rotate_key(signer.clone()).unwrap();
+    let prefix = witness.prefix.clone();
+    assert_ne!(prefix, previous_prefix);
+
+    // New location is signed with new key, and rotation record signed by
+    // both keys designates new prefix as successor of previous one.
+    let loc = witness
+        .get_loc_scheme_for_id(&IdentifierPrefix::Basic(prefix.clone()))?
+        .unwrap();
+    assert_eq!(
+        loc[0].signature.get_signer(),
+        Some(IdentifierPrefix::Basic(prefix.clone()))
+    );
+    let record = witness.key_rotations.successor(&previous_prefix).unwrap();
+    assert_eq!(record.rotation.successor, prefix);
+    assert!(record.verify());
+    let mut forged = record.clone();
+    forged.rotation.successor = BasicPrefix::Ed25519NT(Signer::new().public_key());
+    assert!(!forged.verify());
+
+    // Receipt issued before rotation is still verifiable.
+    let icp = witness.event_storage.get_event_at_sn(&id, 0).unwrap();
+    let receipt = witness.event_storage.get_nt_receipts(&id, 0)?.unwrap();
+    match &receipt.signatures[0] {
+        Nontransferable::Couplet(couplets) => {
+            let (signer, signature) = &couplets[0];
+            assert_eq!(signer, &previous_prefix);
+            assert!(signer.verify(&icp.signed_event_message.event_message.encode()?, signature)?);
+        }
+        Nontransferable::Indexed(_) => unreachable!(),
+    };
+
+    // New events are receipted with new key.
+    let rot = controller.rotate(None, None, None)?;
+    witness.process_notice(Notice::Event(rot))?;
+    let receipt = witness.event_storage.get_nt_receipts(&id, 1)?.unwrap();
+    assert!(matches!(
+        &receipt.signatures[0],
+        Nontransferable::Couplet(couplets) if couplets[0].0 == prefix
+    ));
+    drop(witness);
+
+    // Record is kept after restart with new key.
+    let witness = open_witness(&signer);
+    assert_eq!(witness.key_rotations.records(), vec![record.clone()]);
+    drop(witness);
+
+    // Restarting with previous key and rotating to the same key again
+    // doesn't add record, but rotating to other key is rejected.
+    let mut witness = open_witness(&previous_signer);
+    witness.rotate_key(signer.clone()).unwrap();
+    assert_eq!(witness.prefix, prefix);
+    assert_eq!(witness.key_rotations.records(), vec![record]);
+    drop(witness);
+    let mut witness = open_witness(&previous_signer);
+    assert!(matches!(
+        witness.rotate_key(Arc::new(Signer::new())),
+        Err(crate::witness::WitnessError::KeyAlreadyRotated(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_replayed_notices() -> Result<(), Error> {
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+    let rot = controller.rotate(None, None, None)?;
+    let stream = Message::Notice(Notice::Event(rot.clone())).to_cesr()?;
+
+    let outcome = witness.process_notices_with_outcome(&stream)?;
+    assert_eq!(outcome.len(), 1);
+    assert_eq!(outcome[0].sn, 1);
+    assert_eq!(outcome[0].digest, rot.event_message.digest()?);
+    assert!(outcome[0].receipt.is_some());
+
+    // Replaying the same stream gives the same outcome without storing
+    // anything twice.
+    let replayed = witness.process_notices_with_outcome(&stream)?;
+    assert_eq!(replayed, outcome);
+    assert_eq!(
+        witness.event_storage.get_kel_messages(&id)?.unwrap().len(),
+        2
+    );
+    assert!(witness
+        .event_storage
+        .escrow_db
+        .get_duplicious_events(&id)
+        .map_or(true, |mut events| events.next().is_none()));
+    assert_eq!(witness.get_mailbox_messages(&id)?.receipt.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_event_shards() -> Result<(), Error> {
+    use keri_core::database::redb::sharded::RedbStorage;
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+    let open_witness = || {
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+
+    let witness = open_witness();
+    let mut controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+    let rot = controller.rotate(None, None, None)?;
+    witness.process_notice(Notice::Event(rot))?;
+    assert_eq!(Witness::event_shards(root.path()).unwrap(), Some(1));
+    drop(witness);
+
+    // Split single database.
+    Witness::reshard(root.path(), 3).unwrap();
+    assert_eq!(Witness::event_shards(root.path()).unwrap(), Some(3));
+    assert!(root.path().join("events_database.unsharded").exists());
+    let witness = open_witness();
+    assert!(matches!(
+        *witness.event_storage.events_db,
+        RedbStorage::Sharded(_)
+    ));
+    assert_eq!(
+        witness.event_storage.get_kel_messages(&id)?.unwrap().len(),
+        2
+    );
+    drop(witness);
+
+    // Rebalance existing shards.
+    Witness::reshard(root.path(), 2).unwrap();
+    assert_eq!(Witness::event_shards(root.path()).unwrap(), Some(2));
+    assert!(root.path().join("events_shards.old").exists());
+    // Previous shards have to be removed before the next resharding.
+    assert!(Witness::reshard(root.path(), 4).is_err());
+
+    let witness = open_witness();
+    assert_eq!(witness.event_storage.events_db.shard_count(), 2);
+    assert_eq!(
+        witness.event_storage.get_kel_messages(&id)?.unwrap().len(),
+        2
+    );
+    let state = witness.event_storage.get_state(&id).unwrap();
+    assert_eq!(state.sn, 1);
+
+    let backup_root = Builder::new().prefix("test-backup").tempdir().unwrap();
+    witness.backup(backup_root.path()).unwrap();
+    assert_eq!(Witness::event_shards(backup_root.path()).unwrap(), Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_canonicalization() -> Result<(), Error> {
+    use keri_core::event_message::cesr_adapter::ParseError;
+
+    let mut witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+    let icp_digest = witness
+        .event_storage
+        .get_state(&id)
+        .unwrap()
+        .last_event_digest;
+    let ixn = controller.anchor(&[])?;
+    let stream = String::from_utf8(Message::Notice(Notice::Event(ixn)).to_cesr()?).unwrap();
+
+    // The same event with swapped fields. Size of event doesn't change, so
+    // it can be parsed.
+    let sn_field = r#""s":"1","#;
+    let prev_field = format!(r#""p":"{}","#, icp_digest);
+    let reordered = stream.replacen(
+        &format!("{}{}", sn_field, prev_field),
+        &format!("{}{}", prev_field, sn_field),
+        1,
+    );
+    assert_ne!(reordered, stream);
+
+    witness.strict_canonicalization = true;
+    let err = witness.parse_and_process_notices(reordered.as_bytes());
+    assert!(matches!(
+        err,
+        Err(Error::DeserializeError(ParseError::NonCanonical(ref mismatch)))
+            if mismatch.received.starts_with(r#""p""#) && mismatch.expected.starts_with(r#""s""#)
+    ));
+    assert_eq!(witness.event_storage.get_state(&id).unwrap().sn, 0);
+
+    witness.parse_and_process_notices(stream.as_bytes())?;
+    assert_eq!(witness.event_storage.get_state(&id).unwrap().sn, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_receipts_pruning() -> Result<(), Error> {
+    use std::time::Duration;
+
+    use crate::pruning::{PruningArchive, PruningConfig, PruningPolicy};
+
+    let mut witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let mut controller = setup_controller(&witness)?;
+    let id = controller.prefix().clone();
+    let rot = controller.rotate(None, None, None)?;
+    witness.process_notice(Notice::Event(rot))?;
+    assert!(witness.event_storage.get_nt_receipts(&id, 0)?.is_some());
+
+    // Identifiers that didn't opt in aren't pruned.
+    assert!(witness.prune().unwrap().is_empty());
+
+    // Rotation isn't old enough yet.
+    witness.pruning = PruningConfig {
+        policies: vec![PruningPolicy {
+            min_age: Some(Duration::from_secs(3600)),
+            ..PruningPolicy::new(id.clone())
+        }],
+    };
+    assert!(witness.prune().unwrap().is_empty());
+
+    witness.pruning.policies[0].min_age = None;
+    let reports = witness.prune().unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].before_sn, 1);
+    assert_eq!(reports[0].receipts, 1);
+    assert_eq!(reports[0].events, 0);
+    assert!(witness.event_storage.get_nt_receipts(&id, 0)?.is_none());
+    // Receipts of rotation and KEL are kept.
+    assert!(witness.event_storage.get_nt_receipts(&id, 1)?.is_some());
+    assert_eq!(witness.event_storage.get_state(&id).unwrap().sn, 1);
+
+    let records = PruningArchive::read(witness.archive.path()).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, id);
+
+    // Nothing more to prune.
+    assert!(witness.prune().unwrap().is_empty());
+
+    let restored = witness.import_archive(witness.archive.path()).unwrap();
+    assert_eq!(restored, 1);
+    assert!(witness.event_storage.get_nt_receipts(&id, 0)?.is_some());
+
+    // Events can be pruned too, if identifier allows it. Key state doesn't
+    // change.
+    let state = witness.event_storage.get_state(&id);
+    witness.pruning.policies[0].events = true;
+    let reports = witness.prune().unwrap();
+    assert_eq!(reports[0].receipts, 1);
+    assert_eq!(reports[0].events, 1);
+    assert!(witness.event_storage.get_event_at_sn(&id, 0).is_none());
+    assert!(witness.event_storage.get_event_at_sn(&id, 1).is_some());
+    assert_eq!(witness.event_storage.get_state(&id), state);
+
+    let records = PruningArchive::read(witness.archive.path()).unwrap();
+    assert_eq!(records.len(), 2);
+    witness.import_archive(witness.archive.path()).unwrap();
+    assert!(witness.event_storage.get_event_at_sn(&id, 0).is_some());
+    assert_eq!(witness.event_storage.get_state(&id), state);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_witness_scope() -> Result<(), Error> {
+    use actix_web::{test, web, App};
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Arc::new(
+            Witness::new(
+                Url::parse("http://example.com").unwrap(),
+                Arc::new(Signer::new()),
+                root.path(),
+                oobi_root.path(),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let app = test::init_service(
+        App::new().service(web::scope("/keri").service(crate::witness_scope(witness.clone()))),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/keri/introduce").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    // Admin endpoints are disabled without token.
+    let req = test::TestRequest::get()
+        .uri("/keri/admin/identifiers")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_client_error());
+
+    let req = test::TestRequest::get().uri("/introduce").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_correlation_id() -> Result<(), Error> {
+    use actix_web::{middleware::from_fn, test, App};
+    use keri_core::transport::correlation::CORRELATION_ID_HEADER;
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Arc::new(
+            Witness::new(
+                Url::parse("http://example.com").unwrap(),
+                Arc::new(Signer::new()),
+                root.path(),
+                oobi_root.path(),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(crate::correlate))
+            .service(crate::witness_scope(witness)),
+    )
+    .await;
+
+    // ID received from caller is returned.
+    let req = test::TestRequest::get()
+        .uri("/introduce")
+        .insert_header((CORRELATION_ID_HEADER, "req-1"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get(CORRELATION_ID_HEADER).unwrap(), "req-1");
+
+    // Without it, new one is generated.
+    let req = test::TestRequest::get().uri("/introduce").to_request();
+    let resp = test::call_service(&app, req).await;
+    let generated = resp.headers().get(CORRELATION_ID_HEADER).unwrap();
+    assert!(!generated.is_empty());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn test_conditional_kel_request() -> Result<(), Error> {
+    use actix_web::{http::header, test};
+    use keri_core::transport::conditional::kel_etag;
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Arc::new(
+            Witness::new(
+                Url::parse("http://example.com").unwrap(),
+                Arc::new(Signer::new()),
+                root.path(),
+                oobi_root.path(),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let controller = setup_controller(&witness)?;
+    let uri = format!("/oobis/{}", controller.prefix());
+    let app =
+        test::init_service(actix_web::App::new().service(crate::witness_scope(witness.clone())))
+            .await;
+
+    // Entity tag is digest of the last event.
+    let state = witness
+        .event_storage
+        .get_state(controller.prefix())
+        .unwrap();
+    let etag = kel_etag(&state.last_event_digest);
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+    assert!(!test::read_body(resp).await.is_empty());
+
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header((header::IF_NONE_MATCH, etag.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 304);
+    assert!(test::read_body(resp).await.is_empty());
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::HEAD)
+        .uri(&uri)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+    // New event changes entity tag.
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn))?;
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header((header::IF_NONE_MATCH, etag.as_str()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_ne!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+    Ok(())
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn test_axum_router() -> Result<(), Error> {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use crate::AdminToken;
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Arc::new(
+            Witness::new(
+                Url::parse("http://example.com").unwrap(),
+                Arc::new(Signer::new()),
+                root.path(),
+                oobi_root.path(),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let router = crate::axum_listener::witness_router(witness.clone());
+
+    let resp = router
+        .clone()
+        .oneshot(Request::get("/introduce").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    let resp = router
+        .clone()
+        .oneshot(Request::get("/info").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    // Admin endpoints are disabled without token.
+    let resp = router
+        .oneshot(
+            Request::get("/admin/identifiers")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+
+    let router = crate::axum_listener::witness_router_with_admin_token(
+        witness,
+        AdminToken(Some("secret".to_string())),
+    );
+    let admin_request = |token: &str| {
+        Request::get("/admin/identifiers")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let resp = router
+        .clone()
+        .oneshot(admin_request("wrong"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = router.oneshot(admin_request("secret")).await.unwrap();
+    assert!(resp.status().is_success());
+
+    Ok(())
+}
+
+#[test]
+fn test_tel_event_awaiting_anchor() -> Result<(), Error> {
+    use keri_core::actor::parse_event_stream;
+    use teliox::{
+        event::{manager_event::Config, verifiable_event::VerifiableEvent},
+        seal::EventSourceSeal,
+        tel::event_generator,
+    };
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+
+    // Issuer's inception and interaction event anchoring registry inception.
+    let issuer_kel = r#"{"v":"KERI10JSON00012b_","t":"icp","d":"EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI","i":"EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI","s":"0","kt":"1","k":["DHdoiqT1iac2HI6-HfCYcc01Piz2FTTPvZDFt6vADioD"],"nt":"1","n":["EH8IzIWeQFiUr3rr2dh8xAiW9Akwl6EooDt8iduQYyq_"],"bt":"0","b":[],"c":[],"a":[]}-AABAABvFFeXb9uW2G16o3C9xJZvY3a_utMPxd4NIUcGWRTqykMO1NzKwjsA_AQrOEwgO5jselWHREcK6vcAxRfv6-QC{"v":"KERI10JSON00013a_","t":"ixn","d":"EMOzEVoFjbkS3ZS5JtmJO4LeZ4gydbr8iXNrEQAt1OR2","i":"EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI","s":"1","p":"EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI","a":[{"i":"EF3TVac5quxrbLGLKAHF21laISjMgjYQAIg3OsTen969","s":"0","d":"ENIKpuUkjM-1K2Sv_TZwF_k8FTVkefAgy8sIpiFp0uWh"}]}-AABAACvrSS_EZUMKQ6Ax8FaB_Sf99O0y6MmfoRDBKMphVWWtuCOlFQm6N0XrTwtYxO3pO0AEZkJ1vzu52-RDK-w3YAN"#;
+    let kel = parse_event_stream(issuer_kel.as_bytes()).unwrap();
+    let (icp, ixn) = match (&kel[0], &kel[1]) {
+        (Message::Notice(icp), Message::Notice(ixn)) => (icp.clone(), ixn.clone()),
+        _ => unreachable!(),
+    };
+    witness.processor.process_notice(&icp)?;
+
+    let vcp = event_generator::make_inception_event(
+        "EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI"
+            .parse()
+            .unwrap(),
+        vec![Config::NoBackers],
+        0,
+        vec![],
+        None,
+        None,
+    )
+    .unwrap();
+    let registry_id = vcp.get_prefix();
+    let seal = EventSourceSeal {
+        sn: 1,
+        digest: "EMOzEVoFjbkS3ZS5JtmJO4LeZ4gydbr8iXNrEQAt1OR2"
+            .parse()
+            .unwrap(),
+    };
+
+    // Anchoring ixn wasn't received yet, so registry inception is escrowed.
+    witness
+        .tel
+        .processor
+        .process(VerifiableEvent::new(vcp, seal.into()))
+        .unwrap();
+    assert!(witness
+        .tel
+        .get_management_tel_state(&registry_id)
+        .unwrap()
+        .is_none());
+
+    // It's accepted when ixn arrives.
+    witness.processor.process_notice(&ixn)?;
+    let state = witness
+        .tel
+        .get_management_tel_state(&registry_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.sn, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_takedown() -> Result<(), ActorError> {
+    use keri_core::event_message::signature::Signature;
+
+    use crate::takedown::{SignedTakedown, Takedown, TakedownLog};
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let mut witness = {
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let operator = Signer::new();
+    let sign = |signer: &Signer, takedown: &Takedown| -> Result<SignedTakedown, ActorError> {
+        let signature = signer.sign(takedown.encode().unwrap())?;
+        Ok(SignedTakedown {
+            takedown: takedown.clone(),
+            signature: Signature::NonTransferable(Nontransferable::Couplet(vec![(
+                BasicPrefix::Ed25519NT(signer.public_key()),
+                SelfSigningPrefix::Ed25519Sha512(signature),
+            )])),
+        })
+    };
+
+    let controller = setup_controller(&witness)?;
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn))?;
+    assert_eq!(
+        witness
+            .get_mailbox_messages(controller.prefix())?
+            .receipt
+            .len(),
+        2
+    );
+
+    let takedown = Takedown {
+        ids: vec![controller.prefix().clone()],
+        reason: "abuse".to_string(),
+    };
+    // Takedowns are rejected if operator isn't configured.
+    assert!(matches!(
+        witness.process_takedown(sign(&operator, &takedown)?),
+        Err(ActorError::Unauthorized)
+    ));
+
+    witness.takedown_operator = Some(IdentifierPrefix::Basic(BasicPrefix::Ed25519NT(
+        operator.public_key(),
+    )));
+    // Takedown signed by someone else is rejected.
+    assert!(matches!(
+        witness.process_takedown(sign(&Signer::new(), &takedown)?),
+        Err(ActorError::Unauthorized)
+    ));
+    // Signature needs to match takedown.
+    let mut tampered = sign(&operator, &takedown)?;
+    tampered.takedown.reason = "other".to_string();
+    assert!(witness.process_takedown(tampered).is_err());
+    assert!(witness
+        .process_query(controller.query_mailbox(&witness.prefix))
+        .is_ok());
+
+    let signed = sign(&operator, &takedown)?;
+    witness.process_takedown(signed.clone())?;
+
+    // Mailbox content is removed and identifier isn't served anymore.
+    assert!(witness
+        .get_mailbox_messages(controller.prefix())?
+        .receipt
+        .is_empty());
+    assert!(matches!(
+        witness.process_query(controller.query_mailbox(&witness.prefix)),
+        Err(ActorError::TakenDown { id }) if &id == controller.prefix()
+    ));
+    let ixn = controller.anchor(&[])?;
+    assert!(witness.process_notice(Notice::Event(ixn)).is_err());
+    // Messages aren't forwarded to its mailbox.
+    let sender = setup_controller(&witness)?;
+    let exn = sender.create_forward_message(
+        controller.prefix(),
+        &sender.anchor(&[])?,
+        ForwardTopic::Multisig,
+    )?;
+    assert!(matches!(
+        witness.process_exchange(exn),
+        Err(ActorError::TakenDown { id }) if &id == controller.prefix()
+    ));
+
+    // Signed takedown is kept in log for audit.
+    assert_eq!(witness.takedowns.records(), vec![signed.clone()]);
+    let log = TakedownLog::open(&root.path().join("takedowns")).unwrap();
+    assert_eq!(log.records(), vec![signed]);
+    assert!(log.is_taken_down(controller.prefix()));
+
+    Ok(())
+}
+
+#[test]
+fn test_expired_exchange() -> Result<(), ActorError> {
+    use keri_core::mailbox::exchange::{Exchange, ExchangeMetadata};
+
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let sender = setup_controller(&witness)?;
+    let exn = |expiry: &str| -> Result<_, ActorError> {
+        let mut exn = sender.create_forward_message(
+            controller.prefix(),
+            &sender.anchor(&[])?,
+            ForwardTopic::Multisig,
+        )?;
+        let Exchange::Fwd { args, .. } = &mut exn.exchange_message.data.data;
+        args.metadata = Some(ExchangeMetadata::default().with_expiry(expiry.parse().unwrap()));
+        Ok(exn)
+    };
+
+    // Expired requests aren't stored in recipient's mailbox.
+    assert!(matches!(
+        witness.process_exchange(exn("2022-10-26T09:53:04.117732+00:00")?),
+        Err(ActorError::ExchangeExpired)
+    ));
+    assert!(witness
+        .get_mailbox_messages(controller.prefix())?
+        .multisig
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_mailbox_quota() -> Result<(), ActorError> {
+    use std::time::Duration;
+
+    use keri_core::{
+        actor::prelude::HashFunction, event::sections::seal::DigestSeal,
+        query::mailbox::QueryArgsMbx, query::mailbox::QueryTopics,
+    };
+
+    use crate::mailbox_quota::MailboxQuota;
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let mut witness = {
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    witness.mailbox_quota = MailboxQuota {
+        max_messages: Some(2),
+        max_bytes: None,
+        expiry: None,
+    };
+
+    let sender = setup_controller(&witness)?;
+    let recipient = setup_controller(&witness)?;
+    // Every forwarded event is different, so it isn't deduplicated.
+    let forward = |data: &str| {
+        let digest = HashFunction::from(HashFunctionCode::Blake3_256).derive(data.as_bytes());
+        let ixn = sender.anchor(&[Seal::Digest(DigestSeal::new(digest))])?;
+        sender.create_forward_message(recipient.prefix(), &ixn, ForwardTopic::Delegate)
+    };
+
+    witness.process_exchange(forward("first")?)?;
+    witness.process_exchange(forward("second")?)?;
+    assert!(matches!(
+        witness.process_exchange(forward("third")?),
+        Err(ActorError::MailboxQuotaExceeded { id }) if &id == recipient.prefix()
+    ));
+    assert_eq!(
+        witness
+            .get_mailbox_messages(recipient.prefix())?
+            .delegate
+            .len(),
+        2
+    );
+
+    // Expired messages are removed, so new ones fit in quota.
+    witness.mailbox_quota.expiry = Some(Duration::ZERO);
+    witness.process_exchange(forward("third")?)?;
+    let mailbox = witness.get_mailbox_messages(recipient.prefix())?;
+    assert_eq!(mailbox.delegate.len(), 1);
+
+    // Remaining message keeps its topic index.
+    let query_from = |delegate: usize| {
+        witness.event_storage.get_mailbox_messages(&QueryArgsMbx {
+            pre: recipient.prefix().clone(),
+            i: recipient.prefix().clone(),
+            src: IdentifierPrefix::Basic(witness.prefix.clone()),
+            topics: QueryTopics {
+                delegate,
+                ..Default::default()
+            },
+            resume: false,
+            limit: None,
+        })
+    };
+    assert_eq!(query_from(2)?.delegate, mailbox.delegate);
+    assert!(query_from(3)?.delegate.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_query_replay() -> Result<(), ActorError> {
+    use std::time::Duration;
+
+    use keri_core::query::replay::QueryReplayGuard;
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let mut witness = {
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    witness.query_replay_guard = Some(QueryReplayGuard::new(Duration::from_secs(60)));
+    let controller = setup_controller(&witness)?;
+
+    let query = controller.query_mailbox(&witness.prefix);
+    witness.process_query(query.clone())?;
+    assert!(matches!(
+        witness.process_query(query),
+        Err(ActorError::QueryError(SignedQueryError::Replayed { .. }))
+    ));
+    // New query, with fresh timestamp, is accepted.
+    witness.process_query(controller.query_mailbox(&witness.prefix))?;
+
+    Ok(())
+}
+
+#[test]
+fn test_metrics() -> Result<(), ActorError> {
+    let witness = {
+        let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+    let ixn = controller.anchor(&[])?;
+    witness.process_notice(Notice::Event(ixn))?;
+    witness.process_query(controller.query_mailbox(&witness.prefix))?;
+
+    let metrics = witness.gather_metrics().unwrap();
+    assert!(metrics.contains("witness_notices_processed_total 2"));
+    assert!(metrics.contains("witness_receipts_issued_total 2"));
+    assert!(metrics.contains("witness_queries_served_total{response=\"mbx\"} 1"));
+    assert!(metrics.contains("witness_transport_errors_total 0"));
+    assert!(metrics.contains("witness_escrow_size{escrow=\"out_of_order\"} 0"));
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_log() -> Result<(), ActorError> {
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let witness = {
+        let oobi_root = Builder::new().prefix("test-oobi").tempdir().unwrap();
+        Witness::new(
+            Url::parse("http://example.com").unwrap(),
+            Arc::new(Signer::new()),
+            root.path(),
+            oobi_root.path(),
+            WitnessEscrowConfig::default(),
+        )
+        .unwrap()
+    };
+    let controller = setup_controller(&witness)?;
+
+    // Log is disabled by default.
+    let query = Message::Op(Op::Query(controller.query_mailbox(&witness.prefix)))
+        .to_cesr()
+        .unwrap();
+    witness.parse_and_process_queries(&query)?;
+    assert!(!witness.stream_log.path().exists());
+
+    witness.stream_log.set_enabled(true);
+    let ixn = controller.anchor(&[])?;
+    let sn = ixn.event_message.data.get_sn();
+    let stream = Message::Notice(Notice::Event(ixn)).to_cesr().unwrap();
+    witness.parse_and_process_notices(&stream)?;
+
+    let log = std::fs::read_to_string(witness.stream_log.path()).unwrap();
+    assert!(log.contains(&format!(
+        "# ixn {} sn {}, 1 signatures",
+        controller.prefix(),
+        sn
+    )));
+    assert!(log.contains(std::str::from_utf8(&stream).unwrap()));
+
+    Ok(())
+}
+
+#[cfg(feature = "tcp")]
+#[tokio::test]
+async fn test_tcp_listener() -> Result<(), ActorError> {
+    use std::time::Duration;
+
+    use keri_core::{
+        oobi::{LocationScheme, Scheme},
+        transport::{
+            default::{DefaultTransport, TransportConfig},
+            tcp::{self, TcpRequest},
+            Transport,
+        },
+    };
+
+    // Find free port.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let url = Url::parse(&format!("tcp://127.0.0.1:{}/", port)).unwrap();
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let listener =
+        crate::WitnessListener::setup(url.clone(), root.path(), None, Default::default())
+            .unwrap()
+            .with_admin_token("secret".to_string());
+    let witness_id = IdentifierPrefix::Basic(listener.get_prefix());
+    tokio::spawn(listener.listen_tcp(("127.0.0.1", port)));
+
+    // Listener is bound when spawned task is polled, so first connection
+    // attempt can be refused.
+    let config = TransportConfig {
+        retries: 5,
+        backoff: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let transport = DefaultTransport::<ActorError>::new_with_config(config.clone());
+    let loc = LocationScheme::new(witness_id.clone(), Scheme::Tcp, url.clone());
+
+    // Witness advertises its TCP location.
+    let ops = transport
+        .request_loc_scheme(loc.clone())
+        .await
+        .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+    assert!(matches!(
+        &ops[..],
+        [Op::Reply(rpy)] if matches!(
+            rpy.reply.get_route(),
+            ReplyRoute::LocScheme(ref loc) if loc.scheme == Scheme::Tcp && loc.eid == witness_id
+        )
+    ));
+
+    // Remote errors are returned like over HTTP.
+    let unknown: IdentifierPrefix = "EETk5xW-rl2TgHTTXr8m5kGXiC30m3gMgsYcBAjOE9eI"
+        .parse()
+        .unwrap();
+    let err = transport
+        .request_end_role(loc, unknown.clone(), Role::Watcher, unknown)
+        .await;
+    assert!(matches!(
+        err,
+        Err(keri_core::transport::TransportError::RemoteError(_))
+    ));
+
+    let resp =
+        tcp::request::<ActorError>(&url, &TcpRequest::new("GET", "nothing", vec![]), &config)
+            .await
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+    assert_eq!(resp.status, 404);
+
+    // Admin endpoints require token, like over HTTP.
+    let resp = tcp::request::<ActorError>(
+        &url,
+        &TcpRequest::new("GET", "admin/identifiers", vec![]),
+        &config,
+    )
+    .await
+    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+    assert_eq!(resp.status, 401);
+    let resp = tcp::request::<ActorError>(
+        &url,
+        &TcpRequest::new("GET", "admin/identifiers", vec![])
+            .with_header("Authorization", "Bearer secret"),
+        &config,
+    )
+    .await
+    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+    assert!(resp.is_success());
+
+    Ok(())
+}
+
+#[cfg(feature = "tcp")]
+#[tokio::test]
+async fn test_response_envelope() -> Result<(), ActorError> {
+    use std::time::Duration;
+
+    use actor_server::{tcp_listener::handle_request, AdminToken};
+    use keri_core::{
+        actor::keri_actor::KeriActor,
+        oobi::{LocationScheme, Scheme},
+        query::{envelope::EnvelopeError, mailbox::QueryTopics},
+        transport::{
+            default::DefaultTransport,
+            tcp::{serve, TcpRequest, TcpResponse},
+            Transport, TransportError,
+        },
+    };
+    use tokio::net::TcpListener;
+
+    /// Serves witness endpoints on free port. Requests and responses are
+    /// passed through `tamper_request` and `tamper_response`, like through
+    /// intermediary.
+    async fn spawn_server(
+        witness: Arc<dyn KeriActor>,
+        tamper_request: impl Fn(TcpRequest) -> TcpRequest + Clone + Send + Sync + 'static,
+        tamper_response: fn(TcpResponse) -> TcpResponse,
+    ) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("tcp://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(serve(listener, Duration::from_secs(60), move |req| {
+            let (witness, tamper_request) = (witness.clone(), tamper_request.clone());
+            async move {
+                let resp = handle_request(&*witness, &AdminToken(None), tamper_request(req)).await;
+                tamper_response(resp)
+            }
+        }));
+        url
+    }
+
+    let root = Builder::new().prefix("test-witness").tempdir().unwrap();
+    let url = Url::parse("tcp://127.0.0.1:0/").unwrap();
+    let listener =
+        crate::WitnessListener::setup(url, root.path(), None, Default::default()).unwrap();
+    let witness = listener.witness_data.clone();
+    let controller = setup_controller(&witness)?;
+    let witness_id = IdentifierPrefix::Basic(witness.prefix.clone());
+
+    let transport = DefaultTransport::<ActorError>::new().with_envelope_verification();
+    let query_topics = |receipt| QueryTopics {
+        receipt,
+        ..Default::default()
+    };
+    let first_query = controller.query_mailbox_from(&witness.prefix, query_topics(0), false);
+    let second_query = controller.query_mailbox_from(&witness.prefix, query_topics(1), false);
+
+    // Response returned directly by witness is accepted.
+    let url = spawn_server(witness.clone(), |req| req, |resp| resp).await;
+    let resp = transport
+        .send_query(
+            LocationScheme::new(witness_id.clone(), Scheme::Tcp, url),
+            first_query.clone(),
+        )
+        .await
+        .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+    assert!(matches!(resp, PossibleResponse::Mbx(_)));
+
+    // Intermediary truncates response.
+    let url = spawn_server(
+        witness.clone(),
+        |req| req,
+        |mut resp| {
+            resp.body.truncate(resp.body.len() / 2);
+            resp
+        },
+    )
+    .await;
+    let resp = transport
+        .send_query(
+            LocationScheme::new(witness_id.clone(), Scheme::Tcp, url),
+            second_query.clone(),
+        )
+        .await;
+    assert!(matches!(
+        resp,
+        Err(TransportError::InvalidEnvelope(e)) if e == EnvelopeError::DigestMismatch.to_string()
+    ));
+
+    // Intermediary returns response to other query.
+    let first_query = Message::from(first_query).to_cesr().unwrap();
+    let url = spawn_server(
+        witness.clone(),
+        move |req| TcpRequest {
+            body: first_query.clone(),
+            ..req
+        },
+        |resp| resp,
+    )
+    .await;
+    let resp = transport
+        .send_query(
+            LocationScheme::new(witness_id.clone(), Scheme::Tcp, url),
+            second_query,
+        )
+        .await;
+    assert!(matches!(
+        resp,
+        Err(TransportError::InvalidEnvelope(e)) if e == EnvelopeError::QueryMismatch.to_string()
+    ));
+
+    Ok(())
+}
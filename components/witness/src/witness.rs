@@ -1,16 +1,30 @@
 use std::{
-    fs::File,
+    future::Future,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
 };
 
+use itertools::Itertools;
 use keri_core::{
     actor::{
-        error::ActorError, parse_exchange_stream, parse_notice_stream, parse_query_stream,
-        parse_reply_stream, prelude::*, process_reply, process_signed_exn, process_signed_query,
+        error::ActorError,
+        keri_actor::{ActorRequest, ActorResponse, KeriActor},
+        parse_notice_stream, parse_stream,
+        prelude::*,
+        process_guarded_query, process_mailbox_ack, process_reply, process_signed_exn,
+        process_signed_query,
         simple_controller::PossibleResponse,
+        stream_log::StreamLog,
+    },
+    database::{
+        redb::{
+            sharded::{RedbStorage, ShardedRedbDatabase},
+            RedbError,
+        },
+        sled::DbError,
+        EventDatabase,
     },
-    database::{redb::RedbDatabase, sled::DbError, EventDatabase},
     error::Error,
     event::KeyEvent,
     event_message::{
@@ -19,32 +33,72 @@ use keri_core::{
         signature::Nontransferable,
         signed_event_message::{Notice, SignedNontransferableReceipt},
     },
-    mailbox::MailboxResponse,
-    oobi::{LocationScheme, OobiManager},
-    prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
+    mailbox::{exchange::Exchange, MailboxResponse, DEFAULT_MAILBOX_MAX_SIZE},
+    oobi::{LocationScheme, Oobi, OobiManager, Role, Scheme},
+    prefix::{BasicPrefix, CesrPrimitive, IdentifierPrefix, SelfSigningPrefix},
     processor::notification::{Notification, NotificationBus, Notifier},
     query::{
+        envelope::ResponseEnvelope,
         mailbox::{QueryArgsMbx, QueryTopics},
+        query_event::{LogsQueryArgs, QueryEvent, QueryRoute, SignedKelQuery, SignedQueryMessage},
+        replay::QueryReplayGuard,
         reply_event::{ReplyEvent, ReplyRoute, SignedReply},
         ReplyType,
     },
     signer::Signer,
+    transport::{default::DefaultTransport, Transport, TransportError},
 };
 use serde::{Deserialize, Serialize};
 use teliox::{
     event::{parse_tel_query_stream, verifiable_event::VerifiableEvent},
-    processor::{escrow::default_escrow_bus, storage::TelEventStorage, TelReplyType},
+    processor::{
+        escrow::{escrow_bus, TelEscrowConfig},
+        storage::TelEventStorage,
+        TelReplyType,
+    },
     tel::Tel,
 };
 use thiserror::Error;
 use url::Url;
 
-use crate::witness_processor::{WitnessEscrowConfig, WitnessProcessor};
+use crate::{
+    key_rotation::{KeyRotationLog, SignedKeyRotation},
+    mailbox_quota::MailboxQuota,
+    metrics::WitnessMetrics,
+    outbox::{self, OutboundDelivery, Outbox, OutboxConfig, OutboxReport},
+    peer_receipts::{PeerReceiptsConfig, PendingReceipts},
+    pruning::{ArchiveRecord, PruningArchive, PruningConfig, PruningReport},
+    takedown::{SignedTakedown, TakedownLog},
+    witness_listener::http_handlers,
+    witness_processor::{WitnessEscrowConfig, WitnessProcessor},
+};
+
+/// Name of events database file in witness events directory.
+const EVENTS_DATABASE: &str = "events_database";
+/// Name of directory with events database shards. It's used instead of
+/// [`EVENTS_DATABASE`] file if it exists, see [`Witness::reshard`].
+const EVENTS_SHARDS: &str = "events_shards";
+
+/// Schemes of peer locations tried by default, in order, when peer
+/// advertises more than one location.
+pub const DEFAULT_PEER_SCHEME_PRIORITY: [Scheme; 3] = [Scheme::Https, Scheme::Http, Scheme::Tcp];
+
+/// Checks if request failed because peer couldn't be reached at given
+/// location, so other location can be tried.
+fn is_unreachable(err: &TransportError) -> bool {
+    matches!(
+        err,
+        TransportError::NetworkError(_)
+            | TransportError::Timeout(_)
+            | TransportError::ConnectionRefused(_)
+    )
+}
 
 pub struct WitnessReceiptGenerator {
-    pub prefix: BasicPrefix,
-    pub signer: Arc<Signer>,
-    pub storage: EventStorage<RedbDatabase>,
+    /// Key used to sign receipts. It's replaced when witness rotates its key.
+    signer: RwLock<Arc<Signer>>,
+    pub storage: EventStorage<RedbStorage>,
+    metrics: Option<Arc<WitnessMetrics>>,
 }
 
 impl Notifier for WitnessReceiptGenerator {
@@ -52,11 +106,12 @@ impl Notifier for WitnessReceiptGenerator {
         match notification {
             Notification::KeyEventAdded(event) => {
                 let non_trans_receipt =
-                    self.respond_to_key_event(&event.event_message, self.signer.clone())?;
+                    self.respond_to_key_event(&event.event_message, self.signer()?)?;
                 let prefix = &event.event_message.data.get_prefix(); //&non_trans_receipt.body.event.prefix.clone();
                 self.storage
                     .events_db
                     .add_receipt_nt(non_trans_receipt.clone(), prefix)?;
+                self.count_receipt();
                 bus.notify(&Notification::ReceiptAccepted)?;
                 self.storage.add_mailbox_receipt(non_trans_receipt)?;
                 Ok(())
@@ -67,11 +122,12 @@ impl Notifier for WitnessReceiptGenerator {
                     .add_kel_finalized_event(prt.clone(), &prt.event_message.data.get_prefix())?;
                 bus.notify(&Notification::KeyEventAdded(prt.clone()))?;
                 let non_trans_receipt =
-                    self.respond_to_key_event(&prt.event_message, self.signer.clone())?;
+                    self.respond_to_key_event(&prt.event_message, self.signer()?)?;
                 let prefix = &non_trans_receipt.body.prefix.clone();
                 self.storage
                     .events_db
                     .add_receipt_nt(non_trans_receipt.clone(), prefix)?;
+                self.count_receipt();
                 bus.notify(&Notification::ReceiptAccepted)?;
                 self.storage.add_mailbox_receipt(non_trans_receipt)
             }
@@ -84,17 +140,48 @@ impl WitnessReceiptGenerator {
     pub fn new(
         signer: Arc<Signer>,
         db: Arc<SledEventDatabase>,
-        events_db: Arc<RedbDatabase>,
+        events_db: Arc<RedbStorage>,
     ) -> Self {
         let storage = EventStorage::new(events_db.clone(), db.clone());
-        let prefix = BasicPrefix::Ed25519NT(signer.public_key());
         Self {
-            prefix,
-            signer,
+            signer: RwLock::new(signer),
             storage,
+            metrics: None,
         }
     }
 
+    /// Counts issued receipts in `metrics`.
+    pub fn with_metrics(self, metrics: Arc<WitnessMetrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    fn count_receipt(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.receipts_issued.inc();
+        }
+    }
+
+    pub fn prefix(&self) -> Result<BasicPrefix, Error> {
+        Ok(BasicPrefix::Ed25519NT(self.signer()?.public_key()))
+    }
+
+    /// Replaces key used to sign receipts of events processed from now on.
+    pub fn rotate(&self, signer: Arc<Signer>) -> Result<(), Error> {
+        *self.signer.write().map_err(|_e| Error::MutexPoisoned)? = signer;
+        Ok(())
+    }
+
+    fn signer(&self) -> Result<Arc<Signer>, Error> {
+        Ok(self
+            .signer
+            .read()
+            .map_err(|_e| Error::MutexPoisoned)?
+            .clone())
+    }
+
     fn respond_to_key_event(
         &self,
         event_message: &KeriEvent<KeyEvent>,
@@ -108,7 +195,8 @@ impl WitnessReceiptGenerator {
             .build()?;
 
         let signature = SelfSigningPrefix::Ed25519Sha512(signature);
-        let nontrans = Nontransferable::Couplet(vec![(self.prefix.clone(), signature)]);
+        let prefix = BasicPrefix::Ed25519NT(signer.public_key());
+        let nontrans = Nontransferable::Couplet(vec![(prefix, signature)]);
 
         let signed_rcp = SignedNontransferableReceipt::new(&rcp, vec![nontrans]);
 
@@ -127,19 +215,96 @@ pub enum WitnessError {
     #[error(transparent)]
     DatabaseError(#[from] DbError),
 
+    #[error(transparent)]
+    RedbError(#[from] RedbError),
+
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
     #[error("Signing error")]
     SigningError,
+
+    #[error("Metrics error: {0}")]
+    MetricsError(String),
+
+    #[error("Witness key {0} was already rotated to other key")]
+    KeyAlreadyRotated(String),
+}
+
+/// Outcome of processing event submitted to witness. Submitting the same
+/// event again gives the same outcome, and doesn't store it twice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoticeOutcome {
+    pub id: IdentifierPrefix,
+    pub sn: u64,
+    pub digest: SelfAddressingIdentifier,
+    /// Witness receipt of the event in CESR, if event was accepted into KEL.
+    /// Escrowed events have no receipt yet.
+    pub receipt: Option<String>,
+}
+
+/// Summary of identifier stored by witness.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentifierCensus {
+    pub id: IdentifierPrefix,
+    /// Sequence number of the latest accepted event.
+    pub sn: u64,
+    /// Number of witnesses designated at the latest event.
+    pub witnesses: usize,
+    /// Number of witness receipts stored for the latest event.
+    pub receipts: usize,
+    /// Size of KEL with attached receipts, in bytes.
+    pub storage_size: usize,
 }
 
 pub struct Witness {
     pub address: Url,
     pub prefix: BasicPrefix,
     pub processor: WitnessProcessor,
-    pub event_storage: Arc<EventStorage<RedbDatabase>>,
+    pub event_storage: Arc<EventStorage<RedbStorage>>,
     pub oobi_manager: OobiManager,
     pub signer: Arc<Signer>,
     pub receipt_generator: Arc<WitnessReceiptGenerator>,
-    pub tel: Arc<Tel>,
+    pub tel: Arc<Tel<RedbStorage>>,
+    /// Used for communication with other witnesses.
+    pub transport: Box<dyn Transport + Send + Sync>,
+    /// Maximal size of mailbox response in bytes. Rest of messages can be
+    /// retrieved with next query.
+    pub mailbox_max_size: usize,
+    /// Limits of messages forwarded to single recipient, see
+    /// [`Witness::process_exchange`].
+    pub mailbox_quota: MailboxQuota,
+    /// Rejects replayed queries and queries with timestamp out of allowed
+    /// skew. Queries aren't checked if it's not set.
+    pub query_replay_guard: Option<QueryReplayGuard>,
+    /// Identifier allowed to sign takedowns. Takedowns are rejected if it's
+    /// not set.
+    pub takedown_operator: Option<IdentifierPrefix>,
+    pub takedowns: TakedownLog,
+    /// Rotations of witness key, see [`Witness::rotate_key`].
+    pub key_rotations: KeyRotationLog,
+    pub metrics: Arc<WitnessMetrics>,
+    /// Debug log of processed streams, disabled by default.
+    pub stream_log: StreamLog,
+    /// Reject events, which bytes differ from their canonical
+    /// serialization. See
+    /// [`check_canonical`](keri_core::event_message::cesr_adapter::check_canonical).
+    pub strict_canonicalization: bool,
+    /// Identifiers whose old receipts and events can be pruned, see
+    /// [`Witness::prune`].
+    pub pruning: PruningConfig,
+    /// Archive of pruned receipts and events.
+    pub archive: PruningArchive,
+    /// Messages waiting to be delivered to peers, see
+    /// [`Witness::process_outbox`].
+    pub outbox: Outbox,
+    /// Accepted events, which receipts are requested from other witnesses,
+    /// see [`Witness::request_pending_receipts`].
+    pub pending_receipts: Arc<PendingReceipts>,
+    pub peer_receipts: PeerReceiptsConfig,
+    /// Order in which schemes of peer locations are tried. Locations with
+    /// schemes missing here aren't used.
+    pub scheme_priority: Vec<Scheme>,
 }
 
 impl Witness {
@@ -155,29 +320,43 @@ impl Witness {
         events_path.push(event_path);
         let mut escrow_path = events_path.clone();
         let mut tel_path = events_path.clone();
-        let mut events_database_path = events_path.clone();
+        let takedowns_path = events_path.join("takedowns");
+        let key_rotations_path = events_path.join("key_rotations");
+        let stream_log_path = events_path.join("streams.log");
+        let archive_path = events_path.join("pruned_receipts");
+        let outbox_path = events_path.join("outbox");
 
         events_path.push("events");
         escrow_path.push("escrow");
 
         let prefix = BasicPrefix::Ed25519NT(signer.public_key());
-        let db = Arc::new(SledEventDatabase::new(events_path.as_path())?);
-
-        events_database_path.push("events_database");
-        let _file = File::create(&events_database_path).unwrap();
+        let clock = escrow_config.clock.clone();
+        let db = Arc::new(SledEventDatabase::new(events_path.as_path())?.with_clock(clock.clone()));
 
-        let events_db =
-            Arc::new(RedbDatabase::new(&events_database_path).map_err(|_| Error::DbError)?);
+        let events_db = Arc::new(
+            RedbStorage::open(
+                &event_path.join(EVENTS_DATABASE),
+                &event_path.join(EVENTS_SHARDS),
+            )
+            .map_err(|_| Error::DbError)?
+            .with_clock(clock.clone()),
+        );
         let escrow_db = Arc::new(EscrowDb::new(escrow_path.as_path())?);
+        let tel_escrow_config = TelEscrowConfig {
+            missing_issuer_timeout: escrow_config.tel_missing_anchor_timeout,
+            ..Default::default()
+        };
         let mut witness_processor =
             WitnessProcessor::new(events_db.clone(), db.clone(), escrow_db, escrow_config);
-        let event_storage = Arc::new(EventStorage::new(events_db.clone(), db.clone()));
+        let event_storage =
+            Arc::new(EventStorage::new(events_db.clone(), db.clone()).with_clock(clock.clone()));
 
-        let receipt_generator = Arc::new(WitnessReceiptGenerator::new(
-            signer.clone(),
-            db,
-            events_db.clone(),
-        ));
+        let metrics =
+            Arc::new(WitnessMetrics::new().map_err(|e| WitnessError::MetricsError(e.to_string()))?);
+        let receipt_generator = Arc::new(
+            WitnessReceiptGenerator::new(signer.clone(), db, events_db.clone())
+                .with_metrics(metrics.clone()),
+        );
         witness_processor.register_observer(
             receipt_generator.clone(),
             &[
@@ -186,6 +365,10 @@ impl Witness {
             ],
         )?;
 
+        let pending_receipts = Arc::new(PendingReceipts::default());
+        witness_processor
+            .register_observer(pending_receipts.clone(), &[JustNotification::KeyEventAdded])?;
+
         // Initiate tel and it's escrows
         let tel_events_db = {
             tel_path.push("tel");
@@ -200,12 +383,16 @@ impl Witness {
             Arc::new(EscrowDb::new(&tel_path)?)
         };
         let tel_storage = Arc::new(TelEventStorage::new(tel_events_db));
-        let (tel_bus, _missing_issuer, _out_of_order, _missing_registy) = default_escrow_bus(
+        let (tel_bus, missing_issuer, _out_of_order, _missing_registy) = escrow_bus(
             tel_storage.clone(),
             event_storage.clone(),
             tel_escrow_db.clone(),
+            tel_escrow_config,
         )
         .unwrap();
+        // TEL events which anchor wasn't received yet are escrowed and
+        // accepted when anchoring event is added to KEL.
+        witness_processor.register_observer(missing_issuer, &[JustNotification::KeyEventAdded])?;
 
         let tel = Arc::new(Tel::new(
             tel_storage.clone(),
@@ -220,11 +407,37 @@ impl Witness {
             signer,
             event_storage,
             receipt_generator,
-            oobi_manager: OobiManager::new(oobi_path),
+            oobi_manager: OobiManager::new(oobi_path).with_clock(clock),
             tel,
+            transport: Box::new(DefaultTransport::new()),
+            mailbox_max_size: DEFAULT_MAILBOX_MAX_SIZE,
+            mailbox_quota: MailboxQuota::default(),
+            query_replay_guard: None,
+            takedown_operator: None,
+            takedowns: TakedownLog::open(&takedowns_path)?,
+            key_rotations: KeyRotationLog::open(&key_rotations_path)?,
+            metrics,
+            stream_log: StreamLog::new(&stream_log_path),
+            strict_canonicalization: false,
+            pruning: PruningConfig::default(),
+            archive: PruningArchive::new(&archive_path),
+            outbox: Outbox::open(&outbox_path, OutboxConfig::default())?,
+            pending_receipts,
+            peer_receipts: PeerReceiptsConfig::default(),
+            scheme_priority: DEFAULT_PEER_SCHEME_PRIORITY.to_vec(),
         })
     }
 
+    /// Sets how far in the future timestamps of accepted replies and key
+    /// state notices can be.
+    pub fn with_max_clock_drift(self, max_clock_drift: Duration) -> Self {
+        Self {
+            processor: self.processor.with_max_clock_drift(max_clock_drift),
+            oobi_manager: self.oobi_manager.with_max_clock_drift(max_clock_drift),
+            ..self
+        }
+    }
+
     pub fn setup(
         public_address: url::Url,
         event_db_path: &Path,
@@ -237,6 +450,24 @@ impl Witness {
                 .map(|key| Signer::new_with_seed(&key.parse()?))
                 .unwrap_or_else(|| Ok(Signer::new()))?,
         );
+        Self::setup_with_signer(
+            public_address,
+            event_db_path,
+            oobi_db_path,
+            signer,
+            escrow_config,
+        )
+    }
+
+    /// Like [`Witness::setup`], but signs with provided signer, e.g. one
+    /// backed by [`Keystore`](keri_core::signer::Keystore).
+    pub fn setup_with_signer(
+        public_address: url::Url,
+        event_db_path: &Path,
+        oobi_db_path: &Path,
+        signer: Arc<Signer>,
+        escrow_config: WitnessEscrowConfig,
+    ) -> Result<Self, WitnessError> {
         let prefix = BasicPrefix::Ed25519NT(signer.public_key());
         // construct witness loc scheme oobi
         let loc_scheme = LocationScheme::new(
@@ -269,6 +500,54 @@ impl Witness {
         Ok(witness)
     }
 
+    /// Rotates witness to new nontransferable key. Witness gets new prefix,
+    /// signs its location scheme with new key and receipts events processed
+    /// from now on with it. Rotation record signed by both keys, designating
+    /// new prefix as successor of previous one, is saved in
+    /// [`Witness::key_rotations`], so it can be found by previous prefix
+    /// after restart. Rotating again to the same key doesn't add a record.
+    /// Receipts made before rotation stay in database and are still
+    /// verifiable with previous prefix. Controllers need to rotate their
+    /// witness lists to use new prefix.
+    pub fn rotate_key(&mut self, signer: Arc<Signer>) -> Result<(), WitnessError> {
+        let prefix = BasicPrefix::Ed25519NT(signer.public_key());
+        match self.key_rotations.successor(&self.prefix) {
+            Some(record) if record.rotation.successor == prefix => (),
+            Some(_) => return Err(WitnessError::KeyAlreadyRotated(self.prefix.to_str())),
+            None => self
+                .key_rotations
+                .append(SignedKeyRotation::new(&self.signer, &signer)?)?,
+        };
+        let loc_scheme = LocationScheme::new(
+            IdentifierPrefix::Basic(prefix.clone()),
+            self.address.scheme().parse().unwrap(),
+            self.address.clone(),
+        );
+        let loc_reply = Self::sign_reply(ReplyRoute::LocScheme(loc_scheme), &signer)?;
+        self.oobi_manager.save_oobi(&loc_reply)?;
+
+        self.receipt_generator.rotate(signer.clone())?;
+        self.prefix = prefix;
+        self.signer = signer;
+        Ok(())
+    }
+
+    fn sign_reply(route: ReplyRoute, signer: &Signer) -> Result<SignedReply, WitnessError> {
+        let reply = ReplyEvent::new_reply(
+            route,
+            HashFunctionCode::Blake3_256,
+            SerializationFormats::JSON,
+        );
+        let signature = signer
+            .sign(reply.encode()?)
+            .map_err(|_e| WitnessError::SigningError)?;
+        Ok(SignedReply::new_nontrans(
+            reply,
+            BasicPrefix::Ed25519NT(signer.public_key()),
+            SelfSigningPrefix::Ed25519Sha512(signature),
+        ))
+    }
+
     pub fn oobi(&self) -> LocationScheme {
         LocationScheme::new(
             IdentifierPrefix::Basic(self.prefix.clone()),
@@ -281,19 +560,31 @@ impl Witness {
         &self,
         eid: &IdentifierPrefix,
     ) -> Result<Option<Vec<SignedReply>>, Error> {
-        Ok(self.oobi_manager.get_loc_scheme(eid)?.map(|oobis_to_sign| {
-            oobis_to_sign
-                .iter()
-                .map(|oobi_to_sing| {
-                    let signature = self.signer.sign(oobi_to_sing.encode().unwrap()).unwrap();
-                    SignedReply::new_nontrans(
-                        oobi_to_sing.clone(),
-                        self.prefix.clone(),
-                        SelfSigningPrefix::Ed25519Sha512(signature),
-                    )
-                })
-                .collect()
-        }))
+        // Replies are returned with signatures they were saved with, so
+        // location of witness' previous prefix is still signed by its
+        // previous key after rotation.
+        Ok(self.oobi_manager.get_signed_loc_scheme(eid)?)
+    }
+
+    /// Returns all end role replies of `cid` and location schemes of
+    /// identifiers designated in them, so all of controller's endpoints can be
+    /// discovered at once.
+    pub fn get_oobis_for_cid(&self, cid: &IdentifierPrefix) -> Result<Vec<SignedReply>, Error> {
+        let end_roles = self.oobi_manager.get_all_end_roles(cid)?;
+        let eids = end_roles
+            .iter()
+            .filter_map(|rpy| match rpy.reply.get_route() {
+                ReplyRoute::EndRoleAdd(end_role) => Some(end_role.eid),
+                _ => None,
+            })
+            .chain(std::iter::once(cid.clone()))
+            .unique()
+            .collect::<Vec<_>>();
+        let mut oobis = end_roles;
+        for eid in eids {
+            oobis.append(&mut self.get_loc_scheme_for_id(&eid)?.unwrap_or_default());
+        }
+        Ok(oobis)
     }
 
     pub fn get_signed_ksn_for_prefix(
@@ -319,7 +610,13 @@ impl Witness {
     }
 
     pub fn process_notice(&self, notice: Notice) -> Result<(), Error> {
-        match self.processor.process_notice(&notice) {
+        if let Notice::Event(event) = &notice {
+            let id = event.event_message.data.get_prefix();
+            if self.takedowns.is_taken_down(&id) {
+                return Err(Error::EventRejected(format!("{} was taken down", id)));
+            }
+        }
+        let result = match self.processor.process_notice(&notice) {
             Err(Error::MissingDelegatorSealError(id)) => {
                 if let Notice::Event(delegated_event) = notice {
                     self.event_storage
@@ -329,13 +626,553 @@ impl Witness {
                 }
             }
             whatever => whatever,
+        };
+        if result.is_ok() {
+            self.metrics.notices_processed.inc();
         }
+        result
     }
 
+    /// Returns witnesses of the identifier whose receipts of event at `sn`
+    /// are not stored yet.
+    pub fn missing_receipts(
+        &self,
+        id: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<Vec<BasicPrefix>, Error> {
+        let event = self
+            .event_storage
+            .get_event_at_sn(id, sn)
+            .ok_or(Error::MissingEvent)?
+            .signed_event_message
+            .event_message;
+        let witnesses = self
+            .event_storage
+            .get_witnesses_at_event(sn, id, &event.digest()?)?;
+        let receipted: Vec<BasicPrefix> = self
+            .event_storage
+            .get_nt_receipts(id, sn)?
+            .map(|rct| {
+                rct.signatures
+                    .into_iter()
+                    .flat_map(|sigs| match sigs {
+                        Nontransferable::Couplet(couplets) => {
+                            couplets.into_iter().map(|(bp, _sig)| bp).collect()
+                        }
+                        Nontransferable::Indexed(indexed) => indexed
+                            .into_iter()
+                            .filter_map(|sig| witnesses.get(sig.index.current() as usize).cloned())
+                            .collect::<Vec<_>>(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(witnesses
+            .into_iter()
+            .filter(|wit| !receipted.contains(wit))
+            .collect())
+    }
+
+    /// Lists identifiers stored by witness with their latest sequence number,
+    /// receipt coverage of latest event and size of stored KEL.
+    pub fn census(&self) -> Result<Vec<IdentifierCensus>, Error> {
+        self.event_storage
+            .events_db
+            .get_identifiers()?
+            .into_iter()
+            .filter_map(|id| self.event_storage.get_state(&id))
+            .map(|state| {
+                let witnesses = state.witness_config.witnesses.len();
+                let missing = self.missing_receipts(&state.prefix, state.sn)?.len();
+                let storage_size = self
+                    .event_storage
+                    .get_kel_messages_with_receipts_all(&state.prefix)?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|notice| Message::Notice(notice).to_cesr().map(|cesr| cesr.len()))
+                    .sum::<Result<usize, _>>()?;
+                Ok(IdentifierCensus {
+                    id: state.prefix,
+                    sn: state.sn,
+                    witnesses,
+                    receipts: witnesses.saturating_sub(missing),
+                    storage_size,
+                })
+            })
+            .collect()
+    }
+
+    /// Copies witness databases into `target` directory without stopping
+    /// witness. Events are stored in the same layout as in events directory
+    /// passed to [`Witness::new`], and oobis in `oobi` subdirectory. Escrows
+    /// are not copied.
+    pub fn backup(&self, target: &Path) -> Result<(), WitnessError> {
+        std::fs::create_dir_all(target.join("events"))?;
+        let events_db = &self.event_storage.events_db;
+        match **events_db {
+            RedbStorage::Single(_) => events_db.backup(&target.join(EVENTS_DATABASE))?,
+            RedbStorage::Sharded(_) => events_db.backup(&target.join(EVENTS_SHARDS))?,
+        };
+        self.event_storage.escrow_db.backup(target.join("events"))?;
+        self.tel
+            .processor
+            .tel_reference
+            .db
+            .backup(target.join("tel").join("events"))?;
+        self.oobi_manager.backup(&target.join("oobi"))?;
+        Ok(())
+    }
+
+    /// Returns number of events database shards in `event_path` directory,
+    /// which is 1 if events are stored in single database file, or `None`
+    /// if there's no events database yet.
+    pub fn event_shards(event_path: &Path) -> Result<Option<usize>, WitnessError> {
+        let shards_dir = event_path.join(EVENTS_SHARDS);
+        if shards_dir.exists() {
+            Ok(Some(ShardedRedbDatabase::count_shards(&shards_dir)?))
+        } else if event_path.join(EVENTS_DATABASE).exists() {
+            Ok(Some(1))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Moves events stored in `event_path` directory into `shards` redb
+    /// databases. Single database file is split into shards and kept as
+    /// `events_database.unsharded`. Existing shards are rebalanced and old
+    /// ones are kept in `events_shards.old` directory. Previous data can be
+    /// removed once witness works with new shards. Empty shards are created
+    /// if there's no events database yet. Must be called while witness isn't
+    /// running.
+    pub fn reshard(event_path: &Path, shards: usize) -> Result<(), WitnessError> {
+        let db_path = event_path.join(EVENTS_DATABASE);
+        let shards_dir = event_path.join(EVENTS_SHARDS);
+        if !db_path.exists() && !shards_dir.exists() {
+            ShardedRedbDatabase::new(&shards_dir, shards)?;
+            return Ok(());
+        }
+        let previous = match shards_dir.exists() {
+            true => event_path.join("events_shards.old"),
+            false => event_path.join("events_database.unsharded"),
+        };
+        if previous.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists, remove it first", previous.display()),
+            )
+            .into());
+        }
+        // Leftover of interrupted resharding.
+        let new_dir = event_path.join("events_shards.new");
+        if new_dir.exists() {
+            std::fs::remove_dir_all(&new_dir)?;
+        }
+
+        let storage = RedbStorage::open(&db_path, &shards_dir)?;
+        storage.reshard(&new_dir, shards)?;
+        let current = match storage {
+            RedbStorage::Single(_) => db_path,
+            RedbStorage::Sharded(_) => shards_dir.clone(),
+        };
+        drop(storage);
+        std::fs::rename(current, previous)?;
+        std::fs::rename(new_dir, shards_dir)?;
+        Ok(())
+    }
+
+    /// Removes receipts of identifiers, that opted into pruning, made for
+    /// events preceding their latest establishment event (first seen at
+    /// least `min_age` ago, if set in identifier's policy). If policy allows
+    /// it, the events are removed too, and key state preceding remaining
+    /// events is kept in database. Removed data is written and synced to
+    /// archive before it's removed from database.
+    pub fn prune(&self) -> Result<Vec<PruningReport>, WitnessError> {
+        let events_db = &self.event_storage.events_db;
+        let mut reports = vec![];
+        for policy in &self.pruning.policies {
+            let id = &policy.id;
+            let threshold = policy.min_age.map(|age| {
+                SystemTime::now()
+                    .checked_sub(age)
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+            });
+            let before_sn = events_db
+                .get_state_history(id)?
+                .into_iter()
+                .rev()
+                .find(|transition| {
+                    threshold.map_or(true, |threshold| {
+                        SystemTime::from(transition.first_seen) <= threshold
+                    })
+                })
+                .map(|transition| transition.sn);
+            let before_sn = match before_sn {
+                Some(sn) if sn > 0 => sn,
+                _ => continue,
+            };
+            let pruned = events_db.get_prunable(id, before_sn, policy.events)?;
+            if pruned.is_empty() {
+                continue;
+            }
+            let state = if policy.events {
+                Some(
+                    self.event_storage
+                        .compute_state_at_sn(id, before_sn - 1)?
+                        .ok_or(Error::MissingEvent)?,
+                )
+            } else {
+                None
+            };
+            self.archive.append(&ArchiveRecord::new(
+                id.clone(),
+                before_sn,
+                Self::encode_pruned(&pruned)?,
+            ))?;
+            events_db.prune(id, before_sn, state.as_ref())?;
+            let events = pruned
+                .iter()
+                .filter(|notice| matches!(notice, Notice::Event(_)))
+                .count();
+            reports.push(PruningReport {
+                id: id.clone(),
+                before_sn,
+                receipts: pruned.len() - events,
+                events,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Puts events and receipts from archive at `path` back into database,
+    /// so pruned data can be audited. Returns number of restored messages.
+    pub fn import_archive(&self, path: &Path) -> Result<usize, WitnessError> {
+        let events_db = &self.event_storage.events_db;
+        let mut restored = 0;
+        let mut restored_kels = vec![];
+        for record in PruningArchive::read(path)? {
+            let pruned = parse_notice_stream(record.receipts.as_bytes()).map_err(Error::from)?;
+            restored += pruned.len();
+            for notice in pruned {
+                match notice {
+                    Notice::Event(event) => {
+                        if !restored_kels.contains(&record.id) {
+                            restored_kels.push(record.id.clone());
+                        }
+                        events_db.add_kel_finalized_event(event, &record.id)?
+                    }
+                    Notice::NontransferableRct(rct) => events_db.add_receipt_nt(rct, &record.id)?,
+                    Notice::TransferableRct(rct) => events_db.add_receipt_t(rct, &record.id)?,
+                }
+            }
+        }
+        // Key state is computed from the whole KEL again, once it starts
+        // with inception.
+        for id in restored_kels {
+            if self.event_storage.get_event_at_sn(&id, 0).is_some() {
+                events_db.remove_pruned_state(&id)?;
+            }
+        }
+        Ok(restored)
+    }
+
+    fn encode_pruned(pruned: &[Notice]) -> Result<String, WitnessError> {
+        let cesr = pruned
+            .iter()
+            .map(|notice| Message::Notice(notice.clone()).to_cesr())
+            .flatten_ok()
+            .collect::<Result<Vec<u8>, _>>()?;
+        String::from_utf8(cesr).map_err(|_| Error::CesrError.into())
+    }
+
+    /// Asks other witnesses of the identifier for their receipts of event at
+    /// `sn`, instead of waiting for controller to broadcast them. Peers are
+    /// found using saved location scheme OOBIs and asked with `logs` query.
+    /// Returns witnesses that couldn't provide the receipt.
+    pub async fn request_peer_receipts(
+        &self,
+        id: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<Vec<BasicPrefix>, ActorError> {
+        let mut failed = vec![];
+        for peer in self.missing_receipts(id, sn)? {
+            if let Err(e) = self.request_receipt_from(&peer, id, sn).await {
+                log::warn!(
+                    "Requesting receipt of {} at {} from {} failed: {}",
+                    id,
+                    sn,
+                    peer,
+                    e
+                );
+                failed.push(peer);
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Asks peers for missing receipts of events accepted since the last
+    /// call, see [`Witness::request_peer_receipts`]. Events are forgotten
+    /// once all receipts are collected, or after
+    /// [`PeerReceiptsConfig::max_attempts`] rounds. Returns number of events
+    /// still waiting for receipts.
+    pub async fn request_pending_receipts(&self) -> Result<usize, ActorError> {
+        let mut waiting = 0;
+        for (id, sn) in self.pending_receipts.pending() {
+            let missing = match self.request_peer_receipts(&id, sn).await {
+                Ok(_) => self.missing_receipts(&id, sn),
+                Err(e) => Err(e),
+            };
+            let missing = match missing {
+                Ok(missing) => Some(missing),
+                // Event was pruned or superseded.
+                Err(ActorError::KeriError(Error::MissingEvent)) => Some(vec![]),
+                // Other events are still processed, this one counts as
+                // failed round.
+                Err(e) => {
+                    log::warn!("Collecting receipts of {} at {} failed: {}", id, sn, e);
+                    None
+                }
+            };
+            if missing.as_ref().is_some_and(|missing| missing.is_empty()) {
+                self.pending_receipts.remove(&id, sn);
+            } else if self.pending_receipts.failed(&id, sn) >= self.peer_receipts.max_attempts {
+                log::warn!(
+                    "Giving up collecting receipts of {} at {}, missing: {:?}",
+                    id,
+                    sn,
+                    missing.unwrap_or_default()
+                );
+                self.pending_receipts.remove(&id, sn);
+            } else {
+                waiting += 1;
+            }
+        }
+        Ok(waiting)
+    }
+
+    async fn request_receipt_from(
+        &self,
+        peer: &BasicPrefix,
+        id: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<(), ActorError> {
+        let peer_id = IdentifierPrefix::Basic(peer.clone());
+
+        let qry = QueryEvent::new_query(
+            QueryRoute::Logs {
+                reply_route: "".to_string(),
+                args: LogsQueryArgs {
+                    i: id.clone(),
+                    s: Some(sn),
+                    limit: Some(1),
+                    src: Some(peer_id),
+                    after: None,
+                },
+            },
+            SerializationFormats::JSON,
+            HashFunctionCode::Blake3_256,
+        );
+        let signature = SelfSigningPrefix::Ed25519Sha512(self.signer.sign(qry.encode()?)?);
+        let qry = SignedQueryMessage::KelQuery(SignedKelQuery::new_nontrans(
+            qry,
+            self.prefix.clone(),
+            signature,
+        ));
+
+        let response = self
+            .with_peer_locations(&peer_id, |loc| self.transport.send_query(loc, qry.clone()))
+            .await?;
+        match response {
+            PossibleResponse::Kel(msgs) => {
+                for msg in msgs {
+                    if let Message::Notice(Notice::NontransferableRct(rct)) = msg {
+                        self.process_notice(Notice::NontransferableRct(rct))?;
+                    }
+                }
+                Ok(())
+            }
+            resp => Err(ActorError::UnexpectedResponse(resp.to_string())),
+        }
+    }
+
+    /// Returns locations of peer from saved location scheme OOBIs, ordered
+    /// by [`Witness::scheme_priority`].
+    fn peer_locations(&self, peer: &IdentifierPrefix) -> Result<Vec<LocationScheme>, ActorError> {
+        let mut ranked: Vec<_> = self
+            .oobi_manager
+            .get_loc_scheme(peer)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|rpy| match rpy.data.data {
+                ReplyRoute::LocScheme(loc) => self
+                    .scheme_priority
+                    .iter()
+                    .position(|scheme| scheme == &loc.scheme)
+                    .map(|rank| (rank, loc)),
+                _ => None,
+            })
+            .collect();
+        if ranked.is_empty() {
+            return Err(ActorError::NoLocation { id: peer.clone() });
+        }
+        ranked.sort_by_key(|(rank, _)| *rank);
+        Ok(ranked.into_iter().map(|(_, loc)| loc).collect())
+    }
+
+    /// Runs request with locations of peer in turn, until one of them can
+    /// be reached.
+    async fn with_peer_locations<T, F, Fut>(
+        &self,
+        peer: &IdentifierPrefix,
+        request: F,
+    ) -> Result<T, ActorError>
+    where
+        F: Fn(LocationScheme) -> Fut,
+        Fut: Future<Output = Result<T, TransportError>>,
+    {
+        let mut last_error = None;
+        for loc in self.peer_locations(peer)? {
+            match request(loc).await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    self.metrics.transport_errors.inc();
+                    let unreachable = is_unreachable(&e);
+                    last_error = Some(e);
+                    if !unreachable {
+                        break;
+                    }
+                }
+            }
+        }
+        Err(last_error
+            .map(ActorError::from)
+            .unwrap_or(ActorError::NoLocation { id: peer.clone() }))
+    }
+
+    /// Queues `messages` for delivery to `peer`, e.g. exchange messages
+    /// forwarded to other mailbox. They are sent by
+    /// [`Witness::process_outbox`], so delivery isn't lost if peer is
+    /// unreachable. Location of peer needs to be known by then.
+    pub fn enqueue_delivery(
+        &self,
+        peer: IdentifierPrefix,
+        messages: Vec<Message>,
+    ) -> Result<u64, WitnessError> {
+        let cesr = messages
+            .into_iter()
+            .map(|msg| msg.to_cesr())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        self.outbox
+            .push(peer, String::from_utf8_lossy(&cesr).to_string())
+    }
+
+    /// Queues receipts of event at `sn` stored by witness for delivery to
+    /// other witnesses of the identifier, so they can collect receipts
+    /// without waiting for controller. Returns number of queued deliveries.
+    pub fn push_receipts(&self, id: &IdentifierPrefix, sn: u64) -> Result<usize, WitnessError> {
+        let Some(receipts) = self.event_storage.get_nt_receipts(id, sn)? else {
+            return Ok(0);
+        };
+        let event = self
+            .event_storage
+            .get_event_at_sn(id, sn)
+            .ok_or(Error::MissingEvent)?
+            .signed_event_message
+            .event_message;
+        let peers: Vec<_> = self
+            .event_storage
+            .get_witnesses_at_event(sn, id, &event.digest()?)?
+            .into_iter()
+            .filter(|witness| witness != &self.prefix)
+            .collect();
+        for peer in &peers {
+            self.enqueue_delivery(
+                IdentifierPrefix::Basic(peer.clone()),
+                vec![Message::Notice(Notice::NontransferableRct(
+                    receipts.clone(),
+                ))],
+            )?;
+        }
+        Ok(peers.len())
+    }
+
+    /// Tries to deliver queued messages, which next attempt is due. Failed
+    /// deliveries are retried with exponential backoff and moved to dead
+    /// letters after [`OutboxConfig::max_attempts`].
+    pub async fn process_outbox(&self) -> Result<OutboxReport, WitnessError> {
+        let mut report = OutboxReport::default();
+        for delivery in self.outbox.due(outbox::now())? {
+            match self.deliver(&delivery).await {
+                Ok(()) => {
+                    self.outbox.delivered(delivery.id)?;
+                    report.delivered += 1;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Delivery {} to {} failed: {}",
+                        delivery.id,
+                        delivery.peer,
+                        e
+                    );
+                    let dead_letter =
+                        self.outbox
+                            .failed(delivery.id, e.to_string(), outbox::now())?;
+                    if dead_letter.is_some() {
+                        report.dead_lettered += 1;
+                    } else {
+                        report.retried += 1;
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn deliver(&self, delivery: &OutboundDelivery) -> Result<(), ActorError> {
+        let messages = parse_stream::<Message>(delivery.message.as_bytes(), false)?;
+        self.with_peer_locations(&delivery.peer, |loc| {
+            let messages = messages.clone();
+            async move {
+                for msg in messages {
+                    self.transport.send_message(loc.clone(), msg).await?;
+                }
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    /// Stores message forwarded in `exn` in recipient's mailbox. Expired
+    /// messages are removed from the mailbox first. Fails with
+    /// `ActorError::MailboxQuotaExceeded` if the message doesn't fit in
+    /// mailbox quota, with `ActorError::TakenDown` if recipient was taken
+    /// down and with `ActorError::ExchangeExpired` if request expired.
     pub fn process_exchange(
         &self,
         exn: keri_core::mailbox::exchange::SignedExchange,
     ) -> Result<(), ActorError> {
+        let (recipient, to_forward) = match &exn.exchange_message.data.data {
+            Exchange::Fwd { args, to_forward } => (&args.recipient_id, to_forward),
+        };
+        self.check_served(recipient)?;
+        if exn
+            .exchange_message
+            .data
+            .data
+            .is_expired(self.event_storage.clock.now().into())
+        {
+            return Err(ActorError::ExchangeExpired);
+        }
+        if let Some(expiry) = self.mailbox_quota.expiry {
+            self.event_storage.expire_mailbox(recipient, expiry)?;
+        }
+        let usage = self.event_storage.mailbox_usage(recipient)?;
+        if !self.mailbox_quota.allows(usage, to_forward.encode()?.len()) {
+            return Err(ActorError::MailboxQuotaExceeded {
+                id: recipient.clone(),
+            });
+        }
         process_signed_exn(exn, &self.event_storage)?;
         Ok(())
     }
@@ -352,9 +1189,28 @@ impl Witness {
 
     pub fn process_query(
         &self,
-        qry: keri_core::query::query_event::SignedQueryMessage,
+        qry: SignedQueryMessage,
     ) -> Result<Option<PossibleResponse>, ActorError> {
-        let response = process_signed_query(qry, &self.event_storage)?;
+        let queried = match &qry {
+            SignedQueryMessage::KelQuery(kqry) => kqry.query.get_prefix(),
+            SignedQueryMessage::MailboxQuery(mqry) => mqry.query.get_args().i,
+        };
+        self.check_served(&queried)?;
+        if let (SignedQueryMessage::MailboxQuery(_), Some(expiry)) =
+            (&qry, self.mailbox_quota.expiry)
+        {
+            self.event_storage.expire_mailbox(&queried, expiry)?;
+        }
+        let mailbox_topics = match &qry {
+            SignedQueryMessage::MailboxQuery(mqry) => {
+                Some(self.event_storage.mailbox_start(&mqry.query.get_args())?)
+            }
+            SignedQueryMessage::KelQuery(_) => None,
+        };
+        let response = match &self.query_replay_guard {
+            Some(guard) => process_guarded_query(qry, &self.event_storage, guard)?,
+            None => process_signed_query(qry, &self.event_storage)?,
+        };
 
         match response {
             ReplyType::Ksn(ksn) => {
@@ -366,34 +1222,201 @@ impl Witness {
 
                 let signature = SelfSigningPrefix::Ed25519Sha512(self.signer.sign(rpy.encode()?)?);
                 let reply = SignedReply::new_nontrans(rpy, self.prefix.clone(), signature);
+                self.metrics
+                    .queries_served
+                    .with_label_values(&["ksn"])
+                    .inc();
                 Ok(Some(PossibleResponse::Ksn(reply)))
             }
-            ReplyType::Kel(msgs) => Ok(Some(PossibleResponse::Kel(msgs))),
-            ReplyType::Mbx(mailbox_response) => Ok(Some(PossibleResponse::Mbx(mailbox_response))),
+            ReplyType::Kel(msgs) => {
+                self.metrics
+                    .queries_served
+                    .with_label_values(&["kel"])
+                    .inc();
+                Ok(Some(PossibleResponse::Kel(msgs)))
+            }
+            ReplyType::Mbx(mailbox_response) => {
+                let mailbox_response = match mailbox_topics {
+                    Some(from) => mailbox_response.limit_size(self.mailbox_max_size, &from)?,
+                    None => mailbox_response,
+                };
+                self.metrics
+                    .queries_served
+                    .with_label_values(&["mbx"])
+                    .inc();
+                Ok(Some(PossibleResponse::Mbx(mailbox_response)))
+            }
+        }
+    }
+
+    /// Returns witness metrics in Prometheus text format. Escrow and outbox
+    /// sizes are updated first.
+    pub fn gather_metrics(&self) -> Result<String, WitnessError> {
+        for (escrow, size) in self.processor.escrow_sizes() {
+            self.metrics
+                .escrow_size
+                .with_label_values(&[escrow])
+                .set(size as i64);
+        }
+        let pending = self.outbox.pending()?;
+        let stuck = pending.iter().filter(|delivery| delivery.attempts > 0);
+        for (state, size) in [
+            ("pending", pending.len()),
+            ("stuck", stuck.count()),
+            ("dead_letter", self.outbox.dead_letters()?.len()),
+        ] {
+            self.metrics
+                .outbox_size
+                .with_label_values(&[state])
+                .set(size as i64);
+        }
+        self.metrics
+            .encode()
+            .map_err(|e| WitnessError::MetricsError(e.to_string()))
+    }
+
+    /// Signs digest of the whole query response stream together with digest
+    /// of `query` stream, so client can check that response wasn't truncated
+    /// on the way and was returned for its query.
+    pub fn sign_response(&self, query: &[u8], stream: &[u8]) -> Result<ResponseEnvelope, Error> {
+        let query = ResponseEnvelope::digest(query);
+        let digest = ResponseEnvelope::digest(stream);
+        let signature = SelfSigningPrefix::Ed25519Sha512(
+            self.signer
+                .sign(ResponseEnvelope::signed_data(&query, &digest))?,
+        );
+        Ok(ResponseEnvelope::new(
+            self.prefix.clone(),
+            query,
+            digest,
+            signature,
+        ))
+    }
+
+    /// Writes processed stream to debug log, if it's enabled.
+    fn record_stream(&self, kind: &str, input_stream: &[u8]) {
+        if let Err(e) = self.stream_log.record(kind, input_stream) {
+            log::warn!("Failed to record {} stream: {}", kind, e);
         }
     }
 
     pub fn parse_and_process_notices(&self, input_stream: &[u8]) -> Result<(), Error> {
-        parse_notice_stream(input_stream)?
+        self.record_stream("notices", input_stream);
+        parse_stream::<Notice>(input_stream, self.strict_canonicalization)?
             .into_iter()
             .try_for_each(|notice| self.process_notice(notice))
     }
 
+    /// Processes notices stream and returns outcome of every event in it.
+    /// Already processed events are not stored again, and their outcome
+    /// contains receipt issued before.
+    pub fn process_notices_with_outcome(
+        &self,
+        input_stream: &[u8],
+    ) -> Result<Vec<NoticeOutcome>, Error> {
+        self.record_stream("notices", input_stream);
+        let notices = parse_stream::<Notice>(input_stream, self.strict_canonicalization)?;
+        let events = notices
+            .iter()
+            .filter_map(|notice| match notice {
+                Notice::Event(event) => Some(event.event_message.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        notices
+            .into_iter()
+            .try_for_each(|notice| self.process_notice(notice))?;
+        events
+            .into_iter()
+            .map(|event| {
+                let id = event.data.get_prefix();
+                let sn = event.data.get_sn();
+                let digest = event.digest()?;
+                let accepted = self
+                    .event_storage
+                    .get_event_at_sn(&id, sn)
+                    .map(|stored| stored.signed_event_message.event_message.digest())
+                    .transpose()?
+                    .as_ref()
+                    == Some(&digest);
+                let receipt = if accepted {
+                    self.issued_receipt(&id, sn)?
+                        .map(|rct| {
+                            Message::Notice(Notice::NontransferableRct(rct))
+                                .to_cesr()
+                                .map(|cesr| String::from_utf8_lossy(&cesr).to_string())
+                        })
+                        .transpose()?
+                } else {
+                    None
+                };
+                Ok(NoticeOutcome {
+                    id,
+                    sn,
+                    digest,
+                    receipt,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns receipt of event at `sn` signed by this witness.
+    fn issued_receipt(
+        &self,
+        id: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<Option<SignedNontransferableReceipt>, Error> {
+        Ok(self.event_storage.get_nt_receipts(id, sn)?.and_then(|rct| {
+            rct.signatures
+                .iter()
+                .flat_map(|sigs| match sigs {
+                    Nontransferable::Couplet(couplets) => couplets.clone(),
+                    Nontransferable::Indexed(_) => vec![],
+                })
+                .find(|(bp, _sig)| bp == &self.prefix)
+                .map(|couplet| {
+                    SignedNontransferableReceipt::new(
+                        &rct.body,
+                        vec![Nontransferable::Couplet(vec![couplet])],
+                    )
+                })
+        }))
+    }
+
     pub fn parse_and_process_queries(
         &self,
         input_stream: &[u8],
     ) -> Result<Vec<PossibleResponse>, ActorError> {
-        parse_query_stream(input_stream)?
+        self.record_stream("queries", input_stream);
+        parse_stream::<SignedQueryMessage>(input_stream, self.strict_canonicalization)?
             .into_iter()
             .map(|qry| self.process_query(qry))
             .filter_map(Result::transpose)
             .collect()
     }
 
+    /// Saves topic indexes of signed mailbox queries as acknowledged by
+    /// querying identifiers. Messages before them won't be returned for
+    /// mailbox queries that resume.
+    pub fn parse_and_process_mailbox_acks(&self, input_stream: &[u8]) -> Result<(), ActorError> {
+        self.record_stream("mailbox_acks", input_stream);
+        parse_stream::<SignedQueryMessage>(input_stream, self.strict_canonicalization)?
+            .into_iter()
+            .try_for_each(|qry| match qry {
+                SignedQueryMessage::MailboxQuery(mqry) => {
+                    Ok(process_mailbox_ack(mqry, &self.event_storage)?)
+                }
+                SignedQueryMessage::KelQuery(_) => Err(ActorError::GeneralError(
+                    "only mailbox queries can be acknowledged".to_string(),
+                )),
+            })
+    }
+
     pub fn parse_and_process_tel_queries(
         &self,
         input_stream: &[u8],
     ) -> Result<Vec<TelReplyType>, ActorError> {
+        self.record_stream("tel_queries", input_stream);
         Ok(parse_tel_query_stream(input_stream)
             .unwrap()
             .into_iter()
@@ -403,6 +1426,7 @@ impl Witness {
     }
 
     pub fn parse_and_process_tel_events(&self, input_stream: &[u8]) -> Result<(), ActorError> {
+        self.record_stream("tel_events", input_stream);
         VerifiableEvent::parse(input_stream)
             .unwrap()
             .into_iter()
@@ -413,19 +1437,57 @@ impl Witness {
     }
 
     pub fn parse_and_process_replies(&self, input_stream: &[u8]) -> Result<(), ActorError> {
-        for reply in parse_reply_stream(input_stream)? {
+        self.record_stream("replies", input_stream);
+        for reply in parse_stream::<SignedReply>(input_stream, self.strict_canonicalization)? {
             self.process_reply(reply)?;
         }
         Ok(())
     }
 
     pub fn parse_and_process_exchanges(&self, input_stream: &[u8]) -> Result<(), ActorError> {
-        for exchange in parse_exchange_stream(input_stream)? {
+        self.record_stream("exchanges", input_stream);
+        for exchange in parse_stream(input_stream, self.strict_canonicalization)? {
             self.process_exchange(exchange)?;
         }
         Ok(())
     }
 
+    /// Fails with `ActorError::TakenDown` if `id` was taken down.
+    pub fn check_served(&self, id: &IdentifierPrefix) -> Result<(), ActorError> {
+        if self.takedowns.is_taken_down(id) {
+            Err(ActorError::TakenDown { id: id.clone() })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies takedown signed by configured operator: identifiers' mailbox
+    /// content is deleted and they aren't served anymore. Signed takedown is
+    /// kept in takedown log for audit. Transferable operator's KEL needs to
+    /// be known to witness.
+    pub fn process_takedown(&self, signed: SignedTakedown) -> Result<(), ActorError> {
+        let operator = self
+            .takedown_operator
+            .as_ref()
+            .ok_or(ActorError::Unauthorized)?;
+        if signed.signature.get_signer().as_ref() != Some(operator) {
+            return Err(ActorError::Unauthorized);
+        }
+        let data = signed
+            .takedown
+            .encode()
+            .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        if !signed.signature.verify(&data, &self.event_storage)? {
+            return Err(Error::SignatureVerificationError.into());
+        }
+        for id in &signed.takedown.ids {
+            self.event_storage.clear_mailbox(id)?;
+        }
+        self.takedowns
+            .append(signed)
+            .map_err(|e| ActorError::GeneralError(e.to_string()))
+    }
+
     pub fn get_mailbox_messages(&self, id: &IdentifierPrefix) -> Result<MailboxResponse, Error> {
         self.event_storage.get_mailbox_messages(&QueryArgsMbx {
             pre: IdentifierPrefix::Basic(self.prefix.clone()),
@@ -439,6 +1501,62 @@ impl Witness {
                 delegate: 0,
                 reply: 0,
             },
+            resume: false,
+            limit: None,
         })
     }
 }
+
+#[async_trait::async_trait]
+impl KeriActor for Witness {
+    fn prefix(&self) -> BasicPrefix {
+        self.prefix.clone()
+    }
+
+    fn oobi(&self) -> LocationScheme {
+        Witness::oobi(self)
+    }
+
+    fn process_notices(&self, stream: &[u8]) -> Result<(), ActorError> {
+        Ok(self.parse_and_process_notices(stream)?)
+    }
+
+    fn process_replies(&self, stream: &[u8]) -> Result<(), ActorError> {
+        self.parse_and_process_replies(stream)
+    }
+
+    fn process_exchanges(&self, stream: &[u8]) -> Result<(), ActorError> {
+        self.parse_and_process_exchanges(stream)
+    }
+
+    async fn process_queries(&self, stream: &[u8]) -> Result<Vec<PossibleResponse>, ActorError> {
+        self.parse_and_process_queries(stream)
+    }
+
+    fn location_oobis(&self, eid: &IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+        http_handlers::location_oobis(self, eid)
+    }
+
+    fn role_oobis(
+        &self,
+        cid: &IdentifierPrefix,
+        role: Role,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        http_handlers::role_oobis(self, cid.clone(), role, eid)
+    }
+
+    async fn resolve_oobi(&self, _oobi: Oobi) -> Result<(), ActorError> {
+        // Witness learns about others only from replies sent to it.
+        Err(ActorError::GeneralError(
+            "Witness doesn't resolve OOBIs".to_string(),
+        ))
+    }
+
+    async fn handle_request(
+        &self,
+        req: &ActorRequest,
+    ) -> Option<Result<ActorResponse, ActorError>> {
+        http_handlers::handle_request(self, req)
+    }
+}
@@ -2,19 +2,37 @@ use std::{
     net::ToSocketAddrs,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use actix_web::{dev::Server, web::Data, App, HttpServer};
+use actor_server::{actix_listener::correlate, AdminToken};
 use anyhow::Result;
-use keri_core::{self, prefix::BasicPrefix};
+use keri_core::{
+    self,
+    actor::stream_log::StreamLog,
+    oobi::Scheme,
+    prefix::{BasicPrefix, IdentifierPrefix},
+    query::replay::QueryReplayGuard,
+    signer::Signer,
+    transport::Transport,
+};
 
 use crate::{
+    http_routing::witness_scope,
+    mailbox_quota::MailboxQuota,
+    outbox::OutboxConfig,
+    peer_receipts::PeerReceiptsConfig,
+    pruning::PruningConfig,
     witness::{Witness, WitnessError},
     witness_processor::WitnessEscrowConfig,
 };
 
 pub struct WitnessListener {
     pub witness_data: Arc<Witness>,
+    /// Token required by admin endpoints. Admin endpoints are disabled if
+    /// it's not set.
+    pub admin_token: Option<String>,
 }
 
 impl WitnessListener {
@@ -35,51 +53,184 @@ impl WitnessListener {
                 priv_key,
                 escrow_config,
             )?),
+            admin_token: None,
         })
     }
 
+    /// Like [`WitnessListener::setup`], but witness signs with provided
+    /// signer, e.g. one backed by [`Keystore`](keri_core::signer::Keystore).
+    pub fn setup_with_signer(
+        pub_addr: url::Url,
+        event_db_path: &Path,
+        signer: Arc<Signer>,
+        escrow_config: WitnessEscrowConfig,
+    ) -> Result<Self, WitnessError> {
+        let oobi_path = event_db_path.join("oobi");
+        Ok(Self {
+            witness_data: Arc::new(Witness::setup_with_signer(
+                pub_addr,
+                event_db_path,
+                oobi_path.as_path(),
+                signer,
+                escrow_config,
+            )?),
+            admin_token: None,
+        })
+    }
+
+    pub fn with_admin_token(self, token: String) -> Self {
+        Self {
+            admin_token: Some(token),
+            ..self
+        }
+    }
+
+    /// Sets maximal size of mailbox response in bytes. Needs to be called
+    /// before witness data is shared.
+    pub fn with_mailbox_max_size(mut self, max_size: usize) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .mailbox_max_size = max_size;
+        self
+    }
+
+    /// Sets limits of messages forwarded to single recipient, see
+    /// [`Witness::process_exchange`]. Needs to be called before witness
+    /// data is shared.
+    pub fn with_mailbox_quota(mut self, quota: MailboxQuota) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .mailbox_quota = quota;
+        self
+    }
+
+    /// Makes witness reject queries with timestamp differing from its clock
+    /// by more than `max_skew` and queries it processed before. Needs to be
+    /// called before witness data is shared.
+    pub fn with_query_max_skew(mut self, max_skew: Duration) -> Self {
+        let witness =
+            Arc::get_mut(&mut self.witness_data).expect("Witness data shouldn't be shared yet");
+        let clock = witness.event_storage.clock.clone();
+        witness.query_replay_guard = Some(QueryReplayGuard::new(max_skew).with_clock(clock));
+        self
+    }
+
+    /// Sets how far in the future timestamps of accepted replies and key
+    /// state notices can be. Needs to be called before witness data is
+    /// shared.
+    pub fn with_max_clock_drift(self, max_clock_drift: Duration) -> Self {
+        let Ok(witness) = Arc::try_unwrap(self.witness_data) else {
+            panic!("Witness data shouldn't be shared yet")
+        };
+        Self {
+            witness_data: Arc::new(witness.with_max_clock_drift(max_clock_drift)),
+            ..self
+        }
+    }
+
+    /// Sets identifier allowed to sign takedowns, see
+    /// [`Witness::process_takedown`]. Needs to be called before witness data
+    /// is shared.
+    pub fn with_takedown_operator(mut self, operator: IdentifierPrefix) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .takedown_operator = Some(operator);
+        self
+    }
+
+    /// Replaces debug log of processed streams, which by default is written
+    /// to `streams.log` in witness database directory. Needs to be called
+    /// before witness data is shared.
+    pub fn with_stream_log(mut self, stream_log: StreamLog) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .stream_log = stream_log;
+        self
+    }
+
+    /// Makes witness reject events, which bytes differ from their canonical
+    /// serialization. Needs to be called before witness data is shared.
+    pub fn with_strict_canonicalization(mut self) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .strict_canonicalization = true;
+        self
+    }
+
+    /// Sets identifiers whose old receipts and events can be pruned, see
+    /// [`Witness::prune`]. Needs to be called before witness data is shared.
+    pub fn with_pruning(mut self, pruning: PruningConfig) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .pruning = pruning;
+        self
+    }
+
+    /// Sets retry policy of outbound deliveries, see
+    /// [`Witness::process_outbox`]. Needs to be called before witness data
+    /// is shared.
+    pub fn with_outbox_config(mut self, config: OutboxConfig) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .outbox
+            .config = config;
+        self
+    }
+
+    /// Sets how missing receipts are requested from other witnesses, see
+    /// [`Witness::request_pending_receipts`]. Needs to be called before
+    /// witness data is shared.
+    pub fn with_peer_receipts_config(mut self, config: PeerReceiptsConfig) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .peer_receipts = config;
+        self
+    }
+
+    /// Sets order in which schemes of other witnesses' locations are tried,
+    /// see [`Witness::scheme_priority`]. Needs to be called before witness
+    /// data is shared.
+    pub fn with_scheme_priority(mut self, priority: Vec<Scheme>) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .scheme_priority = priority;
+        self
+    }
+
+    /// Sets transport used for communication with other witnesses. Needs to
+    /// be called before witness data is shared.
+    pub fn with_transport(mut self, transport: Box<dyn Transport + Send + Sync>) -> Self {
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .transport = transport;
+        self
+    }
+
+    /// Rotates witness to key derived from `seed`, see
+    /// [`Witness::rotate_key`]. Needs to be called before witness data is
+    /// shared.
+    pub fn with_rotated_key(mut self, seed: &str) -> Result<Self, WitnessError> {
+        let signer = seed
+            .parse()
+            .map_err(keri_core::error::Error::from)
+            .and_then(|seed| Signer::new_with_seed(&seed))?;
+        Arc::get_mut(&mut self.witness_data)
+            .expect("Witness data shouldn't be shared yet")
+            .rotate_key(Arc::new(signer))?;
+        Ok(self)
+    }
+
+    /// Serves witness endpoints over HTTP and spawns
+    /// [`outbox_processing`] and [`peer_receipts_processing`] tasks.
     pub fn listen_http(&self, addr: impl ToSocketAddrs) -> Server {
-        let state = Data::new(self.witness_data.clone());
+        let witness_data = self.witness_data.clone();
+        actix_web::rt::spawn(outbox_processing(witness_data.clone()));
+        actix_web::rt::spawn(peer_receipts_processing(witness_data.clone()));
+        let admin_token = Data::new(AdminToken(self.admin_token.clone()));
         HttpServer::new(move || {
             App::new()
-                .app_data(state.clone())
-                .route(
-                    "/introduce",
-                    actix_web::web::get().to(http_handlers::introduce),
-                )
-                .route(
-                    "/oobi/{id}",
-                    actix_web::web::get().to(http_handlers::resolve_location),
-                )
-                .route(
-                    "/oobi/{cid}/{role}/{eid}",
-                    actix_web::web::get().to(http_handlers::resolve_role),
-                )
-                .route(
-                    "/process",
-                    actix_web::web::post().to(http_handlers::process_notice),
-                )
-                .route(
-                    "/query",
-                    actix_web::web::post().to(http_handlers::process_query),
-                )
-                .route(
-                    "/query/tel",
-                    actix_web::web::post().to(http_handlers::process_tel_query),
-                )
-                .route(
-                    "/process/tel",
-                    actix_web::web::post().to(http_handlers::process_tel_events),
-                )
-                .route(
-                    "/register",
-                    actix_web::web::post().to(http_handlers::process_reply),
-                )
-                .route(
-                    "/forward",
-                    actix_web::web::post().to(http_handlers::process_exchange),
-                )
-                .route("/info", actix_web::web::get().to(http_handlers::info))
+                .wrap(actix_web::middleware::from_fn(correlate))
+                .service(witness_scope(witness_data.clone()).app_data(admin_token.clone()))
         })
         .bind(addr)
         .unwrap()
@@ -91,68 +242,96 @@ impl WitnessListener {
     }
 }
 
+/// Delivers queued outbound messages every
+/// [`OutboxConfig::poll_interval`]. Needs to be spawned separately when
+/// witness routes are mounted directly or served only over TCP.
+pub async fn outbox_processing(data: Arc<Witness>) {
+    loop {
+        if let Err(e) = data.process_outbox().await {
+            log::error!("Outbox processing failed: {}", e);
+        }
+        actix_web::rt::time::sleep(data.outbox.config.poll_interval).await;
+    }
+}
+
+/// Asks other witnesses for missing receipts of accepted events every
+/// [`PeerReceiptsConfig::poll_interval`]. Needs to be spawned separately
+/// when witness routes are mounted directly or served only over TCP.
+pub async fn peer_receipts_processing(data: Arc<Witness>) {
+    loop {
+        if let Err(e) = data.request_pending_receipts().await {
+            log::error!("Requesting peer receipts failed: {}", e);
+        }
+        actix_web::rt::time::sleep(data.peer_receipts.poll_interval).await;
+    }
+}
+
 mod test {
-    use actix_web::body::MessageBody;
+    use actor_server::{route, AdminToken};
     use keri_core::{
         actor::{
             error::ActorError,
+            keri_actor::ActorRequest,
             parse_event_stream, parse_op_stream,
             simple_controller::{parse_response, PossibleResponse},
         },
-        event_message::signed_event_message::{Message, Op},
+        event_message::signed_event_message::{
+            Message, Notice, Op, SignedEventMessage, SignedNontransferableReceipt,
+        },
         oobi::Role,
         prefix::IdentifierPrefix,
         query::{
             self,
             query_event::{QueryRoute, SignedQueryMessage},
         },
+        transport::receipts_from_outcomes,
     };
 
+    impl super::WitnessListener {
+        /// Answers request the same way as listeners do and returns body of
+        /// response.
+        async fn request(
+            &self,
+            method: &str,
+            path: String,
+            body: Vec<u8>,
+        ) -> Result<Vec<u8>, ActorError> {
+            let req = ActorRequest::new(method, path, body);
+            route(&*self.witness_data, &AdminToken::default(), &req)
+                .await?
+                .into_bytes()
+                .map_err(ActorError::KeriError)
+        }
+    }
+
     #[async_trait::async_trait]
     impl keri_core::transport::test::TestActor for super::WitnessListener {
         async fn send_message(&self, msg: Message) -> Result<(), ActorError> {
-            let payload = String::from_utf8(msg.to_cesr().unwrap()).unwrap();
-            let data = actix_web::web::Data::new(self.witness_data.clone());
-            match msg {
-                Message::Notice(_) => {
-                    super::http_handlers::process_notice(payload, data)
-                        .await
-                        .map_err(|err| err.0)?;
-                }
-                Message::Op(op) => match op {
-                    Op::Query(_) => {
-                        super::http_handlers::process_query(payload, data)
-                            .await
-                            .map_err(|err| err.0)?;
-                    }
-                    Op::Reply(_) => {
-                        super::http_handlers::process_reply(payload, data)
-                            .await
-                            .map_err(|err| err.0)?;
-                    }
-                    Op::Exchange(_) => {
-                        super::http_handlers::process_exchange(payload, data)
-                            .await
-                            .map_err(|err| err.0)?;
-                    }
-                },
-            }
-
+            let path = match &msg {
+                Message::Notice(_) => "process",
+                Message::Op(Op::Query(_)) => "query",
+                Message::Op(Op::Reply(_)) => "register",
+                Message::Op(Op::Exchange(_)) => "forward",
+            };
+            self.request("POST", path.to_string(), msg.to_cesr().unwrap())
+                .await?;
             Ok(())
         }
+        async fn send_event(
+            &self,
+            event: SignedEventMessage,
+        ) -> Result<Vec<SignedNontransferableReceipt>, ActorError> {
+            let payload = Message::Notice(Notice::Event(event)).to_cesr().unwrap();
+            let resp = self.request("POST", "process".to_string(), payload).await?;
+            Ok(receipts_from_outcomes(&resp))
+        }
+
         async fn send_query(
             &self,
             query: SignedQueryMessage,
         ) -> Result<PossibleResponse, ActorError> {
-            let payload =
-                String::from_utf8(Message::Op(Op::Query(query.clone())).to_cesr().unwrap())
-                    .unwrap();
-
-            let data = actix_web::web::Data::new(self.witness_data.clone());
-            let resp = super::http_handlers::process_query(payload, data)
-                .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
+            let payload = Message::Op(Op::Query(query.clone())).to_cesr().unwrap();
+            let resp = self.request("POST", "query".to_string(), payload).await?;
             match query {
                 SignedQueryMessage::KelQuery(qry) => match qry.query.get_route() {
                     QueryRoute::Ksn { .. } => {
@@ -163,7 +342,7 @@ mod test {
                         };
                         Ok(PossibleResponse::Ksn(reply))
                     }
-                    QueryRoute::Logs { .. } => {
+                    QueryRoute::Logs { .. } | QueryRoute::LogsBetween { .. } => {
                         let log = parse_event_stream(&resp).unwrap();
                         Ok(PossibleResponse::Kel(log))
                     }
@@ -173,20 +352,16 @@ mod test {
                         reply_route: _,
                         args: _,
                     } => {
-                        let resp = String::from_utf8(resp.to_vec()).unwrap();
+                        let resp = String::from_utf8(resp).unwrap();
                         let resp = parse_response(&resp).unwrap();
                         Ok(resp)
                     }
-                }, // },
+                },
             }
         }
         async fn request_loc_scheme(&self, eid: IdentifierPrefix) -> Result<Vec<Op>, ActorError> {
-            let data = actix_web::web::Data::new(self.witness_data.clone());
-            let resp = super::http_handlers::resolve_location(eid.into(), data)
-                .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
-            let resp = parse_op_stream(resp.as_ref()).unwrap();
+            let resp = self.request("GET", format!("oobi/{}", eid), vec![]).await?;
+            let resp = parse_op_stream(&resp).unwrap();
             Ok(resp)
         }
         async fn request_end_role(
@@ -195,52 +370,100 @@ mod test {
             role: Role,
             eid: IdentifierPrefix,
         ) -> Result<Vec<u8>, ActorError> {
-            let data = actix_web::web::Data::new(self.witness_data.clone());
-            let resp = super::http_handlers::resolve_role((cid, role, eid).into(), data)
+            self.request("GET", format!("oobi/{}/{}/{}", cid, role, eid), vec![])
                 .await
-                .map_err(|err| err.0)?;
-            let resp = resp.into_body().try_into_bytes().unwrap();
-            Ok(resp.to_vec())
+        }
+
+        async fn request_oobis(&self, cid: IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+            self.request("GET", format!("oobis/{}", cid), vec![]).await
+        }
+
+        async fn request_provenance(
+            &self,
+            _id: IdentifierPrefix,
+            _vc_id: Option<IdentifierPrefix>,
+        ) -> Result<keri_core::query::provenance::SignedProvenance, ActorError> {
+            Err(ActorError::GeneralError(
+                "witness doesn't provide provenance".to_string(),
+            ))
         }
 
         async fn resolve_oobi(&self, _msg: keri_core::oobi::Oobi) -> Result<(), ActorError> {
             todo!()
         }
     }
+
+    #[async_trait::async_trait]
+    impl teliox::transport::test::TelTestActor for super::WitnessListener {
+        async fn send_query(
+            &self,
+            qry: teliox::query::SignedTelQuery,
+        ) -> Result<String, ActorError> {
+            let input_stream = qry
+                .to_cesr()
+                .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+            Ok(self
+                .witness_data
+                .parse_and_process_tel_queries(&input_stream)?
+                .into_iter()
+                .map(|msg| msg.to_string())
+                .collect())
+        }
+        async fn send_tel_event(
+            &self,
+            event: teliox::event::verifiable_event::VerifiableEvent,
+        ) -> Result<(), ActorError> {
+            let input_stream = event
+                .serialize()
+                .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+            self.witness_data
+                .parse_and_process_tel_events(&input_stream)
+        }
+    }
 }
 
 pub mod http_handlers {
-    use std::sync::Arc;
+    use std::path::PathBuf;
 
-    use actix_web::{
-        http::{header::ContentType, StatusCode},
-        web, HttpResponse, Responder, ResponseError,
-    };
+    use actor_server::{parse_id, parse_json, parse_role};
     use itertools::Itertools;
     use keri_core::{
-        actor::{error::ActorError, prelude::Message},
+        actor::{
+            error::ActorError,
+            keri_actor::{ActorRequest, ActorResponse},
+            prelude::Message,
+        },
         error::Error,
         event_message::signed_event_message::Op,
         oobi::Role,
         prefix::{CesrPrimitive, IdentifierPrefix},
+        query::envelope::RESPONSE_ENVELOPE_HEADER,
+        transport::conditional::{self, etag_matches, ETAG_HEADER, IF_NONE_MATCH_HEADER},
     };
+    use serde::{Deserialize, Serialize};
     use teliox::event::verifiable_event::VerifiableEvent;
 
-    use crate::witness::Witness;
+    use crate::{metrics::METRICS_CONTENT_TYPE, takedown::SignedTakedown, witness::Witness};
 
-    pub async fn introduce(data: web::Data<Arc<Witness>>) -> Result<HttpResponse, ApiError> {
-        Ok(HttpResponse::Ok().json(data.oobi()))
+    /// Returns entity tag of KEL of `cid`, if it's known. See
+    /// [`keri_core::transport::conditional`].
+    pub(crate) fn kel_etag(data: &Witness, cid: &IdentifierPrefix) -> Option<String> {
+        data.event_storage
+            .get_state(cid)
+            .map(|state| conditional::kel_etag(&state.last_event_digest))
     }
 
-    pub async fn resolve_location(
-        eid: web::Path<IdentifierPrefix>,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
+    /// Returns signed location schemes of `eid` in CESR. Body of
+    /// `/oobi/{eid}` response.
+    pub(crate) fn location_oobis(
+        data: &Witness,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
         let loc_scheme = data
-            .get_loc_scheme_for_id(&eid)
+            .get_loc_scheme_for_id(eid)
             .map_err(ActorError::KeriError)?
             .unwrap_or_default();
-        let oobis: Vec<u8> = loc_scheme
+        loc_scheme
             .into_iter()
             .map(|sr| {
                 let sed = Message::Op(Op::Reply(sr));
@@ -248,19 +471,18 @@ pub mod http_handlers {
             })
             .flatten_ok()
             .try_collect()
-            .map_err(ActorError::KeriError)?;
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(String::from_utf8(oobis).unwrap()))
+            .map_err(ActorError::KeriError)
     }
 
-    pub async fn resolve_role(
-        path: web::Path<(IdentifierPrefix, Role, IdentifierPrefix)>,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        let (cid, role, eid) = path.into_inner();
-        let out = if role == Role::Witness {
+    /// Body of `/oobi/{cid}/{role}/{eid}` response.
+    pub(crate) fn role_oobis(
+        data: &Witness,
+        cid: IdentifierPrefix,
+        role: Role,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError> {
+        data.check_served(&cid)?;
+        if role == Role::Witness {
             // Check if it is TEL identifier
             let management_tel = data.tel.get_management_tel(&cid).unwrap();
             match management_tel {
@@ -272,7 +494,7 @@ pub mod http_handlers {
                 // Otherwise return location and KEL
                 None => {
                     let location_signed = data
-                        .get_loc_scheme_for_id(&eid)
+                        .get_loc_scheme_for_id(eid)
                         .map_err(ActorError::KeriError)?
                         .unwrap_or_default()
                         .into_iter()
@@ -281,13 +503,8 @@ pub mod http_handlers {
                             sed.to_cesr().map_err(|_| Error::CesrError).unwrap()
                         });
 
-                    Ok(data
-                        .event_storage
-                        .get_kel_messages_with_receipts_all(&cid)
-                        .map_err(ActorError::KeriError)?
-                        .unwrap_or_default()
+                    Ok(kel_cesr(data, &cid)?
                         .into_iter()
-                        .flat_map(|not| Message::Notice(not).to_cesr().unwrap())
                         .chain(location_signed)
                         .collect::<Vec<_>>())
                 }
@@ -300,7 +517,7 @@ pub mod http_handlers {
             match end_role {
                 Some(role_oobi) => {
                     let location_signed = data
-                        .get_loc_scheme_for_id(&eid)
+                        .get_loc_scheme_for_id(eid)
                         .map_err(ActorError::KeriError)?
                         .unwrap_or_default();
 
@@ -318,136 +535,273 @@ pub mod http_handlers {
 
                     // (for now) Append controller kel to be able to verify end role signature.
                     // TODO use ksn instead
-                    Ok(data
-                        .event_storage
-                        .get_kel_messages_with_receipts_all(&cid)
-                        .map_err(ActorError::KeriError)?
-                        .unwrap_or_default()
+                    Ok(kel_cesr(data, &cid)?
                         .into_iter()
-                        .flat_map(|not| Message::Notice(not).to_cesr().unwrap())
                         .chain(oobis)
                         .collect::<Vec<_>>())
                 }
-                None => Err(ApiError(ActorError::MissingRole { role, id: cid })),
+                None => Err(ActorError::MissingRole { role, id: cid }),
             }
-        };
+        }
+    }
+
+    /// Serialized KEL of `cid` with receipts. Events are loaded from
+    /// database in chunks.
+    fn kel_cesr(data: &Witness, cid: &IdentifierPrefix) -> Result<Vec<u8>, ActorError> {
+        data.event_storage
+            .get_kel_stream(cid)
+            .with_receipts()
+            .map(|notice| notice.and_then(|notice| Message::Notice(notice).to_cesr()))
+            .flatten_ok()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ActorError::KeriError)
+    }
 
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(String::from_utf8(out?).unwrap()))
-    }
-
-    pub async fn process_notice(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nWitness {} got notice to process: \n{}",
-            &data.prefix.to_str(),
-            post_data
-        );
-        data.parse_and_process_notices(post_data.as_bytes())
+    /// Body of `/oobis/{cid}` response in chunks. KEL can be long, so it's
+    /// sent in chunks instead of being loaded into memory at once.
+    pub(crate) fn cid_oobis(
+        data: &Witness,
+        cid: &IdentifierPrefix,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>, Error>>, ActorError> {
+        data.check_served(cid)?;
+        let oobis = data
+            .get_oobis_for_cid(cid)
+            .map_err(ActorError::KeriError)?
+            .into_iter()
+            .map(|sr| Message::Op(Op::Reply(sr)).to_cesr())
+            .flatten_ok()
+            .collect::<Result<Vec<_>, _>>()
             .map_err(ActorError::KeriError)?;
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(()))
-    }
-
-    pub async fn process_query(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!(
-            "\nWitness {} got query to process: \n{}",
-            &data.prefix.to_str(),
-            post_data
-        );
-        let resp = data
-            .parse_and_process_queries(post_data.as_bytes())?
-            .iter()
-            .map(|msg| msg.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        println!("\nWitness responds with: {}", resp);
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(resp))
-    }
-
-    pub async fn process_tel_query(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!("\nGot tel query to process: \n{}", post_data);
-        let resp = data
-            .parse_and_process_tel_queries(post_data.as_bytes())?
-            .iter()
-            .map(|msg| msg.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        println!("\nWitness responds with: {}", resp);
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(resp))
-    }
-
-    pub async fn process_reply(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!("\nGot reply to process: \n{}", post_data);
-        data.parse_and_process_replies(post_data.as_bytes())?;
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(()))
-    }
-
-    pub async fn process_exchange(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!("\nGot exchange to process: \n{}", post_data);
-        data.parse_and_process_exchanges(post_data.as_bytes())?;
-
-        Ok(HttpResponse::Ok()
-            .content_type(ContentType::plaintext())
-            .body(()))
-    }
-
-    pub async fn process_tel_events(
-        post_data: String,
-        data: web::Data<Arc<Witness>>,
-    ) -> Result<HttpResponse, ApiError> {
-        println!("\nGot tel event to process: \n{}", post_data);
-        let parsed = VerifiableEvent::parse(post_data.as_bytes()).unwrap();
-        for ev in parsed {
-            data.tel.processor.process(ev).unwrap()
-        }
 
-        Ok(HttpResponse::Ok().body(()))
+        let kel = data
+            .event_storage
+            .get_kel_stream(cid)
+            .with_receipts()
+            .map(|notice| notice.and_then(|notice| Message::Notice(notice).to_cesr()));
+        Ok(kel.chain(std::iter::once(Ok(oobis))))
     }
 
-    pub async fn info() -> impl Responder {
-        let version = option_env!("CARGO_PKG_VERSION");
-        if let Some(version) = version {
-            HttpResponse::Ok().json(serde_json::json!({ "version": version }))
+    /// Returns response with KEL of `cid` and its entity tag. Body is built
+    /// only if client doesn't have that KEL yet and request isn't `HEAD`.
+    fn kel_response(
+        data: &Witness,
+        req: &ActorRequest,
+        cid: &IdentifierPrefix,
+        body: impl FnOnce() -> Result<ActorResponse, ActorError>,
+    ) -> Result<ActorResponse, ActorError> {
+        data.check_served(cid)?;
+        let etag = kel_etag(data, cid);
+        let not_modified = match (req.header(IF_NONE_MATCH_HEADER), &etag) {
+            (Some(if_none_match), Some(etag)) => etag_matches(if_none_match, etag),
+            _ => false,
+        };
+        let resp = if not_modified {
+            ActorResponse::new(304, vec![])
+        } else if req.method == "HEAD" {
+            ActorResponse::ok(vec![])
         } else {
-            HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "error": "Failed to retrieve version information" }))
-        }
+            body()?
+        };
+        Ok(match etag {
+            Some(etag) => resp.with_header(ETAG_HEADER, etag),
+            None => resp,
+        })
     }
 
-    #[derive(Debug, derive_more::Display, derive_more::From, derive_more::Error)]
-    pub struct ApiError(pub ActorError);
+    /// Answers requests to endpoints specific to witness, see
+    /// [`KeriActor::handle_request`](keri_core::actor::keri_actor::KeriActor::handle_request).
+    /// Requests for KEL (`/oobis/{cid}` and `/oobi/{cid}/{role}/{eid}`) are
+    /// conditional, see [`keri_core::transport::conditional`].
+    pub(crate) fn handle_request(
+        data: &Witness,
+        req: &ActorRequest,
+    ) -> Option<Result<ActorResponse, ActorError>> {
+        route(data, req).transpose()
+    }
 
-    impl ResponseError for ApiError {
-        fn status_code(&self) -> StatusCode {
-            self.0.http_status_code()
-        }
+    fn route(data: &Witness, req: &ActorRequest) -> Result<Option<ActorResponse>, ActorError> {
+        let body = req.body.as_slice();
+        let resp = match (req.method.as_str(), req.segments().as_slice()) {
+            ("GET" | "HEAD", ["oobis", cid]) => {
+                let cid = parse_id(cid)?;
+                kel_response(data, req, &cid, || {
+                    Ok(ActorResponse::chunked(cid_oobis(data, &cid)?))
+                })?
+            }
+            ("GET" | "HEAD", ["oobi", cid, role, eid]) => {
+                let (cid, role, eid) = (parse_id(cid)?, parse_role(role)?, parse_id(eid)?);
+                kel_response(data, req, &cid, || {
+                    Ok(ActorResponse::ok(role_oobis(
+                        data,
+                        cid.clone(),
+                        role,
+                        &eid,
+                    )?))
+                })?
+            }
+            ("POST", ["process"]) => {
+                println!(
+                    "\nWitness {} got notice to process: \n{}",
+                    &data.prefix.to_str(),
+                    String::from_utf8_lossy(body)
+                );
+                let outcome = data
+                    .process_notices_with_outcome(body)
+                    .map_err(ActorError::KeriError)?;
+                ActorResponse::json(&outcome)?
+            }
+            ("POST", ["query"]) => {
+                println!(
+                    "\nWitness {} got query to process: \n{}",
+                    &data.prefix.to_str(),
+                    String::from_utf8_lossy(body)
+                );
+                let resp = data
+                    .parse_and_process_queries(body)?
+                    .iter()
+                    .map(|msg| msg.to_string())
+                    .collect::<Vec<_>>()
+                    .join("");
+                println!("\nWitness responds with: {}", resp);
+                let envelope = data
+                    .sign_response(body, resp.as_bytes())
+                    .map_err(ActorError::KeriError)?;
+                ActorResponse::ok(resp).with_header(RESPONSE_ENVELOPE_HEADER, envelope)
+            }
+            // Saves topic indexes of signed mailbox query as acknowledged by
+            // its signer.
+            ("POST", ["mailbox", "ack"]) => {
+                println!(
+                    "\nGot mailbox acknowledgment: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                data.parse_and_process_mailbox_acks(body)?;
+                ActorResponse::ok(vec![])
+            }
+            ("POST", ["query", "tel"]) => {
+                println!(
+                    "\nGot tel query to process: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                let resp = data
+                    .parse_and_process_tel_queries(body)?
+                    .iter()
+                    .map(|msg| msg.to_string())
+                    .collect::<Vec<_>>()
+                    .join("");
+                println!("\nWitness responds with: {}", resp);
+                ActorResponse::ok(resp)
+            }
+            ("POST", ["process", "tel"]) => {
+                println!(
+                    "\nGot tel event to process: \n{}",
+                    String::from_utf8_lossy(body)
+                );
+                let parsed = VerifiableEvent::parse(body)
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                for ev in parsed {
+                    data.tel
+                        .processor
+                        .process(ev)
+                        .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                }
+                ActorResponse::ok(vec![])
+            }
+            // Applies takedown signed by witness operator.
+            ("POST", ["takedown"]) => {
+                let takedown: SignedTakedown = parse_json(body)?;
+                println!("\nGot takedown to process: \n{:?}", takedown);
+                data.process_takedown(takedown)?;
+                ActorResponse::ok(vec![])
+            }
+            // Returns signed takedowns applied by witness.
+            ("GET", ["takedowns"]) => ActorResponse::json(&data.takedowns.records())?,
+            // Returns rotations of witness key, signed by previous and
+            // successor key.
+            ("GET", ["key_rotations"]) => ActorResponse::json(&data.key_rotations.records())?,
+            ("GET", ["info"]) => {
+                ActorResponse::json(&serde_json::json!({ "version": env!("CARGO_PKG_VERSION") }))?
+            }
+            // Returns witness metrics in Prometheus text format.
+            ("GET", ["metrics"]) => {
+                let metrics = data
+                    .gather_metrics()
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                ActorResponse::ok(metrics).with_header("Content-Type", METRICS_CONTENT_TYPE)
+            }
+            ("GET", ["admin", "identifiers"]) => {
+                ActorResponse::json(&data.census().map_err(ActorError::KeriError)?)?
+            }
+            // Lists outbound deliveries waiting in queue and abandoned ones,
+            // so stuck deliveries can be inspected.
+            ("GET", ["admin", "outbox"]) => {
+                let pending = data
+                    .outbox
+                    .pending()
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                let dead_letters = data
+                    .outbox
+                    .dead_letters()
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                ActorResponse::json(&serde_json::json!({
+                    "pending": pending,
+                    "dead_letters": dead_letters,
+                }))?
+            }
+            ("POST", ["admin", "backup"]) => {
+                let BackupRequest { path } = parse_json(body)?;
+                data.backup(&path)
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                ActorResponse::json(&serde_json::json!({ "path": path }))?
+            }
+            // Prunes receipts and events of identifiers that opted into
+            // pruning and returns summary of removed data.
+            ("POST", ["admin", "prune"]) => {
+                let reports = data
+                    .prune()
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                ActorResponse::json(&serde_json::json!({
+                    "pruned": reports,
+                    "archive": data.archive.path(),
+                }))?
+            }
+            // Puts events and receipts from archive of pruned data back into
+            // database.
+            ("POST", ["admin", "archive", "import"]) => {
+                let ArchiveImportRequest { path } = parse_json(body)?;
+                let restored = data
+                    .import_archive(&path)
+                    .map_err(|e| ActorError::GeneralError(e.to_string()))?;
+                ActorResponse::json(&serde_json::json!({ "restored": restored }))?
+            }
+            // Enables or disables debug log of processed streams.
+            ("POST", ["admin", "stream-log"]) => {
+                let StreamLogRequest { enabled } = parse_json(body)?;
+                data.stream_log.set_enabled(enabled);
+                ActorResponse::json(&serde_json::json!({
+                    "enabled": enabled,
+                    "path": data.stream_log.path(),
+                }))?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(resp))
+    }
 
-        fn error_response(&self) -> HttpResponse {
-            HttpResponse::build(self.status_code()).json(&self.0)
-        }
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BackupRequest {
+        /// Directory on witness host where backup will be written.
+        pub path: PathBuf,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ArchiveImportRequest {
+        /// Archive file on witness host.
+        pub path: PathBuf,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct StreamLogRequest {
+        pub enabled: bool,
     }
 }
@@ -1,24 +1,36 @@
 use std::{sync::Arc, time::Duration};
 
 use keri_core::{
-    database::{escrow::EscrowDb, redb::RedbDatabase, sled::SledEventDatabase, EventDatabase},
+    clock::Clock,
+    database::{
+        escrow::EscrowDb, redb::sharded::RedbStorage, sled::SledEventDatabase, EventDatabase,
+        QueryParameters,
+    },
     error::Error,
     event_message::signed_event_message::{Notice, SignedEventMessage},
     processor::{
-        escrow::{DelegationEscrow, EscrowConfig, OutOfOrderEscrow, PartiallySignedEscrow},
+        escrow::{
+            DelegationEscrow, EscrowConfig, OutOfOrderEscrow, PartiallySignedEscrow,
+            TransReceiptsEscrow,
+        },
         notification::{JustNotification, Notification, NotificationBus, Notifier},
         validator::EventValidator,
         EventProcessor, Processor,
     },
     query::reply_event::SignedReply,
 };
+use teliox::processor::escrow::TelEscrowConfig;
 
 pub struct WitnessProcessor {
     processor: EventProcessor<<WitnessProcessor as keri_core::processor::Processor>::Database>,
+    partially_signed_escrow: Arc<PartiallySignedEscrow<RedbStorage>>,
+    out_of_order_escrow: Arc<OutOfOrderEscrow<RedbStorage>>,
+    trans_receipts_escrow: Arc<TransReceiptsEscrow<RedbStorage>>,
+    delegation_escrow: Arc<DelegationEscrow<RedbStorage>>,
 }
 
 impl Processor for WitnessProcessor {
-    type Database = RedbDatabase;
+    type Database = RedbStorage;
     fn register_observer(
         &mut self,
         observer: Arc<dyn Notifier + Send + Sync>,
@@ -45,6 +57,13 @@ pub struct WitnessEscrowConfig {
     pub partially_signed_timeout: Duration,
     pub out_of_order_timeout: Duration,
     pub delegation_timeout: Duration,
+    pub trans_receipt_timeout: Duration,
+    /// Time after which TEL events, which anchoring KEL event wasn't
+    /// received, are removed.
+    pub tel_missing_anchor_timeout: Duration,
+    /// Clock used to check if escrowed events are stale and to check and
+    /// issue timestamps.
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Default for WitnessEscrowConfig {
@@ -54,56 +73,125 @@ impl Default for WitnessEscrowConfig {
             partially_signed_timeout: default.partially_signed_timeout,
             out_of_order_timeout: default.out_of_order_timeout,
             delegation_timeout: default.delegation_timeout,
+            trans_receipt_timeout: default.trans_receipt_timeout,
+            tel_missing_anchor_timeout: TelEscrowConfig::default().missing_issuer_timeout,
+            clock: default.clock,
         }
     }
 }
 
 impl WitnessProcessor {
     pub fn new(
-        redb: Arc<RedbDatabase>,
+        redb: Arc<RedbStorage>,
         sled_db: Arc<SledEventDatabase>,
         escrow_db: Arc<EscrowDb>,
         escrow_config: WitnessEscrowConfig,
     ) -> Self {
         let mut bus = NotificationBus::new();
-        let partially_signed_escrow = Arc::new(PartiallySignedEscrow::new(
-            redb.clone(),
-            sled_db.clone(),
-            escrow_db.clone(),
-            escrow_config.partially_signed_timeout,
-        ));
+        let partially_signed_escrow = Arc::new(
+            PartiallySignedEscrow::new(
+                redb.clone(),
+                sled_db.clone(),
+                escrow_db.clone(),
+                escrow_config.partially_signed_timeout,
+            )
+            .with_clock(escrow_config.clock.clone()),
+        );
         bus.register_observer(
-            partially_signed_escrow,
+            partially_signed_escrow.clone(),
             vec![JustNotification::PartiallySigned],
         );
-        let out_of_order_escrow = Arc::new(OutOfOrderEscrow::new(
-            redb.clone(),
-            sled_db.clone(),
-            escrow_db.clone(),
-            escrow_config.out_of_order_timeout,
-        ));
+        let out_of_order_escrow = Arc::new(
+            OutOfOrderEscrow::new(
+                redb.clone(),
+                sled_db.clone(),
+                escrow_db.clone(),
+                escrow_config.out_of_order_timeout,
+            )
+            .with_clock(escrow_config.clock.clone()),
+        );
         bus.register_observer(
-            out_of_order_escrow,
+            out_of_order_escrow.clone(),
             vec![
                 JustNotification::OutOfOrder,
                 JustNotification::KeyEventAdded,
             ],
         );
-        let deleating_escrow = Arc::new(DelegationEscrow::new(
-            redb.clone(),
-            sled_db.clone(),
-            escrow_db,
-            escrow_config.delegation_timeout,
-        ));
+        // Receipts of validators whose KEL is not known yet wait until
+        // validator's event is accepted.
+        let trans_receipts_escrow = Arc::new(
+            TransReceiptsEscrow::new(
+                redb.clone(),
+                sled_db.clone(),
+                escrow_db.clone(),
+                escrow_config.trans_receipt_timeout,
+            )
+            .with_clock(escrow_config.clock.clone()),
+        );
         bus.register_observer(
-            deleating_escrow,
+            trans_receipts_escrow.clone(),
+            vec![
+                JustNotification::TransReceiptOutOfOrder,
+                JustNotification::KeyEventAdded,
+            ],
+        );
+        let deleating_escrow = Arc::new(
+            DelegationEscrow::new(
+                redb.clone(),
+                sled_db.clone(),
+                escrow_db,
+                escrow_config.delegation_timeout,
+            )
+            .with_clock(escrow_config.clock.clone()),
+        );
+        bus.register_observer(
+            deleating_escrow.clone(),
             vec![
                 JustNotification::MissingDelegatingEvent,
                 JustNotification::KeyEventAdded,
             ],
         );
-        let processor = EventProcessor::new(sled_db, bus, redb.clone());
-        Self { processor }
+        let processor =
+            EventProcessor::new(sled_db, bus, redb.clone()).with_clock(escrow_config.clock);
+        Self {
+            processor,
+            partially_signed_escrow,
+            out_of_order_escrow,
+            trans_receipts_escrow,
+            delegation_escrow: deleating_escrow,
+        }
+    }
+
+    /// Sets how far in the future timestamps of accepted replies can be.
+    pub fn with_max_clock_drift(self, max_clock_drift: Duration) -> Self {
+        Self {
+            processor: self.processor.with_max_clock_drift(max_clock_drift),
+            ..self
+        }
+    }
+
+    /// Returns number of messages in each escrow, with escrow name.
+    pub fn escrow_sizes(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            (
+                "partially_signed",
+                self.partially_signed_escrow
+                    .escrowed_partially_signed
+                    .count(),
+            ),
+            (
+                "out_of_order",
+                self.out_of_order_escrow.escrowed_out_of_order.count(),
+            ),
+            (
+                "trans_receipts",
+                self.trans_receipts_escrow.escrowed_trans_receipts.count(),
+            ),
+            (
+                "delegation",
+                self.delegation_escrow.delegation_escrow.count(),
+            ),
+        ]
     }
 
     /// Witness processing strategy
@@ -137,6 +225,10 @@ impl WitnessProcessor {
             Err(Error::NotEnoughSigsError) => {
                 publisher.notify(&Notification::PartiallySigned(signed_event))
             }
+            Err(Error::EventDuplicateError) if Self::is_resubmitted(db.as_ref(), &signed_event) => {
+                // The same event was already accepted, nothing to do.
+                Ok(())
+            }
             Err(Error::EventDuplicateError) => {
                 escrow_db.add_duplicious_event(signed_event.clone(), id)?;
                 publisher.notify(&Notification::DupliciousEvent(signed_event))
@@ -144,4 +236,21 @@ impl WitnessProcessor {
             Err(e) => Err(e),
         }
     }
+
+    /// Checks if event is already in KEL, so it was submitted again rather
+    /// than being duplicitous.
+    fn is_resubmitted<D: EventDatabase>(db: &D, signed_event: &SignedEventMessage) -> bool {
+        let event = &signed_event.event_message;
+        let sn = event.data.get_sn();
+        db.get_kel_finalized_events(QueryParameters::BySn {
+            id: event.data.get_prefix(),
+            sn,
+        })
+        .and_then(|mut events| {
+            events.find(|stored| stored.signed_event_message.event_message.data.get_sn() == sn)
+        })
+        .map_or(false, |stored| {
+            stored.signed_event_message.event_message.digest().ok() == event.digest().ok()
+        })
+    }
 }
@@ -0,0 +1,121 @@
+//! Stable API of KERIOX.
+//!
+//! Everything exported from this crate follows semantic versioning. Other
+//! crates of the workspace (`keri-core`, `keri-controller`, `teliox`) are
+//! implementation details and can change in any release.
+//!
+//! Most operations on identifiers are done in three steps: prepare data,
+//! sign it with external key provider and finalize operation with the
+//! signature. See [`Controller`] and [`Identifier`].
+use std::sync::Arc;
+
+pub use keri_controller::{
+    config::{ControllerConfig, Profile},
+    controller::{import::ImportProgress, Controller},
+    error::ControllerError,
+    identifier::mechanics::MechanicsError,
+    identifier::Identifier,
+};
+
+/// Identifiers, keys and signatures.
+pub mod prefix {
+    pub use keri_core::prefix::{
+        BasicPrefix, CesrPrimitive, IdentifierPrefix, IndexedSignature, SeedPrefix,
+        SelfSigningPrefix,
+    };
+}
+
+/// Out-of-band introductions used to discover witnesses, watchers and
+/// identifiers.
+pub mod oobi {
+    pub use keri_core::oobi::{EndRole, LocationScheme, Oobi, Role, Scheme};
+}
+
+/// Key providers. [`CryptoBox`](signer::CryptoBox) is meant for tests and
+/// examples, production code should use its own [`KeyManager`](signer::KeyManager).
+pub mod signer {
+    #[cfg(feature = "pkcs11")]
+    pub use keri_core::signer::Pkcs11Keystore;
+    pub use keri_core::signer::{
+        CryptoBox, KeyManager, KeyType, Keystore, KeystoreKeyManager, Signer,
+    };
+    #[cfg(feature = "wallet")]
+    pub use keri_core::signer::{FileKeystore, Lockable};
+}
+
+/// States of identifiers and credentials.
+pub mod state {
+    pub use keri_controller::identifier::rotation_readiness::{NextKeyStatus, RotationReadiness};
+    pub use keri_controller::identifier::witness_health::{
+        WitnessHealth, WitnessHealthChecker, WitnessPolicy, WitnessRotationAdvice,
+    };
+    pub use keri_core::processor::event_storage::KelEntry;
+    pub use keri_core::processor::kel_audit::{AuditReport, Divergence, DivergenceReason};
+    pub use keri_core::query::provenance::{
+        EventProvenance, Provenance, ProvenanceBundle, SignedProvenance,
+    };
+    pub use keri_core::state::{IdentifierState, StateTransition};
+    pub use teliox::state::vc_state::TelState;
+}
+
+/// Events, signatures and digests.
+pub mod event {
+    pub use keri_controller::identifier::mechanics::kel_managing::AnchoringEvent;
+    pub use keri_core::event::sections::seal::{EventSeal, Seal};
+    pub use keri_core::event_message::signature::Signature;
+    pub use said::{
+        derivation::{HashFunction, HashFunctionCode},
+        version::format::SerializationFormats,
+        SelfAddressingIdentifier,
+    };
+}
+
+/// Messages received from mailbox, that require action from identifier's
+/// controller.
+pub mod mailbox {
+    pub use keri_controller::identifier::delegation_policy::{
+        AllowedDelegates, Decision, DelegationPolicy,
+    };
+    pub use keri_controller::mailbox_updating::ActionRequired;
+}
+
+/// Local policy checked before controller accepts key events.
+pub mod acceptance {
+    pub use keri_controller::acceptance::{Acceptance, AcceptanceHook};
+}
+
+pub mod error {
+    pub use keri_controller::config::ProfileError;
+    pub use keri_controller::{error::ControllerError, identifier::mechanics::MechanicsError};
+    pub use keri_core::processor::validator::VerificationError;
+}
+
+pub mod tel;
+
+/// Verifies signed data against KELs known to controller. Unknown signers'
+/// KELs need to be resolved first, for example with
+/// [`Identifier::resolve_oobi`].
+#[derive(Clone)]
+pub struct Verifier {
+    controller: Arc<Controller>,
+}
+
+impl Verifier {
+    pub fn new(controller: Arc<Controller>) -> Self {
+        Self { controller }
+    }
+
+    /// Checks signature of `data` made by identifier's current keys.
+    pub fn verify(
+        &self,
+        data: &[u8],
+        signature: &event::Signature,
+    ) -> Result<(), error::VerificationError> {
+        self.controller.verify(data, signature)
+    }
+
+    /// Verifies signatures of all signed elements of CESR stream.
+    pub fn verify_from_cesr(&self, stream: &str) -> Result<(), ControllerError> {
+        self.controller.known_events.verify_from_cesr(stream)
+    }
+}
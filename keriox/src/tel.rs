@@ -0,0 +1,124 @@
+//! Credential registries (TEL) managed in a single call per operation.
+//!
+//! [`Identifier`] exposes TEL operations as separate steps: generating
+//! events, signing the anchoring `ixn`, finalizing it, publishing the KEL
+//! event to witnesses, collecting receipts and publishing TEL events to
+//! backers. [`TelIdentifier`] runs all of them with provided [`KeyManager`].
+use keri_controller::identifier::Identifier;
+use keri_core::{
+    prefix::{IdentifierPrefix, SelfSigningPrefix},
+    signer::KeyManager,
+};
+use said::SelfAddressingIdentifier;
+use teliox::state::vc_state::TelState;
+
+use crate::error::ControllerError;
+
+pub use keri_controller::identifier::subscription::TelEvent;
+
+/// Identifier together with key provider that signs its events.
+pub struct TelIdentifier<'a, K: KeyManager> {
+    identifier: &'a mut Identifier,
+    key_manager: &'a K,
+}
+
+impl<'a, K: KeyManager> TelIdentifier<'a, K> {
+    pub fn new(identifier: &'a mut Identifier, key_manager: &'a K) -> Self {
+        Self {
+            identifier,
+            key_manager,
+        }
+    }
+
+    /// Creates registry without backers, anchors it in KEL and publishes it
+    /// to witnesses. Returns registry identifier.
+    pub async fn incept_registry(&mut self) -> Result<IdentifierPrefix, ControllerError> {
+        self.ensure_unlocked()?;
+        let (registry_id, ixn) = self.identifier.incept_registry()?;
+        let signature = self.sign(&ixn)?;
+        self.identifier
+            .finalize_incept_registry(&ixn, signature)
+            .await?;
+        self.publish().await?;
+        Ok(registry_id)
+    }
+
+    /// Issues credential of given digest in identifier's registry. Returns
+    /// credential identifier in TEL.
+    pub async fn issue(
+        &mut self,
+        credential_digest: SelfAddressingIdentifier,
+    ) -> Result<IdentifierPrefix, ControllerError> {
+        self.ensure_unlocked()?;
+        let (vc_id, ixn) = self.identifier.issue(credential_digest)?;
+        let signature = self.sign(&ixn)?;
+        self.identifier.finalize_issue(&ixn, signature).await?;
+        self.publish().await?;
+        Ok(vc_id)
+    }
+
+    /// Revokes credential issued in identifier's registry.
+    pub async fn revoke(
+        &mut self,
+        credential_digest: &SelfAddressingIdentifier,
+    ) -> Result<(), ControllerError> {
+        self.ensure_unlocked()?;
+        let ixn = self.identifier.revoke(credential_digest)?;
+        let signature = self.sign(&ixn)?;
+        self.identifier.finalize_revoke(&ixn, signature).await?;
+        self.publish().await
+    }
+
+    /// Asks identifier's watcher for credential's TEL and returns its state.
+    /// Issuer's KEL needs to be known, for example resolved with OOBI.
+    pub async fn query_tel_state(
+        &self,
+        registry_id: IdentifierPrefix,
+        credential_digest: &SelfAddressingIdentifier,
+    ) -> Result<Option<TelState>, ControllerError> {
+        self.ensure_unlocked()?;
+        let qry = self.identifier.query_tel(
+            registry_id,
+            IdentifierPrefix::self_addressing(credential_digest.clone()),
+        )?;
+        let signature = self.sign(&qry.encode()?)?;
+        self.identifier.finalize_query_tel(qry, signature).await?;
+        self.identifier.find_vc_state(credential_digest)
+    }
+
+    /// Fails before any event is generated if key manager is locked, so
+    /// operation isn't left half done.
+    fn ensure_unlocked(&self) -> Result<(), ControllerError> {
+        if self.key_manager.is_locked() {
+            Err(ControllerError::WalletLocked)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<SelfSigningPrefix, ControllerError> {
+        let signature = self.key_manager.sign(data).map_err(|e| match e {
+            keri_core::error::Error::WalletLocked => ControllerError::WalletLocked,
+            e => e.into(),
+        })?;
+        Ok(self.key_manager.key_type().signature(signature))
+    }
+
+    /// Sends anchoring `ixn` to witnesses, collects their receipts and sends
+    /// TEL events to backers.
+    async fn publish(&mut self) -> Result<(), ControllerError> {
+        self.identifier.notify_witnesses().await?;
+        let witnesses: Vec<_> = self.identifier.witnesses().collect();
+        let queries = self
+            .identifier
+            .query_mailbox(self.identifier.id(), &witnesses)?;
+        let mut signed = Vec::with_capacity(queries.len());
+        for qry in queries {
+            let signature = self.sign(&qry.encode()?)?;
+            signed.push((qry, signature));
+        }
+        self.identifier.finalize_query_mailbox(signed).await?;
+        self.identifier.notify_backers().await?;
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use crate::{
     prefix::IdentifierPrefix,
 };
 use said::version::error::Error as VersionError;
+use std::time::Duration;
 
 #[derive(Debug, thiserror::Error, serde::Serialize, serde::Deserialize)]
 pub enum ActorError {
@@ -61,6 +62,27 @@ pub enum ActorError {
 
     #[error("Unexpected response: {0}")]
     UnexpectedResponse(String),
+
+    #[error("Missing or invalid authorization")]
+    Unauthorized,
+
+    /// Identifier was taken down by actor's operator and its content isn't
+    /// served anymore.
+    #[error("{id} was taken down")]
+    TakenDown { id: IdentifierPrefix },
+
+    /// Recipient's mailbox reached quota configured by witness, so message
+    /// forwarded to it was rejected.
+    #[error("mailbox of {id} is full")]
+    MailboxQuotaExceeded { id: IdentifierPrefix },
+
+    /// Forwarded request has expiry set in its metadata, which has passed.
+    #[error("exchange message expired")]
+    ExchangeExpired,
+
+    /// Actor is overloaded and asks client to repeat request later.
+    #[error("Too many requests, retry after {retry_after} seconds")]
+    RetryAfter { retry_after: u64 },
 }
 
 #[cfg(feature = "oobi")]
@@ -96,7 +118,30 @@ impl ActorError {
             #[cfg(feature = "oobi")]
             ActorError::OobiError(OobiError::SignerMismatch) => StatusCode::UNAUTHORIZED,
 
+            ActorError::Unauthorized => StatusCode::UNAUTHORIZED,
+
+            ActorError::QueryError(
+                SignedQueryError::Replayed { .. } | SignedQueryError::TimestampOutOfSkew { .. },
+            ) => StatusCode::BAD_REQUEST,
+
+            ActorError::RetryAfter { .. } => StatusCode::TOO_MANY_REQUESTS,
+
+            ActorError::TakenDown { .. } => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+
+            ActorError::MailboxQuotaExceeded { .. } => StatusCode::INSUFFICIENT_STORAGE,
+
+            ActorError::ExchangeExpired => StatusCode::GONE,
+
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    /// Returns time after which request can be repeated, if actor asked to
+    /// back off.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ActorError::RetryAfter { retry_after } => Some(Duration::from_secs(*retry_after)),
+            _ => None,
+        }
+    }
 }
@@ -1,7 +1,9 @@
 use said::SelfAddressingIdentifier;
 
 #[cfg(feature = "mailbox")]
-use crate::mailbox::exchange::{Exchange, ExchangeMessage, ForwardTopic, FwdArgs};
+use crate::mailbox::exchange::{
+    Exchange, ExchangeMessage, ExchangeMetadata, ForwardTopic, FwdArgs,
+};
 #[cfg(feature = "oobi")]
 use crate::oobi::{EndRole, Role};
 #[cfg(feature = "query")]
@@ -16,7 +18,7 @@ use crate::{
         KeyEvent,
     },
     event_message::{event_msg_builder::EventMsgBuilder, msg::KeriEvent, EventTypeTag},
-    prefix::{BasicPrefix, IdentifierPrefix},
+    prefix::{BasicPrefix, CesrPrimitive, IdentifierPrefix},
     state::IdentifierState,
 };
 
@@ -27,6 +29,81 @@ pub fn incept(
     witnesses: Vec<BasicPrefix>,
     witness_threshold: u64,
     delegator_id: Option<&IdentifierPrefix>,
+) -> Result<String, Error> {
+    make_inception(
+        public_keys,
+        next_pub_keys,
+        witnesses,
+        &SignatureThreshold::Simple(witness_threshold),
+        delegator_id,
+    )
+}
+
+/// Generates inception event with witness threshold that can be weighted.
+/// Weighted threshold needs one weight for each witness.
+pub fn incept_with_witness_threshold(
+    public_keys: Vec<BasicPrefix>,
+    next_pub_keys: Vec<BasicPrefix>,
+    witnesses: Vec<BasicPrefix>,
+    witness_threshold: &SignatureThreshold,
+    delegator_id: Option<&IdentifierPrefix>,
+) -> Result<String, Error> {
+    if !witness_threshold.is_achievable(witnesses.len()) {
+        return Err(Error::EventGenerationError(
+            "Improper witness threshold".into(),
+        ));
+    };
+    make_inception(
+        public_keys,
+        next_pub_keys,
+        witnesses,
+        witness_threshold,
+        delegator_id,
+    )
+}
+
+/// Generates inception event of identifier controlled by several keys, with
+/// current and next signing thresholds that can be weighted.
+pub fn incept_with_thresholds(
+    public_keys: Vec<BasicPrefix>,
+    signature_threshold: &SignatureThreshold,
+    next_pub_keys: Vec<BasicPrefix>,
+    next_threshold: &SignatureThreshold,
+    witnesses: Vec<BasicPrefix>,
+    witness_threshold: &SignatureThreshold,
+) -> Result<String, Error> {
+    if !signature_threshold.is_achievable(public_keys.len())
+        || !next_threshold.is_achievable(next_pub_keys.len())
+    {
+        return Err(Error::EventGenerationError(
+            "Improper signature threshold".into(),
+        ));
+    };
+    if !witness_threshold.is_achievable(witnesses.len()) {
+        return Err(Error::EventGenerationError(
+            "Improper witness threshold".into(),
+        ));
+    };
+    let icp = EventMsgBuilder::new(EventTypeTag::Icp)
+        .with_keys(public_keys)
+        .with_threshold(signature_threshold)
+        .with_next_keys(next_pub_keys)
+        .with_next_threshold(next_threshold)
+        .with_witness_list(witnesses.as_slice())
+        .with_witness_threshold(witness_threshold)
+        .build()
+        .map_err(|e| Error::EventGenerationError(e.to_string()))?
+        .encode()
+        .map_err(|e| Error::EventGenerationError(e.to_string()))?;
+    String::from_utf8(icp).map_err(|e| Error::EventGenerationError(e.to_string()))
+}
+
+fn make_inception(
+    public_keys: Vec<BasicPrefix>,
+    next_pub_keys: Vec<BasicPrefix>,
+    witnesses: Vec<BasicPrefix>,
+    witness_threshold: &SignatureThreshold,
+    delegator_id: Option<&IdentifierPrefix>,
 ) -> Result<String, Error> {
     let event_builder = match delegator_id {
         Some(delegator) => EventMsgBuilder::new(EventTypeTag::Dip).with_delegator(delegator),
@@ -36,7 +113,7 @@ pub fn incept(
         .with_keys(public_keys)
         .with_next_keys(next_pub_keys)
         .with_witness_list(witnesses.as_slice())
-        .with_witness_threshold(&SignatureThreshold::Simple(witness_threshold))
+        .with_witness_threshold(witness_threshold)
         .build()
         .map_err(|e| Error::EventGenerationError(e.to_string()))?
         .encode()
@@ -106,6 +183,43 @@ pub fn rotate(
     witness_to_remove: Vec<BasicPrefix>,
     witness_threshold: u64,
 ) -> Result<String, Error> {
+    let rot = make_rotation(
+        state,
+        current_keys,
+        new_next_keys,
+        new_next_threshold,
+        witness_to_add,
+        witness_to_remove,
+        &SignatureThreshold::Simple(witness_threshold),
+    )?
+    .encode()
+    .map_err(|e| Error::EventGenerationError(e.to_string()))?;
+    String::from_utf8(rot).map_err(|e| Error::EventGenerationError(e.to_string()))
+}
+
+/// Generates rotation event with witness threshold that can be weighted.
+/// Threshold is checked against witness list resulting from the rotation.
+pub fn rotate_with_witness_threshold(
+    state: IdentifierState,
+    current_keys: Vec<BasicPrefix>,
+    new_next_keys: Vec<BasicPrefix>,
+    new_next_threshold: u64,
+    witness_to_add: Vec<BasicPrefix>,
+    witness_to_remove: Vec<BasicPrefix>,
+    witness_threshold: &SignatureThreshold,
+) -> Result<String, Error> {
+    let witnesses_count = state
+        .witness_config
+        .witnesses
+        .iter()
+        .filter(|wit| !witness_to_remove.contains(wit))
+        .chain(witness_to_add.iter())
+        .count();
+    if !witness_threshold.is_achievable(witnesses_count) {
+        return Err(Error::EventGenerationError(
+            "Improper witness threshold".into(),
+        ));
+    };
     let rot = make_rotation(
         state,
         current_keys,
@@ -120,6 +234,103 @@ pub fn rotate(
     String::from_utf8(rot).map_err(|e| Error::EventGenerationError(e.to_string()))
 }
 
+/// Generates rotation event that reveals only `current_keys` subset of keys
+/// committed in previous establishment event. Revealed keys need to satisfy
+/// previous next threshold. Unrevealed keys stay unexposed and can be
+/// committed again in `new_next_keys`. Both thresholds can be weighted.
+#[allow(clippy::too_many_arguments)]
+pub fn partial_rotate(
+    state: IdentifierState,
+    current_keys: Vec<BasicPrefix>,
+    current_threshold: SignatureThreshold,
+    new_next_keys: Vec<BasicPrefix>,
+    new_next_threshold: SignatureThreshold,
+    witness_to_add: Vec<BasicPrefix>,
+    witness_to_remove: Vec<BasicPrefix>,
+    witness_threshold: u64,
+) -> Result<String, Error> {
+    let committed = state.current.next_keys_data.next_keys_hashes();
+    let revealed = current_keys
+        .iter()
+        .map(|key| {
+            committed
+                .iter()
+                .position(|digest| digest.verify_binding(key.to_str().as_bytes()))
+                .ok_or_else(|| {
+                    Error::EventGenerationError(format!(
+                        "Key {} wasn't committed in previous event",
+                        key.to_str()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    state
+        .current
+        .next_keys_data
+        .threshold
+        .enough_signatures(&revealed)
+        .map_err(|e| Error::EventGenerationError(e.to_string()))?;
+
+    let rot = EventMsgBuilder::new(EventTypeTag::Rot)
+        .with_prefix(&state.prefix)
+        .with_sn(state.sn + 1)
+        .with_previous_event(&state.last_event_digest)
+        .with_keys(current_keys)
+        .with_threshold(&current_threshold)
+        .with_next_keys(new_next_keys)
+        .with_next_threshold(&new_next_threshold)
+        .with_witness_to_add(&witness_to_add)
+        .with_witness_to_remove(&witness_to_remove)
+        .with_witness_threshold(&SignatureThreshold::Simple(witness_threshold))
+        .build()
+        .map_err(|e| Error::EventGenerationError(e.to_string()))?
+        .encode()
+        .map_err(|e| Error::EventGenerationError(e.to_string()))?;
+    String::from_utf8(rot).map_err(|e| Error::EventGenerationError(e.to_string()))
+}
+
+/// Generates rotation event with next keys provided as digests. Used for
+/// group identifiers, whose participants publish only digests of their next
+/// keys.
+#[allow(clippy::too_many_arguments)]
+pub fn rotate_with_next_hashes(
+    state: IdentifierState,
+    current_keys: Vec<BasicPrefix>,
+    signature_threshold: &SignatureThreshold,
+    next_keys_hashes: Vec<SelfAddressingIdentifier>,
+    next_threshold: &SignatureThreshold,
+    witness_to_add: Vec<BasicPrefix>,
+    witness_to_remove: Vec<BasicPrefix>,
+    witness_threshold: u64,
+) -> Result<KeriEvent<KeyEvent>, Error> {
+    let threshold = SignatureThreshold::Simple(witness_threshold);
+    let witnesses_count = state
+        .witness_config
+        .witnesses
+        .iter()
+        .filter(|wit| !witness_to_remove.contains(wit))
+        .chain(witness_to_add.iter())
+        .count();
+    if !threshold.is_achievable(witnesses_count) {
+        return Err(Error::EventGenerationError(
+            "Improper witness threshold".into(),
+        ));
+    };
+    EventMsgBuilder::new(EventTypeTag::Rot)
+        .with_prefix(&state.prefix)
+        .with_sn(state.sn + 1)
+        .with_previous_event(&state.last_event_digest)
+        .with_keys(current_keys)
+        .with_threshold(signature_threshold)
+        .with_next_keys_hashes(next_keys_hashes)
+        .with_next_threshold(next_threshold)
+        .with_witness_to_add(&witness_to_add)
+        .with_witness_to_remove(&witness_to_remove)
+        .with_witness_threshold(&threshold)
+        .build()
+        .map_err(|e| Error::EventGenerationError(e.to_string()))
+}
+
 fn make_rotation(
     state: IdentifierState,
     current_keys: Vec<BasicPrefix>,
@@ -127,7 +338,7 @@ fn make_rotation(
     new_next_threshold: u64,
     witness_to_add: Vec<BasicPrefix>,
     witness_to_remove: Vec<BasicPrefix>,
-    witness_threshold: u64,
+    witness_threshold: &SignatureThreshold,
 ) -> Result<KeriEvent<KeyEvent>, Error> {
     EventMsgBuilder::new(EventTypeTag::Rot)
         .with_prefix(&state.prefix)
@@ -137,7 +348,7 @@ fn make_rotation(
         .with_next_keys(new_next_keys)
         .with_witness_to_add(&witness_to_add)
         .with_witness_to_remove(&witness_to_remove)
-        .with_witness_threshold(&SignatureThreshold::Simple(witness_threshold))
+        .with_witness_threshold(witness_threshold)
         .with_next_threshold(&SignatureThreshold::Simple(new_next_threshold))
         .build()
         .map_err(|e| Error::EventGenerationError(e.to_string()))
@@ -149,8 +360,7 @@ pub fn anchor(
 ) -> Result<String, Error> {
     let seal_list = payload
         .iter()
-        .map(|seal| Seal::Digest(DigestSeal::new(seal.to_owned())))
-        .collect();
+        .map(|digest| DigestSeal::new(digest.to_owned()));
     let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
         .with_prefix(&state.prefix)
         .with_sn(state.sn + 1)
@@ -171,7 +381,7 @@ pub fn anchor_with_seal(
         .with_prefix(&state.prefix)
         .with_sn(state.sn + 1)
         .with_previous_event(&state.last_event_digest)
-        .with_seal(seal_list.to_owned())
+        .with_seal(seal_list.iter().cloned())
         .build()
         .map_err(|e| Error::EventGenerationError(e.to_string()))?;
     Ok(ev)
@@ -210,6 +420,18 @@ pub fn exchange(
     receipient: &IdentifierPrefix,
     data: &KeriEvent<KeyEvent>,
     topic: ForwardTopic,
+) -> ExchangeMessage {
+    exchange_with_metadata(receipient, data, topic, None)
+}
+
+/// Generates exn message with initiator's metadata attached, so recipient
+/// gets context of the request.
+#[cfg(feature = "mailbox")]
+pub fn exchange_with_metadata(
+    receipient: &IdentifierPrefix,
+    data: &KeriEvent<KeyEvent>,
+    topic: ForwardTopic,
+    metadata: Option<ExchangeMetadata>,
 ) -> ExchangeMessage {
     use said::derivation::HashFunctionCode;
     use said::version::format::SerializationFormats;
@@ -220,6 +442,7 @@ pub fn exchange(
         args: FwdArgs {
             recipient_id: receipient.clone(),
             topic,
+            metadata,
         },
         to_forward: data.clone(),
     });
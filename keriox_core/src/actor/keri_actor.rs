@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    oobi::{LocationScheme, Oobi, Role},
+    prefix::{BasicPrefix, IdentifierPrefix},
+};
+
+use super::{error::ActorError, simple_controller::PossibleResponse};
+
+/// Interface common to KERI actors, like witness and watcher, so they can be
+/// hosted the same way regardless of their type. All streams are CESR
+/// encoded and responses are ready to be sent back to the requester.
+#[async_trait::async_trait]
+pub trait KeriActor: Send + Sync {
+    /// Non transferable identifier of actor.
+    fn prefix(&self) -> BasicPrefix;
+
+    /// Location of actor, as it is introduced to others.
+    fn oobi(&self) -> LocationScheme;
+
+    /// Processes key events with their signatures and receipts.
+    fn process_notices(&self, stream: &[u8]) -> Result<(), ActorError>;
+
+    /// Processes signed replies, like location schemes and end roles.
+    fn process_replies(&self, stream: &[u8]) -> Result<(), ActorError>;
+
+    /// Processes signed exchange messages.
+    fn process_exchanges(&self, stream: &[u8]) -> Result<(), ActorError>;
+
+    /// Answers signed KEL and mailbox queries. Queries that don't require
+    /// response are omitted from result.
+    async fn process_queries(&self, stream: &[u8]) -> Result<Vec<PossibleResponse>, ActorError>;
+
+    /// Signed location schemes of `eid` known to actor.
+    fn location_oobis(&self, eid: &IdentifierPrefix) -> Result<Vec<u8>, ActorError>;
+
+    /// Signed end role of `eid` for `cid`, followed by location schemes of
+    /// `eid`. Actors may add other data needed to verify `cid`, e.g. its KEL.
+    fn role_oobis(
+        &self,
+        cid: &IdentifierPrefix,
+        role: Role,
+        eid: &IdentifierPrefix,
+    ) -> Result<Vec<u8>, ActorError>;
+
+    /// Resolves `oobi`. Fails if actor doesn't resolve OOBIs on request.
+    async fn resolve_oobi(&self, oobi: Oobi) -> Result<(), ActorError>;
+
+    /// Answers requests to endpoints specific to actor. It's called before
+    /// common endpoints are matched, so it can also override them. Returns
+    /// `None` if actor doesn't serve requested path.
+    async fn handle_request(
+        &self,
+        _req: &ActorRequest,
+    ) -> Option<Result<ActorResponse, ActorError>> {
+        None
+    }
+}
+
+/// Request to actor endpoint, independent of protocol it was received with,
+/// so the same routes can be served over HTTP and framed TCP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorRequest {
+    pub method: String,
+    /// Path relative to actor's root, e.g. `/process` or `/oobi/{eid}`.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl ActorRequest {
+    pub fn new(method: &str, path: impl Display, body: Vec<u8>) -> Self {
+        Self {
+            method: method.to_string(),
+            path: format!("/{}", path.to_string().trim_start_matches('/')),
+            headers: vec![],
+            body,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Display) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns value of header, which name matches case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns path split into segments, without leading slash.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.trim_start_matches('/').split('/').collect()
+    }
+}
+
+/// Body of [`ActorResponse`]. Long bodies, like KELs, can be produced in
+/// chunks, so they aren't loaded into memory at once.
+pub enum ResponseBody {
+    Full(Vec<u8>),
+    Chunked(Box<dyn Iterator<Item = Result<Vec<u8>, Error>> + Send>),
+}
+
+/// Response of actor endpoint, independent of protocol it is sent with.
+pub struct ActorResponse {
+    /// HTTP status code.
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: ResponseBody,
+}
+
+impl ActorResponse {
+    pub const PLAIN_TEXT: &'static str = "text/plain; charset=utf-8";
+
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: ResponseBody::Full(body.into()),
+        }
+    }
+
+    /// Successful response with CESR stream or other text.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200, body).with_header("Content-Type", Self::PLAIN_TEXT)
+    }
+
+    /// Successful response with text produced in chunks.
+    pub fn chunked(chunks: impl Iterator<Item = Result<Vec<u8>, Error>> + Send + 'static) -> Self {
+        Self {
+            body: ResponseBody::Chunked(Box::new(chunks)),
+            ..Self::ok(vec![])
+        }
+    }
+
+    pub fn json(value: &impl Serialize) -> Result<Self, ActorError> {
+        let body =
+            serde_json::to_vec(value).map_err(|e| ActorError::GeneralError(e.to_string()))?;
+        Ok(Self::new(200, body).with_header("Content-Type", "application/json"))
+    }
+
+    /// Sets header, replacing previous value of header with the same name.
+    pub fn with_header(mut self, name: &str, value: impl Display) -> Self {
+        self.headers
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Collects chunks of body.
+    pub fn into_bytes(self) -> Result<Vec<u8>, Error> {
+        match self.body {
+            ResponseBody::Full(body) => Ok(body),
+            ResponseBody::Chunked(chunks) => chunks
+                .collect::<Result<Vec<_>, _>>()
+                .map(|chunks| chunks.concat()),
+        }
+    }
+}
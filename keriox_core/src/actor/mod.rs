@@ -1,5 +1,7 @@
-use std::convert::TryFrom;
+use std::{borrow::Cow, convert::TryFrom};
 
+use chrono::{DateTime, FixedOffset};
+use said::SelfAddressingIdentifier;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "oobi")]
@@ -14,6 +16,7 @@ use crate::{
         mailbox::MailboxRoute,
         query_event::QueryRoute,
         query_event::SignedQueryMessage,
+        replay::QueryReplayGuard,
         reply_event::{ReplyRoute, SignedReply},
         ReplyType,
     },
@@ -21,7 +24,7 @@ use crate::{
 use crate::{
     error::Error,
     event_message::{
-        cesr_adapter::ParseError,
+        cesr_adapter::{check_canonical, ParseError},
         signed_event_message::{Message, Notice},
     },
     prefix::IdentifierPrefix,
@@ -30,37 +33,58 @@ use crate::{
 #[cfg(feature = "mailbox")]
 use crate::{
     event_message::{signature::Signature, signed_event_message::SignedEventMessage},
-    mailbox::exchange::{Exchange, ExchangeMessage, ForwardTopic, SignedExchange},
+    mailbox::{
+        exchange::{Exchange, ExchangeMessage, ForwardTopic, SignedExchange},
+        MultisigMetadata,
+    },
 };
 pub use cesrox::cesr_proof::MaterialPath;
-use cesrox::parse_many;
+use cesrox::{parse_many, ParsedData};
 #[cfg(feature = "query")]
 use said::version::format::SerializationFormats;
 
 pub mod error;
 pub mod event_generator;
 #[cfg(all(feature = "query", feature = "oobi", feature = "mailbox"))]
+pub mod keri_actor;
+#[cfg(all(feature = "query", feature = "oobi", feature = "mailbox"))]
 pub mod simple_controller;
+pub mod stream_log;
+
+/// Returns stream in CESR 1.0 framing. With `cesr2` feature streams
+/// starting with KERI/ACDC 2.x genus version code are converted, see
+/// [`to_cesr1`](crate::event_message::cesr2::to_cesr1).
+fn cesr1_stream(stream: &[u8]) -> Result<Cow<'_, [u8]>, ParseError> {
+    #[cfg(feature = "cesr2")]
+    if crate::event_message::cesr2::is_cesr2(stream) {
+        return crate::event_message::cesr2::to_cesr1(stream).map(Cow::Owned);
+    }
+    Ok(Cow::Borrowed(stream))
+}
 
 pub fn parse_event_stream(stream: &[u8]) -> Result<Vec<Message>, ParseError> {
-    let (_rest, events) = parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    let stream = cesr1_stream(stream)?;
+    let (_rest, events) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     events.into_iter().map(Message::try_from).collect()
 }
 
 pub fn parse_notice_stream(stream: &[u8]) -> Result<Vec<Notice>, ParseError> {
-    let (_rest, notices) = parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    let stream = cesr1_stream(stream)?;
+    let (_rest, notices) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     notices.into_iter().map(Notice::try_from).collect()
 }
 
 #[cfg(any(feature = "query", feature = "oobi"))]
 pub fn parse_op_stream(stream: &[u8]) -> Result<Vec<Op>, ParseError> {
-    let (_rest, ops) = parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    let stream = cesr1_stream(stream)?;
+    let (_rest, ops) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     ops.into_iter().map(Op::try_from).collect()
 }
 
 #[cfg(any(feature = "query", feature = "oobi"))]
 pub fn parse_query_stream(stream: &[u8]) -> Result<Vec<SignedQueryMessage>, ParseError> {
-    let (_rest, queries) = parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    let stream = cesr1_stream(stream)?;
+    let (_rest, queries) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     queries
         .into_iter()
         .map(SignedQueryMessage::try_from)
@@ -69,20 +93,41 @@ pub fn parse_query_stream(stream: &[u8]) -> Result<Vec<SignedQueryMessage>, Pars
 
 #[cfg(any(feature = "query", feature = "oobi"))]
 pub fn parse_reply_stream(stream: &[u8]) -> Result<Vec<SignedReply>, ParseError> {
-    let (_rest, replies) = parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    let stream = cesr1_stream(stream)?;
+    let (_rest, replies) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     replies.into_iter().map(SignedReply::try_from).collect()
 }
 
 #[cfg(feature = "mailbox")]
 pub fn parse_exchange_stream(stream: &[u8]) -> Result<Vec<SignedExchange>, ParseError> {
+    let stream = cesr1_stream(stream)?;
     let (_rest, exchanges) =
-        parse_many(stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+        parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
     exchanges
         .into_iter()
         .map(SignedExchange::try_from)
         .collect()
 }
 
+/// Parses stream into elements of type `T`. In strict mode elements, which
+/// bytes differ from canonical serialization, are rejected with
+/// [`ParseError::NonCanonical`]. See [`check_canonical`].
+pub fn parse_stream<T>(stream: &[u8], strict: bool) -> Result<Vec<T>, ParseError>
+where
+    T: TryFrom<ParsedData, Error = ParseError>,
+{
+    let stream = cesr1_stream(stream)?;
+    let (_rest, data) = parse_many(&stream).map_err(|e| ParseError::CesrError(e.to_string()))?;
+    data.into_iter()
+        .map(|data| {
+            if strict {
+                check_canonical(&data.payload)?;
+            }
+            T::try_from(data)
+        })
+        .collect()
+}
+
 pub fn process_notice<P: Processor>(msg: Notice, processor: &P) -> Result<(), Error> {
     processor.process_notice(&msg)
 }
@@ -179,6 +224,15 @@ fn process_exn<D: EventDatabase>(
 
     match topic {
         ForwardTopic::Multisig => {
+            if let Some(metadata) = exn.data.data.get_metadata() {
+                storage.add_mailbox_multisig_metadata(
+                    receipient,
+                    MultisigMetadata {
+                        event_digest: to_forward.digest()?,
+                        metadata: metadata.clone(),
+                    },
+                )?;
+            }
             storage.add_mailbox_multisig(receipient, signed_to_forward)?;
         }
         ForwardTopic::Delegate => {
@@ -193,30 +247,49 @@ pub fn process_signed_query<D: EventDatabase>(
     qr: SignedQueryMessage,
     storage: &EventStorage<D>,
 ) -> Result<ReplyType, SignedQueryError> {
-    let verify = |data: &[u8], signature: Signature| -> Result<_, SignedQueryError> {
-        let ver_result = signature.verify(&data, storage)?;
-        if !ver_result {
-            Err(SignedQueryError::InvalidSignature)
-        } else {
-            Ok(())
-        }
-    };
+    verify_signed_query(&qr, storage)?;
+    process_verified_query(qr, storage)
+}
+
+/// Processes signed query like [`process_signed_query`], but also rejects
+/// queries with timestamp out of skew allowed by `guard` and queries that
+/// were processed before.
+#[cfg(feature = "query")]
+pub fn process_guarded_query<D: EventDatabase>(
+    qr: SignedQueryMessage,
+    storage: &EventStorage<D>,
+    guard: &QueryReplayGuard,
+) -> Result<ReplyType, SignedQueryError> {
+    verify_signed_query(&qr, storage)?;
+    guard.check(&qr)?;
+    process_verified_query(qr, storage)
+}
+
+#[cfg(feature = "query")]
+fn verify_signed_query<D: EventDatabase>(
+    qr: &SignedQueryMessage,
+    storage: &EventStorage<D>,
+) -> Result<(), SignedQueryError> {
+    let data = match qr {
+        SignedQueryMessage::KelQuery(kqry) => kqry.query.encode(),
+        SignedQueryMessage::MailboxQuery(mqry) => mqry.query.encode(),
+    }
+    .map_err(|_e| Error::VersionError)?;
+    if qr.signature().verify(&data, storage)? {
+        Ok(())
+    } else {
+        Err(SignedQueryError::InvalidSignature)
+    }
+}
+
+#[cfg(feature = "query")]
+fn process_verified_query<D: EventDatabase>(
+    qr: SignedQueryMessage,
+    storage: &EventStorage<D>,
+) -> Result<ReplyType, SignedQueryError> {
     match qr {
-        SignedQueryMessage::KelQuery(kqry) => {
-            let signature = kqry.signature;
-            let data = &kqry.query.encode().map_err(|_e| Error::VersionError)?;
-            // check signatures
-            verify(&data, signature)?;
-
-            // TODO check timestamps
-            // unpack and check what's inside
-            Ok(process_query(kqry.query.get_route(), storage)?)
-        }
+        SignedQueryMessage::KelQuery(kqry) => Ok(process_query(kqry.query.get_route(), storage)?),
         SignedQueryMessage::MailboxQuery(mqry) => {
-            let signature = mqry.signature;
-            let data = &mqry.query.encode().map_err(|_e| Error::VersionError)?;
-            // check signatures
-            verify(&data, signature)?;
             Ok(process_mailbox_query(&mqry.query.data.data, storage)?)
         }
     }
@@ -238,6 +311,12 @@ pub enum SignedQueryError {
 
     #[error("signature verification failed")]
     InvalidSignature,
+
+    #[error("query {digest} was already processed")]
+    Replayed { digest: SelfAddressingIdentifier },
+
+    #[error("query timestamp {timestamp} is out of allowed clock skew")]
+    TimestampOutOfSkew { timestamp: DateTime<FixedOffset> },
 }
 
 #[cfg(feature = "query")]
@@ -251,19 +330,36 @@ pub fn process_query<D: EventDatabase>(
             let state = storage
                 .get_state(&args.i)
                 .ok_or_else(|| QueryError::UnknownId { id: args.i.clone() })?;
-            let ksn = KeyStateNotice::new_ksn(state, SerializationFormats::JSON);
+            let ksn = KeyStateNotice::new_ksn(
+                state,
+                SerializationFormats::JSON,
+                storage.clock.now().into(),
+            );
             Ok(ReplyType::Ksn(ksn))
         }
         QueryRoute::Logs {
             reply_route: _,
             args,
         } => {
-            let response = match (args.s, args.limit) {
-                (None, _) => storage.get_kel_messages_with_receipts_all(&args.i)?,
-                (Some(sn), None) => storage
+            let response = match (args.after, args.s, args.limit) {
+                (Some(after), _, limit) => {
+                    let state = storage
+                        .get_state(&args.i)
+                        .ok_or_else(|| QueryError::UnknownId { id: args.i.clone() })?;
+                    if after >= state.sn {
+                        // Nothing new since `after`.
+                        Some(vec![])
+                    } else {
+                        let available = state.sn - after;
+                        let limit = limit.map_or(available, |limit| limit.min(available));
+                        storage.get_kel_messages_with_receipts_range(&args.i, after + 1, limit)?
+                    }
+                }
+                (None, None, _) => storage.get_kel_messages_with_receipts_all(&args.i)?,
+                (None, Some(sn), None) => storage
                     .get_event_at_sn(&args.i, sn)
                     .map(|event| vec![Notice::Event(event.signed_event_message)]),
-                (Some(sn), Some(limit)) => {
+                (None, Some(sn), Some(limit)) => {
                     storage.get_kel_messages_with_receipts_range(&args.i, sn, limit)?
                 }
             }
@@ -274,6 +370,22 @@ pub fn process_query<D: EventDatabase>(
 
             Ok(ReplyType::Kel(response))
         }
+        QueryRoute::LogsBetween { args, .. } => {
+            if storage.get_state(&args.i).is_none() {
+                return Err(QueryError::UnknownId { id: args.i.clone() });
+            }
+            let response = storage
+                .get_kel_messages_with_receipts_between(
+                    &args.i,
+                    args.from.with_timezone(&chrono::Local),
+                    args.to.with_timezone(&chrono::Local),
+                )?
+                .ok_or(Error::NotIndexedError)?
+                .into_iter()
+                .map(Message::Notice)
+                .collect();
+            Ok(ReplyType::Kel(response))
+        }
     }
 }
 
@@ -290,6 +402,27 @@ pub fn process_mailbox_query<D: EventDatabase>(
     }
 }
 
+/// Verifies that mailbox query is signed by querying identifier and saves
+/// its topic indexes as acknowledged by it. Following queries that set
+/// [`QueryArgsMbx::resume`](crate::query::mailbox::QueryArgsMbx::resume)
+/// skip acknowledged messages.
+#[cfg(feature = "mailbox")]
+pub fn process_mailbox_ack<D: EventDatabase>(
+    qr: crate::query::mailbox::SignedMailboxQuery,
+    storage: &EventStorage<D>,
+) -> Result<(), SignedQueryError> {
+    let args = qr.query.get_args();
+    if qr.signature.get_signer().as_ref() != Some(&args.pre) {
+        return Err(SignedQueryError::InvalidSignature);
+    }
+    let data = qr.query.encode().map_err(|_e| Error::VersionError)?;
+    if !qr.signature.verify(&data, storage)? {
+        return Err(SignedQueryError::InvalidSignature);
+    }
+    storage.acknowledge_mailbox(&args)?;
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error, Serialize, Deserialize)]
 pub enum QueryError {
     #[error(transparent)]
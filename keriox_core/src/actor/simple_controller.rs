@@ -5,7 +5,10 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::{database::EventDatabase, query::mailbox::SignedMailboxQuery};
+use crate::{
+    database::EventDatabase,
+    query::mailbox::{QueryTopics, SignedMailboxQuery},
+};
 use crate::{event_message::cesr_adapter::ParseError, query::query_event::LogsQueryArgs};
 use cesrox::{cesr_proof::MaterialPath, parse, primitives::CesrPrimitive};
 use said::derivation::{HashFunction, HashFunctionCode};
@@ -18,7 +21,7 @@ use super::{
 #[cfg(feature = "mailbox")]
 use crate::mailbox::{
     exchange::{Exchange, ForwardTopic, FwdArgs, SignedExchange},
-    MailboxResponse,
+    MailboxResponse, MultisigMetadata,
 };
 use crate::{
     actor::parse_event_stream,
@@ -44,7 +47,7 @@ use crate::{
             default_escrow_bus, DelegationEscrow, EscrowConfig, OutOfOrderEscrow,
             PartiallyWitnessedEscrow,
         },
-        event_storage::EventStorage,
+        event_storage::{EventStorage, KelEntry},
         Processor,
     },
     signer::KeyManager,
@@ -113,6 +116,10 @@ impl PossibleResponse {
                     receipt: String,
                     multisig: String,
                     delegate: String,
+                    #[serde(skip_serializing_if = "Vec::is_empty")]
+                    multisig_metadata: Vec<MultisigMetadata>,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    next: Option<QueryTopics>,
                 }
                 serde_json::to_vec(&GroupedResponse {
                     receipt: String::from_utf8(receipts_stream)
@@ -121,6 +128,8 @@ impl PossibleResponse {
                         .map_err(|e| Error::SerializationError(e.to_string()))?,
                     delegate: String::from_utf8(delegate_stream)
                         .map_err(|e| Error::SerializationError(e.to_string()))?,
+                    multisig_metadata: mbx.multisig_metadata.clone(),
+                    next: mbx.next.clone(),
                 })
                 .map_err(|e| Error::SerializationError(e.to_string()))?
             }
@@ -151,6 +160,10 @@ pub fn parse_mailbox_response(response: &str) -> Result<PossibleResponse, ParseE
         receipt: String,
         multisig: String,
         delegate: String,
+        #[serde(default)]
+        multisig_metadata: Vec<MultisigMetadata>,
+        #[serde(default)]
+        next: Option<QueryTopics>,
     }
     let res: GroupedResponse =
         serde_json::from_str(&response).map_err(|e| ParseError::DeserializeError(e.to_string()))?;
@@ -188,6 +201,8 @@ pub fn parse_mailbox_response(response: &str) -> Result<PossibleResponse, ParseE
         receipt: receipts,
         multisig: multisig,
         delegate,
+        multisig_metadata: res.multisig_metadata,
+        next: res.next,
     }))
 }
 
@@ -261,6 +276,18 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
         }
     }
 
+    /// Returns own KEL with receipts, without serializing it to CESR.
+    pub fn get_kel_typed(&self) -> Result<Vec<KelEntry>, Error> {
+        self.storage.get_kel_typed(self.prefix())
+    }
+
+    /// Streams own KEL with receipts from database.
+    pub fn kel_iter(&self) -> impl Iterator<Item = Result<KelEntry, Error>> {
+        self.storage
+            .get_kel_stream(self.prefix())
+            .with_typed_receipts()
+    }
+
     pub fn incept(
         &mut self,
         initial_witness: Option<Vec<BasicPrefix>>,
@@ -306,6 +333,7 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
             s: None,
             src: None,
             limit: None,
+            after: None,
         };
 
         let qry = QueryEvent::new_query(
@@ -659,6 +687,7 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
             args: FwdArgs {
                 recipient_id: receipient.clone(),
                 topic,
+                metadata: None,
             },
             to_forward: data.event_message.clone(),
         }
@@ -702,7 +731,32 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
 
     #[cfg(feature = "mailbox")]
     pub fn query_mailbox(&self, witness: &BasicPrefix) -> SignedQueryMessage {
-        use crate::query::mailbox::{MailboxQuery, MailboxRoute, QueryArgsMbx, QueryTopics};
+        use crate::query::mailbox::QueryTopics;
+
+        self.query_mailbox_from(
+            witness,
+            QueryTopics {
+                credential: 0,
+                receipt: 0,
+                replay: 0,
+                multisig: 0,
+                delegate: 0,
+                reply: 0,
+            },
+            false,
+        )
+    }
+
+    /// Queries mailbox for messages starting from `topics` indexes, or from
+    /// indexes acknowledged before if `resume` is set and they are further.
+    #[cfg(feature = "mailbox")]
+    pub fn query_mailbox_from(
+        &self,
+        witness: &BasicPrefix,
+        topics: crate::query::mailbox::QueryTopics,
+        resume: bool,
+    ) -> SignedQueryMessage {
+        use crate::query::mailbox::{MailboxQuery, MailboxRoute, QueryArgsMbx};
 
         let qry_msg = MailboxQuery::new_query(
             MailboxRoute::Mbx {
@@ -710,14 +764,9 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
                     i: self.prefix.clone(),
                     pre: self.prefix.clone(),
                     src: IdentifierPrefix::Basic(witness.clone()),
-                    topics: QueryTopics {
-                        credential: 0,
-                        receipt: 0,
-                        replay: 0,
-                        multisig: 0,
-                        delegate: 0,
-                        reply: 0,
-                    },
+                    topics,
+                    resume,
+                    limit: None,
                 },
                 reply_route: "".to_string(),
             },
@@ -760,6 +809,8 @@ impl<K: KeyManager, D: EventDatabase + Send + Sync + 'static> SimpleController<K
                                 delegate: 0,
                                 reply: 0,
                             },
+                            resume: false,
+                            limit: None,
                         },
                         reply_route: "".to_string(),
                     },
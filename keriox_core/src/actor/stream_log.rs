@@ -0,0 +1,214 @@
+//! Debug log of processed inbound streams. Every record contains plaintext
+//! summary of parsed messages followed by raw stream, so what client actually
+//! sent can be reconstructed.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use cesrox::parse_many;
+
+use crate::event_message::signed_event_message::{Message, Notice};
+
+/// Rotating file sink of processed streams. It's disabled until
+/// [`StreamLog::set_enabled`] is called, so it can be toggled at runtime.
+pub struct StreamLog {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    enabled: AtomicBool,
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl StreamLog {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_size: 10 * 1024 * 1024,
+            max_files: 5,
+            enabled: AtomicBool::new(false),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Sets size in bytes after which log file is rotated.
+    pub fn with_max_size(self, max_size: u64) -> Self {
+        Self { max_size, ..self }
+    }
+
+    /// Sets number of rotated log files to keep.
+    pub fn with_max_files(self, max_files: usize) -> Self {
+        Self { max_files, ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables recording. Log file is closed when disabled.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            if let Ok(mut file) = self.file.lock() {
+                *file = None;
+            }
+        }
+    }
+
+    /// Records stream of given kind (e.g. `notices` or `queries`), if log is
+    /// enabled.
+    pub fn record(&self, kind: &str, stream: &[u8]) -> Result<(), std::io::Error> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let mut record = format!(
+            "--- {} {} {} bytes\n",
+            chrono::Utc::now().to_rfc3339(),
+            kind,
+            stream.len()
+        )
+        .into_bytes();
+        for line in summarize(stream) {
+            record.extend_from_slice(format!("# {}\n", line).as_bytes());
+        }
+        record.extend_from_slice(stream);
+        record.push(b'\n');
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "poisoned lock"))?;
+        if let Some((_, size)) = file.as_ref() {
+            if *size > 0 && size + record.len() as u64 > self.max_size {
+                *file = None;
+                self.rotate()?;
+            }
+        }
+        if file.is_none() {
+            let opened = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let size = opened.metadata()?.len();
+            *file = Some((opened, size));
+        }
+        if let Some((opened, size)) = file.as_mut() {
+            opened.write_all(&record)?;
+            *size += record.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Renames `<path>` to `<path>.1`, `<path>.1` to `<path>.2` and so on,
+    /// removing the oldest file.
+    fn rotate(&self) -> Result<(), std::io::Error> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+        let _ = fs::remove_file(numbered(self.max_files));
+        for n in (1..self.max_files).rev() {
+            if numbered(n).exists() {
+                fs::rename(numbered(n), numbered(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, numbered(1))
+    }
+}
+
+/// Returns one line description of every message in stream.
+pub fn summarize(stream: &[u8]) -> Vec<String> {
+    match parse_many(stream) {
+        Ok((rest, parsed)) => {
+            let mut lines: Vec<_> = parsed
+                .into_iter()
+                .map(|data| match Message::try_from(data) {
+                    Ok(msg) => describe(&msg),
+                    Err(e) => format!("invalid message: {}", e),
+                })
+                .collect();
+            if !rest.is_empty() {
+                lines.push(format!("{} unparsed bytes", rest.len()));
+            }
+            lines
+        }
+        Err(e) => vec![format!("not a CESR stream: {}", e)],
+    }
+}
+
+fn describe(msg: &Message) -> String {
+    match msg {
+        Message::Notice(Notice::Event(event)) => format!(
+            "{} {} sn {}, {} signatures",
+            format!("{:?}", event.event_message.event_type).to_lowercase(),
+            event.event_message.data.get_prefix(),
+            event.event_message.data.get_sn(),
+            event.signatures.len()
+        ),
+        Message::Notice(Notice::NontransferableRct(rct)) => format!(
+            "rct {} sn {}, {} witness signatures",
+            rct.body.prefix,
+            rct.body.sn,
+            rct.signatures.len()
+        ),
+        Message::Notice(Notice::TransferableRct(rct)) => format!(
+            "rct {} sn {} by {}",
+            rct.body.prefix, rct.body.sn, rct.validator_seal.prefix
+        ),
+        #[cfg(any(feature = "query", feature = "oobi"))]
+        Message::Op(op) => match op {
+            #[cfg(feature = "mailbox")]
+            crate::event_message::signed_event_message::Op::Exchange(_) => "exn".to_string(),
+            #[cfg(feature = "oobi")]
+            crate::event_message::signed_event_message::Op::Reply(_) => "rpy".to_string(),
+            #[cfg(feature = "query")]
+            crate::event_message::signed_event_message::Op::Query(_) => "qry".to_string(),
+        },
+    }
+}
+
+#[test]
+fn test_stream_log() -> Result<(), std::io::Error> {
+    let dir = tempfile::Builder::new()
+        .prefix("test-stream-log")
+        .tempdir()?;
+    let path = dir.path().join("streams.log");
+    let log = StreamLog::new(&path).with_max_size(600).with_max_files(2);
+
+    let stream = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP"#;
+
+    // Nothing is written until log is enabled.
+    log.record("notices", stream)?;
+    assert!(!path.exists());
+
+    log.set_enabled(true);
+    log.record("notices", stream)?;
+    let content = fs::read_to_string(&path)?;
+    assert!(
+        content.contains("# icp EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf sn 0, 1 signatures")
+    );
+    assert!(content.contains(std::str::from_utf8(stream).unwrap()));
+
+    // Garbage is recorded too.
+    log.record("notices", b"garbage")?;
+    assert!(fs::read_to_string(&path)?.contains("garbage"));
+
+    // Files are rotated and only `max_files` of them are kept.
+    for _ in 0..4 {
+        log.record("notices", stream)?;
+    }
+    assert!(dir.path().join("streams.log.1").exists());
+    assert!(dir.path().join("streams.log.2").exists());
+    assert!(!dir.path().join("streams.log.3").exists());
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+
+/// Source of current time. Used for escrow timeouts and timestamps, so it
+/// can be replaced in tests.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// Clock returning system time.
+#[derive(Debug, Default, Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Clock that changes only when asked to. Allows to move time forward
+/// deterministically in tests.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: RwLock<DateTime<Local>>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Local>) -> Self {
+        Self {
+            now: RwLock::new(start),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Local>) {
+        *self.now.write().unwrap() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.write().unwrap();
+        *now = *now + chrono::Duration::from_std(duration).unwrap();
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(Local::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.now.read().unwrap()
+    }
+}
+
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+#[test]
+fn test_manual_clock() {
+    let clock = ManualClock::default();
+    let start = clock.now();
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(clock.now() - start, chrono::Duration::seconds(10));
+    clock.set(start);
+    assert_eq!(clock.now(), start);
+}
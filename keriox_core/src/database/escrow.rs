@@ -1,8 +1,13 @@
+use chrono::{DateTime, Local};
+use said::SelfAddressingIdentifier;
 use serde::{de::DeserializeOwned, Serialize};
 use sled::{Db, Tree};
 use std::{path::Path, sync::Arc, time::Duration};
 
-use crate::prefix::IdentifierPrefix;
+use crate::{
+    clock::{system_clock, Clock},
+    prefix::IdentifierPrefix,
+};
 
 use super::{
     sled::DbError,
@@ -10,12 +15,88 @@ use super::{
     timestamped::Timestamped,
 };
 
+/// Returns digest, which escrowed value is indexed by.
+pub type DigestOf<T> = fn(&T) -> Option<SelfAddressingIdentifier>;
+
+/// Escrowed values keyed by digest, so value can be found without reading
+/// all values escrowed for identifier. Values are stored together with key
+/// of identifier they were escrowed for.
+struct DigestIndex<T> {
+    tree: Tree,
+    digest_of: DigestOf<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + PartialEq> DigestIndex<T> {
+    fn get(
+        &self,
+        digest: &SelfAddressingIdentifier,
+    ) -> Result<Vec<(u64, Timestamped<T>)>, DbError> {
+        match self.tree.get(digest.to_string())? {
+            Some(values) => Ok(serde_cbor::from_slice(&values)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn put(
+        &self,
+        digest: &SelfAddressingIdentifier,
+        values: Vec<(u64, Timestamped<T>)>,
+    ) -> Result<(), DbError> {
+        if values.is_empty() {
+            self.tree.remove(digest.to_string())?;
+        } else {
+            self.tree
+                .insert(digest.to_string(), serde_cbor::to_vec(&values)?)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&self, id_key: u64, value: Timestamped<T>) -> Result<(), DbError> {
+        if let Some(digest) = (self.digest_of)(&value.signed_event_message) {
+            let mut values = self.get(&digest)?;
+            values.push((id_key, value));
+            self.put(&digest, values)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, id_key: u64, value: &T) -> Result<(), DbError> {
+        if let Some(digest) = (self.digest_of)(value) {
+            let values = self
+                .get(&digest)?
+                .into_iter()
+                .filter(|(key, e)| *key != id_key || &e.signed_event_message != value)
+                .collect();
+            self.put(&digest, values)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces index content with values of `escrow`, so values added or
+    /// removed while index wasn't updated (e.g. by older version, or when
+    /// process was interrupted between writes) are indexed correctly.
+    fn rebuild(&self, escrow: &SledEventTreeVec<Timestamped<T>>) -> Result<(), DbError> {
+        self.tree.clear()?;
+        if let Some(keys) = escrow.get_keys() {
+            for id_key in keys {
+                if let Some(mut values) = escrow.iter_values(id_key) {
+                    values.try_for_each(|value| self.insert(id_key, value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Collection of values, which removes values older than `duration`
 ///
 pub struct Escrow<T> {
+    name: Vec<u8>,
     escrow_db: Arc<EscrowDb>,
     tree: SledEventTreeVec<Timestamped<T>>,
     duration: Duration,
+    clock: Arc<dyn Clock>,
+    digest_index: Option<DigestIndex<T>>,
 }
 
 impl<T: Serialize + DeserializeOwned + PartialEq + Clone> Escrow<T> {
@@ -24,16 +105,49 @@ impl<T: Serialize + DeserializeOwned + PartialEq + Clone> Escrow<T> {
         V: AsRef<[u8]>,
     {
         Self {
-            tree: SledEventTreeVec::new(escrow_db.add_bucket(name).unwrap()),
+            name: name.as_ref().to_vec(),
+            tree: SledEventTreeVec::new(escrow_db.add_bucket(&name).unwrap()),
             duration,
             escrow_db,
+            clock: system_clock(),
+            digest_index: None,
+        }
+    }
+
+    /// Indexes escrowed values by digest returned by `digest_of`, so they
+    /// can be found with [`Escrow::get_by_digest`]. Index is kept in
+    /// separate bucket and rebuilt from escrowed values when escrow is
+    /// opened, so it can't diverge from them.
+    pub fn with_digest_index(self, digest_of: DigestOf<T>) -> Self {
+        let index_name = [self.name.as_slice(), b".digests".as_slice()].concat();
+        let index = DigestIndex {
+            tree: self.escrow_db.add_bucket(index_name).unwrap(),
+            digest_of,
+        };
+        index.rebuild(&self.tree).unwrap();
+        Self {
+            digest_index: Some(index),
+            ..self
         }
     }
 
+    /// Sets clock used for timestamping escrowed values and checking if they
+    /// are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
     pub fn add(&self, id: &IdentifierPrefix, event: T) -> Result<(), DbError> {
-        let event = event.into();
+        let event = Timestamped::new_at(event, self.clock.now());
         if !self.tree.contains_value(&event) {
-            self.tree.push(self.escrow_db.get_key(id)?, event)?;
+            let id_key = self.escrow_db.get_key(id)?;
+            if let Some(index) = &self.digest_index {
+                index.insert(
+                    id_key,
+                    Timestamped::new_at(event.signed_event_message.clone(), event.timestamp),
+                )?;
+            }
+            self.tree.push(id_key, event)?;
             self.escrow_db.db.flush()?;
             Ok(())
         } else {
@@ -44,8 +158,15 @@ impl<T: Serialize + DeserializeOwned + PartialEq + Clone> Escrow<T> {
     fn cleanup(&self, id: u64) -> Result<(), DbError> {
         if let Some(data) = self.tree.iter_values(id) {
             // Remove stale events
-            let new_data = data.filter(|e| !e.is_stale(self.duration).unwrap());
-            self.tree.put(id, new_data.collect())?;
+            let now = self.clock.now();
+            let (stale, fresh): (Vec<_>, Vec<_>) =
+                data.partition(|e| e.is_stale_at(now, self.duration).unwrap());
+            if let Some(index) = &self.digest_index {
+                stale
+                    .iter()
+                    .try_for_each(|e| index.remove(id, &e.signed_event_message))?;
+            }
+            self.tree.put(id, fresh)?;
         };
         Ok(())
     }
@@ -59,13 +180,69 @@ impl<T: Serialize + DeserializeOwned + PartialEq + Clone> Escrow<T> {
             .map(|t| t.map(|t| t.signed_event_message))
     }
 
+    /// Returns escrowed values of identifier together with time they were
+    /// escrowed at.
+    pub fn get_timestamped(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Option<impl DoubleEndedIterator<Item = (T, DateTime<Local>)>> {
+        let id_key = self.escrow_db.get_key(id).ok()?;
+        self.cleanup(id_key).ok();
+        self.tree
+            .iter_values(id_key)
+            .map(|t| t.map(|t| (t.signed_event_message, t.timestamp)))
+    }
+
     pub fn remove(&self, id: &IdentifierPrefix, event: &T) -> Result<(), DbError> {
         let id_key = self.escrow_db.get_key(id)?;
-        self.tree.remove(id_key, &event.into())?;
+        // Timestamp isn't compared, so any time can be used here.
+        self.tree.remove(
+            id_key,
+            &Timestamped::new(event.clone(), self.clock.as_ref()),
+        )?;
+        if let Some(index) = &self.digest_index {
+            index.remove(id_key, event)?;
+        }
         self.escrow_db.db.flush()?;
         Ok(())
     }
 
+    /// Returns not stale values with given digest. Returns `None` if escrow
+    /// isn't indexed by digest, see [`Escrow::with_digest_index`].
+    pub fn get_by_digest(
+        &self,
+        digest: &SelfAddressingIdentifier,
+    ) -> Option<impl DoubleEndedIterator<Item = T>> {
+        let values = self.digest_index.as_ref()?.get(digest).ok()?;
+        let (now, duration) = (self.clock.now(), self.duration);
+        Some(
+            values
+                .into_iter()
+                .filter(move |(_, e)| !e.is_stale_at(now, duration).unwrap_or(true))
+                .map(|(_, e)| e.signed_event_message),
+        )
+    }
+
+    /// Returns not stale values with given digest, escrowed for identifier
+    /// `id`. Returns `None` if escrow isn't indexed by digest.
+    pub fn get_by_id_and_digest(
+        &self,
+        id: &IdentifierPrefix,
+        digest: &SelfAddressingIdentifier,
+    ) -> Option<impl DoubleEndedIterator<Item = T>> {
+        let id_key = self.escrow_db.get_key(id).ok()?;
+        let values = self.digest_index.as_ref()?.get(digest).ok()?;
+        let (now, duration) = (self.clock.now(), self.duration);
+        Some(
+            values
+                .into_iter()
+                .filter(move |(key, e)| {
+                    *key == id_key && !e.is_stale_at(now, duration).unwrap_or(true)
+                })
+                .map(|(_, e)| e.signed_event_message),
+        )
+    }
+
     pub fn get_all(&self) -> Option<impl DoubleEndedIterator<Item = T>> {
         // TODO should return result?
         let keys = self.tree.get_keys().unwrap();
@@ -74,6 +251,22 @@ impl<T: Serialize + DeserializeOwned + PartialEq + Clone> Escrow<T> {
             .get_all()
             .map(|t| t.map(|t| t.signed_event_message))
     }
+
+    /// Returns number of escrowed values. Stale values are removed first.
+    pub fn count(&self) -> usize {
+        self.get_all().map_or(0, |values| values.count())
+    }
+
+    /// Returns all escrowed values together with time they were escrowed at.
+    pub fn get_all_timestamped(
+        &self,
+    ) -> Option<impl DoubleEndedIterator<Item = (T, DateTime<Local>)>> {
+        let keys = self.tree.get_keys().unwrap();
+        keys.for_each(|key| self.cleanup(key).unwrap());
+        self.tree
+            .get_all()
+            .map(|t| t.map(|t| (t.signed_event_message, t.timestamp)))
+    }
 }
 
 pub struct EscrowDb {
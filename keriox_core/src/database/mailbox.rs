@@ -1,12 +1,21 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sled::Db;
 
-use crate::event_message::signed_event_message::{
-    SignedEventMessage, SignedNontransferableReceipt,
+use crate::{
+    clock::{system_clock, Clock},
+    event_message::signed_event_message::{SignedEventMessage, SignedNontransferableReceipt},
+    mailbox::MultisigMetadata,
+    prefix::IdentifierPrefix,
+    query::mailbox::QueryTopics,
 };
 
-use super::{sled::DbError, tables::SledEventTreeVec, timestamped::TimestampedSignedEventMessage};
+use super::{
+    sled::DbError,
+    tables::{SledEventTree, SledEventTreeVec},
+    timestamped::{Timestamped, TimestampedSignedEventMessage},
+};
 
 pub struct MailboxData {
     db: Arc<sled::Db>,
@@ -14,6 +23,20 @@ pub struct MailboxData {
     mailbox_replies: SledEventTreeVec<SignedEventMessage>,
     mailbox_multisig: SledEventTreeVec<TimestampedSignedEventMessage>,
     mailbox_delegate: SledEventTreeVec<TimestampedSignedEventMessage>,
+    mailbox_multisig_metadata: SledEventTreeVec<MultisigMetadata>,
+    mailbox_cursors: SledEventTreeVec<MailboxCursor>,
+    /// Number of messages removed from the beginning of each topic, so
+    /// indexes of remaining messages don't change.
+    mailbox_evicted: SledEventTree<QueryTopics>,
+    /// Used for timestamping forwarded messages and checking their age.
+    clock: Arc<dyn Clock>,
+}
+
+/// Topic indexes of mailbox messages acknowledged by recipient.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MailboxCursor {
+    pub recipient: IdentifierPrefix,
+    pub topics: QueryTopics,
 }
 
 impl MailboxData {
@@ -23,10 +46,20 @@ impl MailboxData {
             mailbox_replies: SledEventTreeVec::new(db.open_tree(b"mbxrpy")?),
             mailbox_multisig: SledEventTreeVec::new(db.open_tree(b"mbxm")?),
             mailbox_delegate: SledEventTreeVec::new(db.open_tree(b"mbxd")?),
+            mailbox_multisig_metadata: SledEventTreeVec::new(db.open_tree(b"mbxmmd")?),
+            mailbox_cursors: SledEventTreeVec::new(db.open_tree(b"mbxcur")?),
+            mailbox_evicted: SledEventTree::new(db.open_tree(b"mbxevc")?),
             db,
+            clock: system_clock(),
         })
     }
 
+    /// Sets clock used for timestamping forwarded messages and checking if
+    /// they expired.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
     pub fn add_mailbox_receipt(
         &self,
         key: u64,
@@ -66,8 +99,11 @@ impl MailboxData {
     }
 
     pub fn add_mailbox_multisig(&self, key: u64, event: SignedEventMessage) -> Result<(), DbError> {
-        self.mailbox_multisig.push(key, event.into())?;
-        self.db.flush()?;
+        if !Self::is_forwarded(&self.mailbox_multisig, key, &event)? {
+            self.mailbox_multisig
+                .push(key, Timestamped::new(event, self.clock.as_ref()))?;
+            self.db.flush()?;
+        }
         Ok(())
     }
 
@@ -78,20 +114,218 @@ impl MailboxData {
         self.mailbox_multisig.iter_values(key)
     }
 
+    pub fn add_mailbox_multisig_metadata(
+        &self,
+        key: u64,
+        metadata: MultisigMetadata,
+    ) -> Result<(), DbError> {
+        if !self.mailbox_multisig_metadata.contains_value(&metadata) {
+            self.mailbox_multisig_metadata.push(key, metadata)?;
+            self.db.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn get_mailbox_multisig_metadata(
+        &self,
+        key: u64,
+    ) -> Option<impl DoubleEndedIterator<Item = MultisigMetadata>> {
+        self.mailbox_multisig_metadata.iter_values(key)
+    }
+
     pub fn add_mailbox_delegate(
         &self,
         key: u64,
         delegated: SignedEventMessage,
     ) -> Result<(), DbError> {
-        self.mailbox_delegate.push(key, delegated.into())?;
-        self.db.flush()?;
+        if !Self::is_forwarded(&self.mailbox_delegate, key, &delegated)? {
+            self.mailbox_delegate
+                .push(key, Timestamped::new(delegated, self.clock.as_ref()))?;
+            self.db.flush()?;
+        }
         Ok(())
     }
 
+    /// Checks if recipient's mailbox topic already contains `event` with all
+    /// of its signatures. Group participants often forward the same event,
+    /// so it's delivered only once. Event with new signatures is stored
+    /// again, because recipient needs them to collect threshold.
+    fn is_forwarded(
+        topic: &SledEventTreeVec<TimestampedSignedEventMessage>,
+        key: u64,
+        event: &SignedEventMessage,
+    ) -> Result<bool, DbError> {
+        Ok(topic.get(key)?.unwrap_or_default().iter().any(|stored| {
+            let stored = &stored.signed_event_message;
+            stored.event_message == event.event_message
+                && event
+                    .signatures
+                    .iter()
+                    .all(|sig| stored.signatures.contains(sig))
+        }))
+    }
+
     pub fn get_mailbox_delegate(
         &self,
         key: u64,
     ) -> Option<impl DoubleEndedIterator<Item = TimestampedSignedEventMessage>> {
         self.mailbox_delegate.iter_values(key)
     }
+
+    /// Removes all mailbox messages stored under `key`, with recipients'
+    /// cursors.
+    pub fn clear_mailbox(&self, key: u64) -> Result<(), DbError> {
+        self.mailbox_receipts.clear(key)?;
+        self.mailbox_replies.clear(key)?;
+        self.mailbox_multisig.clear(key)?;
+        self.mailbox_delegate.clear(key)?;
+        self.mailbox_multisig_metadata.clear(key)?;
+        self.mailbox_cursors.clear(key)?;
+        self.mailbox_evicted.insert(key, &QueryTopics::default())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_mailbox_cursor(
+        &self,
+        key: u64,
+        recipient: &IdentifierPrefix,
+    ) -> Result<Option<QueryTopics>, DbError> {
+        Ok(self.mailbox_cursors.get(key)?.and_then(|cursors| {
+            cursors
+                .into_iter()
+                .find(|cursor| &cursor.recipient == recipient)
+                .map(|cursor| cursor.topics)
+        }))
+    }
+
+    pub fn set_mailbox_cursor(
+        &self,
+        key: u64,
+        recipient: &IdentifierPrefix,
+        topics: QueryTopics,
+    ) -> Result<(), DbError> {
+        let mut cursors = self.mailbox_cursors.get(key)?.unwrap_or_default();
+        cursors.retain(|cursor| &cursor.recipient != recipient);
+        cursors.push(MailboxCursor {
+            recipient: recipient.clone(),
+            topics,
+        });
+        self.mailbox_cursors.put(key, cursors)?;
+        self.compact_mailbox(key)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Removes receipts, multisig and delegate messages acknowledged by all
+    /// recipients that have cursor in mailbox stored under `key`. Removed
+    /// messages are counted as evicted, so indexes of remaining ones don't
+    /// change.
+    fn compact_mailbox(&self, key: u64) -> Result<(), DbError> {
+        let acknowledged = match self
+            .mailbox_cursors
+            .get(key)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|cursor| cursor.topics)
+            .reduce(|acc, topics| acc.nearest(&topics))
+        {
+            Some(acknowledged) => acknowledged,
+            None => return Ok(()),
+        };
+        let mut evicted = self.get_mailbox_evicted(key)?;
+        let receipt = Self::remove_first(
+            &self.mailbox_receipts,
+            key,
+            acknowledged.receipt.saturating_sub(evicted.receipt),
+        )?;
+        let multisig = Self::remove_first(
+            &self.mailbox_multisig,
+            key,
+            acknowledged.multisig.saturating_sub(evicted.multisig),
+        )?;
+        let delegate = Self::remove_first(
+            &self.mailbox_delegate,
+            key,
+            acknowledged.delegate.saturating_sub(evicted.delegate),
+        )?;
+        if receipt.is_empty() && multisig.is_empty() && delegate.is_empty() {
+            return Ok(());
+        }
+        self.remove_multisig_metadata(key, &multisig)?;
+
+        evicted.receipt += receipt.len();
+        evicted.multisig += multisig.len();
+        evicted.delegate += delegate.len();
+        self.mailbox_evicted.insert(key, &evicted)?;
+        Ok(())
+    }
+
+    /// Removes and returns up to `count` messages from the beginning of
+    /// `topic`.
+    fn remove_first<T: Serialize + DeserializeOwned>(
+        topic: &SledEventTreeVec<T>,
+        key: u64,
+        count: usize,
+    ) -> Result<Vec<T>, DbError> {
+        let mut messages = topic.get(key)?.unwrap_or_default();
+        let removed: Vec<_> = messages.drain(..count.min(messages.len())).collect();
+        if !removed.is_empty() {
+            topic.put(key, messages)?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes metadata of removed multisig events.
+    fn remove_multisig_metadata(
+        &self,
+        key: u64,
+        removed: &[TimestampedSignedEventMessage],
+    ) -> Result<(), DbError> {
+        let digests: Vec<_> = removed
+            .iter()
+            .filter_map(|msg| msg.signed_event_message.event_message.digest().ok())
+            .collect();
+        if !digests.is_empty() {
+            let mut metadata = self.mailbox_multisig_metadata.get(key)?.unwrap_or_default();
+            metadata.retain(|md| !digests.contains(&md.event_digest));
+            self.mailbox_multisig_metadata.put(key, metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Returns number of messages evicted from the beginning of each topic.
+    pub fn get_mailbox_evicted(&self, key: u64) -> Result<QueryTopics, DbError> {
+        Ok(self.mailbox_evicted.get(key)?.unwrap_or_default())
+    }
+
+    /// Removes forwarded multisig and delegate messages stored longer than
+    /// `max_age`, with metadata of removed multisig events. Messages are
+    /// stored in order of arrival, so only the oldest ones are removed and
+    /// topic indexes of remaining messages stay the same. Returns number of
+    /// removed messages.
+    pub fn expire_mailbox_forwards(&self, key: u64, max_age: Duration) -> Result<usize, DbError> {
+        let expire = |topic: &SledEventTreeVec<TimestampedSignedEventMessage>| {
+            let expired = topic
+                .get(key)?
+                .unwrap_or_default()
+                .iter()
+                .take_while(|msg| msg.is_stale(self.clock.as_ref(), max_age).unwrap_or(false))
+                .count();
+            Self::remove_first(topic, key, expired)
+        };
+        let multisig = expire(&self.mailbox_multisig)?;
+        let delegate = expire(&self.mailbox_delegate)?;
+        if multisig.is_empty() && delegate.is_empty() {
+            return Ok(0);
+        }
+        self.remove_multisig_metadata(key, &multisig)?;
+
+        let mut evicted = self.get_mailbox_evicted(key)?;
+        evicted.multisig += multisig.len();
+        evicted.delegate += delegate.len();
+        self.mailbox_evicted.insert(key, &evicted)?;
+        self.db.flush()?;
+        Ok(multisig.len() + delegate.len())
+    }
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local};
 use timestamped::TimestampedSignedEventMessage;
 
 use crate::{
@@ -8,6 +9,7 @@ use crate::{
         },
     },
     prefix::IdentifierPrefix,
+    state::IdentifierState,
 };
 
 pub mod escrow;
@@ -15,6 +17,8 @@ pub mod escrow;
 pub mod mailbox;
 pub mod redb;
 pub mod sled;
+#[cfg(feature = "sqlite-db")]
+pub mod sqlite;
 pub(crate) mod tables;
 pub(crate) mod timestamped;
 
@@ -67,4 +71,62 @@ pub trait EventDatabase {
         &self,
         params: QueryParameters,
     ) -> Option<impl DoubleEndedIterator<Item = SignedNontransferableReceipt>>;
+
+    /// Like [`get_kel_finalized_events`](EventDatabase::get_kel_finalized_events),
+    /// but returns database error instead of `None`. `None` means that
+    /// identifier is unknown. By default errors can't be told apart from
+    /// unknown identifier.
+    fn try_get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Option<Vec<TimestampedSignedEventMessage>>, Self::Error> {
+        Ok(self
+            .get_kel_finalized_events(params)
+            .map(|events| events.collect()))
+    }
+
+    /// Like [`get_receipts_t`](EventDatabase::get_receipts_t), but returns
+    /// database error instead of `None`.
+    fn try_get_receipts_t(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<Transferable>, Self::Error> {
+        Ok(self
+            .get_receipts_t(params)
+            .map(|receipts| receipts.collect())
+            .unwrap_or_default())
+    }
+
+    /// Like [`get_receipts_nt`](EventDatabase::get_receipts_nt), but returns
+    /// database error instead of `None`.
+    fn try_get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<SignedNontransferableReceipt>, Self::Error> {
+        Ok(self
+            .get_receipts_nt(params)
+            .map(|receipts| receipts.collect())
+            .unwrap_or_default())
+    }
+
+    /// Returns events of identifier first seen between `from` (inclusive)
+    /// and `to` (exclusive), ordered by first seen time. Returns `None` if
+    /// database doesn't index events by first seen time.
+    fn get_kel_finalized_events_between(
+        &self,
+        _id: &IdentifierPrefix,
+        _from: DateTime<Local>,
+        _to: DateTime<Local>,
+    ) -> Option<Vec<TimestampedSignedEventMessage>> {
+        None
+    }
+
+    /// Returns state of identifier preceding the first event left in its KEL
+    /// after its older events were pruned, or `None` if they weren't.
+    fn get_pruned_state(
+        &self,
+        _id: &IdentifierPrefix,
+    ) -> Result<Option<IdentifierState>, Self::Error> {
+        Ok(None)
+    }
 }
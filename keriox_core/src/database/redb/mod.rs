@@ -1,4 +1,5 @@
 pub(crate) mod rkyv_adapter;
+pub mod sharded;
 
 /// Kel storage. (identifier, sn) -> event digest
 /// The `KELS` table links an identifier and sequence number to the digest of an event,
@@ -24,25 +25,52 @@ const NONTRANS_RCTS: MultimapTableDefinition<(&str, u64), &[u8]> =
 const TRANS_RCTS: MultimapTableDefinition<(&str, u64), &[u8]> =
     MultimapTableDefinition::new("trans_receipts");
 
-use std::{path::Path, u64};
-
-use redb::{Database, MultimapTableDefinition, TableDefinition};
+/// Key state history. (identifier, sn) -> serialized [`StateTransition`]
+/// The `STATE_HISTORY` table keeps compact record of establishment events,
+/// so changes of identifier's keys can be checked without reading its KEL.
+const STATE_HISTORY: TableDefinition<(&str, u64), &[u8]> = TableDefinition::new("state_history");
+
+/// First seen index. (identifier, first seen timestamp in microseconds, sn) -> event digest
+/// The `FIRST_SEEN` table orders identifier's events by time they were
+/// accepted into KEL, so events from given time range can be found without
+/// reading the whole KEL.
+const FIRST_SEEN: TableDefinition<(&str, i64, u64), &[u8]> = TableDefinition::new("first_seen");
+
+/// Pruned key states. identifier -> serialized [`IdentifierState`]
+/// The `PRUNED_STATES` table keeps state of identifier preceding the first
+/// event left in KEL after pruning, so key state can be computed without
+/// pruned events.
+const PRUNED_STATES: TableDefinition<&str, &[u8]> = TableDefinition::new("pruned_states");
+
+use std::{path::Path, sync::Arc, u64};
+
+use chrono::{DateTime, Local};
+use redb::{
+    Database, MultimapTableDefinition, ReadTransaction, ReadableMultimapTable, ReadableTable,
+    TableDefinition, WriteTransaction,
+};
 use rkyv::{
     api::high::HighSerializer, rancor::Failure, ser::allocator::ArenaHandle, util::AlignedVec,
 };
 use rkyv_adapter::deserialize_indexed_signatures;
-use said::{sad::SerializationFormats, SelfAddressingIdentifier};
+use said::{
+    derivation::{HashFunction, HashFunctionCode},
+    sad::SerializationFormats,
+    SelfAddressingIdentifier,
+};
 
 use crate::{
-    event::{receipt::Receipt, KeyEvent},
+    clock::{system_clock, Clock},
+    event::{event_data::EventData, receipt::Receipt, KeyEvent},
     event_message::{
         msg::KeriEvent,
         signature::{Nontransferable, Transferable},
         signed_event_message::{
-            SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
+            Notice, SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
         },
     },
     prefix::{IdentifierPrefix, IndexedSignature},
+    state::{IdentifierState, StateTransition},
 };
 use cesrox::primitives::CesrPrimitive;
 
@@ -74,6 +102,14 @@ pub enum RedbError {
     MissingDigest,
     #[error("Rkyv error: {0}")]
     Rkyv(#[from] rkyv::rancor::Error),
+    #[error("Database is inconsistent. KEL entries without event: {0}")]
+    Inconsistent(usize),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Number of shards must be positive")]
+    NoShards,
+    #[error("Directory contains {found} shards, expected {expected}")]
+    ShardCountMismatch { expected: usize, found: usize },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -82,10 +118,41 @@ pub enum KeyError {
     UnparsableSaid,
     #[error("Can't parse index in key")]
     UnparsableIndex,
+    #[error("Can't parse identifier in key")]
+    UnparsableId,
+    #[error("Can't parse timestamp in key")]
+    UnparsableTimestamp,
+}
+
+impl StateTransition {
+    /// Returns transition made by `event`, if it's establishment event.
+    /// `first_seen` is time when event was accepted into KEL.
+    pub(crate) fn from_event(
+        event: &KeriEvent<KeyEvent>,
+        first_seen: DateTime<Local>,
+    ) -> Result<Option<Self>, RedbError> {
+        let key_config = match &event.data.event_data {
+            EventData::Icp(icp) => &icp.key_config,
+            EventData::Dip(dip) => &dip.inception_data.key_config,
+            EventData::Rot(rot) | EventData::Drt(rot) => &rot.key_config,
+            EventData::Ixn(_) => return Ok(None),
+        };
+        let keys =
+            serde_json::to_vec(&key_config.public_keys).map_err(|_| RedbError::WrongValue)?;
+        Ok(Some(Self {
+            sn: event.data.sn,
+            event_type: event.event_type.clone(),
+            digest: event.digest().map_err(|_e| RedbError::MissingDigest)?,
+            first_seen,
+            keys_digest: HashFunction::from(HashFunctionCode::Blake3_256).derive(&keys),
+        }))
+    }
 }
 
 pub struct RedbDatabase {
     db: Database,
+    /// Used for first seen timestamps of events.
+    clock: Arc<dyn Clock>,
 }
 
 impl RedbDatabase {
@@ -99,10 +166,380 @@ impl RedbDatabase {
             write_txn.open_multimap_table(SIGS)?;
             write_txn.open_multimap_table(TRANS_RCTS)?;
             write_txn.open_multimap_table(NONTRANS_RCTS)?;
+            write_txn.open_table(STATE_HISTORY)?;
+            write_txn.open_table(FIRST_SEEN)?;
+            write_txn.open_table(PRUNED_STATES)?;
+        }
+        write_txn.commit()?;
+        Ok(Self {
+            db,
+            clock: system_clock(),
+        })
+    }
+
+    /// Sets clock used for first seen timestamps of events.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Returns identifiers that have at least one event in KEL table.
+    pub fn get_identifiers(&self) -> Result<Vec<IdentifierPrefix>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(KELS)?;
+        let mut ids: Vec<IdentifierPrefix> = vec![];
+        for entry in table.iter()? {
+            let (key, _value) = entry?;
+            let (id, _sn) = key.value();
+            if ids.last().map(|last| last.to_str() != id).unwrap_or(true) {
+                ids.push(
+                    id.parse()
+                        .map_err(|_e| RedbError::WrongKey(KeyError::UnparsableId))?,
+                );
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Returns key state transitions of identifier, ordered by sn.
+    pub fn get_state_history(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<StateTransition>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(STATE_HISTORY)?;
+        let id = id.to_str();
+        table
+            .range((id.as_str(), 0)..=(id.as_str(), u64::MAX))?
+            .map(|entry| {
+                let (_key, value) = entry?;
+                serde_json::from_slice(value.value()).map_err(|_| RedbError::WrongValue)
+            })
+            .collect()
+    }
+
+    /// Returns events of identifier first seen between `from` (inclusive)
+    /// and `to` (exclusive), ordered by first seen time. Timestamp of
+    /// returned event is its first seen time. Events replaced in KEL since
+    /// then (e.g. by recovery) are skipped.
+    pub fn get_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Result<Vec<TimestampedSignedEventMessage>, RedbError> {
+        let (from, to) = (from.timestamp_micros(), to.timestamp_micros());
+        if from >= to {
+            return Ok(vec![]);
+        }
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(FIRST_SEEN)?;
+        let kels = read_txn.open_table(KELS)?;
+        let id_str = id.to_str();
+        let mut events = vec![];
+        for entry in table.range((id_str.as_str(), from, 0)..(id_str.as_str(), to, 0))? {
+            let (key, digest) = entry?;
+            let (_id, timestamp, sn) = key.value();
+            match kels.get((id_str.as_str(), sn))? {
+                Some(current) if current.value() == digest.value() => (),
+                _ => continue,
+            };
+            let event = match self.get_event_by_serialized_key(digest.value())? {
+                Some(event) => event,
+                None => {
+                    let digest = rkyv_adapter::deserialize_said(digest.value())?;
+                    return Err(RedbError::NotFound(digest));
+                }
+            };
+            let signatures = self
+                .get_signatures((id_str.as_str(), sn))?
+                .map(|signatures| signatures.collect())
+                .unwrap_or_default();
+            let first_seen = DateTime::from_timestamp_micros(timestamp)
+                .ok_or(KeyError::UnparsableTimestamp)?
+                .with_timezone(&Local);
+            events.push(TimestampedSignedEventMessage::new_at(
+                SignedEventMessage::new(&event, signatures, None, None),
+                first_seen,
+            ));
+        }
+        Ok(events)
+    }
+}
+
+impl RedbDatabase {
+    /// Copies content of database into new database at `target` path. All
+    /// tables are read in single read transaction, so backup is consistent
+    /// snapshot of database and writes aren't blocked while copying.
+    pub fn backup(&self, target: &Path) -> Result<(), RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let backup = RedbDatabase::new(target)?;
+        let write_txn = backup.db.begin_write()?;
+        {
+            let source = read_txn.open_table(EVENTS)?;
+            let mut table = write_txn.open_table(EVENTS)?;
+            for entry in source.iter()? {
+                let (key, value) = entry?;
+                table.insert(key.value(), value.value())?;
+            }
+
+            for definition in [KELS, STATE_HISTORY] {
+                let source = read_txn.open_table(definition)?;
+                let mut table = write_txn.open_table(definition)?;
+                for entry in source.iter()? {
+                    let (key, value) = entry?;
+                    table.insert(key.value(), value.value())?;
+                }
+            }
+
+            let source = read_txn.open_table(FIRST_SEEN)?;
+            let mut table = write_txn.open_table(FIRST_SEEN)?;
+            for entry in source.iter()? {
+                let (key, value) = entry?;
+                table.insert(key.value(), value.value())?;
+            }
+
+            let source = read_txn.open_table(PRUNED_STATES)?;
+            let mut table = write_txn.open_table(PRUNED_STATES)?;
+            for entry in source.iter()? {
+                let (key, value) = entry?;
+                table.insert(key.value(), value.value())?;
+            }
+
+            for definition in [SIGS, NONTRANS_RCTS, TRANS_RCTS] {
+                copy_multimap_table(&read_txn, &write_txn, definition)?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Replaces database at `db_path` with backup created by
+    /// [`RedbDatabase::backup`]. Backup is checked for consistency first.
+    /// Database at `db_path` shouldn't be in use during restoring.
+    pub fn restore(backup_path: &Path, db_path: &Path) -> Result<Self, RedbError> {
+        {
+            let backup = RedbDatabase::new(backup_path)?;
+            let dangling = backup.check_consistency()?;
+            if !dangling.is_empty() {
+                return Err(RedbError::Inconsistent(dangling.len()));
+            }
+            if db_path.exists() {
+                std::fs::remove_file(db_path)?;
+            }
+            backup.backup(db_path)?;
+        }
+        RedbDatabase::new(db_path)
+    }
+
+    /// Checks if every KEL entry points to stored event. Returns identifiers
+    /// and sequence numbers of entries without event.
+    pub fn check_consistency(&self) -> Result<Vec<(IdentifierPrefix, u64)>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let kels = read_txn.open_table(KELS)?;
+        let events = read_txn.open_table(EVENTS)?;
+        let mut dangling = vec![];
+        for entry in kels.iter()? {
+            let (key, digest) = entry?;
+            if events.get(digest.value())?.is_none() {
+                let (id, sn) = key.value();
+                let id = id
+                    .parse()
+                    .map_err(|_e| RedbError::WrongKey(KeyError::UnparsableId))?;
+                dangling.push((id, sn));
+            }
+        }
+        Ok(dangling)
+    }
+
+    /// Returns receipts of identifier's events with sn lower than
+    /// `before_sn`, that would be removed by [`RedbDatabase::prune`]. If
+    /// `events` is true, the events with their signatures are returned too.
+    pub fn get_prunable(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        events: bool,
+    ) -> Result<Vec<Notice>, RedbError> {
+        let id_str = id.to_str();
+        let mut prunable = vec![];
+        let read_txn = self.db.begin_read()?;
+        let kels = read_txn.open_table(KELS)?;
+        let nontrans_table = read_txn.open_multimap_table(NONTRANS_RCTS)?;
+        let trans_table = read_txn.open_multimap_table(TRANS_RCTS)?;
+        for entry in kels.range((id_str.as_str(), 0)..(id_str.as_str(), before_sn))? {
+            let (key, digest) = entry?;
+            let (_id, sn) = key.value();
+            let digest = rkyv_adapter::deserialize_said(digest.value())?;
+            if events {
+                let event = self
+                    .get_event_by_digest(&digest)?
+                    .ok_or(RedbError::NotFound(digest.clone()))?;
+                let signatures = self
+                    .get_signatures((id_str.as_str(), sn))?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                prunable.push(Notice::Event(SignedEventMessage::new(
+                    &event, signatures, None, None,
+                )));
+            }
+            let receipt = Receipt::new(SerializationFormats::JSON, digest, id.clone(), sn);
+
+            let nontrans = nontrans_table
+                .get((id_str.as_str(), sn))?
+                .map(|value| Ok(rkyv_adapter::deserialize_nontransferable(value?.value())?))
+                .collect::<Result<Vec<_>, RedbError>>()?;
+            if !nontrans.is_empty() {
+                prunable.push(Notice::NontransferableRct(SignedNontransferableReceipt {
+                    body: receipt.clone(),
+                    signatures: nontrans,
+                }));
+            }
+            for value in trans_table.get((id_str.as_str(), sn))? {
+                let Transferable::Seal(seal, signatures) =
+                    rkyv_adapter::deserialize_transferable(value?.value())?;
+                prunable.push(Notice::TransferableRct(SignedTransferableReceipt::new(
+                    receipt.clone(),
+                    seal,
+                    signatures,
+                )));
+            }
+        }
+        Ok(prunable)
+    }
+
+    /// Removes nontransferable and transferable receipts of identifier's
+    /// events with sn lower than `before_sn`. If `state` is provided, events
+    /// and their signatures are removed too, and `state` is saved as state
+    /// of identifier preceding event at `before_sn`, so key state can still
+    /// be computed. Everything is removed in single write transaction.
+    pub fn prune(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        state: Option<&IdentifierState>,
+    ) -> Result<(), RedbError> {
+        let id_str = id.to_str();
+        let range = (id_str.as_str(), 0)..(id_str.as_str(), before_sn);
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut nontrans_table = write_txn.open_multimap_table(NONTRANS_RCTS)?;
+            let mut trans_table = write_txn.open_multimap_table(TRANS_RCTS)?;
+            let mut kels = write_txn.open_table(KELS)?;
+            let sns = kels
+                .range(range.clone())?
+                .map(|entry| Ok(entry?.0.value().1))
+                .collect::<Result<Vec<_>, RedbError>>()?;
+            for sn in &sns {
+                nontrans_table.remove_all((id_str.as_str(), *sn))?;
+                trans_table.remove_all((id_str.as_str(), *sn))?;
+            }
+            if let Some(state) = state {
+                let mut events = write_txn.open_table(EVENTS)?;
+                let mut sigs = write_txn.open_multimap_table(SIGS)?;
+                for sn in sns {
+                    if let Some(digest) = kels.remove((id_str.as_str(), sn))? {
+                        events.remove(digest.value())?;
+                    }
+                    sigs.remove_all((id_str.as_str(), sn))?;
+                }
+                let value = serde_json::to_vec(state).map_err(|_| RedbError::WrongValue)?;
+                write_txn
+                    .open_table(PRUNED_STATES)?
+                    .insert(id_str.as_str(), value.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Removes pruned state of identifier, e.g. after its pruned events
+    /// were put back into database.
+    pub fn remove_pruned_state(&self, id: &IdentifierPrefix) -> Result<(), RedbError> {
+        let write_txn = self.db.begin_write()?;
+        write_txn
+            .open_table(PRUNED_STATES)?
+            .remove(id.to_str().as_str())?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Copies events, signatures, receipts, key state history, first seen
+    /// index and pruned state of identifier into `target` database.
+    pub fn copy_identifier(
+        &self,
+        id: &IdentifierPrefix,
+        target: &RedbDatabase,
+    ) -> Result<(), RedbError> {
+        let id = id.to_str();
+        let range = (id.as_str(), 0)..=(id.as_str(), u64::MAX);
+        let read_txn = self.db.begin_read()?;
+        let write_txn = target.db.begin_write()?;
+        {
+            let source_kels = read_txn.open_table(KELS)?;
+            let source_events = read_txn.open_table(EVENTS)?;
+            let mut kels = write_txn.open_table(KELS)?;
+            let mut events = write_txn.open_table(EVENTS)?;
+            for entry in source_kels.range(range.clone())? {
+                let (key, digest) = entry?;
+                kels.insert(key.value(), digest.value())?;
+                if let Some(event) = source_events.get(digest.value())? {
+                    events.insert(digest.value(), event.value())?;
+                }
+            }
+
+            let source = read_txn.open_table(STATE_HISTORY)?;
+            let mut table = write_txn.open_table(STATE_HISTORY)?;
+            for entry in source.range(range.clone())? {
+                let (key, value) = entry?;
+                table.insert(key.value(), value.value())?;
+            }
+
+            let source = read_txn.open_table(FIRST_SEEN)?;
+            let mut table = write_txn.open_table(FIRST_SEEN)?;
+            let first_seen_range = (id.as_str(), i64::MIN, 0)..=(id.as_str(), i64::MAX, u64::MAX);
+            for entry in source.range(first_seen_range)? {
+                let (key, value) = entry?;
+                table.insert(key.value(), value.value())?;
+            }
+
+            let source = read_txn.open_table(PRUNED_STATES)?;
+            if let Some(state) = source.get(id.as_str())? {
+                write_txn
+                    .open_table(PRUNED_STATES)?
+                    .insert(id.as_str(), state.value())?;
+            }
+
+            for definition in [SIGS, NONTRANS_RCTS, TRANS_RCTS] {
+                let source = read_txn.open_multimap_table(definition)?;
+                let mut table = write_txn.open_multimap_table(definition)?;
+                for entry in source.range(range.clone())? {
+                    let (key, values) = entry?;
+                    for value in values {
+                        table.insert(key.value(), value?.value())?;
+                    }
+                }
+            }
         }
         write_txn.commit()?;
-        Ok(Self { db })
+        Ok(())
+    }
+}
+
+fn copy_multimap_table(
+    read_txn: &ReadTransaction,
+    write_txn: &WriteTransaction,
+    definition: MultimapTableDefinition<(&str, u64), &[u8]>,
+) -> Result<(), RedbError> {
+    let source = read_txn.open_multimap_table(definition)?;
+    let mut table = write_txn.open_multimap_table(definition)?;
+    for entry in source.iter()? {
+        let (key, values) = entry?;
+        for value in values {
+            table.insert(key.value(), value?.value())?;
+        }
     }
+    Ok(())
 }
 
 impl EventDatabase for RedbDatabase {
@@ -147,53 +584,95 @@ impl EventDatabase for RedbDatabase {
         self.insert_nontrans_receipt(&id.to_str(), sn, &receipts)
     }
 
+    /// Returns `None` also if database can't be read. Use
+    /// [`EventDatabase::try_get_kel_finalized_events`] to get the error.
     fn get_kel_finalized_events(
         &self,
         params: super::QueryParameters,
     ) -> Option<impl DoubleEndedIterator<Item = super::timestamped::TimestampedSignedEventMessage>>
     {
+        self.try_get_kel_finalized_events(params)
+            .ok()
+            .flatten()
+            .map(|kel| kel.into_iter())
+    }
+
+    fn try_get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Option<Vec<TimestampedSignedEventMessage>>, RedbError> {
         match params {
-            QueryParameters::BySn { id, sn } => Some(self.get_kel(&id, sn, 1).into_iter()),
+            QueryParameters::BySn { id, sn } => self.get_kel(&id, sn, 1).map(Some),
             QueryParameters::Range { id, start, limit } => {
-                Some(self.get_kel(&id, start, limit).into_iter())
+                self.get_kel(&id, start, limit).map(Some)
             }
-            QueryParameters::All { id } => self.get_full_kel(id).map(|kel| kel.into_iter()),
+            QueryParameters::All { id } => self.get_full_kel(id),
         }
     }
 
+    fn get_kel_finalized_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Option<Vec<TimestampedSignedEventMessage>> {
+        self.get_events_between(id, from, to).ok()
+    }
+
+    /// Returns `None` if database can't be read. Use
+    /// [`EventDatabase::try_get_receipts_t`] to get the error.
     fn get_receipts_t(
         &self,
         params: super::QueryParameters,
     ) -> Option<impl DoubleEndedIterator<Item = Transferable>> {
-        match params {
-            QueryParameters::BySn { id, sn } => self.get_trans_receipts(&id.to_str(), sn).ok(),
-            QueryParameters::Range {
-                id: _,
-                start: _,
-                limit: _,
-            } => todo!(),
-            QueryParameters::All { id: _ } => todo!(),
-        }
+        self.try_get_receipts_t(params)
+            .map(|receipts| receipts.into_iter())
+            .ok()
     }
 
+    fn try_get_receipts_t(&self, params: QueryParameters) -> Result<Vec<Transferable>, RedbError> {
+        let (id, start, limit) = query_range(&params);
+        self.get_trans_receipts_range(&id.to_str(), start, limit)
+    }
+
+    /// Returns `None` if database can't be read. Use
+    /// [`EventDatabase::try_get_receipts_nt`] to get the error.
     fn get_receipts_nt(
         &self,
         params: super::QueryParameters,
     ) -> Option<impl DoubleEndedIterator<Item = SignedNontransferableReceipt>> {
-        match params {
-            QueryParameters::BySn { id, sn } => self
-                .get_nontrans_receipts_range(&id.to_str(), sn, 1)
-                .ok()
-                .map(|e| e.into_iter()),
-            QueryParameters::Range { id, start, limit } => self
-                .get_nontrans_receipts_range(&id.to_str(), start, limit)
-                .ok()
-                .map(|e| e.into_iter()),
-            QueryParameters::All { id } => self
-                .get_nontrans_receipts_range(&id.to_str(), 0, u64::MAX)
-                .ok()
-                .map(|e| e.into_iter()),
-        }
+        self.try_get_receipts_nt(params)
+            .map(|receipts| receipts.into_iter())
+            .ok()
+    }
+
+    fn try_get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<SignedNontransferableReceipt>, RedbError> {
+        let (id, start, limit) = query_range(&params);
+        self.get_nontrans_receipts_range(&id.to_str(), start, limit)
+    }
+
+    fn get_pruned_state(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Option<IdentifierState>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(PRUNED_STATES)?;
+        let state = table.get(id.to_str().as_str())?;
+        state
+            .map(|value| serde_json::from_slice(value.value()).map_err(|_| RedbError::WrongValue))
+            .transpose()
+    }
+}
+
+/// Returns identifier, first sn and number of sns covered by query.
+fn query_range<'a>(params: &'a QueryParameters) -> (&'a IdentifierPrefix, u64, u64) {
+    match params {
+        QueryParameters::BySn { id, sn } => (id, *sn, 1),
+        QueryParameters::Range { id, start, limit } => (id, *start, *limit),
+        QueryParameters::All { id } => (*id, 0, u64::MAX),
     }
 }
 
@@ -214,8 +693,19 @@ impl RedbDatabase {
     }
 
     /// Saves KEL event of given identifier. Key is identifier and sn of event, and value is event digest.
+    /// In the same transaction records time when event was first seen,
+    /// unless the same event is in KEL already, and key state transition
+    /// made by establishment event, unless it was recorded before.
     fn save_to_kel(&self, event: &KeriEvent<KeyEvent>) -> Result<(), RedbError> {
         let digest = event.digest().map_err(|_e| RedbError::MissingDigest)?;
+        let first_seen = self.clock.now();
+        let transition = StateTransition::from_event(event, first_seen)?
+            .map(|transition| {
+                serde_json::to_vec(&transition)
+                    .map(|value| (transition.sn, value))
+                    .map_err(|_| RedbError::WrongValue)
+            })
+            .transpose()?;
 
         let write_txn = self.db.begin_write()?;
         {
@@ -223,7 +713,24 @@ impl RedbDatabase {
             let id = event.data.prefix.to_str();
             let sn = event.data.sn;
             let serialized_said = rkyv_adapter::serialize_said(&digest)?;
+            let current = table
+                .get((id.as_str(), sn))?
+                .map(|current| current.value().to_vec());
+            if current.as_deref() != Some(serialized_said.as_slice()) {
+                let mut first_seen_table = write_txn.open_table(FIRST_SEEN)?;
+                first_seen_table.insert(
+                    (id.as_str(), first_seen.timestamp_micros(), sn),
+                    serialized_said.as_slice(),
+                )?;
+            }
             table.insert((id.as_str(), sn), &serialized_said.as_slice())?;
+
+            if let Some((sn, value)) = transition {
+                let mut history = write_txn.open_table(STATE_HISTORY)?;
+                if history.get((id.as_str(), sn))?.is_none() {
+                    history.insert((id.as_str(), sn), value.as_slice())?;
+                }
+            }
         }
         write_txn.commit()?;
 
@@ -299,40 +806,34 @@ impl RedbDatabase {
         nontrans.map(|el| el.into_iter())
     }
 
+    /// Returns receipts of events with sn in range `start..start + limit`.
+    /// Receipts of events missing in KEL are skipped, because receipt body
+    /// can't be built without event digest.
     fn get_nontrans_receipts_range(
         &self,
         id: &str,
         start: u64,
         limit: u64,
     ) -> Result<Vec<SignedNontransferableReceipt>, RedbError> {
-        let from_db_iterator = {
-            let read_txn = self.db.begin_read()?;
-            let table = read_txn.open_multimap_table(NONTRANS_RCTS)?;
-            table.range((id, start)..(id, start + limit))
-        }?;
-        let out: Vec<SignedNontransferableReceipt> = from_db_iterator
-            .map(|sig| match sig {
-                Ok((key, value)) => {
-                    let (identifier, sn) = key.value();
-                    let id = identifier.parse().unwrap();
-                    let digest = self.get_event_digest(&id, sn).unwrap();
-                    let nontrans = value
-                        .map(|value| match value {
-                            Ok(element) => {
-                                rkyv_adapter::deserialize_nontransferable(element.value()).unwrap()
-                            }
-                            Err(_) => todo!(),
-                        })
-                        .collect::<Vec<_>>();
-                    let rct = Receipt::new(SerializationFormats::JSON, digest.unwrap(), id, sn);
-                    SignedNontransferableReceipt {
-                        body: rct,
-                        signatures: nontrans,
-                    }
-                }
-                Err(_) => todo!(),
-            })
-            .collect();
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_multimap_table(NONTRANS_RCTS)?;
+        let mut out = vec![];
+        for entry in table.range((id, start)..(id, start.saturating_add(limit)))? {
+            let (key, values) = entry?;
+            let (identifier, sn) = key.value();
+            let id: IdentifierPrefix = identifier.parse().map_err(|_| RedbError::WrongValue)?;
+            let digest = match self.get_event_digest(&id, sn)? {
+                Some(digest) => digest,
+                None => continue,
+            };
+            let signatures = values
+                .map(|value| Ok(rkyv_adapter::deserialize_nontransferable(value?.value())?))
+                .collect::<Result<Vec<_>, RedbError>>()?;
+            out.push(SignedNontransferableReceipt {
+                body: Receipt::new(SerializationFormats::JSON, digest, id, sn),
+                signatures,
+            });
+        }
         Ok(out)
     }
 
@@ -359,20 +860,24 @@ impl RedbDatabase {
         Ok(Box::new(out))
     }
 
-    fn get_trans_receipts(
+    /// Returns transferable receipts of events with sn in range
+    /// `start..start + limit`.
+    fn get_trans_receipts_range(
         &self,
         id: &str,
-        sn: u64,
-    ) -> Result<impl DoubleEndedIterator<Item = Transferable>, RedbError> {
-        let from_db_iterator = {
-            let read_txn = self.db.begin_read()?;
-            let table = read_txn.open_multimap_table(TRANS_RCTS)?;
-            table.get((id, sn))
-        }?;
-        Ok(from_db_iterator.map(|sig| match sig {
-            Ok(sig) => rkyv_adapter::deserialize_transferable(sig.value()).unwrap(),
-            Err(_) => todo!(),
-        }))
+        start: u64,
+        limit: u64,
+    ) -> Result<Vec<Transferable>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_multimap_table(TRANS_RCTS)?;
+        let mut out = vec![];
+        for entry in table.range((id, start)..(id, start.saturating_add(limit)))? {
+            let (_, values) = entry?;
+            for value in values {
+                out.push(rkyv_adapter::deserialize_transferable(value?.value())?);
+            }
+        }
+        Ok(out)
     }
 
     fn get_event_digest(
@@ -381,7 +886,7 @@ impl RedbDatabase {
         sn: u64,
     ) -> Result<Option<SelfAddressingIdentifier>, RedbError> {
         Ok({
-            let read_txn = self.db.begin_read().unwrap();
+            let read_txn = self.db.begin_read()?;
             let table = read_txn.open_table(KELS)?;
             table
                 .get((identifier.to_str().as_str(), sn))?
@@ -437,73 +942,55 @@ impl RedbDatabase {
                 read_txn.open_multimap_table(SIGS)?;
             table.get(key)
         }?;
-        Ok(Some(from_db_iterator.map(|sig| match sig {
-            Ok(sig) => deserialize_indexed_signatures(sig.value()).unwrap(),
-            Err(_) => todo!(),
-        })))
+        let signatures = from_db_iterator
+            .map(|sig| Ok(deserialize_indexed_signatures(sig?.value())?))
+            .collect::<Result<Vec<_>, RedbError>>()?;
+        Ok(Some(signatures.into_iter()))
     }
 
-    fn get_kel<'a>(
-        &'a self,
+    /// Returns events of identifier with sn in range `from..from + limit`.
+    fn get_kel(
+        &self,
         id: &IdentifierPrefix,
         from: u64,
         limit: u64,
-    ) -> Vec<timestamped::Timestamped<SignedEventMessage>> {
-        let digests = {
-            let read_txn = self.db.begin_read().unwrap();
-            let table = read_txn.open_table(KELS).unwrap();
-            table
-                .range((id.to_str().as_str(), from)..(id.to_str().as_str(), from + limit))
-                .unwrap()
-        };
-
-        digests
+    ) -> Result<Vec<timestamped::Timestamped<SignedEventMessage>>, RedbError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(KELS)?;
+        let id = id.to_str();
+        table
+            .range((id.as_str(), from)..(id.as_str(), from.saturating_add(limit)))?
             .map(|entry| {
-                let (key, value) = entry.unwrap();
-                let signatures = self.get_signatures(key.value()).unwrap().unwrap().collect();
-
-                let event = self
-                    .get_event_by_serialized_key(&value.value())
-                    .unwrap()
-                    .unwrap();
-                TimestampedSignedEventMessage::new(SignedEventMessage::new(
-                    &event, signatures, None, None,
+                let (key, value) = entry?;
+                let signatures = self
+                    .get_signatures(key.value())?
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                let event = match self.get_event_by_serialized_key(value.value())? {
+                    Some(event) => event,
+                    None => {
+                        let digest = rkyv_adapter::deserialize_said(value.value())?;
+                        return Err(RedbError::NotFound(digest));
+                    }
+                };
+                Ok(TimestampedSignedEventMessage::new(
+                    SignedEventMessage::new(&event, signatures, None, None),
+                    self.clock.as_ref(),
                 ))
             })
             .collect()
     }
 
-    fn get_full_kel<'a>(
-        &'a self,
+    /// Returns whole KEL of identifier, or `None` if no KEL was saved yet.
+    fn get_full_kel(
+        &self,
         id: &IdentifierPrefix,
-    ) -> Option<Vec<timestamped::Timestamped<SignedEventMessage>>> {
-        let digests = {
-            let read_txn = self.db.begin_read().unwrap();
-            let table = read_txn.open_table(KELS);
-            match table {
-                Ok(table) => table
-                    .range((id.to_str().as_str(), 0)..(id.to_str().as_str(), u64::MAX))
-                    .unwrap(),
-                Err(_e) => return None,
-            }
-        };
-
-        Some(
-            digests
-                .map(|entry| {
-                    let (key, value) = entry.unwrap();
-                    let signatures = self.get_signatures(key.value()).unwrap().unwrap().collect();
-
-                    let event = self
-                        .get_event_by_serialized_key(value.value())
-                        .unwrap()
-                        .unwrap();
-                    TimestampedSignedEventMessage::new(SignedEventMessage::new(
-                        &event, signatures, None, None,
-                    ))
-                })
-                .collect(),
-        )
+    ) -> Result<Option<Vec<timestamped::Timestamped<SignedEventMessage>>>, RedbError> {
+        match self.get_kel(id, 0, u64::MAX) {
+            Err(RedbError::TableError(redb::TableError::TableDoesNotExist(_))) => Ok(None),
+            kel => kel.map(Some),
+        }
     }
 }
 
@@ -561,7 +1048,7 @@ fn test_retrieve_kel() {
     assert_eq!(sigs_from_db.count(), 1);
 
     // Retrieve KEL in range
-    let mut part_of_kel_events = db.get_kel(&first_id, 1, 2).into_iter();
+    let mut part_of_kel_events = db.get_kel(&first_id, 1, 2).unwrap().into_iter();
 
     let rot = part_of_kel_events.next().unwrap();
     assert_eq!(
@@ -598,7 +1085,7 @@ fn test_retrieve_kel() {
     assert_eq!(part_of_kel_events.next(), None);
 
     // Retrieve KEL in range
-    let mut part_of_kel_events = db.get_kel(&first_id, 0, 2).into_iter();
+    let mut part_of_kel_events = db.get_kel(&first_id, 0, 2).unwrap().into_iter();
     let icp = part_of_kel_events.next().unwrap();
     assert_eq!(
         icp.signed_event_message.event_message.event_type,
@@ -632,6 +1119,33 @@ fn test_retrieve_kel() {
     assert_eq!(rot.signed_event_message.signatures.len(), 3);
 
     assert_eq!(part_of_kel_events.next(), None);
+
+    // Only establishment events are kept in state history.
+    let history = db.get_state_history(&first_id).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|transition| (transition.sn, transition.event_type.clone()))
+            .collect::<Vec<_>>(),
+        vec![(0, EventTypeTag::Icp), (1, EventTypeTag::Rot)]
+    );
+    assert_eq!(
+        history[1].digest,
+        "EHjzZj4i_-RpTN2Yh-NocajFROJ_GkBtlByhRykqiXgz"
+            .parse::<SelfAddressingIdentifier>()
+            .unwrap()
+    );
+    assert_ne!(history[0].keys_digest, history[1].keys_digest);
+    assert!(history[0].first_seen <= history[1].first_seen);
+    assert_eq!(db.get_state_history(&second_id).unwrap().len(), 1);
+
+    // Adding event again doesn't change its first seen time.
+    let evs = parse_event_stream(rot_raw).unwrap();
+    if let Some(Message::Notice(Notice::Event(event))) = evs.first() {
+        db.add_kel_finalized_event(event.clone(), &first_id)
+            .unwrap();
+    }
+    assert_eq!(db.get_state_history(&first_id).unwrap(), history);
 }
 
 #[test]
@@ -673,3 +1187,184 @@ fn test_retrieve_receipts() {
         .unwrap();
     assert_eq!(all_retrived_rcts.count(), 4);
 }
+
+#[test]
+fn test_backup_and_restore() {
+    use crate::actor::parse_event_stream;
+    use crate::event_message::signed_event_message::{Message, Notice};
+    use tempfile::NamedTempFile;
+
+    let file_path = NamedTempFile::new().unwrap();
+    let db = RedbDatabase::new(file_path.path()).unwrap();
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP"#;
+    let id: IdentifierPrefix = "EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf"
+        .parse()
+        .unwrap();
+    match parse_event_stream(icp_raw).unwrap().first().unwrap() {
+        Message::Notice(Notice::Event(event)) => {
+            db.add_kel_finalized_event(event.clone(), &id).unwrap();
+        }
+        _ => unreachable!(),
+    }
+    let kel: Vec<_> = db
+        .get_full_kel(&id)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .map(|ev| ev.signed_event_message)
+        .collect();
+
+    let backup_path = NamedTempFile::new().unwrap();
+    db.backup(backup_path.path()).unwrap();
+
+    let restored_path = NamedTempFile::new().unwrap();
+    let restored = RedbDatabase::restore(backup_path.path(), restored_path.path()).unwrap();
+    assert!(restored.check_consistency().unwrap().is_empty());
+    assert_eq!(restored.get_identifiers().unwrap(), vec![id.clone()]);
+    let restored_kel: Vec<_> = restored
+        .get_full_kel(&id)
+        .unwrap()
+        .unwrap()
+        .into_iter()
+        .map(|ev| ev.signed_event_message)
+        .collect();
+    assert_eq!(restored_kel, kel);
+}
+
+#[test]
+fn test_prune_receipts() {
+    use crate::actor::parse_event_stream;
+    use tempfile::NamedTempFile;
+
+    let file_path = NamedTempFile::new().unwrap();
+    let db = RedbDatabase::new(file_path.path()).unwrap();
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP"#;
+    let id: IdentifierPrefix = "EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf"
+        .parse()
+        .unwrap();
+    let icp = match parse_event_stream(icp_raw).unwrap().first().unwrap() {
+        crate::event_message::signed_event_message::Message::Notice(Notice::Event(event)) => {
+            event.clone()
+        }
+        _ => unreachable!(),
+    };
+    db.add_kel_finalized_event(icp.clone(), &id).unwrap();
+    let receipt = SignedNontransferableReceipt {
+        body: Receipt::new(
+            SerializationFormats::JSON,
+            icp.event_message.digest().unwrap(),
+            id.clone(),
+            0,
+        ),
+        signatures: vec![Nontransferable::Indexed(icp.signatures.clone())],
+    };
+    db.add_receipt_nt(receipt.clone(), &id).unwrap();
+
+    // Nothing is pruned before inception.
+    assert!(db.get_prunable(&id, 0, true).unwrap().is_empty());
+
+    let pruned = db.get_prunable(&id, 1, false).unwrap();
+    assert_eq!(pruned, vec![Notice::NontransferableRct(receipt.clone())]);
+    // Prunable receipts are only read.
+    assert_eq!(
+        db.get_nontrans_couplets(&id.to_str(), 0).unwrap().count(),
+        1
+    );
+    db.prune(&id, 1, None).unwrap();
+    assert_eq!(
+        db.get_nontrans_couplets(&id.to_str(), 0).unwrap().count(),
+        0
+    );
+    // Event is kept.
+    assert_eq!(db.get_full_kel(&id).unwrap().unwrap().len(), 1);
+
+    // Pruned receipts can be added back.
+    db.add_receipt_nt(receipt.clone(), &id).unwrap();
+    assert_eq!(
+        db.get_nontrans_couplets(&id.to_str(), 0).unwrap().count(),
+        1
+    );
+
+    // Events are pruned together with their receipts, and state preceding
+    // remaining events is kept.
+    let pruned = db.get_prunable(&id, 1, true).unwrap();
+    assert_eq!(
+        pruned,
+        vec![
+            Notice::Event(icp.clone()),
+            Notice::NontransferableRct(receipt)
+        ]
+    );
+    let state = IdentifierState::default().apply(&icp).unwrap();
+    db.prune(&id, 1, Some(&state)).unwrap();
+    assert!(db.get_full_kel(&id).unwrap().unwrap().is_empty());
+    assert_eq!(db.get_pruned_state(&id).unwrap(), Some(state));
+    assert!(db.check_consistency().unwrap().is_empty());
+
+    db.add_kel_finalized_event(icp, &id).unwrap();
+    db.remove_pruned_state(&id).unwrap();
+    assert_eq!(db.get_full_kel(&id).unwrap().unwrap().len(), 1);
+    assert!(db.get_pruned_state(&id).unwrap().is_none());
+}
+
+#[test]
+fn test_events_between() {
+    use crate::actor::parse_event_stream;
+    use crate::clock::ManualClock;
+    use crate::event_message::signed_event_message::Message;
+    use chrono::Duration;
+    use tempfile::NamedTempFile;
+
+    let clock = Arc::new(ManualClock::default());
+    let file_path = NamedTempFile::new().unwrap();
+    let db = RedbDatabase::new(file_path.path())
+        .unwrap()
+        .with_clock(clock.clone());
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP"#;
+    let id: IdentifierPrefix = "EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf"
+        .parse()
+        .unwrap();
+    let icp = match parse_event_stream(icp_raw).unwrap().first().unwrap() {
+        Message::Notice(Notice::Event(event)) => event.clone(),
+        _ => unreachable!(),
+    };
+
+    let before = clock.now();
+    db.add_kel_finalized_event(icp.clone(), &id).unwrap();
+    let after = before + Duration::microseconds(1);
+
+    let events = db.get_events_between(&id, before, after).unwrap();
+    assert_eq!(events.len(), 1);
+    let first_seen = events[0].timestamp;
+    // First seen time is taken from database clock.
+    assert_eq!(first_seen.timestamp_micros(), before.timestamp_micros());
+    assert_eq!(events[0].signed_event_message, icp);
+    assert_eq!(db.get_state_history(&id).unwrap()[0].first_seen, before);
+
+    // Adding the same event again doesn't change its first seen time.
+    clock.advance(std::time::Duration::from_secs(10));
+    db.add_kel_finalized_event(icp.clone(), &id).unwrap();
+    let events = db
+        .get_events_between(&id, before, clock.now() + Duration::seconds(1))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].timestamp, first_seen);
+
+    // Nothing was seen before.
+    assert!(db
+        .get_events_between(&id, before - Duration::days(31), before)
+        .unwrap()
+        .is_empty());
+
+    // Index is copied with identifier.
+    let target_path = NamedTempFile::new().unwrap();
+    let target = RedbDatabase::new(target_path.path()).unwrap();
+    db.copy_identifier(&id, &target).unwrap();
+    assert_eq!(
+        target.get_events_between(&id, before, after).unwrap()[0].timestamp,
+        first_seen
+    );
+}
@@ -0,0 +1,626 @@
+//! Storage split into several redb files. Identifier's events, signatures,
+//! receipts and key state history are kept in one shard, selected by hash of
+//! identifier prefix, so writes of different identifiers don't wait for the
+//! same database lock. Shards are stored as `shard-<index>.redb` files in
+//! one directory. [`RedbStorage`] lets services open either single or
+//! sharded database, without knowing which one is used.
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    clock::Clock,
+    database::{timestamped::TimestampedSignedEventMessage, EventDatabase, QueryParameters},
+    event_message::{
+        signature::Transferable,
+        signed_event_message::{
+            Notice, SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
+        },
+    },
+    prefix::IdentifierPrefix,
+    state::{IdentifierState, StateTransition},
+};
+
+use super::{RedbDatabase, RedbError};
+
+pub struct ShardedRedbDatabase {
+    shards: Vec<RedbDatabase>,
+}
+
+impl ShardedRedbDatabase {
+    /// Opens `shards` databases in `dir`, creating missing ones. Fails if
+    /// `dir` already contains different number of shards, because
+    /// identifiers would be looked up in wrong files. Use
+    /// [`ShardedRedbDatabase::rebalance`] to change number of shards.
+    pub fn new(dir: &Path, shards: usize) -> Result<Self, RedbError> {
+        if shards == 0 {
+            return Err(RedbError::NoShards);
+        }
+        std::fs::create_dir_all(dir)?;
+        let found = Self::count_shards(dir)?;
+        if found != 0 && found != shards {
+            return Err(RedbError::ShardCountMismatch {
+                expected: shards,
+                found,
+            });
+        }
+        let shards = (0..shards)
+            .map(|index| RedbDatabase::new(&shard_path(dir, index)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { shards })
+    }
+
+    /// Sets clock used for first seen timestamps of events in every shard.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            shards: self
+                .shards
+                .into_iter()
+                .map(|shard| shard.with_clock(clock.clone()))
+                .collect(),
+        }
+    }
+
+    /// Opens shards that already exist in `dir`.
+    pub fn open(dir: &Path) -> Result<Self, RedbError> {
+        Self::new(dir, Self::count_shards(dir)?)
+    }
+
+    /// Returns number of shards stored in `dir`.
+    pub fn count_shards(dir: &Path) -> Result<usize, RedbError> {
+        if !dir.exists() {
+            return Ok(0);
+        }
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("shard-") && name.ends_with(".redb") {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn shards(&self) -> &[RedbDatabase] {
+        &self.shards
+    }
+
+    /// Returns index of shard that stores data of identifier.
+    pub fn shard_index(&self, id: &IdentifierPrefix) -> usize {
+        (fnv1a(id.to_str().as_bytes()) % self.shards.len() as u64) as usize
+    }
+
+    fn shard(&self, id: &IdentifierPrefix) -> &RedbDatabase {
+        &self.shards[self.shard_index(id)]
+    }
+
+    /// Returns identifiers that have at least one event in KEL table of any
+    /// shard.
+    pub fn get_identifiers(&self) -> Result<Vec<IdentifierPrefix>, RedbError> {
+        let mut ids = vec![];
+        for shard in &self.shards {
+            ids.extend(shard.get_identifiers()?);
+        }
+        Ok(ids)
+    }
+
+    /// Returns key state transitions of identifier, ordered by sn.
+    pub fn get_state_history(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<StateTransition>, RedbError> {
+        self.shard(id).get_state_history(id)
+    }
+
+    /// See [`RedbDatabase::get_events_between`].
+    pub fn get_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Result<Vec<TimestampedSignedEventMessage>, RedbError> {
+        self.shard(id).get_events_between(id, from, to)
+    }
+
+    /// See [`RedbDatabase::get_prunable`].
+    pub fn get_prunable(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        events: bool,
+    ) -> Result<Vec<Notice>, RedbError> {
+        self.shard(id).get_prunable(id, before_sn, events)
+    }
+
+    /// See [`RedbDatabase::prune`].
+    pub fn prune(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        state: Option<&IdentifierState>,
+    ) -> Result<(), RedbError> {
+        self.shard(id).prune(id, before_sn, state)
+    }
+
+    /// See [`RedbDatabase::remove_pruned_state`].
+    pub fn remove_pruned_state(&self, id: &IdentifierPrefix) -> Result<(), RedbError> {
+        self.shard(id).remove_pruned_state(id)
+    }
+
+    /// Checks consistency of every shard. Returns identifiers and sequence
+    /// numbers of KEL entries without event.
+    pub fn check_consistency(&self) -> Result<Vec<(IdentifierPrefix, u64)>, RedbError> {
+        let mut dangling = vec![];
+        for shard in &self.shards {
+            dangling.extend(shard.check_consistency()?);
+        }
+        Ok(dangling)
+    }
+
+    /// Backs up every shard into `target` directory, using the same layout.
+    /// Shards are copied one by one, so backup isn't a single snapshot.
+    pub fn backup(&self, target: &Path) -> Result<(), RedbError> {
+        std::fs::create_dir_all(target)?;
+        for (index, shard) in self.shards.iter().enumerate() {
+            shard.backup(&shard_path(target, index))?;
+        }
+        Ok(())
+    }
+
+    /// Splits single redb database into `shards` databases in `target`
+    /// directory, which should be empty. Source database isn't changed.
+    pub fn split(source: &RedbDatabase, target: &Path, shards: usize) -> Result<Self, RedbError> {
+        let sharded = Self::new(target, shards)?;
+        sharded.import(source)?;
+        Ok(sharded)
+    }
+
+    /// Copies data into new set of `shards` databases in `target` directory,
+    /// which should be empty. Data written during rebalancing may be missing
+    /// in the new shards, so it should be done while database isn't used.
+    /// Old shards aren't changed and can be removed afterwards.
+    pub fn rebalance(&self, target: &Path, shards: usize) -> Result<Self, RedbError> {
+        let rebalanced = Self::new(target, shards)?.with_clock(self.shards[0].clock.clone());
+        for shard in &self.shards {
+            rebalanced.import(shard)?;
+        }
+        Ok(rebalanced)
+    }
+
+    /// Copies every identifier of `source` database into its shard. Returns
+    /// number of copied identifiers.
+    fn import(&self, source: &RedbDatabase) -> Result<usize, RedbError> {
+        let ids = source.get_identifiers()?;
+        for id in &ids {
+            source.copy_identifier(id, self.shard(id))?;
+        }
+        Ok(ids.len())
+    }
+}
+
+fn shard_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("shard-{}.redb", index))
+}
+
+/// FNV-1a hash. Shard of identifier has to be the same in every release, so
+/// standard library hasher, which can change, isn't used.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn query_id<'a>(params: &'a QueryParameters) -> &'a IdentifierPrefix {
+    match params {
+        QueryParameters::BySn { id, .. } | QueryParameters::Range { id, .. } => id,
+        QueryParameters::All { id } => *id,
+    }
+}
+
+impl EventDatabase for ShardedRedbDatabase {
+    type Error = RedbError;
+
+    fn add_kel_finalized_event(
+        &self,
+        event: SignedEventMessage,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        let shard = self.shard(&event.event_message.data.prefix);
+        shard.add_kel_finalized_event(event, id)
+    }
+
+    fn add_receipt_t(
+        &self,
+        receipt: SignedTransferableReceipt,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        let shard = self.shard(&receipt.body.prefix);
+        shard.add_receipt_t(receipt, id)
+    }
+
+    fn add_receipt_nt(
+        &self,
+        receipt: SignedNontransferableReceipt,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        let shard = self.shard(&receipt.body.prefix);
+        shard.add_receipt_nt(receipt, id)
+    }
+
+    fn get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = TimestampedSignedEventMessage>> {
+        self.shard(query_id(&params))
+            .get_kel_finalized_events(params)
+    }
+
+    fn get_receipts_t(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = Transferable>> {
+        self.shard(query_id(&params)).get_receipts_t(params)
+    }
+
+    fn get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = SignedNontransferableReceipt>> {
+        self.shard(query_id(&params)).get_receipts_nt(params)
+    }
+
+    fn try_get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Option<Vec<TimestampedSignedEventMessage>>, RedbError> {
+        self.shard(query_id(&params))
+            .try_get_kel_finalized_events(params)
+    }
+
+    fn try_get_receipts_t(&self, params: QueryParameters) -> Result<Vec<Transferable>, RedbError> {
+        self.shard(query_id(&params)).try_get_receipts_t(params)
+    }
+
+    fn try_get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<SignedNontransferableReceipt>, RedbError> {
+        self.shard(query_id(&params)).try_get_receipts_nt(params)
+    }
+
+    fn get_kel_finalized_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Option<Vec<TimestampedSignedEventMessage>> {
+        self.get_events_between(id, from, to).ok()
+    }
+
+    fn get_pruned_state(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Option<IdentifierState>, RedbError> {
+        self.shard(id).get_pruned_state(id)
+    }
+}
+
+/// Redb database, either single file or split into shards.
+pub enum RedbStorage {
+    Single(RedbDatabase),
+    Sharded(ShardedRedbDatabase),
+}
+
+impl RedbStorage {
+    /// Opens shards in `shards_dir` if it exists, or single database at
+    /// `db_path` otherwise.
+    pub fn open(db_path: &Path, shards_dir: &Path) -> Result<Self, RedbError> {
+        if shards_dir.exists() {
+            Ok(Self::Sharded(ShardedRedbDatabase::open(shards_dir)?))
+        } else {
+            Ok(Self::Single(RedbDatabase::new(db_path)?))
+        }
+    }
+
+    /// Sets clock used for first seen timestamps of events.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        match self {
+            RedbStorage::Single(db) => RedbStorage::Single(db.with_clock(clock)),
+            RedbStorage::Sharded(db) => RedbStorage::Sharded(db.with_clock(clock)),
+        }
+    }
+
+    /// Returns number of shards, 1 for single database.
+    pub fn shard_count(&self) -> usize {
+        match self {
+            RedbStorage::Single(_) => 1,
+            RedbStorage::Sharded(db) => db.shards().len(),
+        }
+    }
+
+    pub fn get_identifiers(&self) -> Result<Vec<IdentifierPrefix>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.get_identifiers(),
+            RedbStorage::Sharded(db) => db.get_identifiers(),
+        }
+    }
+
+    /// See [`RedbDatabase::get_state_history`].
+    pub fn get_state_history(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<StateTransition>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.get_state_history(id),
+            RedbStorage::Sharded(db) => db.get_state_history(id),
+        }
+    }
+
+    /// See [`RedbDatabase::get_events_between`].
+    pub fn get_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Result<Vec<TimestampedSignedEventMessage>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.get_events_between(id, from, to),
+            RedbStorage::Sharded(db) => db.get_events_between(id, from, to),
+        }
+    }
+
+    /// See [`RedbDatabase::get_prunable`].
+    pub fn get_prunable(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        events: bool,
+    ) -> Result<Vec<Notice>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.get_prunable(id, before_sn, events),
+            RedbStorage::Sharded(db) => db.get_prunable(id, before_sn, events),
+        }
+    }
+
+    /// See [`RedbDatabase::prune`].
+    pub fn prune(
+        &self,
+        id: &IdentifierPrefix,
+        before_sn: u64,
+        state: Option<&IdentifierState>,
+    ) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.prune(id, before_sn, state),
+            RedbStorage::Sharded(db) => db.prune(id, before_sn, state),
+        }
+    }
+
+    /// See [`RedbDatabase::remove_pruned_state`].
+    pub fn remove_pruned_state(&self, id: &IdentifierPrefix) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.remove_pruned_state(id),
+            RedbStorage::Sharded(db) => db.remove_pruned_state(id),
+        }
+    }
+
+    pub fn check_consistency(&self) -> Result<Vec<(IdentifierPrefix, u64)>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.check_consistency(),
+            RedbStorage::Sharded(db) => db.check_consistency(),
+        }
+    }
+
+    /// Backs up database to `target`, which is a file for single database
+    /// and a directory of shards for sharded one.
+    pub fn backup(&self, target: &Path) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.backup(target),
+            RedbStorage::Sharded(db) => db.backup(target),
+        }
+    }
+
+    /// Copies data into `shards` databases in `target` directory, which
+    /// should be empty. Database isn't changed, so it should be done while
+    /// it isn't used.
+    pub fn reshard(&self, target: &Path, shards: usize) -> Result<ShardedRedbDatabase, RedbError> {
+        match self {
+            RedbStorage::Single(db) => ShardedRedbDatabase::split(db, target, shards),
+            RedbStorage::Sharded(db) => db.rebalance(target, shards),
+        }
+    }
+}
+
+impl EventDatabase for RedbStorage {
+    type Error = RedbError;
+
+    fn add_kel_finalized_event(
+        &self,
+        event: SignedEventMessage,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.add_kel_finalized_event(event, id),
+            RedbStorage::Sharded(db) => db.add_kel_finalized_event(event, id),
+        }
+    }
+
+    fn add_receipt_t(
+        &self,
+        receipt: SignedTransferableReceipt,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.add_receipt_t(receipt, id),
+            RedbStorage::Sharded(db) => db.add_receipt_t(receipt, id),
+        }
+    }
+
+    fn add_receipt_nt(
+        &self,
+        receipt: SignedNontransferableReceipt,
+        id: &IdentifierPrefix,
+    ) -> Result<(), RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.add_receipt_nt(receipt, id),
+            RedbStorage::Sharded(db) => db.add_receipt_nt(receipt, id),
+        }
+    }
+
+    fn get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = TimestampedSignedEventMessage>> {
+        match self {
+            RedbStorage::Single(db) => db.get_kel_finalized_events(params).map(Iterator::collect),
+            RedbStorage::Sharded(db) => db.get_kel_finalized_events(params).map(Iterator::collect),
+        }
+        .map(|events: Vec<_>| events.into_iter())
+    }
+
+    fn get_receipts_t(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = Transferable>> {
+        match self {
+            RedbStorage::Single(db) => db.get_receipts_t(params).map(Iterator::collect),
+            RedbStorage::Sharded(db) => db.get_receipts_t(params).map(Iterator::collect),
+        }
+        .map(|receipts: Vec<_>| receipts.into_iter())
+    }
+
+    fn get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = SignedNontransferableReceipt>> {
+        match self {
+            RedbStorage::Single(db) => db.get_receipts_nt(params).map(Iterator::collect),
+            RedbStorage::Sharded(db) => db.get_receipts_nt(params).map(Iterator::collect),
+        }
+        .map(|receipts: Vec<_>| receipts.into_iter())
+    }
+
+    fn try_get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Option<Vec<TimestampedSignedEventMessage>>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.try_get_kel_finalized_events(params),
+            RedbStorage::Sharded(db) => db.try_get_kel_finalized_events(params),
+        }
+    }
+
+    fn try_get_receipts_t(&self, params: QueryParameters) -> Result<Vec<Transferable>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.try_get_receipts_t(params),
+            RedbStorage::Sharded(db) => db.try_get_receipts_t(params),
+        }
+    }
+
+    fn try_get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<SignedNontransferableReceipt>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.try_get_receipts_nt(params),
+            RedbStorage::Sharded(db) => db.try_get_receipts_nt(params),
+        }
+    }
+
+    fn get_kel_finalized_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Option<Vec<TimestampedSignedEventMessage>> {
+        self.get_events_between(id, from, to).ok()
+    }
+
+    fn get_pruned_state(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Option<IdentifierState>, RedbError> {
+        match self {
+            RedbStorage::Single(db) => db.get_pruned_state(id),
+            RedbStorage::Sharded(db) => db.get_pruned_state(id),
+        }
+    }
+}
+
+#[test]
+fn test_sharded_database() {
+    use crate::actor::parse_event_stream;
+    use crate::event_message::signed_event_message::Message;
+
+    let first_icp: &[u8] = br#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}-AADAAD4SyJSYlsQG22MGXzRGz2PTMqpkgOyUfq7cS99sC2BCWwdVmEMKiTEeWe5kv-l_d9auxdadQuArLtAGEArW8wEABD0z_vQmFImZXfdR-0lclcpZFfkJJJNXDcUNrf7a-mGsxNLprJo-LROwDkH5m7tVrb-a1jcor2dHD9Jez-r4bQIACBFeU05ywfZycLdR0FxCvAR9BfV9im8tWe1DglezqJLf-vHRQSChY1KafbYNc96hYYpbuN90WzuCRMgV8KgRsEC"#;
+    let second_icp: &[u8] = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = ShardedRedbDatabase::new(dir.path(), 4).unwrap();
+    let mut ids = vec![];
+    for raw in [first_icp, second_icp] {
+        match parse_event_stream(raw).unwrap().first().unwrap() {
+            Message::Notice(Notice::Event(event)) => {
+                let id = event.event_message.data.get_prefix();
+                db.add_kel_finalized_event(event.clone(), &id).unwrap();
+                ids.push(id);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    for id in &ids {
+        // Data is stored only in identifier's shard.
+        for (index, shard) in db.shards().iter().enumerate() {
+            assert_eq!(
+                shard.get_identifiers().unwrap().contains(id),
+                index == db.shard_index(id)
+            );
+        }
+        let kel = db
+            .get_kel_finalized_events(QueryParameters::All { id })
+            .unwrap();
+        assert_eq!(kel.count(), 1);
+        assert_eq!(db.get_state_history(id).unwrap().len(), 1);
+    }
+
+    // Shards can't be opened with different count.
+    assert!(matches!(
+        ShardedRedbDatabase::new(dir.path(), 2),
+        Err(RedbError::ShardCountMismatch {
+            expected: 2,
+            found: 4
+        })
+    ));
+    assert_eq!(
+        ShardedRedbDatabase::open(dir.path())
+            .unwrap()
+            .shards()
+            .len(),
+        4
+    );
+
+    let rebalanced_dir = tempfile::tempdir().unwrap();
+    let rebalanced = db.rebalance(rebalanced_dir.path(), 3).unwrap();
+    assert!(rebalanced.check_consistency().unwrap().is_empty());
+    let mut rebalanced_ids = rebalanced.get_identifiers().unwrap();
+    rebalanced_ids.sort_by_key(|id| id.to_str());
+    ids.sort_by_key(|id| id.to_str());
+    assert_eq!(rebalanced_ids, ids);
+    for id in &ids {
+        let events = |db: &ShardedRedbDatabase| {
+            db.get_kel_finalized_events(QueryParameters::All { id })
+                .unwrap()
+                .map(|event| event.signed_event_message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(events(&rebalanced), events(&db));
+        assert_eq!(
+            rebalanced.get_state_history(id).unwrap(),
+            db.get_state_history(id).unwrap()
+        );
+    }
+}
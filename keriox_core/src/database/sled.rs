@@ -12,6 +12,7 @@ use super::tables::{SledEventTree, SledEventTreeVec};
 #[cfg(feature = "query")]
 use crate::query::reply_event::SignedReply;
 use crate::{
+    clock::{system_clock, Clock},
     event::KeyEvent,
     event_message::{
         msg::KeriEvent,
@@ -20,6 +21,8 @@ use crate::{
     },
     prefix::IdentifierPrefix,
 };
+#[cfg(feature = "mailbox")]
+use crate::{mailbox::MultisigMetadata, query::mailbox::QueryTopics};
 
 use super::timestamped::TimestampedSignedEventMessage;
 
@@ -41,6 +44,9 @@ pub struct SledEventDatabase {
 
     #[cfg(feature = "mailbox")]
     mailbox: MailboxData,
+
+    // Used for timestamping duplicitous events
+    clock: Arc<dyn Clock>,
 }
 
 // TODO: remove all the `.ok()`s
@@ -67,16 +73,40 @@ impl SledEventDatabase {
             #[cfg(feature = "query")]
             escrowed_replys: SledEventTreeVec::new(db.open_tree(b"knes")?),
             db,
+            clock: system_clock(),
         })
     }
 
+    /// Sets clock used for timestamping duplicitous events and forwarded
+    /// mailbox messages.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            #[cfg(feature = "mailbox")]
+            mailbox: self.mailbox.with_clock(clock.clone()),
+            clock,
+            ..self
+        }
+    }
+
+    /// Copies all trees into new database created in `target` directory.
+    /// Layout of `target` is the same as expected by
+    /// [`SledEventDatabase::new`].
+    pub fn backup(&self, target: impl AsRef<Path>) -> Result<(), DbError> {
+        let target_db = sled::open(target.as_ref().join("events"))?;
+        target_db.import(self.db.export());
+        target_db.flush()?;
+        Ok(())
+    }
+
     pub fn add_likely_duplicious_event(
         &self,
         event: KeriEvent<KeyEvent>,
         id: &IdentifierPrefix,
     ) -> Result<(), DbError> {
-        self.likely_duplicious_events
-            .push(self.identifiers.designated_key(id)?, event.into())?;
+        self.likely_duplicious_events.push(
+            self.identifiers.designated_key(id)?,
+            TimestampedEventMessage::new_at(event, self.clock.now()),
+        )?;
         self.db.flush()?;
         Ok(())
     }
@@ -94,8 +124,10 @@ impl SledEventDatabase {
         event: SignedEventMessage,
         id: &IdentifierPrefix,
     ) -> Result<(), DbError> {
-        self.duplicitous_events
-            .push(self.identifiers.designated_key(id)?, event.into())
+        self.duplicitous_events.push(
+            self.identifiers.designated_key(id)?,
+            TimestampedSignedEventMessage::new_at(event, self.clock.now()),
+        )
     }
 
     pub fn get_duplicious_events(
@@ -259,6 +291,27 @@ impl SledEventDatabase {
             .get_mailbox_multisig(self.identifiers.designated_key(id).ok()?)
     }
 
+    #[cfg(feature = "mailbox")]
+    pub fn add_mailbox_multisig_metadata(
+        &self,
+        metadata: MultisigMetadata,
+        target_id: &IdentifierPrefix,
+    ) -> Result<(), DbError> {
+        self.mailbox
+            .add_mailbox_multisig_metadata(self.identifiers.designated_key(target_id)?, metadata)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "mailbox")]
+    pub fn get_mailbox_multisig_metadata(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Option<impl DoubleEndedIterator<Item = MultisigMetadata>> {
+        self.mailbox
+            .get_mailbox_multisig_metadata(self.identifiers.designated_key(id).ok()?)
+    }
+
     #[cfg(feature = "mailbox")]
     pub fn add_mailbox_delegate(
         &self,
@@ -279,6 +332,51 @@ impl SledEventDatabase {
         self.mailbox
             .get_mailbox_delegate(self.identifiers.designated_key(id).ok()?)
     }
+
+    #[cfg(feature = "mailbox")]
+    pub fn clear_mailbox(&self, id: &IdentifierPrefix) -> Result<(), DbError> {
+        self.mailbox
+            .clear_mailbox(self.identifiers.designated_key(id)?)
+    }
+
+    #[cfg(feature = "mailbox")]
+    pub fn get_mailbox_evicted(&self, id: &IdentifierPrefix) -> Result<QueryTopics, DbError> {
+        self.mailbox
+            .get_mailbox_evicted(self.identifiers.designated_key(id)?)
+    }
+
+    #[cfg(feature = "mailbox")]
+    pub fn expire_mailbox_forwards(
+        &self,
+        id: &IdentifierPrefix,
+        max_age: std::time::Duration,
+    ) -> Result<usize, DbError> {
+        self.mailbox
+            .expire_mailbox_forwards(self.identifiers.designated_key(id)?, max_age)
+    }
+
+    /// Returns topic indexes of messages from `id` mailbox acknowledged by
+    /// `recipient`.
+    #[cfg(feature = "mailbox")]
+    pub fn get_mailbox_cursor(
+        &self,
+        id: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
+    ) -> Result<Option<QueryTopics>, DbError> {
+        self.mailbox
+            .get_mailbox_cursor(self.identifiers.designated_key(id)?, recipient)
+    }
+
+    #[cfg(feature = "mailbox")]
+    pub fn set_mailbox_cursor(
+        &self,
+        id: &IdentifierPrefix,
+        recipient: &IdentifierPrefix,
+        topics: QueryTopics,
+    ) -> Result<(), DbError> {
+        self.mailbox
+            .set_mailbox_cursor(self.identifiers.designated_key(id)?, recipient, topics)
+    }
 }
 
 #[derive(Debug, thiserror::Error, Serialize, Deserialize)]
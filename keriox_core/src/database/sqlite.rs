@@ -0,0 +1,1101 @@
+//! SQLite implementation of [`EventDatabase`]. Stores the same data as
+//! [`RedbDatabase`](super::redb::RedbDatabase), but in plain SQL tables, so
+//! database can be inspected and backed up with standard SQLite tools.
+//! Events are stored in format they were serialized in (JSON, CBOR or
+//! MGPK), other values are encoded as JSON text.
+//!
+//! Tables:
+//! - `events`: event digest -> event serialized as in KEL,
+//! - `kels`: (identifier, sn) -> event digest,
+//! - `signatures`: (identifier, sn) -> indexed signature (one or more),
+//! - `nontrans_receipts`: (identifier, sn) -> nontransferable receipt (one or more),
+//! - `trans_receipts`: (identifier, sn) -> transferable receipt (one or more),
+//! - `first_seen`: (identifier, sn, event digest) -> time when event was
+//!   accepted into KEL for the first time,
+//! - `state_history`: (identifier, sn) -> key state transition made by
+//!   establishment event,
+//! - `key_states`: identifier -> current key state,
+//! - `replies`: identifier -> accepted reply (one or more).
+//!
+//! Event already in KEL can be replaced only by rotation, that recovers
+//! identifier. Replaced event and events following it are removed from KEL
+//! together with their signatures and receipts. Escrows stay in their own
+//! databases.
+//!
+//! # Migration from redb
+//!
+//! Stop the service, then copy content of redb database into new SQLite file
+//! with [`SqliteDatabase::import_redb`]:
+//!
+//! ```ignore
+//! let redb = RedbDatabase::new(Path::new("events_database"))?;
+//! let sqlite = SqliteDatabase::new(Path::new("events.sqlite"))?;
+//! sqlite.import_redb(&redb)?;
+//! assert!(sqlite.check_consistency()?.is_empty());
+//! ```
+//!
+//! Import can be repeated, already stored elements are skipped. Keep redb
+//! database until new one is verified. Replies are kept in sled database
+//! by redb based services, so they aren't imported.
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Local};
+use rusqlite::{params, Connection, OptionalExtension};
+use said::{sad::SerializationFormats, SelfAddressingIdentifier};
+
+#[cfg(feature = "query")]
+use crate::query::reply_event::{ReplyRoute, SignedReply};
+use crate::{
+    clock::{system_clock, Clock},
+    event::{event_data::EventData, receipt::Receipt, KeyEvent},
+    event_message::{
+        cesr_adapter::{parse_event_type, EventType},
+        msg::KeriEvent,
+        signature::{Nontransferable, Transferable},
+        signed_event_message::{
+            SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
+        },
+    },
+    prefix::{IdentifierPrefix, IndexedSignature},
+    state::{IdentifierState, StateTransition},
+};
+use cesrox::primitives::CesrPrimitive;
+
+use super::{
+    redb::RedbDatabase, timestamped::TimestampedSignedEventMessage, EventDatabase, QueryParameters,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SqliteError {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("No digest in provided event")]
+    MissingDigest,
+    #[error("Can't parse stored value: {0}")]
+    WrongValue(String),
+    #[error("Database lock poisoned")]
+    Poisoned,
+    #[error("Redb error: {0}")]
+    Redb(#[from] super::redb::RedbError),
+    #[error("KEL of {id} contains other event at sn {sn}")]
+    Conflict { id: IdentifierPrefix, sn: u64 },
+    #[error("Can't compute key state: {0}")]
+    KeyState(#[from] crate::error::Error),
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        digest TEXT PRIMARY KEY,
+        event BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS kels (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        digest TEXT NOT NULL,
+        PRIMARY KEY (identifier, sn)
+    );
+    CREATE TABLE IF NOT EXISTS signatures (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        signature TEXT NOT NULL,
+        UNIQUE (identifier, sn, signature)
+    );
+    CREATE TABLE IF NOT EXISTS nontrans_receipts (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        receipt TEXT NOT NULL,
+        UNIQUE (identifier, sn, receipt)
+    );
+    CREATE TABLE IF NOT EXISTS trans_receipts (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        receipt TEXT NOT NULL,
+        UNIQUE (identifier, sn, receipt)
+    );
+    CREATE TABLE IF NOT EXISTS first_seen (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        digest TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        PRIMARY KEY (identifier, sn, digest)
+    );
+    CREATE INDEX IF NOT EXISTS first_seen_time ON first_seen (identifier, timestamp);
+    CREATE TABLE IF NOT EXISTS state_history (
+        identifier TEXT NOT NULL,
+        sn INTEGER NOT NULL,
+        transition TEXT NOT NULL,
+        PRIMARY KEY (identifier, sn)
+    );
+    CREATE TABLE IF NOT EXISTS key_states (
+        identifier TEXT PRIMARY KEY,
+        state TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS replies (
+        identifier TEXT NOT NULL,
+        ksn_signer TEXT,
+        reply TEXT NOT NULL,
+        UNIQUE (identifier, reply)
+    );
+";
+
+/// Tables keyed by identifier and sn, that can hold many values for one key.
+#[derive(Clone, Copy)]
+enum MultiTable {
+    Signatures,
+    NontransReceipts,
+    TransReceipts,
+}
+
+impl MultiTable {
+    fn name(&self) -> &'static str {
+        match self {
+            MultiTable::Signatures => "signatures",
+            MultiTable::NontransReceipts => "nontrans_receipts",
+            MultiTable::TransReceipts => "trans_receipts",
+        }
+    }
+
+    fn column(&self) -> &'static str {
+        match self {
+            MultiTable::Signatures => "signature",
+            MultiTable::NontransReceipts | MultiTable::TransReceipts => "receipt",
+        }
+    }
+}
+
+/// Tables cleared from replaced event on, when KEL is recovered.
+const KEL_TABLES: [&str; 5] = [
+    "kels",
+    "signatures",
+    "nontrans_receipts",
+    "trans_receipts",
+    "state_history",
+];
+
+pub struct SqliteDatabase {
+    connection: Mutex<Connection>,
+    /// Used for first seen timestamps of events.
+    clock: Arc<dyn Clock>,
+}
+
+/// SQLite integers are signed, so sequence numbers are capped at `i64::MAX`.
+fn sql_sn(sn: u64) -> i64 {
+    sn.min(i64::MAX as u64) as i64
+}
+
+/// Parses stored key event, in any serialization format.
+fn decode_event(bytes: &[u8]) -> Result<KeriEvent<KeyEvent>, SqliteError> {
+    match parse_event_type(bytes) {
+        Ok(EventType::KeyEvent(event)) => Ok(event),
+        _ => Err(SqliteError::WrongValue("event".to_string())),
+    }
+}
+
+fn decode_timestamp(micros: i64) -> Result<DateTime<Local>, SqliteError> {
+    DateTime::from_timestamp_micros(micros)
+        .map(|time| time.with_timezone(&Local))
+        .ok_or_else(|| SqliteError::WrongValue("timestamp".to_string()))
+}
+
+fn insert_multi<V: serde::Serialize>(
+    conn: &Connection,
+    table: MultiTable,
+    id: &IdentifierPrefix,
+    sn: u64,
+    values: &[V],
+) -> Result<(), SqliteError> {
+    let mut stmt = conn.prepare(&format!(
+        "INSERT OR IGNORE INTO {} (identifier, sn, {}) VALUES (?1, ?2, ?3)",
+        table.name(),
+        table.column()
+    ))?;
+    for value in values {
+        stmt.execute(params![
+            id.to_str(),
+            sql_sn(sn),
+            serde_json::to_string(value)?
+        ])?;
+    }
+    Ok(())
+}
+
+/// Returns key state computed from KEL stored in database.
+fn compute_key_state(
+    conn: &Connection,
+    id: &IdentifierPrefix,
+) -> Result<IdentifierState, SqliteError> {
+    let mut stmt = conn.prepare(
+        "SELECT events.event FROM kels
+        JOIN events ON kels.digest = events.digest
+        WHERE kels.identifier = ?1
+        ORDER BY kels.sn",
+    )?;
+    let events = stmt.query_map(params![id.to_str()], |row| row.get::<_, Vec<u8>>(0))?;
+    let mut state = IdentifierState::default();
+    for event in events {
+        state = state.apply(&decode_event(&event?)?)?;
+    }
+    Ok(state)
+}
+
+impl SqliteDatabase {
+    pub fn new(db_path: &Path) -> Result<Self, SqliteError> {
+        let connection = Connection::open(db_path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            clock: system_clock(),
+        })
+    }
+
+    /// Sets clock used for first seen timestamps of events.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    fn connection(&self) -> Result<std::sync::MutexGuard<'_, Connection>, SqliteError> {
+        self.connection.lock().map_err(|_| SqliteError::Poisoned)
+    }
+
+    /// Returns identifiers that have at least one event in KEL table.
+    pub fn get_identifiers(&self) -> Result<Vec<IdentifierPrefix>, SqliteError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare("SELECT DISTINCT identifier FROM kels ORDER BY identifier")?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|id| {
+                id?.parse()
+                    .map_err(|_| SqliteError::WrongValue("identifier".to_string()))
+            })
+            .collect();
+        ids
+    }
+
+    /// Returns current key state of identifier, or `None` if identifier is
+    /// unknown.
+    pub fn get_key_state(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Option<IdentifierState>, SqliteError> {
+        let conn = self.connection()?;
+        conn.query_row(
+            "SELECT state FROM key_states WHERE identifier = ?1",
+            params![id.to_str()],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+        .map(|state| Ok(serde_json::from_str(&state)?))
+        .transpose()
+    }
+
+    /// Returns key state transitions of identifier, ordered by sn.
+    pub fn get_state_history(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<StateTransition>, SqliteError> {
+        let conn = self.connection()?;
+        let mut stmt =
+            conn.prepare("SELECT transition FROM state_history WHERE identifier = ?1 ORDER BY sn")?;
+        let transitions = stmt
+            .query_map(params![id.to_str()], |row| row.get::<_, String>(0))?
+            .map(|transition| Ok(serde_json::from_str(&transition?)?))
+            .collect();
+        transitions
+    }
+
+    /// Returns events of identifier first seen between `from` (inclusive)
+    /// and `to` (exclusive), ordered by first seen time. Timestamp of
+    /// returned event is its first seen time. Events replaced in KEL since
+    /// then are skipped.
+    pub fn get_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Result<Vec<TimestampedSignedEventMessage>, SqliteError> {
+        let events = {
+            let conn = self.connection()?;
+            let mut stmt = conn.prepare(
+                "SELECT kels.sn, events.event, first_seen.timestamp FROM first_seen
+                JOIN kels ON first_seen.identifier = kels.identifier
+                    AND first_seen.sn = kels.sn AND first_seen.digest = kels.digest
+                JOIN events ON kels.digest = events.digest
+                WHERE first_seen.identifier = ?1
+                    AND first_seen.timestamp >= ?2 AND first_seen.timestamp < ?3
+                ORDER BY first_seen.timestamp, kels.sn",
+            )?;
+            let rows = stmt.query_map(
+                params![id.to_str(), from.timestamp_micros(), to.timestamp_micros()],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)? as u64,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        events
+            .into_iter()
+            .map(|(sn, event, timestamp)| {
+                let signatures = self
+                    .get_multi::<IndexedSignature>(MultiTable::Signatures, id, sn, 1)?
+                    .into_iter()
+                    .flat_map(|(_sn, sigs)| sigs)
+                    .collect();
+                Ok(TimestampedSignedEventMessage::new_at(
+                    SignedEventMessage::new(&decode_event(&event)?, signatures, None, None),
+                    decode_timestamp(timestamp)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Checks if every KEL entry points to stored event. Returns identifiers
+    /// and sequence numbers of entries without event.
+    pub fn check_consistency(&self) -> Result<Vec<(IdentifierPrefix, u64)>, SqliteError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT kels.identifier, kels.sn FROM kels
+            LEFT JOIN events ON kels.digest = events.digest
+            WHERE events.digest IS NULL",
+        )?;
+        let dangling = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .map(|entry| {
+                let (id, sn) = entry?;
+                let id = id
+                    .parse()
+                    .map_err(|_| SqliteError::WrongValue("identifier".to_string()))?;
+                Ok((id, sn as u64))
+            })
+            .collect();
+        dangling
+    }
+
+    /// Copies content of database into new SQLite file at `target` path.
+    pub fn backup(&self, target: &Path) -> Result<(), SqliteError> {
+        let conn = self.connection()?;
+        conn.execute("VACUUM INTO ?1", params![target.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Copies KELs, receipts, first seen times and key state transitions of
+    /// all identifiers from redb database. See module documentation for
+    /// migration notes.
+    pub fn import_redb(&self, redb: &RedbDatabase) -> Result<(), SqliteError> {
+        let (since, until) = (
+            DateTime::<chrono::Utc>::UNIX_EPOCH.with_timezone(&Local),
+            Local::now() + Duration::days(1),
+        );
+        for id in redb.get_identifiers()? {
+            {
+                let conn = self.connection()?;
+                for event in redb.get_events_between(&id, since, until)? {
+                    let message = &event.signed_event_message.event_message;
+                    conn.execute(
+                        "INSERT OR IGNORE INTO first_seen (identifier, sn, digest, timestamp)
+                        VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            id.to_str(),
+                            sql_sn(message.data.sn),
+                            message
+                                .digest()
+                                .map_err(|_e| SqliteError::MissingDigest)?
+                                .to_string(),
+                            event.timestamp.timestamp_micros()
+                        ],
+                    )?;
+                }
+                for transition in redb.get_state_history(&id)? {
+                    conn.execute(
+                        "INSERT OR REPLACE INTO state_history (identifier, sn, transition)
+                        VALUES (?1, ?2, ?3)",
+                        params![
+                            id.to_str(),
+                            sql_sn(transition.sn),
+                            serde_json::to_string(&transition)?
+                        ],
+                    )?;
+                }
+            }
+            let kel = redb
+                .get_kel_finalized_events(QueryParameters::All { id: &id })
+                .into_iter()
+                .flatten();
+            for event in kel {
+                let event = event.signed_event_message;
+                let sn = event.event_message.data.sn;
+                self.add_kel_finalized_event(event, &id)?;
+                let trans = redb
+                    .get_receipts_t(QueryParameters::BySn { id: id.clone(), sn })
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                insert_multi(
+                    &*self.connection()?,
+                    MultiTable::TransReceipts,
+                    &id,
+                    sn,
+                    &trans,
+                )?;
+            }
+            let nontrans = redb
+                .get_receipts_nt(QueryParameters::All { id: &id })
+                .into_iter()
+                .flatten();
+            for receipt in nontrans {
+                self.add_receipt_nt(receipt, &id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves accepted reply of identifier. Key state notice replaces notice
+    /// signed by the same identifier saved before.
+    #[cfg(feature = "query")]
+    pub fn update_accepted_reply(
+        &self,
+        rpy: SignedReply,
+        id: &IdentifierPrefix,
+    ) -> Result<(), SqliteError> {
+        let ksn_signer = match rpy.reply.get_route() {
+            ReplyRoute::Ksn(signer, _) => Some(signer.to_str()),
+            _ => None,
+        };
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        if let Some(signer) = &ksn_signer {
+            tx.execute(
+                "DELETE FROM replies WHERE identifier = ?1 AND ksn_signer = ?2",
+                params![id.to_str(), signer],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR IGNORE INTO replies (identifier, ksn_signer, reply) VALUES (?1, ?2, ?3)",
+            params![id.to_str(), ksn_signer, serde_json::to_string(&rpy)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns accepted replies of identifier, in order they were saved.
+    #[cfg(feature = "query")]
+    pub fn get_accepted_replys(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Vec<SignedReply>, SqliteError> {
+        let conn = self.connection()?;
+        let mut stmt =
+            conn.prepare("SELECT reply FROM replies WHERE identifier = ?1 ORDER BY rowid")?;
+        let replies = stmt
+            .query_map(params![id.to_str()], |row| row.get::<_, String>(0))?
+            .map(|reply| Ok(serde_json::from_str(&reply?)?))
+            .collect();
+        replies
+    }
+
+    #[cfg(feature = "query")]
+    pub fn remove_accepted_reply(
+        &self,
+        id: &IdentifierPrefix,
+        rpy: SignedReply,
+    ) -> Result<(), SqliteError> {
+        self.connection()?.execute(
+            "DELETE FROM replies WHERE identifier = ?1 AND reply = ?2",
+            params![id.to_str(), serde_json::to_string(&rpy)?],
+        )?;
+        Ok(())
+    }
+
+    /// Returns values stored in table for sequence numbers in range
+    /// `start..start + limit`, grouped by sequence number.
+    fn get_multi<V: serde::de::DeserializeOwned>(
+        &self,
+        table: MultiTable,
+        id: &IdentifierPrefix,
+        start: u64,
+        limit: u64,
+    ) -> Result<Vec<(u64, Vec<V>)>, SqliteError> {
+        let conn = self.connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT sn, {} FROM {} WHERE identifier = ?1 AND sn >= ?2 AND sn < ?3 ORDER BY sn, rowid",
+            table.column(),
+            table.name()
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                id.to_str(),
+                sql_sn(start),
+                sql_sn(start.saturating_add(limit))
+            ],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?)),
+        )?;
+        let mut out: Vec<(u64, Vec<V>)> = vec![];
+        for row in rows {
+            let (sn, value) = row?;
+            let value = serde_json::from_str(&value)?;
+            match out.last_mut() {
+                Some((last_sn, values)) if *last_sn == sn => values.push(value),
+                _ => out.push((sn, vec![value])),
+            }
+        }
+        Ok(out)
+    }
+
+    fn get_event_digest(
+        &self,
+        id: &IdentifierPrefix,
+        sn: u64,
+    ) -> Result<Option<SelfAddressingIdentifier>, SqliteError> {
+        let conn = self.connection()?;
+        conn.query_row(
+            "SELECT digest FROM kels WHERE identifier = ?1 AND sn = ?2",
+            params![id.to_str(), sql_sn(sn)],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?
+        .map(|digest| {
+            digest
+                .parse()
+                .map_err(|_| SqliteError::WrongValue("digest".to_string()))
+        })
+        .transpose()
+    }
+
+    /// Returns events of KEL in range `start..start + limit`. Timestamp of
+    /// returned event is its first seen time.
+    pub fn get_kel(
+        &self,
+        id: &IdentifierPrefix,
+        start: u64,
+        limit: u64,
+    ) -> Result<Vec<TimestampedSignedEventMessage>, SqliteError> {
+        let events = {
+            let conn = self.connection()?;
+            let mut stmt = conn.prepare(
+                "SELECT kels.sn, events.event, first_seen.timestamp FROM kels
+                JOIN events ON kels.digest = events.digest
+                LEFT JOIN first_seen ON first_seen.identifier = kels.identifier
+                    AND first_seen.sn = kels.sn AND first_seen.digest = kels.digest
+                WHERE kels.identifier = ?1 AND kels.sn >= ?2 AND kels.sn < ?3
+                ORDER BY kels.sn",
+            )?;
+            let rows = stmt.query_map(
+                params![
+                    id.to_str(),
+                    sql_sn(start),
+                    sql_sn(start.saturating_add(limit))
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)? as u64,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, Option<i64>>(2)?,
+                    ))
+                },
+            )?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        let mut signatures = self
+            .get_multi::<IndexedSignature>(MultiTable::Signatures, id, start, limit)?
+            .into_iter()
+            .peekable();
+
+        events
+            .into_iter()
+            .map(|(sn, event, first_seen)| {
+                let event = decode_event(&event)?;
+                let sigs = match signatures.next_if(|(sig_sn, _)| *sig_sn <= sn) {
+                    Some((sig_sn, sigs)) if sig_sn == sn => sigs,
+                    _ => vec![],
+                };
+                let event = SignedEventMessage::new(&event, sigs, None, None);
+                Ok(match first_seen {
+                    Some(first_seen) => {
+                        TimestampedSignedEventMessage::new_at(event, decode_timestamp(first_seen)?)
+                    }
+                    None => TimestampedSignedEventMessage::new(event, self.clock.as_ref()),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns nontransferable receipts of events in range
+    /// `start..start + limit`.
+    pub fn get_nontrans_receipts(
+        &self,
+        id: &IdentifierPrefix,
+        start: u64,
+        limit: u64,
+    ) -> Result<Vec<SignedNontransferableReceipt>, SqliteError> {
+        self.get_multi::<Nontransferable>(MultiTable::NontransReceipts, id, start, limit)?
+            .into_iter()
+            .filter_map(|(sn, signatures)| {
+                // Receipts of events that are not in KEL yet are skipped.
+                self.get_event_digest(id, sn).transpose().map(|digest| {
+                    Ok(SignedNontransferableReceipt {
+                        body: Receipt::new(SerializationFormats::JSON, digest?, id.clone(), sn),
+                        signatures,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Returns transferable receipts of events in range
+    /// `start..start + limit`.
+    pub fn get_trans_receipts(
+        &self,
+        id: &IdentifierPrefix,
+        start: u64,
+        limit: u64,
+    ) -> Result<Vec<Transferable>, SqliteError> {
+        Ok(self
+            .get_multi::<Transferable>(MultiTable::TransReceipts, id, start, limit)?
+            .into_iter()
+            .flat_map(|(_sn, receipts)| receipts)
+            .collect())
+    }
+}
+
+/// Returns range of sequence numbers queried with `params`.
+fn query_range(params: QueryParameters) -> (IdentifierPrefix, u64, u64) {
+    match params {
+        QueryParameters::BySn { id, sn } => (id, sn, 1),
+        QueryParameters::Range { id, start, limit } => (id, start, limit),
+        QueryParameters::All { id } => (id.clone(), 0, u64::MAX),
+    }
+}
+
+impl EventDatabase for SqliteDatabase {
+    type Error = SqliteError;
+
+    fn add_kel_finalized_event(
+        &self,
+        signed_event: SignedEventMessage,
+        _id: &IdentifierPrefix,
+    ) -> Result<(), SqliteError> {
+        let event = &signed_event.event_message;
+        let digest = event
+            .digest()
+            .map_err(|_e| SqliteError::MissingDigest)?
+            .to_string();
+        let id = &event.data.prefix;
+        let sn = event.data.sn;
+        let encoded = event
+            .encode()
+            .map_err(|e| SqliteError::WrongValue(e.to_string()))?;
+        let first_seen = self.clock.now();
+        let transition = StateTransition::from_event(event, first_seen)?;
+
+        let mut conn = self.connection()?;
+        let tx = conn.transaction()?;
+        let current = tx
+            .query_row(
+                "SELECT digest FROM kels WHERE identifier = ?1 AND sn = ?2",
+                params![id.to_str(), sql_sn(sn)],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        let kel_changed = match current {
+            Some(current) if current == digest => false,
+            Some(_) => match event.data.event_data {
+                EventData::Rot(_) | EventData::Drt(_) => {
+                    for table in KEL_TABLES {
+                        tx.execute(
+                            &format!("DELETE FROM {} WHERE identifier = ?1 AND sn >= ?2", table),
+                            params![id.to_str(), sql_sn(sn)],
+                        )?;
+                    }
+                    true
+                }
+                _ => return Err(SqliteError::Conflict { id: id.clone(), sn }),
+            },
+            None => true,
+        };
+        tx.execute(
+            "INSERT OR IGNORE INTO events (digest, event) VALUES (?1, ?2)",
+            params![digest, encoded],
+        )?;
+        if kel_changed {
+            tx.execute(
+                "INSERT INTO kels (identifier, sn, digest) VALUES (?1, ?2, ?3)",
+                params![id.to_str(), sql_sn(sn), digest],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO first_seen (identifier, sn, digest, timestamp)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    id.to_str(),
+                    sql_sn(sn),
+                    digest,
+                    first_seen.timestamp_micros()
+                ],
+            )?;
+            if let Some(transition) = transition {
+                tx.execute(
+                    "INSERT OR IGNORE INTO state_history (identifier, sn, transition)
+                    VALUES (?1, ?2, ?3)",
+                    params![id.to_str(), sql_sn(sn), serde_json::to_string(&transition)?],
+                )?;
+            }
+            let stored = tx
+                .query_row(
+                    "SELECT state FROM key_states WHERE identifier = ?1",
+                    params![id.to_str()],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+                .map(|state| serde_json::from_str::<IdentifierState>(&state))
+                .transpose()?;
+            // Next event is applied to stored state, otherwise (e.g. after
+            // recovery) state is computed from the whole KEL.
+            let state = match stored {
+                Some(state) if state.sn + 1 == sn => state.apply(event)?,
+                None if sn == 0 => IdentifierState::default().apply(event)?,
+                _ => compute_key_state(&tx, id)?,
+            };
+            tx.execute(
+                "INSERT OR REPLACE INTO key_states (identifier, state) VALUES (?1, ?2)",
+                params![id.to_str(), serde_json::to_string(&state)?],
+            )?;
+        }
+        insert_multi(
+            &tx,
+            MultiTable::Signatures,
+            id,
+            sn,
+            &signed_event.signatures,
+        )?;
+        if let Some(wits) = &signed_event.witness_receipts {
+            insert_multi(&tx, MultiTable::NontransReceipts, id, sn, wits)?;
+        };
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn add_receipt_t(
+        &self,
+        receipt: SignedTransferableReceipt,
+        _id: &IdentifierPrefix,
+    ) -> Result<(), SqliteError> {
+        let sn = receipt.body.sn;
+        let id = receipt.body.prefix;
+        let transferable = Transferable::Seal(receipt.validator_seal, receipt.signatures);
+        insert_multi(
+            &*self.connection()?,
+            MultiTable::TransReceipts,
+            &id,
+            sn,
+            &[transferable],
+        )
+    }
+
+    fn add_receipt_nt(
+        &self,
+        receipt: SignedNontransferableReceipt,
+        _id: &IdentifierPrefix,
+    ) -> Result<(), SqliteError> {
+        let sn = receipt.body.sn;
+        let id = receipt.body.prefix;
+        insert_multi(
+            &*self.connection()?,
+            MultiTable::NontransReceipts,
+            &id,
+            sn,
+            &receipt.signatures,
+        )
+    }
+
+    /// Returns `None` also if database can't be read. Use
+    /// [`EventDatabase::try_get_kel_finalized_events`] to get the error.
+    fn get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = TimestampedSignedEventMessage>> {
+        self.try_get_kel_finalized_events(params)
+            .unwrap_or_default()
+            .map(|kel| kel.into_iter())
+    }
+
+    fn try_get_kel_finalized_events(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Option<Vec<TimestampedSignedEventMessage>>, SqliteError> {
+        let all = matches!(params, QueryParameters::All { .. });
+        let (id, start, limit) = query_range(params);
+        let kel = self.get_kel(&id, start, limit)?;
+        Ok(if all && kel.is_empty() {
+            None
+        } else {
+            Some(kel)
+        })
+    }
+
+    fn get_kel_finalized_events_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Option<Vec<TimestampedSignedEventMessage>> {
+        self.get_events_between(id, from, to).ok()
+    }
+
+    /// Returns `None` if database can't be read. Use
+    /// [`EventDatabase::try_get_receipts_t`] to get the error.
+    fn get_receipts_t(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = Transferable>> {
+        self.try_get_receipts_t(params)
+            .map(|receipts| receipts.into_iter())
+            .ok()
+    }
+
+    fn try_get_receipts_t(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<Transferable>, SqliteError> {
+        let (id, start, limit) = query_range(params);
+        self.get_trans_receipts(&id, start, limit)
+    }
+
+    /// Returns `None` if database can't be read. Use
+    /// [`EventDatabase::try_get_receipts_nt`] to get the error.
+    fn get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Option<impl DoubleEndedIterator<Item = SignedNontransferableReceipt>> {
+        self.try_get_receipts_nt(params)
+            .map(|receipts| receipts.into_iter())
+            .ok()
+    }
+
+    fn try_get_receipts_nt(
+        &self,
+        params: QueryParameters,
+    ) -> Result<Vec<SignedNontransferableReceipt>, SqliteError> {
+        let (id, start, limit) = query_range(params);
+        self.get_nontrans_receipts(&id, start, limit)
+    }
+}
+
+#[test]
+fn test_sqlite_kel_and_receipts() {
+    use crate::actor::parse_event_stream;
+    use crate::event_message::signed_event_message::{Message, Notice};
+    use tempfile::NamedTempFile;
+
+    let file_path = NamedTempFile::new().unwrap();
+    let db = SqliteDatabase::new(file_path.path()).unwrap();
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}-AADAAD4SyJSYlsQG22MGXzRGz2PTMqpkgOyUfq7cS99sC2BCWwdVmEMKiTEeWe5kv-l_d9auxdadQuArLtAGEArW8wEABD0z_vQmFImZXfdR-0lclcpZFfkJJJNXDcUNrf7a-mGsxNLprJo-LROwDkH5m7tVrb-a1jcor2dHD9Jez-r4bQIACBFeU05ywfZycLdR0FxCvAR9BfV9im8tWe1DglezqJLf-vHRQSChY1KafbYNc96hYYpbuN90WzuCRMgV8KgRsEC"#;
+    let rot_raw: &[u8] = br#"{"v":"KERI10JSON00021c_","t":"rot","d":"EHjzZj4i_-RpTN2Yh-NocajFROJ_GkBtlByhRykqiXgz","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"1","p":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","kt":"2","k":["DCjxOXniUc5EUzDqERlXdptfKPHy6jNo_ZGsS4Vd8fAE","DNZHARO4dCJlluv0qezEMRmErIWWc-lzOzolBOQ15tHV","DOCQ4KN1jUlKbfjRteDYt9fxgpq1NK9_MqO5IA7shpED"],"nt":"2","n":["EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m","EATiZAHl0kzKID6faaQP2O7zB3Hj7eH3bE-vgKVAtsyU","EG6e7dJhh78ZqeIZ-eMbe-OB3TwFMPmrSsh9k75XIjLP"],"bt":"0","br":[],"ba":[],"a":[]}-AADAAAqV6xpsAAEB_FJP5UdYO5qiJphz8cqXbTjB9SRy8V0wIim-lgafF4o-b7TW0spZtzx2RXUfZLQQCIKZsw99k8AABBP8nfF3t6bf4z7eNoBgUJR-hdhw7wnlljMZkeY5j2KFRI_s8wqtcOFx1A913xarGJlO6UfrqFWo53e9zcD8egIACB8DKLMZcCGICuk98RCEVuS0GsqVngi1d-7gAX0jid42qUcR3aiYDMp2wJhqJn-iHJVvtB-LK7TRTggBtMDjuwB"#;
+    let id: IdentifierPrefix = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+
+    let mut events = vec![];
+    for event in [icp_raw, rot_raw] {
+        match parse_event_stream(event).unwrap().first().unwrap() {
+            Message::Notice(Notice::Event(event)) => {
+                db.add_kel_finalized_event(event.clone(), &id).unwrap();
+                events.push(event.clone());
+            }
+            _ => unreachable!(),
+        }
+    }
+    assert_eq!(db.get_identifiers().unwrap(), vec![id.clone()]);
+    assert!(db.check_consistency().unwrap().is_empty());
+
+    let kel: Vec<_> = db
+        .get_kel_finalized_events(QueryParameters::All { id: &id })
+        .unwrap()
+        .map(|ev| ev.signed_event_message)
+        .collect();
+    assert_eq!(kel, events);
+
+    let rot: Vec<_> = db
+        .get_kel_finalized_events(QueryParameters::Range {
+            id: id.clone(),
+            start: 1,
+            limit: 5,
+        })
+        .unwrap()
+        .collect();
+    assert_eq!(rot.len(), 1);
+    assert_eq!(rot[0].signed_event_message, events[1]);
+
+    // Adding the same event again doesn't duplicate signatures.
+    db.add_kel_finalized_event(events[0].clone(), &id).unwrap();
+    let icp = db
+        .get_kel_finalized_events(QueryParameters::BySn {
+            id: id.clone(),
+            sn: 0,
+        })
+        .unwrap()
+        .next()
+        .unwrap();
+    assert_eq!(icp.signed_event_message.signatures.len(), 3);
+
+    let receipt = SignedNontransferableReceipt {
+        body: Receipt::new(
+            SerializationFormats::JSON,
+            events[0].event_message.digest().unwrap(),
+            id.clone(),
+            0,
+        ),
+        signatures: vec![Nontransferable::Indexed(events[0].signatures[..1].to_vec())],
+    };
+    db.add_receipt_nt(receipt.clone(), &id).unwrap();
+    let receipts: Vec<_> = db
+        .get_receipts_nt(QueryParameters::All { id: &id })
+        .unwrap()
+        .collect();
+    assert_eq!(receipts, vec![receipt]);
+
+    // Migrated database contains the same KEL.
+    let redb_path = NamedTempFile::new().unwrap();
+    let redb = RedbDatabase::new(redb_path.path()).unwrap();
+    for event in &events {
+        redb.add_kel_finalized_event(event.clone(), &id).unwrap();
+    }
+    let migrated_path = NamedTempFile::new().unwrap();
+    let migrated = SqliteDatabase::new(migrated_path.path()).unwrap();
+    migrated.import_redb(&redb).unwrap();
+    let migrated_kel: Vec<_> = migrated
+        .get_kel_finalized_events(QueryParameters::All { id: &id })
+        .unwrap()
+        .map(|ev| ev.signed_event_message)
+        .collect();
+    assert_eq!(migrated_kel, events);
+}
+
+#[test]
+fn test_sqlite_first_seen_key_state_and_formats() {
+    use crate::actor::parse_event_stream;
+    use crate::event::sections::seal::DigestSeal;
+    use crate::event_message::{
+        event_msg_builder::EventMsgBuilder,
+        signed_event_message::{Message, Notice},
+        EventTypeTag,
+    };
+    use said::derivation::{HashFunction, HashFunctionCode};
+    use tempfile::NamedTempFile;
+
+    let file_path = NamedTempFile::new().unwrap();
+    let db = SqliteDatabase::new(file_path.path()).unwrap();
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}-AADAAD4SyJSYlsQG22MGXzRGz2PTMqpkgOyUfq7cS99sC2BCWwdVmEMKiTEeWe5kv-l_d9auxdadQuArLtAGEArW8wEABD0z_vQmFImZXfdR-0lclcpZFfkJJJNXDcUNrf7a-mGsxNLprJo-LROwDkH5m7tVrb-a1jcor2dHD9Jez-r4bQIACBFeU05ywfZycLdR0FxCvAR9BfV9im8tWe1DglezqJLf-vHRQSChY1KafbYNc96hYYpbuN90WzuCRMgV8KgRsEC"#;
+    let icp = match parse_event_stream(icp_raw).unwrap().first().unwrap() {
+        Message::Notice(Notice::Event(event)) => event.clone(),
+        _ => unreachable!(),
+    };
+    let id = icp.event_message.data.prefix.clone();
+    db.add_kel_finalized_event(icp.clone(), &id).unwrap();
+
+    let state = db.get_key_state(&id).unwrap().unwrap();
+    assert_eq!(state.sn, 0);
+    assert_eq!(state.prefix, id);
+    assert_eq!(db.get_state_history(&id).unwrap().len(), 1);
+
+    // Other event at the same sn can't replace interaction event.
+    let ixn = |payload: &str| {
+        let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+            .with_prefix(&id)
+            .with_sn(1)
+            .with_previous_event(&icp.event_message.digest().unwrap())
+            .with_seal([DigestSeal::new(
+                HashFunction::from(HashFunctionCode::Blake3_256).derive(payload.as_bytes()),
+            )])
+            .build()
+            .unwrap();
+        SignedEventMessage::new(&ixn, icp.signatures.clone(), None, None)
+    };
+    db.add_kel_finalized_event(ixn("first"), &id).unwrap();
+    assert!(matches!(
+        db.add_kel_finalized_event(ixn("second"), &id),
+        Err(SqliteError::Conflict { sn: 1, .. })
+    ));
+    assert_eq!(db.get_key_state(&id).unwrap().unwrap().sn, 1);
+
+    // First seen time survives reopening database.
+    let first_seen = db.get_kel(&id, 0, 2).unwrap();
+    drop(db);
+    let db = SqliteDatabase::new(file_path.path()).unwrap();
+    let reopened = db.get_kel(&id, 0, 2).unwrap();
+    assert_eq!(reopened.len(), 2);
+    assert_eq!(
+        reopened.iter().map(|ev| ev.timestamp).collect::<Vec<_>>(),
+        first_seen.iter().map(|ev| ev.timestamp).collect::<Vec<_>>()
+    );
+    let between = db
+        .get_events_between(
+            &id,
+            first_seen[0].timestamp,
+            Local::now() + Duration::seconds(1),
+        )
+        .unwrap();
+    assert_eq!(between.len(), 2);
+
+    // Events serialized in CBOR are stored as they are.
+    let cbor_icp = match &icp.event_message.data.event_data {
+        EventData::Icp(icp) => icp
+            .clone()
+            .incept_self_addressing(
+                HashFunctionCode::Blake3_256.into(),
+                SerializationFormats::CBOR,
+            )
+            .unwrap(),
+        _ => unreachable!(),
+    };
+    let cbor_id = cbor_icp.data.prefix.clone();
+    db.add_kel_finalized_event(
+        SignedEventMessage::new(&cbor_icp, icp.signatures.clone(), None, None),
+        &cbor_id,
+    )
+    .unwrap();
+    let stored = db
+        .try_get_kel_finalized_events(QueryParameters::All { id: &cbor_id })
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored[0].signed_event_message.event_message, cbor_icp);
+    assert!(db.check_consistency().unwrap().is_empty());
+}
+
+#[cfg(feature = "query")]
+#[test]
+fn test_sqlite_replies() {
+    use crate::actor::parse_op_stream;
+    use crate::event_message::signed_event_message::Op;
+    use tempfile::NamedTempFile;
+
+    let file_path = NamedTempFile::new().unwrap();
+    let db = SqliteDatabase::new(file_path.path()).unwrap();
+
+    let end_role = br#"{"v":"KERI10JSON000116_","t":"rpy","d":"EGS28iaGy4iDF8w4q7XkXtdnp4lLj97oZ9n-QrZCyt_8","dt":"2023-07-20T12:31:59.458182+00:00","r":"/end/role/add","a":{"cid":"ECQxoxuKOcO7KkbMyKHnbzhi3sWnSJRnGP_-XsL6eamT","role":"messagebox","eid":"BL0RdEFZxEf2wkq7TTYDagcoOxfmWK45enEUQHwLPadJ"}}-FABECQxoxuKOcO7KkbMyKHnbzhi3sWnSJRnGP_-XsL6eamT0AAAAAAAAAAAAAAAAAAAAAAAECQxoxuKOcO7KkbMyKHnbzhi3sWnSJRnGP_-XsL6eamT-AABAABtWkO0ezyg_IC9HeG2rc3wplzAj95lKqVTdnEu46xlELl2M2oHrcpVWC_n1Bg1zgjqAAOeRkBgqdwKha7ZS4IK"#;
+    let rpy = match parse_op_stream(end_role).unwrap().pop().unwrap() {
+        Op::Reply(rpy) => rpy,
+        _ => unreachable!(),
+    };
+    let id: IdentifierPrefix = "ECQxoxuKOcO7KkbMyKHnbzhi3sWnSJRnGP_-XsL6eamT"
+        .parse()
+        .unwrap();
+
+    db.update_accepted_reply(rpy.clone(), &id).unwrap();
+    // Saving the same reply again doesn't duplicate it.
+    db.update_accepted_reply(rpy.clone(), &id).unwrap();
+    assert_eq!(db.get_accepted_replys(&id).unwrap(), vec![rpy.clone()]);
+
+    db.remove_accepted_reply(&id, rpy).unwrap();
+    assert!(db.get_accepted_replys(&id).unwrap().is_empty());
+}
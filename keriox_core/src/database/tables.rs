@@ -74,6 +74,13 @@ where
         }
     }
 
+    /// Removes all elements stored under `key`
+    ///
+    pub fn clear(&self, key: u64) -> Result<(), DbError> {
+        self.tree.remove(key_bytes(key))?;
+        Ok(())
+    }
+
     /// Appends one `Vec<T>` into DB present one
     /// or `put()`s it if not present as is.
     ///
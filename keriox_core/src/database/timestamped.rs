@@ -4,6 +4,7 @@ use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    clock::Clock,
     error::Error,
     event_message::signed_event_message::{
         SignedEventMessage, SignedNontransferableReceipt, SignedTransferableReceipt,
@@ -17,15 +18,27 @@ pub struct Timestamped<M> {
 }
 
 impl<M> Timestamped<M> {
-    pub fn new(event: M) -> Self {
+    /// Timestamps message with current time of `clock`.
+    pub fn new(event: M, clock: &dyn Clock) -> Self {
+        Self::new_at(event, clock.now())
+    }
+
+    pub fn new_at(event: M, timestamp: DateTime<Local>) -> Self {
         Self {
-            timestamp: Local::now(),
+            timestamp,
             signed_event_message: event,
         }
     }
 
-    pub fn is_stale(&self, duration: Duration) -> Result<bool, Error> {
-        Ok(Local::now() - self.timestamp
+    /// Checks if message is older than `duration` at current time of
+    /// `clock`.
+    pub fn is_stale(&self, clock: &dyn Clock, duration: Duration) -> Result<bool, Error> {
+        self.is_stale_at(clock.now(), duration)
+    }
+
+    /// Checks if message is older than `duration` at `now`.
+    pub fn is_stale_at(&self, now: DateTime<Local>, duration: Duration) -> Result<bool, Error> {
+        Ok(now - self.timestamp
             >= chrono::Duration::from_std(duration)
                 .map_err(|_e| Error::SemanticError("Improper duration".into()))?)
     }
@@ -49,18 +62,6 @@ impl From<Timestamped<SignedTransferableReceipt>> for SignedTransferableReceipt
     }
 }
 
-impl<M> From<M> for Timestamped<M> {
-    fn from(event: M) -> Timestamped<M> {
-        Timestamped::new(event)
-    }
-}
-
-impl<M: Clone> From<&M> for Timestamped<M> {
-    fn from(event: &M) -> Timestamped<M> {
-        Timestamped::new(event.clone())
-    }
-}
-
 impl<M: PartialEq> PartialEq for Timestamped<M> {
     fn eq(&self, other: &Self) -> bool {
         self.signed_event_message == other.signed_event_message
@@ -3,8 +3,10 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    database::redb::RedbError, event::sections::key_config::SignatureError,
-    event_message::cesr_adapter::ParseError, prefix::IdentifierPrefix,
+    database::redb::RedbError,
+    event::sections::{configuration::ConfigurationTrait, key_config::SignatureError},
+    event_message::cesr_adapter::ParseError,
+    prefix::IdentifierPrefix,
     processor::validator::VerificationError,
 };
 
@@ -117,6 +119,27 @@ pub enum Error {
 
     #[error(transparent)]
     VerificationError(#[from] VerificationError),
+
+    #[error("Event violates {0} configuration trait")]
+    ConfigurationTraitViolation(ConfigurationTrait),
+
+    #[error("Notification observer stopped")]
+    ObserverStopped,
+
+    #[error("Key manager is locked")]
+    WalletLocked,
+
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+
+    #[error("Key manager can't be locked without passphrase")]
+    MissingPassphrase,
+
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+
+    #[error("Event rejected: {0}")]
+    EventRejected(String),
 }
 
 impl From<VersionError> for Error {
@@ -1,5 +1,5 @@
 use super::{
-    super::sections::{InceptionWitnessConfig, KeyConfig},
+    super::sections::{configuration::ConfigurationTrait, InceptionWitnessConfig, KeyConfig},
     EventData,
 };
 use crate::{
@@ -58,6 +58,14 @@ impl InceptionEvent {
         }
     }
 
+    /// Returns known configuration traits set in `c` field.
+    pub fn configuration_traits(&self) -> Vec<ConfigurationTrait> {
+        self.inception_configuration
+            .iter()
+            .filter_map(|c| c.parse().ok())
+            .collect()
+    }
+
     /// Incept Self Addressing
     ///
     /// Takes the inception data and creates an EventMessage based on it, with
@@ -100,6 +108,7 @@ impl EventSemantics for InceptionEvent {
             current: self.key_config.clone(),
             witness_config: self.witness_config.clone().into(),
             last_est,
+            config: self.inception_configuration.clone(),
             ..state
         })
     }
@@ -1,4 +1,4 @@
-use super::super::sections::seal::*;
+use super::super::sections::{configuration::ConfigurationTrait, seal::*};
 use crate::database::redb::rkyv_adapter::said_wrapper::SaidValue;
 use crate::error::Error;
 use crate::state::{EventSemantics, IdentifierState};
@@ -39,6 +39,44 @@ impl InteractionEvent {
 
 impl EventSemantics for InteractionEvent {
     fn apply_to(&self, state: IdentifierState) -> Result<IdentifierState, Error> {
+        if state.has_trait(&ConfigurationTrait::EstablishmentOnly) {
+            return Err(Error::ConfigurationTraitViolation(
+                ConfigurationTrait::EstablishmentOnly,
+            ));
+        };
         Ok(IdentifierState { ..state })
     }
 }
+
+#[test]
+fn test_establishment_only() -> Result<(), Error> {
+    use crate::event_message::{event_msg_builder::EventMsgBuilder, EventTypeTag};
+
+    let icp = EventMsgBuilder::new(EventTypeTag::Icp)
+        .with_configuration(&[ConfigurationTrait::EstablishmentOnly])
+        .build()?;
+    let state = IdentifierState::default().apply(&icp)?;
+    assert!(state.has_trait(&ConfigurationTrait::EstablishmentOnly));
+
+    let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+        .with_prefix(&icp.data.get_prefix())
+        .with_sn(1)
+        .with_previous_event(&icp.digest()?)
+        .build()?;
+    assert!(matches!(
+        state.clone().apply(&ixn),
+        Err(Error::ConfigurationTraitViolation(
+            ConfigurationTrait::EstablishmentOnly
+        ))
+    ));
+
+    // Interaction events are allowed without configuration trait.
+    let icp = EventMsgBuilder::new(EventTypeTag::Icp).build()?;
+    let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+        .with_prefix(&icp.data.get_prefix())
+        .with_sn(1)
+        .with_previous_event(&icp.digest()?)
+        .build()?;
+    assert!(IdentifierState::default().apply(&icp)?.apply(&ixn).is_ok());
+    Ok(())
+}
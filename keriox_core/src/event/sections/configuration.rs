@@ -0,0 +1,66 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Configuration traits that can be set in `c` field of inception event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigurationTrait {
+    /// Only establishment events are allowed in KEL.
+    #[serde(rename = "EO")]
+    EstablishmentOnly,
+    /// Identifier can't act as delegator.
+    #[serde(rename = "DND")]
+    DoNotDelegate,
+    /// Registries of identifier can't use registrar backers.
+    #[serde(rename = "NRB")]
+    NoRegistrarBackers,
+}
+
+impl ConfigurationTrait {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigurationTrait::EstablishmentOnly => "EO",
+            ConfigurationTrait::DoNotDelegate => "DND",
+            ConfigurationTrait::NoRegistrarBackers => "NRB",
+        }
+    }
+}
+
+impl FromStr for ConfigurationTrait {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EO" => Ok(ConfigurationTrait::EstablishmentOnly),
+            "DND" => Ok(ConfigurationTrait::DoNotDelegate),
+            "NRB" => Ok(ConfigurationTrait::NoRegistrarBackers),
+            _ => Err(Error::SemanticError(format!(
+                "Unknown configuration trait: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Display for ConfigurationTrait {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[test]
+fn test_configuration_trait_parsing() {
+    for config in [
+        ConfigurationTrait::EstablishmentOnly,
+        ConfigurationTrait::DoNotDelegate,
+        ConfigurationTrait::NoRegistrarBackers,
+    ] {
+        assert_eq!(
+            config.to_string().parse::<ConfigurationTrait>().unwrap(),
+            config
+        );
+    }
+    assert!("XYZ".parse::<ConfigurationTrait>().is_err());
+}
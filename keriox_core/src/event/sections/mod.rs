@@ -1,6 +1,7 @@
 use crate::{prefix::BasicPrefix, state::WitnessConfig};
 use serde::{Deserialize, Serialize};
 
+pub mod configuration;
 pub mod key_config;
 pub mod seal;
 pub mod threshold;
@@ -42,6 +42,10 @@ impl DigestSeal {
     pub fn new(said: SelfAddressingIdentifier) -> Self {
         Self { dig: said.into() }
     }
+
+    pub fn digest(&self) -> SelfAddressingIdentifier {
+        self.dig.said.clone()
+    }
 }
 
 #[derive(
@@ -60,6 +64,71 @@ pub struct RootSeal {
     tree_root: SaidValue,
 }
 
+impl RootSeal {
+    pub fn new(tree_root: SelfAddressingIdentifier) -> Self {
+        Self {
+            tree_root: tree_root.into(),
+        }
+    }
+}
+
+impl Seal {
+    /// Seal of arbitrary data, e.g. ACDC credential, referenced by its
+    /// digest.
+    pub fn digest(said: SelfAddressingIdentifier) -> Self {
+        Seal::Digest(DigestSeal::new(said))
+    }
+
+    /// Seal of key event of other identifier, e.g. approved delegated event.
+    pub fn event(
+        prefix: IdentifierPrefix,
+        sn: u64,
+        event_digest: SelfAddressingIdentifier,
+    ) -> Self {
+        Seal::Event(EventSeal::new(prefix, sn, event_digest))
+    }
+
+    /// Seal of TEL registry event (`vcp`, `iss`, `rev` etc.), that anchors
+    /// it in issuer's KEL.
+    pub fn registry(
+        registry_id: IdentifierPrefix,
+        sn: u64,
+        event_digest: SelfAddressingIdentifier,
+    ) -> Self {
+        Seal::Event(EventSeal::new(registry_id, sn, event_digest))
+    }
+}
+
+impl From<SelfAddressingIdentifier> for Seal {
+    fn from(said: SelfAddressingIdentifier) -> Self {
+        Seal::digest(said)
+    }
+}
+
+impl From<LocationSeal> for Seal {
+    fn from(seal: LocationSeal) -> Self {
+        Seal::Location(seal)
+    }
+}
+
+impl From<EventSeal> for Seal {
+    fn from(seal: EventSeal) -> Self {
+        Seal::Event(seal)
+    }
+}
+
+impl From<DigestSeal> for Seal {
+    fn from(seal: DigestSeal) -> Self {
+        Seal::Digest(seal)
+    }
+}
+
+impl From<RootSeal> for Seal {
+    fn from(seal: RootSeal) -> Self {
+        Seal::Root(seal)
+    }
+}
+
 #[derive(
     Serialize,
     Deserialize,
@@ -167,4 +236,21 @@ fn test_seal_deserialization() {
     let seal: Seal = serde_json::from_str(seal_str).unwrap();
     assert!(matches!(seal, Seal::Digest(_)));
     assert_eq!(serde_json::to_string(&seal).unwrap(), seal_str);
+
+    // Convenience constructors
+    let said: SelfAddressingIdentifier = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+    assert_eq!(Seal::from(said.clone()), seal);
+    let registry_seal = Seal::registry(
+        "EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"
+            .parse()
+            .unwrap(),
+        0,
+        said,
+    );
+    assert_eq!(
+        serde_json::to_string(&registry_seal).unwrap(),
+        r#"{"i":"EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m","s":"0","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"}"#
+    );
 }
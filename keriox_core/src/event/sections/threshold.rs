@@ -101,6 +101,14 @@ pub enum WeightedThreshold {
 }
 
 impl WeightedThreshold {
+    /// Returns number of weights in threshold.
+    pub fn length(&self) -> usize {
+        match self {
+            WeightedThreshold::Single(clause) => clause.length(),
+            WeightedThreshold::Multi(clauses) => clauses.length(),
+        }
+    }
+
     pub fn enough_signatures(&self, sigs_indexes: &[usize]) -> Result<(), SignatureError> {
         match self {
             WeightedThreshold::Single(clause) => clause.enough_signatures(0, sigs_indexes),
@@ -138,6 +146,17 @@ impl SignatureThreshold {
             SignatureThreshold::Weighted(ref thresh) => thresh.enough_signatures(sigs_indexes),
         }
     }
+
+    /// Checks if threshold can be satisfied by `count` signers, e.g. keys or
+    /// witnesses. Weighted threshold needs a weight for each signer.
+    pub fn is_achievable(&self, count: usize) -> bool {
+        match self {
+            SignatureThreshold::Simple(t) => *t <= count as u64,
+            SignatureThreshold::Weighted(w) => {
+                w.length() == count && w.enough_signatures(&(0..count).collect::<Vec<_>>()).is_ok()
+            }
+        }
+    }
 }
 
 impl Default for SignatureThreshold {
@@ -356,3 +375,22 @@ pub fn test_weighted_treshold_serialization() -> Result<(), SignatureError> {
     assert_eq!(serde_json::to_string(&wt).unwrap(), single_threshold);
     Ok(())
 }
+
+#[test]
+fn test_threshold_achievable() {
+    assert!(SignatureThreshold::Simple(0).is_achievable(0));
+    assert!(SignatureThreshold::Simple(2).is_achievable(3));
+    assert!(!SignatureThreshold::Simple(4).is_achievable(3));
+
+    let weighted = SignatureThreshold::single_weighted(vec![(1, 2), (1, 2), (1, 4)]);
+    assert!(weighted.is_achievable(3));
+    // Weights don't match number of signers.
+    assert!(!weighted.is_achievable(2));
+    assert!(!weighted.is_achievable(4));
+    // Sum of all weights is below one.
+    assert!(!SignatureThreshold::single_weighted(vec![(1, 4), (1, 4)]).is_achievable(2));
+    assert!(
+        SignatureThreshold::multi_weighted(vec![vec![(1, 1)], vec![(1, 2), (1, 2)]])
+            .is_achievable(3)
+    );
+}
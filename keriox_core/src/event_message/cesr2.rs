@@ -0,0 +1,610 @@
+//! Framing of CESR 2.0 streams.
+//!
+//! In CESR 2.0 every count code holds size of its group in quadlets (4
+//! characters of text domain) instead of number of elements, so group
+//! boundaries can be found without knowing what is inside. This module
+//! splits stream into message bodies, genus version codes and count code
+//! groups, keeping raw content of each group. Groups of unknown codes are
+//! preserved as they are, so stream can be forwarded without loss.
+//!
+//! Messages are parsed by converting stream into CESR 1.0 with
+//! [`to_cesr1`], which is done by stream parsers in [`crate::actor`] for
+//! streams starting with KERI/ACDC 2.x genus version code. Message bodies
+//! have to use 1.0 version string.
+use super::cesr_adapter::ParseError;
+
+/// Genus of KERI and ACDC protocols, used in genus version code.
+pub const KERIACDC_GENUS: &str = "AAA";
+
+const B64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn b64_to_u32(chars: &[u8]) -> Result<u32, ParseError> {
+    chars.iter().try_fold(0u32, |acc, c| {
+        let value = B64_ALPHABET.iter().position(|a| a == c).ok_or_else(|| {
+            ParseError::CesrError(format!("invalid base64 character {}", *c as char))
+        })?;
+        Ok(acc * 64 + value as u32)
+    })
+}
+
+fn u32_to_b64(mut value: u32, len: usize) -> String {
+    let mut out = vec![b'A'; len];
+    for c in out.iter_mut().rev() {
+        *c = B64_ALPHABET[(value % 64) as usize];
+        value /= 64;
+    }
+    String::from_utf8(out).unwrap()
+}
+
+/// Count codes of CESR 2.0 KERI/ACDC genus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CountCode {
+    GenericGroup,
+    BodyWithAttachmentGroup,
+    AttachmentGroup,
+    DatagramSegmentGroup,
+    EssrWrapperGroup,
+    FixBodyGroup,
+    MapBodyGroup,
+    NonNativeBodyGroup,
+    GenericMapGroup,
+    GenericListGroup,
+    ControllerIdxSigs,
+    WitnessIdxSigs,
+    NonTransReceiptCouples,
+    TransReceiptQuadruples,
+    FirstSeenReplayCouples,
+    PathedMaterialCouples,
+    DigestSealSingles,
+    MerkleRootSealSingles,
+    SealSourceCouples,
+    SealSourceTriples,
+    SealSourceLastSingles,
+    BackerRegistrarSealCouples,
+    TypedDigestSealCouples,
+    TransIdxSigGroups,
+    TransLastIdxSigGroups,
+    EssrPayloadGroup,
+    /// Well formed code, that isn't known to this implementation.
+    Unknown(char),
+}
+
+impl CountCode {
+    fn from_char(c: char) -> Self {
+        match c {
+            'A' => Self::GenericGroup,
+            'B' => Self::BodyWithAttachmentGroup,
+            'C' => Self::AttachmentGroup,
+            'D' => Self::DatagramSegmentGroup,
+            'E' => Self::EssrWrapperGroup,
+            'F' => Self::FixBodyGroup,
+            'G' => Self::MapBodyGroup,
+            'H' => Self::NonNativeBodyGroup,
+            'I' => Self::GenericMapGroup,
+            'J' => Self::GenericListGroup,
+            'K' => Self::ControllerIdxSigs,
+            'L' => Self::WitnessIdxSigs,
+            'M' => Self::NonTransReceiptCouples,
+            'N' => Self::TransReceiptQuadruples,
+            'O' => Self::FirstSeenReplayCouples,
+            'P' => Self::PathedMaterialCouples,
+            'Q' => Self::DigestSealSingles,
+            'R' => Self::MerkleRootSealSingles,
+            'S' => Self::SealSourceCouples,
+            'T' => Self::SealSourceTriples,
+            'U' => Self::SealSourceLastSingles,
+            'V' => Self::BackerRegistrarSealCouples,
+            'W' => Self::TypedDigestSealCouples,
+            'X' => Self::TransIdxSigGroups,
+            'Y' => Self::TransLastIdxSigGroups,
+            'Z' => Self::EssrPayloadGroup,
+            other => Self::Unknown(other),
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Self::GenericGroup => 'A',
+            Self::BodyWithAttachmentGroup => 'B',
+            Self::AttachmentGroup => 'C',
+            Self::DatagramSegmentGroup => 'D',
+            Self::EssrWrapperGroup => 'E',
+            Self::FixBodyGroup => 'F',
+            Self::MapBodyGroup => 'G',
+            Self::NonNativeBodyGroup => 'H',
+            Self::GenericMapGroup => 'I',
+            Self::GenericListGroup => 'J',
+            Self::ControllerIdxSigs => 'K',
+            Self::WitnessIdxSigs => 'L',
+            Self::NonTransReceiptCouples => 'M',
+            Self::TransReceiptQuadruples => 'N',
+            Self::FirstSeenReplayCouples => 'O',
+            Self::PathedMaterialCouples => 'P',
+            Self::DigestSealSingles => 'Q',
+            Self::MerkleRootSealSingles => 'R',
+            Self::SealSourceCouples => 'S',
+            Self::SealSourceTriples => 'T',
+            Self::SealSourceLastSingles => 'U',
+            Self::BackerRegistrarSealCouples => 'V',
+            Self::TypedDigestSealCouples => 'W',
+            Self::TransIdxSigGroups => 'X',
+            Self::TransLastIdxSigGroups => 'Y',
+            Self::EssrPayloadGroup => 'Z',
+            Self::Unknown(c) => *c,
+        }
+    }
+
+    /// Returns true if group content is a stream of other frames.
+    pub fn is_nested(&self) -> bool {
+        matches!(
+            self,
+            Self::GenericGroup | Self::BodyWithAttachmentGroup | Self::AttachmentGroup
+        )
+    }
+}
+
+/// Count code group with its raw content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountGroup {
+    pub code: CountCode,
+    /// Set if group was encoded with big count code (`--`), which allows
+    /// bigger sizes.
+    pub big: bool,
+    /// Group content, its length is multiple of 4.
+    pub content: Vec<u8>,
+}
+
+impl CountGroup {
+    /// Returns frames inside nested group, like attachment group.
+    pub fn frames(&self) -> Result<Vec<Frame>, ParseError> {
+        if !self.code.is_nested() {
+            return Err(ParseError::AttachmentError(format!(
+                "group {:?} doesn't contain frames",
+                self.code
+            )));
+        }
+        parse_frames(&self.content)
+    }
+
+    fn to_cesr(&self) -> Vec<u8> {
+        let quadlets = (self.content.len() / 4) as u32;
+        let code = if self.big {
+            format!("--{}{}", self.code.to_char(), u32_to_b64(quadlets, 5))
+        } else {
+            format!("-{}{}", self.code.to_char(), u32_to_b64(quadlets, 2))
+        };
+        [code.into_bytes(), self.content.clone()].concat()
+    }
+}
+
+/// Element of CESR 2.0 stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// Message body with version string, for example JSON serialized event.
+    Body(Vec<u8>),
+    /// Genus and version of codes that follow, e.g. `-_AAACAA` for KERI/ACDC
+    /// 2.00.
+    GenusVersion {
+        genus: String,
+        major: u32,
+        minor: u32,
+    },
+    Group(CountGroup),
+}
+
+impl Frame {
+    pub fn to_cesr(&self) -> Vec<u8> {
+        match self {
+            Frame::Body(body) => body.clone(),
+            Frame::GenusVersion {
+                genus,
+                major,
+                minor,
+            } => format!(
+                "-_{}{}{}",
+                genus,
+                u32_to_b64(*major, 1),
+                u32_to_b64(*minor, 2)
+            )
+            .into_bytes(),
+            Frame::Group(group) => group.to_cesr(),
+        }
+    }
+}
+
+/// Returns size of message body from its version string. Supports both 1.0
+/// (`KERI10JSON00012b_`) and 2.0 (`KERICAACAAJSONAAAB.`) version strings.
+fn body_size(stream: &[u8]) -> Result<usize, ParseError> {
+    let start = stream
+        .windows(5)
+        .take(16)
+        .position(|w| w == b"\"v\":\"")
+        .ok_or_else(|| ParseError::CesrError("missing version string".to_string()))?
+        + 5;
+    let version = &stream[start..];
+    let size = |range: std::ops::Range<usize>, terminator: usize, mark: u8| match (
+        version.get(range),
+        version.get(terminator),
+    ) {
+        (Some(size), Some(t)) if *t == mark => Some(size),
+        _ => None,
+    };
+    if let Some(size) = size(10..16, 16, b'_') {
+        usize::from_str_radix(&String::from_utf8_lossy(size), 16)
+            .map_err(|e| ParseError::CesrError(e.to_string()))
+    } else if let Some(size) = size(14..18, 18, b'.') {
+        Ok(b64_to_u32(size)? as usize)
+    } else {
+        Err(ParseError::CesrError("invalid version string".to_string()))
+    }
+}
+
+fn take(stream: &[u8], len: usize) -> Result<(&[u8], &[u8]), ParseError> {
+    if stream.len() < len {
+        Err(ParseError::CesrError(
+            "unexpected end of stream".to_string(),
+        ))
+    } else {
+        Ok(stream.split_at(len))
+    }
+}
+
+/// Splits CESR 2.0 stream into frames. Groups are not parsed further, see
+/// [`CountGroup::frames`].
+pub fn parse_frames(mut stream: &[u8]) -> Result<Vec<Frame>, ParseError> {
+    let mut frames = vec![];
+    while !stream.is_empty() {
+        let (frame, rest) = parse_frame(stream)?;
+        frames.push(frame);
+        stream = rest;
+    }
+    Ok(frames)
+}
+
+/// Returns frame that starts the stream and the rest of stream.
+fn parse_frame(stream: &[u8]) -> Result<(Frame, &[u8]), ParseError> {
+    match stream {
+        [b'{', ..] => {
+            let (body, rest) = take(stream, body_size(stream)?)?;
+            Ok((Frame::Body(body.to_vec()), rest))
+        }
+        [b'-', b'_', ..] => {
+            let (code, rest) = take(stream, 8)?;
+            let frame = Frame::GenusVersion {
+                genus: String::from_utf8_lossy(&code[2..5]).to_string(),
+                major: b64_to_u32(&code[5..6])?,
+                minor: b64_to_u32(&code[6..8])?,
+            };
+            Ok((frame, rest))
+        }
+        [b'-', b'-', ..] => {
+            let (code, rest) = take(stream, 8)?;
+            let (content, rest) = take(rest, b64_to_u32(&code[3..8])? as usize * 4)?;
+            let group = CountGroup {
+                code: CountCode::from_char(code[2] as char),
+                big: true,
+                content: content.to_vec(),
+            };
+            Ok((Frame::Group(group), rest))
+        }
+        [b'-', c, ..] if c.is_ascii_alphabetic() => {
+            let (code, rest) = take(stream, 4)?;
+            let (content, rest) = take(rest, b64_to_u32(&code[2..4])? as usize * 4)?;
+            let group = CountGroup {
+                code: CountCode::from_char(*c as char),
+                big: false,
+                content: content.to_vec(),
+            };
+            Ok((Frame::Group(group), rest))
+        }
+        _ => Err(ParseError::CesrError(format!(
+            "unexpected frame start: {}",
+            String::from_utf8_lossy(&stream[..stream.len().min(4)])
+        ))),
+    }
+}
+
+/// Returns true if stream starts with KERI/ACDC genus version code of major
+/// version 2.
+pub fn is_cesr2(stream: &[u8]) -> bool {
+    stream.starts_with(b"-_AAAC")
+}
+
+/// Converts CESR 2.0 stream into CESR 1.0 stream. Count codes of attachment
+/// groups are replaced with their 1.0 equivalents, which hold number of
+/// elements instead of group size. Groups of unknown codes are skipped.
+pub fn to_cesr1(stream: &[u8]) -> Result<Vec<u8>, ParseError> {
+    frames_to_cesr1(&parse_frames(stream)?)
+}
+
+fn frames_to_cesr1(frames: &[Frame]) -> Result<Vec<u8>, ParseError> {
+    let mut out = vec![];
+    for frame in frames {
+        match frame {
+            Frame::Body(body) => {
+                if !is_cesr1_body(body) {
+                    return Err(ParseError::CesrError(
+                        "messages with CESR 2.0 version string are not supported".to_string(),
+                    ));
+                }
+                out.extend_from_slice(body)
+            }
+            Frame::GenusVersion { genus, major, .. } => {
+                if genus != KERIACDC_GENUS || *major != 2 {
+                    return Err(ParseError::CesrError(format!(
+                        "unsupported genus version: {}{}",
+                        genus, major
+                    )));
+                }
+            }
+            Frame::Group(group) => out.extend(group_to_cesr1(group)?),
+        }
+    }
+    Ok(out)
+}
+
+/// Kinds of primitives in element of count code group.
+#[derive(Clone, Copy)]
+enum Element {
+    Matter,
+    Indexed,
+    /// Nested group of controller indexed signatures.
+    Signatures,
+}
+
+fn group_to_cesr1(group: &CountGroup) -> Result<Vec<u8>, ParseError> {
+    use Element::*;
+    let (code, element): (&str, &[Element]) = match &group.code {
+        CountCode::GenericGroup
+        | CountCode::BodyWithAttachmentGroup
+        | CountCode::AttachmentGroup => return frames_to_cesr1(&group.frames()?),
+        CountCode::ControllerIdxSigs => ("-A", &[Indexed]),
+        CountCode::WitnessIdxSigs => ("-B", &[Indexed]),
+        CountCode::NonTransReceiptCouples => ("-C", &[Matter, Matter]),
+        CountCode::TransReceiptQuadruples => ("-D", &[Matter, Matter, Matter, Indexed]),
+        CountCode::FirstSeenReplayCouples => ("-E", &[Matter, Matter]),
+        CountCode::TransIdxSigGroups => ("-F", &[Matter, Matter, Matter, Signatures]),
+        CountCode::SealSourceCouples => ("-G", &[Matter, Matter]),
+        CountCode::TransLastIdxSigGroups => ("-H", &[Matter, Signatures]),
+        CountCode::SealSourceTriples => ("-I", &[Matter, Matter, Matter]),
+        CountCode::Unknown(_) => return Ok(vec![]),
+        other => {
+            return Err(ParseError::AttachmentError(format!(
+                "CESR 2.0 group {:?} can't be converted to CESR 1.0",
+                other
+            )))
+        }
+    };
+
+    let mut content = &group.content[..];
+    let mut converted = vec![];
+    let mut count = 0;
+    while !content.is_empty() {
+        for kind in element {
+            let rest = match kind {
+                Matter => {
+                    let (primitive, rest) = take(content, matter_size(content)?)?;
+                    converted.extend_from_slice(primitive);
+                    rest
+                }
+                Indexed => {
+                    let (primitive, rest) = take(content, indexed_size(content)?)?;
+                    converted.extend_from_slice(primitive);
+                    rest
+                }
+                Signatures => match parse_frame(content)? {
+                    (Frame::Group(nested), rest) if nested.code == CountCode::ControllerIdxSigs => {
+                        converted.extend(group_to_cesr1(&nested)?);
+                        rest
+                    }
+                    _ => {
+                        return Err(ParseError::AttachmentError(
+                            "missing controller signatures group".to_string(),
+                        ))
+                    }
+                },
+            };
+            content = rest;
+        }
+        count += 1;
+    }
+    if count >= 64 * 64 {
+        return Err(ParseError::AttachmentError(format!(
+            "too many elements in group: {}",
+            count
+        )));
+    }
+    Ok([
+        format!("{}{}", code, u32_to_b64(count, 2)).into_bytes(),
+        converted,
+    ]
+    .concat())
+}
+
+/// Returns true if body has CESR 1.0 version string, like
+/// `KERI10JSON00012b_`.
+fn is_cesr1_body(body: &[u8]) -> bool {
+    body.windows(5)
+        .take(16)
+        .position(|w| w == b"\"v\":\"")
+        .and_then(|start| body.get(start + 5 + 16))
+        == Some(&b'_')
+}
+
+/// Returns size of fixed size primitive, which starts the stream, from its
+/// code.
+fn matter_size(stream: &[u8]) -> Result<usize, ParseError> {
+    let size = match stream {
+        [b'0', b'A', ..] => Some(24),
+        [b'0', b'H', ..] => Some(8),
+        [b'0', c, ..] if c.is_ascii_alphabetic() => Some(88),
+        [b'1', b'A', b'A', c, ..] => match c {
+            b'A' | b'B' | b'J' => Some(48),
+            b'C' | b'D' => Some(80),
+            b'E' => Some(156),
+            b'F' => Some(8),
+            b'G' => Some(36),
+            b'H' => Some(100),
+            _ => None,
+        },
+        [b'M', ..] => Some(4),
+        [b'N', ..] => Some(12),
+        [b'P', ..] => Some(124),
+        [c, ..] if c.is_ascii_alphabetic() => Some(44),
+        _ => None,
+    };
+    size.ok_or_else(|| {
+        ParseError::AttachmentError(format!(
+            "unsupported primitive code: {}",
+            String::from_utf8_lossy(&stream[..stream.len().min(4)])
+        ))
+    })
+}
+
+/// Returns size of indexed signature, which starts the stream, from its
+/// code.
+fn indexed_size(stream: &[u8]) -> Result<usize, ParseError> {
+    let size = match stream {
+        [b'A'..=b'F', ..] => Some(88),
+        [b'0', b'A' | b'B', ..] => Some(156),
+        [b'2', b'A'..=b'F', ..] => Some(92),
+        [b'3', b'A' | b'B', ..] => Some(160),
+        _ => None,
+    };
+    size.ok_or_else(|| {
+        ParseError::AttachmentError(format!(
+            "unsupported indexed signature code: {}",
+            String::from_utf8_lossy(&stream[..stream.len().min(2)])
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_message::signed_event_message::{Message, Notice};
+
+    #[test]
+    fn test_keripy_stream() {
+        // Inception event and signatures from keripy `test_multisig_digprefix`
+        // test (keripy/tests/core/test_eventing.py), framed with CESR 2.0
+        // count codes.
+        let body = r#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}"#;
+        let signatures = [
+        "AAD4SyJSYlsQG22MGXzRGz2PTMqpkgOyUfq7cS99sC2BCWwdVmEMKiTEeWe5kv-l_d9auxdadQuArLtAGEArW8wE",
+        "ABD0z_vQmFImZXfdR-0lclcpZFfkJJJNXDcUNrf7a-mGsxNLprJo-LROwDkH5m7tVrb-a1jcor2dHD9Jez-r4bQI",
+        "ACBFeU05ywfZycLdR0FxCvAR9BfV9im8tWe1DglezqJLf-vHRQSChY1KafbYNc96hYYpbuN90WzuCRMgV8KgRsEC",
+        ]
+        .concat();
+        let cesr1 = format!("{body}-AAD{signatures}");
+        // Attachment group of 67 quadlets contains controller signatures
+        // group of 66 quadlets, followed by unknown group.
+        let cesr2 = format!("-_AAACAA{body}-CBD-KBC{signatures}-zABabcd");
+
+        assert!(is_cesr2(cesr2.as_bytes()));
+        assert!(!is_cesr2(cesr1.as_bytes()));
+        assert_eq!(to_cesr1(cesr2.as_bytes()).unwrap(), cesr1.as_bytes());
+
+        let messages = crate::actor::parse_event_stream(cesr2.as_bytes()).unwrap();
+        assert_eq!(
+            messages,
+            crate::actor::parse_event_stream(cesr1.as_bytes()).unwrap()
+        );
+        let Message::Notice(Notice::Event(icp)) = &messages[0] else {
+            panic!("expected event")
+        };
+        assert_eq!(icp.signatures.len(), 3);
+
+        // Groups are preserved when forwarded.
+        let frames = parse_frames(cesr2.as_bytes()).unwrap();
+        let serialized: Vec<u8> = frames.iter().flat_map(Frame::to_cesr).collect();
+        assert_eq!(serialized, cesr2.as_bytes());
+    }
+
+    #[test]
+    fn test_nested_signatures_conversion() {
+        let prefix = "E".to_string() + &"A".repeat(43);
+        let sn = "0A".to_string() + &"A".repeat(22);
+        let said = "E".to_string() + &"B".repeat(43);
+        let signature = "AA".to_string() + &"C".repeat(86);
+        let group = format!("{prefix}{sn}{said}-KAW{signature}");
+        assert_eq!(group.len() % 4, 0);
+        let stream = format!("-XA{}{group}", u32_to_b64(group.len() as u32 / 4, 1));
+
+        assert_eq!(
+            to_cesr1(stream.as_bytes()).unwrap(),
+            format!("-FAB{prefix}{sn}{said}-AAB{signature}").as_bytes()
+        );
+        // Body with 2.0 version string can't be parsed.
+        let v2 = r#"{"v":"KERICAACAAJSONAAAs.","t":"icp","d":""}"#;
+        assert!(to_cesr1(v2.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let body = r#"{"v":"KERI10JSON00002a_","t":"ixn","d":""}"#;
+        assert_eq!(body.len(), 0x2a);
+        let signature = "AA".to_string() + &"B".repeat(86);
+        let stream = format!("-_AAACAA{body}-CAX-KAW{signature}-zABabcd--AAAAAB-aAA");
+
+        let frames = parse_frames(stream.as_bytes()).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(
+            frames[0],
+            Frame::GenusVersion {
+                genus: KERIACDC_GENUS.to_string(),
+                major: 2,
+                minor: 0
+            }
+        );
+        assert_eq!(frames[1], Frame::Body(body.as_bytes().to_vec()));
+
+        // Attachment group contains group of controller signatures.
+        let Frame::Group(attachments) = &frames[2] else {
+            panic!("expected group")
+        };
+        assert_eq!(attachments.code, CountCode::AttachmentGroup);
+        let inner = attachments.frames().unwrap();
+        assert_eq!(
+            inner,
+            vec![Frame::Group(CountGroup {
+                code: CountCode::ControllerIdxSigs,
+                big: false,
+                content: signature.as_bytes().to_vec()
+            })]
+        );
+
+        // Unknown groups are skipped by their size and kept as they are.
+        assert!(matches!(
+            &frames[3],
+            Frame::Group(CountGroup {
+                code: CountCode::Unknown('z'),
+                big: false,
+                ..
+            })
+        ));
+        let Frame::Group(generic) = &frames[4] else {
+            panic!("expected group")
+        };
+        assert!(generic.big);
+        assert_eq!(
+            generic.frames().unwrap(),
+            vec![Frame::Group(CountGroup {
+                code: CountCode::Unknown('a'),
+                big: false,
+                content: vec![]
+            })]
+        );
+
+        let serialized: Vec<u8> = frames.iter().flat_map(Frame::to_cesr).collect();
+        assert_eq!(serialized, stream.as_bytes());
+    }
+
+    #[test]
+    fn test_body_size() {
+        let v2 = r#"{"v":"KERICAACAAJSONAAAs.","t":"icp","d":""}"#;
+        assert_eq!(v2.len(), 44);
+        assert_eq!(body_size(v2.as_bytes()).unwrap(), 44);
+        assert!(parse_frames(b"-KAB").is_err());
+    }
+}
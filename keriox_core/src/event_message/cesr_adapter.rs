@@ -55,6 +55,74 @@ pub enum ParseError {
     AttachmentError(String),
     #[error("Wrong event type: {0}")]
     WrongEventType(String),
+    #[error("Non-canonical serialization: {0}")]
+    NonCanonical(CanonicalMismatch),
+}
+
+/// Difference between received event bytes and canonical serialization of
+/// the event parsed from them.
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize, Deserialize)]
+#[error("event {digest} differs at byte {offset}: got `{received}`, expected `{expected}`")]
+pub struct CanonicalMismatch {
+    /// Digest declared in received event.
+    pub digest: String,
+    /// Position of the first differing byte.
+    pub offset: usize,
+    pub received_len: usize,
+    pub canonical_len: usize,
+    /// Fragment of received bytes starting at `offset`.
+    pub received: String,
+    /// Fragment of canonical serialization starting at `offset`.
+    pub expected: String,
+}
+
+impl CanonicalMismatch {
+    /// Length of fragments included in mismatch.
+    const FRAGMENT_LEN: usize = 24;
+
+    fn find(received: &[u8], canonical: &[u8]) -> Option<Self> {
+        if received == canonical {
+            return None;
+        }
+        let offset = received
+            .iter()
+            .zip(canonical)
+            .position(|(r, c)| r != c)
+            .unwrap_or_else(|| received.len().min(canonical.len()));
+        let fragment = |bytes: &[u8]| {
+            let end = bytes.len().min(offset + Self::FRAGMENT_LEN);
+            String::from_utf8_lossy(&bytes[offset..end]).to_string()
+        };
+        let digest = serde_json::from_slice::<serde_json::Value>(received)
+            .ok()
+            .and_then(|value| value.get("d")?.as_str().map(str::to_string))
+            .unwrap_or_default();
+        Some(Self {
+            digest,
+            offset,
+            received_len: received.len(),
+            canonical_len: canonical.len(),
+            received: fragment(received),
+            expected: fragment(canonical),
+        })
+    }
+}
+
+/// Checks if payload bytes are exactly the canonical serialization of the
+/// event parsed from them. Digests and signatures are computed over
+/// canonical serialization, so differently encoded events would be verified
+/// over other bytes than received ones.
+pub fn check_canonical(payload: &Payload) -> Result<(), ParseError> {
+    let received = match payload {
+        Payload::JSON(bytes) | Payload::CBOR(bytes) | Payload::MGPK(bytes) => bytes.clone(),
+    };
+    let canonical = EventType::try_from(payload.clone())?
+        .serialize()
+        .map_err(|e| ParseError::DeserializeError(e.to_string()))?;
+    match CanonicalMismatch::find(&received, &canonical) {
+        Some(mismatch) => Err(ParseError::NonCanonical(mismatch)),
+        None => Ok(()),
+    }
 }
 
 pub fn parse_event_type(input: &[u8]) -> Result<EventType, ParseError> {
@@ -158,79 +226,96 @@ impl From<SignedNontransferableReceipt> for ParsedData {
     }
 }
 
-impl From<SignedTransferableReceipt> for ParsedData {
-    fn from(rcp: SignedTransferableReceipt) -> ParsedData {
+impl TryFrom<SignedTransferableReceipt> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(rcp: SignedTransferableReceipt) -> Result<Self, Self::Error> {
         let seal = rcp.validator_seal;
         let event_digest = seal.event_digest();
         let signatures = rcp.signatures.into_iter().map(|sig| sig.into()).collect();
-        let quadruple = (seal.prefix.into(), seal.sn, event_digest.into(), signatures);
+        let quadruple = (
+            super::signature::cesr_identifier(seal.prefix)?,
+            seal.sn,
+            event_digest.into(),
+            signatures,
+        );
         let group = Group::TransIndexedSigGroups(vec![quadruple]);
 
-        ParsedData {
+        Ok(ParsedData {
             payload: rcp.body.into(),
             attachments: vec![group],
-        }
+        })
     }
 }
 
 #[cfg(feature = "query")]
-impl From<SignedReply> for ParsedData {
-    fn from(ev: SignedReply) -> Self {
-        let attachments = vec![ev.signature.into()];
-        ParsedData {
+impl TryFrom<SignedReply> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(ev: SignedReply) -> Result<Self, Self::Error> {
+        let attachments = vec![ev.signature.try_into()?];
+        Ok(ParsedData {
             payload: ev.reply.into(),
             attachments,
-        }
+        })
     }
 }
 
 #[cfg(feature = "query")]
-impl From<SignedKelQuery> for ParsedData {
-    fn from(ev: SignedKelQuery) -> Self {
-        let groups = signatures_into_groups(&[ev.signature]);
+impl TryFrom<SignedKelQuery> for ParsedData {
+    type Error = ParseError;
 
-        ParsedData {
+    fn try_from(ev: SignedKelQuery) -> Result<Self, Self::Error> {
+        let groups = signatures_into_groups(&[ev.signature])?;
+
+        Ok(ParsedData {
             payload: ev.query.into(),
             attachments: groups,
-        }
+        })
     }
 }
 
 #[cfg(feature = "query")]
-impl From<SignedMailboxQuery> for ParsedData {
-    fn from(ev: SignedMailboxQuery) -> Self {
-        let groups = signatures_into_groups(&[ev.signature]);
+impl TryFrom<SignedMailboxQuery> for ParsedData {
+    type Error = ParseError;
 
-        ParsedData {
+    fn try_from(ev: SignedMailboxQuery) -> Result<Self, Self::Error> {
+        let groups = signatures_into_groups(&[ev.signature])?;
+
+        Ok(ParsedData {
             payload: ev.query.into(),
             attachments: groups,
-        }
+        })
     }
 }
 
 #[cfg(feature = "query")]
-impl From<SignedQueryMessage> for ParsedData {
-    fn from(ev: SignedQueryMessage) -> Self {
+impl TryFrom<SignedQueryMessage> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(ev: SignedQueryMessage) -> Result<Self, Self::Error> {
         match ev {
-            SignedQueryMessage::KelQuery(kqry) => ParsedData::from(kqry),
-            SignedQueryMessage::MailboxQuery(mqry) => ParsedData::from(mqry),
+            SignedQueryMessage::KelQuery(kqry) => ParsedData::try_from(kqry),
+            SignedQueryMessage::MailboxQuery(mqry) => ParsedData::try_from(mqry),
         }
     }
 }
 
 #[cfg(feature = "mailbox")]
-impl From<SignedExchange> for ParsedData {
-    fn from(ev: SignedExchange) -> Self {
-        let mut attachments = signature::signatures_into_groups(&ev.signature);
+impl TryFrom<SignedExchange> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(ev: SignedExchange) -> Result<Self, Self::Error> {
+        let mut attachments = signature::signatures_into_groups(&ev.signature)?;
 
-        let data_signatures = signature::signatures_into_groups(&ev.data_signature.1);
+        let data_signatures = signature::signatures_into_groups(&ev.data_signature.1)?;
 
         let data_attachment = Group::PathedMaterialQuadruplet(ev.data_signature.0, data_signatures);
         attachments.push(data_attachment);
-        ParsedData {
+        Ok(ParsedData {
             payload: ev.exchange_message.into(),
             attachments,
-        }
+        })
     }
 }
 
@@ -755,4 +840,31 @@ pub mod test {
         assert!(rest.is_empty());
         assert_eq!(messages.len(), 7);
     }
+
+    #[test]
+    fn test_check_canonical() {
+        use super::{check_canonical, ParseError};
+
+        let icp = r#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}"#;
+        let (_rest, parsed) = parse(icp.as_bytes()).unwrap();
+        assert!(check_canonical(&parsed.payload).is_ok());
+
+        // The same event with reordered fields has the same size, so it's
+        // parsed, but it's not canonical.
+        let reordered = icp.replacen(r#""bt":"0","b":[]"#, r#""b":[],"bt":"0""#, 1);
+        let (_rest, parsed) = parse(reordered.as_bytes()).unwrap();
+        match check_canonical(&parsed.payload) {
+            Err(ParseError::NonCanonical(mismatch)) => {
+                assert_eq!(
+                    mismatch.digest,
+                    "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+                );
+                assert_eq!(mismatch.offset, icp.find(r#""bt""#).unwrap() + 2);
+                assert_eq!(mismatch.received_len, mismatch.canonical_len);
+                assert!(mismatch.received.starts_with(r#"":[],"bt""#));
+                assert!(mismatch.expected.starts_with(r#"t":"0","b""#));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
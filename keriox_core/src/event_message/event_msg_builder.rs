@@ -7,13 +7,14 @@ use crate::{
             rotation::RotationEvent,
         },
         sections::{
-            key_config::NextKeysData, threshold::SignatureThreshold, RotationWitnessConfig,
+            configuration::ConfigurationTrait, key_config::NextKeysData,
+            threshold::SignatureThreshold, RotationWitnessConfig,
         },
     },
     event::{
         event_data::{inception::InceptionEvent, EventData},
         receipt::Receipt,
-        sections::seal::Seal,
+        sections::seal::{EventSeal, Seal},
         sections::InceptionWitnessConfig,
         sections::KeyConfig,
         KeyEvent,
@@ -28,6 +29,7 @@ use said::{
     derivation::{HashFunction, HashFunctionCode},
     SelfAddressingIdentifier,
 };
+use serde::Serialize;
 
 use super::{msg::KeriEvent, EventTypeTag};
 
@@ -49,6 +51,7 @@ pub struct EventMsgBuilder {
     witness_to_remove: Vec<BasicPrefix>,
     format: SerializationFormats,
     derivation: HashFunction,
+    configuration: Vec<String>,
 }
 
 impl EventMsgBuilder {
@@ -78,6 +81,7 @@ impl EventMsgBuilder {
             format: SerializationFormats::JSON,
             derivation: hash_function,
             next_keys_hashes: None,
+            configuration: vec![],
         }
     }
 
@@ -113,11 +117,48 @@ impl EventMsgBuilder {
         }
     }
 
-    pub fn with_seal(mut self, seals: Vec<Seal>) -> Self {
-        self.data.extend(seals);
+    /// Adds seals to event anchored data (`a` field). Accepts any typed
+    /// seals, e.g. `[EventSeal::new(registry_id, sn, digest)]` to anchor TEL
+    /// registry event or delegated event.
+    pub fn with_seal<S: Into<Seal>>(mut self, seals: impl IntoIterator<Item = S>) -> Self {
+        self.data.extend(seals.into_iter().map(Into::into));
         EventMsgBuilder { ..self }
     }
 
+    /// Adds seal made from any serializable structure. Structure need to
+    /// have the shape of one of supported seals, otherwise error is returned.
+    pub fn with_serialized_seal<T: Serialize>(self, payload: &T) -> Result<Self, Error> {
+        let value =
+            serde_json::to_value(payload).map_err(|e| Error::SerializationError(e.to_string()))?;
+        let seal: Seal = serde_json::from_value(value)
+            .map_err(|e| Error::SemanticError(format!("Unsupported seal payload: {}", e)))?;
+        Ok(self.with_seal([seal]))
+    }
+
+    /// Anchors TEL registry event with given sn and digest.
+    pub fn with_registry_seal(
+        self,
+        registry_id: &IdentifierPrefix,
+        sn: u64,
+        event_digest: &SelfAddressingIdentifier,
+    ) -> Self {
+        self.with_seal([EventSeal::new(
+            registry_id.clone(),
+            sn,
+            event_digest.clone(),
+        )])
+    }
+
+    /// Anchors delegated event, so delegate's event can be approved.
+    pub fn with_delegation_seal(
+        self,
+        delegate: &IdentifierPrefix,
+        sn: u64,
+        event_digest: &SelfAddressingIdentifier,
+    ) -> Self {
+        self.with_seal([EventSeal::new(delegate.clone(), sn, event_digest.clone())])
+    }
+
     pub fn with_delegator(self, delegator: &IdentifierPrefix) -> Self {
         EventMsgBuilder {
             delegator: delegator.clone(),
@@ -167,6 +208,14 @@ impl EventMsgBuilder {
         }
     }
 
+    /// Sets configuration traits of inception event.
+    pub fn with_configuration(self, configuration: &[ConfigurationTrait]) -> Self {
+        EventMsgBuilder {
+            configuration: configuration.iter().map(|c| c.to_string()).collect(),
+            ..self
+        }
+    }
+
     pub fn build(self) -> Result<KeriEvent<KeyEvent>, Error> {
         let next_key_hash = if let Some(hashes) = self.next_keys_hashes {
             NextKeysData::new(self.next_key_threshold, hashes)
@@ -190,8 +239,8 @@ impl EventMsgBuilder {
                         tally: self.witness_threshold,
                         initial_witnesses: self.witnesses,
                     },
-                    inception_configuration: vec![],
-                    data: vec![],
+                    inception_configuration: self.configuration.clone(),
+                    data: self.data,
                 };
 
                 match prefix {
@@ -234,8 +283,8 @@ impl EventMsgBuilder {
                         tally: self.witness_threshold,
                         initial_witnesses: self.witnesses,
                     },
-                    inception_configuration: vec![],
-                    data: vec![],
+                    inception_configuration: self.configuration.clone(),
+                    data: self.data,
                 };
                 DelegatedInceptionEvent {
                     inception_data: icp_data,
@@ -335,3 +384,55 @@ fn test_multisig_prefix_derivation() {
 
     assert_eq!(expected_event.to_vec(), msg.encode().unwrap());
 }
+
+#[test]
+fn test_seal_payloads() {
+    use crate::event::sections::seal::DigestSeal;
+
+    let registry_id: IdentifierPrefix = "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+        .parse()
+        .unwrap();
+    let digest: SelfAddressingIdentifier = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+
+    #[derive(Serialize)]
+    struct CustomSeal {
+        d: SelfAddressingIdentifier,
+    }
+
+    let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+        .with_registry_seal(&registry_id, 0, &digest)
+        .with_serialized_seal(&CustomSeal { d: digest.clone() })
+        .unwrap()
+        .build()
+        .unwrap();
+    match ixn.data.event_data {
+        EventData::Ixn(ixn) => assert_eq!(
+            ixn.data,
+            vec![
+                Seal::Event(EventSeal::new(registry_id.clone(), 0, digest.clone())),
+                Seal::Digest(DigestSeal::new(digest.clone())),
+            ]
+        ),
+        _ => unreachable!(),
+    };
+
+    // Inception event can anchor data too.
+    let icp = EventMsgBuilder::new(EventTypeTag::Icp)
+        .with_delegation_seal(&registry_id, 1, &digest)
+        .build()
+        .unwrap();
+    match icp.data.event_data {
+        EventData::Icp(icp) => assert_eq!(
+            icp.data,
+            vec![Seal::Event(EventSeal::new(registry_id, 1, digest))]
+        ),
+        _ => unreachable!(),
+    };
+
+    // Structure that doesn't look like any seal.
+    assert!(EventMsgBuilder::new(EventTypeTag::Ixn)
+        .with_serialized_seal(&vec![1, 2, 3])
+        .is_err());
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "cesr2")]
+pub mod cesr2;
 pub mod cesr_adapter;
 pub mod dummy_event;
 pub mod event_msg_builder;
@@ -53,9 +55,9 @@ pub struct TimestampedEventMessage {
 }
 
 impl TimestampedEventMessage {
-    pub fn new(event: KeriEvent<KeyEvent>) -> Self {
+    pub fn new_at(event: KeriEvent<KeyEvent>, timestamp: DateTime<Local>) -> Self {
         Self {
-            timestamp: Local::now(),
+            timestamp,
             event_message: event,
         }
     }
@@ -97,13 +99,6 @@ impl From<TimestampedEventMessage> for KeriEvent<KeyEvent> {
     }
 }
 
-/// WARNING: timestamp will change on conversion to current time
-impl From<KeriEvent<KeyEvent>> for TimestampedEventMessage {
-    fn from(event: KeriEvent<KeyEvent>) -> TimestampedEventMessage {
-        TimestampedEventMessage::new(event)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     mod test_utils;
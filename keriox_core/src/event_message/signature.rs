@@ -1,4 +1,7 @@
-use cesrox::{group::Group, primitives::IndexedSignature as CesrIndexedSignature};
+use cesrox::{
+    group::Group,
+    primitives::{Identifier, IndexedSignature as CesrIndexedSignature},
+};
 use said::SelfAddressingIdentifier;
 use serde::{Deserialize, Serialize};
 
@@ -109,40 +112,39 @@ impl Signature {
     }
 }
 
-pub fn signatures_into_groups(sigs: &[Signature]) -> Vec<Group> {
+pub fn signatures_into_groups(sigs: &[Signature]) -> Result<Vec<Group>, ParseError> {
     // Group same type of signature in one attachment
-    let (trans_seal, trans_last, nontrans, indexed, witness_indexed) = sigs.iter().cloned().fold(
-        (vec![], vec![], vec![], vec![], vec![]),
-        |(mut trans_seal, mut trans_last, mut nontrans, mut indexed, mut witness_indexed), sig| {
-            match sig {
-                Signature::Transferable(SignerData::EventSeal(seal), sig) => {
-                    let event_digest = seal.event_digest();
-                    trans_seal.push((
-                        seal.prefix.into(),
-                        seal.sn,
-                        event_digest.into(),
-                        sig.into_iter().map(|sig| sig.into()).collect(),
-                    ))
-                }
-                Signature::Transferable(SignerData::LastEstablishment(id), sig) => {
-                    trans_last.push((id.into(), sig.into_iter().map(|sig| sig.into()).collect()))
-                }
-                Signature::Transferable(SignerData::JustSignatures, sig) => {
-                    indexed.append(&mut sig.into_iter().map(|sig| sig.into()).collect())
-                }
-                Signature::NonTransferable(Nontransferable::Couplet(couplets)) => nontrans.append(
-                    &mut couplets
-                        .into_iter()
-                        .map(|(bp, sp)| (bp.into(), sp.into()))
-                        .collect(),
-                ),
-                Signature::NonTransferable(Nontransferable::Indexed(sigs)) => {
-                    witness_indexed.append(&mut sigs.into_iter().map(|sig| sig.into()).collect())
-                }
-            };
-            (trans_seal, trans_last, nontrans, indexed, witness_indexed)
-        },
-    );
+    let (mut trans_seal, mut trans_last, mut nontrans, mut indexed, mut witness_indexed) =
+        (vec![], vec![], vec![], vec![], vec![]);
+    for sig in sigs.iter().cloned() {
+        match sig {
+            Signature::Transferable(SignerData::EventSeal(seal), sig) => {
+                let event_digest = seal.event_digest();
+                trans_seal.push((
+                    cesr_identifier(seal.prefix)?,
+                    seal.sn,
+                    event_digest.into(),
+                    sig.into_iter().map(|sig| sig.into()).collect(),
+                ))
+            }
+            Signature::Transferable(SignerData::LastEstablishment(id), sig) => trans_last.push((
+                cesr_identifier(id)?,
+                sig.into_iter().map(|sig| sig.into()).collect(),
+            )),
+            Signature::Transferable(SignerData::JustSignatures, sig) => {
+                indexed.append(&mut sig.into_iter().map(|sig| sig.into()).collect())
+            }
+            Signature::NonTransferable(Nontransferable::Couplet(couplets)) => nontrans.append(
+                &mut couplets
+                    .into_iter()
+                    .map(|(bp, sp)| (bp.into(), sp.into()))
+                    .collect(),
+            ),
+            Signature::NonTransferable(Nontransferable::Indexed(sigs)) => {
+                witness_indexed.append(&mut sigs.into_iter().map(|sig| sig.into()).collect())
+            }
+        };
+    }
 
     let mut attachments = vec![];
     if !trans_seal.is_empty() {
@@ -160,7 +162,12 @@ pub fn signatures_into_groups(sigs: &[Signature]) -> Vec<Group> {
     if !witness_indexed.is_empty() {
         attachments.push(Group::IndexedWitnessSignatures(witness_indexed));
     };
-    attachments
+    Ok(attachments)
+}
+
+pub(crate) fn cesr_identifier(id: IdentifierPrefix) -> Result<Identifier, ParseError> {
+    id.try_into()
+        .map_err(|e: crate::prefix::error::Error| ParseError::AttachmentError(e.to_string()))
 }
 
 pub fn get_signatures(group: Group) -> Result<Vec<Signature>, ParseError> {
@@ -214,9 +221,9 @@ pub fn get_signatures(group: Group) -> Result<Vec<Signature>, ParseError> {
     }
 }
 
-impl Into<Group> for Nontransferable {
-    fn into(self) -> Group {
-        match self {
+impl From<Nontransferable> for Group {
+    fn from(signature: Nontransferable) -> Self {
+        match signature {
             Nontransferable::Indexed(indexed) => {
                 let signatures = indexed.into_iter().map(|sig| sig.into()).collect();
                 Group::IndexedWitnessSignatures(signatures)
@@ -232,9 +239,32 @@ impl Into<Group> for Nontransferable {
     }
 }
 
-impl Into<Group> for crate::event_message::signature::Signature {
-    fn into(self) -> Group {
-        match self {
+impl TryFrom<Group> for Nontransferable {
+    type Error = ParseError;
+
+    fn try_from(group: Group) -> Result<Self, Self::Error> {
+        match group {
+            Group::IndexedWitnessSignatures(sigs) => Ok(Nontransferable::Indexed(
+                sigs.into_iter().map(|sig| sig.into()).collect(),
+            )),
+            Group::NontransReceiptCouples(couples) => Ok(Nontransferable::Couplet(
+                couples
+                    .into_iter()
+                    .map(|(bp, sp)| (bp.into(), sp.into()))
+                    .collect(),
+            )),
+            _ => Err(ParseError::AttachmentError(
+                "Improper attachment type".into(),
+            )),
+        }
+    }
+}
+
+impl TryFrom<Signature> for Group {
+    type Error = ParseError;
+
+    fn try_from(signature: Signature) -> Result<Self, Self::Error> {
+        Ok(match signature {
             crate::event_message::signature::Signature::Transferable(seal, signature) => {
                 let signatures: Vec<CesrIndexedSignature> =
                     signature.into_iter().map(|sig| sig.into()).collect();
@@ -242,14 +272,14 @@ impl Into<Group> for crate::event_message::signature::Signature {
                     crate::event_message::signature::SignerData::EventSeal(event_seal) => {
                         let event_digest = event_seal.event_digest();
                         Group::TransIndexedSigGroups(vec![(
-                            event_seal.prefix.into(),
+                            cesr_identifier(event_seal.prefix)?,
                             event_seal.sn,
                             event_digest.into(),
                             signatures,
                         )])
                     }
                     crate::event_message::signature::SignerData::LastEstablishment(id) => {
-                        Group::LastEstSignaturesGroups(vec![(id.into(), signatures)])
+                        Group::LastEstSignaturesGroups(vec![(cesr_identifier(id)?, signatures)])
                     }
                     crate::event_message::signature::SignerData::JustSignatures => {
                         Group::IndexedControllerSignatures(signatures)
@@ -257,6 +287,6 @@ impl Into<Group> for crate::event_message::signature::Signature {
                 }
             }
             crate::event_message::signature::Signature::NonTransferable(nt) => nt.into(),
-        }
+        })
     }
 }
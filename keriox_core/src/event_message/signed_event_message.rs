@@ -1,7 +1,9 @@
 use cesrox::{group::Group, ParsedData};
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 
-use super::{msg::KeriEvent, serializer::to_string, signature::Nontransferable};
+use super::{
+    cesr_adapter::ParseError, msg::KeriEvent, serializer::to_string, signature::Nontransferable,
+};
 #[cfg(feature = "query")]
 use crate::query::{query_event::SignedQueryMessage, reply_event::SignedReply};
 use crate::{
@@ -45,45 +47,52 @@ pub enum Op {
     Query(SignedQueryMessage),
 }
 
-impl From<Message> for ParsedData {
-    fn from(message: Message) -> Self {
+impl TryFrom<Message> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
         match message {
-            Message::Notice(notice) => ParsedData::from(notice),
+            Message::Notice(notice) => ParsedData::try_from(notice),
             #[cfg(any(feature = "query", feature = "oobi"))]
-            Message::Op(op) => ParsedData::from(op),
+            Message::Op(op) => ParsedData::try_from(op),
         }
     }
 }
 
-impl From<Notice> for ParsedData {
-    fn from(notice: Notice) -> Self {
+impl TryFrom<Notice> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(notice: Notice) -> Result<Self, Self::Error> {
         match notice {
-            Notice::Event(event) => ParsedData::from(&event),
-            Notice::NontransferableRct(rct) => ParsedData::from(rct),
-            Notice::TransferableRct(rct) => ParsedData::from(rct),
+            Notice::Event(event) => Ok(ParsedData::from(&event)),
+            Notice::NontransferableRct(rct) => Ok(ParsedData::from(rct)),
+            Notice::TransferableRct(rct) => ParsedData::try_from(rct),
         }
     }
 }
 
 #[cfg(any(feature = "query", feature = "oobi"))]
-impl From<Op> for ParsedData {
-    fn from(op: Op) -> Self {
+impl TryFrom<Op> for ParsedData {
+    type Error = ParseError;
+
+    fn try_from(op: Op) -> Result<Self, Self::Error> {
         match op {
             #[cfg(feature = "oobi")]
-            Op::Reply(ksn) => ParsedData::from(ksn),
+            Op::Reply(ksn) => ParsedData::try_from(ksn),
             #[cfg(feature = "query")]
-            Op::Query(qry) => ParsedData::from(qry),
+            Op::Query(qry) => ParsedData::try_from(qry),
             // #[cfg(feature = "query")]
             // Op::MailboxQuery(qry) => ParsedData::from(qry),
             #[cfg(feature = "mailbox")]
-            Op::Exchange(exn) => ParsedData::from(exn),
+            Op::Exchange(exn) => ParsedData::try_from(exn),
         }
     }
 }
 
 impl Message {
     pub fn to_cesr(&self) -> Result<Vec<u8>, Error> {
-        ParsedData::from(self.clone())
+        ParsedData::try_from(self.clone())
+            .map_err(|_e| Error::CesrError)?
             .to_cesr()
             .map_err(|_e| Error::CesrError)
     }
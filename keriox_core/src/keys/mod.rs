@@ -8,6 +8,12 @@ use zeroize::Zeroize;
 pub enum KeysError {
     #[error("ED25519Dalek signature error")]
     Ed25519DalekSignatureError,
+
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+
+    #[error("Invalid {0} key")]
+    InvalidKey(String),
 }
 
 impl From<ed25519_dalek::SignatureError> for KeysError {
@@ -67,6 +73,13 @@ impl PublicKey {
             Err(_) => false,
         }
     }
+
+    pub fn verify_ed448(&self, msg: &[u8], sig: &[u8]) -> bool {
+        match ed448_rust::PublicKey::try_from(self.public_key.as_slice()) {
+            Ok(key) => key.verify(msg, sig, None).is_ok(),
+            Err(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -93,6 +106,15 @@ impl PrivateKey {
             .to_vec())
     }
 
+    pub fn sign_ed448(&self, msg: &[u8]) -> Result<Vec<u8>, KeysError> {
+        let sk = ed448_rust::PrivateKey::try_from(self.key.as_slice())
+            .map_err(|_e| KeysError::InvalidKey("Ed448".to_string()))?;
+        let sig = sk
+            .sign(msg, None)
+            .map_err(|_e| KeysError::InvalidKey("Ed448".to_string()))?;
+        Ok(sig.to_vec())
+    }
+
     pub fn key(&self) -> Vec<u8> {
         self.key.clone()
     }
@@ -1,4 +1,8 @@
+//! Internal crate of KERIOX. Its API isn't stable and can change in any
+//! release. Applications should use the `keriox` crate, which re-exports
+//! semver-stable part of it.
 pub mod actor;
+pub mod clock;
 pub mod database;
 pub mod error;
 pub mod event;
@@ -12,6 +16,7 @@ pub mod prefix;
 pub mod processor;
 #[cfg(feature = "query")]
 pub mod query;
+pub mod sai;
 pub mod signer;
 pub mod state;
 #[cfg(feature = "oobi")]
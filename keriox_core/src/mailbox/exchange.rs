@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::event::KeyEvent;
 use crate::event_message::msg::KeriEvent;
-use crate::event_message::timestamped::Timestamped;
+use crate::event_message::timestamped::{TimeStamp, Timestamped};
 use crate::prefix::IdentifierPrefix;
 
 use crate::event_message::{signature::Signature, EventTypeTag, Typeable};
@@ -43,6 +43,15 @@ impl Exchange {
 }
 
 impl Exchange {
+    pub fn get_metadata(&self) -> Option<&ExchangeMetadata> {
+        match self {
+            Exchange::Fwd {
+                args,
+                to_forward: _,
+            } => args.metadata.as_ref(),
+        }
+    }
+
     pub fn get_prefix(&self) -> IdentifierPrefix {
         match self {
             Exchange::Fwd {
@@ -51,6 +60,14 @@ impl Exchange {
             } => args.recipient_id.clone(),
         }
     }
+
+    /// Checks if request expired at provided time, according to expiry set
+    /// in its metadata.
+    pub fn is_expired(&self, now: TimeStamp) -> bool {
+        self.get_metadata()
+            .map(|metadata| metadata.is_expired(now))
+            .unwrap_or(false)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -58,6 +75,52 @@ pub struct FwdArgs {
     #[serde(rename = "pre")]
     pub recipient_id: IdentifierPrefix,
     pub topic: ForwardTopic,
+    #[serde(rename = "md", default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ExchangeMetadata>,
+}
+
+/// Context of forwarded request provided by its initiator, so recipients
+/// can decide whether to accept it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ExchangeMetadata {
+    /// Identifier of proposal, common for all exchanges sent to group
+    /// participants.
+    #[serde(rename = "pid", default, skip_serializing_if = "Option::is_none")]
+    pub proposal_id: Option<String>,
+    /// Human-readable description of request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Time after which request shouldn't be accepted.
+    #[serde(rename = "exp", default, skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<TimeStamp>,
+}
+
+impl ExchangeMetadata {
+    pub fn with_proposal_id(self, proposal_id: String) -> Self {
+        Self {
+            proposal_id: Some(proposal_id),
+            ..self
+        }
+    }
+
+    pub fn with_memo(self, memo: String) -> Self {
+        Self {
+            memo: Some(memo),
+            ..self
+        }
+    }
+
+    pub fn with_expiry(self, expiry: TimeStamp) -> Self {
+        Self {
+            expiry: Some(expiry),
+            ..self
+        }
+    }
+
+    /// Checks if request expired at provided time.
+    pub fn is_expired(&self, now: TimeStamp) -> bool {
+        self.expiry.map(|expiry| expiry < now).unwrap_or(false)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -91,3 +154,30 @@ fn test_exn_serialization() -> Result<(), crate::error::Error> {
     assert_eq!(exchange, ser_deser);
     Ok(())
 }
+
+#[test]
+fn test_exn_metadata() -> Result<(), crate::error::Error> {
+    let icp_raw = r#"{"v":"KERI10JSON000215_","t":"icp","d":"EC61gZ9lCKmHAS7U5ehUfEbGId5rcY0D7MirFZHDQcE2","i":"EC61gZ9lCKmHAS7U5ehUfEbGId5rcY0D7MirFZHDQcE2","s":"0","kt":"2","k":["DOZlWGPfDHLMf62zSFzE8thHmnQUOgA3_Y-KpOyF9ScG","DHGb2qY9WwZ1sBnC9Ip0F-M8QjTM27ftI-3jTGF9mc6K"],"nt":"2","n":["EBvD5VIVvf6NpP9GRmTqu_Cd1KN0RKrKNfPJ-uhIxurj","EHlpcaxffvtcpoUUMTc6tpqAVtb2qnOYVk_3HRsZ34PH"],"bt":"3","b":["BBilc4-L3tFUnfM_wJr4S4OJanAv_VmF_dJNN6vkf2Ha","BLskRTInXnMxWaGqcpSyMgo0nYbalW99cGZESrz3zapM","BIKKuvBwpmDVA4Ds-EpL5bt9OqPzWPja2LigFYZN2YfX"],"c":[],"a":[]}"#;
+    let icp: KeriEvent<KeyEvent> = serde_json::from_str(icp_raw).unwrap();
+    let expiry: TimeStamp = "2022-10-26T09:53:04.117732+00:00".parse().unwrap();
+    let metadata = ExchangeMetadata::default()
+        .with_proposal_id("proposal-1".to_string())
+        .with_memo("Company board group".to_string())
+        .with_expiry(expiry);
+
+    let exn = Exchange::Fwd {
+        args: FwdArgs {
+            recipient_id: icp.data.get_prefix(),
+            topic: ForwardTopic::Multisig,
+            metadata: Some(metadata.clone()),
+        },
+        to_forward: icp,
+    }
+    .to_message(SerializationFormats::JSON, HashFunctionCode::Blake3_256);
+
+    let parsed: ExchangeMessage = serde_json::from_slice(&exn.encode()?).unwrap();
+    assert_eq!(parsed.data.data.get_metadata(), Some(&metadata));
+    assert!(!metadata.is_expired("2022-10-26T09:00:00+00:00".parse().unwrap()));
+    assert!(metadata.is_expired("2022-10-27T09:00:00+00:00".parse().unwrap()));
+    Ok(())
+}
@@ -1,14 +1,183 @@
+use said::SelfAddressingIdentifier;
 use serde::{Deserialize, Serialize};
 
-use crate::event_message::signed_event_message::{
-    SignedEventMessage, SignedNontransferableReceipt,
+use crate::{
+    error::Error,
+    event_message::signed_event_message::{
+        Message, Notice, SignedEventMessage, SignedNontransferableReceipt,
+    },
+    query::mailbox::QueryTopics,
 };
 
+use self::exchange::ExchangeMetadata;
+
 pub mod exchange;
 
+/// Default maximal size of mailbox response in bytes. Bigger responses are
+/// split, so they can be transferred over slow connections before request
+/// times out.
+pub const DEFAULT_MAILBOX_MAX_SIZE: usize = 1024 * 1024;
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MailboxResponse {
     pub receipt: Vec<SignedNontransferableReceipt>,
     pub multisig: Vec<SignedEventMessage>,
     pub delegate: Vec<SignedEventMessage>,
+    /// Metadata attached by initiators to multisig events from `multisig`
+    /// field.
+    #[serde(default)]
+    pub multisig_metadata: Vec<MultisigMetadata>,
+    /// Continuation token. Set if response was truncated, contains topic
+    /// indexes that should be used in next query to get the rest of messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<QueryTopics>,
+}
+
+/// Number and size of forwarded messages stored in recipient's mailbox.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct MailboxUsage {
+    pub messages: usize,
+    /// Size of serialized messages in bytes, without attachments.
+    pub bytes: usize,
+}
+
+impl MailboxUsage {
+    /// Returns usage increased by one message of `size` bytes.
+    pub fn with(self, size: usize) -> Self {
+        Self {
+            messages: self.messages + 1,
+            bytes: self.bytes + size,
+        }
+    }
+}
+
+/// Metadata of forwarded multisig event, bound to event by its digest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MultisigMetadata {
+    #[serde(rename = "d")]
+    pub event_digest: SelfAddressingIdentifier,
+    #[serde(rename = "md")]
+    pub metadata: ExchangeMetadata,
+}
+
+impl MailboxResponse {
+    /// Returns metadata of multisig event of provided digest, if initiator
+    /// attached any.
+    pub fn get_multisig_metadata(
+        &self,
+        event_digest: &SelfAddressingIdentifier,
+    ) -> Option<&ExchangeMetadata> {
+        self.multisig_metadata
+            .iter()
+            .find(|md| &md.event_digest == event_digest)
+            .map(|md| &md.metadata)
+    }
+
+    /// Leaves at most `limit` messages, in order of receipts, multisig and
+    /// delegate topic. At least one message is left, so client can make
+    /// progress. If any message was removed, `next` is set to indexes
+    /// following the last left message of each topic. `from` are topic
+    /// indexes of the query.
+    pub fn limit_count(self, limit: usize, from: &QueryTopics) -> Self {
+        let mut left = limit.max(1);
+        let mut take = |len: usize| {
+            let count = len.min(left);
+            left -= count;
+            count
+        };
+        let receipt_count = take(self.receipt.len());
+        let multisig_count = take(self.multisig.len());
+        let delegate_count = take(self.delegate.len());
+        if receipt_count + multisig_count + delegate_count
+            == self.receipt.len() + self.multisig.len() + self.delegate.len()
+        {
+            return self;
+        }
+        self.truncate(receipt_count, multisig_count, delegate_count, from)
+    }
+
+    /// Leaves only messages that fit in `max_size` bytes of CESR stream, in
+    /// order of receipts, multisig and delegate topic. First message is
+    /// always left, so client can make progress. If any message was removed,
+    /// `next` is set to indexes following the last left message of each
+    /// topic. `from` are topic indexes of the query.
+    pub fn limit_size(self, max_size: usize, from: &QueryTopics) -> Result<Self, Error> {
+        let receipt_sizes = self
+            .receipt
+            .iter()
+            .map(|rct| {
+                Message::Notice(Notice::NontransferableRct(rct.clone()))
+                    .to_cesr()
+                    .map(|cesr| cesr.len())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let event_sizes = |events: &[SignedEventMessage]| {
+            events
+                .iter()
+                .map(|ev| {
+                    Message::Notice(Notice::Event(ev.clone()))
+                        .to_cesr()
+                        .map(|cesr| cesr.len())
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        };
+        let multisig_sizes = event_sizes(&self.multisig)?;
+        let delegate_sizes = event_sizes(&self.delegate)?;
+
+        let mut total = 0;
+        let mut truncated = false;
+        let mut fitting = |sizes: Vec<usize>| -> usize {
+            let mut count = 0;
+            for size in sizes {
+                if truncated || (total > 0 && total + size > max_size) {
+                    truncated = true;
+                    break;
+                }
+                total += size;
+                count += 1;
+            }
+            count
+        };
+        let receipt_count = fitting(receipt_sizes);
+        let multisig_count = fitting(multisig_sizes);
+        let delegate_count = fitting(delegate_sizes);
+        if !truncated {
+            return Ok(self);
+        }
+
+        Ok(self.truncate(receipt_count, multisig_count, delegate_count, from))
+    }
+
+    /// Leaves first messages of each topic, with metadata of left multisig
+    /// events, and sets `next` to indexes following them.
+    fn truncate(
+        self,
+        receipt_count: usize,
+        multisig_count: usize,
+        delegate_count: usize,
+        from: &QueryTopics,
+    ) -> Self {
+        let multisig: Vec<_> = self.multisig.into_iter().take(multisig_count).collect();
+        let multisig_metadata = self
+            .multisig_metadata
+            .into_iter()
+            .filter(|md| {
+                multisig
+                    .iter()
+                    .any(|ev| ev.event_message.digest().ok().as_ref() == Some(&md.event_digest))
+            })
+            .collect();
+        MailboxResponse {
+            receipt: self.receipt.into_iter().take(receipt_count).collect(),
+            multisig,
+            delegate: self.delegate.into_iter().take(delegate_count).collect(),
+            multisig_metadata,
+            next: Some(QueryTopics {
+                receipt: from.receipt + receipt_count,
+                multisig: from.multisig + multisig_count,
+                delegate: from.delegate + delegate_count,
+                ..from.clone()
+            }),
+        }
+    }
 }
@@ -1,20 +1,24 @@
-use std::{convert::TryFrom, path::Path};
+use std::{convert::TryFrom, path::Path, sync::Arc, time::Duration};
 
 use cesrox::parse_many;
 use serde::{Deserialize, Serialize};
-use strum_macros::EnumString;
+use strum_macros::{Display, EnumString};
 use url::Url;
 
 use self::error::OobiError;
 use crate::{
+    clock::{system_clock, Clock},
     database::sled::DbError,
     error::Error,
     event_message::signed_event_message::{Message, Op},
     prefix::IdentifierPrefix,
-    query::reply_event::{bada_logic, ReplyEvent, ReplyRoute, SignedReply},
+    query::reply_event::{
+        bada_logic, ReplyEvent, ReplyRoute, SignedReply, DEFAULT_MAX_CLOCK_DRIFT,
+    },
 };
 
 pub mod storage;
+pub mod traversal;
 
 use self::storage::OobiStorage;
 
@@ -66,11 +70,13 @@ impl LocationScheme {
 pub enum Scheme {
     #[strum(serialize = "http")]
     Http,
+    #[strum(serialize = "https")]
+    Https,
     #[strum(serialize = "tcp")]
     Tcp,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, EnumString)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, EnumString, Display)]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
     #[strum(serialize = "controller")]
@@ -81,16 +87,36 @@ pub enum Role {
     Watcher,
     #[strum(serialize = "messagebox")]
     Messagebox,
+    #[strum(serialize = "agent")]
+    Agent,
 }
 
 pub struct OobiManager {
     store: OobiStorage,
+    max_clock_drift: Duration,
+    clock: Arc<dyn Clock>,
 }
 
 impl OobiManager {
     pub fn new(oobi_db_path: &Path) -> Self {
         Self {
             store: OobiStorage::new(oobi_db_path).unwrap(),
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+            clock: system_clock(),
+        }
+    }
+
+    /// Sets clock which timestamps of oobi replies are compared with.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Sets how far in the future timestamps of accepted oobi replies can
+    /// be.
+    pub fn with_max_clock_drift(self, max_clock_drift: Duration) -> Self {
+        Self {
+            max_clock_drift,
+            ..self
         }
     }
 
@@ -103,6 +129,7 @@ impl OobiManager {
                 if rpy.signature.get_signer().ok_or(Error::MissingSigner)? != lc.get_eid() {
                     return Err(OobiError::SignerMismatch);
                 };
+                rpy.check_clock_drift(self.clock.now().into(), self.max_clock_drift)?;
 
                 if let Some(old_rpy) = self.store.get_last_loc_scheme(&lc.eid, &lc.scheme)? {
                     bada_logic(rpy, &old_rpy)?;
@@ -113,6 +140,7 @@ impl OobiManager {
                 if rpy.signature.get_signer().ok_or(Error::MissingSigner)? != er.cid {
                     return Err(OobiError::SignerMismatch);
                 };
+                rpy.check_clock_drift(self.clock.now().into(), self.max_clock_drift)?;
                 if let Some(old_rpy) = self
                     .store
                     .get_end_role(&er.cid, er.role)?
@@ -158,6 +186,15 @@ impl OobiManager {
             .map(|replies| replies.into_iter().map(|e| e.reply).collect()))
     }
 
+    /// Returns location scheme replies of given identifier together with
+    /// their signatures.
+    pub fn get_signed_loc_scheme(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Result<Option<Vec<SignedReply>>, DbError> {
+        self.store.get_oobis_for_eid(id)
+    }
+
     pub fn get_end_role(
         &self,
         id: &IdentifierPrefix,
@@ -167,6 +204,26 @@ impl OobiManager {
         // .map(|e_list| e_list.into_iter().map(|e| e.reply).collect()))
     }
 
+    /// Returns end role replies of given role that weren't cut by later
+    /// reply.
+    pub fn get_current_end_roles(
+        &self,
+        id: &IdentifierPrefix,
+        role: Role,
+    ) -> Result<Vec<SignedReply>, DbError> {
+        self.store.get_current_end_roles(id, role)
+    }
+
+    /// Returns end role replies of all roles for given controller.
+    pub fn get_all_end_roles(&self, cid: &IdentifierPrefix) -> Result<Vec<SignedReply>, DbError> {
+        self.store.get_end_roles(cid)
+    }
+
+    /// Copies saved oobis into new database at `target` path.
+    pub fn backup(&self, target: &Path) -> Result<(), DbError> {
+        self.store.backup(target)
+    }
+
     /// Assumes that signatures were verified.
     pub fn process_oobi(&self, oobi_rpy: &SignedReply) -> Result<(), OobiError> {
         self.check_oobi_reply(oobi_rpy)?;
@@ -198,6 +255,12 @@ pub mod error {
 
         #[error("invalid message type")]
         InvalidMessageType,
+
+        #[error("response too large: {0} bytes")]
+        ResponseTooLarge(usize),
+
+        #[error("too many requests to {0} while resolving oobi")]
+        RequestLimitExceeded(String),
     }
 }
 
@@ -324,4 +387,68 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_current_end_roles() -> Result<(), OobiError> {
+        use said::{derivation::HashFunctionCode, version::format::SerializationFormats};
+
+        use super::Role;
+        use crate::{
+            prefix::{BasicPrefix, SelfSigningPrefix},
+            query::reply_event::{ReplyEvent, SignedReply},
+            signer::Signer,
+        };
+
+        let oobi_manager = setup_oobi_manager();
+        let signer = Signer::new();
+        let bp = BasicPrefix::Ed25519NT(signer.public_key());
+        let cid = IdentifierPrefix::Basic(bp.clone());
+        let first_watcher =
+            IdentifierPrefix::Basic(BasicPrefix::Ed25519NT(Signer::new().public_key()));
+        let second_watcher =
+            IdentifierPrefix::Basic(BasicPrefix::Ed25519NT(Signer::new().public_key()));
+        let end_role = |eid: &IdentifierPrefix, add: bool| {
+            let end_role = EndRole {
+                cid: cid.clone(),
+                role: Role::Watcher,
+                eid: eid.clone(),
+            };
+            let route = if add {
+                ReplyRoute::EndRoleAdd(end_role)
+            } else {
+                ReplyRoute::EndRoleCut(end_role)
+            };
+            let rpy = ReplyEvent::new_reply(
+                route,
+                HashFunctionCode::Blake3_256,
+                SerializationFormats::JSON,
+            );
+            let signature =
+                SelfSigningPrefix::Ed25519Sha512(signer.sign(rpy.encode().unwrap()).unwrap());
+            SignedReply::new_nontrans(rpy, bp.clone(), signature)
+        };
+
+        oobi_manager.save_oobi(&end_role(&first_watcher, true))?;
+        oobi_manager.save_oobi(&end_role(&second_watcher, true))?;
+        oobi_manager.save_oobi(&end_role(&first_watcher, false))?;
+
+        let current = oobi_manager
+            .get_current_end_roles(&cid, Role::Watcher)?
+            .into_iter()
+            .map(|rpy| rpy.reply.get_route())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            current,
+            vec![ReplyRoute::EndRoleAdd(EndRole {
+                cid: cid.clone(),
+                role: Role::Watcher,
+                eid: second_watcher,
+            })]
+        );
+        assert!(oobi_manager
+            .get_current_end_roles(&cid, Role::Agent)?
+            .is_empty());
+
+        Ok(())
+    }
 }
@@ -77,6 +77,46 @@ impl OobiStorage {
         }))
     }
 
+    /// Returns end role replies of given role that weren't cut by later
+    /// reply for the same endpoint.
+    pub fn get_current_end_roles(
+        &self,
+        cid: &IdentifierPrefix,
+        role: Role,
+    ) -> Result<Vec<SignedReply>, DbError> {
+        let key = self.identifiers.designated_key(cid)?;
+        let mut current: Vec<SignedReply> = vec![];
+        for rpy in self.cids.get(key)?.unwrap_or_default() {
+            let (end_role, added) = match rpy.reply.get_route() {
+                ReplyRoute::EndRoleAdd(er) => (er, true),
+                ReplyRoute::EndRoleCut(er) => (er, false),
+                _ => continue,
+            };
+            if end_role.role != role {
+                continue;
+            }
+            current.retain(|saved| {
+                !matches!(saved.reply.get_route(), ReplyRoute::EndRoleAdd(er) if er.eid == end_role.eid)
+            });
+            if added {
+                current.push(rpy);
+            }
+        }
+        Ok(current)
+    }
+
+    /// Returns end role replies of all roles for given controller.
+    pub fn get_end_roles(&self, cid: &IdentifierPrefix) -> Result<Vec<SignedReply>, DbError> {
+        let key = self.identifiers.designated_key(cid)?;
+        Ok(self
+            .cids
+            .get(key)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|oobi| matches!(oobi.reply.get_route(), ReplyRoute::EndRoleAdd(_)))
+            .collect())
+    }
+
     pub fn save_oobi(&self, signed_reply: &SignedReply) -> Result<(), DbError> {
         match signed_reply.reply.get_route() {
             ReplyRoute::Ksn(_, _) => todo!(),
@@ -106,4 +146,12 @@ impl OobiStorage {
         self.db.flush()?;
         Ok(())
     }
+
+    /// Copies all trees of oobi database into new database at `target` path.
+    pub fn backup(&self, target: &Path) -> Result<(), DbError> {
+        let target_db = sled::open(target)?;
+        target_db.import(self.db.export());
+        target_db.flush()?;
+        Ok(())
+    }
 }
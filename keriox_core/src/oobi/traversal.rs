@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use url::Url;
+
+use super::{error::OobiError, Oobi};
+use crate::{
+    event_message::signed_event_message::{Message, Op},
+    prefix::IdentifierPrefix,
+    query::reply_event::ReplyRoute,
+};
+
+/// Limits of OOBI resolution. Responses can reveal further OOBIs, which are
+/// followed only within these limits, so malicious OOBI graph can't cause
+/// unbounded fetching.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OobiLimits {
+    /// How many hops from resolved OOBI are followed. `0` disables following
+    /// revealed OOBIs.
+    pub max_depth: usize,
+    /// Maximal number of requests made while resolving one OOBI.
+    pub max_requests: usize,
+    /// Maximal number of requests sent to one host while resolving one OOBI.
+    pub max_requests_per_host: usize,
+    /// Maximal size of response in bytes.
+    pub max_response_size: usize,
+}
+
+impl Default for OobiLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_requests: 16,
+            max_requests_per_host: 4,
+            max_response_size: 1024 * 1024,
+        }
+    }
+}
+
+/// State of resolving OOBI together with OOBIs revealed by responses. Each
+/// OOBI is visited once, so cycles in OOBI graph are not followed.
+pub struct OobiTraversal {
+    limits: OobiLimits,
+    queue: VecDeque<(Oobi, usize)>,
+    seen: HashSet<String>,
+    requests: usize,
+    requests_per_host: HashMap<String, usize>,
+}
+
+impl OobiTraversal {
+    pub fn new(oobi: Oobi, limits: OobiLimits) -> Self {
+        let mut traversal = Self {
+            limits,
+            queue: VecDeque::new(),
+            seen: HashSet::new(),
+            requests: 0,
+            requests_per_host: HashMap::new(),
+        };
+        traversal.push(oobi, 0);
+        traversal
+    }
+
+    /// Returns next OOBI to resolve, with its distance from the first one.
+    pub fn next_oobi(&mut self) -> Option<(Oobi, usize)> {
+        self.queue.pop_front()
+    }
+
+    /// Checks if request to `url` fits in limits and counts it if so.
+    pub fn admit(&mut self, url: &Url) -> Result<(), OobiError> {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let host_requests = self.requests_per_host.entry(host.clone()).or_default();
+        if self.requests >= self.limits.max_requests
+            || *host_requests >= self.limits.max_requests_per_host
+        {
+            return Err(OobiError::RequestLimitExceeded(host));
+        }
+        *host_requests += 1;
+        self.requests += 1;
+        Ok(())
+    }
+
+    pub fn check_response(&self, response: &[u8]) -> Result<(), OobiError> {
+        if response.len() > self.limits.max_response_size {
+            Err(OobiError::ResponseTooLarge(response.len()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds OOBIs revealed in response from `source` to OOBI at `depth`.
+    /// Only OOBIs of other endpoint providers than `source` are followed.
+    pub fn discovered(&mut self, depth: usize, source: &IdentifierPrefix, messages: &[Message]) {
+        if depth >= self.limits.max_depth {
+            return;
+        }
+        for message in messages {
+            let revealed = match message {
+                Message::Op(Op::Reply(rpy)) => match rpy.reply.get_route() {
+                    ReplyRoute::EndRoleAdd(er) if &er.eid != source => Oobi::EndRole(er),
+                    ReplyRoute::LocScheme(loc) if &loc.eid != source => Oobi::Location(loc),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            self.push(revealed, depth + 1);
+        }
+    }
+
+    fn push(&mut self, oobi: Oobi, depth: usize) {
+        let key = serde_json::to_string(&oobi).unwrap_or_default();
+        if self.seen.insert(key) {
+            self.queue.push_back((oobi, depth));
+        }
+    }
+}
+
+#[test]
+fn test_oobi_traversal() {
+    use cesrox::parse_many;
+
+    let root: Oobi = serde_json::from_str(r#"{"eid":"BuyRFMideczFZoapylLIyCjSdhtqVb31wZkRKvPfNqkw","scheme":"http","url":"http://127.0.0.1:5643/"}"#).unwrap();
+    let source: IdentifierPrefix = "BuyRFMideczFZoapylLIyCjSdhtqVb31wZkRKvPfNqkw"
+        .parse()
+        .unwrap();
+    // Response revealing two locations of other witness.
+    let response = br#"{"v":"KERI10JSON0000fa_","t":"rpy","d":"Elxbk-5h8a2PhoserezofHRXEDgAEwhrW0wvhXqyupmY","dt":"2022-04-08T15:00:29.163849+00:00","r":"/loc/scheme","a":{"eid":"Bgoq68HCmYNUDgOz4Skvlu306o_NY-NrYuKAVhk3Zh9c","scheme":"http","url":"http://127.0.0.1:5644/"}}-VAi-CABBgoq68HCmYNUDgOz4Skvlu306o_NY-NrYuKAVhk3Zh9c0BezpFQMVxodb7WMUBL4aLeQW1CUTUYbcFNPGohh02cKl7kSajyRZAentI-MkconvyI8-QfaO1in5mexYF-1ZPBg{"v":"KERI10JSON0000f8_","t":"rpy","d":"EfJP2Mkp_2UZJoWoNCWZHMgU7uWMIkzih19Nvit36Cho","dt":"2022-04-08T15:00:29.165103+00:00","r":"/loc/scheme","a":{"eid":"Bgoq68HCmYNUDgOz4Skvlu306o_NY-NrYuKAVhk3Zh9c","scheme":"tcp","url":"tcp://127.0.0.1:5634/"}}-VAi-CABBgoq68HCmYNUDgOz4Skvlu306o_NY-NrYuKAVhk3Zh9c0BFcwrcL7Hc8HYLSPvzMGAAEn5QyY76QWY1l2RotQqsX01HgDh4UZYU5GpiVY2A-AbsRIsUpfIKnQi7r4dc0o0DA"#;
+    let messages: Vec<Message> = parse_many(response)
+        .unwrap()
+        .1
+        .into_iter()
+        .map(|data| Message::try_from(data).unwrap())
+        .collect();
+
+    let mut traversal = OobiTraversal::new(root.clone(), OobiLimits::default());
+    let (oobi, depth) = traversal.next_oobi().unwrap();
+    assert_eq!((oobi, depth), (root.clone(), 0));
+
+    // Locations of other witness are followed.
+    traversal.discovered(0, &source, &messages);
+    for _ in 0..2 {
+        let (revealed, depth) = traversal.next_oobi().unwrap();
+        assert_eq!(depth, 1);
+        assert!(matches!(revealed, Oobi::Location(ref loc) if loc.eid != source));
+    }
+
+    // Already visited OOBIs are skipped.
+    traversal.discovered(1, &source, &messages);
+    assert!(traversal.next_oobi().is_none());
+
+    // Nothing is followed beyond max depth.
+    let mut traversal = OobiTraversal::new(
+        root.clone(),
+        OobiLimits {
+            max_depth: 0,
+            ..Default::default()
+        },
+    );
+    traversal.next_oobi();
+    traversal.discovered(0, &source, &messages);
+    assert!(traversal.next_oobi().is_none());
+
+    // Requests per host and in total are limited.
+    let mut traversal = OobiTraversal::new(
+        root,
+        OobiLimits {
+            max_requests: 3,
+            max_requests_per_host: 2,
+            ..Default::default()
+        },
+    );
+    let url = Url::parse("http://127.0.0.1:5643/").unwrap();
+    let other_url = Url::parse("http://witness.example/").unwrap();
+    assert!(traversal.admit(&url).is_ok());
+    assert!(traversal.admit(&url).is_ok());
+    assert!(matches!(
+        traversal.admit(&url),
+        Err(OobiError::RequestLimitExceeded(_))
+    ));
+    assert!(traversal.admit(&other_url).is_ok());
+    assert!(traversal.admit(&other_url).is_err());
+
+    assert!(traversal.check_response(&[0; 16]).is_ok());
+    assert!(matches!(
+        traversal.check_response(&vec![0; 1024 * 1024 + 1]),
+        Err(OobiError::ResponseTooLarge(_))
+    ));
+}
@@ -1,3 +1,8 @@
+//! Conversions between keri_core types and CESR primitives of `cesrox`.
+//!
+//! Conversions are implemented in both directions with `From` (or `TryFrom`
+//! where not every value is representable), so crates using both can use
+//! `into()` instead of matching on codes.
 use cesrox::primitives::{
     codes::attached_signature_code::{AttachedSignatureCode, Index as CesrIndex},
     CesrPrimitive, Digest, Identifier, IdentifierCode, IndexedSignature as CesrIndexedSignature,
@@ -10,7 +15,7 @@ use crate::{
     prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
 };
 
-use super::attached_signature::Index;
+use super::{attached_signature::Index, error::Error};
 
 impl From<CesrIndexedSignature> for IndexedSignature {
     fn from((code, value): CesrIndexedSignature) -> Self {
@@ -102,30 +107,36 @@ impl From<Identifier> for IdentifierPrefix {
     }
 }
 
-impl Into<Signature> for SelfSigningPrefix {
-    fn into(self) -> Signature {
-        (self.get_code(), self.derivative())
+impl From<SelfSigningPrefix> for Signature {
+    fn from(signature: SelfSigningPrefix) -> Self {
+        (signature.get_code(), signature.derivative())
     }
 }
 
-impl Into<PublicKey> for BasicPrefix {
-    fn into(self) -> PublicKey {
-        (self.get_code(), self.derivative())
+impl From<BasicPrefix> for PublicKey {
+    fn from(bp: BasicPrefix) -> Self {
+        (bp.get_code(), bp.derivative())
     }
 }
 
-impl Into<CesrIndexedSignature> for IndexedSignature {
-    fn into(self) -> CesrIndexedSignature {
+impl From<IndexedSignature> for CesrIndexedSignature {
+    fn from(signature: IndexedSignature) -> Self {
         (
-            AttachedSignatureCode::new(self.signature.get_code(), (&self.index).into()),
-            self.derivative(),
+            AttachedSignatureCode::new(signature.signature.get_code(), (&signature.index).into()),
+            signature.derivative(),
         )
     }
 }
 
-impl Into<CesrIndex> for &Index {
-    fn into(self) -> CesrIndex {
-        match self {
+impl From<Index> for CesrIndex {
+    fn from(index: Index) -> Self {
+        (&index).into()
+    }
+}
+
+impl From<&Index> for CesrIndex {
+    fn from(index: &Index) -> Self {
+        match index {
             Index::CurrentOnly(i) => {
                 if *i < 64 {
                     CesrIndex::CurrentOnly(*i)
@@ -145,17 +156,116 @@ impl Into<CesrIndex> for &Index {
     }
 }
 
-impl Into<Identifier> for IdentifierPrefix {
-    fn into(self) -> Identifier {
-        match &self {
+/// Fails for self-signing identifiers, which have no CESR identifier code.
+impl TryFrom<IdentifierPrefix> for Identifier {
+    type Error = Error;
+
+    fn try_from(id: IdentifierPrefix) -> Result<Self, Self::Error> {
+        match &id {
             IdentifierPrefix::Basic(bp) => {
-                (IdentifierCode::Basic(bp.get_code()), self.derivative())
+                Ok((IdentifierCode::Basic(bp.get_code()), id.derivative()))
             }
-            IdentifierPrefix::SelfAddressing(sa) => (
+            IdentifierPrefix::SelfAddressing(sa) => Ok((
                 IdentifierCode::SelfAddressing((&sa.said.derivation).into()),
-                self.derivative(),
-            ),
-            IdentifierPrefix::SelfSigning(_ss) => todo!(),
+                id.derivative(),
+            )),
+            IdentifierPrefix::SelfSigning(_ss) => Err(Error::WrongIdentifierType(id.to_string())),
         }
     }
 }
+
+impl TryFrom<Identifier> for BasicPrefix {
+    type Error = Error;
+
+    fn try_from(identifier: Identifier) -> Result<Self, Self::Error> {
+        match IdentifierPrefix::from(identifier) {
+            IdentifierPrefix::Basic(bp) => Ok(bp),
+            other => Err(Error::WrongIdentifierType(other.to_string())),
+        }
+    }
+}
+
+impl From<SourceSeal> for (u64, Digest) {
+    fn from(seal: SourceSeal) -> Self {
+        (seal.sn, seal.digest.into())
+    }
+}
+
+impl From<(Identifier, u64, Digest)> for EventSeal {
+    fn from((identifier, sn, digest): (Identifier, u64, Digest)) -> Self {
+        EventSeal::new(identifier.into(), sn, digest.into())
+    }
+}
+
+impl TryFrom<EventSeal> for (Identifier, u64, Digest) {
+    type Error = Error;
+
+    fn try_from(seal: EventSeal) -> Result<Self, Self::Error> {
+        let digest = seal.event_digest().into();
+        Ok((seal.prefix.try_into()?, seal.sn, digest))
+    }
+}
+
+#[test]
+fn test_cesr_conversions() {
+    use crate::event_message::signature::Nontransferable;
+    use cesrox::group::Group;
+
+    let bp: BasicPrefix = "DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q"
+        .parse()
+        .unwrap();
+    let said: SelfAddressingIdentifier = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+    let signature: SelfSigningPrefix =
+        "0BCZ0jw5JCQwn2v7GKCMQHISMi5rsscfcA4nbY9AqqWMyG6FyCH2cZFwqezPkq8p3sr8f37Xb3wXgh3UPG8igSYJ"
+            .parse()
+            .unwrap();
+
+    let pk: PublicKey = bp.clone().into();
+    assert_eq!(BasicPrefix::from(pk), bp);
+
+    let sig: Signature = signature.clone().into();
+    assert_eq!(SelfSigningPrefix::from(sig), signature);
+
+    for indexed in [
+        IndexedSignature::new_both_same(signature.clone(), 1),
+        IndexedSignature::new_both_diffrent(signature.clone(), 1, 2),
+        IndexedSignature::new_current_only(signature.clone(), 3),
+    ] {
+        let cesr: CesrIndexedSignature = indexed.clone().into();
+        assert_eq!(IndexedSignature::from(cesr), indexed);
+    }
+
+    for id in [
+        IdentifierPrefix::Basic(bp.clone()),
+        IdentifierPrefix::SelfAddressing(said.clone().into()),
+    ] {
+        let cesr: Identifier = id.clone().try_into().unwrap();
+        assert_eq!(IdentifierPrefix::from(cesr.clone()), id);
+
+        let seal = EventSeal::new(id.clone(), 3, said.clone());
+        let cesr_seal: (Identifier, u64, Digest) = seal.clone().try_into().unwrap();
+        assert_eq!(EventSeal::from(cesr_seal), seal);
+    }
+    let basic_id: Identifier = IdentifierPrefix::Basic(bp.clone()).try_into().unwrap();
+    assert_eq!(BasicPrefix::try_from(basic_id).unwrap(), bp);
+    let sai_id: Identifier = IdentifierPrefix::SelfAddressing(said.clone().into())
+        .try_into()
+        .unwrap();
+    assert!(BasicPrefix::try_from(sai_id).is_err());
+
+    // Self-signing identifiers have no CESR identifier code.
+    let self_signing = IdentifierPrefix::SelfSigning(signature.clone());
+    assert!(Identifier::try_from(self_signing.clone()).is_err());
+    let seal = EventSeal::new(self_signing, 0, said.clone());
+    assert!(<(Identifier, u64, Digest)>::try_from(seal).is_err());
+
+    let source_seal = SourceSeal::new(2, said.clone());
+    let cesr_source_seal: (u64, Digest) = source_seal.clone().into();
+    assert_eq!(SourceSeal::from(cesr_source_seal), source_seal);
+
+    let couplets = Nontransferable::Couplet(vec![(bp, signature)]);
+    let group: Group = couplets.clone().into();
+    assert_eq!(Nontransferable::try_from(group).unwrap(), couplets);
+}
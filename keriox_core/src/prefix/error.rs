@@ -16,6 +16,9 @@ pub enum Error {
     #[error(transparent)]
     ParseError(#[from] cesrox::error::Error),
 
+    #[error("Wrong identifier type: {0}")]
+    WrongIdentifierType(String),
+
     #[error("ED25519Dalek signature error")]
     Ed25519DalekSignatureError,
 }
@@ -131,6 +131,10 @@ pub fn verify(
             }
             _ => Err(SignatureError::WrongSignatureTypeError),
         },
+        BasicPrefix::Ed448(key) | BasicPrefix::Ed448NT(key) => match signature {
+            SelfSigningPrefix::Ed448(signature) => Ok(key.verify_ed448(data.as_ref(), signature)),
+            _ => Err(SignatureError::WrongSignatureTypeError),
+        },
         _ => Err(SignatureError::WrongKeyTypeError),
     }
 }
@@ -149,6 +153,8 @@ pub fn derive(seed: &SeedPrefix, transferable: bool) -> Result<BasicPrefix, Erro
         SeedPrefix::RandomSeed256ECDSAsecp256k1(_) if !transferable => {
             BasicPrefix::ECDSAsecp256k1NT(pk)
         }
+        SeedPrefix::RandomSeed448(_) if transferable => BasicPrefix::Ed448(pk),
+        SeedPrefix::RandomSeed448(_) if !transferable => BasicPrefix::Ed448NT(pk),
         _ => return Err(Error::WrongSeedTypeError),
     })
 }
@@ -45,6 +45,14 @@ impl SeedPrefix {
                     PrivateKey::new(sk.to_bytes().to_vec()),
                 ))
             }
+            Self::RandomSeed448(seed) => {
+                let sk = ed448_rust::PrivateKey::try_from(seed.as_slice())
+                    .map_err(|_e| Error::WrongSeedTypeError)?;
+                Ok((
+                    PublicKey::new(ed448_rust::PublicKey::from(&sk).as_byte().to_vec()),
+                    PrivateKey::new(seed.clone()),
+                ))
+            }
             _ => Err(Error::WrongSeedTypeError),
         }
     }
@@ -52,6 +52,18 @@ impl<D: EventDatabase> BasicProcessor<D> {
         Self(processor)
     }
 
+    /// Sets how far in the future timestamps of accepted replies can be.
+    #[cfg(feature = "query")]
+    pub fn with_max_clock_drift(self, max_clock_drift: std::time::Duration) -> Self {
+        Self(self.0.with_max_clock_drift(max_clock_drift))
+    }
+
+    /// Sets clock which timestamps of accepted replies are compared with.
+    #[cfg(feature = "query")]
+    pub fn with_clock(self, clock: Arc<dyn crate::clock::Clock>) -> Self {
+        Self(self.0.with_clock(clock))
+    }
+
     fn basic_processing_strategy(
         events_db: Arc<D>,
         db: Arc<SledEventDatabase>,
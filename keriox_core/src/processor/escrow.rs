@@ -1,5 +1,6 @@
 use std::{fmt::Debug, sync::Arc, time::Duration};
 
+use chrono::{DateTime, Local};
 use said::SelfAddressingIdentifier;
 
 use super::{
@@ -8,6 +9,7 @@ use super::{
     validator::EventValidator,
 };
 use crate::{
+    clock::{system_clock, Clock},
     database::{
         escrow::{Escrow, EscrowDb},
         sled::SledEventDatabase,
@@ -41,6 +43,8 @@ pub struct EscrowConfig {
     pub partially_witnessed_timeout: Duration,
     pub trans_receipt_timeout: Duration,
     pub delegation_timeout: Duration,
+    /// Clock used to check if escrowed events are stale.
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Default for EscrowConfig {
@@ -51,10 +55,23 @@ impl Default for EscrowConfig {
             partially_witnessed_timeout: Duration::from_secs(60),
             trans_receipt_timeout: Duration::from_secs(60),
             delegation_timeout: Duration::from_secs(60),
+            clock: system_clock(),
         }
     }
 }
 
+/// Digest, which escrowed events are indexed by.
+fn event_digest(event: &SignedEventMessage) -> Option<SelfAddressingIdentifier> {
+    event.event_message.digest().ok()
+}
+
+/// Digest of receipted event, which escrowed receipts are indexed by.
+fn receipted_event_digest(
+    receipt: &SignedNontransferableReceipt,
+) -> Option<SelfAddressingIdentifier> {
+    Some(receipt.body.receipted_event_digest.clone())
+}
+
 pub fn default_escrow_bus<D: EventDatabase + Send + Sync + 'static>(
     event_db: Arc<D>,
     sled_db: Arc<SledEventDatabase>,
@@ -72,12 +89,15 @@ pub fn default_escrow_bus<D: EventDatabase + Send + Sync + 'static>(
     let mut bus = NotificationBus::new();
 
     // Register out of order escrow, to save and reprocess out of order events
-    let ooo_escrow = Arc::new(OutOfOrderEscrow::new(
-        event_db.clone(),
-        sled_db.clone(),
-        escrow_db.clone(),
-        escrow_config.out_of_order_timeout,
-    ));
+    let ooo_escrow = Arc::new(
+        OutOfOrderEscrow::new(
+            event_db.clone(),
+            sled_db.clone(),
+            escrow_db.clone(),
+            escrow_config.out_of_order_timeout,
+        )
+        .with_clock(escrow_config.clock.clone()),
+    );
     bus.register_observer(
         ooo_escrow.clone(),
         vec![
@@ -86,20 +106,26 @@ pub fn default_escrow_bus<D: EventDatabase + Send + Sync + 'static>(
         ],
     );
 
-    let ps_escrow = Arc::new(PartiallySignedEscrow::new(
-        event_db.clone(),
-        sled_db.clone(),
-        escrow_db.clone(),
-        escrow_config.partially_signed_timeout,
-    ));
+    let ps_escrow = Arc::new(
+        PartiallySignedEscrow::new(
+            event_db.clone(),
+            sled_db.clone(),
+            escrow_db.clone(),
+            escrow_config.partially_signed_timeout,
+        )
+        .with_clock(escrow_config.clock.clone()),
+    );
     bus.register_observer(ps_escrow.clone(), vec![JustNotification::PartiallySigned]);
 
-    let pw_escrow = Arc::new(PartiallyWitnessedEscrow::new(
-        event_db.clone(),
-        sled_db.clone(),
-        escrow_db.clone(),
-        escrow_config.partially_witnessed_timeout,
-    ));
+    let pw_escrow = Arc::new(
+        PartiallyWitnessedEscrow::new(
+            event_db.clone(),
+            sled_db.clone(),
+            escrow_db.clone(),
+            escrow_config.partially_witnessed_timeout,
+        )
+        .with_clock(escrow_config.clock.clone()),
+    );
     bus.register_observer(
         pw_escrow.clone(),
         vec![
@@ -109,24 +135,30 @@ pub fn default_escrow_bus<D: EventDatabase + Send + Sync + 'static>(
     );
 
     bus.register_observer(
-        Arc::new(TransReceiptsEscrow::new(
-            event_db.clone(),
-            sled_db.clone(),
-            escrow_db.clone(),
-            escrow_config.trans_receipt_timeout,
-        )),
+        Arc::new(
+            TransReceiptsEscrow::new(
+                event_db.clone(),
+                sled_db.clone(),
+                escrow_db.clone(),
+                escrow_config.trans_receipt_timeout,
+            )
+            .with_clock(escrow_config.clock.clone()),
+        ),
         vec![
             JustNotification::KeyEventAdded,
             JustNotification::TransReceiptOutOfOrder,
         ],
     );
 
-    let delegation_escrow = Arc::new(DelegationEscrow::new(
-        event_db,
-        sled_db,
-        escrow_db,
-        escrow_config.delegation_timeout,
-    ));
+    let delegation_escrow = Arc::new(
+        DelegationEscrow::new(
+            event_db,
+            sled_db,
+            escrow_db,
+            escrow_config.delegation_timeout,
+        )
+        .with_clock(escrow_config.clock.clone()),
+    );
     bus.register_observer(
         delegation_escrow.clone(),
         vec![
@@ -151,7 +183,7 @@ impl<D: EventDatabase> OutOfOrderEscrow<D> {
         escrow_db: Arc<EscrowDb>,
         duration: Duration,
     ) -> Self {
-        let escrow = Escrow::new(b"ooes", duration, escrow_db);
+        let escrow = Escrow::new(b"ooes", duration, escrow_db).with_digest_index(event_digest);
         Self {
             db,
             sled_db,
@@ -159,19 +191,27 @@ impl<D: EventDatabase> OutOfOrderEscrow<D> {
         }
     }
 
+    /// Sets clock used to check if escrowed events are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            escrowed_out_of_order: self.escrowed_out_of_order.with_clock(clock),
+            ..self
+        }
+    }
+
     pub fn get_event_by_sn_and_digest(
         &self,
         sn: u64,
         id: &IdentifierPrefix,
         event_digest: &SelfAddressingIdentifier,
     ) -> Option<SignedEventMessage> {
-        self.escrowed_out_of_order.get(id).and_then(|mut events| {
-            events.find(|event| {
-                event.event_message.data.sn == sn
-                    && &event.event_message.data.prefix == id
-                    && event.event_message.digest().ok().as_ref() == Some(event_digest)
+        self.escrowed_out_of_order
+            .get_by_digest(event_digest)
+            .and_then(|mut events| {
+                events.find(|event| {
+                    event.event_message.data.sn == sn && &event.event_message.data.prefix == id
+                })
             })
-        })
     }
 }
 impl<D: EventDatabase> Notifier for OutOfOrderEscrow<D> {
@@ -249,6 +289,14 @@ impl<D: EventDatabase> PartiallySignedEscrow<D> {
             escrowed_partially_signed: escrow,
         }
     }
+
+    /// Sets clock used to check if escrowed events are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            escrowed_partially_signed: self.escrowed_partially_signed.with_clock(clock),
+            ..self
+        }
+    }
 }
 
 impl<D: EventDatabase> PartiallySignedEscrow<D> {
@@ -365,7 +413,7 @@ impl<D: EventDatabase> PartiallySignedEscrow<D> {
 pub struct PartiallyWitnessedEscrow<D: EventDatabase> {
     db: Arc<D>,
     old_db: Arc<SledEventDatabase>,
-    pub(crate) escrowed_partially_witnessed: Escrow<SignedEventMessage>,
+    pub escrowed_partially_witnessed: Escrow<SignedEventMessage>,
     pub(crate) escrowed_nontranferable_receipts: Escrow<SignedNontransferableReceipt>,
 }
 
@@ -379,8 +427,23 @@ impl<D: EventDatabase> PartiallyWitnessedEscrow<D> {
         Self {
             db,
             old_db,
-            escrowed_partially_witnessed: Escrow::new(b"pwes", duration, escrow_db.clone()),
-            escrowed_nontranferable_receipts: Escrow::new(b"ures", duration, escrow_db.clone()),
+            escrowed_partially_witnessed: Escrow::new(b"pwes", duration, escrow_db.clone())
+                .with_digest_index(event_digest),
+            escrowed_nontranferable_receipts: Escrow::new(b"ures", duration, escrow_db.clone())
+                .with_digest_index(receipted_event_digest),
+        }
+    }
+
+    /// Sets clock used to check if escrowed events are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            escrowed_partially_witnessed: self
+                .escrowed_partially_witnessed
+                .with_clock(clock.clone()),
+            escrowed_nontranferable_receipts: self
+                .escrowed_nontranferable_receipts
+                .with_clock(clock),
+            ..self
         }
     }
 
@@ -393,12 +456,10 @@ impl<D: EventDatabase> PartiallyWitnessedEscrow<D> {
         event_digest: &SelfAddressingIdentifier,
     ) -> Option<SignedEventMessage> {
         self.escrowed_partially_witnessed
-            .get(id)
+            .get_by_digest(event_digest)
             .and_then(|mut events| {
                 events.find(|event| {
-                    event.event_message.data.sn == sn
-                        && &event.event_message.data.prefix == id
-                        && event.event_message.digest().ok().as_ref() == Some(event_digest)
+                    event.event_message.data.sn == sn && &event.event_message.data.prefix == id
                 })
             })
     }
@@ -409,11 +470,24 @@ impl<D: EventDatabase> PartiallyWitnessedEscrow<D> {
         sn: u64,
         digest: &SelfAddressingIdentifier,
     ) -> Option<Vec<SignedNontransferableReceipt>> {
-        self.escrowed_nontranferable_receipts.get(id).map(|r| {
-            r.filter(|rct| rct.body.sn == sn && &rct.body.receipted_event_digest == digest)
-                // TODO avoid collect
-                .collect()
-        })
+        self.escrowed_nontranferable_receipts
+            .get_by_digest(digest)
+            .map(|r| {
+                r.filter(|rct| rct.body.sn == sn && &rct.body.prefix == id)
+                    // TODO avoid collect
+                    .collect()
+            })
+    }
+
+    /// Returns escrowed receipts of given identifier's events.
+    pub fn get_escrowed_receipts_of(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Vec<SignedNontransferableReceipt> {
+        match self.escrowed_nontranferable_receipts.get(id) {
+            Some(receipts) => receipts.collect(),
+            None => vec![],
+        }
     }
 
     pub fn get_partially_witnessed_events(&self) -> Vec<SignedEventMessage> {
@@ -423,6 +497,18 @@ impl<D: EventDatabase> PartiallyWitnessedEscrow<D> {
         }
     }
 
+    /// Returns escrowed partially witnessed events of given identifier
+    /// together with time they were escrowed at.
+    pub fn get_partially_witnessed_of(
+        &self,
+        id: &IdentifierPrefix,
+    ) -> Vec<(SignedEventMessage, DateTime<Local>)> {
+        match self.escrowed_partially_witnessed.get_timestamped(id) {
+            Some(events) => events.collect(),
+            None => vec![],
+        }
+    }
+
     /// Saves nontransferable receipt in escrow.
     fn escrow_receipt(
         &self,
@@ -671,7 +757,7 @@ impl<D: EventDatabase> Notifier for PartiallyWitnessedEscrow<D> {
 pub struct TransReceiptsEscrow<D: EventDatabase> {
     db: Arc<D>,
     old_db: Arc<SledEventDatabase>,
-    pub(crate) escrowed_trans_receipts: Escrow<SignedTransferableReceipt>,
+    pub escrowed_trans_receipts: Escrow<SignedTransferableReceipt>,
 }
 impl<D: EventDatabase> TransReceiptsEscrow<D> {
     pub fn new(
@@ -686,6 +772,14 @@ impl<D: EventDatabase> TransReceiptsEscrow<D> {
             escrowed_trans_receipts: Escrow::new(b"vres", duration, escrow_db.clone()),
         }
     }
+
+    /// Sets clock used to check if escrowed events are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            escrowed_trans_receipts: self.escrowed_trans_receipts.with_clock(clock),
+            ..self
+        }
+    }
 }
 impl<D: EventDatabase> Notifier for TransReceiptsEscrow<D> {
     fn notify(&self, notification: &Notification, bus: &NotificationBus) -> Result<(), Error> {
@@ -796,6 +890,8 @@ impl<D: EventDatabase> ReplyEscrow<D> {
                         self.escrow_db.remove_escrowed_reply(&id, &sig_rep)?;
                     }
                     Err(Error::EventOutOfOrderError)
+                    | Err(Error::QueryError(QueryError::FutureRpy(_)))
+                    | Err(Error::QueryError(QueryError::FutureKsn(_)))
                     | Err(Error::VerificationError(VerificationError::MoreInfo(
                         MoreInfoError::EventNotFound(_),
                     ))) => (), // keep in escrow,
@@ -821,7 +917,7 @@ impl<D: EventDatabase> DelegationEscrow<D> {
         escrow_db: Arc<EscrowDb>,
         duration: Duration,
     ) -> Self {
-        let escrow = Escrow::new(b"dees", duration, escrow_db);
+        let escrow = Escrow::new(b"dees", duration, escrow_db).with_digest_index(event_digest);
         Self {
             db,
             sled_db,
@@ -829,6 +925,16 @@ impl<D: EventDatabase> DelegationEscrow<D> {
         }
     }
 
+    /// Sets clock used to check if escrowed events are stale.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            delegation_escrow: self.delegation_escrow.with_clock(clock),
+            ..self
+        }
+    }
+
+    /// Returns delegated event of given sn and digest, escrowed until
+    /// `delegator_id` anchors it.
     pub fn get_event_by_sn_and_digest(
         &self,
         sn: u64,
@@ -836,13 +942,8 @@ impl<D: EventDatabase> DelegationEscrow<D> {
         event_digest: &SelfAddressingIdentifier,
     ) -> Option<SignedEventMessage> {
         self.delegation_escrow
-            .get(delegator_id)
-            .and_then(|mut events| {
-                events.find(|event| {
-                    event.event_message.data.sn == sn
-                        && event.event_message.digest().ok().as_ref() == Some(event_digest)
-                })
-            })
+            .get_by_id_and_digest(delegator_id, event_digest)
+            .and_then(|mut events| events.find(|event| event.event_message.data.sn == sn))
     }
 }
 
@@ -2,7 +2,6 @@ use std::{
     convert::{TryFrom, TryInto},
     fs,
     sync::Arc,
-    thread::{self, sleep},
     time::Duration,
 };
 
@@ -10,6 +9,7 @@ use cesrox::{parse, parse_many, payload::parse_payload};
 use tempfile::NamedTempFile;
 
 use crate::{
+    clock::ManualClock,
     database::{
         escrow::EscrowDb, redb::RedbDatabase, sled::SledEventDatabase, EventDatabase,
         QueryParameters,
@@ -575,6 +575,7 @@ fn test_partially_sign_escrow() -> Result<(), Error> {
     use tempfile::Builder;
 
     // events from keripy/tests/core/test_escrow.py::test_partial_signed_escrow
+    let clock = Arc::new(ManualClock::default());
     let (processor, storage, ps_escrow) = {
         let witness_root = Builder::new().prefix("test-db").tempdir().unwrap();
         let path = witness_root.path();
@@ -722,6 +723,7 @@ fn test_out_of_order_cleanup() -> Result<(), Error> {
 
     use tempfile::Builder;
 
+    let clock = Arc::new(ManualClock::default());
     let (processor, storage, ooo_escrow) = {
         let witness_root = Builder::new().prefix("test-db").tempdir().unwrap();
         let path = witness_root.path();
@@ -733,12 +735,15 @@ fn test_out_of_order_cleanup() -> Result<(), Error> {
         // Register out of order escrow, to save and reprocess out of order events
         let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
         let escrow_db = Arc::new(EscrowDb::new(escrow_root.path())?);
-        let ooo_escrow = Arc::new(OutOfOrderEscrow::new(
-            events_db.clone(),
-            sled_db.clone(),
-            escrow_db,
-            Duration::from_secs(1),
-        ));
+        let ooo_escrow = Arc::new(
+            OutOfOrderEscrow::new(
+                events_db.clone(),
+                sled_db.clone(),
+                escrow_db,
+                Duration::from_secs(1),
+            )
+            .with_clock(clock.clone()),
+        );
         processor.register_observer(
             ooo_escrow.clone(),
             &[
@@ -771,8 +776,8 @@ fn test_out_of_order_cleanup() -> Result<(), Error> {
     // Process one more out of order event.
     processor.process(&ev3.clone())?;
 
-    // Wait until escrowed events become stale.
-    thread::sleep(Duration::from_secs(1));
+    // Move time forward until escrowed events become stale.
+    clock.advance(Duration::from_secs(1));
 
     // Process inorder missing event.
     processor.process(&ev2.clone())?;
@@ -807,11 +812,136 @@ fn test_out_of_order_cleanup() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_escrow_digest_index() -> Result<(), Error> {
+    use crate::database::escrow::Escrow;
+    use tempfile::Builder;
+
+    let kel = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL","i":"EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL","s":"0","kt":"1","k":["DODv7KGqEEhAP7-VYXzZvNi5wmgEB8w5y6HLUQL08PNh"],"nt":"1","n":["ECo41Mn5wku-tQd7L4Hp65KhaX1KkdTtSY_NXx4rQphS"],"bt":"0","b":["DPOIlcZk_GLVCVtG7KLbDQa2a5drXGt09wpaeY93G--1"],"c":[],"a":[]}-AABAADtEDd5x0DRfSlGl99G2V3aiJQlILTMG8LHNbG6V3ticL8r1vMK8-nmhZBhZglI06mVChxc-EkgqWPzPlI2rAwD{"v":"KERI10JSON000160_","t":"rot","d":"EDBBxc3_cczsEld6szaFdmhR3JyOhnYaDCCdo_wDe95p","i":"EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL","s":"1","p":"EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL","kt":"1","k":["DIgRd-GK29iB-G7tao3-BCdMbUCATveeMrzivmmmM_Nf"],"nt":"1","n":["EBrEok_A-yJGpR9GH_ktdd11x3UR0cHaCg0nzAnYLgGj"],"bt":"0","br":[],"ba":[],"a":[]}-AABAADLgLBVFeCOP8t-sxOWKif-JbQ-PnOz0W7aZCuLPOUEri-OdGXjOV2d3y6-R_SsS2U3toE3TNVJ9UyO5NhBSkkO{"v":"KERI10JSON000160_","t":"rot","d":"ENtkE-NChURiXS5j8ES9GeX9VCqr5PLxilygqUJQ5Wr9","i":"EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL","s":"2","p":"EDBBxc3_cczsEld6szaFdmhR3JyOhnYaDCCdo_wDe95p","kt":"1","k":["DGx72gYpAdz0N3br4blkVRRoIASdcBTJaqtLnGI6PXHV"],"nt":"1","n":["EMEVqKOHmF9juqQSmphqjnP24tT__JILJJ2Z4u9QKSUn"],"bt":"0","br":[],"ba":[],"a":[]}-AABAAAHF__vhEKj4kn1uW0fdBRS75nyG3uvJuEfcOdnx4sfy2vNirkDLkm6WGluUVDfQ7y9_b2TIaIHLfAoBefjNBkF"#;
+    let mut kell = parse_many(kel)
+        .unwrap()
+        .1
+        .into_iter()
+        .map(|e| Message::try_from(e).unwrap());
+    let ev1 = kell.next().unwrap();
+    let ev2 = kell.next().unwrap();
+    let ev3 = kell.next().unwrap();
+    let signed_ev3 = match &ev3 {
+        Message::Notice(Notice::Event(event)) => event.clone(),
+        _ => unreachable!(),
+    };
+    let ev3_digest = signed_ev3.event_message.digest()?;
+
+    let clock = Arc::new(ManualClock::default());
+    let witness_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let sled_db = Arc::new(SledEventDatabase::new(witness_root.path()).unwrap());
+    let events_db_path = NamedTempFile::new().unwrap();
+    let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+    let mut processor = BasicProcessor::new(events_db.clone(), sled_db.clone(), None);
+    let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
+    let escrow_db = Arc::new(EscrowDb::new(escrow_root.path())?);
+    let ooo_escrow = Arc::new(
+        OutOfOrderEscrow::new(
+            events_db.clone(),
+            sled_db.clone(),
+            escrow_db.clone(),
+            Duration::from_secs(1),
+        )
+        .with_clock(clock.clone()),
+    );
+    processor.register_observer(
+        ooo_escrow.clone(),
+        &[
+            JustNotification::KeyEventAdded,
+            JustNotification::OutOfOrder,
+        ],
+    )?;
+    let id: IdentifierPrefix = "EO8cED9H5XPqBdoVatgBkEuSP8yXic7HtWpkex-9e0sL".parse()?;
+
+    // Escrowed event is found by digest.
+    processor.process(&ev1)?;
+    processor.process(&ev3)?;
+    assert_eq!(
+        ooo_escrow.get_event_by_sn_and_digest(2, &id, &ev3_digest),
+        Some(signed_ev3.clone())
+    );
+    assert!(ooo_escrow
+        .get_event_by_sn_and_digest(1, &id, &ev3_digest)
+        .is_none());
+
+    // Event accepted into KEL is removed from index.
+    processor.process(&ev2)?;
+    assert_eq!(
+        EventStorage::new(events_db, sled_db)
+            .get_state(&id)
+            .unwrap()
+            .sn,
+        2
+    );
+    assert!(ooo_escrow
+        .get_event_by_sn_and_digest(2, &id, &ev3_digest)
+        .is_none());
+
+    // Index is built from events escrowed before it was enabled.
+    Escrow::new(b"test", Duration::from_secs(1), escrow_db.clone())
+        .with_clock(clock.clone())
+        .add(&id, signed_ev3.clone())?;
+    let indexed: Escrow<SignedEventMessage> =
+        Escrow::new(b"test", Duration::from_secs(1), escrow_db.clone())
+            .with_clock(clock.clone())
+            .with_digest_index(|event| event.event_message.digest().ok());
+    assert_eq!(
+        indexed
+            .get_by_digest(&ev3_digest)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![signed_ev3.clone()]
+    );
+
+    // Events are found only for identifier they were escrowed for.
+    let other_id: IdentifierPrefix = "EDBBxc3_cczsEld6szaFdmhR3JyOhnYaDCCdo_wDe95p".parse()?;
+    assert_eq!(
+        indexed
+            .get_by_id_and_digest(&id, &ev3_digest)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        vec![signed_ev3.clone()]
+    );
+    assert!(indexed
+        .get_by_id_and_digest(&other_id, &ev3_digest)
+        .unwrap()
+        .next()
+        .is_none());
+
+    // Stale events aren't returned.
+    clock.advance(Duration::from_secs(1));
+    assert!(indexed.get_by_digest(&ev3_digest).unwrap().next().is_none());
+
+    // Index is rebuilt when escrow is opened, so events removed without
+    // updating index aren't returned.
+    let open_indexed = || -> Escrow<SignedEventMessage> {
+        Escrow::new(b"test2", Duration::from_secs(1), escrow_db.clone())
+            .with_clock(clock.clone())
+            .with_digest_index(|event| event.event_message.digest().ok())
+    };
+    open_indexed().add(&id, signed_ev3.clone())?;
+    Escrow::<SignedEventMessage>::new(b"test2", Duration::from_secs(1), escrow_db.clone())
+        .remove(&id, &signed_ev3)?;
+    assert!(open_indexed()
+        .get_by_digest(&ev3_digest)
+        .unwrap()
+        .next()
+        .is_none());
+
+    Ok(())
+}
+
 #[test]
 fn test_partially_sign_escrow_cleanup() -> Result<(), Error> {
     use tempfile::Builder;
 
     // events from keripy/tests/core/test_escrow.py::test_partial_signed_escrow
+    let clock = Arc::new(ManualClock::default());
     let (processor, storage, ps_escrow) = {
         let witness_root = Builder::new().prefix("test-db").tempdir().unwrap();
         let path = witness_root.path();
@@ -824,12 +954,15 @@ fn test_partially_sign_escrow_cleanup() -> Result<(), Error> {
         // Register partially signed escrow, to save and reprocess partially signed events
         let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
         let escrow_db = Arc::new(EscrowDb::new(escrow_root.path())?);
-        let ps_escrow = Arc::new(PartiallySignedEscrow::new(
-            events_db.clone(),
-            witness_db.clone(),
-            escrow_db,
-            Duration::from_secs(1),
-        ));
+        let ps_escrow = Arc::new(
+            PartiallySignedEscrow::new(
+                events_db.clone(),
+                witness_db.clone(),
+                escrow_db,
+                Duration::from_secs(1),
+            )
+            .with_clock(clock.clone()),
+        );
         processor.register_observer(ps_escrow.clone(), &[JustNotification::PartiallySigned])?;
 
         (
@@ -871,8 +1004,8 @@ fn test_partially_sign_escrow_cleanup() -> Result<(), Error> {
     // check if event was accepted into kel
     assert_eq!(storage.get_state(&id), None);
 
-    // Wait until escrowed events become stale.
-    thread::sleep(Duration::from_secs(1));
+    // Move time forward until escrowed events become stale.
+    clock.advance(Duration::from_secs(1));
 
     // Check if stale event was removed
     let mut escrowed = ps_escrow
@@ -918,12 +1051,16 @@ pub fn test_partially_witnessed_escrow_cleanup() -> Result<(), Error> {
     // Register not fully witnessed escrow, to save and reprocess events
     let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
     let escrow_db = Arc::new(EscrowDb::new(escrow_root.path())?);
-    let partially_witnessed_escrow = Arc::new(PartiallyWitnessedEscrow::new(
-        events_db.clone(),
-        db.clone(),
-        escrow_db,
-        Duration::from_secs(1),
-    ));
+    let clock = Arc::new(ManualClock::default());
+    let partially_witnessed_escrow = Arc::new(
+        PartiallyWitnessedEscrow::new(
+            events_db.clone(),
+            db.clone(),
+            escrow_db,
+            Duration::from_secs(1),
+        )
+        .with_clock(clock.clone()),
+    );
     event_processor.register_observer(
         partially_witnessed_escrow.clone(),
         &[
@@ -972,8 +1109,8 @@ pub fn test_partially_witnessed_escrow_cleanup() -> Result<(), Error> {
     let state = event_storage.get_state(&id);
     assert_eq!(state, None);
 
-    // Wait until escrowed events become stale.
-    sleep(Duration::from_secs(1));
+    // Move time forward until escrowed events become stale.
+    clock.advance(Duration::from_secs(1));
 
     // check if icp still in escrow
     let mut esc = partially_witnessed_escrow
@@ -1006,12 +1143,16 @@ pub fn test_nt_receipt_escrow_cleanup() -> Result<(), Error> {
     // Register not fully witnessed escrow, to save and reprocess events
     let escrow_root = Builder::new().prefix("test-db-escrow").tempdir().unwrap();
     let escrow_db = Arc::new(EscrowDb::new(escrow_root.path())?);
-    let partially_witnessed_escrow = Arc::new(PartiallyWitnessedEscrow::new(
-        events_db.clone(),
-        db.clone(),
-        escrow_db,
-        Duration::from_secs(1),
-    ));
+    let clock = Arc::new(ManualClock::default());
+    let partially_witnessed_escrow = Arc::new(
+        PartiallyWitnessedEscrow::new(
+            events_db.clone(),
+            db.clone(),
+            escrow_db,
+            Duration::from_secs(1),
+        )
+        .with_clock(clock.clone()),
+    );
     event_processor.register_observer(
         partially_witnessed_escrow.clone(),
         &[
@@ -1043,8 +1184,8 @@ pub fn test_nt_receipt_escrow_cleanup() -> Result<(), Error> {
     let state = event_storage.get_state(&id);
     assert_eq!(state, None);
 
-    // Wait until receipt become stale
-    thread::sleep(Duration::from_secs(1));
+    // Move time forward until receipt become stale
+    clock.advance(Duration::from_secs(1));
 
     // Check escrow. Old receipt should be removed because it is stale.
     let mut esc = partially_witnessed_escrow
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Local};
+
 use super::compute_state;
 #[cfg(feature = "query")]
 use crate::query::{
@@ -7,6 +9,7 @@ use crate::query::{
 };
 use crate::{
     actor::prelude::Message,
+    clock::{system_clock, Clock},
     database::{
         sled::SledEventDatabase,
         timestamped::{Timestamped, TimestampedSignedEventMessage},
@@ -14,11 +17,12 @@ use crate::{
     error::Error,
     event::{
         event_data::EventData,
+        receipt::Receipt,
         sections::{seal::EventSeal, KeyConfig},
     },
     event_message::{
         signature::Transferable,
-        signed_event_message::{Notice, SignedNontransferableReceipt},
+        signed_event_message::{Notice, SignedNontransferableReceipt, SignedTransferableReceipt},
     },
     prefix::{BasicPrefix, IdentifierPrefix},
     state::{EventSemantics, IdentifierState},
@@ -31,11 +35,121 @@ use said::version::format::SerializationFormats;
 use said::SelfAddressingIdentifier;
 
 #[cfg(feature = "mailbox")]
-use crate::mailbox::MailboxResponse;
+use crate::mailbox::{MailboxResponse, MailboxUsage, MultisigMetadata};
 
 pub struct EventStorage<D: EventDatabase> {
     pub events_db: Arc<D>,
     pub escrow_db: Arc<SledEventDatabase>,
+    /// Clock used for timestamping key state notices.
+    pub clock: Arc<dyn Clock>,
+}
+
+impl<D: EventDatabase> Clone for EventStorage<D> {
+    fn clone(&self) -> Self {
+        Self {
+            events_db: self.events_db.clone(),
+            escrow_db: self.escrow_db.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+/// Number of events loaded from database at once by [`KelStream`].
+const KEL_STREAM_CHUNK_SIZE: u64 = 100;
+
+/// Iterator over identifier's KEL returned by [`EventStorage::get_kel_stream`].
+/// Events are loaded from database in chunks, so the whole KEL is never kept
+/// in memory.
+pub struct KelStream<D: EventDatabase> {
+    storage: EventStorage<D>,
+    id: IdentifierPrefix,
+    next_sn: u64,
+    buffer: std::collections::VecDeque<SignedEventMessage>,
+    finished: bool,
+}
+
+/// Accepted event with receipts stored for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KelEntry {
+    pub event: SignedEventMessage,
+    /// Witness receipts of the event, with all stored couplets and indexed
+    /// signatures merged.
+    pub receipts: Option<SignedNontransferableReceipt>,
+    pub trans_receipts: Vec<SignedTransferableReceipt>,
+}
+
+impl From<KelEntry> for Vec<Notice> {
+    fn from(entry: KelEntry) -> Self {
+        std::iter::once(Notice::Event(entry.event))
+            .chain(entry.receipts.map(Notice::NontransferableRct))
+            .chain(
+                entry
+                    .trans_receipts
+                    .into_iter()
+                    .map(Notice::TransferableRct),
+            )
+            .collect()
+    }
+}
+
+impl<D: EventDatabase> KelStream<D> {
+    /// Returns events with their nontransferable and transferable receipts.
+    pub fn with_typed_receipts(self) -> impl Iterator<Item = Result<KelEntry, Error>> {
+        let storage = self.storage.clone();
+        self.map(move |event| {
+            let event = event?;
+            let data = &event.event_message.data;
+            let receipts = storage.get_nt_receipts(&data.get_prefix(), data.get_sn())?;
+            let trans_receipts = storage.try_get_trans_receipts(&event)?;
+            Ok(KelEntry {
+                event,
+                receipts,
+                trans_receipts,
+            })
+        })
+    }
+
+    /// Returns events interleaved with their nontransferable and
+    /// transferable receipts, in the same order as
+    /// [`EventStorage::get_kel_messages_with_receipts_all`].
+    pub fn with_receipts(self) -> impl Iterator<Item = Result<Notice, Error>> {
+        self.with_typed_receipts()
+            .flat_map(|entry| -> Vec<Result<Notice, Error>> {
+                match entry {
+                    Ok(entry) => Vec::<Notice>::from(entry).into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                }
+            })
+    }
+}
+
+impl<D: EventDatabase> Iterator for KelStream<D> {
+    type Item = Result<SignedEventMessage, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.finished {
+            let chunk =
+                self.storage
+                    .events_db
+                    .try_get_kel_finalized_events(QueryParameters::Range {
+                        id: self.id.clone(),
+                        start: self.next_sn,
+                        limit: KEL_STREAM_CHUNK_SIZE,
+                    });
+            let chunk = match chunk {
+                Ok(chunk) => chunk.unwrap_or_default(),
+                Err(_) => {
+                    self.finished = true;
+                    return Some(Err(Error::DbError));
+                }
+            };
+            self.finished = (chunk.len() as u64) < KEL_STREAM_CHUNK_SIZE;
+            self.next_sn += KEL_STREAM_CHUNK_SIZE;
+            self.buffer
+                .extend(chunk.into_iter().map(|event| event.signed_event_message));
+        }
+        self.buffer.pop_front().map(Ok)
+    }
 }
 
 // Collection of methods for getting data from database.
@@ -44,9 +158,15 @@ impl<D: EventDatabase> EventStorage<D> {
         Self {
             escrow_db,
             events_db,
+            clock: system_clock(),
         }
     }
 
+    /// Sets clock used for timestamping key state notices.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
     pub fn get_state(&self, identifier: &IdentifierPrefix) -> Option<IdentifierState> {
         compute_state(self.events_db.clone(), identifier)
     }
@@ -56,15 +176,14 @@ impl<D: EventDatabase> EventStorage<D> {
     /// Returns serialized in CESR current validated KEL for a given Prefix
     pub fn get_kel(&self, id: &IdentifierPrefix) -> Result<Option<Vec<u8>>, Error> {
         let kel = self.get_kel_messages(id)?;
-        Ok(kel.map(|events| {
+        kel.map(|events| {
             events
                 .into_iter()
-                .map(|event| Message::Notice(event).to_cesr().unwrap_or_default())
-                .fold(vec![], |mut accum, serialized_event| {
-                    accum.extend(serialized_event);
-                    accum
-                })
-        }))
+                .map(|event| Message::Notice(event).to_cesr())
+                .collect::<Result<Vec<_>, _>>()
+                .map(|events| events.concat())
+        })
+        .transpose()
     }
 
     /// Get KERL for Prefix
@@ -73,10 +192,12 @@ impl<D: EventDatabase> EventStorage<D> {
     pub fn get_kel_messages(&self, id: &IdentifierPrefix) -> Result<Option<Vec<Notice>>, Error> {
         match self
             .events_db
-            .get_kel_finalized_events(QueryParameters::All { id })
+            .try_get_kel_finalized_events(QueryParameters::All { id })
+            .map_err(|_| Error::DbError)?
         {
             Some(events) => Ok(Some(
                 events
+                    .into_iter()
                     .map(|event| Notice::Event(event.signed_event_message))
                     .collect(),
             )),
@@ -84,17 +205,37 @@ impl<D: EventDatabase> EventStorage<D> {
         }
     }
 
+    /// Returns iterator over KEL of identifier, that loads events from
+    /// database lazily. Prefer it over [`EventStorage::get_kel`] for long
+    /// KELs. Iterator is empty if identifier is unknown.
+    pub fn get_kel_stream(&self, id: &IdentifierPrefix) -> KelStream<D> {
+        KelStream {
+            storage: self.clone(),
+            id: id.clone(),
+            next_sn: 0,
+            buffer: Default::default(),
+            finished: false,
+        }
+    }
+
+    /// Returns accepted events of identifier with their receipts. Result is
+    /// empty if identifier is unknown.
+    pub fn get_kel_typed(&self, id: &IdentifierPrefix) -> Result<Vec<KelEntry>, Error> {
+        self.get_kel_stream(id).with_typed_receipts().collect()
+    }
+
     pub fn get_kel_messages_with_receipts_all(
         &self,
         id: &IdentifierPrefix,
     ) -> Result<Option<Vec<Notice>>, Error> {
         let events = self
             .events_db
-            .get_kel_finalized_events(QueryParameters::All { id });
-        Ok(match events {
-            None => None,
+            .try_get_kel_finalized_events(QueryParameters::All { id })
+            .map_err(|_| Error::DbError)?;
+        match events {
+            None => Ok(None),
             Some(events) => self.collect_with_receipts(events),
-        })
+        }
     }
 
     pub fn get_kel_messages_with_receipts_range(
@@ -105,40 +246,53 @@ impl<D: EventDatabase> EventStorage<D> {
     ) -> Result<Option<Vec<Notice>>, Error> {
         let events = self
             .events_db
-            .get_kel_finalized_events(QueryParameters::Range {
+            .try_get_kel_finalized_events(QueryParameters::Range {
                 id: id.clone(),
                 start: sn,
                 limit,
             })
-            .and_then(|events| self.collect_with_receipts(events));
-        Ok(events)
+            .map_err(|_| Error::DbError)?;
+        match events {
+            None => Ok(None),
+            Some(events) => self.collect_with_receipts(events),
+        }
     }
 
-    fn collect_with_receipts<'a, I>(&self, events: I) -> Option<Vec<Notice>>
+    /// Returns events of identifier first seen between `from` (inclusive)
+    /// and `to` (exclusive), with their receipts. Returns `None` if events
+    /// database doesn't index events by first seen time.
+    pub fn get_kel_messages_with_receipts_between(
+        &self,
+        id: &IdentifierPrefix,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Result<Option<Vec<Notice>>, Error> {
+        match self
+            .events_db
+            .get_kel_finalized_events_between(id, from, to)
+        {
+            Some(events) => Ok(Some(
+                self.collect_with_receipts(events)?.unwrap_or_default(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn collect_with_receipts<'a, I>(&self, events: I) -> Result<Option<Vec<Notice>>, Error>
     where
         I: IntoIterator<Item = Timestamped<SignedEventMessage>>,
     {
-        let evs: Vec<_> = events
-            .into_iter()
-            .flat_map(|event: Timestamped<SignedEventMessage>| {
-                let rcts_from_db = self
-                    .get_nt_receipts(
-                        &event.signed_event_message.event_message.data.get_prefix(),
-                        event.signed_event_message.event_message.data.get_sn(),
-                    )
-                    .unwrap()
-                    .map(Notice::NontransferableRct);
-                match rcts_from_db {
-                    Some(rct) => vec![Notice::Event(event.signed_event_message), rct],
-                    None => vec![Notice::Event(event.into())],
-                }
-            })
-            .collect();
-        if evs.is_empty() {
-            None
-        } else {
-            Some(evs)
+        let mut evs = vec![];
+        for event in events {
+            let event = event.signed_event_message;
+            let data = &event.event_message.data;
+            let rcts_from_db = self.get_nt_receipts(&data.get_prefix(), data.get_sn())?;
+            let trans_rcts = self.try_get_trans_receipts(&event)?;
+            evs.push(Notice::Event(event));
+            evs.extend(rcts_from_db.map(Notice::NontransferableRct));
+            evs.extend(trans_rcts.into_iter().map(Notice::TransferableRct));
         }
+        Ok(if evs.is_empty() { None } else { Some(evs) })
     }
 
     pub fn get_event_at_sn(
@@ -168,6 +322,18 @@ impl<D: EventDatabase> EventStorage<D> {
         Ok(())
     }
 
+    #[cfg(feature = "mailbox")]
+    pub fn add_mailbox_multisig_metadata(
+        &self,
+        receipient: &IdentifierPrefix,
+        metadata: MultisigMetadata,
+    ) -> Result<(), Error> {
+        self.escrow_db
+            .add_mailbox_multisig_metadata(metadata, receipient)?;
+
+        Ok(())
+    }
+
     #[cfg(feature = "mailbox")]
     pub fn add_mailbox_delegate(
         &self,
@@ -199,39 +365,130 @@ impl<D: EventDatabase> EventStorage<D> {
     #[cfg(feature = "mailbox")]
     pub fn get_mailbox_messages(&self, args: &QueryArgsMbx) -> Result<MailboxResponse, Error> {
         let id = args.i.clone();
+        let topics = self.mailbox_start(args)?;
+        // Evicted messages aren't stored anymore, so positions of remaining
+        // ones are shifted from their topic indexes.
+        let evicted = self.escrow_db.get_mailbox_evicted(&id)?;
 
         // query receipts
         let receipt = self
             .escrow_db
             .get_mailbox_receipts(&id)
-            .map(|it| it.skip(args.topics.receipt).collect())
+            .map(|it| it.skip(topics.receipt - evicted.receipt).collect())
             .unwrap_or_default();
 
-        let multisig = self
+        let multisig: Vec<SignedEventMessage> = self
             .escrow_db
             .get_mailbox_multisig(&id)
             .map(|it| {
-                it.skip(args.topics.multisig)
+                it.skip(topics.multisig - evicted.multisig)
                     .map(|ev| ev.signed_event_message)
                     .collect()
             })
             .unwrap_or_default();
 
+        let multisig_metadata = self
+            .escrow_db
+            .get_mailbox_multisig_metadata(&id)
+            .map(|it| {
+                it.filter(|md| {
+                    multisig
+                        .iter()
+                        .any(|ev| ev.event_message.digest().ok().as_ref() == Some(&md.event_digest))
+                })
+                .collect()
+            })
+            .unwrap_or_default();
+
         let delegate = self
             .escrow_db
             .get_mailbox_delegate(&id)
             .map(|it| {
-                it.skip(args.topics.delegate)
+                it.skip(topics.delegate - evicted.delegate)
                     .map(|ev| ev.signed_event_message)
                     .collect()
             })
             .unwrap_or_default();
 
         // TODO: query and return the rest of topics
-        Ok(MailboxResponse {
+        let response = MailboxResponse {
             receipt,
             multisig,
             delegate,
+            multisig_metadata,
+            next: None,
+        };
+        Ok(match args.limit {
+            Some(limit) => response.limit_count(limit, &topics),
+            None => response,
+        })
+    }
+
+    /// Returns topic indexes from which mailbox messages should be returned.
+    /// If query asks to resume, indexes acknowledged by querying identifier
+    /// are taken into account. Indexes never point before evicted messages.
+    #[cfg(feature = "mailbox")]
+    pub fn mailbox_start(
+        &self,
+        args: &QueryArgsMbx,
+    ) -> Result<crate::query::mailbox::QueryTopics, Error> {
+        let requested = match self.escrow_db.get_mailbox_cursor(&args.i, &args.pre)? {
+            Some(acknowledged) if args.resume => args.topics.furthest(&acknowledged),
+            _ => args.topics.clone(),
+        };
+        Ok(requested.furthest(&self.escrow_db.get_mailbox_evicted(&args.i)?))
+    }
+
+    /// Saves topic indexes of `args` as acknowledged by querying identifier,
+    /// so messages before them aren't returned anymore for queries that
+    /// resume. Cursor never moves back. Messages acknowledged by all
+    /// recipients that acknowledge `args.i` mailbox are removed.
+    #[cfg(feature = "mailbox")]
+    pub fn acknowledge_mailbox(&self, args: &QueryArgsMbx) -> Result<(), Error> {
+        let topics = match self.escrow_db.get_mailbox_cursor(&args.i, &args.pre)? {
+            Some(acknowledged) => args.topics.furthest(&acknowledged),
+            None => args.topics.clone(),
+        };
+        self.escrow_db
+            .set_mailbox_cursor(&args.i, &args.pre, topics)?;
+        Ok(())
+    }
+
+    /// Removes all messages from `id` mailbox.
+    #[cfg(feature = "mailbox")]
+    pub fn clear_mailbox(&self, id: &IdentifierPrefix) -> Result<(), Error> {
+        self.escrow_db.clear_mailbox(id)?;
+        Ok(())
+    }
+
+    /// Removes multisig and delegate messages forwarded to `id` longer than
+    /// `max_age` ago. Returns number of removed messages.
+    #[cfg(feature = "mailbox")]
+    pub fn expire_mailbox(
+        &self,
+        id: &IdentifierPrefix,
+        max_age: std::time::Duration,
+    ) -> Result<usize, Error> {
+        Ok(self.escrow_db.expire_mailbox_forwards(id, max_age)?)
+    }
+
+    /// Returns number and size of multisig and delegate messages forwarded
+    /// to `id` and still stored in its mailbox.
+    #[cfg(feature = "mailbox")]
+    pub fn mailbox_usage(&self, id: &IdentifierPrefix) -> Result<MailboxUsage, Error> {
+        let forwarded = self
+            .escrow_db
+            .get_mailbox_multisig(id)
+            .into_iter()
+            .flatten()
+            .chain(
+                self.escrow_db
+                    .get_mailbox_delegate(id)
+                    .into_iter()
+                    .flatten(),
+            );
+        forwarded.try_fold(MailboxUsage::default(), |usage, msg| -> Result<_, Error> {
+            Ok(usage.with(msg.signed_event_message.event_message.encode()?.len()))
         })
     }
 
@@ -286,7 +543,16 @@ impl<D: EventDatabase> EventStorage<D> {
         id: &IdentifierPrefix,
         sn: u64,
     ) -> Result<Option<IdentifierState>, Error> {
-        let mut state = IdentifierState::default();
+        let mut state = match self
+            .events_db
+            .get_pruned_state(id)
+            .map_err(|_| Error::DbError)?
+        {
+            // State of pruned events isn't known.
+            Some(pruned) if pruned.sn > sn => return Err(Error::MissingEvent),
+            Some(pruned) => pruned,
+            None => IdentifierState::default(),
+        };
         if let Some(events) = self
             .events_db
             .get_kel_finalized_events(QueryParameters::Range {
@@ -377,17 +643,38 @@ impl<D: EventDatabase> EventStorage<D> {
         sn: u64,
         // digest: &SelfAddressingIdentifier,
     ) -> Result<Option<SignedNontransferableReceipt>, Error> {
-        match self
+        let receipts = self
             .events_db
-            .get_receipts_nt(QueryParameters::BySn { id: id.clone(), sn })
-        {
-            Some(mut events) => {
-                let sigs = events.next();
-                // let body = Receipt::new(SerializationFormats::JSON, digest.clone(), id.clone(), sn);
-                Ok(sigs)
-            }
-            None => Ok(None),
-        }
+            .try_get_receipts_nt(QueryParameters::BySn { id: id.clone(), sn })
+            .map_err(|_| Error::DbError)?;
+        Ok(receipts.into_iter().next())
+    }
+
+    /// Returns transferable receipts of provided event, made by validators.
+    pub fn get_trans_receipts(&self, event: &SignedEventMessage) -> Vec<SignedTransferableReceipt> {
+        self.try_get_trans_receipts(event).unwrap_or_default()
+    }
+
+    /// Like [`EventStorage::get_trans_receipts`], but returns database
+    /// error instead of empty list.
+    pub fn try_get_trans_receipts(
+        &self,
+        event: &SignedEventMessage,
+    ) -> Result<Vec<SignedTransferableReceipt>, Error> {
+        let id = event.event_message.data.get_prefix();
+        let sn = event.event_message.data.get_sn();
+        let digest = event.event_message.digest()?;
+        let receipts = self
+            .events_db
+            .try_get_receipts_t(QueryParameters::BySn { id: id.clone(), sn })
+            .map_err(|_| Error::DbError)?;
+        Ok(receipts
+            .into_iter()
+            .map(|Transferable::Seal(seal, sigs)| {
+                let body = Receipt::new(SerializationFormats::JSON, digest.clone(), id.clone(), sn);
+                SignedTransferableReceipt::new(body, seal, sigs)
+            })
+            .collect())
     }
 
     #[cfg(feature = "query")]
@@ -475,6 +762,10 @@ impl<D: EventDatabase> EventStorage<D> {
         let state = self
             .get_state(prefix)
             .ok_or_else(|| Error::SemanticError("No state in db".into()))?;
-        Ok(KeyStateNotice::new_ksn(state, format))
+        Ok(KeyStateNotice::new_ksn(
+            state,
+            format,
+            self.clock.now().into(),
+        ))
     }
 }
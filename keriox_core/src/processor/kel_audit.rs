@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+
+use super::event_storage::EventStorage;
+use crate::{
+    database::EventDatabase,
+    error::Error,
+    event::event_data::EventData,
+    event_message::{signature::Nontransferable, signed_event_message::SignedEventMessage},
+    prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature},
+    state::{EventSemantics, IdentifierState},
+};
+
+/// Reason why stored event couldn't be verified during KEL replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DivergenceReason {
+    /// Events aren't stored in consecutive sn order.
+    UnexpectedSn { expected: u64 },
+    /// Event digest doesn't match its content.
+    IncorrectDigest,
+    /// Prior event digest doesn't match digest of previous event in KEL.
+    BrokenChain,
+    /// Event can't be applied to state computed from preceding events.
+    InvalidEvent(String),
+    /// Signatures don't satisfy current signing threshold, or in case of
+    /// rotation, previous next keys threshold.
+    SignatureThreshold,
+    /// Receipt signature of witness is invalid.
+    InvalidReceipt(BasicPrefix),
+    /// Valid witness receipts don't satisfy witness threshold.
+    ReceiptQuorum,
+}
+
+/// Stored event that failed verification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub sn: u64,
+    pub reason: DivergenceReason,
+}
+
+/// Result of [`EventStorage::verify_kel_integrity`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub id: IdentifierPrefix,
+    /// Number of events verified successfully before divergence was found.
+    pub verified_events: u64,
+    /// First event that failed verification. Events after it aren't checked.
+    pub divergence: Option<Divergence>,
+}
+
+impl AuditReport {
+    pub fn is_valid(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+impl<D: EventDatabase> EventStorage<D> {
+    /// Replays stored KEL of identifier from inception, verifying again
+    /// digest chain, signatures and witness receipts of every event. Stops
+    /// at the first event that fails verification. Useful after restoring
+    /// database from backup or after migration. Delegator seals aren't
+    /// checked.
+    pub fn verify_kel_integrity(&self, id: &IdentifierPrefix) -> Result<AuditReport, Error> {
+        let mut state = IdentifierState::default();
+        let mut verified_events = 0;
+        let mut divergence = None;
+        for event in self.get_kel_stream(id) {
+            let event = event?;
+            let sn = event.event_message.data.get_sn();
+            match self.verify_event(&state, &event, verified_events)? {
+                Ok(new_state) => {
+                    state = new_state;
+                    verified_events += 1;
+                }
+                Err(reason) => {
+                    divergence = Some(Divergence { sn, reason });
+                    break;
+                }
+            }
+        }
+        Ok(AuditReport {
+            id: id.clone(),
+            verified_events,
+            divergence,
+        })
+    }
+
+    /// Returns state after applying `event`, or reason why it can't be
+    /// applied. Outer error means that data couldn't be read from database.
+    fn verify_event(
+        &self,
+        state: &IdentifierState,
+        event: &SignedEventMessage,
+        expected_sn: u64,
+    ) -> Result<Result<IdentifierState, DivergenceReason>, Error> {
+        let message = &event.event_message;
+        if message.data.get_sn() != expected_sn {
+            return Ok(Err(DivergenceReason::UnexpectedSn {
+                expected: expected_sn,
+            }));
+        }
+        if message.check_digest().is_err() {
+            return Ok(Err(DivergenceReason::IncorrectDigest));
+        }
+        let prior_digest = match message.data.get_event_data() {
+            EventData::Rot(rot) | EventData::Drt(rot) => Some(rot.previous_event_hash().clone()),
+            EventData::Ixn(ixn) => Some(ixn.previous_event_hash().clone()),
+            EventData::Icp(_) | EventData::Dip(_) => None,
+        };
+        if prior_digest.is_some_and(|prior| prior != state.last_event_digest) {
+            return Ok(Err(DivergenceReason::BrokenChain));
+        }
+        let new_state = match message.apply_to(state.clone()) {
+            Ok(new_state) => new_state,
+            Err(e) => return Ok(Err(DivergenceReason::InvalidEvent(e.to_string()))),
+        };
+
+        // Signatures.
+        if let EventData::Rot(rot) | EventData::Drt(rot) = message.data.get_event_data() {
+            if state
+                .current
+                .next_keys_data
+                .check_threshold(
+                    &rot.key_config.public_keys,
+                    event.signatures.iter().map(|sig| &sig.index),
+                )
+                .is_err()
+            {
+                return Ok(Err(DivergenceReason::SignatureThreshold));
+            }
+        }
+        let serialized = message.encode()?;
+        if !new_state
+            .current
+            .verify(&serialized, &event.signatures)
+            .unwrap_or(false)
+        {
+            return Ok(Err(DivergenceReason::SignatureThreshold));
+        }
+
+        // Witness receipts.
+        let witnesses = &new_state.witness_config.witnesses;
+        let digest = message.digest()?;
+        let mut receipted = vec![];
+        if let Some(rct) = self.get_nt_receipts(&message.data.get_prefix(), expected_sn)? {
+            if rct.body.receipted_event_digest == digest {
+                for sig in rct.signatures {
+                    match sig {
+                        Nontransferable::Couplet(couplets) => receipted.extend(couplets),
+                        Nontransferable::Indexed(indexed) => {
+                            receipted.extend(indexed.into_iter().filter_map(|sig| {
+                                witnesses
+                                    .get(sig.index.current() as usize)
+                                    .map(|wit| (wit.clone(), sig.signature))
+                            }))
+                        }
+                    }
+                }
+            }
+        }
+        for (witness, signature) in &receipted {
+            if !witness.verify(&serialized, signature).unwrap_or(false) {
+                return Ok(Err(DivergenceReason::InvalidReceipt(witness.clone())));
+            }
+        }
+        if !new_state
+            .witness_config
+            .enough_receipts(receipted, Vec::<IndexedSignature>::new())?
+        {
+            return Ok(Err(DivergenceReason::ReceiptQuorum));
+        }
+
+        Ok(Ok(new_state))
+    }
+}
+
+#[test]
+fn test_verify_kel_integrity() -> Result<(), Error> {
+    use std::{convert::TryFrom, sync::Arc};
+
+    use cesrox::parse;
+    use tempfile::{Builder, NamedTempFile};
+
+    use crate::{
+        database::{redb::RedbDatabase, sled::SledEventDatabase},
+        event_message::signed_event_message::{Message, Notice},
+        processor::{basic_processor::BasicProcessor, Processor},
+    };
+
+    // Events from keripy `test_multisig_digprefix` test.
+    let icp_raw = br#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}-AADAAD4SyJSYlsQG22MGXzRGz2PTMqpkgOyUfq7cS99sC2BCWwdVmEMKiTEeWe5kv-l_d9auxdadQuArLtAGEArW8wEABD0z_vQmFImZXfdR-0lclcpZFfkJJJNXDcUNrf7a-mGsxNLprJo-LROwDkH5m7tVrb-a1jcor2dHD9Jez-r4bQIACBFeU05ywfZycLdR0FxCvAR9BfV9im8tWe1DglezqJLf-vHRQSChY1KafbYNc96hYYpbuN90WzuCRMgV8KgRsEC"#;
+    let rot_raw = br#"{"v":"KERI10JSON00021c_","t":"rot","d":"EHjzZj4i_-RpTN2Yh-NocajFROJ_GkBtlByhRykqiXgz","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"1","p":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","kt":"2","k":["DCjxOXniUc5EUzDqERlXdptfKPHy6jNo_ZGsS4Vd8fAE","DNZHARO4dCJlluv0qezEMRmErIWWc-lzOzolBOQ15tHV","DOCQ4KN1jUlKbfjRteDYt9fxgpq1NK9_MqO5IA7shpED"],"nt":"2","n":["EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m","EATiZAHl0kzKID6faaQP2O7zB3Hj7eH3bE-vgKVAtsyU","EG6e7dJhh78ZqeIZ-eMbe-OB3TwFMPmrSsh9k75XIjLP"],"bt":"0","br":[],"ba":[],"a":[]}-AADAAAqV6xpsAAEB_FJP5UdYO5qiJphz8cqXbTjB9SRy8V0wIim-lgafF4o-b7TW0spZtzx2RXUfZLQQCIKZsw99k8AABBP8nfF3t6bf4z7eNoBgUJR-hdhw7wnlljMZkeY5j2KFRI_s8wqtcOFx1A913xarGJlO6UfrqFWo53e9zcD8egIACB8DKLMZcCGICuk98RCEVuS0GsqVngi1d-7gAX0jid42qUcR3aiYDMp2wJhqJn-iHJVvtB-LK7TRTggBtMDjuwB"#;
+    let parse_event = |raw: &[u8]| -> SignedEventMessage {
+        match Message::try_from(parse(raw).unwrap().1).unwrap() {
+            Message::Notice(Notice::Event(event)) => event,
+            _ => unreachable!(),
+        }
+    };
+    let icp = parse_event(icp_raw);
+    let rot = parse_event(rot_raw);
+    let id = icp.event_message.data.get_prefix();
+
+    let setup = || {
+        let root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let events_db_path = NamedTempFile::new().unwrap();
+        let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+        let sled_db = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+        (root, events_db_path, events_db, sled_db)
+    };
+
+    // KEL accepted by processor is valid.
+    let (_root, _path, events_db, sled_db) = setup();
+    let processor = BasicProcessor::new(events_db.clone(), sled_db.clone(), None);
+    processor.process_notice(&Notice::Event(icp.clone()))?;
+    processor.process_notice(&Notice::Event(rot.clone()))?;
+    let storage = EventStorage::new(events_db, sled_db);
+    let report = storage.verify_kel_integrity(&id)?;
+    assert!(report.is_valid());
+    assert_eq!(report.verified_events, 2);
+
+    // Simulate corrupted database: rotation saved with inception signatures.
+    let (_root, _path, events_db, sled_db) = setup();
+    let mut corrupted_rot = rot.clone();
+    corrupted_rot.signatures = icp.signatures.clone();
+    events_db.add_kel_finalized_event(icp, &id).unwrap();
+    events_db
+        .add_kel_finalized_event(corrupted_rot, &id)
+        .unwrap();
+    let storage = EventStorage::new(events_db, sled_db);
+    let report = storage.verify_kel_integrity(&id)?;
+    assert_eq!(report.verified_events, 1);
+    assert_eq!(
+        report.divergence,
+        Some(Divergence {
+            sn: 1,
+            reason: DivergenceReason::SignatureThreshold
+        })
+    );
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ pub mod escrow;
 #[cfg(test)]
 mod escrow_tests;
 pub mod event_storage;
+pub mod kel_audit;
 pub mod notification;
 #[cfg(test)]
 mod processor_tests;
@@ -84,6 +85,24 @@ impl<D: EventDatabase> EventProcessor<D> {
         }
     }
 
+    /// Sets how far in the future timestamps of accepted replies can be.
+    #[cfg(feature = "query")]
+    pub fn with_max_clock_drift(self, max_clock_drift: std::time::Duration) -> Self {
+        Self {
+            validator: self.validator.with_max_clock_drift(max_clock_drift),
+            ..self
+        }
+    }
+
+    /// Sets clock which timestamps of accepted replies are compared with.
+    #[cfg(feature = "query")]
+    pub fn with_clock(self, clock: Arc<dyn crate::clock::Clock>) -> Self {
+        Self {
+            validator: self.validator.with_clock(clock),
+            ..self
+        }
+    }
+
     pub fn register_observer(
         &mut self,
         observer: Arc<dyn Notifier + Send + Sync>,
@@ -200,8 +219,8 @@ pub fn compute_state<D: EventDatabase>(
 ) -> Option<IdentifierState> {
     if let Some(events) = db.get_kel_finalized_events(crate::database::QueryParameters::All { id })
     {
-        // start with empty state
-        let mut state = IdentifierState::default();
+        // start with empty state, or state preceding pruned KEL
+        let mut state = db.get_pruned_state(id).ok().flatten().unwrap_or_default();
         // we sort here to get inception first
         let mut sorted_events = events.collect::<Vec<TimestampedSignedEventMessage>>();
         // TODO why identifier is in database if there are no events for it?
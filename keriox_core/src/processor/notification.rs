@@ -1,4 +1,12 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{sync_channel, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
 
 #[cfg(feature = "query")]
 use crate::query::reply_event::SignedReply;
@@ -33,6 +41,21 @@ impl NotificationBus {
         });
     }
 
+    /// Registers observer that is notified in its own thread, so slow
+    /// observers (e.g. doing I/O) don't block processing. See
+    /// [`AsyncNotifier`].
+    pub fn register_async_observer(
+        &mut self,
+        observer: Arc<dyn Notifier + Send + Sync>,
+        notification: Vec<JustNotification>,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Arc<AsyncNotifier> {
+        let notifier = Arc::new(AsyncNotifier::new(observer, capacity, policy));
+        self.register_observer(notifier.clone(), notification);
+        notifier
+    }
+
     pub fn notify(&self, notification: &Notification) -> Result<(), Error> {
         if let Some(obs) = self.observers.get(&notification.into()) {
             for esc in obs.iter() {
@@ -53,6 +76,84 @@ pub trait Notifier {
     fn notify(&self, notification: &Notification, bus: &NotificationBus) -> Result<(), Error>;
 }
 
+/// Behaviour of [`AsyncNotifier`] when its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    /// Wait until observer takes notification from the queue.
+    Block,
+    /// Drop new notification.
+    Drop,
+}
+
+/// Delivers notifications to wrapped observer in separate thread. They are
+/// queued in bounded channel and delivered in the order they were
+/// published, so notifications about the same identifier don't get
+/// reordered. Notifications emitted by wrapped observer are not dispatched,
+/// so it's meant for observers with side effects like forwarding events,
+/// not for escrows.
+pub struct AsyncNotifier {
+    sender: SyncSender<Notification>,
+    policy: OverflowPolicy,
+    dropped: AtomicUsize,
+    failed: Arc<AtomicUsize>,
+}
+
+impl AsyncNotifier {
+    pub fn new(
+        observer: Arc<dyn Notifier + Send + Sync>,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Self {
+        let (sender, receiver) = sync_channel::<Notification>(capacity);
+        let failed = Arc::new(AtomicUsize::new(0));
+        let worker_failed = failed.clone();
+        // Thread ends when notifier is dropped and queue is drained.
+        thread::spawn(move || {
+            let bus = NotificationBus::new();
+            for notification in receiver {
+                if observer.notify(&notification, &bus).is_err() {
+                    worker_failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        Self {
+            sender,
+            policy,
+            dropped: AtomicUsize::new(0),
+            failed,
+        }
+    }
+
+    /// Number of notifications dropped because queue was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of notifications that wrapped observer failed to handle.
+    pub fn failed(&self) -> usize {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl Notifier for AsyncNotifier {
+    fn notify(&self, notification: &Notification, _bus: &NotificationBus) -> Result<(), Error> {
+        match self.policy {
+            OverflowPolicy::Block => self
+                .sender
+                .send(notification.clone())
+                .map_err(|_e| Error::ObserverStopped),
+            OverflowPolicy::Drop => match self.sender.try_send(notification.clone()) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }
+                Err(TrySendError::Disconnected(_)) => Err(Error::ObserverStopped),
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Notification {
     KeyEventAdded(SignedEventMessage),
@@ -113,3 +214,103 @@ impl From<&Notification> for JustNotification {
         }
     }
 }
+
+#[test]
+fn test_async_notifier() -> Result<(), Error> {
+    use std::{
+        sync::{
+            mpsc::{channel, Receiver, Sender},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    /// Forwards notifications to channel. If gate is set, waits for it
+    /// before forwarding.
+    struct Forwarder {
+        sender: Mutex<Sender<Notification>>,
+        gate: Option<Mutex<Receiver<()>>>,
+    }
+
+    impl Notifier for Forwarder {
+        fn notify(&self, notification: &Notification, _bus: &NotificationBus) -> Result<(), Error> {
+            let sender = self.sender.lock().map_err(|_e| Error::MutexPoisoned)?;
+            sender
+                .send(notification.clone())
+                .map_err(|_e| Error::ObserverStopped)?;
+            if let Some(gate) = &self.gate {
+                gate.lock()
+                    .map_err(|_e| Error::MutexPoisoned)?
+                    .recv()
+                    .map_err(|_e| Error::ObserverStopped)?;
+            }
+            Ok(())
+        }
+    }
+    let timeout = Duration::from_secs(5);
+    let notifications = vec![
+        JustNotification::ReceiptAccepted,
+        JustNotification::ReceiptEscrowed,
+    ];
+
+    // Notifications are delivered in publishing order.
+    let (sender, receiver) = channel();
+    let mut bus = NotificationBus::new();
+    bus.register_async_observer(
+        Arc::new(Forwarder {
+            sender: Mutex::new(sender),
+            gate: None,
+        }),
+        notifications.clone(),
+        2,
+        OverflowPolicy::Block,
+    );
+    let published = vec![
+        Notification::ReceiptAccepted,
+        Notification::ReceiptEscrowed,
+        Notification::ReceiptAccepted,
+        Notification::ReceiptAccepted,
+        Notification::ReceiptEscrowed,
+    ];
+    for notification in &published {
+        bus.notify(notification)?;
+    }
+    let received = published
+        .iter()
+        .map(|_| receiver.recv_timeout(timeout).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(received, published);
+
+    // Notifications that don't fit in the queue are dropped.
+    let (sender, receiver) = channel();
+    let (gate_sender, gate) = channel();
+    let mut bus = NotificationBus::new();
+    let notifier = bus.register_async_observer(
+        Arc::new(Forwarder {
+            sender: Mutex::new(sender),
+            gate: Some(Mutex::new(gate)),
+        }),
+        notifications,
+        1,
+        OverflowPolicy::Drop,
+    );
+    bus.notify(&Notification::ReceiptAccepted)?;
+    // Observer holds the first notification, so second one fills the queue.
+    assert_eq!(
+        receiver.recv_timeout(timeout).unwrap(),
+        Notification::ReceiptAccepted
+    );
+    bus.notify(&Notification::ReceiptEscrowed)?;
+    bus.notify(&Notification::ReceiptAccepted)?;
+    assert_eq!(notifier.dropped(), 1);
+
+    gate_sender.send(()).unwrap();
+    assert_eq!(
+        receiver.recv_timeout(timeout).unwrap(),
+        Notification::ReceiptEscrowed
+    );
+    gate_sender.send(()).unwrap();
+    assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+    Ok(())
+}
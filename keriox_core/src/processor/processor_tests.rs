@@ -327,6 +327,202 @@ fn test_compute_state_at_sn() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_kel_stream() -> Result<(), Error> {
+    use tempfile::Builder;
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let db = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+    let events_db_path = NamedTempFile::new().unwrap();
+    let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+
+    let event_processor = BasicProcessor::new(events_db.clone(), Arc::clone(&db), None);
+    let event_storage = EventStorage::new(Arc::clone(&events_db), Arc::clone(&db));
+
+    let kerl_str = br#"{"v":"KERI10JSON000159_","t":"icp","d":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"0","kt":"1","k":["DIwDbi2Sr1kLZFpsX0Od6Y8ariGVLLjZXxBC5bXEI85e"],"nt":"1","n":["ELhmgZ5JFc-ACs9TJxHMxtcKzQxKXLhlAmUT_sKf1-l7"],"bt":"0","b":["DM73ulUG2_DJyA27DfxBXT5SJ5U3A3c2oeG8Z4bUOgyL"],"c":[],"a":[]}-AABAAAPGpCUdR6EfVWROUjpuTsxg5BIcMnfi7PDciv8VuY9NqZ0ioRoaHxMZue_5ALys86sX4aQzKqm_bID3ZBwlMUP{"v":"KERI10JSON000160_","t":"rot","d":"EBHj01Xvz4yfCnScRh3QgeoE7ntSaVcQwRRQkBTHrHX5","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"1","p":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","kt":"1","k":["DGbzWMG2eMghiXRfbbU_JfCB06R1WPE86nYD1XNFRpsL"],"nt":"1","n":["EJypM7yvZBRF-CXqJcCg5j7syRngnwy6TLdq8pSMP9ct"],"bt":"0","br":[],"ba":[],"a":[]}-AABAADbXBjlIg0SgXHzK7YMp1SasIDrRZ2zBG8Ulqee3GtsOBPXG-LFLpmNSa-5EARl3Jq6hn1wZmtagVX3u-U0qN8C{"v":"KERI10JSON000160_","t":"rot","d":"EJUn-ix3QWTa5dyCYaMnyUMLMrkHNXmJPlM6sPpZm8eo","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"2","p":"EBHj01Xvz4yfCnScRh3QgeoE7ntSaVcQwRRQkBTHrHX5","kt":"1","k":["DNMcalsTFQRW_gr-0uOo-0GYMSMqrDh-RBmQ9k_tfg5x"],"nt":"1","n":["EAk5C3kZzIWylApdvVdTPRmnGxw8AnhluGBtNVZ-MQlj"],"bt":"0","br":[],"ba":[],"a":[]}-AABAADb7X_2Am8I3G9U8_rMiEpjLVW1AqCJpE2Xn1_dy3grzF6BiGS6hkXlkdBE4tKg3panQkAGgGmWOFMa0wIe8cUN{"v":"KERI10JSON000160_","t":"rot","d":"EDYkjQ0T1CDBpqkSmZiuUEBgIhlwq4CNUXw9Z6pRWrRQ","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"3","p":"EJUn-ix3QWTa5dyCYaMnyUMLMrkHNXmJPlM6sPpZm8eo","kt":"1","k":["DGKuTfTIkfsaDGbI_c16ZQ1e_CyC2VCAi5sAgR4Kd-De"],"nt":"1","n":["EDFasM0kFMfgVRV2maR2xEnCT28yr9Cwbjb8AWudLfTB"],"bt":"0","br":[],"ba":[],"a":[]}-AABAAARXXCBpfCrmQ7WmD5WQYjgq--6vYULSMW6RRhXT-lWCe6pDtiP6VqGVO7CQHOF45BN1VfpUIZBjoQMOJxqXREE{"v":"KERI10JSON000160_","t":"rot","d":"EE7l2mmUQVgicVhBbfwHkmzVxeAzYhxDAe2vlZPjJ2Yg","i":"EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf","s":"4","p":"EDYkjQ0T1CDBpqkSmZiuUEBgIhlwq4CNUXw9Z6pRWrRQ","kt":"1","k":["DB-2T6cfJtJp6ZKcTaA31qTZRp8Jh9Xs0RpThQWh6-0X"],"nt":"1","n":["EC2AwY44hG7GbKKjpu39yg9sq_2h80184XPO-v7BBJw8"],"bt":"0","br":[],"ba":[],"a":[]}-AABAADm6yCLOiht10BodxeL8U4gCmZQMFZ6IjYgPaX8xBvNZFb-4Kdk3STrIOm7M2XWQ2V7xyu--VrhI4TExqqjvFcB"#;
+    parse_many(kerl_str)
+        .unwrap()
+        .1
+        .into_iter()
+        .for_each(|event| {
+            event_processor
+                .process(&Message::try_from(event).unwrap())
+                .unwrap();
+        });
+    let id: IdentifierPrefix = "EFb-WY7Ie1WPEgsioZz1CyzwnuCg-C9k2QCNpcUfM5Jf".parse()?;
+
+    let streamed = event_storage
+        .get_kel_stream(&id)
+        .map(|event| event.map(Notice::Event))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(streamed.len(), 5);
+    assert_eq!(Some(streamed), event_storage.get_kel_messages(&id)?);
+
+    let streamed_with_receipts = event_storage
+        .get_kel_stream(&id)
+        .with_receipts()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        Some(streamed_with_receipts),
+        event_storage.get_kel_messages_with_receipts_all(&id)?
+    );
+
+    let typed = event_storage.get_kel_typed(&id)?;
+    assert_eq!(typed.len(), 5);
+    assert!(typed
+        .iter()
+        .all(|entry| entry.receipts.is_none() && entry.trans_receipts.is_empty()));
+    assert_eq!(
+        Some(typed.into_iter().flat_map(Vec::<Notice>::from).collect()),
+        event_storage.get_kel_messages_with_receipts_all(&id)?
+    );
+
+    // Unknown identifier
+    let unknown: IdentifierPrefix = "EBHj01Xvz4yfCnScRh3QgeoE7ntSaVcQwRRQkBTHrHX5".parse()?;
+    assert_eq!(event_storage.get_kel_stream(&unknown).count(), 0);
+    assert!(event_storage.get_kel_typed(&unknown)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_long_kel_stream() -> Result<(), Error> {
+    use tempfile::Builder;
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let db = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+    let events_db_path = NamedTempFile::new().unwrap();
+    let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+    let processor = BasicProcessor::new(events_db.clone(), Arc::clone(&db), None);
+    let storage = EventStorage::new(Arc::clone(&events_db), Arc::clone(&db));
+
+    let signers = setup_signers();
+    let sign = |event: &crate::event_message::msg::KeriEvent<crate::event::KeyEvent>| {
+        let signature = signers[0].sign(event.encode().unwrap()).unwrap();
+        event.sign(
+            vec![IndexedSignature::new_both_same(
+                SelfSigningPrefix::Ed25519Sha512(signature),
+                0,
+            )],
+            None,
+            None,
+        )
+    };
+    let icp = EventMsgBuilder::new(EventTypeTag::Icp)
+        .with_keys(vec![BasicPrefix::Ed25519(signers[0].public_key())])
+        .with_next_keys(vec![BasicPrefix::Ed25519(signers[1].public_key())])
+        .build()?;
+    let id = icp.data.get_prefix();
+    processor.process_notice(&Notice::Event(sign(&icp)))?;
+    // KEL spans few chunks loaded by stream.
+    let mut previous = icp.digest()?;
+    for sn in 1..=250 {
+        let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+            .with_prefix(&id)
+            .with_sn(sn)
+            .with_previous_event(&previous)
+            .build()?;
+        previous = ixn.digest()?;
+        processor.process_notice(&Notice::Event(sign(&ixn)))?;
+    }
+    assert_eq!(storage.get_state(&id).unwrap().sn, 250);
+
+    let streamed = storage.get_kel_stream(&id).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(streamed.len(), 251);
+    assert!(streamed
+        .iter()
+        .enumerate()
+        .all(|(sn, event)| event.event_message.data.get_sn() == sn as u64));
+    assert_eq!(
+        Some(streamed.into_iter().map(Notice::Event).collect()),
+        storage.get_kel_messages(&id)?
+    );
+    assert_eq!(storage.get_kel_typed(&id)?.len(), 251);
+
+    Ok(())
+}
+
+#[test]
+fn test_do_not_delegate() -> Result<(), Error> {
+    use tempfile::Builder;
+
+    use crate::{
+        event::sections::{
+            configuration::ConfigurationTrait,
+            seal::{EventSeal, SourceSeal},
+        },
+        event_message::{msg::KeriEvent, signed_event_message::SignedEventMessage},
+        processor::validator::EventValidator,
+    };
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let db = Arc::new(SledEventDatabase::new(root.path()).unwrap());
+    let events_db_path = NamedTempFile::new().unwrap();
+    let events_db = Arc::new(RedbDatabase::new(events_db_path.path()).unwrap());
+    let processor = BasicProcessor::new(events_db.clone(), Arc::clone(&db), None);
+    let validator = EventValidator::new(Arc::clone(&db), events_db.clone());
+
+    let signers = setup_signers();
+    let sign = |signer: usize,
+                event: &KeriEvent<crate::event::KeyEvent>,
+                delegator_seal: Option<SourceSeal>|
+     -> SignedEventMessage {
+        let signature = signers[signer].sign(event.encode().unwrap()).unwrap();
+        event.sign(
+            vec![IndexedSignature::new_both_same(
+                SelfSigningPrefix::Ed25519Sha512(signature),
+                0,
+            )],
+            None,
+            delegator_seal,
+        )
+    };
+
+    // Delegator anchors delegated inception event in its KEL and returns it
+    // with attached delegator seal.
+    let delegate = |configuration: &[ConfigurationTrait]| -> Result<SignedEventMessage, Error> {
+        let delegator_icp = EventMsgBuilder::new(EventTypeTag::Icp)
+            .with_keys(vec![BasicPrefix::Ed25519(signers[0].public_key())])
+            .with_next_keys(vec![BasicPrefix::Ed25519(signers[1].public_key())])
+            .with_configuration(configuration)
+            .build()?;
+        let delegator = delegator_icp.data.get_prefix();
+        processor.process_notice(&Notice::Event(sign(0, &delegator_icp, None)))?;
+
+        let dip = EventMsgBuilder::new(EventTypeTag::Dip)
+            .with_keys(vec![BasicPrefix::Ed25519(signers[2].public_key())])
+            .with_next_keys(vec![BasicPrefix::Ed25519(signers[3].public_key())])
+            .with_delegator(&delegator)
+            .build()?;
+        let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+            .with_prefix(&delegator)
+            .with_sn(1)
+            .with_previous_event(&delegator_icp.digest()?)
+            .with_seal(vec![EventSeal::new(
+                dip.data.get_prefix(),
+                0,
+                dip.digest()?,
+            )])
+            .build()?;
+        processor.process_notice(&Notice::Event(sign(0, &ixn, None)))?;
+        Ok(sign(2, &dip, Some(SourceSeal::new(1, ixn.digest()?))))
+    };
+
+    let dip = delegate(&[ConfigurationTrait::DoNotDelegate])?;
+    assert!(matches!(
+        validator.validate_event(&dip),
+        Err(Error::ConfigurationTraitViolation(
+            ConfigurationTrait::DoNotDelegate
+        ))
+    ));
+
+    // The same delegation is accepted if delegator allows it.
+    let dip = delegate(&[])?;
+    assert!(validator.validate_event(&dip)?.is_some());
+
+    Ok(())
+}
+
 #[test]
 pub fn test_partial_rotation_simple_threshold() -> Result<(), Error> {
     use tempfile::Builder;
@@ -7,6 +7,8 @@ use thiserror::Error;
 
 use super::event_storage::EventStorage;
 #[cfg(feature = "query")]
+use crate::clock::Clock;
+#[cfg(feature = "query")]
 use crate::query::{key_state_notice::KeyStateNotice, reply_event::SignedReply, QueryError};
 use crate::{
     database::{sled::SledEventDatabase, EventDatabase},
@@ -14,6 +16,7 @@ use crate::{
     event::{
         event_data::EventData,
         sections::{
+            configuration::ConfigurationTrait,
             key_config::SignatureError,
             seal::{EventSeal, Seal},
         },
@@ -58,12 +61,37 @@ pub enum MoreInfoError {
 
 pub struct EventValidator<D: EventDatabase> {
     event_storage: EventStorage<D>,
+    #[cfg(feature = "query")]
+    max_clock_drift: std::time::Duration,
+    #[cfg(feature = "query")]
+    clock: Arc<dyn Clock>,
 }
 
 impl<D: EventDatabase> EventValidator<D> {
     pub fn new(db: Arc<SledEventDatabase>, event_database: Arc<D>) -> Self {
         Self {
             event_storage: EventStorage::new(event_database, db),
+            #[cfg(feature = "query")]
+            max_clock_drift: crate::query::reply_event::DEFAULT_MAX_CLOCK_DRIFT,
+            #[cfg(feature = "query")]
+            clock: crate::clock::system_clock(),
+        }
+    }
+
+    /// Sets clock which timestamps of accepted key state notices are
+    /// compared with.
+    #[cfg(feature = "query")]
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Sets how far in the future timestamps of accepted key state notices
+    /// can be.
+    #[cfg(feature = "query")]
+    pub fn with_max_clock_drift(self, max_clock_drift: std::time::Duration) -> Self {
+        Self {
+            max_clock_drift,
+            ..self
         }
     }
 
@@ -104,6 +132,16 @@ impl<D: EventDatabase> EventValidator<D> {
         )?;
         // If delegated event, check its delegator seal.
         if let Some(seal) = self.get_delegator_seal(signed_event)? {
+            if self
+                .event_storage
+                .get_state(&seal.prefix)
+                .map(|state| state.has_trait(&ConfigurationTrait::DoNotDelegate))
+                .unwrap_or(false)
+            {
+                return Err(Error::ConfigurationTraitViolation(
+                    ConfigurationTrait::DoNotDelegate,
+                ));
+            };
             self.validate_seal(seal, &signed_event.event_message)?;
         };
 
@@ -354,7 +392,7 @@ impl<D: EventDatabase> EventValidator<D> {
         &self,
         rpy: &SignedReply,
     ) -> Result<Option<IdentifierState>, Error> {
-        use crate::query::reply_event::{bada_logic, ReplyRoute};
+        use crate::query::reply_event::{bada_logic, is_from_future, ReplyRoute};
 
         let route = rpy.reply.get_route();
         // check if signature was made by ksn creator
@@ -365,6 +403,11 @@ impl<D: EventDatabase> EventValidator<D> {
             self.verify(&rpy.reply.encode()?, &rpy.signature)?;
 
             rpy.reply.check_digest()?;
+            let now = self.clock.now().into();
+            rpy.check_clock_drift(now, self.max_clock_drift)?;
+            if is_from_future(ksn.timestamp, now, self.max_clock_drift) {
+                return Err(QueryError::FutureKsn(ksn.timestamp.to_rfc3339()).into());
+            };
             let reply_prefix = ksn.state.prefix.clone();
 
             // check if there's previous reply to compare
@@ -390,9 +433,16 @@ impl<D: EventDatabase> EventValidator<D> {
         pref: &IdentifierPrefix,
         aid: &IdentifierPrefix,
     ) -> Result<(), Error> {
+        use crate::query::reply_event::ReplyRoute;
+
+        // Compare with time of previous notice, not its reply, so the same
+        // state reported again is not considered stale.
         match self.event_storage.get_last_ksn_reply(pref, aid) {
             Some(old_ksn) => {
-                let old_dt = old_ksn.reply.get_timestamp();
+                let old_dt = match old_ksn.reply.get_route() {
+                    ReplyRoute::Ksn(_, ksn) => ksn.timestamp,
+                    _ => old_ksn.reply.get_timestamp(),
+                };
                 if old_dt > new_dt {
                     Err(QueryError::StaleKsn.into())
                 } else {
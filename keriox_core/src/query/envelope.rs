@@ -0,0 +1,200 @@
+use std::{fmt::Display, str::FromStr};
+
+use said::{
+    derivation::{HashFunction, HashFunctionCode},
+    SelfAddressingIdentifier,
+};
+use thiserror::Error;
+
+use crate::prefix::{BasicPrefix, CesrPrimitive, SelfSigningPrefix};
+
+/// Name of HTTP header, in which witness attaches envelope to query response.
+pub const RESPONSE_ENVELOPE_HEADER: &str = "KERI-Response-Envelope";
+
+/// Integrity envelope of query response. Contains SAIDs computed over the
+/// query stream and the whole returned stream, signed together by responding
+/// actor. Allows client to detect if response was truncated or modified by
+/// intermediaries (proxies, caches), or if it was returned for different
+/// query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseEnvelope {
+    pub signer: BasicPrefix,
+    pub query: SelfAddressingIdentifier,
+    pub digest: SelfAddressingIdentifier,
+    pub signature: SelfSigningPrefix,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EnvelopeError {
+    #[error("Missing response envelope")]
+    Missing,
+    #[error("Can't parse response envelope: {0}")]
+    Unparsable(String),
+    #[error("Response signed by {got}, expected {expected}")]
+    WrongSigner { expected: String, got: String },
+    #[error("Response was returned for different query")]
+    QueryMismatch,
+    #[error("Response digest doesn't match returned stream")]
+    DigestMismatch,
+    #[error("Wrong signature of response digest")]
+    WrongSignature,
+}
+
+impl ResponseEnvelope {
+    /// Computes digest of query or response stream.
+    pub fn digest(stream: &[u8]) -> SelfAddressingIdentifier {
+        HashFunction::from(HashFunctionCode::Blake3_256).derive(stream)
+    }
+
+    /// Returns data that should be signed by responding actor, which binds
+    /// response digest to query digest.
+    pub fn signed_data(
+        query: &SelfAddressingIdentifier,
+        digest: &SelfAddressingIdentifier,
+    ) -> Vec<u8> {
+        format!("q={};d={}", query, digest).into_bytes()
+    }
+
+    pub fn new(
+        signer: BasicPrefix,
+        query: SelfAddressingIdentifier,
+        digest: SelfAddressingIdentifier,
+        signature: SelfSigningPrefix,
+    ) -> Self {
+        Self {
+            signer,
+            query,
+            digest,
+            signature,
+        }
+    }
+
+    /// Checks if envelope matches sent query and response stream and is
+    /// signed by expected actor.
+    pub fn verify(
+        &self,
+        query: &[u8],
+        stream: &[u8],
+        expected_signer: &BasicPrefix,
+    ) -> Result<(), EnvelopeError> {
+        if &self.signer != expected_signer {
+            return Err(EnvelopeError::WrongSigner {
+                expected: expected_signer.to_str(),
+                got: self.signer.to_str(),
+            });
+        }
+        if !self.query.verify_binding(query) {
+            return Err(EnvelopeError::QueryMismatch);
+        }
+        if !self.digest.verify_binding(stream) {
+            return Err(EnvelopeError::DigestMismatch);
+        }
+        match self.signer.verify(
+            &Self::signed_data(&self.query, &self.digest),
+            &self.signature,
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(EnvelopeError::WrongSignature),
+        }
+    }
+}
+
+/// Envelope is serialized as
+/// `i=<signer>;q=<query digest>;d=<digest>;s=<signature>`, so it can be used
+/// as HTTP header value.
+impl Display for ResponseEnvelope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "i={};q={};d={};s={}",
+            self.signer.to_str(),
+            self.query,
+            self.digest,
+            self.signature.to_str()
+        )
+    }
+}
+
+impl FromStr for ResponseEnvelope {
+    type Err = EnvelopeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut signer, mut query, mut digest, mut signature) = (None, None, None, None);
+        for field in s.split(';') {
+            let (key, value) = field
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| EnvelopeError::Unparsable(field.to_string()))?;
+            let unparsable = |e: &dyn Display| EnvelopeError::Unparsable(e.to_string());
+            match key {
+                "i" => signer = Some(value.parse().map_err(|e| unparsable(&e))?),
+                "q" => query = Some(value.parse().map_err(|e| unparsable(&e))?),
+                "d" => digest = Some(value.parse().map_err(|e| unparsable(&e))?),
+                "s" => signature = Some(value.parse().map_err(|e| unparsable(&e))?),
+                _ => return Err(EnvelopeError::Unparsable(key.to_string())),
+            }
+        }
+        match (signer, query, digest, signature) {
+            (Some(signer), Some(query), Some(digest), Some(signature)) => {
+                Ok(Self::new(signer, query, digest, signature))
+            }
+            _ => Err(EnvelopeError::Unparsable(s.to_string())),
+        }
+    }
+}
+
+#[test]
+fn test_response_envelope() -> Result<(), crate::error::Error> {
+    use crate::signer::Signer;
+
+    let signer = Signer::new();
+    let prefix = BasicPrefix::Ed25519NT(signer.public_key());
+    let query = br#"{"v":"KERI10JSON000105_","t":"qry"}"#;
+    let stream = br#"{"v":"KERI10JSON00012b_","t":"icp"}"#;
+    let (query_digest, digest) = (
+        ResponseEnvelope::digest(query),
+        ResponseEnvelope::digest(stream),
+    );
+    let signature = SelfSigningPrefix::Ed25519Sha512(
+        signer.sign(ResponseEnvelope::signed_data(&query_digest, &digest))?,
+    );
+    let envelope = ResponseEnvelope::new(prefix.clone(), query_digest, digest, signature);
+
+    let parsed: ResponseEnvelope = envelope.to_string().parse().unwrap();
+    assert_eq!(parsed, envelope);
+    assert!(parsed.verify(query, stream, &prefix).is_ok());
+
+    // Truncated stream
+    assert_eq!(
+        parsed.verify(query, &stream[..10], &prefix),
+        Err(EnvelopeError::DigestMismatch)
+    );
+
+    // Response to other query
+    assert_eq!(
+        parsed.verify(
+            br#"{"v":"KERI10JSON000105_","t":"qry","i":"other"}"#,
+            stream,
+            &prefix
+        ),
+        Err(EnvelopeError::QueryMismatch)
+    );
+
+    // Query digest replaced, without valid signature
+    let replaced = ResponseEnvelope {
+        query: ResponseEnvelope::digest(b"other"),
+        ..envelope.clone()
+    };
+    assert_eq!(
+        replaced.verify(b"other", stream, &prefix),
+        Err(EnvelopeError::WrongSignature)
+    );
+
+    // Envelope signed by someone else
+    let other = BasicPrefix::Ed25519NT(Signer::new().public_key());
+    assert!(matches!(
+        parsed.verify(query, stream, &other),
+        Err(EnvelopeError::WrongSigner { .. })
+    ));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use chrono::{DateTime, FixedOffset, SecondsFormat};
 use said::version::{format::SerializationFormats, SerializationInfo};
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use serde_hex::{Compact, SerHex};
@@ -21,6 +21,11 @@ pub struct KeyStateNotice {
 
     #[serde(rename = "c")]
     config: Vec<String>,
+
+    /// Time when state was last confirmed by identifier's witnesses. Set
+    /// by watcher answering from state it verified before.
+    #[serde(rename = "vt", default)]
+    pub verified_at: Option<DateTime<FixedOffset>>,
 }
 
 impl Serialize for KeyStateNotice {
@@ -49,20 +54,30 @@ impl Serialize for KeyStateNotice {
         em.serialize_field("c", &self.config)?;
         em.serialize_field("ee", &self.state.last_est)?;
         em.serialize_field("di", &self.state.delegator.clone().unwrap_or_default())?;
+        if let Some(verified_at) = &self.verified_at {
+            em.serialize_field(
+                "vt",
+                &verified_at.to_rfc3339_opts(SecondsFormat::Micros, false),
+            )?;
+        }
         em.end()
     }
 }
 
 impl KeyStateNotice {
-    pub fn new_ksn(state: IdentifierState, serialization: SerializationFormats) -> Self {
-        let dt: DateTime<FixedOffset> = DateTime::from(Utc::now());
-
+    /// Creates notice of `state` issued at `timestamp`.
+    pub fn new_ksn(
+        state: IdentifierState,
+        serialization: SerializationFormats,
+        timestamp: DateTime<FixedOffset>,
+    ) -> Self {
         KeyStateNotice {
             serialization_info: SerializationInfo::new("KERI".to_string(), 1, 0, serialization, 0),
-            timestamp: dt,
+            timestamp,
             state,
             first_seen_sn: 0,
             config: vec![],
+            verified_at: None,
         }
     }
 }
@@ -68,9 +68,17 @@ pub struct QueryArgsMbx {
     pub i: IdentifierPrefix,
     /// To which witness given query message reply will be sent
     pub src: IdentifierPrefix,
+    /// Start from messages following the ones acknowledged by `pre`, if they
+    /// are further than `topics` indexes.
+    #[serde(rename = "rs", default, skip_serializing_if = "std::ops::Not::not")]
+    pub resume: bool,
+    /// Maximal number of messages returned in single response. If there
+    /// are more, response contains topic indexes of the next page.
+    #[serde(rename = "l", default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct QueryTopics {
     #[serde(rename = "/receipt")]
     pub receipt: usize,
@@ -86,6 +94,32 @@ pub struct QueryTopics {
     pub delegate: usize,
 }
 
+impl QueryTopics {
+    /// Returns greater index of each topic.
+    pub fn furthest(&self, other: &QueryTopics) -> QueryTopics {
+        QueryTopics {
+            receipt: self.receipt.max(other.receipt),
+            replay: self.replay.max(other.replay),
+            reply: self.reply.max(other.reply),
+            multisig: self.multisig.max(other.multisig),
+            credential: self.credential.max(other.credential),
+            delegate: self.delegate.max(other.delegate),
+        }
+    }
+
+    /// Returns smaller index of each topic.
+    pub fn nearest(&self, other: &QueryTopics) -> QueryTopics {
+        QueryTopics {
+            receipt: self.receipt.min(other.receipt),
+            replay: self.replay.min(other.replay),
+            reply: self.reply.min(other.reply),
+            multisig: self.multisig.min(other.multisig),
+            credential: self.credential.min(other.credential),
+            delegate: self.delegate.min(other.delegate),
+        }
+    }
+}
+
 #[test]
 fn test_query_mbx_deserialize() {
     use crate::query::mailbox::QueryTopics;
@@ -8,10 +8,14 @@ use self::key_state_notice::KeyStateNotice;
 
 use thiserror::Error;
 
+pub mod envelope;
 pub mod key_state_notice;
 pub mod mailbox;
+pub mod provenance;
 pub mod query_event;
 pub mod reply_event;
+pub mod replay;
+pub mod verdict;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ReplyType {
@@ -27,6 +31,10 @@ pub enum QueryError {
     StaleKsn,
     #[error("Got stale reply message")]
     StaleRpy,
+    #[error("Got key state notice from the future: {0}")]
+    FutureKsn(String),
+    #[error("Got reply message from the future: {0}")]
+    FutureRpy(String),
     #[error("No previous reply in database")]
     NoSavedReply,
     #[error("Error: {0}")]
@@ -0,0 +1,121 @@
+use chrono::{DateTime, FixedOffset};
+use said::SelfAddressingIdentifier;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
+};
+
+/// Where watcher got data from and when.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Witness that supplied data.
+    pub source: IdentifierPrefix,
+    pub retrieved_at: DateTime<FixedOffset>,
+}
+
+/// Provenance of single event accepted in watcher's KEL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventProvenance {
+    pub sn: u64,
+    pub digest: SelfAddressingIdentifier,
+    #[serde(flatten)]
+    pub provenance: Provenance,
+}
+
+/// Lineage of data that watcher provides about identifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProvenanceBundle {
+    /// Events of KEL of `id`, that watcher retrieved from witnesses. Events
+    /// received in other way (e.g. sent directly to watcher) are missing.
+    Kel {
+        id: IdentifierPrefix,
+        events: Vec<EventProvenance>,
+    },
+    /// Last retrieval of TEL of `vc_id` from registry `ri` backer.
+    Tel {
+        ri: IdentifierPrefix,
+        vc_id: IdentifierPrefix,
+        provenance: Option<Provenance>,
+    },
+}
+
+/// Provenance bundle signed by watcher, so relying party can audit where
+/// watcher's data comes from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedProvenance {
+    pub bundle: ProvenanceBundle,
+    pub signer: BasicPrefix,
+    pub signature: SelfSigningPrefix,
+}
+
+impl SignedProvenance {
+    pub fn new(
+        bundle: ProvenanceBundle,
+        signer: BasicPrefix,
+        signature: SelfSigningPrefix,
+    ) -> Self {
+        Self {
+            bundle,
+            signer,
+            signature,
+        }
+    }
+
+    /// Returns bytes of bundle, that are signed by watcher.
+    pub fn signed_data(bundle: &ProvenanceBundle) -> Result<Vec<u8>, Error> {
+        serde_json::to_vec(bundle).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Checks if bundle is signed by `expected_signer`.
+    pub fn verify(&self, expected_signer: &BasicPrefix) -> Result<bool, Error> {
+        if &self.signer != expected_signer {
+            return Ok(false);
+        }
+        Ok(self
+            .signer
+            .verify(&Self::signed_data(&self.bundle)?, &self.signature)?)
+    }
+}
+
+#[test]
+fn test_signed_provenance() -> Result<(), Error> {
+    use crate::signer::Signer;
+    use chrono::Utc;
+
+    let signer = Signer::new();
+    let prefix = BasicPrefix::Ed25519NT(signer.public_key());
+    let witness = BasicPrefix::Ed25519NT(Signer::new().public_key());
+    let bundle = ProvenanceBundle::Kel {
+        id: IdentifierPrefix::Basic(witness.clone()),
+        events: vec![EventProvenance {
+            sn: 0,
+            digest: "EJJR2nmwyYAfSVPzhzS6b5CMZAoTNZH3ULvaU6Z-i0d8"
+                .parse()
+                .unwrap(),
+            provenance: Provenance {
+                source: IdentifierPrefix::Basic(witness.clone()),
+                retrieved_at: Utc::now().into(),
+            },
+        }],
+    };
+    let signature =
+        SelfSigningPrefix::Ed25519Sha512(signer.sign(SignedProvenance::signed_data(&bundle)?)?);
+    let signed = SignedProvenance::new(bundle, prefix.clone(), signature);
+
+    let parsed: SignedProvenance =
+        serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+    assert_eq!(parsed, signed);
+    assert!(parsed.verify(&prefix)?);
+    assert!(!parsed.verify(&witness)?);
+
+    // Modified bundle
+    let mut modified = parsed;
+    if let ProvenanceBundle::Kel { events, .. } = &mut modified.bundle {
+        events[0].sn = 1;
+    }
+    assert!(!modified.verify(&prefix)?);
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use cesrox::{payload::Payload, ParsedData};
+use chrono::{DateTime, FixedOffset};
 use said::derivation::HashFunctionCode;
 use said::version::format::SerializationFormats;
+use said::SelfAddressingIdentifier;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -35,6 +37,13 @@ pub enum QueryRoute {
         #[serde(rename = "q")]
         args: LogsQueryArgs,
     },
+    #[serde(rename = "logs/between")]
+    LogsBetween {
+        #[serde(rename = "rr")]
+        reply_route: String,
+        #[serde(rename = "q")]
+        args: LogsBetweenQueryArgs,
+    },
 }
 
 impl QueryRoute {
@@ -42,6 +51,17 @@ impl QueryRoute {
         match self {
             QueryRoute::Ksn { ref args, .. } => args.i.clone(),
             QueryRoute::Logs { ref args, .. } => args.i.clone(),
+            QueryRoute::LogsBetween { ref args, .. } => args.i.clone(),
+        }
+    }
+
+    /// Returns identifier of node that should answer query, if set.
+    pub fn get_source(&self) -> Option<&IdentifierPrefix> {
+        match self {
+            QueryRoute::Ksn { ref args, .. } | QueryRoute::Logs { ref args, .. } => {
+                args.src.as_ref()
+            }
+            QueryRoute::LogsBetween { ref args, .. } => args.src.as_ref(),
         }
     }
 }
@@ -54,6 +74,23 @@ pub struct LogsQueryArgs {
     pub i: IdentifierPrefix,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub src: Option<IdentifierPrefix>,
+    /// Asks only for events with sn greater than given one, with their
+    /// receipts, so KEL known up to that sn isn't downloaded again. Takes
+    /// precedence over `s`; `l` limits number of returned events.
+    #[serde(rename = "a", skip_serializing_if = "Option::is_none")]
+    pub after: Option<u64>,
+}
+
+/// Asks for events of identifier first seen by queried node between `from`
+/// (inclusive) and `to` (exclusive), with their receipts. Lets auditors
+/// check what identifier did in given period without downloading whole KEL.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogsBetweenQueryArgs {
+    pub i: IdentifierPrefix,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<IdentifierPrefix>,
+    pub from: DateTime<FixedOffset>,
+    pub to: DateTime<FixedOffset>,
 }
 
 pub type QueryEvent = KeriEvent<Timestamped<QueryRoute>>;
@@ -99,6 +136,21 @@ impl SignedQueryMessage {
         }
     }
 
+    /// Returns time of query creation, set by querying identifier.
+    pub fn timestamp(&self) -> DateTime<FixedOffset> {
+        match self {
+            SignedQueryMessage::KelQuery(qry) => qry.query.data.timestamp,
+            SignedQueryMessage::MailboxQuery(qry) => qry.query.data.timestamp,
+        }
+    }
+
+    pub fn digest(&self) -> Result<SelfAddressingIdentifier, Error> {
+        match self {
+            SignedQueryMessage::KelQuery(qry) => qry.query.digest(),
+            SignedQueryMessage::MailboxQuery(qry) => qry.query.digest(),
+        }
+    }
+
     pub fn prefix(&self) -> IdentifierPrefix {
         match self {
             SignedQueryMessage::KelQuery(qry) => qry.query.get_prefix(),
@@ -149,7 +201,8 @@ where
 {
     pub fn to_cesr(&self) -> Result<Vec<u8>, Error> {
         let payload: Payload = self.query.clone().into();
-        let attachments = signatures_into_groups(&[self.signature.clone()]);
+        let attachments =
+            signatures_into_groups(&[self.signature.clone()]).map_err(|_e| Error::CesrError)?;
         ParsedData {
             payload,
             attachments,
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+use said::SelfAddressingIdentifier;
+
+use crate::{
+    actor::SignedQueryError,
+    clock::{system_clock, Clock},
+    error::Error,
+    prefix::IdentifierPrefix,
+};
+
+use super::query_event::SignedQueryMessage;
+
+/// Digests of processed queries with their timestamps, by signer.
+type SeenQueries =
+    HashMap<IdentifierPrefix, HashMap<SelfAddressingIdentifier, DateTime<FixedOffset>>>;
+
+/// Rejects signed queries with timestamp too far from current time and
+/// queries processed before. Only digests of queries within allowed skew
+/// are remembered, because older ones are rejected by timestamp anyway.
+pub struct QueryReplayGuard {
+    max_skew: Duration,
+    seen: Mutex<SeenQueries>,
+    clock: Arc<dyn Clock>,
+}
+
+impl QueryReplayGuard {
+    pub fn new(max_skew: Duration) -> Self {
+        Self {
+            max_skew,
+            seen: Mutex::new(HashMap::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Sets clock which query timestamps are compared with.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Checks timestamp of query and remembers its digest. Should be called
+    /// after signature of query is verified, so others can't block query
+    /// by sending it first with forged signature.
+    pub fn check(&self, qry: &SignedQueryMessage) -> Result<(), SignedQueryError> {
+        self.check_at(self.clock.now().into(), qry)
+    }
+
+    /// Checks query as if current time was `now`.
+    pub fn check_at(
+        &self,
+        now: DateTime<FixedOffset>,
+        qry: &SignedQueryMessage,
+    ) -> Result<(), SignedQueryError> {
+        let max_skew = chrono::Duration::from_std(self.max_skew)
+            .map_err(|_e| Error::SemanticError("Improper duration".into()))?;
+        let timestamp = qry.timestamp();
+        if (now - timestamp).abs() > max_skew {
+            return Err(SignedQueryError::TimestampOutOfSkew { timestamp });
+        }
+        let signer = qry.signature().get_signer().unwrap_or_else(|| qry.prefix());
+        let digest = qry.digest()?;
+
+        let mut seen = self.seen.lock().map_err(|_e| Error::MutexPoisoned)?;
+        seen.retain(|_signer, digests| {
+            digests.retain(|_digest, timestamp| now - *timestamp <= max_skew);
+            !digests.is_empty()
+        });
+        let digests = seen.entry(signer).or_default();
+        if digests.contains_key(&digest) {
+            return Err(SignedQueryError::Replayed { digest });
+        }
+        digests.insert(digest, timestamp);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_query_replay_guard() -> Result<(), SignedQueryError> {
+    use crate::{
+        clock::ManualClock,
+        prefix::{BasicPrefix, SelfSigningPrefix},
+        query::query_event::{LogsQueryArgs, QueryEvent, QueryRoute, SignedKelQuery},
+        signer::Signer,
+    };
+    use said::{derivation::HashFunctionCode, version::format::SerializationFormats};
+
+    let signer = Signer::new();
+    let prefix = BasicPrefix::Ed25519NT(signer.public_key());
+    let query = |i: u64| {
+        let qry = QueryEvent::new_query(
+            QueryRoute::Logs {
+                reply_route: "".to_string(),
+                args: LogsQueryArgs {
+                    s: Some(i),
+                    limit: None,
+                    i: IdentifierPrefix::Basic(prefix.clone()),
+                    src: None,
+                    after: None,
+                },
+            },
+            SerializationFormats::JSON,
+            HashFunctionCode::Blake3_256,
+        );
+        let signature =
+            SelfSigningPrefix::Ed25519Sha512(signer.sign(qry.encode().unwrap()).unwrap());
+        SignedQueryMessage::KelQuery(SignedKelQuery::new_nontrans(qry, prefix.clone(), signature))
+    };
+
+    let guard = QueryReplayGuard::new(Duration::from_secs(60));
+    let first = query(0);
+    let now = first.timestamp();
+    guard.check_at(now, &first)?;
+    assert!(matches!(
+        guard.check_at(now, &first),
+        Err(SignedQueryError::Replayed { .. })
+    ));
+    guard.check_at(now, &query(1))?;
+
+    // Queries from too far in the past or future are rejected.
+    let over_skew = chrono::Duration::seconds(61);
+    assert!(matches!(
+        guard.check_at(now + over_skew, &first),
+        Err(SignedQueryError::TimestampOutOfSkew { .. })
+    ));
+    assert!(matches!(
+        guard.check_at(now - over_skew, &query(2)),
+        Err(SignedQueryError::TimestampOutOfSkew { .. })
+    ));
+
+    // Queries are checked against guard's clock.
+    let clock = Arc::new(ManualClock::new(now.with_timezone(&chrono::Local)));
+    let guard = QueryReplayGuard::new(Duration::from_secs(60)).with_clock(clock.clone());
+    guard.check(&query(3))?;
+    clock.advance(Duration::from_secs(61));
+    assert!(matches!(
+        guard.check(&query(4)),
+        Err(SignedQueryError::TimestampOutOfSkew { .. })
+    ));
+    Ok(())
+}
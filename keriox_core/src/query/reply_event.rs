@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use cesrox::primitives::CesrPrimitive;
 use chrono::{DateTime, FixedOffset};
 use said::derivation::HashFunctionCode;
@@ -211,6 +213,22 @@ pub fn bada_logic(new_rpy: &SignedReply, old_rpy: &SignedReply) -> Result<(), Qu
     }
 }
 
+/// Default tolerance for timestamps of messages from peers with skewed
+/// clocks.
+pub const DEFAULT_MAX_CLOCK_DRIFT: Duration = Duration::from_secs(5 * 60);
+
+/// Returns true if `timestamp` is later than `now` by more than `max_drift`.
+pub fn is_from_future(
+    timestamp: DateTime<FixedOffset>,
+    now: DateTime<FixedOffset>,
+    max_drift: Duration,
+) -> bool {
+    match chrono::Duration::from_std(max_drift) {
+        Ok(max_drift) => timestamp - now > max_drift,
+        Err(_) => false,
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SignedReply {
     pub reply: ReplyEvent,
@@ -239,6 +257,21 @@ impl SignedReply {
             signature,
         }
     }
+
+    /// Checks if reply timestamp isn't further than `max_drift` in the
+    /// future of `now`.
+    pub fn check_clock_drift(
+        &self,
+        now: DateTime<FixedOffset>,
+        max_drift: Duration,
+    ) -> Result<(), QueryError> {
+        let timestamp = self.reply.get_timestamp();
+        if is_from_future(timestamp, now, max_drift) {
+            Err(QueryError::FutureRpy(timestamp.to_rfc3339()))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -314,4 +347,21 @@ pub mod tests {
             assert!(false)
         };
     }
+
+    #[test]
+    pub fn test_clock_drift() {
+        use std::time::Duration;
+
+        use chrono::{DateTime, FixedOffset};
+
+        use super::is_from_future;
+
+        let now: DateTime<FixedOffset> = "2023-07-20T12:00:00.000000+00:00".parse().unwrap();
+        let skewed: DateTime<FixedOffset> = "2023-07-20T12:01:00.000000+00:00".parse().unwrap();
+        let past: DateTime<FixedOffset> = "2023-07-20T11:00:00.000000+00:00".parse().unwrap();
+
+        assert!(!is_from_future(skewed, now, Duration::from_secs(120)));
+        assert!(is_from_future(skewed, now, Duration::from_secs(30)));
+        assert!(!is_from_future(past, now, Duration::from_secs(0)));
+    }
 }
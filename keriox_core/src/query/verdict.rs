@@ -0,0 +1,72 @@
+use chrono::{DateTime, FixedOffset};
+use said::SelfAddressingIdentifier;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
+};
+
+/// Status of credential in TEL of its registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStatus {
+    Issued,
+    Revoked,
+    NotIssued,
+    /// TEL couldn't be retrieved or verified.
+    Unknown,
+}
+
+/// Result of credential verification done by watcher on behalf of relying
+/// party.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Verdict {
+    /// SAID of verified credential.
+    pub credential: SelfAddressingIdentifier,
+    pub issuer: IdentifierPrefix,
+    /// Registry of credential, if it has one.
+    pub registry: Option<IdentifierPrefix>,
+    /// Credential signatures match issuer's keys from its KEL.
+    pub signatures_valid: bool,
+    pub status: CredentialStatus,
+    /// Signatures are valid and credential is issued and not revoked.
+    pub valid: bool,
+    /// Reasons of failed checks.
+    pub errors: Vec<String>,
+    pub verified_at: DateTime<FixedOffset>,
+}
+
+/// Verdict signed by watcher, so it can be trusted by clients that don't
+/// verify credentials themselves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedVerdict {
+    pub verdict: Verdict,
+    pub signer: BasicPrefix,
+    pub signature: SelfSigningPrefix,
+}
+
+impl SignedVerdict {
+    pub fn new(verdict: Verdict, signer: BasicPrefix, signature: SelfSigningPrefix) -> Self {
+        Self {
+            verdict,
+            signer,
+            signature,
+        }
+    }
+
+    /// Returns bytes of verdict, that are signed by watcher.
+    pub fn signed_data(verdict: &Verdict) -> Result<Vec<u8>, Error> {
+        serde_json::to_vec(verdict).map_err(|e| Error::SerializationError(e.to_string()))
+    }
+
+    /// Checks if verdict is signed by `expected_signer`.
+    pub fn verify(&self, expected_signer: &BasicPrefix) -> Result<bool, Error> {
+        if &self.signer != expected_signer {
+            return Ok(false);
+        }
+        Ok(self
+            .signer
+            .verify(&Self::signed_data(&self.verdict)?, &self.signature)?)
+    }
+}
@@ -0,0 +1,327 @@
+//! Computing self-addressing identifiers of arbitrary serializable data.
+//!
+//! Digest is computed the same way as for KERI events: SAID fields are
+//! replaced with `#` characters of the length of the resulting digest, data
+//! is serialized in the requested format and hashed.
+use std::fmt;
+
+use said::{
+    derivation::{HashFunction, HashFunctionCode},
+    version::format::SerializationFormats,
+    SelfAddressingIdentifier,
+};
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// Default name of SAID field.
+pub const SAID_FIELD: &str = "d";
+
+/// Computes SAID of `data`, treating `d` field as SAID field.
+pub fn compute_said<T: Serialize>(
+    data: &T,
+    code: HashFunctionCode,
+    format: SerializationFormats,
+) -> Result<SelfAddressingIdentifier, Error> {
+    compute_said_with_fields(data, &[SAID_FIELD], code, format)
+}
+
+/// Computes SAID of `data`. All of provided top level `fields` are replaced
+/// with dummy characters before computing digest, so they need to be present
+/// in data. If data starts with KERI or ACDC version string (`v` field, e.g.
+/// `KERI10JSON0001e7_`), its size is set to the size of serialized data.
+pub fn compute_said_with_fields<T: Serialize>(
+    data: &T,
+    fields: &[&str],
+    code: HashFunctionCode,
+    format: SerializationFormats,
+) -> Result<SelfAddressingIdentifier, Error> {
+    let hash_function = HashFunction::from(code);
+    let derivation_data = derivation_data(data, fields, &hash_function, &format)?;
+    Ok(hash_function.derive(&derivation_data))
+}
+
+/// Checks if `said` matches digest of `data` computed with the same hash
+/// function and `fields` as SAID fields.
+pub fn verify_said<T: Serialize>(
+    data: &T,
+    fields: &[&str],
+    said: &SelfAddressingIdentifier,
+    format: SerializationFormats,
+) -> Result<bool, Error> {
+    let hash_function = HashFunction::from(said.derivation.clone());
+    let derivation_data = derivation_data(data, fields, &hash_function, &format)?;
+    Ok(said.verify_binding(&derivation_data))
+}
+
+fn derivation_data<T: Serialize>(
+    data: &T,
+    fields: &[&str],
+    hash_function: &HashFunction,
+    format: &SerializationFormats,
+) -> Result<Vec<u8>, Error> {
+    // Data is serialized to JSON first, so field order of serialized types
+    // is kept.
+    let json = serde_json::to_vec(data).map_err(|e| Error::SerializationError(e.to_string()))?;
+    let mut value: OrderedValue =
+        serde_json::from_slice(&json).map_err(|e| Error::SerializationError(e.to_string()))?;
+    let OrderedValue::Object(entries) = &mut value else {
+        return Err(Error::SerializationError(
+            "SAID can be computed only for objects".to_string(),
+        ));
+    };
+    let dummy = "#".repeat(hash_function.get_len());
+    for field in fields {
+        match entries.iter_mut().find(|(key, _)| key == field) {
+            Some((_, value)) => *value = OrderedValue::Scalar(Value::String(dummy.clone())),
+            None => {
+                return Err(Error::SemanticError(format!(
+                    "Missing SAID field: {}",
+                    field
+                )))
+            }
+        }
+    }
+
+    let encode = |value: &OrderedValue| {
+        format
+            .encode(value)
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    };
+    let encoded = encode(&value)?;
+    // Dummy characters may have different length than replaced values, so
+    // size in version string needs to be updated. Size has fixed width, so
+    // updating it doesn't change the size.
+    match version_string(&mut value) {
+        Some(version) => {
+            let sized = format!("{}{:06x}_", &version[..10], encoded.len());
+            if sized != *version {
+                *version = sized;
+                return encode(&value);
+            }
+            Ok(encoded)
+        }
+        None => Ok(encoded),
+    }
+}
+
+/// Returns version string of KERI or ACDC data, if it's the first field of
+/// the object.
+fn version_string(value: &mut OrderedValue) -> Option<&mut String> {
+    match value {
+        OrderedValue::Object(entries) => match entries.first_mut() {
+            Some((key, OrderedValue::Scalar(Value::String(version))))
+                if key == "v"
+                    && version.len() == 17
+                    && version.is_ascii()
+                    && (version.starts_with("KERI") || version.starts_with("ACDC"))
+                    && version.ends_with('_')
+                    && version[10..16].chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Some(version)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// JSON value which keeps order of object fields, so data can be serialized
+/// again exactly as it was received.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderedValue {
+    /// Null, boolean, number or string.
+    Scalar(Value),
+    Array(Vec<OrderedValue>),
+    Object(Vec<(String, OrderedValue)>),
+}
+
+impl Serialize for OrderedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OrderedValue::Scalar(value) => value.serialize(serializer),
+            OrderedValue::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            OrderedValue::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OrderedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OrderedValueVisitor)
+    }
+}
+
+struct OrderedValueVisitor;
+
+impl<'de> Visitor<'de> for OrderedValueVisitor {
+    type Value = OrderedValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::Bool(v)))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::from(v)))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::from(v)))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::from(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::String(v.to_string())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::String(v)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::Null))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(OrderedValue::Scalar(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(OrderedValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = vec![];
+        while let Some((key, value)) = map.next_entry()? {
+            entries.push((key, value));
+        }
+        Ok(OrderedValue::Object(entries))
+    }
+}
+
+#[test]
+fn test_compute_said() -> Result<(), Error> {
+    use crate::event::KeyEvent;
+    use crate::event_message::msg::KeriEvent;
+
+    let icp_raw: &[u8] = br#"{"v":"KERI10JSON0001e7_","t":"icp","d":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen","s":"0","kt":"2","k":["DErocgXD2RGSyvn3MObcx59jeOsEQhv2TqHirVkzrp0Q","DFXLiTjiRdSBPLL6hLa0rskIxk3dh4XwJLfctkJFLRSS","DE9YgIQVgpLwocTVrG8tidKScsQSMWwLWywNC48fhq4f"],"nt":"2","n":["EDJk5EEpC4-tQ7YDwBiKbpaZahh1QCyQOnZRF7p2i8k8","EAXfDjKvUFRj-IEB_o4y-Y_qeJAjYfZtOMD9e7vHNFss","EN8l6yJC2PxribTN0xfri6bLz34Qvj-x3cNwcV3DvT2m"],"bt":"0","b":[],"c":[],"a":[]}"#;
+    let event: KeriEvent<KeyEvent> = serde_json::from_slice(icp_raw).unwrap();
+    let expected: SelfAddressingIdentifier = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+
+    let said = compute_said_with_fields(
+        &event,
+        &["d", "i"],
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    )?;
+    assert_eq!(said, expected);
+    assert!(verify_said(
+        &event,
+        &["d", "i"],
+        &expected,
+        SerializationFormats::JSON
+    )?);
+    assert!(!verify_said(
+        &event,
+        &["d"],
+        &expected,
+        SerializationFormats::JSON
+    )?);
+
+    let custom = serde_json::json!({"d": "", "name": "document"});
+    let said = compute_said(
+        &custom,
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    )?;
+    assert!(verify_said(
+        &serde_json::json!({"d": said.to_string(), "name": "document"}),
+        &[SAID_FIELD],
+        &said,
+        SerializationFormats::JSON
+    )?);
+    // Order of fields is kept, also in nested objects.
+    let document: OrderedValue =
+        serde_json::from_str(r#"{"name":"document","d":"","a":{"z":1,"b":[{"y":true,"c":null}]}}"#)
+            .unwrap();
+    let said = compute_said(
+        &document,
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    )?;
+    let expected = HashFunction::from(HashFunctionCode::Blake3_256).derive(
+        format!(
+            r#"{{"name":"document","d":"{}","a":{{"z":1,"b":[{{"y":true,"c":null}}]}}}}"#,
+            "#".repeat(44)
+        )
+        .as_bytes(),
+    );
+    assert_eq!(said, expected);
+
+    // SAID field needs to be present.
+    assert!(compute_said_with_fields(
+        &event,
+        &["d", "x"],
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON
+    )
+    .is_err());
+
+    // Size in version string is computed with dummy characters in place.
+    let acdc: OrderedValue = serde_json::from_str(
+        r#"{"v":"ACDC10JSON000000_","d":"","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"}"#,
+    )
+    .unwrap();
+    let said = compute_said(
+        &acdc,
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON,
+    )?;
+    let expected = HashFunction::from(HashFunctionCode::Blake3_256).derive(
+        format!(
+            r#"{{"v":"ACDC10JSON00007f_","d":"{}","i":"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"}}"#,
+            "#".repeat(44)
+        )
+        .as_bytes(),
+    );
+    assert_eq!(said, expected);
+
+    assert!(compute_said(
+        &"not an object",
+        HashFunctionCode::Blake3_256,
+        SerializationFormats::JSON
+    )
+    .is_err());
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::{
+    keystore::Keystore,
+    wallet::{derive_key, EncryptionKey},
+    KeyType,
+};
+use crate::{
+    error::Error,
+    keys::{PrivateKey, PublicKey},
+};
+
+const SALT_FILE: &str = "salt";
+const KEY_EXTENSION: &str = "key";
+
+/// Keystore which keeps every private key in separate file, encrypted with
+/// key derived from passphrase. Key file contains nonce, public key and
+/// encrypted private key.
+pub struct FileKeystore {
+    dir: PathBuf,
+    key: EncryptionKey,
+}
+
+impl FileKeystore {
+    /// Opens keystore in `dir`, creating it if needed. Fails with
+    /// `Error::WrongPassphrase` if stored keys can't be decrypted with
+    /// provided passphrase.
+    pub fn open(dir: &Path, passphrase: &[u8]) -> Result<Self, Error> {
+        fs::create_dir_all(dir).map_err(keystore_error)?;
+        let salt_path = dir.join(SALT_FILE);
+        let salt = match fs::read(&salt_path) {
+            Ok(salt) => salt,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                write_private(&salt_path, &salt)?;
+                salt.to_vec()
+            }
+            Err(e) => return Err(keystore_error(e)),
+        };
+        let keystore = Self {
+            dir: dir.to_path_buf(),
+            key: derive_key(passphrase, &salt)?,
+        };
+        // Check passphrase against any stored key.
+        let stored_key = fs::read_dir(dir)
+            .map_err(keystore_error)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .find(|path| path.extension().is_some_and(|ext| ext == KEY_EXTENSION));
+        if let Some(path) = stored_key {
+            keystore.read_private_key(&path)?;
+        }
+        Ok(keystore)
+    }
+
+    fn path(&self, label: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", label, KEY_EXTENSION))
+    }
+
+    fn read(&self, label: &str) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.path(label)) {
+            Ok(content) if content.len() > 12 + 32 => Ok(Some(content)),
+            Ok(_) => Err(Error::KeystoreError(format!(
+                "Malformed key file: {}",
+                label
+            ))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(keystore_error(e)),
+        }
+    }
+
+    fn read_private_key(&self, path: &Path) -> Result<PrivateKey, Error> {
+        let content = fs::read(path).map_err(keystore_error)?;
+        if content.len() <= 12 + 32 {
+            return Err(Error::KeystoreError(format!(
+                "Malformed key file: {}",
+                path.display()
+            )));
+        }
+        let (nonce, rest) = content.split_at(12);
+        let (_public_key, ciphertext) = rest.split_at(32);
+        let plaintext = Zeroizing::new(
+            ChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()))
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_e| Error::WrongPassphrase)?,
+        );
+        Ok(PrivateKey::new(plaintext.to_vec()))
+    }
+}
+
+impl Keystore for FileKeystore {
+    fn generate(&self, label: &str) -> Result<PublicKey, Error> {
+        let path = self.path(label);
+        if path.exists() {
+            return Err(Error::KeystoreError(format!(
+                "Key {} already exists",
+                label
+            )));
+        }
+        let (public_key, private_key) = KeyType::Ed25519.generate_key_pair()?;
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()))
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Zeroizing::new(private_key.key()).as_slice(),
+            )
+            .map_err(|_e| Error::KeystoreError("Encryption failed".to_string()))?;
+
+        let mut content = nonce.to_vec();
+        content.extend(public_key.key());
+        content.extend(ciphertext);
+        write_private(&path, &content)?;
+        Ok(public_key)
+    }
+
+    fn public_key(&self, label: &str) -> Result<Option<PublicKey>, Error> {
+        Ok(self
+            .read(label)?
+            .map(|content| PublicKey::new(content[12..12 + 32].to_vec())))
+    }
+
+    fn sign(&self, label: &str, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.read_private_key(&self.path(label))?.sign_ed(msg)?)
+    }
+
+    fn delete(&self, label: &str) -> Result<(), Error> {
+        match fs::remove_file(self.path(label)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(keystore_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn keystore_error(e: std::io::Error) -> Error {
+    Error::KeystoreError(e.to_string())
+}
+
+/// Writes file readable only by its owner.
+fn write_private(path: &Path, content: &[u8]) -> Result<(), Error> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(keystore_error)?;
+    std::io::Write::write_all(&mut file, content).map_err(keystore_error)
+}
+
+#[test]
+fn test_file_keystore() -> Result<(), Error> {
+    use super::{KeyManager, KeystoreConfig, KeystoreKeyManager, Signer};
+    use std::sync::Arc;
+
+    let dir = tempfile::Builder::new()
+        .prefix("keystore")
+        .tempdir()
+        .unwrap();
+    let msg = b"message";
+
+    let keystore = Arc::new(FileKeystore::open(dir.path(), b"passphrase")?);
+    let mut km = KeystoreKeyManager::open(keystore.clone(), "controller")?;
+    let signature = km.sign(msg)?;
+    assert!(km.public_key().verify_ed(msg, &signature));
+
+    let next_public_key = km.next_public_key();
+    km.rotate()?;
+    assert_eq!(km.public_key(), next_public_key);
+    let signature = km.sign(msg)?;
+    assert!(km.public_key().verify_ed(msg, &signature));
+    // Rotated out key is removed.
+    assert!(keystore.public_key("controller-0")?.is_none());
+
+    // Signer backed by keystore.
+    let signer = Signer::from_keystore(keystore.clone(), "witness")?;
+    assert!(signer.public_key().verify_ed(msg, &signer.sign(msg)?));
+
+    // Signer opened from service config.
+    std::env::set_var("KERIOX_TEST_KEYSTORE_PASSPHRASE", "passphrase");
+    let config: KeystoreConfig = serde_json::from_value(serde_json::json!({
+        "type": "file",
+        "path": dir.path(),
+        "passphrase_env": "KERIOX_TEST_KEYSTORE_PASSPHRASE",
+    }))
+    .unwrap();
+    assert_eq!(config.signer("witness")?.public_key(), signer.public_key());
+
+    // Keys are restored after reopening.
+    drop(km);
+    let keystore = Arc::new(FileKeystore::open(dir.path(), b"passphrase")?);
+    let km = KeystoreKeyManager::open(keystore, "controller")?;
+    assert_eq!(km.sign(msg)?, signature);
+
+    assert!(matches!(
+        FileKeystore::open(dir.path(), b"wrong passphrase"),
+        Err(Error::WrongPassphrase)
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use super::KeyManager;
+use crate::{error::Error, keys::PublicKey};
+
+/// Storage of Ed25519 private keys, that signs data with them. Private keys
+/// don't need to leave the keystore, so implementations can keep them
+/// encrypted on disk or in HSM.
+pub trait Keystore: Send + Sync {
+    /// Generates key pair stored under `label` and returns its public key.
+    fn generate(&self, label: &str) -> Result<PublicKey, Error>;
+
+    /// Returns public key of key pair stored under `label`, if there's one.
+    fn public_key(&self, label: &str) -> Result<Option<PublicKey>, Error>;
+
+    /// Signs `msg` with private key stored under `label`.
+    fn sign(&self, label: &str, msg: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Removes key pair stored under `label`.
+    fn delete(&self, label: &str) -> Result<(), Error>;
+}
+
+/// Key manager which current and next keys are kept in [`Keystore`] under
+/// labels `<name>-<n>`. Rotation generates new next key and removes the
+/// previous current one.
+pub struct KeystoreKeyManager {
+    keystore: Arc<dyn Keystore>,
+    name: String,
+    index: u64,
+    public_key: PublicKey,
+    next_public_key: PublicKey,
+}
+
+impl KeystoreKeyManager {
+    /// Opens key manager of given `name`. Keys are generated, if keystore
+    /// doesn't contain them yet.
+    pub fn open(keystore: Arc<dyn Keystore>, name: &str) -> Result<Self, Error> {
+        let label = |index: u64| format!("{}-{}", name, index);
+        // Keys preceding current one are removed on rotation, so the first
+        // stored key is the current one.
+        let mut index = 0;
+        let public_key = loop {
+            match keystore.public_key(&label(index))? {
+                Some(public_key) => break public_key,
+                None if keystore.public_key(&label(index + 1))?.is_some() => index += 1,
+                None if index == 0 => break keystore.generate(&label(index))?,
+                None => {
+                    return Err(Error::KeystoreError(format!(
+                        "Missing current key of {}",
+                        name
+                    )))
+                }
+            }
+        };
+        let next_public_key = match keystore.public_key(&label(index + 1))? {
+            Some(next_public_key) => next_public_key,
+            None => keystore.generate(&label(index + 1))?,
+        };
+        Ok(Self {
+            keystore,
+            name: name.to_string(),
+            index,
+            public_key,
+            next_public_key,
+        })
+    }
+
+    fn label(&self, index: u64) -> String {
+        format!("{}-{}", self.name, index)
+    }
+}
+
+impl KeyManager for KeystoreKeyManager {
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        self.keystore.sign(&self.label(self.index), msg)
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    fn next_public_key(&self) -> PublicKey {
+        self.next_public_key.clone()
+    }
+
+    fn rotate(&mut self) -> Result<(), Error> {
+        let new_next_public_key = self.keystore.generate(&self.label(self.index + 2))?;
+        self.keystore.delete(&self.label(self.index))?;
+        self.index += 1;
+        self.public_key = std::mem::replace(&mut self.next_public_key, new_next_public_key);
+        Ok(())
+    }
+}
+
+/// Keystore of service key, e.g. read from witness or watcher config file.
+/// Passphrase and PIN are read from environment variables, so they aren't
+/// kept in config file.
+#[cfg(any(feature = "wallet", feature = "pkcs11"))]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum KeystoreConfig {
+    /// [`FileKeystore`](super::FileKeystore) in `path` directory.
+    #[cfg(feature = "wallet")]
+    File {
+        path: std::path::PathBuf,
+        /// Name of environment variable containing passphrase.
+        passphrase_env: String,
+        /// Label of key, service name by default.
+        label: Option<String>,
+    },
+    /// [`Pkcs11Keystore`](super::Pkcs11Keystore) using PKCS#11 module at
+    /// `module` path.
+    #[cfg(feature = "pkcs11")]
+    Pkcs11 {
+        module: std::path::PathBuf,
+        /// Name of environment variable containing user PIN.
+        pin_env: String,
+        /// Label of key, service name by default.
+        label: Option<String>,
+    },
+}
+
+#[cfg(any(feature = "wallet", feature = "pkcs11"))]
+impl KeystoreConfig {
+    /// Opens keystore and returns signer using key stored under configured
+    /// label, or `default_label` if it's not set. Key is generated if
+    /// keystore doesn't contain it yet.
+    pub fn signer(&self, default_label: &str) -> Result<super::Signer, Error> {
+        let secret = |var: &str| {
+            std::env::var(var)
+                .map_err(|_e| Error::KeystoreError(format!("Missing environment variable {}", var)))
+        };
+        let (keystore, label): (Arc<dyn Keystore>, _) = match self {
+            #[cfg(feature = "wallet")]
+            KeystoreConfig::File {
+                path,
+                passphrase_env,
+                label,
+            } => (
+                Arc::new(super::FileKeystore::open(
+                    path,
+                    secret(passphrase_env)?.as_bytes(),
+                )?),
+                label,
+            ),
+            #[cfg(feature = "pkcs11")]
+            KeystoreConfig::Pkcs11 {
+                module,
+                pin_env,
+                label,
+            } => (
+                Arc::new(super::Pkcs11Keystore::open(module, &secret(pin_env)?)?),
+                label,
+            ),
+        };
+        super::Signer::from_keystore(keystore, label.as_deref().unwrap_or(default_label))
+    }
+}
@@ -1,25 +1,126 @@
 use crate::{
     error::Error,
     keys::{KeysError, PrivateKey, PublicKey},
-    prefix::SeedPrefix,
+    prefix::{BasicPrefix, SeedPrefix, SelfSigningPrefix},
 };
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore};
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+#[cfg(feature = "wallet")]
+mod file_keystore;
+mod keystore;
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
+#[cfg(feature = "wallet")]
+mod wallet;
+#[cfg(feature = "wallet")]
+pub use file_keystore::FileKeystore;
+#[cfg(any(feature = "wallet", feature = "pkcs11"))]
+pub use keystore::KeystoreConfig;
+pub use keystore::{Keystore, KeystoreKeyManager};
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::Pkcs11Keystore;
+#[cfg(feature = "wallet")]
+pub use wallet::Lockable;
 
 pub trait KeyManager {
     fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, Error>;
     fn public_key(&self) -> PublicKey;
     fn next_public_key(&self) -> PublicKey;
     fn rotate(&mut self) -> Result<(), Error>;
+
+    /// Returns true if private keys aren't available until key manager is
+    /// unlocked. Signing fails with `Error::WalletLocked` then.
+    fn is_locked(&self) -> bool {
+        false
+    }
+
+    /// Type of keys used for signing.
+    fn key_type(&self) -> KeyType {
+        KeyType::Ed25519
+    }
+}
+
+/// Type of keys generated by [`Signer`] and [`CryptoBox`].
+///
+/// ECDSA keys are on secp256k1 curve. secp256r1 (P-256) isn't available,
+/// because [`BasicPrefix`] has no CESR code for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyType {
+    #[default]
+    Ed25519,
+    ECDSAsecp256k1,
+    Ed448,
+}
+
+impl KeyType {
+    /// Generates random key pair of this type.
+    pub fn generate_key_pair(&self) -> Result<(PublicKey, PrivateKey), Error> {
+        match self {
+            KeyType::Ed25519 => {
+                let kp = ed25519_dalek::Keypair::generate(&mut OsRng {});
+                Ok((
+                    PublicKey::new(kp.public.to_bytes().to_vec()),
+                    PrivateKey::new(kp.secret.to_bytes().to_vec()),
+                ))
+            }
+            KeyType::ECDSAsecp256k1 => {
+                let mut secret = [0u8; 32];
+                OsRng.fill_bytes(&mut secret);
+                let sk = k256::ecdsa::SigningKey::from_bytes(&secret)
+                    .map_err(|_e| KeysError::InvalidKey("ECDSA secp256k1".to_string()))?;
+                secret.zeroize();
+                Ok((
+                    PublicKey::new(k256::ecdsa::VerifyingKey::from(&sk).to_bytes().to_vec()),
+                    PrivateKey::new(sk.to_bytes().to_vec()),
+                ))
+            }
+            KeyType::Ed448 => {
+                let mut secret = [0u8; 57];
+                OsRng.fill_bytes(&mut secret);
+                let sk = ed448_rust::PrivateKey::from(secret);
+                secret.zeroize();
+                Ok((
+                    PublicKey::new(ed448_rust::PublicKey::from(&sk).as_byte().to_vec()),
+                    PrivateKey::new(sk.as_bytes().to_vec()),
+                ))
+            }
+        }
+    }
+
+    /// Returns transferable basic prefix of `key`.
+    pub fn basic_prefix(&self, key: PublicKey) -> BasicPrefix {
+        match self {
+            KeyType::Ed25519 => BasicPrefix::Ed25519(key),
+            KeyType::ECDSAsecp256k1 => BasicPrefix::ECDSAsecp256k1(key),
+            KeyType::Ed448 => BasicPrefix::Ed448(key),
+        }
+    }
+
+    /// Wraps signature made with key of this type.
+    pub fn signature(&self, signature: Vec<u8>) -> SelfSigningPrefix {
+        match self {
+            KeyType::Ed25519 => SelfSigningPrefix::Ed25519Sha512(signature),
+            KeyType::ECDSAsecp256k1 => SelfSigningPrefix::ECDSAsecp256k1Sha256(signature),
+            KeyType::Ed448 => SelfSigningPrefix::Ed448(signature),
+        }
+    }
 }
 
 pub struct CryptoBox {
     signer: Signer,
     next_priv_key: PrivateKey,
     pub next_pub_key: PublicKey,
+    #[cfg(feature = "wallet")]
+    wallet: Option<wallet::Wallet>,
 }
 
 impl KeyManager for CryptoBox {
     fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        if self.is_locked() {
+            return Err(Error::WalletLocked);
+        }
         Ok(self.signer.sign(msg)?)
     }
 
@@ -32,34 +133,104 @@ impl KeyManager for CryptoBox {
     }
 
     fn rotate(&mut self) -> Result<(), Error> {
-        let (next_pub_key, next_priv_key) = generate_key_pair()?;
+        if self.is_locked() {
+            return Err(Error::WalletLocked);
+        }
+        let (next_pub_key, next_priv_key) = self.signer.key_type.generate_key_pair()?;
 
         let new_signer = Signer {
             priv_key: self.next_priv_key.clone(),
             pub_key: self.next_pub_key.clone(),
+            key_type: self.signer.key_type,
+            keystore: None,
         };
         self.signer = new_signer;
         self.next_priv_key = next_priv_key;
         self.next_pub_key = next_pub_key;
 
+        #[cfg(feature = "wallet")]
+        if let Some(wallet) = self.wallet.as_mut() {
+            wallet.seal(&[&self.signer.priv_key, &self.next_priv_key])?;
+        }
+
         Ok(())
     }
+
+    #[cfg(feature = "wallet")]
+    fn is_locked(&self) -> bool {
+        self.wallet
+            .as_ref()
+            .is_some_and(|wallet| wallet.is_locked())
+    }
+
+    fn key_type(&self) -> KeyType {
+        self.signer.key_type
+    }
 }
 impl CryptoBox {
     pub fn new() -> Result<Self, Error> {
-        let signer = Signer::new();
-        let (next_pub_key, next_priv_key) = generate_key_pair()?;
+        Self::with_key_type(KeyType::Ed25519)
+    }
+
+    /// Creates key manager that generates keys of `key_type`, also on
+    /// rotation.
+    pub fn with_key_type(key_type: KeyType) -> Result<Self, Error> {
+        let signer = Signer::new_with_type(key_type)?;
+        let (next_pub_key, next_priv_key) = key_type.generate_key_pair()?;
         Ok(CryptoBox {
             signer,
             next_pub_key,
             next_priv_key,
+            #[cfg(feature = "wallet")]
+            wallet: None,
+        })
+    }
+
+    /// Sets passphrase needed to unlock keys after
+    /// [`Lockable::lock`]. Keys stay unlocked.
+    #[cfg(feature = "wallet")]
+    pub fn with_passphrase(self, passphrase: &[u8]) -> Result<Self, Error> {
+        let wallet =
+            wallet::Wallet::new(passphrase, &[&self.signer.priv_key, &self.next_priv_key])?;
+        Ok(Self {
+            wallet: Some(wallet),
+            ..self
         })
     }
 }
 
+#[cfg(feature = "wallet")]
+impl Lockable for CryptoBox {
+    fn lock(&mut self) -> Result<(), Error> {
+        let wallet = self.wallet.as_mut().ok_or(Error::MissingPassphrase)?;
+        wallet.lock();
+        // Replaced keys are zeroized on drop.
+        self.signer.priv_key = PrivateKey::new(vec![]);
+        self.next_priv_key = PrivateKey::new(vec![]);
+        Ok(())
+    }
+
+    fn unlock(&mut self, passphrase: &[u8]) -> Result<(), Error> {
+        let wallet = self.wallet.as_mut().ok_or(Error::MissingPassphrase)?;
+        let mut keys = wallet.unlock(passphrase)?.into_iter();
+        match (keys.next(), keys.next()) {
+            (Some(current), Some(next)) => {
+                self.signer.priv_key = current;
+                self.next_priv_key = next;
+                Ok(())
+            }
+            _ => Err(Error::WrongPassphrase),
+        }
+    }
+}
+
 pub struct Signer {
     priv_key: PrivateKey,
     pub_key: PublicKey,
+    key_type: KeyType,
+    /// Keystore holding private key and its label. If set, `priv_key` is
+    /// empty and signing is delegated to the keystore.
+    keystore: Option<(Arc<dyn Keystore>, String)>,
 }
 
 impl Signer {
@@ -69,7 +240,23 @@ impl Signer {
         let pub_key = PublicKey::new(ed.public.to_bytes().to_vec());
         let priv_key = PrivateKey::new(ed.secret.to_bytes().to_vec());
 
-        Signer { pub_key, priv_key }
+        Signer {
+            pub_key,
+            priv_key,
+            key_type: KeyType::Ed25519,
+            keystore: None,
+        }
+    }
+
+    /// Creates a new Signer with a random key of `key_type`.
+    pub fn new_with_type(key_type: KeyType) -> Result<Self, Error> {
+        let (pub_key, priv_key) = key_type.generate_key_pair()?;
+        Ok(Signer {
+            pub_key,
+            priv_key,
+            key_type,
+            keystore: None,
+        })
     }
 
     /// Creates a new Signer with the given ED25519_dalek private key.
@@ -80,25 +267,62 @@ impl Signer {
         Ok(Signer {
             priv_key: PrivateKey::new(priv_key.as_bytes().to_vec()),
             pub_key: PublicKey::new(pub_key.as_bytes().to_vec()),
+            key_type: KeyType::Ed25519,
+            keystore: None,
         })
     }
 
     pub fn new_with_seed(seed: &SeedPrefix) -> Result<Self, Error> {
         let (public_key, private_key) = seed.derive_key_pair()?;
+        let key_type = match seed {
+            SeedPrefix::RandomSeed256ECDSAsecp256k1(_) => KeyType::ECDSAsecp256k1,
+            SeedPrefix::RandomSeed448(_) => KeyType::Ed448,
+            _ => KeyType::Ed25519,
+        };
 
         Ok(Signer {
             priv_key: private_key,
             pub_key: public_key,
+            key_type,
+            keystore: None,
+        })
+    }
+
+    /// Creates a Signer which private key is kept in `keystore` under
+    /// `label`. Key is generated if keystore doesn't contain it yet.
+    pub fn from_keystore(keystore: Arc<dyn Keystore>, label: &str) -> Result<Self, Error> {
+        let pub_key = match keystore.public_key(label)? {
+            Some(pub_key) => pub_key,
+            None => keystore.generate(label)?,
+        };
+        Ok(Signer {
+            priv_key: PrivateKey::new(vec![]),
+            pub_key,
+            key_type: KeyType::Ed25519,
+            keystore: Some((keystore, label.to_string())),
         })
     }
 
     pub fn sign(&self, msg: impl AsRef<[u8]>) -> Result<Vec<u8>, KeysError> {
-        self.priv_key.sign_ed(msg.as_ref())
+        match &self.keystore {
+            Some((keystore, label)) => keystore
+                .sign(label, msg.as_ref())
+                .map_err(|e| KeysError::KeystoreError(e.to_string())),
+            None => match self.key_type {
+                KeyType::Ed25519 => self.priv_key.sign_ed(msg.as_ref()),
+                KeyType::ECDSAsecp256k1 => self.priv_key.sign_ecdsa(msg.as_ref()),
+                KeyType::Ed448 => self.priv_key.sign_ed448(msg.as_ref()),
+            },
+        }
     }
 
     pub fn public_key(&self) -> PublicKey {
         self.pub_key.clone()
     }
+
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
 }
 
 impl Default for Signer {
@@ -107,14 +331,6 @@ impl Default for Signer {
     }
 }
 
-fn generate_key_pair() -> Result<(PublicKey, PrivateKey), Error> {
-    let kp = ed25519_dalek::Keypair::generate(&mut OsRng {});
-    let (vk, sk) = (kp.public, kp.secret);
-    let vk = PublicKey::new(vk.to_bytes().to_vec());
-    let sk = PrivateKey::new(sk.to_bytes().to_vec());
-    Ok((vk, sk))
-}
-
 /// Helper function to generate keypairs that can be used for signing in tests.
 #[cfg(test)]
 pub(crate) fn setup_signers() -> Vec<Signer> {
@@ -149,3 +365,22 @@ pub(crate) fn setup_signers() -> Vec<Signer> {
     })
     .collect::<Vec<_>>()
 }
+
+#[test]
+fn test_key_types() -> Result<(), Error> {
+    let msg = b"hello there";
+    for key_type in [KeyType::Ed25519, KeyType::ECDSAsecp256k1, KeyType::Ed448] {
+        let mut km = CryptoBox::with_key_type(key_type)?;
+        km.rotate()?;
+        assert_eq!(km.key_type(), key_type);
+
+        let key = key_type.basic_prefix(km.public_key());
+        let signature = key_type.signature(km.sign(msg)?);
+        assert!(key.verify(msg, &signature)?);
+        assert!(!key.verify(b"other message", &signature)?);
+
+        let next_key = key_type.basic_prefix(km.next_public_key());
+        assert!(!next_key.verify(msg, &signature)?);
+    }
+    Ok(())
+}
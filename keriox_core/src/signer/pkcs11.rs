@@ -0,0 +1,202 @@
+use std::{path::Path, sync::Mutex};
+
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::Mechanism,
+    object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::AuthPin,
+};
+
+use super::keystore::Keystore;
+use crate::{error::Error, keys::PublicKey};
+
+/// DER encoded OID of Ed25519 curve (1.3.101.112).
+const ED25519_OID: [u8; 5] = [0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Keystore backed by HSM accessed through PKCS#11 module. Private keys are
+/// generated on the token as sensitive and non-extractable, so they never
+/// leave it.
+pub struct Pkcs11Keystore {
+    session: Mutex<Session>,
+}
+
+impl Pkcs11Keystore {
+    /// Loads PKCS#11 module from `module_path` and logs in to the first
+    /// slot with a token using user `pin`.
+    pub fn open(module_path: &Path, pin: &str) -> Result<Self, Error> {
+        let pkcs11 = Pkcs11::new(module_path).map_err(pkcs11_error)?;
+        pkcs11
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(pkcs11_error)?;
+        let slot = pkcs11
+            .get_slots_with_token()
+            .map_err(pkcs11_error)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::KeystoreError("No PKCS#11 token found".to_string()))?;
+        let session = pkcs11.open_rw_session(slot).map_err(pkcs11_error)?;
+        session
+            .login(UserType::User, Some(&AuthPin::new(pin.to_string())))
+            .map_err(|_e| Error::WrongPassphrase)?;
+        Ok(Self {
+            session: Mutex::new(session),
+        })
+    }
+
+    fn find(
+        session: &Session,
+        label: &str,
+        class: ObjectClass,
+    ) -> Result<Option<ObjectHandle>, Error> {
+        Ok(session
+            .find_objects(&[
+                Attribute::Class(class),
+                Attribute::Label(label.as_bytes().to_vec()),
+            ])
+            .map_err(pkcs11_error)?
+            .into_iter()
+            .next())
+    }
+
+    fn read_public_key(session: &Session, handle: ObjectHandle) -> Result<PublicKey, Error> {
+        let attributes = session
+            .get_attributes(handle, &[AttributeType::EcPoint])
+            .map_err(pkcs11_error)?;
+        match attributes.into_iter().next() {
+            // EC point is DER encoded octet string, raw key are its last
+            // 32 bytes.
+            Some(Attribute::EcPoint(point)) if point.len() >= 32 => {
+                Ok(PublicKey::new(point[point.len() - 32..].to_vec()))
+            }
+            _ => Err(Error::KeystoreError("Missing EC point".to_string())),
+        }
+    }
+
+    fn session(&self) -> Result<std::sync::MutexGuard<'_, Session>, Error> {
+        self.session.lock().map_err(|_e| Error::MutArcKeyVaultError)
+    }
+}
+
+impl Keystore for Pkcs11Keystore {
+    fn generate(&self, label: &str) -> Result<PublicKey, Error> {
+        let session = self.session()?;
+        if Self::find(&session, label, ObjectClass::PRIVATE_KEY)?.is_some() {
+            return Err(Error::KeystoreError(format!(
+                "Key {} already exists",
+                label
+            )));
+        }
+        let label = label.as_bytes().to_vec();
+        let public_template = [
+            Attribute::Token(true),
+            Attribute::KeyType(KeyType::EC_EDWARDS),
+            Attribute::EcParams(ED25519_OID.to_vec()),
+            Attribute::Verify(true),
+            Attribute::Label(label.clone()),
+        ];
+        let private_template = [
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Sign(true),
+            Attribute::Label(label),
+        ];
+        let (public_handle, _private_handle) = session
+            .generate_key_pair(
+                &Mechanism::EccEdwardsKeyPairGen,
+                &public_template,
+                &private_template,
+            )
+            .map_err(pkcs11_error)?;
+        Self::read_public_key(&session, public_handle)
+    }
+
+    fn public_key(&self, label: &str) -> Result<Option<PublicKey>, Error> {
+        let session = self.session()?;
+        Self::find(&session, label, ObjectClass::PUBLIC_KEY)?
+            .map(|handle| Self::read_public_key(&session, handle))
+            .transpose()
+    }
+
+    fn sign(&self, label: &str, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        let session = self.session()?;
+        let handle = Self::find(&session, label, ObjectClass::PRIVATE_KEY)?
+            .ok_or_else(|| Error::KeystoreError(format!("Unknown key {}", label)))?;
+        session
+            .sign(&Mechanism::Eddsa, handle, msg)
+            .map_err(pkcs11_error)
+    }
+
+    fn delete(&self, label: &str) -> Result<(), Error> {
+        let session = self.session()?;
+        for class in [ObjectClass::PRIVATE_KEY, ObjectClass::PUBLIC_KEY] {
+            if let Some(handle) = Self::find(&session, label, class)? {
+                session.destroy_object(handle).map_err(pkcs11_error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn pkcs11_error(e: cryptoki::error::Error) -> Error {
+    Error::KeystoreError(e.to_string())
+}
+
+/// Runs against token of PKCS#11 module set in `PKCS11_MODULE` variable,
+/// with user PIN set in `PKCS11_PIN`, e.g. SoftHSM token set up in CI.
+/// Skipped if they aren't set.
+#[test]
+fn test_pkcs11_keystore() -> Result<(), Error> {
+    use super::{KeyManager, KeystoreConfig, KeystoreKeyManager, Signer};
+    use std::sync::Arc;
+
+    let (module, pin) = match (std::env::var("PKCS11_MODULE"), std::env::var("PKCS11_PIN")) {
+        (Ok(module), Ok(pin)) => (module, pin),
+        _ => return Ok(()),
+    };
+    let msg = b"message";
+
+    let keystore = Arc::new(Pkcs11Keystore::open(Path::new(&module), &pin)?);
+    for label in [
+        "test-controller-0",
+        "test-controller-1",
+        "test-controller-2",
+        "test-witness",
+    ] {
+        keystore.delete(label)?;
+    }
+    let mut km = KeystoreKeyManager::open(keystore.clone(), "test-controller")?;
+    let signature = km.sign(msg)?;
+    assert!(km.public_key().verify_ed(msg, &signature));
+    assert!(matches!(
+        keystore.generate("test-controller-0"),
+        Err(Error::KeystoreError(_))
+    ));
+
+    let next_public_key = km.next_public_key();
+    km.rotate()?;
+    assert_eq!(km.public_key(), next_public_key);
+    let signature = km.sign(msg)?;
+    assert!(km.public_key().verify_ed(msg, &signature));
+    // Rotated out key is removed.
+    assert!(keystore.public_key("test-controller-0")?.is_none());
+
+    let signer = Signer::from_keystore(keystore.clone(), "test-witness")?;
+    let public_key = signer.public_key();
+    assert!(public_key.verify_ed(msg, &signer.sign(msg)?));
+
+    // Keys are found after reopening, once module is finalized.
+    drop((km, signer, keystore));
+    let config = KeystoreConfig::Pkcs11 {
+        module: module.into(),
+        pin_env: "PKCS11_PIN".to_string(),
+        label: Some("test-witness".to_string()),
+    };
+    let reopened = config.signer("witness")?;
+    assert_eq!(reopened.public_key(), public_key);
+    assert!(public_key.verify_ed(msg, &reopened.sign(msg)?));
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::KeyManager;
+use crate::{error::Error, keys::PrivateKey};
+
+/// Key manager which private keys can be removed from memory and restored
+/// later with passphrase. Allows wallets to implement auto-lock policies.
+pub trait Lockable: KeyManager {
+    /// Zeroizes private keys held in memory. Fails with
+    /// `Error::MissingPassphrase` if no passphrase was set.
+    fn lock(&mut self) -> Result<(), Error>;
+
+    /// Restores private keys. Fails with `Error::WrongPassphrase` if keys
+    /// can't be decrypted with provided passphrase.
+    fn unlock(&mut self, passphrase: &[u8]) -> Result<(), Error>;
+}
+
+pub(crate) type EncryptionKey = Zeroizing<[u8; 32]>;
+
+/// Private keys encrypted with key derived from passphrase. Derived key is
+/// kept only while wallet is unlocked, so keys can be encrypted again after
+/// rotation.
+pub(crate) struct Wallet {
+    salt: [u8; 16],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+    key: Option<EncryptionKey>,
+}
+
+impl Wallet {
+    pub(crate) fn new(passphrase: &[u8], keys: &[&PrivateKey]) -> Result<Self, Error> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt)?;
+        let mut wallet = Self {
+            salt,
+            nonce: [0u8; 12],
+            ciphertext: vec![],
+            key: Some(key),
+        };
+        wallet.seal(keys)?;
+        Ok(wallet)
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// Encrypts keys again, e.g. after rotation. Wallet must be unlocked.
+    /// Each key is prefixed with its length, because it depends on key type,
+    /// e.g. Ed448 keys are 57 bytes long.
+    pub(crate) fn seal(&mut self, keys: &[&PrivateKey]) -> Result<(), Error> {
+        let key = self.key.as_ref().ok_or(Error::WalletLocked)?;
+        let mut plaintext = Zeroizing::new(Vec::new());
+        for key in keys {
+            let key = Zeroizing::new(key.key());
+            let len = u16::try_from(key.len()).map_err(|_e| Error::SigningError)?;
+            plaintext.extend_from_slice(&len.to_be_bytes());
+            plaintext.extend_from_slice(&key);
+        }
+        OsRng.fill_bytes(&mut self.nonce);
+        self.ciphertext = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(Nonce::from_slice(&self.nonce), plaintext.as_slice())
+            .map_err(|_e| Error::SigningError)?;
+        Ok(())
+    }
+
+    pub(crate) fn lock(&mut self) {
+        self.key = None;
+    }
+
+    /// Decrypts keys and keeps derived key until wallet is locked again.
+    pub(crate) fn unlock(&mut self, passphrase: &[u8]) -> Result<Vec<PrivateKey>, Error> {
+        let key = derive_key(passphrase, &self.salt)?;
+        let plaintext = Zeroizing::new(
+            ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+                .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+                .map_err(|_e| Error::WrongPassphrase)?,
+        );
+        let mut keys = vec![];
+        let mut rest = plaintext.as_slice();
+        while !rest.is_empty() {
+            let (len, tail) = rest.split_at_checked(2).ok_or(Error::WrongPassphrase)?;
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+            let (priv_key, tail) = tail.split_at_checked(len).ok_or(Error::WrongPassphrase)?;
+            keys.push(PrivateKey::new(priv_key.to_vec()));
+            rest = tail;
+        }
+        self.key = Some(key);
+        Ok(keys)
+    }
+}
+
+pub(crate) fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<EncryptionKey, Error> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    argon2::Argon2::default()
+        .hash_password_into(passphrase, salt, key.as_mut())
+        .map_err(|_e| Error::WrongPassphrase)?;
+    Ok(key)
+}
+
+#[test]
+fn test_lock_unlock() -> Result<(), Error> {
+    use super::CryptoBox;
+
+    let mut km = CryptoBox::new()?;
+    // Can't lock without passphrase.
+    assert!(matches!(km.lock(), Err(Error::MissingPassphrase)));
+
+    let mut km = km.with_passphrase(b"passphrase")?;
+    let msg = b"message";
+    let signature = km.sign(msg)?;
+
+    km.lock()?;
+    assert!(km.is_locked());
+    assert!(matches!(km.sign(msg), Err(Error::WalletLocked)));
+    assert!(matches!(km.rotate(), Err(Error::WalletLocked)));
+    assert!(matches!(
+        km.unlock(b"wrong passphrase"),
+        Err(Error::WrongPassphrase)
+    ));
+    assert!(km.is_locked());
+
+    km.unlock(b"passphrase")?;
+    assert!(!km.is_locked());
+    assert_eq!(km.sign(msg)?, signature);
+
+    // Keys rotated while unlocked are restored after next lock.
+    km.rotate()?;
+    let rotated_signature = km.sign(msg)?;
+    km.lock()?;
+    km.unlock(b"passphrase")?;
+    assert_eq!(km.sign(msg)?, rotated_signature);
+    assert_ne!(rotated_signature, signature);
+
+    Ok(())
+}
+
+#[test]
+fn test_lock_unlock_key_types() -> Result<(), Error> {
+    use super::{CryptoBox, KeyType};
+
+    for key_type in [KeyType::Ed25519, KeyType::ECDSAsecp256k1, KeyType::Ed448] {
+        let mut km = CryptoBox::with_key_type(key_type)?.with_passphrase(b"passphrase")?;
+        let msg = b"message";
+        let current_key = km.public_key();
+        let next_key = km.next_public_key();
+
+        km.lock()?;
+        km.unlock(b"passphrase")?;
+        let signature = km.sign(msg)?;
+        let prefix = key_type.basic_prefix(current_key.clone());
+        assert!(prefix.verify(msg, &key_type.signature(signature))?);
+
+        // Restored next key is used after rotation.
+        km.rotate()?;
+        assert_eq!(km.public_key(), next_key);
+        km.lock()?;
+        km.unlock(b"passphrase")?;
+        let signature = km.sign(msg)?;
+        let prefix = key_type.basic_prefix(next_key);
+        assert!(prefix.verify(msg, &key_type.signature(signature))?);
+    }
+
+    Ok(())
+}
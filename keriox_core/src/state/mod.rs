@@ -1,10 +1,15 @@
 use std::collections::HashSet;
 
+use chrono::{DateTime, Local};
+
 use crate::{
     error::Error,
     event::{
         event_data::EventData,
-        sections::{threshold::SignatureThreshold, KeyConfig},
+        sections::{
+            configuration::ConfigurationTrait, key_config::SignatureError,
+            threshold::SignatureThreshold, KeyConfig,
+        },
     },
     event_message::EventTypeTag,
     prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
@@ -13,6 +18,18 @@ use said::SelfAddressingIdentifier;
 use serde::{Deserialize, Serialize};
 use serde_hex::{Compact, SerHex};
 
+/// Change of identifier's key state made by establishment event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub sn: u64,
+    pub event_type: EventTypeTag,
+    pub digest: SelfAddressingIdentifier,
+    /// Time when event was accepted into database for the first time.
+    pub first_seen: DateTime<Local>,
+    /// Digest of current public keys set by event.
+    pub keys_digest: SelfAddressingIdentifier,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct LastEstablishmentData {
     #[serde(rename = "s", with = "SerHex::<Compact>")]
@@ -48,14 +65,12 @@ impl WitnessConfig {
             SignatureThreshold::Simple(t) => {
                 let mut unique = HashSet::new();
                 // save indexed signer's identifiers
-                indexed_receipts.into_iter().for_each(|w| {
-                    unique.insert(
-                        self.witnesses
-                            .get(w.index.current() as usize)
-                            .unwrap()
-                            .clone(),
-                    );
-                });
+                indexed_receipts
+                    .into_iter()
+                    .filter_map(|w| self.witnesses.get(w.index.current() as usize))
+                    .for_each(|witness_id| {
+                        unique.insert(witness_id.clone());
+                    });
                 receipts_couplets
                     .into_iter()
                     .filter(|(witness, _sig)| self.witnesses.contains(witness))
@@ -65,17 +80,23 @@ impl WitnessConfig {
                 Ok(unique.len() >= t as usize)
             }
             SignatureThreshold::Weighted(t) => {
+                // Each witness weight is counted once, no matter how many
+                // receipts it provided.
                 let indexes = receipts_couplets
                     .into_iter()
                     .filter_map(|(id, _signature)| self.witnesses.iter().position(|wit| wit == &id))
                     .chain(
                         indexed_receipts
                             .into_iter()
-                            .map(|att| att.index.current() as usize),
+                            .map(|att| att.index.current() as usize)
+                            .filter(|index| index < &self.witnesses.len()),
                     )
+                    .collect::<HashSet<_>>()
+                    .into_iter()
                     .collect::<Vec<_>>();
                 match t.enough_signatures(&indexes) {
                     Ok(_) => Ok(true),
+                    Err(SignatureError::NotEnoughSigsError) => Ok(false),
                     Err(e) => Err(Error::KeyConfigError(e)),
                 }
             }
@@ -113,6 +134,9 @@ pub struct IdentifierState {
 
     #[serde(rename = "ee")]
     pub last_est: LastEstablishmentData,
+
+    #[serde(rename = "c", default, skip_serializing_if = "Vec::is_empty")]
+    pub config: Vec<String>,
 }
 
 mod empty_string_as_none {
@@ -171,6 +195,11 @@ impl IdentifierState {
     pub fn apply<T: EventSemantics>(self, event: &T) -> Result<Self, Error> {
         event.apply_to(self)
     }
+
+    /// Checks if identifier's inception event sets given configuration trait.
+    pub fn has_trait(&self, config: &ConfigurationTrait) -> bool {
+        self.config.iter().any(|c| c == config.code())
+    }
 }
 
 /// EventSemantics
@@ -182,3 +211,63 @@ pub trait EventSemantics {
         Ok(state)
     }
 }
+
+#[test]
+fn test_weighted_witness_threshold() -> Result<(), Error> {
+    use crate::{
+        actor::event_generator,
+        event_message::cesr_adapter::{parse_event_type, EventType},
+        signer::setup_signers,
+    };
+
+    let signers = setup_signers();
+    let keys = vec![BasicPrefix::Ed25519(signers[0].public_key())];
+    let next_keys = vec![BasicPrefix::Ed25519(signers[1].public_key())];
+    let witnesses: Vec<BasicPrefix> = signers[2..5]
+        .iter()
+        .map(|signer| BasicPrefix::Ed25519NT(signer.public_key()))
+        .collect();
+    let threshold = SignatureThreshold::single_weighted(vec![(1, 2), (1, 2), (1, 4)]);
+
+    // Weighted threshold needs weight for each witness.
+    assert!(event_generator::incept_with_witness_threshold(
+        keys.clone(),
+        next_keys.clone(),
+        witnesses[..2].to_vec(),
+        &threshold,
+        None,
+    )
+    .is_err());
+
+    let icp = event_generator::incept_with_witness_threshold(
+        keys,
+        next_keys,
+        witnesses.clone(),
+        &threshold,
+        None,
+    )?;
+    assert!(icp.contains(r#""bt":["1/2","1/2","1/4"]"#));
+    let witness_config: WitnessConfig = match parse_event_type(icp.as_bytes()).unwrap() {
+        EventType::KeyEvent(ke) => match ke.data.event_data {
+            EventData::Icp(icp) => icp.witness_config.into(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    assert_eq!(witness_config.tally, threshold);
+
+    let sig = SelfSigningPrefix::Ed25519Sha512(vec![0; 64]);
+    let couplet = |i: usize| (witnesses[i].clone(), sig.clone());
+    let indexed = |i: u16| IndexedSignature::new_both_same(sig.clone(), i);
+
+    assert!(!witness_config.enough_receipts(vec![couplet(0)], vec![])?);
+    // Receipts of the same witness are counted once.
+    assert!(!witness_config.enough_receipts(vec![couplet(0)], vec![indexed(0)])?);
+    // Unknown witness index is ignored.
+    assert!(!witness_config.enough_receipts(vec![couplet(0)], vec![indexed(5)])?);
+    assert!(!witness_config.enough_receipts(vec![couplet(0), couplet(2)], vec![])?);
+    assert!(witness_config.enough_receipts(vec![couplet(0)], vec![indexed(1)])?);
+    assert!(witness_config.enough_receipts(vec![couplet(0), couplet(1), couplet(2)], vec![])?);
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Conditional requests of KELs. Actors serving KEL send digest of its
+//! latest event as `ETag` header. Clients polling for KEL updates send it
+//! back in `If-None-Match` header and receive `304 Not Modified` without
+//! body if no event was added since.
+use said::SelfAddressingIdentifier;
+
+/// Name of HTTP header carrying entity tag of response.
+pub const ETAG_HEADER: &str = "ETag";
+/// Name of HTTP header carrying entity tags known by client.
+pub const IF_NONE_MATCH_HEADER: &str = "If-None-Match";
+
+/// Result of conditional request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conditional<T> {
+    /// Nothing changed since response with known entity tag.
+    NotModified,
+    Modified {
+        body: T,
+        /// Entity tag to send in next request, if returned by remote actor.
+        etag: Option<String>,
+    },
+}
+
+impl<T> Conditional<T> {
+    pub fn is_modified(&self) -> bool {
+        matches!(self, Conditional::Modified { .. })
+    }
+}
+
+/// Returns entity tag of KEL, which last event has `last_digest`.
+pub fn kel_etag(last_digest: &SelfAddressingIdentifier) -> String {
+    format!("\"{}\"", last_digest)
+}
+
+/// Checks if `If-None-Match` header value contains `etag`. Weak tags are
+/// compared by their value.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.trim_start_matches("W/") == etag.trim_start_matches("W/")
+    })
+}
+
+#[test]
+fn test_etag_matches() {
+    let digest: SelfAddressingIdentifier = "EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen"
+        .parse()
+        .unwrap();
+    let etag = kel_etag(&digest);
+    assert_eq!(etag, "\"EBfxc4RiVY6saIFmUfEtETs1FcqmktZW88UkbnOg0Qen\"");
+
+    assert!(etag_matches(&etag, &etag));
+    assert!(etag_matches("*", &etag));
+    assert!(etag_matches(&format!("\"other\", W/{}", etag), &etag));
+    assert!(!etag_matches("\"other\"", &etag));
+    // Unquoted digest is not a valid tag.
+    assert!(!etag_matches(&digest.to_string(), &etag));
+}
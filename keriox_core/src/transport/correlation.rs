@@ -0,0 +1,143 @@
+//! Correlation IDs let multi-actor flows be traced across witness, watcher
+//! and controller logs. Actors read ID from incoming request header (or
+//! generate new one), process request within its scope and return it in
+//! response. [`DefaultTransport`](super::default::DefaultTransport) attaches
+//! ID of current scope to outgoing requests, so actors asked by the
+//! processing actor log the same ID.
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Name of HTTP header carrying correlation ID.
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+thread_local! {
+    static CURRENT: RefCell<Option<CorrelationId>> = RefCell::new(None);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Generates random ID.
+    pub fn generate() -> Self {
+        let bytes: [u8; 16] = rand::random();
+        Self(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Uses ID received from other actor if it's present and looks sane,
+    /// otherwise generates new one.
+    pub fn from_header(value: Option<&str>) -> Self {
+        match value {
+            Some(id)
+                if !id.is_empty()
+                    && id.len() <= 128
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                Self(id.to_string())
+            }
+            _ => Self::generate(),
+        }
+    }
+
+    /// Returns ID of scope that is currently executed, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Runs `f` with this ID set as current. Previous ID is restored
+    /// afterwards, also if `f` panics.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _guard = ScopeGuard {
+            previous: CURRENT.with(|current| current.replace(Some(self.clone()))),
+        };
+        f()
+    }
+
+    /// Wraps future, so this ID is current whenever it's polled, regardless
+    /// of thread that polls it.
+    pub fn instrument<F: Future>(&self, future: F) -> Instrumented<F> {
+        Instrumented {
+            id: self.clone(),
+            inner: Box::pin(future),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Restores ID of enclosing scope when dropped.
+struct ScopeGuard {
+    previous: Option<CorrelationId>,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        // Thread local can be already destroyed if guard is dropped during
+        // thread shutdown.
+        let _ = CURRENT.try_with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Future returned by [`CorrelationId::instrument`].
+pub struct Instrumented<F: Future> {
+    id: CorrelationId,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let id = this.id.clone();
+        id.scope(|| this.inner.as_mut().poll(cx))
+    }
+}
+
+#[test]
+fn test_correlation_scope() {
+    let id = CorrelationId::from_header(Some("req-1"));
+    assert_eq!(id.as_str(), "req-1");
+    // Unexpected characters aren't propagated.
+    assert_ne!(
+        CorrelationId::from_header(Some("a\r\nb")).as_str(),
+        "a\r\nb"
+    );
+
+    assert_eq!(CorrelationId::current(), None);
+    id.scope(|| {
+        assert_eq!(CorrelationId::current(), Some(id.clone()));
+        let nested = CorrelationId::generate();
+        nested.scope(|| assert_eq!(CorrelationId::current(), Some(nested.clone())));
+        assert_eq!(CorrelationId::current(), Some(id.clone()));
+    });
+    assert_eq!(CorrelationId::current(), None);
+
+    let current = futures::executor::block_on(id.instrument(async { CorrelationId::current() }));
+    assert_eq!(current, Some(id.clone()));
+
+    // Enclosing scope is restored when nested one panics.
+    id.scope(|| {
+        let nested = CorrelationId::generate();
+        let result = std::panic::catch_unwind(|| nested.scope(|| panic!("processing failed")));
+        assert!(result.is_err());
+        assert_eq!(CorrelationId::current(), Some(id.clone()));
+    });
+    assert_eq!(CorrelationId::current(), None);
+}
@@ -1,29 +1,331 @@
+use std::{future::Future, time::Duration};
+
 use serde::Deserialize;
 
-use super::{Transport, TransportError};
+use super::{
+    conditional::{Conditional, ETAG_HEADER, IF_NONE_MATCH_HEADER},
+    correlation::{CorrelationId, CORRELATION_ID_HEADER},
+    receipts_from_outcomes,
+    tcp::{TcpRequest, TcpResponse},
+    Transport, TransportError,
+};
 use crate::{
     actor::{
         parse_op_stream,
         simple_controller::{parse_response, PossibleResponse},
     },
-    event_message::signed_event_message::{Message, Op},
+    event_message::signed_event_message::{
+        Message, Notice, Op, SignedEventMessage, SignedNontransferableReceipt,
+    },
     oobi::{LocationScheme, Oobi, Role, Scheme},
     prefix::IdentifierPrefix,
-    query::query_event::SignedQueryMessage,
+    query::{provenance::SignedProvenance, query_event::SignedQueryMessage},
 };
+#[cfg(feature = "query")]
+use crate::{
+    prefix::CesrPrimitive,
+    query::envelope::{EnvelopeError, ResponseEnvelope, RESPONSE_ENVELOPE_HEADER},
+};
+
+/// Builds error from unsuccessful response. Uses error serialized in response
+/// body if possible, otherwise falls back to `Retry-After` header, which
+/// delay is limited to `max_retry_after`.
+pub async fn remote_error<E>(
+    resp: reqwest::Response,
+    max_retry_after: Duration,
+) -> TransportError<E>
+where
+    E: for<'a> Deserialize<'a>,
+{
+    let retry_after = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(|secs| secs.min(max_retry_after.as_secs()));
+    let body = match resp.text().await {
+        Ok(body) => body,
+        Err(e) => return request_error(e),
+    };
+    match (serde_json::from_str(&body), retry_after) {
+        (Ok(err), _) => TransportError::RemoteError(err),
+        (Err(_), Some(secs)) => TransportError::RetryAfter(secs),
+        (Err(_), None) => TransportError::UnknownError(body),
+    }
+}
+
+/// Distinguishes timeouts and refused connections from other network
+/// errors.
+pub fn request_error<E>(e: reqwest::Error) -> TransportError<E> {
+    if e.is_timeout() {
+        TransportError::Timeout(e.to_string())
+    } else if e.is_connect() {
+        TransportError::ConnectionRefused(e.to_string())
+    } else {
+        TransportError::NetworkError(e.to_string())
+    }
+}
+
+/// Timeouts and retry policy of [`DefaultTransport`] requests.
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// Maximal time of establishing connection with other actor.
+    pub connect_timeout: Duration,
+    /// Maximal time of the whole request, including reading response.
+    pub request_timeout: Duration,
+    /// How many times request is repeated. Requests are repeated if remote
+    /// actor asks to back off (`Retry-After`), or if they fail before
+    /// receiving response. Failed requests that could reach remote actor are
+    /// repeated only if they are idempotent, e.g. signed events and queries
+    /// aren't sent twice.
+    pub retries: u32,
+    /// Delay before first retry of failed request. It's doubled before each
+    /// next one.
+    pub backoff: Duration,
+    /// Maximal delay between retries of failed request.
+    pub max_backoff: Duration,
+    /// Maximal delay before repeating request, when remote actor asks to
+    /// back off. Longer `Retry-After` values are clamped to it, so
+    /// misbehaving actor can't stall requests indefinitely.
+    pub max_retry_after: Duration,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(60),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Delay before retry number `attempt`, counting from 0.
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt)
+            .and_then(|factor| self.backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+}
+
+/// Result of single request attempt.
+enum Attempt<R, E> {
+    Response(R),
+    /// Remote actor asked to repeat request after delay. Response is
+    /// returned if there are no retries left.
+    RetryAfter(Duration, R),
+    /// Request didn't get response. `sent` is false if it didn't reach
+    /// remote actor at all, so it's safe to repeat it.
+    Failed {
+        error: TransportError<E>,
+        sent: bool,
+    },
+}
+
+/// Reads `Retry-After` header value in seconds.
+fn retry_after(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Repeats `attempt` according to `config`. This is the only retry layer of
+/// requests sent with [`DefaultTransport`].
+async fn with_retries<R, E, F, Fut>(
+    config: &TransportConfig,
+    idempotent: bool,
+    attempt: F,
+) -> Result<R, TransportError<E>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Attempt<R, E>>,
+{
+    let mut retry = 0;
+    loop {
+        let delay = match attempt().await {
+            Attempt::Response(resp) => return Ok(resp),
+            Attempt::RetryAfter(delay, _) if retry < config.retries => {
+                delay.min(config.max_retry_after)
+            }
+            Attempt::RetryAfter(_, resp) => return Ok(resp),
+            Attempt::Failed { sent, .. } if retry < config.retries && (idempotent || !sent) => {
+                config.backoff_delay(retry)
+            }
+            Attempt::Failed { error, .. } => return Err(error),
+        };
+        futures_timer::Delay::new(delay).await;
+        retry += 1;
+    }
+}
 
 /// Default behavior for communication with other actors.
 /// Serializes a keri message, does a net request, and deserializes the response.
 pub struct DefaultTransport<E> {
+    /// Require query responses to be wrapped in integrity envelope signed by
+    /// queried actor. See [`ResponseEnvelope`].
+    verify_envelope: bool,
+    client: reqwest::Client,
+    config: TransportConfig,
     _phantom: std::marker::PhantomData<E>,
 }
 
 impl<E> DefaultTransport<E> {
     pub fn new() -> Self {
+        Self::new_with_config(TransportConfig::default())
+    }
+
+    /// Creates transport, that uses provided timeouts for all requests and
+    /// repeats failed ones according to `config`.
+    pub fn new_with_config(config: TransportConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .build()
+            .expect("Can't initialize HTTP client");
         Self {
+            verify_envelope: false,
+            client,
+            config,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Sends request built by `request`, repeating it according to
+    /// [`TransportConfig::retries`].
+    async fn send<F>(&self, request: F) -> Result<reqwest::Response, TransportError<E>>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let build = || {
+            let builder = match CorrelationId::current() {
+                Some(id) => request(&self.client).header(CORRELATION_ID_HEADER, id.as_str()),
+                None => request(&self.client),
+            };
+            builder.build().map_err(request_error)
+        };
+        let idempotent = build()?.method().is_idempotent();
+        with_retries(&self.config, idempotent, || async {
+            let request = match build() {
+                Ok(request) => request,
+                Err(error) => return Attempt::Failed { error, sent: true },
+            };
+            match self.client.execute(request).await {
+                Ok(resp) if !resp.status().is_success() => {
+                    let delay = retry_after(
+                        resp.headers()
+                            .get(reqwest::header::RETRY_AFTER)
+                            .and_then(|value| value.to_str().ok()),
+                    );
+                    match delay {
+                        Some(delay) => Attempt::RetryAfter(delay, resp),
+                        None => Attempt::Response(resp),
+                    }
+                }
+                Ok(resp) => Attempt::Response(resp),
+                Err(e) => Attempt::Failed {
+                    sent: !e.is_connect(),
+                    error: request_error(e),
+                },
+            }
+        })
+        .await
+    }
+
+    /// Sends request to actor listening on framed TCP, see
+    /// [`tcp`](super::tcp). Retries like [`DefaultTransport::send`].
+    #[cfg(feature = "tcp")]
+    async fn send_tcp(
+        &self,
+        loc: &LocationScheme,
+        request: TcpRequest,
+    ) -> Result<TcpResponse, TransportError<E>> {
+        let request = match CorrelationId::current() {
+            Some(id) => request.with_header(CORRELATION_ID_HEADER, id.as_str()),
+            None => request,
+        };
+        let idempotent = request.method == "GET";
+        with_retries(&self.config, idempotent, || async {
+            match super::tcp::request(&loc.url, &request, &self.config).await {
+                Ok(resp) if !(200..300).contains(&resp.status) => {
+                    match retry_after(resp.header("Retry-After")) {
+                        Some(delay) => Attempt::RetryAfter(delay, resp),
+                        None => Attempt::Response(resp),
+                    }
+                }
+                Ok(resp) => Attempt::Response(resp),
+                Err(error) => Attempt::Failed {
+                    sent: !matches!(error, TransportError::ConnectionRefused(_)),
+                    error,
+                },
+            }
+        })
+        .await
+    }
+
+    #[cfg(not(feature = "tcp"))]
+    async fn send_tcp(
+        &self,
+        _loc: &LocationScheme,
+        _request: TcpRequest,
+    ) -> Result<TcpResponse, TransportError<E>> {
+        Err(TransportError::NetworkError(
+            "TCP transport is not enabled".to_string(),
+        ))
+    }
+
+    /// Rejects query responses without valid envelope, so truncated or
+    /// modified KEL replays are detected.
+    pub fn with_envelope_verification(mut self) -> Self {
+        self.verify_envelope = true;
+        self
+    }
+}
+
+/// Checks if response envelope matches sent query and response body and is
+/// signed by queried actor.
+#[cfg(feature = "query")]
+fn verify_envelope(
+    loc: &LocationScheme,
+    query: &[u8],
+    envelope: Option<&str>,
+    body: &str,
+) -> Result<(), EnvelopeError> {
+    let envelope: ResponseEnvelope = envelope.ok_or(EnvelopeError::Missing)?.parse()?;
+    match &loc.eid {
+        IdentifierPrefix::Basic(signer) => envelope.verify(query, body.as_bytes(), signer),
+        other => Err(EnvelopeError::WrongSigner {
+            expected: other.to_string(),
+            got: envelope.signer.to_str(),
+        }),
+    }
+}
+
+#[cfg(feature = "query")]
+impl<E> DefaultTransport<E> {
+    /// Parses response body of `query`, checking its envelope if required.
+    fn query_response(
+        &self,
+        loc: &LocationScheme,
+        query: &[u8],
+        envelope: Option<&str>,
+        body: &str,
+    ) -> Result<PossibleResponse, TransportError<E>> {
+        use crate::actor::simple_controller::ResponseError;
+
+        if self.verify_envelope {
+            verify_envelope(loc, query, envelope, body)
+                .map_err(|e| TransportError::InvalidEnvelope(e.to_string()))?;
+        }
+        match parse_response(body) {
+            Ok(resp) => Ok(resp),
+            Err(ResponseError::EmptyResponse) => Err(TransportError::EmptyResponse),
+            Err(ResponseError::Unparsable(e)) => Err(TransportError::InvalidResponse(e)),
+        }
+    }
 }
 
 impl<E> Default for DefaultTransport<E> {
@@ -42,91 +344,106 @@ where
         loc: LocationScheme,
         msg: Message,
     ) -> Result<(), TransportError<E>> {
-        let url = match loc.scheme {
-            Scheme::Http => match &msg {
-                Message::Notice(_) => {
-                    // {url}/process
-                    loc.url.join("process").unwrap()
+        let path = match &msg {
+            Message::Notice(_) => "process",
+            Message::Op(op) => match op {
+                #[cfg(feature = "query")]
+                Op::Query(_) => {
+                    panic!("can't send query in send_message");
                 }
-                Message::Op(op) => match op {
-                    #[cfg(feature = "query")]
-                    Op::Query(_) => {
-                        panic!("can't send query in send_message");
-                    }
-                    #[cfg(feature = "query")]
-                    Op::Reply(_) => {
-                        // {url}/register
-                        loc.url.join("register").unwrap()
-                    }
-                    #[cfg(feature = "mailbox")]
-                    Op::Exchange(_) => {
-                        // {url}/forward
-                        loc.url.join("forward").unwrap()
-                    }
-                },
+                #[cfg(feature = "query")]
+                Op::Reply(_) => "register",
+                #[cfg(feature = "mailbox")]
+                Op::Exchange(_) => "forward",
             },
-            Scheme::Tcp => todo!(),
         };
-        let resp = reqwest::Client::new()
-            .post(url)
-            .body(msg.to_cesr().unwrap())
-            .send()
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+        let body = msg.to_cesr().unwrap();
+        if loc.scheme == Scheme::Tcp {
+            self.send_tcp(&loc, TcpRequest::new("POST", path, body))
+                .await?
+                .into_body_clamped(self.config.max_retry_after)?;
+            return Ok(());
+        }
+        // {url}/{path}
+        let url = loc.url.join(path).unwrap();
+        let resp = self
+            .send(|client| client.post(url.clone()).body(body.clone()))
+            .await?;
         if !resp.status().is_success() {
-            let body = resp
-                .text()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
-            let err =
-                serde_json::from_str(&body).map_err(|_e| TransportError::UnknownError(body))?;
-            return Err(TransportError::RemoteError(err));
+            return Err(remote_error(resp, self.config.max_retry_after).await);
         }
         Ok(())
     }
 
+    async fn send_event(
+        &self,
+        loc: LocationScheme,
+        event: SignedEventMessage,
+    ) -> Result<Vec<SignedNontransferableReceipt>, TransportError<E>> {
+        let body = Message::Notice(Notice::Event(event)).to_cesr().unwrap();
+        if loc.scheme == Scheme::Tcp {
+            let resp = self
+                .send_tcp(&loc, TcpRequest::new("POST", "process", body))
+                .await?
+                .into_body_clamped(self.config.max_retry_after)?;
+            return Ok(receipts_from_outcomes(&resp));
+        }
+        // {url}/process
+        let url = loc.url.join("process").unwrap();
+        let resp = self
+            .send(|client| client.post(url.clone()).body(body.clone()))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(remote_error(resp, self.config.max_retry_after).await);
+        }
+        let body = resp.bytes().await.map_err(request_error)?;
+        Ok(receipts_from_outcomes(&body))
+    }
+
     #[cfg(feature = "query")]
     async fn send_query(
         &self,
         loc: LocationScheme,
         qry: SignedQueryMessage,
     ) -> Result<PossibleResponse, TransportError<E>> {
-        use crate::actor::simple_controller::ResponseError;
-
-        let url = match loc.scheme {
-            Scheme::Http => {
-                // {url}/query
-                loc.url.join("query").unwrap()
-            }
-            Scheme::Tcp => todo!(),
-        };
-
         let op: Message = qry.into();
-        let resp = reqwest::Client::new()
-            .post(url)
-            .body(op.to_cesr().unwrap())
-            .send()
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
-        let status = resp.status();
-        let body = resp
-            .text()
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
-        if status.is_success() {
-            match parse_response(&body) {
-                Ok(resp) => Ok(resp),
-                Err(ResponseError::EmptyResponse) => Err(TransportError::EmptyResponse),
-                Err(ResponseError::Unparsable(e)) => Err(TransportError::InvalidResponse(e)),
-            }
+        let query = op.to_cesr().unwrap();
+        if loc.scheme == Scheme::Tcp {
+            let resp = self
+                .send_tcp(&loc, TcpRequest::new("POST", "query", query.clone()))
+                .await?;
+            let envelope = resp.header(RESPONSE_ENVELOPE_HEADER).map(str::to_string);
+            let body = String::from_utf8(resp.into_body_clamped(self.config.max_retry_after)?)
+                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+            return self.query_response(&loc, &query, envelope.as_deref(), &body);
+        }
+        // {url}/query
+        let url = loc.url.join("query").unwrap();
+        let resp = self
+            .send(|client| client.post(url.clone()).body(query.clone()))
+            .await?;
+        if resp.status().is_success() {
+            let envelope = resp
+                .headers()
+                .get(RESPONSE_ENVELOPE_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body = resp.text().await.map_err(request_error)?;
+            self.query_response(&loc, &query, envelope.as_deref(), &body)
         } else {
-            let err =
-                serde_json::from_str(&body).map_err(|_| TransportError::UnknownError(body))?;
-            Err(TransportError::RemoteError(err))
+            Err(remote_error(resp, self.config.max_retry_after).await)
         }
     }
 
     async fn request_loc_scheme(&self, loc: LocationScheme) -> Result<Vec<Op>, TransportError<E>> {
+        if loc.scheme == Scheme::Tcp {
+            let path = format!("oobi/{}", loc.eid);
+            let body = self
+                .send_tcp(&loc, TcpRequest::new("GET", path, vec![]))
+                .await?
+                .into_body_clamped(self.config.max_retry_after)?;
+            return Ok(parse_op_stream(&body)?);
+        }
         // {url}/oobi/{eid}
         let url = loc
             .url
@@ -134,24 +451,13 @@ where
             .unwrap()
             .join(&loc.eid.to_string())
             .unwrap();
-        let resp = reqwest::get(url)
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+        let resp = self.send(|client| client.get(url.clone())).await?;
         if resp.status().is_success() {
-            let body = resp
-                .bytes()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+            let body = resp.bytes().await.map_err(request_error)?;
             let ops = parse_op_stream(&body)?;
             Ok(ops)
         } else {
-            let body = resp
-                .text()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
-            let err =
-                serde_json::from_str(&body).map_err(|_e| TransportError::UnknownError(body))?;
-            Err(TransportError::RemoteError(err))
+            Err(remote_error(resp, self.config.max_retry_after).await)
         }
     }
 
@@ -162,6 +468,20 @@ where
         role: Role,
         eid: IdentifierPrefix,
     ) -> Result<Vec<u8>, TransportError<E>> {
+        let role = match role {
+            Role::Witness => "witness",
+            Role::Watcher => "watcher",
+            Role::Controller => "controller",
+            Role::Messagebox => "messagebox",
+            Role::Agent => "agent",
+        };
+        if loc.scheme == Scheme::Tcp {
+            let path = format!("oobi/{}/{}/{}", cid, role, eid);
+            return self
+                .send_tcp(&loc, TcpRequest::new("GET", path, vec![]))
+                .await?
+                .into_body_clamped(self.config.max_retry_after);
+        }
         // {url}/oobi/{cid}/{role}/{eid}
         let url = loc
             .url
@@ -169,51 +489,160 @@ where
             .unwrap()
             .join(&format!("{}/", &cid.to_string()))
             .unwrap()
-            .join(match role {
-                Role::Witness => "witness/",
-                Role::Watcher => "watcher/",
-                Role::Controller => "controller/",
-                Role::Messagebox => "messagebox/",
-            })
+            .join(&format!("{}/", role))
             .unwrap()
             .join(&eid.to_string())
             .unwrap();
-        let resp = reqwest::get(url)
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+        let resp = self.send(|client| client.get(url.clone())).await?;
         if resp.status().is_success() {
-            let body = resp
-                .bytes()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+            let body = resp.bytes().await.map_err(request_error)?;
 
             let ops = body.to_vec();
             Ok(ops)
         } else {
-            let body = resp
-                .text()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
-            let err =
-                serde_json::from_str(&body).map_err(|_e| TransportError::UnknownError(body))?;
-            Err(TransportError::RemoteError(err))
+            Err(remote_error(resp, self.config.max_retry_after).await)
+        }
+    }
+
+    async fn request_oobis(
+        &self,
+        loc: LocationScheme,
+        cid: IdentifierPrefix,
+    ) -> Result<Vec<u8>, TransportError<E>> {
+        if loc.scheme == Scheme::Tcp {
+            let path = format!("oobis/{}", cid);
+            return self
+                .send_tcp(&loc, TcpRequest::new("GET", path, vec![]))
+                .await?
+                .into_body_clamped(self.config.max_retry_after);
+        }
+        // {url}/oobis/{cid}
+        let url = loc
+            .url
+            .join("oobis/")
+            .unwrap()
+            .join(&cid.to_string())
+            .unwrap();
+        let resp = self.send(|client| client.get(url.clone())).await?;
+        if resp.status().is_success() {
+            let body = resp.bytes().await.map_err(request_error)?;
+            Ok(body.to_vec())
+        } else {
+            Err(remote_error(resp, self.config.max_retry_after).await)
+        }
+    }
+
+    async fn request_oobis_if_changed(
+        &self,
+        loc: LocationScheme,
+        cid: IdentifierPrefix,
+        etag: Option<String>,
+    ) -> Result<Conditional<Vec<u8>>, TransportError<E>> {
+        if loc.scheme == Scheme::Tcp {
+            let request = TcpRequest::new("GET", format!("oobis/{}", cid), vec![]);
+            let request = match &etag {
+                Some(etag) => request.with_header(IF_NONE_MATCH_HEADER, etag),
+                None => request,
+            };
+            let resp = self.send_tcp(&loc, request).await?;
+            if resp.status == 304 {
+                return Ok(Conditional::NotModified);
+            }
+            let etag = resp.header(ETAG_HEADER).map(str::to_string);
+            return Ok(Conditional::Modified {
+                body: resp.into_body_clamped(self.config.max_retry_after)?,
+                etag,
+            });
+        }
+        // {url}/oobis/{cid}
+        let url = loc
+            .url
+            .join("oobis/")
+            .unwrap()
+            .join(&cid.to_string())
+            .unwrap();
+        let resp = self
+            .send(|client| match &etag {
+                Some(etag) => client
+                    .get(url.clone())
+                    .header(IF_NONE_MATCH_HEADER, etag.as_str()),
+                None => client.get(url.clone()),
+            })
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            Ok(Conditional::NotModified)
+        } else if resp.status().is_success() {
+            let etag = resp
+                .headers()
+                .get(ETAG_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body = resp.bytes().await.map_err(request_error)?;
+            Ok(Conditional::Modified {
+                body: body.to_vec(),
+                etag,
+            })
+        } else {
+            Err(remote_error(resp, self.config.max_retry_after).await)
+        }
+    }
+
+    async fn request_provenance(
+        &self,
+        loc: LocationScheme,
+        id: IdentifierPrefix,
+        vc_id: Option<IdentifierPrefix>,
+    ) -> Result<SignedProvenance, TransportError<E>> {
+        let path = match &vc_id {
+            Some(vc_id) => format!("provenance/{}/{}", id, vc_id),
+            None => format!("provenance/{}", id),
+        };
+        let body = if loc.scheme == Scheme::Tcp {
+            self.send_tcp(&loc, TcpRequest::new("GET", path, vec![]))
+                .await?
+                .into_body_clamped(self.config.max_retry_after)?
+        } else {
+            // {url}/provenance/{id}[/{vc_id}]
+            let url = loc.url.join(&path).unwrap();
+            let resp = self.send(|client| client.get(url.clone())).await?;
+            if !resp.status().is_success() {
+                return Err(remote_error(resp, self.config.max_retry_after).await);
+            }
+            resp.bytes().await.map_err(request_error)?.to_vec()
+        };
+        serde_json::from_slice(&body).map_err(|e| TransportError::UnknownError(e.to_string()))
+    }
+
+    async fn request_introduction(
+        &self,
+        url: url::Url,
+    ) -> Result<LocationScheme, TransportError<E>> {
+        // {url}/introduce
+        let url = url.join("introduce").unwrap();
+        let resp = self.send(|client| client.get(url.clone())).await?;
+        if resp.status().is_success() {
+            let body = resp.bytes().await.map_err(request_error)?;
+            serde_json::from_slice(&body).map_err(|e| TransportError::UnknownError(e.to_string()))
+        } else {
+            Err(remote_error(resp, self.config.max_retry_after).await)
         }
     }
 
     async fn resolve_oobi(&self, loc: LocationScheme, oobi: Oobi) -> Result<(), TransportError<E>> {
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(format!("{}resolve", loc.url))
-            .body(serde_json::to_string(&oobi).unwrap())
-            .send()
-            .await
-            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+        let body = serde_json::to_string(&oobi).unwrap();
+        if loc.scheme == Scheme::Tcp {
+            self.send_tcp(&loc, TcpRequest::new("POST", "resolve", body.into_bytes()))
+                .await?
+                .into_body_clamped(self.config.max_retry_after)?;
+            return Ok(());
+        }
+        let url = format!("{}resolve", loc.url);
+        let resp = self
+            .send(|client| client.post(&url).body(body.clone()))
+            .await?;
 
         if !resp.status().is_success() {
-            let body = resp
-                .text()
-                .await
-                .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+            let body = resp.text().await.map_err(request_error)?;
             let err = serde_json::from_str(&body)
                 .map_err(|e| TransportError::NetworkError(e.to_string()))?;
             return Err(TransportError::RemoteError(err));
@@ -221,3 +650,95 @@ where
         Ok(())
     }
 }
+
+#[test]
+fn test_backoff_delay() {
+    let config = TransportConfig {
+        backoff: Duration::from_millis(500),
+        max_backoff: Duration::from_secs(30),
+        ..Default::default()
+    };
+    assert_eq!(config.backoff_delay(0), Duration::from_millis(500));
+    assert_eq!(config.backoff_delay(3), Duration::from_secs(4));
+    assert_eq!(config.backoff_delay(10), Duration::from_secs(30));
+    // Doesn't overflow.
+    assert_eq!(config.backoff_delay(32), Duration::from_secs(30));
+    assert_eq!(config.backoff_delay(u32::MAX), Duration::from_secs(30));
+    let config = TransportConfig {
+        backoff: Duration::MAX,
+        max_backoff: Duration::MAX,
+        ..Default::default()
+    };
+    assert_eq!(config.backoff_delay(1), Duration::MAX);
+}
+
+#[test]
+fn test_retries() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::executor::block_on;
+
+    type Error = TransportError<crate::actor::error::ActorError>;
+    let config = TransportConfig {
+        retries: 3,
+        backoff: Duration::ZERO,
+        ..Default::default()
+    };
+    let attempts = AtomicUsize::new(0);
+    let failing = |sent| {
+        attempts.store(0, Ordering::SeqCst);
+        let attempts = &attempts;
+        move || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Attempt::<(), _>::Failed {
+                error: Error::Timeout("timeout".to_string()),
+                sent,
+            }
+        }
+    };
+
+    // Requests that didn't reach remote actor are repeated.
+    let result = block_on(with_retries(&config, false, failing(false)));
+    assert!(matches!(result, Err(TransportError::Timeout(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+    // Non-idempotent requests that could reach it aren't.
+    let result = block_on(with_retries(&config, false, failing(true)));
+    assert!(matches!(result, Err(TransportError::Timeout(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+    let result = block_on(with_retries(&config, true, failing(true)));
+    assert!(result.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+    // Remote actor asks to back off, response is returned when retries run
+    // out.
+    let attempts = AtomicUsize::new(0);
+    let result: Result<_, Error> = block_on(with_retries(&config, false, || async {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Attempt::RetryAfter(Duration::ZERO, 429),
+            _ => Attempt::Response(200),
+        }
+    }));
+    assert_eq!(result.unwrap(), 200);
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+    let attempts = AtomicUsize::new(0);
+    let result: Result<_, Error> = block_on(with_retries(&config, false, || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Attempt::RetryAfter(Duration::ZERO, 429)
+    }));
+    assert_eq!(result.unwrap(), 429);
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+    // Requested delay is clamped to `max_retry_after`.
+    let config = TransportConfig {
+        retries: 1,
+        max_retry_after: Duration::ZERO,
+        ..Default::default()
+    };
+    let result: Result<_, Error> = block_on(with_retries(&config, false, || async {
+        Attempt::RetryAfter(Duration::from_secs(u32::MAX.into()), 429)
+    }));
+    assert_eq!(result.unwrap(), 429);
+}
@@ -1,20 +1,26 @@
-use std::error::Error;
+use std::{error::Error, time::Duration};
 
 use serde::Deserialize;
 
+use self::conditional::Conditional;
 use crate::{
-    actor::{error::ActorError, simple_controller::PossibleResponse},
+    actor::{error::ActorError, parse_event_stream, simple_controller::PossibleResponse},
     event_message::{
         cesr_adapter::ParseError,
-        signed_event_message::{Message, Op},
+        signed_event_message::{
+            Message, Notice, Op, SignedEventMessage, SignedNontransferableReceipt,
+        },
     },
     oobi::{LocationScheme, Oobi, Role},
     prefix::IdentifierPrefix,
-    query::query_event::SignedQueryMessage,
+    query::{provenance::SignedProvenance, query_event::SignedQueryMessage},
 };
 
+pub mod conditional;
+pub mod correlation;
 pub mod default;
 // pub mod http;
+pub mod tcp;
 pub mod test;
 
 /// Transport trait allows customizing behavior of actors when it comes to making net requests.
@@ -34,6 +40,19 @@ where
         msg: Message,
     ) -> Result<(), TransportError<E>>;
 
+    /// Send key event to witness and return receipts it issued for the
+    /// event in response. Should use `process` endpoint. By default response
+    /// isn't inspected, so no receipts are returned.
+    async fn send_event(
+        &self,
+        loc: LocationScheme,
+        event: SignedEventMessage,
+    ) -> Result<Vec<SignedNontransferableReceipt>, TransportError<E>> {
+        self.send_message(loc, Message::Notice(Notice::Event(event)))
+            .await?;
+        Ok(vec![])
+    }
+
     #[cfg(feature = "query")]
     /// Send a query to other actor and return its response.
     async fn send_query(
@@ -58,21 +77,123 @@ where
         eid: IdentifierPrefix,
     ) -> Result<Vec<u8>, TransportError<E>>;
 
+    /// Request all end roles of id together with location schemes of
+    /// designated endpoint providers from other actor.
+    /// Should use `get_cid_oobis` endpoint.
+    /// Returns KEL of id followed by end role and loc scheme replies.
+    /// By default OOBI set requests aren't supported.
+    async fn request_oobis(
+        &self,
+        _loc: LocationScheme,
+        _cid: IdentifierPrefix,
+    ) -> Result<Vec<u8>, TransportError<E>> {
+        Err(TransportError::UnknownError(
+            "OOBI set requests are not supported".to_string(),
+        ))
+    }
+
+    /// Like [`request_oobis`](Transport::request_oobis), but returns
+    /// [`Conditional::NotModified`] if KEL of id didn't change since response
+    /// with `etag`. See [`conditional`]. By default request isn't
+    /// conditional.
+    async fn request_oobis_if_changed(
+        &self,
+        loc: LocationScheme,
+        cid: IdentifierPrefix,
+        _etag: Option<String>,
+    ) -> Result<Conditional<Vec<u8>>, TransportError<E>> {
+        Ok(Conditional::Modified {
+            body: self.request_oobis(loc, cid).await?,
+            etag: None,
+        })
+    }
+
+    /// Request watcher's signed provenance of KEL of `id`, or of TEL of
+    /// `vc_id` if registry `id` is provided with it.
+    /// Should use `provenance` endpoint. By default provenance isn't
+    /// supported.
+    async fn request_provenance(
+        &self,
+        _loc: LocationScheme,
+        _id: IdentifierPrefix,
+        _vc_id: Option<IdentifierPrefix>,
+    ) -> Result<SignedProvenance, TransportError<E>> {
+        Err(TransportError::UnknownError(
+            "Provenance requests are not supported".to_string(),
+        ))
+    }
+
+    /// Request location scheme that actor at `url` uses to introduce
+    /// itself. Should use `introduce` endpoint. Returned location scheme
+    /// isn't signed, so it should be resolved before use. By default
+    /// introduction isn't supported.
+    async fn request_introduction(
+        &self,
+        _url: url::Url,
+    ) -> Result<LocationScheme, TransportError<E>> {
+        Err(TransportError::UnknownError(
+            "Introduction requests are not supported".to_string(),
+        ))
+    }
+
     /// Orders other actor to [`request_loc_scheme`](Transport::request_loc_scheme) or [`request_end_role`](Transport::request_end_role) and save result to its DB.
     /// Should use `resolve` endpoint.
     async fn resolve_oobi(&self, loc: LocationScheme, oobi: Oobi) -> Result<(), TransportError<E>>;
 }
 
+/// Outcome of processing event, returned by witness `process` endpoint.
+/// Only the receipt is needed by transports.
+#[derive(Deserialize)]
+struct ProcessingOutcome {
+    receipt: Option<String>,
+}
+
+/// Returns receipts from body of witness `process` endpoint response.
+/// Responses of witnesses that don't report outcome of processing have no
+/// receipts.
+pub fn receipts_from_outcomes(body: &[u8]) -> Vec<SignedNontransferableReceipt> {
+    serde_json::from_slice::<Vec<ProcessingOutcome>>(body)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|outcome| outcome.receipt)
+        .flat_map(|receipt| parse_event_stream(receipt.as_bytes()).unwrap_or_default())
+        .filter_map(|msg| match msg {
+            Message::Notice(Notice::NontransferableRct(rct)) => Some(rct),
+            _ => None,
+        })
+        .collect()
+}
+
 #[derive(Debug, thiserror::Error, serde::Serialize, serde::Deserialize)]
 pub enum TransportError<E = ActorError> {
     #[error("network error: {0}")]
     NetworkError(String),
+    #[error("request timed out: {0}")]
+    Timeout(String),
+    #[error("connection refused: {0}")]
+    ConnectionRefused(String),
     #[error("Empty response")]
     EmptyResponse,
     #[error("Invalid response: {0}")]
     InvalidResponse(#[from] ParseError),
+    #[error("Invalid response envelope: {0}")]
+    InvalidEnvelope(String),
     #[error("Unknown error: {0}")]
     UnknownError(String),
     #[error("remote error: {0}")]
     RemoteError(E),
+    #[error("retry after {0} seconds")]
+    RetryAfter(u64),
+}
+
+impl TransportError {
+    /// Returns time after which request can be repeated, if remote actor
+    /// asked to back off.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            TransportError::RetryAfter(secs) => Some(Duration::from_secs(*secs)),
+            TransportError::RemoteError(err) => err.retry_after(),
+            _ => None,
+        }
+    }
 }
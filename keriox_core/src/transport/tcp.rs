@@ -0,0 +1,355 @@
+//! Framed TCP transport, for deployments that avoid HTTP. Every frame starts
+//! with 4 byte big-endian length of its payload. Request payload consists of
+//! request line (`<method> <path>`, the same as HTTP endpoint), header lines
+//! (`<name>: <value>`), empty line and body, which is CESR stream or JSON as
+//! in HTTP request. Response payload starts with HTTP status code line
+//! instead of request line. Connection can carry many requests, each
+//! followed by one response.
+use std::{fmt::Display, time::Duration};
+
+use serde::Deserialize;
+
+use super::TransportError;
+
+/// Default maximal size of frame payload in bytes.
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FrameError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("frame of {0} bytes exceeds size limit")]
+    TooLarge(usize),
+    #[error("malformed frame: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpRequest {
+    pub method: String,
+    /// Path of HTTP endpoint, e.g. `/process` or `/oobi/{eid}`.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpResponse {
+    /// HTTP status code.
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TcpRequest {
+    pub fn new(method: &str, path: impl Display, body: Vec<u8>) -> Self {
+        Self {
+            method: method.to_string(),
+            path: format!("/{}", path.to_string().trim_start_matches('/')),
+            headers: vec![],
+            body,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Display) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Returns path split into segments, without leading slash.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.trim_start_matches('/').split('/').collect()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode(
+            &format!("{} {}", self.method, self.path),
+            &self.headers,
+            &self.body,
+        )
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self, FrameError> {
+        let (line, headers, body) = decode(payload)?;
+        let (method, path) = line
+            .split_once(' ')
+            .ok_or_else(|| FrameError::Malformed(format!("invalid request line {}", line)))?;
+        Ok(Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers,
+            body,
+        })
+    }
+}
+
+impl TcpResponse {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200, body)
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Display) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns body of successful response, or error sent by remote actor.
+    pub fn into_body<E>(self) -> Result<Vec<u8>, TransportError<E>>
+    where
+        E: for<'a> Deserialize<'a>,
+    {
+        self.into_body_clamped(Duration::MAX)
+    }
+
+    /// Like [`TcpResponse::into_body`], but delay requested with
+    /// `Retry-After` header is limited to `max_retry_after`.
+    pub fn into_body_clamped<E>(
+        self,
+        max_retry_after: Duration,
+    ) -> Result<Vec<u8>, TransportError<E>>
+    where
+        E: for<'a> Deserialize<'a>,
+    {
+        if self.is_success() {
+            return Ok(self.body);
+        }
+        let retry_after = self
+            .header("Retry-After")
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(|secs| secs.min(max_retry_after.as_secs()));
+        match (serde_json::from_slice(&self.body), retry_after) {
+            (Ok(err), _) => Err(TransportError::RemoteError(err)),
+            (Err(_), Some(secs)) => Err(TransportError::RetryAfter(secs)),
+            (Err(_), None) => Err(TransportError::UnknownError(
+                String::from_utf8_lossy(&self.body).to_string(),
+            )),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode(&self.status.to_string(), &self.headers, &self.body)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<Self, FrameError> {
+        let (line, headers, body) = decode(payload)?;
+        let status = line
+            .parse()
+            .map_err(|_| FrameError::Malformed(format!("invalid status line {}", line)))?;
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn encode(line: &str, headers: &[(String, String)], body: &[u8]) -> Vec<u8> {
+    let mut head = format!("{}\n", line);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\n", name, value));
+    }
+    head.push('\n');
+    let mut payload = head.into_bytes();
+    payload.extend_from_slice(body);
+    payload
+}
+
+fn decode(payload: &[u8]) -> Result<(String, Vec<(String, String)>, Vec<u8>), FrameError> {
+    let end = payload
+        .windows(2)
+        .position(|window| window == b"\n\n")
+        .ok_or_else(|| FrameError::Malformed("missing end of head".to_string()))?;
+    let head =
+        std::str::from_utf8(&payload[..end]).map_err(|e| FrameError::Malformed(e.to_string()))?;
+    let mut lines = head.lines();
+    let line = lines.next().unwrap_or_default().to_string();
+    let headers = lines
+        .map(|header| {
+            header
+                .split_once(": ")
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .ok_or_else(|| FrameError::Malformed(format!("invalid header {}", header)))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((line, headers, payload[end + 2..].to_vec()))
+}
+
+#[cfg(feature = "tcp")]
+pub use io::{read_frame, request, serve, write_frame};
+
+#[cfg(feature = "tcp")]
+mod io {
+    use std::{future::Future, time::Duration};
+
+    use tokio::{
+        io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use url::Url;
+
+    use super::{FrameError, TcpRequest, TcpResponse};
+    use crate::transport::{default::TransportConfig, TransportError};
+
+    /// Reads one frame. Returns `None` if connection was closed before it
+    /// started.
+    pub async fn read_frame<R: AsyncRead + Unpin>(
+        reader: &mut R,
+        max_size: usize,
+    ) -> Result<Option<Vec<u8>>, FrameError> {
+        let mut len = [0u8; 4];
+        match reader.read_exact(&mut len).await {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let len = u32::from_be_bytes(len) as usize;
+        if len > max_size {
+            return Err(FrameError::TooLarge(len));
+        }
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload).await?;
+        Ok(Some(payload))
+    }
+
+    pub async fn write_frame<W: AsyncWrite + Unpin>(
+        writer: &mut W,
+        payload: &[u8],
+    ) -> Result<(), FrameError> {
+        let len = u32::try_from(payload.len()).map_err(|_| FrameError::TooLarge(payload.len()))?;
+        writer.write_all(&len.to_be_bytes()).await?;
+        writer.write_all(payload).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Sends request to actor listening on host and port of `url` and
+    /// returns its response. Connection is closed afterwards.
+    pub async fn request<E>(
+        url: &Url,
+        request: &TcpRequest,
+        config: &TransportConfig,
+    ) -> Result<TcpResponse, TransportError<E>> {
+        let addr = format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port().unwrap_or_default()
+        );
+        let mut stream = tokio::time::timeout(config.connect_timeout, TcpStream::connect(&addr))
+            .await
+            .map_err(|_| TransportError::Timeout(format!("connecting to {}", addr)))?
+            .map_err(|e| TransportError::ConnectionRefused(e.to_string()))?;
+        let exchange = async {
+            write_frame(&mut stream, &request.encode()).await?;
+            read_frame(&mut stream, super::MAX_FRAME_SIZE)
+                .await?
+                .ok_or_else(|| FrameError::Malformed("connection closed".to_string()))
+        };
+        let payload = tokio::time::timeout(config.request_timeout, exchange)
+            .await
+            .map_err(|_| TransportError::Timeout(format!("request to {}", addr)))?
+            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+        TcpResponse::decode(&payload).map_err(|e| TransportError::NetworkError(e.to_string()))
+    }
+
+    /// Accepts connections and answers their requests with `handler`, until
+    /// listener fails. Connections idle for longer than `idle_timeout` are
+    /// closed.
+    pub async fn serve<H, F>(
+        listener: TcpListener,
+        idle_timeout: Duration,
+        handler: H,
+    ) -> std::io::Result<()>
+    where
+        H: Fn(TcpRequest) -> F + Clone + Send + Sync + 'static,
+        F: Future<Output = TcpResponse> + Send,
+    {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                loop {
+                    let payload = match tokio::time::timeout(
+                        idle_timeout,
+                        read_frame(&mut stream, super::MAX_FRAME_SIZE),
+                    )
+                    .await
+                    {
+                        Ok(Ok(Some(payload))) => payload,
+                        // Closed, idle or sending invalid frames.
+                        Ok(Ok(None)) | Ok(Err(_)) | Err(_) => break,
+                    };
+                    let response = match TcpRequest::decode(&payload) {
+                        Ok(request) => handler(request).await,
+                        Err(e) => TcpResponse::new(400, e.to_string()),
+                    };
+                    if write_frame(&mut stream, &response.encode()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[test]
+fn test_frame_encoding() {
+    let request = TcpRequest::new("POST", "process", br#"{"v":"KERI10JSON"}"#.to_vec())
+        .with_header("X-Correlation-Id", "abc");
+    let decoded = TcpRequest::decode(&request.encode()).unwrap();
+    assert_eq!(decoded, request);
+    assert_eq!(decoded.path, "/process");
+    assert_eq!(decoded.segments(), vec!["process"]);
+    assert_eq!(decoded.header("x-correlation-id"), Some("abc"));
+
+    // Empty body and body containing empty lines.
+    let response = TcpResponse::ok(vec![]);
+    assert_eq!(TcpResponse::decode(&response.encode()).unwrap(), response);
+    let response = TcpResponse::ok(b"a\n\nb".to_vec()).with_header("Retry-After", 5);
+    assert_eq!(TcpResponse::decode(&response.encode()).unwrap(), response);
+
+    // Errors are deserialized like in HTTP responses.
+    let err = TcpResponse::new(503, "busy")
+        .with_header("Retry-After", 5)
+        .into_body::<crate::actor::error::ActorError>();
+    assert!(matches!(err, Err(TransportError::RetryAfter(5))));
+    let err = TcpResponse::new(503, "busy")
+        .with_header("Retry-After", 86400)
+        .into_body_clamped::<crate::actor::error::ActorError>(Duration::from_secs(60));
+    assert!(matches!(err, Err(TransportError::RetryAfter(60))));
+
+    assert!(matches!(
+        TcpResponse::decode(b"200"),
+        Err(FrameError::Malformed(_))
+    ));
+    assert!(matches!(
+        TcpRequest::decode(b"GET\n\n"),
+        Err(FrameError::Malformed(_))
+    ));
+}
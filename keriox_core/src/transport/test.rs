@@ -5,15 +5,27 @@ use serde::Deserialize;
 use super::{Transport, TransportError};
 use crate::{
     actor::{error::ActorError, simple_controller::PossibleResponse},
-    event_message::signed_event_message::{Message, Op},
+    event_message::signed_event_message::{
+        Message, Notice, Op, SignedEventMessage, SignedNontransferableReceipt,
+    },
     oobi::{LocationScheme, Oobi, Role},
     prefix::IdentifierPrefix,
-    query::query_event::SignedQueryMessage,
+    query::{provenance::SignedProvenance, query_event::SignedQueryMessage},
 };
 
 #[async_trait::async_trait]
 pub trait TestActor<E: Error = ActorError> {
     async fn send_message(&self, msg: Message) -> Result<(), E>;
+    /// Processes key event and returns receipts issued for it. By default
+    /// no receipts are returned.
+    async fn send_event(
+        &self,
+        event: SignedEventMessage,
+    ) -> Result<Vec<SignedNontransferableReceipt>, E> {
+        self.send_message(Message::Notice(Notice::Event(event)))
+            .await?;
+        Ok(vec![])
+    }
     async fn send_query(&self, query: SignedQueryMessage) -> Result<PossibleResponse, E>;
     async fn request_loc_scheme(&self, eid: IdentifierPrefix) -> Result<Vec<Op>, E>;
     async fn request_end_role(
@@ -22,6 +34,16 @@ pub trait TestActor<E: Error = ActorError> {
         role: Role,
         eid: IdentifierPrefix,
     ) -> Result<Vec<u8>, E>;
+    /// Returns KEL of `cid` followed by its end role and location scheme
+    /// replies. By default actor doesn't know any OOBIs.
+    async fn request_oobis(&self, _cid: IdentifierPrefix) -> Result<Vec<u8>, E> {
+        Ok(vec![])
+    }
+    async fn request_provenance(
+        &self,
+        id: IdentifierPrefix,
+        vc_id: Option<IdentifierPrefix>,
+    ) -> Result<SignedProvenance, E>;
     async fn resolve_oobi(&self, msg: Oobi) -> Result<(), E>;
 }
 
@@ -77,6 +99,24 @@ where
         Ok(())
     }
 
+    async fn send_event(
+        &self,
+        loc: LocationScheme,
+        event: SignedEventMessage,
+    ) -> Result<Vec<SignedNontransferableReceipt>, TransportError<E>> {
+        let (host, port) = match loc.url.origin() {
+            url::Origin::Tuple(_scheme, host, port) => (host, port),
+            _ => return Err(TransportError::NetworkError("Wrong url".to_string())),
+        };
+
+        self.actors
+            .get(&(host, port))
+            .ok_or(TransportError::NetworkError("No such actor".to_string()))?
+            .send_event(event)
+            .await
+            .map_err(|err| TransportError::RemoteError(err))
+    }
+
     async fn send_query(
         &self,
         loc: LocationScheme,
@@ -138,6 +178,46 @@ where
         Ok(ops)
     }
 
+    async fn request_oobis(
+        &self,
+        loc: LocationScheme,
+        cid: IdentifierPrefix,
+    ) -> Result<Vec<u8>, TransportError<E>> {
+        let (host, port) = match loc.url.origin() {
+            url::Origin::Tuple(_scheme, host, port) => (host, port),
+            _ => return Err(TransportError::NetworkError("Wrong url".into())),
+        };
+
+        let oobis = self
+            .actors
+            .get(&(host, port))
+            .ok_or(TransportError::NetworkError("Unknown actor".into()))?
+            .request_oobis(cid)
+            .await
+            .map_err(|e| TransportError::NetworkError(e.to_string()))?;
+
+        Ok(oobis)
+    }
+
+    async fn request_provenance(
+        &self,
+        loc: LocationScheme,
+        id: IdentifierPrefix,
+        vc_id: Option<IdentifierPrefix>,
+    ) -> Result<SignedProvenance, TransportError<E>> {
+        let (host, port) = match loc.url.origin() {
+            url::Origin::Tuple(_scheme, host, port) => (host, port),
+            _ => return Err(TransportError::NetworkError("Wrong url".into())),
+        };
+
+        self.actors
+            .get(&(host, port))
+            .ok_or(TransportError::NetworkError("Unknown actor".into()))?
+            .request_provenance(id, vc_id)
+            .await
+            .map_err(|err| TransportError::RemoteError(err))
+    }
+
     async fn resolve_oobi(&self, loc: LocationScheme, oobi: Oobi) -> Result<(), TransportError<E>> {
         let (host, port) = match loc.url.origin() {
             url::Origin::Tuple(_scheme, host, port) => (host, port),
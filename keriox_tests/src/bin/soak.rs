@@ -0,0 +1,756 @@
+//! Long running soak test of the whole network. Spins up a pool of
+//! witnesses, a watcher and many controllers communicating over test
+//! transport, drives random operations on them and periodically checks
+//! invariants: identifiers' states are the same in controllers and
+//! witnesses, no events are stuck in escrow and states learned from watcher
+//! don't conflict with identifiers' KELs.
+//!
+//! Run with:
+//! `cargo run --release -p keri-tests --features soak --bin soak`
+//!
+//! It can be configured with environment variables:
+//! - `SOAK_DURATION_SECS`: how long to run, 1 hour by default,
+//! - `SOAK_CONTROLLERS`: maximal number of controllers, 24 by default,
+//! - `SOAK_WITNESSES`: number of witnesses, 5 by default,
+//! - `SOAK_SEED`: seed of random operations. It's printed at start, so the
+//!   same sequence of operations can be repeated.
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use async_std::task::sleep;
+use keri_controller::{
+    controller::Controller, identifier::Identifier, mailbox_updating::ActionRequired, BasicPrefix,
+    CryptoBox, EndRole, IdentifierPrefix, KeyManager, LocationScheme, Oobi, SelfSigningPrefix,
+};
+use keri_core::{
+    actor::{
+        error::ActorError,
+        prelude::{HashFunction, HashFunctionCode, SelfAddressingIdentifier},
+    },
+    event_message::signed_event_message::Message,
+    oobi::{Role, Scheme},
+    prefix::IndexedSignature,
+    transport::test::{TestActorMap, TestTransport},
+};
+use keri_tests::setup_identifier;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use teliox::{
+    state::vc_state::TelState,
+    transport::test::{TelTestActorMap, TelTestTransport},
+};
+use tempfile::{Builder, TempDir};
+use url::{Host, Url};
+use watcher::{WatcherConfig, WatcherListener};
+use witness::{WitnessEscrowConfig, WitnessListener};
+
+/// Number of operations between invariants checks.
+const CHECK_INTERVAL: usize = 20;
+
+struct Settings {
+    duration: Duration,
+    controllers: usize,
+    witnesses: usize,
+    seed: u64,
+}
+
+impl Settings {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            duration: Duration::from_secs(env_or("SOAK_DURATION_SECS", 3600)?),
+            controllers: env_or("SOAK_CONTROLLERS", 24)?.max(2),
+            witnesses: env_or("SOAK_WITNESSES", 5)?.max(1),
+            seed: env_or("SOAK_SEED", rand::random())?,
+        })
+    }
+}
+
+fn env_or<T>(name: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|e| anyhow!("Wrong value of {}: {}", name, e)),
+        Err(_) => Ok(default),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Operation {
+    Incept,
+    Rotate,
+    Anchor,
+    Issue,
+    Verify,
+    Delegate,
+    Multisig,
+}
+
+struct Network {
+    witnesses: HashMap<BasicPrefix, (Arc<WitnessListener>, LocationScheme)>,
+    watcher_id: BasicPrefix,
+    watcher_location: LocationScheme,
+    transport: TestTransport<ActorError>,
+    tel_transport: TelTestTransport,
+    _dirs: Vec<TempDir>,
+}
+
+impl Network {
+    async fn setup(witnesses_count: usize) -> Result<Self> {
+        let mut dirs = vec![];
+        let mut witnesses = HashMap::new();
+        let mut actors: TestActorMap = HashMap::new();
+        let mut tel_actors: TelTestActorMap = HashMap::new();
+        for i in 0..witnesses_count {
+            let host = format!("witness{}", i);
+            let url = Url::parse(&format!("http://{}/", host))?;
+            let dir = Builder::new().prefix("soak-witness").tempdir()?;
+            let witness = Arc::new(WitnessListener::setup(
+                url.clone(),
+                dir.path(),
+                None,
+                WitnessEscrowConfig::default(),
+            )?);
+            actors.insert((Host::Domain(host.clone()), 80), witness.clone());
+            tel_actors.insert((Host::Domain(host), 80), witness.clone());
+            let location = LocationScheme {
+                eid: IdentifierPrefix::Basic(witness.get_prefix()),
+                scheme: Scheme::Http,
+                url,
+            };
+            witnesses.insert(witness.get_prefix(), (witness, location));
+            dirs.push(dir);
+        }
+
+        let watcher_url = Url::parse("http://watcher/")?;
+        let watcher_dir = Builder::new().prefix("soak-watcher").tempdir()?;
+        let watcher = Arc::new(WatcherListener::new(WatcherConfig {
+            public_address: watcher_url.clone(),
+            db_path: watcher_dir.path().to_owned(),
+            transport: Box::new(TestTransport::new(actors.clone())),
+            tel_transport: Box::new(TelTestTransport::new(tel_actors.clone())),
+            tel_storage_path: watcher_dir.path().join("tel_storage"),
+            ..Default::default()
+        })?);
+        async_std::task::spawn(watcher::watcher_listener::update_checking(
+            watcher.watcher.clone(),
+        ));
+        async_std::task::spawn(watcher::watcher_listener::update_tel_checking(
+            watcher.watcher.clone(),
+        ));
+        dirs.push(watcher_dir);
+
+        let watcher_id = watcher.get_prefix();
+        actors.insert((Host::Domain("watcher".to_string()), 80), watcher.clone());
+        tel_actors.insert((Host::Domain("watcher".to_string()), 80), watcher.clone());
+
+        Ok(Self {
+            witnesses,
+            watcher_location: LocationScheme {
+                eid: IdentifierPrefix::Basic(watcher_id.clone()),
+                scheme: Scheme::Http,
+                url: watcher_url,
+            },
+            watcher_id,
+            transport: TestTransport::new(actors),
+            tel_transport: TelTestTransport::new(tel_actors),
+            _dirs: dirs,
+        })
+    }
+}
+
+struct Actor {
+    identifier: Identifier,
+    keys: CryptoBox,
+    controller: Arc<Controller>,
+    witnesses: Vec<BasicPrefix>,
+    /// Sequence number of identifier's last event, that should be accepted.
+    expected_sn: u64,
+    _dir: TempDir,
+}
+
+impl Actor {
+    fn sign(&self, data: &[u8]) -> Result<SelfSigningPrefix> {
+        Ok(SelfSigningPrefix::Ed25519Sha512(self.keys.sign(data)?))
+    }
+
+    async fn query_mailbox(
+        &mut self,
+        id: &IdentifierPrefix,
+        witnesses: &[BasicPrefix],
+    ) -> Result<Vec<ActionRequired>> {
+        let mut actions = vec![];
+        let mut queries = self.identifier.query_mailbox(id, witnesses)?;
+        // Queries for next pages of truncated responses are signed and
+        // finalized too, until mailbox is exhausted.
+        while let Some(qry) = queries.pop() {
+            let signature = self.sign(&qry.encode()?)?;
+            for action in self
+                .identifier
+                .finalize_query_mailbox(vec![(qry, signature)])
+                .await?
+            {
+                match action {
+                    ActionRequired::NextMailboxPage(next) => queries.push(next),
+                    action => actions.push(action),
+                }
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Sends new event to witnesses and collects their receipts.
+    async fn publish(&mut self) -> Result<()> {
+        self.identifier.notify_witnesses().await?;
+        let (id, witnesses) = (self.identifier.id().clone(), self.witnesses.clone());
+        self.query_mailbox(&id, &witnesses).await?;
+        self.expected_sn += 1;
+        Ok(())
+    }
+}
+
+/// Group or delegated identifier, together with actors that should know its
+/// state.
+struct SharedIdentifier {
+    id: IdentifierPrefix,
+    members: Vec<usize>,
+}
+
+struct Soak {
+    settings: Settings,
+    network: Network,
+    actors: Vec<Actor>,
+    shared: Vec<SharedIdentifier>,
+    rng: StdRng,
+    stats: HashMap<Operation, usize>,
+}
+
+impl Soak {
+    fn pick_operation(&mut self) -> Operation {
+        if self.actors.len() < 2 {
+            return Operation::Incept;
+        }
+        let incept_weight = if self.actors.len() < self.settings.controllers {
+            2
+        } else {
+            0
+        };
+        let weights = [
+            (Operation::Incept, incept_weight),
+            (Operation::Rotate, 3),
+            (Operation::Anchor, 4),
+            (Operation::Issue, 2),
+            (Operation::Verify, 2),
+            (Operation::Delegate, 1),
+            (Operation::Multisig, 1),
+        ];
+        weights
+            .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+            .map(|(operation, _)| *operation)
+            .unwrap_or(Operation::Anchor)
+    }
+
+    async fn run(&mut self, operation: Operation) -> Result<()> {
+        match operation {
+            Operation::Incept => self.incept().await,
+            Operation::Rotate => self.rotate().await,
+            Operation::Anchor => self.anchor().await,
+            Operation::Issue => self.issue().await,
+            Operation::Verify => self.verify().await,
+            Operation::Delegate => self.delegate().await,
+            Operation::Multisig => self.multisig().await,
+        }?;
+        *self.stats.entry(operation).or_default() += 1;
+        Ok(())
+    }
+
+    fn random_actor(&mut self) -> usize {
+        self.rng.gen_range(0..self.actors.len())
+    }
+
+    /// Returns two different actors, that have at least one common witness,
+    /// and their common witnesses.
+    fn random_pair(&mut self) -> Option<(usize, usize, Vec<BasicPrefix>)> {
+        let first = self.random_actor();
+        let mut others: Vec<_> = (0..self.actors.len()).filter(|i| *i != first).collect();
+        others.shuffle(&mut self.rng);
+        others.into_iter().find_map(|second| {
+            let common: Vec<_> = self.actors[first]
+                .witnesses
+                .iter()
+                .filter(|w| self.actors[second].witnesses.contains(w))
+                .cloned()
+                .collect();
+            (!common.is_empty()).then_some((first, second, common))
+        })
+    }
+
+    fn random_digest(&mut self) -> SelfAddressingIdentifier {
+        HashFunction::from(HashFunctionCode::Blake3_256).derive(&self.rng.gen::<[u8; 32]>())
+    }
+
+    /// Saves KEL of one actor's identifier in other actor's controller.
+    fn share_kel(&self, from: usize, to: usize) -> Result<()> {
+        let from = &self.actors[from];
+        let kel = from
+            .controller
+            .get_kel_with_receipts(from.identifier.id())
+            .context("Missing KEL")?;
+        for notice in kel {
+            self.actors[to]
+                .controller
+                .known_events
+                .save(&Message::Notice(notice))?;
+        }
+        Ok(())
+    }
+
+    async fn incept(&mut self) -> Result<()> {
+        let dir = Builder::new().prefix("soak-controller").tempdir()?;
+        let locations: Vec<_> = {
+            let all: Vec<_> = self
+                .network
+                .witnesses
+                .values()
+                .map(|(_, loc)| loc)
+                .collect();
+            let count = self.rng.gen_range(1..=all.len().min(2));
+            all.choose_multiple(&mut self.rng, count)
+                .map(|loc| (*loc).clone())
+                .collect()
+        };
+        let (identifier, keys, controller) = setup_identifier(
+            dir.path(),
+            locations,
+            Some(self.network.transport.clone()),
+            Some(self.network.tel_transport.clone()),
+        )
+        .await;
+
+        identifier
+            .resolve_oobi(&Oobi::Location(self.network.watcher_location.clone()))
+            .await?;
+        let witnesses = identifier.witnesses().collect();
+        let mut actor = Actor {
+            identifier,
+            keys,
+            controller,
+            witnesses,
+            expected_sn: 0,
+            _dir: dir,
+        };
+        let add_watcher = actor
+            .identifier
+            .add_watcher(IdentifierPrefix::Basic(self.network.watcher_id.clone()))?;
+        let signature = actor.sign(add_watcher.as_bytes())?;
+        actor
+            .identifier
+            .finalize_add_watcher(add_watcher.as_bytes(), signature)
+            .await?;
+        self.actors.push(actor);
+        Ok(())
+    }
+
+    async fn rotate(&mut self) -> Result<()> {
+        let i = self.random_actor();
+        let actor = &mut self.actors[i];
+        actor.keys.rotate()?;
+        let rotation = actor
+            .identifier
+            .rotate(
+                vec![BasicPrefix::Ed25519NT(actor.keys.public_key())],
+                vec![BasicPrefix::Ed25519NT(actor.keys.next_public_key())],
+                1,
+                vec![],
+                vec![],
+                1,
+            )
+            .await?;
+        let signature = actor.sign(rotation.as_bytes())?;
+        actor
+            .identifier
+            .finalize_rotate(rotation.as_bytes(), signature)
+            .await?;
+        actor.publish().await
+    }
+
+    async fn anchor(&mut self) -> Result<()> {
+        let i = self.random_actor();
+        let digest = self.random_digest();
+        let actor = &mut self.actors[i];
+        let ixn = actor.identifier.anchor(&[digest])?;
+        let signature = actor.sign(ixn.as_bytes())?;
+        actor
+            .identifier
+            .finalize_anchor(ixn.as_bytes(), signature)
+            .await?;
+        actor.publish().await
+    }
+
+    async fn issue(&mut self) -> Result<()> {
+        let i = self.random_actor();
+        let digest = self.random_digest();
+        let actor = &mut self.actors[i];
+        if actor.identifier.registry_id().is_none() {
+            let (_registry_id, vcp_ixn) = actor.identifier.incept_registry()?;
+            let signature = actor.sign(&vcp_ixn)?;
+            actor
+                .identifier
+                .finalize_incept_registry(&vcp_ixn, signature)
+                .await?;
+            actor.publish().await?;
+        }
+        let (vc_hash, iss_ixn) = actor.identifier.issue(digest)?;
+        let signature = actor.sign(&iss_ixn)?;
+        actor.identifier.finalize_issue(&iss_ixn, signature).await?;
+        actor.publish().await?;
+        actor.identifier.notify_backers().await?;
+
+        let said = match vc_hash {
+            IdentifierPrefix::SelfAddressing(sai) => sai.said,
+            _ => bail!("Unexpected credential identifier {}", vc_hash),
+        };
+        ensure!(
+            matches!(
+                actor.identifier.find_vc_state(&said)?,
+                Some(TelState::Issued(_))
+            ),
+            "Credential {} not issued",
+            said
+        );
+        Ok(())
+    }
+
+    /// One actor asks watcher about other actor's KEL. KEL returned by
+    /// watcher may be behind, but it can't differ from the original one.
+    async fn verify(&mut self) -> Result<()> {
+        let verifier = self.random_actor();
+        let target = (verifier + self.rng.gen_range(1..self.actors.len())) % self.actors.len();
+        let target_id = self.actors[target].identifier.id().clone();
+        let seal = self.actors[target]
+            .identifier
+            .get_last_establishment_event_seal()?;
+
+        let actor = &self.actors[verifier];
+        let verifier_id = actor.identifier.id();
+        for witness in &self.actors[target].witnesses {
+            let (_, location) = &self.network.witnesses[witness];
+            actor
+                .identifier
+                .send_oobi_to_watcher(verifier_id, &Oobi::Location(location.clone()))
+                .await?;
+            let end_role = EndRole {
+                cid: target_id.clone(),
+                role: Role::Witness,
+                eid: IdentifierPrefix::Basic(witness.clone()),
+            };
+            actor
+                .identifier
+                .send_oobi_to_watcher(verifier_id, &Oobi::EndRole(end_role))
+                .await?;
+        }
+        // Watcher looks for KEL in background, so ask again after a while.
+        for _ in 0..2 {
+            let queries = actor
+                .identifier
+                .query_watchers(&seal)?
+                .into_iter()
+                .map(|qry| Ok((qry.clone(), actor.sign(&qry.encode()?)?)))
+                .collect::<Result<Vec<_>>>()?;
+            actor.identifier.finalize_query(queries).await;
+            sleep(Duration::from_millis(100)).await;
+        }
+
+        if let Ok(state) = actor.controller.find_state(&target_id) {
+            let original = &self.actors[target];
+            ensure!(
+                state.sn <= original.expected_sn,
+                "Watcher returned KEL of {} longer than original",
+                target_id
+            );
+            let digest = |controller: &Controller| {
+                controller
+                    .known_events
+                    .storage
+                    .get_event_at_sn(&target_id, state.sn)
+                    .and_then(|ev| ev.signed_event_message.event_message.digest().ok())
+            };
+            ensure!(
+                digest(&actor.controller) == digest(&original.controller),
+                "KEL of {} from watcher differs from original at sn {}",
+                target_id,
+                state.sn
+            );
+        }
+        Ok(())
+    }
+
+    async fn delegate(&mut self) -> Result<()> {
+        let Some((delegatee, delegator, witnesses)) = self.random_pair() else {
+            return Ok(());
+        };
+        let delegator_id = self.actors[delegator].identifier.id().clone();
+        self.share_kel(delegator, delegatee)?;
+
+        let actor = &mut self.actors[delegatee];
+        let (dip, exn_messages) = actor.identifier.incept_group(
+            vec![],
+            1,
+            Some(witnesses.clone()),
+            Some(1),
+            Some(delegator_id.clone()),
+        )?;
+        let signature_dip = actor.sign(dip.as_bytes())?;
+        let signature_exn = actor.sign(exn_messages[0].as_bytes())?;
+        let delegate_id = actor
+            .identifier
+            .finalize_group_incept(
+                dip.as_bytes(),
+                signature_dip,
+                vec![(exn_messages[0].as_bytes().to_vec(), signature_exn)],
+            )
+            .await?;
+
+        let actor = &mut self.actors[delegator];
+        let delegator_witnesses = actor.witnesses.clone();
+        for action in actor
+            .query_mailbox(&delegator_id, &delegator_witnesses)
+            .await?
+        {
+            match action {
+                ActionRequired::DelegationRequest(delegating_event, exn) => {
+                    let signature_ixn = actor.sign(&delegating_event.encode()?)?;
+                    let signature_exn = actor.sign(&exn.encode()?)?;
+                    actor
+                        .identifier
+                        .finalize_group_incept(
+                            &delegating_event.encode()?,
+                            signature_ixn.clone(),
+                            vec![],
+                        )
+                        .await?;
+                    actor.publish().await?;
+                    actor
+                        .identifier
+                        .finalize_exchange(
+                            &exn.encode()?,
+                            signature_exn,
+                            IndexedSignature::new_both_same(signature_ixn, 0),
+                        )
+                        .await?;
+                }
+                ActionRequired::MultisigRequest(_, _, _) => {
+                    bail!("Unexpected multisig request for {}", delegator_id)
+                }
+                ActionRequired::NextMailboxPage(_) => {
+                    bail!("Unexpected mailbox page query for {}", delegator_id)
+                }
+            }
+        }
+
+        self.share_kel(delegator, delegatee)?;
+        let actor = &mut self.actors[delegatee];
+        // First query gets delegating event, the second one receipts.
+        for _ in 0..2 {
+            actor.query_mailbox(&delegate_id, &witnesses).await?;
+        }
+        ensure!(
+            actor.identifier.find_state(&delegate_id)?.sn == 0,
+            "Delegated identifier {} not accepted",
+            delegate_id
+        );
+        self.shared.push(SharedIdentifier {
+            id: delegate_id,
+            members: vec![delegatee],
+        });
+        Ok(())
+    }
+
+    async fn multisig(&mut self) -> Result<()> {
+        let Some((initiator, participant, witnesses)) = self.random_pair() else {
+            return Ok(());
+        };
+        self.share_kel(initiator, participant)?;
+        self.share_kel(participant, initiator)?;
+        let participant_id = self.actors[participant].identifier.id().clone();
+
+        let actor = &mut self.actors[initiator];
+        let (icp, exn_messages) = actor.identifier.incept_group(
+            vec![participant_id.clone()],
+            2,
+            Some(witnesses.clone()),
+            Some(1),
+            None,
+        )?;
+        let signature_icp = actor.sign(icp.as_bytes())?;
+        let signature_exn = actor.sign(exn_messages[0].as_bytes())?;
+        let group_id = actor
+            .identifier
+            .finalize_group_incept(
+                icp.as_bytes(),
+                signature_icp,
+                vec![(exn_messages[0].as_bytes().to_vec(), signature_exn)],
+            )
+            .await?;
+
+        let actor = &mut self.actors[participant];
+        let participant_witnesses = actor.witnesses.clone();
+        for action in actor
+            .query_mailbox(&participant_id, &participant_witnesses)
+            .await?
+        {
+            match action {
+                ActionRequired::MultisigRequest(multisig_event, exn, _) => {
+                    let signature_icp = actor.sign(&multisig_event.encode()?)?;
+                    let signature_exn = actor.sign(&exn.encode()?)?;
+                    actor
+                        .identifier
+                        .finalize_group_incept(
+                            &multisig_event.encode()?,
+                            signature_icp,
+                            vec![(exn.encode()?, signature_exn)],
+                        )
+                        .await?;
+                }
+                ActionRequired::DelegationRequest(_, _) => {
+                    bail!("Unexpected delegation request for {}", participant_id)
+                }
+                ActionRequired::NextMailboxPage(_) => {
+                    bail!("Unexpected mailbox page query for {}", participant_id)
+                }
+            }
+        }
+
+        // First query gets participant's signatures, the second one receipts.
+        for _ in 0..2 {
+            self.actors[initiator]
+                .query_mailbox(&group_id, &witnesses)
+                .await?;
+        }
+        self.actors[participant]
+            .query_mailbox(&group_id, &witnesses)
+            .await?;
+        for member in [initiator, participant] {
+            ensure!(
+                self.actors[member].identifier.find_state(&group_id)?.sn == 0,
+                "Group identifier {} not accepted by {}",
+                group_id,
+                self.actors[member].identifier.id()
+            );
+        }
+        self.shared.push(SharedIdentifier {
+            id: group_id,
+            members: vec![initiator, participant],
+        });
+        Ok(())
+    }
+
+    fn check_invariants(&self) -> Result<()> {
+        for actor in &self.actors {
+            let id = actor.identifier.id();
+            let state = actor.identifier.find_state(id)?;
+            ensure!(
+                state.sn == actor.expected_sn,
+                "{}: expected sn {}, got {}",
+                id,
+                actor.expected_sn,
+                state.sn
+            );
+            ensure!(
+                actor
+                    .controller
+                    .known_events
+                    .partially_witnessed_escrow
+                    .get_partially_witnessed_events()
+                    .is_empty(),
+                "{}: events stuck in partially witnessed escrow",
+                id
+            );
+            for witness in &actor.witnesses {
+                let (listener, _) = &self.network.witnesses[witness];
+                let witness_state = listener
+                    .witness_data
+                    .event_storage
+                    .get_state(id)
+                    .with_context(|| format!("{}: unknown to witness {}", id, witness))?;
+                ensure!(
+                    witness_state == state,
+                    "{}: state in witness {} differs",
+                    id,
+                    witness
+                );
+            }
+        }
+        for shared in &self.shared {
+            for member in &shared.members {
+                let state = self.actors[*member].identifier.find_state(&shared.id)?;
+                ensure!(state.sn == 0, "{}: unexpected sn {}", shared.id, state.sn);
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&self, start: Instant) {
+        let mut stats: Vec<_> = self.stats.iter().collect();
+        stats.sort_by_key(|(operation, _)| format!("{:?}", operation));
+        println!(
+            "[{:>6}s] controllers: {}, shared identifiers: {}, operations: {:?}",
+            start.elapsed().as_secs(),
+            self.actors.len(),
+            self.shared.len(),
+            stats
+        );
+    }
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    let settings = Settings::from_env()?;
+    let seed = settings.seed;
+    println!(
+        "Soak test: duration {:?}, {} controllers, {} witnesses, seed {}",
+        settings.duration, settings.controllers, settings.witnesses, seed
+    );
+
+    let mut soak = Soak {
+        network: Network::setup(settings.witnesses).await?,
+        actors: vec![],
+        shared: vec![],
+        rng: StdRng::seed_from_u64(seed),
+        stats: HashMap::new(),
+        settings,
+    };
+
+    let start = Instant::now();
+    let mut count = 0;
+    while start.elapsed() < soak.settings.duration {
+        let operation = soak.pick_operation();
+        soak.run(operation).await.with_context(|| {
+            format!(
+                "Operation {} ({:?}) failed, seed {}",
+                count, operation, seed
+            )
+        })?;
+        count += 1;
+        if count % CHECK_INTERVAL == 0 {
+            soak.check_invariants().with_context(|| {
+                format!("Invariant broken after {} operations, seed {}", count, seed)
+            })?;
+            soak.report(start);
+        }
+    }
+    soak.check_invariants()
+        .with_context(|| format!("Invariant broken after {} operations, seed {}", count, seed))?;
+    soak.report(start);
+    println!("Soak test passed");
+
+    Ok(())
+}
@@ -5,10 +5,9 @@ use keri_controller::{
     CryptoBox, IdentifierPrefix, KeyManager, LocationScheme, SelfSigningPrefix,
 };
 use keri_core::{actor::error::ActorError, transport::test::TestTransport};
-use transport::TelTestTransport;
+use teliox::transport::test::TelTestTransport;
 
 pub mod settings;
-pub mod transport;
 
 // Helper function that incepts identifier
 pub async fn setup_identifier(
@@ -17,31 +16,17 @@ pub async fn setup_identifier(
     transport: Option<TestTransport<ActorError>>,
     tel_transport: Option<TelTestTransport>,
 ) -> (Identifier, CryptoBox, Arc<Controller>) {
-    let verifier_controller = Arc::new(
-        match (transport, tel_transport) {
-            (None, None) => Controller::new(ControllerConfig {
-                db_path: root_path.to_owned(),
-                ..Default::default()
-            }),
-            (None, Some(tel_transport)) => Controller::new(ControllerConfig {
-                db_path: root_path.to_owned(),
-                tel_transport: Box::new(tel_transport.clone()),
-                ..Default::default()
-            }),
-            (Some(transport), None) => Controller::new(ControllerConfig {
-                db_path: root_path.to_owned(),
-                transport: Box::new(transport.clone()),
-                ..Default::default()
-            }),
-            (Some(transport), Some(tel_transport)) => Controller::new(ControllerConfig {
-                db_path: root_path.to_owned(),
-                transport: Box::new(transport.clone()),
-                tel_transport: Box::new(tel_transport.clone()),
-                ..Default::default()
-            }),
-        }
-        .unwrap(),
-    );
+    let mut config = ControllerConfig {
+        db_path: root_path.to_owned(),
+        ..Default::default()
+    };
+    if let Some(transport) = transport {
+        config.transport = Box::new(transport);
+    }
+    if let Some(tel_transport) = tel_transport {
+        config.tel_transport = Box::new(tel_transport);
+    }
+    let verifier_controller = Arc::new(Controller::new(config).unwrap());
 
     let witnesses_id: Vec<BasicPrefix> = witness_locations
         .iter()
@@ -0,0 +1,91 @@
+use std::{collections::HashMap, sync::Arc};
+
+use keri_controller::{
+    error::ControllerError, IdentifierPrefix, LocationScheme, SelfSigningPrefix,
+};
+use keri_core::{
+    event_message::signed_event_message::{Message, Notice},
+    transport::test::TestTransport,
+};
+use keri_tests::setup_identifier;
+use teliox::transport::test::TelTestTransport;
+use tempfile::Builder;
+use url::{Host, Url};
+use witness::{WitnessEscrowConfig, WitnessListener};
+
+#[async_std::test]
+async fn test_endorse() -> Result<(), ControllerError> {
+    let witness = {
+        let witness_root = Builder::new().prefix("test-wit1-db").tempdir().unwrap();
+        Arc::new(
+            WitnessListener::setup(
+                Url::parse("http://witness1/").unwrap(),
+                witness_root.path(),
+                Some("AK8F6AAiYDpXlWdj2O5F5-6wNCCNJh2A4XOlqwR_HwwH".to_string()),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let wit_location = LocationScheme {
+        eid: IdentifierPrefix::Basic(witness.get_prefix()),
+        scheme: keri_core::oobi::Scheme::Http,
+        url: Url::parse("http://witness1/").unwrap(),
+    };
+    let transport = {
+        let mut actors: keri_core::transport::test::TestActorMap = HashMap::new();
+        actors.insert((Host::Domain("witness1".to_string()), 80), witness.clone());
+        TestTransport::new(actors)
+    };
+
+    let alice_root = Builder::new().prefix("test-db-alice").tempdir().unwrap();
+    let (alice, _alice_keypair, _) = setup_identifier(
+        alice_root.path(),
+        vec![wit_location.clone()],
+        Some(transport.clone()),
+        Some(TelTestTransport::new(HashMap::new())),
+    )
+    .await;
+
+    let validator_root = Builder::new()
+        .prefix("test-db-validator")
+        .tempdir()
+        .unwrap();
+    let (validator, validator_keypair, validator_controller) = setup_identifier(
+        validator_root.path(),
+        vec![wit_location],
+        Some(transport),
+        Some(TelTestTransport::new(HashMap::new())),
+    )
+    .await;
+
+    // Validator needs to know Alice's KEL to find her witnesses.
+    let alice_kel = alice.get_own_kel().unwrap();
+    for notice in &alice_kel {
+        validator_controller
+            .known_events
+            .process(&Message::Notice(notice.clone()))?;
+    }
+    let alice_icp = match &alice_kel[0] {
+        Notice::Event(icp) => icp.event_message.clone(),
+        _ => unreachable!(),
+    };
+
+    let signature =
+        SelfSigningPrefix::Ed25519Sha512(validator_keypair.sign(&alice_icp.encode().unwrap())?);
+    let receipt = validator.endorse(&alice_icp, signature).await?;
+    assert_eq!(&receipt.validator_seal.prefix, validator.id());
+    assert_eq!(&receipt.body.prefix, alice.id());
+
+    // Witness serves the receipt together with Alice's KEL.
+    let kel = witness
+        .witness_data
+        .event_storage
+        .get_kel_messages_with_receipts_all(alice.id())?
+        .unwrap();
+    assert!(kel
+        .iter()
+        .any(|notice| notice == &Notice::TransferableRct(receipt.clone())));
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, sync::Arc};
+
+use keri_controller::{
+    config::ControllerConfig, controller::Controller, error::ControllerError,
+    mailbox_updating::ActionRequired, IdentifierPrefix, KeyManager, LocationScheme,
+    SelfSigningPrefix,
+};
+use keri_core::{
+    actor::prelude::{HashFunction, HashFunctionCode},
+    transport::test::TestTransport,
+};
+use keri_tests::setup_identifier;
+use teliox::transport::test::TelTestTransport;
+use tempfile::Builder;
+use url::{Host, Url};
+use witness::{WitnessEscrowConfig, WitnessListener};
+
+#[async_std::test]
+async fn test_mailbox_pages_without_query_signer() -> Result<(), ControllerError> {
+    // Every response of this witness contains single mailbox message.
+    let witness = {
+        let witness_root = Builder::new().prefix("test-wit1-db").tempdir().unwrap();
+        Arc::new(
+            WitnessListener::setup(
+                Url::parse("http://witness1/").unwrap(),
+                witness_root.path(),
+                Some("AK8F6AAiYDpXlWdj2O5F5-6wNCCNJh2A4XOlqwR_HwwH".to_string()),
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap()
+            .with_mailbox_max_size(1),
+        )
+    };
+    let witness_id = witness.get_prefix();
+    let witness_location = LocationScheme {
+        eid: IdentifierPrefix::Basic(witness_id.clone()),
+        scheme: keri_core::oobi::Scheme::Http,
+        url: Url::parse("http://witness1/").unwrap(),
+    };
+
+    let transport = {
+        let mut actors: keri_core::transport::test::TestActorMap = HashMap::new();
+        actors.insert((Host::Domain("witness1".to_string()), 80), witness.clone());
+        TestTransport::new(actors)
+    };
+
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let (mut identifier, keypair, _controller) = setup_identifier(
+        root.path(),
+        vec![witness_location],
+        Some(transport.clone()),
+        Some(TelTestTransport::new(HashMap::new())),
+    )
+    .await;
+
+    // Anchor some data, so witness mailbox contains two receipts.
+    let said = HashFunction::from(HashFunctionCode::Blake3_256).derive(b"hello");
+    let ixn = identifier.anchor(&[said])?;
+    let signature = SelfSigningPrefix::Ed25519Sha512(keypair.sign(ixn.as_bytes())?);
+    identifier
+        .finalize_anchor(ixn.as_bytes(), signature)
+        .await?;
+    identifier.notify_witnesses().await?;
+    for qry in identifier.query_mailbox(identifier.id(), &[witness_id.clone()])? {
+        let signature = SelfSigningPrefix::Ed25519Sha512(keypair.sign(&qry.encode()?)?);
+        identifier
+            .finalize_query_mailbox(vec![(qry, signature)])
+            .await?;
+    }
+    assert_eq!(identifier.find_state(identifier.id())?.sn, 1);
+
+    // Identifier restored on other device asks for whole mailbox again.
+    let other_root = Builder::new().prefix("test-db").tempdir().unwrap();
+    let other_controller = Controller::new(ControllerConfig {
+        db_path: other_root.path().to_owned(),
+        transport: Box::new(transport),
+        ..Default::default()
+    })?;
+    let mut restored = other_controller.import_bundle(&identifier.export_bundle()?)?;
+
+    let mut queries = restored.query_mailbox(restored.id(), &[witness_id.clone()])?;
+    assert_eq!(queries.len(), 1);
+    let mut pages = 0;
+    while let Some(qry) = queries.pop() {
+        pages += 1;
+        let signature = SelfSigningPrefix::Ed25519Sha512(keypair.sign(&qry.encode()?)?);
+        // Without query signer, query for the next page is returned to be
+        // signed.
+        for action in restored
+            .finalize_query_mailbox(vec![(qry, signature)])
+            .await?
+        {
+            match action {
+                ActionRequired::NextMailboxPage(next) => queries.push(next),
+                _ => unreachable!(),
+            }
+        }
+    }
+    assert_eq!(pages, 2);
+
+    // Both receipts were retrieved, so next query starts after them.
+    let qry = &restored.query_mailbox(restored.id(), &[witness_id])?[0];
+    assert_eq!(qry.get_args().topics.receipt, 2);
+
+    Ok(())
+}
@@ -2,10 +2,17 @@ use std::{collections::HashMap, net::Ipv4Addr, sync::Arc};
 
 use anyhow::Result;
 use keri_controller::{
-    config::ControllerConfig, controller::Controller, identifier::mechanics::MechanicsError,
-    mailbox_updating::ActionRequired, Oobi,
+    config::ControllerConfig,
+    controller::Controller,
+    identifier::{
+        end_role::{EndRoleEntry, EndRoleStatus},
+        mechanics::MechanicsError,
+    },
+    mailbox_updating::ActionRequired,
+    Oobi,
 };
 use keri_core::{
+    mailbox::exchange::ExchangeMetadata,
     oobi::{EndRole, LocationScheme, Role},
     prefix::{BasicPrefix, IdentifierPrefix, SelfSigningPrefix},
     signer::{CryptoBox, KeyManager},
@@ -162,7 +169,11 @@ async fn test_multisig() -> Result<()> {
         .await?;
     assert_eq!(
         identifier1.watchers()?,
-        vec![IdentifierPrefix::Basic(watcher_id)]
+        vec![EndRoleEntry {
+            eid: IdentifierPrefix::Basic(watcher_id),
+            role: Role::Watcher,
+            status: EndRoleStatus::Fresh,
+        }]
     );
 
     // Send identifier2 oobis to identifier1's watchers and query for results.
@@ -191,12 +202,16 @@ async fn test_multisig() -> Result<()> {
     }
 
     // Incept group
-    let (group_inception, exn_messages) = identifier1.incept_group(
+    let metadata = ExchangeMetadata::default()
+        .with_proposal_id("group-1".to_string())
+        .with_memo("Shared group identifier".to_string());
+    let (group_inception, exn_messages) = identifier1.incept_group_with_metadata(
         vec![identifier2.id().clone()],
         2,
         Some(vec![witness_id.clone()]),
         Some(1),
         None,
+        Some(metadata.clone()),
     )?;
 
     let signature_icp = SelfSigningPrefix::Ed25519Sha512(km1.sign(group_inception.as_bytes())?);
@@ -227,10 +242,11 @@ async fn test_multisig() -> Result<()> {
             .await?;
 
         match &action_required[0] {
-            ActionRequired::DelegationRequest(_, _) => {
+            ActionRequired::DelegationRequest(_, _) | ActionRequired::NextMailboxPage(_) => {
                 unreachable!()
             }
-            ActionRequired::MultisigRequest(multisig_event, exn) => {
+            ActionRequired::MultisigRequest(multisig_event, exn, request_metadata) => {
+                assert_eq!(request_metadata.as_ref(), Some(&metadata));
                 let signature_ixn =
                     SelfSigningPrefix::Ed25519Sha512(km2.sign(&multisig_event.encode()?)?);
                 let signature_exn = SelfSigningPrefix::Ed25519Sha512(km2.sign(&exn.encode()?)?);
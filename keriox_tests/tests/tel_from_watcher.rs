@@ -6,11 +6,11 @@ use keri_controller::{EndRole, Oobi};
 use keri_controller::{IdentifierPrefix, KeyManager, LocationScheme, SelfSigningPrefix};
 use keri_core::actor::prelude::{HashFunction, HashFunctionCode};
 use keri_core::transport::test::TestTransport;
-use keri_tests::{
-    setup_identifier,
-    transport::{TelTestActor, TelTestTransport},
+use keri_tests::setup_identifier;
+use teliox::{
+    state::vc_state::TelState,
+    transport::test::{TelTestActorMap, TelTestTransport},
 };
-use teliox::state::vc_state::TelState;
 use tempfile::Builder;
 use url::{Host, Url};
 use watcher::{WatcherConfig, WatcherListener};
@@ -85,19 +85,18 @@ async fn test_tel_from_watcher() -> Result<(), anyhow::Error> {
             TestTransport::new(actors)
         };
 
-        let trans = TelTestTransport::new();
-        trans
-            .insert(
+        let trans = {
+            let mut actors: TelTestActorMap = HashMap::new();
+            actors.insert(
                 (Host::Domain("witness1".to_string()), 80),
-                TelTestActor::Witness(verifier_witness.witness_data.clone()),
-            )
-            .await;
-        trans
-            .insert(
+                verifier_witness.clone(),
+            );
+            actors.insert(
                 (Host::Domain("witness2".to_string()), 80),
-                TelTestActor::Witness(issuer_witness.witness_data.clone()),
-            )
-            .await;
+                issuer_witness.clone(),
+            );
+            TelTestTransport::new(actors)
+        };
 
         let watcher_db_path = Builder::new().prefix("cont-test-db").tempdir().unwrap();
         let watcher_listener = Arc::new(WatcherListener::new(WatcherConfig {
@@ -141,26 +140,17 @@ async fn test_tel_from_watcher() -> Result<(), anyhow::Error> {
     };
 
     let tel_transport = {
-        let trans = TelTestTransport::new();
-        trans
-            .insert(
-                (Host::Domain("witness1".to_string()), 80),
-                TelTestActor::Witness(verifier_witness.witness_data.clone()),
-            )
-            .await;
-        trans
-            .insert(
-                (Host::Domain("witness2".to_string()), 80),
-                TelTestActor::Witness(issuer_witness.witness_data.clone()),
-            )
-            .await;
-        trans
-            .insert(
-                (Host::Domain("watcher1".to_string()), 80),
-                TelTestActor::Watcher(watcher.watcher.clone()),
-            )
-            .await;
-        trans
+        let mut actors: TelTestActorMap = HashMap::new();
+        actors.insert(
+            (Host::Domain("witness1".to_string()), 80),
+            verifier_witness.clone(),
+        );
+        actors.insert(
+            (Host::Domain("witness2".to_string()), 80),
+            issuer_witness.clone(),
+        );
+        actors.insert((Host::Domain("watcher1".to_string()), 80), watcher.clone());
+        TelTestTransport::new(actors)
     };
 
     // Setup verifier identifier
@@ -377,16 +367,12 @@ async fn test_tel_from_watcher() -> Result<(), anyhow::Error> {
     }
     assert_eq!(verifier.find_state(issuer.id()).unwrap().sn, 3);
 
-    // Query witness about issuer's tel again.
+    // Watcher's cached TEL is still fresh, so it wouldn't ask witness about
+    // revocation. Force the update.
     let registry_id = issuer.registry_id().unwrap().clone();
-    let qry = verifier.query_tel(registry_id.clone(), vc_hash.clone())?;
-    let signature =
-        SelfSigningPrefix::Ed25519Sha512(verifier_keypair.sign(&qry.encode().unwrap())?);
-    verifier.finalize_query_tel(qry, signature).await?;
-
-    // Give watcher a moment to find TEL and ask again
-    sleep(Duration::from_secs(1)).await;
+    assert_eq!(watcher.watcher.refresh_tel(&registry_id).await?, 1);
 
+    // Query watcher about issuer's tel again.
     let qry = verifier.query_tel(registry_id, vc_hash.clone())?;
     let signature =
         SelfSigningPrefix::Ed25519Sha512(verifier_keypair.sign(&qry.encode().unwrap())?);
@@ -1,11 +1,19 @@
 use std::{collections::HashMap, sync::Arc};
 
 use keri_controller::{
-    error::ControllerError, BasicPrefix, IdentifierPrefix, KeyManager, LocationScheme,
-    SelfSigningPrefix,
+    error::ControllerError,
+    identifier::{
+        mechanics::{
+            ksn_push::{KsnDelivery, KsnDeliveryStatus},
+            MechanicsError,
+        },
+        witness_health::WitnessPolicy,
+    },
+    BasicPrefix, IdentifierPrefix, KeyManager, LocationScheme, SelfSigningPrefix,
 };
-use keri_core::transport::test::TestTransport;
-use keri_tests::{setup_identifier, transport::TelTestTransport};
+use keri_core::{event::sections::threshold::SignatureThreshold, transport::test::TestTransport};
+use keri_tests::setup_identifier;
+use teliox::transport::test::TelTestTransport;
 use tempfile::Builder;
 use url::Host;
 use witness::{WitnessEscrowConfig, WitnessListener};
@@ -67,11 +75,11 @@ async fn test_witness_rotation() -> Result<(), ControllerError> {
     };
 
     // Setup identifier with `witness1` as witness
-    let (mut identifier, mut controller_keypair, _) = setup_identifier(
+    let (mut identifier, mut controller_keypair, controller) = setup_identifier(
         root0.path(),
         vec![wit1_location.clone()],
         Some(transport.clone()),
-        Some(TelTestTransport::new()),
+        Some(TelTestTransport::new(HashMap::new())),
     )
     .await;
 
@@ -99,6 +107,18 @@ async fn test_witness_rotation() -> Result<(), ControllerError> {
         .finalize_rotate(rotation_event.as_bytes(), signature)
         .await?;
 
+    // Key state will be pushed to witness2 and to peer of unknown location,
+    // when rotation gets receipted.
+    let unknown_peer = IdentifierPrefix::Basic(BasicPrefix::Ed25519NT(
+        keri_core::signer::Signer::new().public_key(),
+    ));
+    identifier.set_ksn_peers(vec![
+        IdentifierPrefix::Basic(wit2_id.clone()),
+        unknown_peer.clone(),
+    ])?;
+    let controller_keypair = Arc::new(controller_keypair);
+    identifier.set_query_signer(controller_keypair.clone());
+
     let cached_witnesses = &identifier.witnesses().collect::<Vec<_>>();
     // dbg!(&cached_witnesses);
     let state = identifier.find_state(identifier.id())?;
@@ -125,5 +145,102 @@ async fn test_witness_rotation() -> Result<(), ControllerError> {
     assert_eq!(state.sn, 1);
     assert_eq!(&state.witness_config.witnesses, cached_witnesses);
 
+    // Key state was pushed automatically after receipted rotation.
+    let deliveries = identifier.ksn_deliveries();
+    assert_eq!(deliveries.len(), 2);
+    assert_eq!(
+        deliveries[0],
+        KsnDelivery {
+            peer: IdentifierPrefix::Basic(wit2_id),
+            status: KsnDeliveryStatus::Delivered,
+        }
+    );
+    assert_eq!(deliveries[1].peer, unknown_peer);
+    assert!(matches!(deliveries[1].status, KsnDeliveryStatus::Failed(_)));
+
+    // Peers are restored with identifier.
+    let loaded = controller.load_identifier(identifier.id())?;
+    assert_eq!(loaded.ksn_peers(), identifier.ksn_peers());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn test_witness_rotation_advice_with_weighted_threshold() -> Result<(), ControllerError> {
+    use url::Url;
+    let root = Builder::new().prefix("test-db").tempdir().unwrap();
+
+    let witness = {
+        let witness_root = Builder::new().prefix("test-wit1-db").tempdir().unwrap();
+        Arc::new(
+            WitnessListener::setup(
+                url::Url::parse("http://witness1/").unwrap(),
+                witness_root.path(),
+                None,
+                WitnessEscrowConfig::default(),
+            )
+            .unwrap(),
+        )
+    };
+    let wit_id = witness.get_prefix();
+    let wit_location = LocationScheme {
+        eid: IdentifierPrefix::Basic(wit_id.clone()),
+        scheme: keri_core::oobi::Scheme::Http,
+        url: Url::parse("http://witness1/").unwrap(),
+    };
+
+    let transport = {
+        let mut actors: keri_core::transport::test::TestActorMap = HashMap::new();
+        actors.insert((Host::Domain("witness1".to_string()), 80), witness.clone());
+        TestTransport::new(actors)
+    };
+
+    let (mut identifier, mut controller_keypair, _controller) = setup_identifier(
+        root.path(),
+        vec![wit_location.clone()],
+        Some(transport),
+        Some(TelTestTransport::new(HashMap::new())),
+    )
+    .await;
+
+    // Availability can't exceed 1, so every witness is considered dead.
+    let policy = WitnessPolicy {
+        min_availability: 1.1,
+        backups: vec![],
+    };
+    let advice = identifier.witness_rotation_advice(&policy)?.unwrap();
+    assert_eq!(advice.witness_to_remove, vec![wit_id.clone()]);
+    assert_eq!(advice.witness_threshold, 0);
+
+    // Switch to weighted witness threshold.
+    controller_keypair.rotate()?;
+    let new_curr = BasicPrefix::Ed25519NT(controller_keypair.public_key());
+    let new_next = BasicPrefix::Ed25519NT(controller_keypair.next_public_key());
+    let weighted = SignatureThreshold::single_weighted(vec![(1, 1)]);
+    let rotation_event = identifier
+        .rotate_with_witness_threshold(vec![new_curr], vec![new_next], 1, vec![], vec![], &weighted)
+        .await?;
+    let signature =
+        SelfSigningPrefix::Ed25519Sha512(controller_keypair.sign(rotation_event.as_bytes())?);
+    identifier
+        .finalize_rotate(rotation_event.as_bytes(), signature)
+        .await?;
+    identifier.notify_witnesses().await?;
+    for qry in identifier.query_mailbox(identifier.id(), &[wit_id.clone()])? {
+        let signature = SelfSigningPrefix::Ed25519Sha512(controller_keypair.sign(&qry.encode()?)?);
+        identifier
+            .finalize_query_mailbox(vec![(qry, signature)])
+            .await?;
+    }
+    let state = identifier.find_state(identifier.id())?;
+    assert_eq!(state.sn, 1);
+    assert_eq!(state.witness_config.tally, weighted);
+
+    // Weights of removed witnesses can't be reassigned.
+    assert!(matches!(
+        identifier.witness_rotation_advice(&policy),
+        Err(MechanicsError::WeightedWitnessThreshold)
+    ));
+
     Ok(())
 }
@@ -64,4 +64,12 @@ impl EventDatabase {
         self.management_events
             .iter_values(self.identifiers.designated_key(id))
     }
+
+    /// Copies all trees of database into new database at `target` path.
+    pub fn backup(&self, target: impl AsRef<Path>) -> Result<(), Error> {
+        let target_db = sled::open(target)?;
+        target_db.import(self.db.export());
+        target_db.flush()?;
+        Ok(())
+    }
 }
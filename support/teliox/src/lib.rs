@@ -1,3 +1,6 @@
+//! Internal crate of KERIOX. Its API isn't stable and can change in any
+//! release. Applications should use the `keriox` crate, which re-exports
+//! semver-stable part of it.
 pub mod database;
 pub mod error;
 pub mod event;
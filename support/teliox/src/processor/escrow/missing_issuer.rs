@@ -4,6 +4,7 @@ use keri_core::{
     database::{
         escrow::{Escrow, EscrowDb},
         redb::RedbDatabase,
+        EventDatabase as KelDatabase,
     },
     prefix::IdentifierPrefix,
     processor::{
@@ -22,19 +23,19 @@ use crate::{
     },
 };
 
-pub struct MissingIssuerEscrow {
-    kel_reference: Arc<EventStorage<RedbDatabase>>,
+pub struct MissingIssuerEscrow<D: KelDatabase = RedbDatabase> {
+    kel_reference: Arc<EventStorage<D>>,
     tel_reference: Arc<TelEventStorage>,
     publisher: TelNotificationBus,
     escrowed_missing_issuer: Escrow<VerifiableEvent>,
 }
 
-impl MissingIssuerEscrow {
+impl<D: KelDatabase> MissingIssuerEscrow<D> {
     pub fn new(
         db: Arc<TelEventStorage>,
         escrow_db: Arc<EscrowDb>,
         duration: Duration,
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         bus: TelNotificationBus,
     ) -> Self {
         let escrow = Escrow::new(b"mie.", duration, escrow_db);
@@ -47,7 +48,7 @@ impl MissingIssuerEscrow {
         }
     }
 }
-impl Notifier for MissingIssuerEscrow {
+impl<D: KelDatabase> Notifier for MissingIssuerEscrow<D> {
     fn notify(
         &self,
         notification: &Notification,
@@ -71,7 +72,7 @@ impl Notifier for MissingIssuerEscrow {
     }
 }
 
-impl TelNotifier for MissingIssuerEscrow {
+impl<D: KelDatabase> TelNotifier for MissingIssuerEscrow<D> {
     fn notify(
         &self,
         notification: &TelNotification,
@@ -90,7 +91,7 @@ impl TelNotifier for MissingIssuerEscrow {
     }
 }
 
-impl MissingIssuerEscrow {
+impl<D: KelDatabase> MissingIssuerEscrow<D> {
     /// Reprocess escrowed events that need issuer event of given digest for acceptance.
     pub fn process_missing_issuer_escrow(&self, id: &IdentifierPrefix) -> Result<(), Error> {
         if let Some(esc) = self.escrowed_missing_issuer.get(id) {
@@ -112,8 +113,7 @@ impl MissingIssuerEscrow {
                         // accept tel event
                         self.tel_reference.add_event(event.clone())?;
 
-                        self.publisher
-                            .notify(&TelNotification::TelEventAdded(event))?;
+                        self.publisher.notify_added(event)?;
                     }
                     Err(Error::MissingSealError) => {
                         // remove from escrow
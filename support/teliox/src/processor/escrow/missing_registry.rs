@@ -4,6 +4,7 @@ use keri_core::{
     database::{
         escrow::{Escrow, EscrowDb},
         redb::RedbDatabase,
+        EventDatabase as KelDatabase,
     },
     prefix::IdentifierPrefix,
     processor::event_storage::EventStorage,
@@ -19,16 +20,16 @@ use crate::{
     },
 };
 
-pub struct MissingRegistryEscrow {
+pub struct MissingRegistryEscrow<D: KelDatabase = RedbDatabase> {
     tel_reference: Arc<TelEventStorage>,
-    kel_reference: Arc<EventStorage<RedbDatabase>>,
+    kel_reference: Arc<EventStorage<D>>,
     escrowed_missing_registry: Escrow<VerifiableEvent>,
 }
 
-impl MissingRegistryEscrow {
+impl<D: KelDatabase> MissingRegistryEscrow<D> {
     pub fn new(
         tel_reference: Arc<TelEventStorage>,
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         escrow_db: Arc<EscrowDb>,
         duration: Duration,
     ) -> Self {
@@ -41,7 +42,7 @@ impl MissingRegistryEscrow {
     }
 }
 
-impl TelNotifier for MissingRegistryEscrow {
+impl<D: KelDatabase> TelNotifier for MissingRegistryEscrow<D> {
     fn notify(
         &self,
         notification: &TelNotification,
@@ -64,7 +65,7 @@ impl TelNotifier for MissingRegistryEscrow {
     }
 }
 
-impl MissingRegistryEscrow {
+impl<D: KelDatabase> MissingRegistryEscrow<D> {
     pub fn process_missing_registry(
         &self,
         bus: &TelNotificationBus,
@@ -85,7 +86,7 @@ impl MissingRegistryEscrow {
                         // accept tel event
                         self.tel_reference.add_event(event.clone())?;
 
-                        bus.notify(&TelNotification::TelEventAdded(event.clone()))?;
+                        bus.notify_added(event.clone())?;
                         // stop processing the escrow if tel was updated. It needs to start again.
                         break;
                     }
@@ -1,7 +1,7 @@
 use std::{sync::Arc, time::Duration};
 
 use keri_core::{
-    database::{escrow::EscrowDb, redb::RedbDatabase},
+    database::{escrow::EscrowDb, EventDatabase as KelDatabase},
     processor::event_storage::EventStorage,
 };
 
@@ -18,16 +18,61 @@ pub mod missing_issuer;
 pub mod missing_registry;
 pub mod out_of_order;
 
-pub fn default_escrow_bus(
+/// Times after which escrowed TEL events are removed.
+#[derive(Debug, Clone)]
+pub struct TelEscrowConfig {
+    /// Events which anchoring KEL event wasn't seen yet.
+    pub missing_issuer_timeout: Duration,
+    pub out_of_order_timeout: Duration,
+    pub missing_registry_timeout: Duration,
+}
+
+impl Default for TelEscrowConfig {
+    fn default() -> Self {
+        Self {
+            missing_issuer_timeout: Duration::from_secs(100),
+            out_of_order_timeout: Duration::from_secs(100),
+            missing_registry_timeout: Duration::from_secs(100),
+        }
+    }
+}
+
+pub fn default_escrow_bus<D: KelDatabase + Send + Sync + 'static>(
+    tel_storage: Arc<super::storage::TelEventStorage>,
+    kel_storage: Arc<EventStorage<D>>,
+    tel_escrow_db: Arc<EscrowDb>,
+) -> Result<
+    (
+        TelNotificationBus,
+        Arc<MissingIssuerEscrow<D>>,
+        Arc<OutOfOrderEscrow<D>>,
+        Arc<MissingRegistryEscrow<D>>,
+    ),
+    Error,
+> {
+    escrow_bus(
+        tel_storage,
+        kel_storage,
+        tel_escrow_db,
+        TelEscrowConfig::default(),
+    )
+}
+
+/// Creates notification bus with TEL escrows registered. Returned
+/// [`MissingIssuerEscrow`] needs to be registered as `KeyEventAdded` observer
+/// of KEL processor, so escrowed events are accepted when their anchor
+/// arrives.
+pub fn escrow_bus<D: KelDatabase + Send + Sync + 'static>(
     tel_storage: Arc<super::storage::TelEventStorage>,
-    kel_storage: Arc<EventStorage<RedbDatabase>>,
+    kel_storage: Arc<EventStorage<D>>,
     tel_escrow_db: Arc<EscrowDb>,
+    config: TelEscrowConfig,
 ) -> Result<
     (
         TelNotificationBus,
-        Arc<MissingIssuerEscrow>,
-        Arc<OutOfOrderEscrow>,
-        Arc<MissingRegistryEscrow>,
+        Arc<MissingIssuerEscrow<D>>,
+        Arc<OutOfOrderEscrow<D>>,
+        Arc<MissingRegistryEscrow<D>>,
     ),
     Error,
 > {
@@ -35,20 +80,20 @@ pub fn default_escrow_bus(
         tel_storage.clone(),
         kel_storage.clone(),
         tel_escrow_db.clone(),
-        Duration::from_secs(100),
+        config.out_of_order_timeout,
     ));
     let missing_registry_escrow = Arc::new(MissingRegistryEscrow::new(
         tel_storage.clone(),
         kel_storage.clone(),
         tel_escrow_db.clone(),
-        Duration::from_secs(100),
+        config.missing_registry_timeout,
     ));
     let tel_bus = TelNotificationBus::new();
 
     let missing_issuer_escrow = Arc::new(MissingIssuerEscrow::new(
         tel_storage.clone(),
         tel_escrow_db,
-        Duration::from_secs(100),
+        config.missing_issuer_timeout,
         kel_storage.clone(),
         tel_bus.clone(),
     ));
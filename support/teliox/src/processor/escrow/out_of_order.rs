@@ -4,6 +4,7 @@ use keri_core::{
     database::{
         escrow::{Escrow, EscrowDb},
         redb::RedbDatabase,
+        EventDatabase as KelDatabase,
     },
     prefix::IdentifierPrefix,
     processor::event_storage::EventStorage,
@@ -19,16 +20,16 @@ use crate::{
     },
 };
 
-pub struct OutOfOrderEscrow {
+pub struct OutOfOrderEscrow<D: KelDatabase = RedbDatabase> {
     tel_reference: Arc<TelEventStorage>,
-    kel_reference: Arc<EventStorage<RedbDatabase>>,
+    kel_reference: Arc<EventStorage<D>>,
     escrowed_out_of_order: Escrow<VerifiableEvent>,
 }
 
-impl OutOfOrderEscrow {
+impl<D: KelDatabase> OutOfOrderEscrow<D> {
     pub fn new(
         tel_reference: Arc<TelEventStorage>,
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         escrow_db: Arc<EscrowDb>,
         duration: Duration,
     ) -> Self {
@@ -41,7 +42,7 @@ impl OutOfOrderEscrow {
     }
 }
 
-impl TelNotifier for OutOfOrderEscrow {
+impl<D: KelDatabase> TelNotifier for OutOfOrderEscrow<D> {
     fn notify(
         &self,
         notification: &TelNotification,
@@ -63,7 +64,7 @@ impl TelNotifier for OutOfOrderEscrow {
     }
 }
 
-impl OutOfOrderEscrow {
+impl<D: KelDatabase> OutOfOrderEscrow<D> {
     pub fn process_out_of_order_events(
         &self,
         bus: &TelNotificationBus,
@@ -84,7 +85,7 @@ impl OutOfOrderEscrow {
                         // accept tel event
                         self.tel_reference.add_event(event.clone())?;
 
-                        bus.notify(&TelNotification::TelEventAdded(event.clone()))?;
+                        bus.notify_added(event.clone())?;
                         // stop processing the escrow if tel was updated. It needs to start again.
                         break;
                     }
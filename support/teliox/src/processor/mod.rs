@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use keri_core::{database::redb::RedbDatabase, processor::event_storage::EventStorage};
+use keri_core::{
+    database::{redb::RedbDatabase, EventDatabase as KelDatabase},
+    processor::event_storage::EventStorage,
+};
 
 use crate::{
     error::Error,
@@ -19,15 +22,15 @@ pub mod notification;
 pub mod storage;
 pub mod validator;
 
-pub struct TelEventProcessor {
-    kel_reference: Arc<EventStorage<RedbDatabase>>,
+pub struct TelEventProcessor<D: KelDatabase = RedbDatabase> {
+    kel_reference: Arc<EventStorage<D>>,
     pub tel_reference: Arc<TelEventStorage>,
     pub publisher: TelNotificationBus,
 }
 
-impl TelEventProcessor {
+impl<D: KelDatabase> TelEventProcessor<D> {
     pub fn new(
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         tel_reference: Arc<TelEventStorage>,
         tel_publisher: Option<TelNotificationBus>,
     ) -> Self {
@@ -58,9 +61,7 @@ impl TelEventProcessor {
                         .db
                         .add_new_management_event(event.clone(), &man.data.prefix)
                         .unwrap();
-                    self.publisher
-                        .notify(&TelNotification::TelEventAdded(event))?;
-                    Ok(())
+                    self.publisher.notify_added(event)
                 }
                 Err(e) => match e {
                     Error::OutOfOrderError => {
@@ -85,8 +86,7 @@ impl TelEventProcessor {
                         .db
                         .add_new_event(event.clone(), &vc_ev.data.data.prefix)
                         .unwrap();
-                    self.publisher
-                        .notify(&TelNotification::TelEventAdded(event))
+                    self.publisher.notify_added(event)
                 }
                 Err(Error::MissingIssuerEventError) => self
                     .publisher
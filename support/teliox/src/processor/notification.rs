@@ -3,7 +3,13 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::{error::Error, event::verifiable_event::VerifiableEvent};
+use crate::{
+    error::Error,
+    event::{
+        manager_event::ManagerEventType, vc_event::VCEventType, verifiable_event::VerifiableEvent,
+        Event,
+    },
+};
 #[derive(Clone)]
 pub struct TelNotificationBus {
     observers: Arc<RwLock<HashMap<TelNotificationKind, Vec<Arc<dyn TelNotifier + Send + Sync>>>>>,
@@ -46,6 +52,17 @@ impl TelNotificationBus {
     }
 }
 
+impl TelNotificationBus {
+    /// Notifies about event accepted into TEL, and about registry or
+    /// credential state change made by it.
+    pub fn notify_added(&self, event: VerifiableEvent) -> Result<(), Error> {
+        if let Some(change) = TelNotification::state_change(&event) {
+            self.notify(&change)?;
+        }
+        self.notify(&TelNotification::TelEventAdded(event))
+    }
+}
+
 impl Default for TelNotificationBus {
     fn default() -> Self {
         Self::new()
@@ -63,6 +80,12 @@ pub enum TelNotification {
     MissingIssuer(VerifiableEvent),
     OutOfOrder(VerifiableEvent),
     TelEventAdded(VerifiableEvent),
+    /// Accepted registry inception event.
+    RegistryIncepted(VerifiableEvent),
+    /// Accepted credential issuance event.
+    CredentialIssued(VerifiableEvent),
+    /// Accepted credential revocation event.
+    CredentialRevoked(VerifiableEvent),
 }
 
 #[derive(PartialEq, Hash, Eq, Clone, Debug)]
@@ -71,6 +94,9 @@ pub enum TelNotificationKind {
     MissingIssuer,
     OutOfOrder,
     TelEventAdded,
+    RegistryIncepted,
+    CredentialIssued,
+    CredentialRevoked,
 }
 
 impl From<&TelNotification> for TelNotificationKind {
@@ -80,6 +106,30 @@ impl From<&TelNotification> for TelNotificationKind {
             TelNotification::MissingIssuer(_) => Self::MissingIssuer,
             TelNotification::OutOfOrder(_) => Self::OutOfOrder,
             TelNotification::TelEventAdded(_) => Self::TelEventAdded,
+            TelNotification::RegistryIncepted(_) => Self::RegistryIncepted,
+            TelNotification::CredentialIssued(_) => Self::CredentialIssued,
+            TelNotification::CredentialRevoked(_) => Self::CredentialRevoked,
+        }
+    }
+}
+
+impl TelNotification {
+    /// Returns notification about registry or credential state change made
+    /// by accepted event. Registry rotations don't change it.
+    pub fn state_change(event: &VerifiableEvent) -> Option<Self> {
+        match &event.event {
+            Event::Management(man) => match man.data.event_type {
+                ManagerEventType::Vcp(_) => Some(Self::RegistryIncepted(event.clone())),
+                ManagerEventType::Vrt(_) => None,
+            },
+            Event::Vc(vc) => match vc.data.data.event_type {
+                VCEventType::Iss(_) | VCEventType::Bis(_) => {
+                    Some(Self::CredentialIssued(event.clone()))
+                }
+                VCEventType::Rev(_) | VCEventType::Brv(_) => {
+                    Some(Self::CredentialRevoked(event.clone()))
+                }
+            },
         }
     }
 }
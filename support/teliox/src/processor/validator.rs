@@ -1,8 +1,12 @@
 use std::sync::Arc;
 
 use keri_core::{
-    database::redb::RedbDatabase,
-    event::{event_data::EventData, sections::seal::Seal},
+    database::{redb::RedbDatabase, EventDatabase as KelDatabase},
+    error::Error as KeriError,
+    event::{
+        event_data::EventData,
+        sections::{configuration::ConfigurationTrait, seal::Seal},
+    },
     prefix::IdentifierPrefix,
     processor::event_storage::EventStorage,
 };
@@ -22,13 +26,13 @@ use crate::{
 
 use super::TelEventStorage;
 
-pub struct TelEventValidator {
-    kel_reference: Arc<EventStorage<RedbDatabase>>,
+pub struct TelEventValidator<D: KelDatabase = RedbDatabase> {
+    kel_reference: Arc<EventStorage<D>>,
     db: TelEventStorage,
 }
 
-impl TelEventValidator {
-    pub fn new(db: Arc<EventDatabase>, kel_reference: Arc<EventStorage<RedbDatabase>>) -> Self {
+impl<D: KelDatabase> TelEventValidator<D> {
+    pub fn new(db: Arc<EventDatabase>, kel_reference: Arc<EventStorage<D>>) -> Self {
         Self {
             db: TelEventStorage::new(db),
             kel_reference,
@@ -37,7 +41,7 @@ impl TelEventValidator {
 
     /// Checks if kel event pointed by seal has seal to tel event inside.
     pub fn check_kel_event(
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         seal: &AttachedSourceSeal,
         issuer_id: &IdentifierPrefix,
         expected_digest: SelfAddressingIdentifier,
@@ -96,6 +100,20 @@ impl TelEventValidator {
             event.digest().unwrap(),
         )?;
 
+        if let ManagerEventType::Vcp(vcp) = &event.data.event_type {
+            let no_backers_allowed = self
+                .kel_reference
+                .get_state(&id)
+                .map(|state| state.has_trait(&ConfigurationTrait::NoRegistrarBackers))
+                .unwrap_or(false);
+            if no_backers_allowed && !vcp.backers.is_empty() {
+                return Err(KeriError::ConfigurationTraitViolation(
+                    ConfigurationTrait::NoRegistrarBackers,
+                )
+                .into());
+            }
+        };
+
         let state = self
             .db
             .compute_management_tel_state(&event.data.prefix)?
@@ -137,3 +155,111 @@ impl TelEventValidator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use keri_core::{
+        database::{redb::RedbDatabase, sled::SledEventDatabase},
+        error::Error as KeriError,
+        event::sections::{configuration::ConfigurationTrait, seal::EventSeal},
+        event_message::{
+            event_msg_builder::EventMsgBuilder, signed_event_message::Notice, EventTypeTag,
+        },
+        prefix::{BasicPrefix, IdentifierPrefix, IndexedSignature, SelfSigningPrefix},
+        processor::{basic_processor::BasicProcessor, event_storage::EventStorage, Processor},
+        signer::Signer,
+    };
+
+    use crate::{
+        database::EventDatabase,
+        error::Error,
+        event::Event,
+        processor::validator::TelEventValidator,
+        seal::{AttachedSourceSeal, EventSourceSeal},
+        tel::event_generator::make_inception_event,
+    };
+
+    #[test]
+    pub fn test_no_registrar_backers() -> Result<(), Error> {
+        use tempfile::Builder;
+
+        let keri_root = Builder::new().prefix("test-db").tempfile().unwrap();
+        let keri_db = Arc::new(RedbDatabase::new(keri_root.path()).unwrap());
+        let escrow_root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let escrow_db = Arc::new(SledEventDatabase::new(escrow_root.path()).unwrap());
+        let keri_processor = BasicProcessor::new(keri_db.clone(), escrow_db.clone(), None);
+        let keri_storage = Arc::new(EventStorage::new(keri_db.clone(), escrow_db.clone()));
+
+        let tel_root = Builder::new().prefix("test-db").tempdir().unwrap();
+        let tel_events_db = Arc::new(EventDatabase::new(tel_root.path()).unwrap());
+        let validator = TelEventValidator::new(tel_events_db, keri_storage);
+
+        let signer = Signer::new();
+        let next_signer = Signer::new();
+        let process = |event: &keri_core::event_message::msg::KeriEvent<_>| {
+            let signature =
+                SelfSigningPrefix::Ed25519Sha512(signer.sign(event.encode().unwrap()).unwrap());
+            keri_processor.process_notice(&Notice::Event(event.sign(
+                vec![IndexedSignature::new_both_same(signature, 0)],
+                None,
+                None,
+            )))
+        };
+        let backer = IdentifierPrefix::Basic(BasicPrefix::Ed25519NT(Signer::new().public_key()));
+
+        // Issuer incepts registry with backer and anchors it in its KEL.
+        // Returns validation result of registry inception.
+        let incept_registry = |configuration: &[ConfigurationTrait]| -> Result<(), Error> {
+            let icp = EventMsgBuilder::new(EventTypeTag::Icp)
+                .with_keys(vec![BasicPrefix::Ed25519(signer.public_key())])
+                .with_next_keys(vec![BasicPrefix::Ed25519(next_signer.public_key())])
+                .with_configuration(configuration)
+                .build()?;
+            process(&icp)?;
+            let issuer = icp.data.get_prefix();
+
+            let vcp = match make_inception_event(
+                issuer.clone(),
+                vec![],
+                1,
+                vec![backer.clone()],
+                None,
+                None,
+            )? {
+                Event::Management(vcp) => vcp,
+                Event::Vc(_) => unreachable!(),
+            };
+            let ixn = EventMsgBuilder::new(EventTypeTag::Ixn)
+                .with_prefix(&issuer)
+                .with_sn(1)
+                .with_previous_event(&icp.digest()?)
+                .with_seal(vec![EventSeal::new(
+                    vcp.data.prefix.clone(),
+                    0,
+                    vcp.digest()?,
+                )])
+                .build()?;
+            process(&ixn)?;
+
+            let seal = AttachedSourceSeal::from(EventSourceSeal {
+                sn: 1,
+                digest: ixn.digest()?,
+            });
+            validator.validate_management(&vcp, &seal)
+        };
+
+        assert!(matches!(
+            incept_registry(&[ConfigurationTrait::NoRegistrarBackers]),
+            Err(Error::KeriError(KeriError::ConfigurationTraitViolation(
+                ConfigurationTrait::NoRegistrarBackers
+            )))
+        ));
+
+        // Backers are allowed if issuer's KEL doesn't forbid them.
+        incept_registry(&[])?;
+
+        Ok(())
+    }
+}
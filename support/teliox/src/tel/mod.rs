@@ -13,7 +13,9 @@ use crate::{
     state::{vc_state::TelState, ManagerTelState},
 };
 use keri_core::{
-    database::redb::RedbDatabase, prefix::IdentifierPrefix, processor::event_storage::EventStorage,
+    database::{redb::RedbDatabase, EventDatabase as KelDatabase},
+    prefix::IdentifierPrefix,
+    processor::event_storage::EventStorage,
 };
 use said::SelfAddressingIdentifier;
 
@@ -44,15 +46,15 @@ impl TelNotifier for RecentlyAddedEvents {
 }
 
 /// Transaction Event Log
-pub struct Tel {
-    pub processor: TelEventProcessor,
+pub struct Tel<D: KelDatabase = RedbDatabase> {
+    pub processor: TelEventProcessor<D>,
     pub recently_added_events: Arc<RecentlyAddedEvents>,
 }
 
-impl Tel {
+impl<D: KelDatabase> Tel<D> {
     pub fn new(
         tel_reference: Arc<TelEventStorage>,
-        kel_reference: Arc<EventStorage<RedbDatabase>>,
+        kel_reference: Arc<EventStorage<D>>,
         publisher: Option<TelNotificationBus>,
     ) -> Self {
         let added_events = Arc::new(RecentlyAddedEvents::new());
@@ -0,0 +1,78 @@
+use keri_core::{
+    oobi::{LocationScheme, Scheme},
+    transport::{
+        correlation::{CorrelationId, CORRELATION_ID_HEADER},
+        default::{remote_error, request_error, TransportConfig},
+    },
+};
+
+use super::{TelTransport, TransportError};
+use crate::{event::verifiable_event::VerifiableEvent, query::SignedTelQuery};
+
+/// Default behavior for communication with other actors. Sends TEL events and
+/// queries over HTTP.
+#[derive(Default)]
+pub struct DefaultTelTransport {
+    client: reqwest::Client,
+}
+
+impl DefaultTelTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn post(
+        &self,
+        loc: &LocationScheme,
+        path: &str,
+        body: Vec<u8>,
+    ) -> Result<String, TransportError> {
+        let url = match loc.scheme {
+            Scheme::Http | Scheme::Https => loc
+                .url
+                .join(path)
+                .map_err(|e| TransportError::NetworkError(e.to_string()))?,
+            Scheme::Tcp => {
+                return Err(TransportError::NetworkError(
+                    "TCP scheme is not supported".to_string(),
+                ))
+            }
+        };
+        let request = self.client.post(url).body(body);
+        let request = match CorrelationId::current() {
+            Some(id) => request.header(CORRELATION_ID_HEADER, id.as_str()),
+            None => request,
+        };
+        let resp = request.send().await.map_err(request_error)?;
+        if !resp.status().is_success() {
+            return Err(remote_error(resp, TransportConfig::default().max_retry_after).await);
+        }
+        resp.text().await.map_err(request_error)
+    }
+}
+
+#[async_trait::async_trait]
+impl TelTransport for DefaultTelTransport {
+    async fn send_query(
+        &self,
+        loc: LocationScheme,
+        qry: SignedTelQuery,
+    ) -> Result<String, TransportError> {
+        let body = qry
+            .to_cesr()
+            .map_err(|e| TransportError::UnknownError(e.to_string()))?;
+        self.post(&loc, "query/tel", body).await
+    }
+
+    async fn send_tel_event(
+        &self,
+        loc: LocationScheme,
+        event: VerifiableEvent,
+    ) -> Result<(), TransportError> {
+        let body = event
+            .serialize()
+            .map_err(|e| TransportError::UnknownError(e.to_string()))?;
+        self.post(&loc, "process/tel", body).await?;
+        Ok(())
+    }
+}
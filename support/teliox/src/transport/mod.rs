@@ -1,81 +1,32 @@
-use keri_core::oobi::{LocationScheme, Scheme};
+use keri_core::oobi::LocationScheme;
 
 use crate::{event::verifiable_event::VerifiableEvent, query::SignedTelQuery};
 
-pub struct TelTransport;
+pub mod default;
+pub mod test;
 
-#[async_trait::async_trait]
-impl GeneralTelTransport for TelTransport {
-    async fn send_query(
-        &self,
-        qry: SignedTelQuery,
-        location: LocationScheme,
-    ) -> Result<String, TransportError> {
-        let url = match location.scheme {
-            Scheme::Http => location.url.join("query/tel").unwrap(),
-            Scheme::Tcp => todo!(),
-        };
-        let resp = reqwest::Client::new()
-            .post(url)
-            .body(qry.to_cesr().unwrap())
-            .send()
-            .await
-            .map_err(|_| TransportError::NetworkError)?;
-
-        resp.text()
-            .await
-            .map_err(|_| TransportError::InvalidResponse)
-    }
-
-    async fn send_tel_event(
-        &self,
-        qry: VerifiableEvent,
-        location: LocationScheme,
-    ) -> Result<(), TransportError> {
-        let url = match location.scheme {
-            Scheme::Http => location.url.join("process/tel").unwrap(),
-            Scheme::Tcp => todo!(),
-        };
-        let client = reqwest::Client::new();
-        let query = qry
-            .serialize()
-            .map_err(|_e| TransportError::InvalidResponse)?;
-        let resp = client
-            .post(url)
-            .body(query)
-            .send()
-            .await
-            .map_err(|_| TransportError::NetworkError)?;
-        resp.text()
-            .await
-            .map_err(|_| TransportError::InvalidResponse)?;
-
-        Ok(())
-    }
-}
-
-pub enum HttpTransportError {
-    NetworkError,
-}
+pub use default::DefaultTelTransport;
+pub use keri_core::transport::TransportError;
 
+/// TEL counterpart of [`keri_core::transport::Transport`]. Allows customizing
+/// how TEL events and queries are sent to backers and watchers. Actors and
+/// controllers take a `dyn TelTransport` in their configuration, so a fake
+/// transport can be provided in tests.
 #[async_trait::async_trait]
-pub trait GeneralTelTransport {
+pub trait TelTransport {
+    /// Send TEL query to other actor and return its response.
+    /// Should use `query/tel` endpoint.
     async fn send_query(
         &self,
+        loc: LocationScheme,
         qry: SignedTelQuery,
-        location: LocationScheme,
     ) -> Result<String, TransportError>;
+
+    /// Send TEL event to other actor.
+    /// Should use `process/tel` endpoint.
     async fn send_tel_event(
         &self,
-        qry: VerifiableEvent,
-        location: LocationScheme,
+        loc: LocationScheme,
+        event: VerifiableEvent,
     ) -> Result<(), TransportError>;
 }
-
-#[derive(Debug, thiserror::Error, serde::Serialize, serde::Deserialize)]
-pub enum TransportError {
-    #[error("network error")]
-    NetworkError,
-    #[error("invalid response")]
-    InvalidResponse,
-}
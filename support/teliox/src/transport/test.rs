@@ -0,0 +1,67 @@
+use std::{collections::HashMap, sync::Arc};
+
+use keri_core::{actor::error::ActorError, oobi::LocationScheme};
+
+use super::{TelTransport, TransportError};
+use crate::{event::verifiable_event::VerifiableEvent, query::SignedTelQuery};
+
+/// Actor that can be reached by [`TelTestTransport`].
+#[async_trait::async_trait]
+pub trait TelTestActor {
+    async fn send_query(&self, qry: SignedTelQuery) -> Result<String, ActorError>;
+    async fn send_tel_event(&self, event: VerifiableEvent) -> Result<(), ActorError>;
+}
+
+pub type TelTestActorMap = HashMap<(url::Host, u16), Arc<dyn TelTestActor + Send + Sync>>;
+
+/// Used in tests to connect directly to actors without going through the network.
+#[derive(Clone)]
+pub struct TelTestTransport {
+    actors: Arc<TelTestActorMap>,
+}
+
+impl TelTestTransport {
+    pub fn new(actors: TelTestActorMap) -> Self {
+        Self {
+            actors: Arc::new(actors),
+        }
+    }
+
+    fn actor(
+        &self,
+        loc: &LocationScheme,
+    ) -> Result<&Arc<dyn TelTestActor + Send + Sync>, TransportError> {
+        let (host, port) = match loc.url.origin() {
+            url::Origin::Tuple(_scheme, host, port) => (host, port),
+            _ => return Err(TransportError::NetworkError("Wrong url".to_string())),
+        };
+        self.actors
+            .get(&(host, port))
+            .ok_or(TransportError::NetworkError("No such actor".to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl TelTransport for TelTestTransport {
+    async fn send_query(
+        &self,
+        loc: LocationScheme,
+        qry: SignedTelQuery,
+    ) -> Result<String, TransportError> {
+        self.actor(&loc)?
+            .send_query(qry)
+            .await
+            .map_err(TransportError::RemoteError)
+    }
+
+    async fn send_tel_event(
+        &self,
+        loc: LocationScheme,
+        event: VerifiableEvent,
+    ) -> Result<(), TransportError> {
+        self.actor(&loc)?
+            .send_tel_event(event)
+            .await
+            .map_err(TransportError::RemoteError)
+    }
+}